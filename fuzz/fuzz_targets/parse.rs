@@ -0,0 +1,9 @@
+//! `parse_program` must return, never panic, on any UTF-8 input
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let _ = physlang_core::parse_program(source);
+});
@@ -0,0 +1,12 @@
+//! Whatever `parse_program` accepts, `analyze_program` must check without
+//! panicking
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    if let Ok(program) = physlang_core::parse_program(source) {
+        physlang_core::analyze_program(&program);
+    }
+});
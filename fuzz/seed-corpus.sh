@@ -0,0 +1,10 @@
+#!/bin/sh
+# Seed the corpus of every fuzz target with the repository's .phys fixtures
+set -eu
+cd "$(dirname "$0")/.."
+for target in parse parse_analyze; do
+    mkdir -p "fuzz/corpus/$target"
+    git ls-files '*.phys' | while read -r file; do
+        cp "$file" "fuzz/corpus/$target/$(echo "$file" | tr / _)"
+    done
+done
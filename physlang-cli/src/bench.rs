@@ -0,0 +1,140 @@
+//! `physlang bench`: time each stage of the run pipeline over repeated runs
+
+use physlang_core::{
+    apply_overrides, build_run_context, check_program, expand_functions, parse_program,
+    run_simulation, RunOptions,
+};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Output format for `physlang bench`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum BenchFormat {
+    /// A table of min/median/max per stage
+    Text,
+    /// A JSON object, for tracking regressions in CI
+    Json,
+}
+
+/// Stage names, in pipeline order
+const STAGES: [&str; 5] = [
+    "parse",
+    "analyze",
+    "functions",
+    "build",
+    "simulate_per_step",
+];
+
+/// Timings of one run, indexed like `STAGES`
+type Sample = [Duration; 5];
+
+/// Run `source` once for warm-up and then `repeat` times, and print how long
+/// each stage took
+pub fn bench(
+    source: &str,
+    repeat: usize,
+    steps: Option<usize>,
+    format: BenchFormat,
+) -> Result<(), Box<dyn Error>> {
+    let options = RunOptions {
+        steps,
+        ..Default::default()
+    };
+    time_run(source, &options)?;
+    let mut samples = Vec::with_capacity(repeat);
+    let mut steps_run = 0;
+    for _ in 0..repeat {
+        let (sample, steps) = time_run(source, &options)?;
+        samples.push(sample);
+        steps_run = steps;
+    }
+
+    let stats: Vec<(&str, Stats)> = STAGES
+        .iter()
+        .enumerate()
+        .map(|(i, &stage)| (stage, Stats::of(samples.iter().map(|s| s[i]).collect())))
+        .collect();
+    match format {
+        BenchFormat::Text => {
+            println!("{} runs of {} steps", repeat, steps_run);
+            println!(
+                "{:<18} {:>12} {:>12} {:>12}",
+                "stage", "min", "median", "max"
+            );
+            for (stage, stats) in &stats {
+                println!(
+                    "{:<18} {:>12} {:>12} {:>12}",
+                    stage,
+                    format!("{:.2?}", stats.min),
+                    format!("{:.2?}", stats.median),
+                    format!("{:.2?}", stats.max)
+                );
+            }
+        }
+        BenchFormat::Json => {
+            let stages: serde_json::Map<String, serde_json::Value> = stats
+                .iter()
+                .map(|(stage, stats)| (stage.to_string(), stats.to_json()))
+                .collect();
+            let json = serde_json::json!({
+                "repeat": repeat,
+                "steps": steps_run,
+                "stages": stages,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
+
+/// Run the whole pipeline once, timing each stage; also returns the number of steps
+fn time_run(source: &str, options: &RunOptions) -> Result<(Sample, usize), Box<dyn Error>> {
+    let started = Instant::now();
+    let mut program = parse_program(source)?;
+    apply_overrides(&mut program, options)?;
+    let parse = started.elapsed();
+
+    let started = Instant::now();
+    check_program(&program)?;
+    let analyze = started.elapsed();
+
+    let started = Instant::now();
+    expand_functions(&mut program)?;
+    let functions = started.elapsed();
+
+    let started = Instant::now();
+    let ctx = build_run_context(&program, options)?;
+    let build = started.elapsed();
+
+    let started = Instant::now();
+    let result = run_simulation(ctx, &program, options, |_| Ok(()))?;
+    let simulate = started.elapsed() / result.steps.max(1) as u32;
+
+    Ok(([parse, analyze, functions, build, simulate], result.steps))
+}
+
+/// Summary of one stage's timings
+struct Stats {
+    min: Duration,
+    median: Duration,
+    max: Duration,
+}
+
+impl Stats {
+    fn of(mut timings: Vec<Duration>) -> Self {
+        timings.sort();
+        Self {
+            min: timings[0],
+            median: timings[timings.len() / 2],
+            max: timings[timings.len() - 1],
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "min_ns": self.min.as_nanos() as u64,
+            "median_ns": self.median.as_nanos() as u64,
+            "max_ns": self.max.as_nanos() as u64,
+        })
+    }
+}
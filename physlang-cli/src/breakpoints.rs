@@ -0,0 +1,158 @@
+//! Conditional breakpoints for the VEL
+//!
+//! A breakpoint watches one quantity (a detector, or a coordinate or the
+//! speed of a particle) after every step and fires on the step its condition
+//! goes from false to true. The first value it sees only primes it, so a
+//! condition that already holds when playing starts doesn't stop the run.
+
+use physlang_core::{DetectorResult, SimulationContext};
+use std::fmt;
+
+/// What a breakpoint watches
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quantity {
+    Detector(String),
+    PositionX(String),
+    PositionY(String),
+    Speed(String),
+}
+
+impl Quantity {
+    /// Every quantity `ctx` offers: its detectors, then each particle's
+    /// coordinates and speed
+    pub fn choices(ctx: &SimulationContext) -> Vec<Quantity> {
+        let detectors = ctx
+            .detectors
+            .iter()
+            .map(|d| Quantity::Detector(d.name.clone()));
+        let particles = ctx.world.particles.iter().flat_map(|p| {
+            [
+                Quantity::PositionX(p.name.clone()),
+                Quantity::PositionY(p.name.clone()),
+                Quantity::Speed(p.name.clone()),
+            ]
+        });
+        detectors.chain(particles).collect()
+    }
+
+    /// The current value, or None if the detector or particle is gone
+    pub fn value(&self, ctx: &SimulationContext, detectors: &[DetectorResult]) -> Option<f32> {
+        let particle = |name: &str| {
+            let id = ctx.symbols().resolve(name)?;
+            ctx.world.particles.get(id.index())
+        };
+        match self {
+            Quantity::Detector(name) => detectors.iter().find(|d| d.name == *name).map(|d| d.value),
+            Quantity::PositionX(name) => particle(name).map(|p| p.pos.x),
+            Quantity::PositionY(name) => particle(name).map(|p| p.pos.y),
+            Quantity::Speed(name) => particle(name).map(|p| p.vel.length()),
+        }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantity::Detector(name) => write!(f, "{}", name),
+            Quantity::PositionX(name) => write!(f, "position({}).x", name),
+            Quantity::PositionY(name) => write!(f, "position({}).y", name),
+            Quantity::Speed(name) => write!(f, "speed({})", name),
+        }
+    }
+}
+
+/// How a breakpoint compares its quantity with the threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Below,
+    Above,
+    /// Passes the threshold in either direction
+    Crosses,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 3] = [Operator::Below, Operator::Above, Operator::Crosses];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Operator::Below => "<",
+            Operator::Above => ">",
+            Operator::Crosses => "crosses",
+        }
+    }
+}
+
+/// Pause when `quantity operator threshold` becomes true
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub quantity: Quantity,
+    pub operator: Operator,
+    pub threshold: f32,
+    pub enabled: bool,
+    /// The value seen after the previous step
+    last: Option<f32>,
+}
+
+impl Breakpoint {
+    pub fn new(quantity: Quantity, operator: Operator, threshold: f32) -> Self {
+        Self {
+            quantity,
+            operator,
+            threshold,
+            enabled: true,
+            last: None,
+        }
+    }
+
+    /// Take `value`, seen after a step; true if the breakpoint fires on it
+    pub fn check(&mut self, value: f32) -> bool {
+        let Some(last) = self.last.replace(value) else {
+            return false;
+        };
+        let t = self.threshold;
+        match self.operator {
+            Operator::Below => value < t && last >= t,
+            Operator::Above => value > t && last <= t,
+            Operator::Crosses => (last < t && value >= t) || (last > t && value <= t),
+        }
+    }
+
+    /// Forget the previous value, e.g. after a reload, a seek, or an edit
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.quantity,
+            self.operator.symbol(),
+            self.threshold
+        )
+    }
+}
+
+/// Check every enabled breakpoint against the state of `ctx` after a step;
+/// returns the first that fired. All of them see the step, so none misses
+/// its transition because another fired first
+pub fn check_all<'a>(
+    breakpoints: &'a mut [Breakpoint],
+    ctx: &SimulationContext,
+) -> Option<&'a Breakpoint> {
+    let detectors = ctx.detector_values().unwrap_or_default();
+    let mut fired = None;
+    for (i, breakpoint) in breakpoints.iter_mut().enumerate() {
+        if !breakpoint.enabled {
+            breakpoint.reset();
+            continue;
+        }
+        let value = breakpoint.quantity.value(ctx, &detectors);
+        if value.is_some_and(|v| breakpoint.check(v)) && fired.is_none() {
+            fired = Some(i);
+        }
+    }
+    fired.map(|i| &breakpoints[i])
+}
@@ -0,0 +1,80 @@
+//! Split view: a baseline version of the program run beside the current one
+//!
+//! The baseline is a frozen copy of the source. It restarts whenever the
+//! current run does and steps in lockstep with it, so both panes always show
+//! the same step.
+
+use physlang_core::{
+    build_simulation_context_with_overrides, step_simulation, RunOptions, SimulationContext,
+};
+
+/// Space between the two panes, in pixels
+pub const GAP: f32 = 4.0;
+
+/// The frozen version of the program and its run
+pub struct Baseline {
+    source: String,
+    run: Result<SimulationContext, String>,
+}
+
+impl Baseline {
+    /// Freeze `source` and build its run at step 0
+    pub fn new(source: String, options: &RunOptions) -> Self {
+        let run = build(&source, options);
+        Self { source, run }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The run, unless the source failed to build
+    pub fn ctx(&self) -> Option<&SimulationContext> {
+        self.run.as_ref().ok()
+    }
+
+    /// Why the source failed to build
+    pub fn error(&self) -> Option<&str> {
+        self.run.as_ref().err().map(String::as_str)
+    }
+
+    /// Start over from step 0
+    pub fn restart(&mut self, options: &RunOptions) {
+        self.run = build(&self.source, options);
+    }
+
+    /// Take one step alongside the current run
+    pub fn step(&mut self) {
+        if let Ok(ref mut ctx) = self.run {
+            step_simulation(ctx);
+        }
+    }
+
+    /// Go to `step`, restarting first if the run is already past it
+    pub fn sync_to(&mut self, step: usize, options: &RunOptions) {
+        if self.ctx().is_some_and(|ctx| ctx.current_step > step) {
+            self.restart(options);
+        }
+        if let Ok(ref mut ctx) = self.run {
+            while ctx.current_step < step {
+                if step_simulation(ctx) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn build(source: &str, options: &RunOptions) -> Result<SimulationContext, String> {
+    build_simulation_context_with_overrides(source, options)
+        .map(|(ctx, _)| ctx)
+        .map_err(|e| e.to_string())
+}
+
+/// The left and right halves of `rect`, `GAP` apart
+pub fn split(rect: egui::Rect) -> (egui::Rect, egui::Rect) {
+    let half = (rect.width() - GAP).max(0.0) / 2.0;
+    let left = egui::Rect::from_min_size(rect.min, egui::vec2(half, rect.height()));
+    let right = egui::Rect::from_min_max(egui::pos2(rect.max.x - half, rect.min.y), rect.max);
+    (left, right)
+}
@@ -0,0 +1,44 @@
+//! Debouncing for `physlang watch`
+//!
+//! Editors often fire several events for one save (truncate, write, rename),
+//! so a re-run only happens once the file has been quiet for a short while.
+
+use std::time::{Duration, Instant};
+
+/// Collapses a burst of events into one, fired after a quiet period
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    last_event: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_event: None,
+        }
+    }
+
+    /// Record an event, restarting the quiet period
+    pub fn event(&mut self, at: Instant) {
+        self.last_event = Some(at);
+    }
+
+    /// When the pending burst will be ready, if there is one
+    pub fn deadline(&self) -> Option<Instant> {
+        self.last_event.map(|at| at + self.quiet)
+    }
+
+    /// Whether a burst has been quiet long enough to act on; returns true
+    /// once per burst
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Live detector values for the VEL's side panel
+//!
+//! Each detector keeps its most recent samples, one per simulation step seen,
+//! for drawing a sparkline next to its current value.
+
+use physlang_core::DetectorResult;
+use std::collections::VecDeque;
+
+/// How many samples each sparkline shows
+pub const SPARKLINE_SAMPLES: usize = 300;
+
+/// Recent values of every detector, oldest first
+#[derive(Debug, Default)]
+pub struct DetectorHistory {
+    series: Vec<(String, VecDeque<f32>)>,
+    last_step: Option<usize>,
+}
+
+impl DetectorHistory {
+    /// Record `values` taken at `step`, once per step; the series restart if
+    /// the detectors changed
+    pub fn record(&mut self, step: usize, values: &[DetectorResult]) {
+        if self.last_step == Some(step) {
+            return;
+        }
+        let same_detectors = self.series.len() == values.len()
+            && self
+                .series
+                .iter()
+                .zip(values)
+                .all(|((name, _), v)| *name == v.name);
+        if !same_detectors {
+            self.series = values
+                .iter()
+                .map(|v| (v.name.clone(), VecDeque::new()))
+                .collect();
+        }
+        for ((_, samples), value) in self.series.iter_mut().zip(values) {
+            if samples.len() == SPARKLINE_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(value.value);
+        }
+        self.last_step = Some(step);
+    }
+
+    /// Forget every sample, e.g. after a reset or reload
+    pub fn clear(&mut self) {
+        self.series.clear();
+        self.last_step = None;
+    }
+
+    /// The samples recorded for `name`, oldest first
+    pub fn samples(&self, name: &str) -> Option<&VecDeque<f32>> {
+        self.series
+            .iter()
+            .find(|(series, _)| series == name)
+            .map(|(_, samples)| samples)
+    }
+}
+
+/// Points of a sparkline for `samples` stretched over `rect`, lowest value at
+/// the bottom; non-finite samples are skipped and a flat series is centered
+pub fn sparkline(samples: &VecDeque<f32>, rect: egui::Rect) -> Vec<egui::Pos2> {
+    let finite = || samples.iter().copied().filter(|v| v.is_finite());
+    let min = finite().fold(f32::INFINITY, f32::min);
+    let max = finite().fold(f32::NEG_INFINITY, f32::max);
+    let step = rect.width() / (SPARKLINE_SAMPLES - 1) as f32;
+    samples
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.is_finite())
+        .map(|(i, &v)| {
+            let height = if max > min {
+                (v - min) / (max - min)
+            } else {
+                0.5
+            };
+            egui::pos2(
+                rect.left() + i as f32 * step,
+                rect.bottom() - height * rect.height(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+//! Non-fatal diagnostics for the VEL's bottom panel
+//!
+//! Gathers what `physlang check` would report for the source being shown,
+//! with each diagnostic's line and column resolved against that source.
+
+use physlang_core::{
+    analyze_program, lint_source, parse_program, Diagnostic, DiagnosticSeverity, SourceLocation,
+};
+
+/// One row of the diagnostics list
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticRow {
+    pub severity: DiagnosticSeverity,
+    /// The message, prefixed with its code if it has one
+    pub text: String,
+    pub location: Option<SourceLocation>,
+}
+
+impl DiagnosticRow {
+    /// The icon shown before the message
+    pub fn icon(&self) -> &'static str {
+        match self.severity {
+            DiagnosticSeverity::Error => "⛔",
+            DiagnosticSeverity::Warning => "⚠",
+        }
+    }
+}
+
+/// Static analysis and lint diagnostics of `source`, or its parse error
+pub fn check_source(source: &str) -> Vec<Diagnostic> {
+    match parse_program(source) {
+        Err(e) => vec![Diagnostic::from(&e)],
+        Ok(program) => {
+            let mut diagnostics: Vec<Diagnostic> = analyze_program(&program).into_iter().collect();
+            diagnostics.extend(lint_source(source));
+            diagnostics
+        }
+    }
+}
+
+/// Rows for `diagnostics`, located in `source`; errors come first, and
+/// within each severity those without a location precede the rest in source
+/// order
+pub fn rows(source: &str, diagnostics: &[Diagnostic]) -> Vec<DiagnosticRow> {
+    let mut rows: Vec<DiagnosticRow> = diagnostics
+        .iter()
+        .map(|d| DiagnosticRow {
+            severity: d.severity,
+            text: match d.code {
+                Some(code) => format!("[{}] {}", code, d.message),
+                None => d.message.clone(),
+            },
+            location: d.location(source),
+        })
+        .collect();
+    rows.sort_by_key(|row| {
+        (
+            row.severity != DiagnosticSeverity::Error,
+            row.location.map(|l| (l.line, l.column)),
+        )
+    });
+    rows
+}
+
+/// How many of `diagnostics` are warnings
+pub fn warning_count(diagnostics: &[Diagnostic]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Warning)
+        .count()
+}
@@ -0,0 +1,77 @@
+//! Noticing when a VEL run diverges
+//!
+//! Core stops stepping on the first non-finite position or velocity; this
+//! keeps each particle's last finite position so the VEL can show where the
+//! particle was before it blew up.
+
+use physlang_core::{RunError, SimulationContext};
+
+/// Where and how a run diverged
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub step: usize,
+    pub particle: String,
+    /// "position" or "velocity"
+    pub quantity: String,
+    /// The particle's last finite position, if it ever had one
+    pub last_position: Option<(f32, f32)>,
+}
+
+impl Divergence {
+    pub fn message(&self) -> String {
+        format!(
+            "Diverged at step {}: the {} of '{}' is not finite",
+            self.step, self.quantity, self.particle
+        )
+    }
+}
+
+/// The last finite position of every particle
+#[derive(Debug, Default)]
+pub struct DivergenceWatch {
+    positions: Vec<(String, (f32, f32))>,
+}
+
+impl DivergenceWatch {
+    /// Remember the finite positions in `ctx`
+    pub fn record(&mut self, ctx: &SimulationContext) {
+        for particle in &ctx.world.particles {
+            if !particle.pos.is_finite() {
+                continue;
+            }
+            let position = (particle.pos.x, particle.pos.y);
+            match self.positions.iter_mut().find(|(n, _)| *n == particle.name) {
+                Some((_, last)) => *last = position,
+                None => self.positions.push((particle.name.clone(), position)),
+            }
+        }
+    }
+
+    /// Forget every position, e.g. after a reload
+    pub fn clear(&mut self) {
+        self.positions.clear();
+    }
+
+    /// How `ctx` diverged, or None if it hasn't
+    pub fn check(&self, ctx: &SimulationContext) -> Option<Divergence> {
+        let Some(RunError::NumericalDivergence {
+            step,
+            particle,
+            quantity,
+        }) = &ctx.divergence
+        else {
+            return None;
+        };
+        let last_position = self
+            .positions
+            .iter()
+            .find(|(name, _)| name == particle)
+            .map(|(_, position)| *position);
+        Some(Divergence {
+            step: *step,
+            particle: particle.clone(),
+            quantity: quantity.clone(),
+            last_position,
+        })
+    }
+}
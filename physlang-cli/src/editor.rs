@@ -0,0 +1,144 @@
+//! The VEL's in-app source editor
+//!
+//! Tracks the edit buffer against the text last read from or written to disk,
+//! decides what an external change to the file means for unsaved edits, and
+//! highlights the lines of errors in the buffer.
+
+use physlang_core::{Diagnostic, DiagnosticSeverity, SourceLocation};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Background of lines with errors
+const ERROR_LINE: egui::Color32 = egui::Color32::from_rgba_premultiplied(80, 0, 0, 60);
+
+/// What an external change to the file did to the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalChange {
+    /// The file matches what the editor last loaded or saved
+    Unchanged,
+    /// There were no unsaved edits, so the buffer now holds the new text
+    Reloaded,
+    /// There are unsaved edits; the new text waits for `accept_external` or
+    /// `keep_local`
+    Conflict,
+}
+
+/// Edit buffer for the source of the running program
+#[derive(Debug, Default)]
+pub struct SourceEditor {
+    pub text: String,
+    /// The text on disk as of the last load or save
+    saved: String,
+    /// A change made on disk while the buffer had unsaved edits
+    pending: Option<String>,
+    /// 1-based lines of the errors found by the last failed apply
+    pub error_lines: Vec<usize>,
+}
+
+impl SourceEditor {
+    pub fn new(text: String) -> Self {
+        Self {
+            saved: text.clone(),
+            text,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the buffer differs from the file
+    pub fn is_dirty(&self) -> bool {
+        self.text != self.saved
+    }
+
+    /// Whether an external change is waiting on the user
+    pub fn has_conflict(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The file changed on disk to `text`
+    pub fn external_change(&mut self, text: String) -> ExternalChange {
+        if text == self.saved {
+            ExternalChange::Unchanged
+        } else if self.is_dirty() {
+            self.pending = Some(text);
+            ExternalChange::Conflict
+        } else {
+            self.text = text.clone();
+            self.saved = text;
+            ExternalChange::Reloaded
+        }
+    }
+
+    /// Resolve a conflict by discarding the edits for the file's text, which
+    /// is returned
+    pub fn accept_external(&mut self) -> Option<String> {
+        let text = self.pending.take()?;
+        self.text = text.clone();
+        self.saved = text.clone();
+        Some(text)
+    }
+
+    /// Resolve a conflict by keeping the edits; they stay unsaved
+    pub fn keep_local(&mut self) {
+        if let Some(text) = self.pending.take() {
+            self.saved = text;
+        }
+    }
+
+    /// Write the buffer to `path`
+    pub fn save(&mut self, path: &Path) -> io::Result<()> {
+        fs::write(path, &self.text)?;
+        self.saved = self.text.clone();
+        self.pending = None;
+        Ok(())
+    }
+}
+
+/// 1-based lines of the errors among `diagnostics` in `source`, sorted and
+/// without repeats
+pub fn error_lines(source: &str, diagnostics: &[Diagnostic]) -> Vec<usize> {
+    let mut lines: Vec<usize> = diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .filter_map(|d| d.span)
+        .map(|span| span.to_location(source).line)
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+/// Character index of `location` in `text`, clamped to its end
+pub fn char_index(text: &str, location: SourceLocation) -> usize {
+    let before: usize = text
+        .split_inclusive('\n')
+        .take(location.line.saturating_sub(1))
+        .map(|line| line.chars().count())
+        .sum();
+    (before + location.column.saturating_sub(1)).min(text.chars().count())
+}
+
+/// Lay out `text` in the code font with `error_lines` shaded
+pub fn highlight(text: &str, error_lines: &[usize], style: &egui::Style) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(style);
+    let color = style.visuals.text_color();
+    let mut job = egui::text::LayoutJob::default();
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let background = if error_lines.contains(&(i + 1)) {
+            ERROR_LINE
+        } else {
+            egui::Color32::TRANSPARENT
+        };
+        job.append(
+            line,
+            0.0,
+            egui::TextFormat {
+                font_id: font_id.clone(),
+                color,
+                background,
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
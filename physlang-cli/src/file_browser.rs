@@ -0,0 +1,99 @@
+//! The VEL's Open dialog
+//!
+//! Lists the subdirectories and `.phys` files of one directory at a time;
+//! the window itself is drawn by the app.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// One row of the listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+impl Entry {
+    /// The last component of the path, with a slash after directories
+    pub fn label(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string());
+        if self.is_dir {
+            format!("{}/", name)
+        } else {
+            name
+        }
+    }
+}
+
+/// The subdirectories and `.phys` files of `dir`, directories first, each
+/// group sorted by name; hidden entries are left out
+pub fn list(dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        let is_dir = path.is_dir();
+        if is_dir || path.extension().is_some_and(|ext| ext == "phys") {
+            entries.push(Entry { path, is_dir });
+        }
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.path.cmp(&b.path)));
+    Ok(entries)
+}
+
+/// State of the Open dialog while it's shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<Entry>,
+    /// Why `dir` couldn't be listed
+    pub error: Option<String>,
+    /// The path typed into the dialog
+    pub typed: String,
+}
+
+impl FileBrowser {
+    pub fn new(dir: PathBuf) -> Self {
+        let mut browser = Self {
+            dir: PathBuf::new(),
+            entries: Vec::new(),
+            error: None,
+            typed: String::new(),
+        };
+        browser.enter(dir);
+        browser
+    }
+
+    /// Show the contents of `dir`
+    pub fn enter(&mut self, dir: PathBuf) {
+        let dir = dir.canonicalize().unwrap_or(dir);
+        match list(&dir) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(e) => {
+                self.entries.clear();
+                self.error = Some(format!("{}: {}", dir.display(), e));
+            }
+        }
+        self.dir = dir;
+    }
+
+    /// Show the parent of the current directory, if it has one
+    pub fn up(&mut self) {
+        if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+            self.enter(parent);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! PNG frame sequences for `physlang export --frames`
+//!
+//! Each sampled state becomes one numbered PNG, ready for ffmpeg. The camera is
+//! framed once from the bounds of the whole run so it doesn't jitter.
+
+use crate::render::{draw_text, label_font, write_png};
+use crate::svg::PALETTE;
+use physlang_core::{Bounds, SimulationContext};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use tiny_skia::{Color, FillRule, Paint, PathBuilder, Pixmap, Transform};
+
+/// Background color of every frame, as RGB
+const BACKGROUND: (u8, u8, u8) = (24, 24, 32);
+
+/// Radius in pixels of particles, or of the heaviest one with `mass_radius`
+const RADIUS: f32 = 6.0;
+
+/// Margin in pixels kept clear around the framed trajectory
+const MARGIN: f32 = 24.0;
+
+/// How to draw the frames
+#[derive(Debug, Clone, Copy)]
+pub struct FrameStyle {
+    pub width: u32,
+    pub height: u32,
+    /// Scale each particle's area with its mass
+    pub mass_radius: bool,
+    /// Draw each particle's name next to it
+    pub labels: bool,
+}
+
+/// Sampled states of a run, and the bounds of all of them
+#[derive(Debug, Default)]
+pub struct Frames {
+    names: Vec<String>,
+    masses: Vec<f32>,
+    /// `positions[f][i]` is the position of particle `i` in frame `f`
+    positions: Vec<Vec<(f32, f32)>>,
+    bounds: Option<Bounds>,
+}
+
+impl Frames {
+    /// Record the current state as the next frame
+    pub fn record(&mut self, ctx: &SimulationContext) {
+        if self.positions.is_empty() {
+            self.names = ctx.world.particles.iter().map(|p| p.name.clone()).collect();
+            self.masses = ctx.world.particles.iter().map(|p| p.mass).collect();
+        }
+        self.positions.push(
+            ctx.world
+                .particles
+                .iter()
+                .map(|p| (p.pos.x, p.pos.y))
+                .collect(),
+        );
+        if let Some(bounds) = ctx.world.bounds() {
+            self.bounds = Some(match self.bounds {
+                Some(all) => all.union(&bounds),
+                None => bounds,
+            });
+        }
+    }
+
+    /// Write `frame_00000.png`, `frame_00001.png`, ... into `dir`, creating it
+    /// if needed; returns the number of frames written
+    pub fn write_pngs(&self, dir: &Path, style: FrameStyle) -> Result<usize, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let camera = Camera::framing(self.bounds, style.width, style.height);
+        let fonts = egui::FontDefinitions::default();
+        let font = if style.labels {
+            Some(label_font(&fonts)?)
+        } else {
+            None
+        };
+        let max_mass = self.masses.iter().fold(0.0f32, |max, &m| max.max(m));
+
+        for (number, positions) in self.positions.iter().enumerate() {
+            let mut pixmap = Pixmap::new(style.width, style.height).ok_or("invalid frame size")?;
+            pixmap.fill(Color::from_rgba8(
+                BACKGROUND.0,
+                BACKGROUND.1,
+                BACKGROUND.2,
+                255,
+            ));
+            for (i, &(x, y)) in positions.iter().enumerate() {
+                let (px, py) = camera.to_pixels(x, y);
+                let radius = if style.mass_radius && max_mass > 0.0 {
+                    (RADIUS * 2.0 * (self.masses[i] / max_mass).sqrt()).max(2.0)
+                } else {
+                    RADIUS
+                };
+                let mut paint = Paint::default();
+                paint.set_color(parse_hex(PALETTE[i % PALETTE.len()]));
+                paint.anti_alias = true;
+                if let Some(circle) = PathBuilder::from_circle(px, py, radius) {
+                    pixmap.fill_path(
+                        &circle,
+                        &paint,
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+                if let Some(font) = &font {
+                    draw_text(
+                        &mut pixmap,
+                        font,
+                        &self.names[i],
+                        px + radius + 3.0,
+                        py + 4.0,
+                        [255, 255, 255, 255],
+                    );
+                }
+            }
+            write_png(&dir.join(format!("frame_{:05}.png", number)), &pixmap)?;
+        }
+        Ok(self.positions.len())
+    }
+}
+
+/// Maps simulation coordinates to pixels, y pointing down
+struct Camera {
+    center: (f32, f32),
+    scale: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Camera {
+    /// Fit `bounds` inside the frame with a margin, keeping the aspect ratio
+    fn framing(bounds: Option<Bounds>, width: u32, height: u32) -> Self {
+        let (width, height) = (width as f32, height as f32);
+        let (center, size) = match bounds {
+            Some(bounds) => {
+                let (center, size) = (bounds.center(), bounds.size());
+                ((center.x, center.y), (size.x, size.y))
+            }
+            None => ((0.0, 0.0), (0.0, 0.0)),
+        };
+        // A motionless or single-particle world still spans some space
+        let span_x = size.0.max(1.0);
+        let span_y = size.1.max(1.0);
+        let scale = ((width - 2.0 * MARGIN) / span_x)
+            .min((height - 2.0 * MARGIN) / span_y)
+            .max(f32::MIN_POSITIVE);
+        Self {
+            center,
+            scale,
+            width,
+            height,
+        }
+    }
+
+    fn to_pixels(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.width / 2.0 + (x - self.center.0) * self.scale,
+            self.height / 2.0 - (y - self.center.1) * self.scale,
+        )
+    }
+}
+
+/// `#rrggbb` to an opaque color
+fn parse_hex(hex: &str) -> Color {
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    Color::from_rgba8(channel(1), channel(3), channel(5), 255)
+}
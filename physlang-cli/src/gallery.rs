@@ -0,0 +1,60 @@
+//! The example gallery behind `physlang examples`
+//!
+//! The programs in examples/gallery are built into the binary, so the
+//! subcommand works wherever physlang is installed. Each one opens with a
+//! comment line describing it.
+
+/// One gallery program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+macro_rules! example {
+    ($name:literal) => {
+        Example {
+            name: $name,
+            source: include_str!(concat!("../../examples/gallery/", $name, ".phys")),
+        }
+    };
+}
+
+/// Every gallery program, in the order they're listed
+pub const EXAMPLES: &[Example] = &[
+    example!("orbit"),
+    example!("double_spring"),
+    example!("driven_oscillator"),
+    example!("function_grid"),
+    example!("match_world"),
+];
+
+impl Example {
+    /// The text of the leading comment line, or "" if there is none
+    pub fn description(&self) -> &'static str {
+        self.source
+            .lines()
+            .next()
+            .and_then(|line| line.trim().strip_prefix('#'))
+            .map_or("", str::trim)
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.phys", self.name)
+    }
+}
+
+/// The example called `name`, with or without the `.phys` extension
+pub fn find(name: &str) -> Option<&'static Example> {
+    let name = name.strip_suffix(".phys").unwrap_or(name);
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// One line per example: its name, padded to line up, and its description
+pub fn listing() -> String {
+    let width = EXAMPLES.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    EXAMPLES
+        .iter()
+        .map(|e| format!("{:width$}  {}\n", e.name, e.description(), width = width))
+        .collect()
+}
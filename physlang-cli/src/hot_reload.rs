@@ -0,0 +1,34 @@
+//! How the VEL reloads a file that changed on disk
+//!
+//! Edits that leave the world's declarations alone (comments, lets, the
+//! `simulate` line) continue the running simulation; anything else rebuilds
+//! it from step 0.
+
+use physlang_core::{diff_programs, parse_program, ProgramDiff};
+
+/// How the edit from `old` to `new` changed the program; a source that
+/// doesn't parse counts as a structural change, which reports its errors
+pub fn classify_edit(old: &str, new: &str) -> ProgramDiff {
+    match (parse_program(old), parse_program(new)) {
+        (Ok(old), Ok(new)) => diff_programs(&old, &new),
+        _ => ProgramDiff::Structural,
+    }
+}
+
+/// Whether an edit of this kind keeps the running world
+pub fn keeps_world(diff: ProgramDiff) -> bool {
+    diff != ProgramDiff::Structural
+}
+
+/// The toast shown after reloading an edit of this kind, with the run at `step`
+pub fn toast(diff: ProgramDiff, step: usize) -> String {
+    match diff {
+        ProgramDiff::Unchanged => format!(
+            "Reloaded, no simulation changes: continuing at step {}",
+            step
+        ),
+        ProgramDiff::SimulateOnly => format!("Reloaded dt/steps: continuing at step {}", step),
+        ProgramDiff::LetsOnly => format!("Reloaded let values: continuing at step {}", step),
+        ProgramDiff::Structural => "Declarations changed: restarted from step 0".to_string(),
+    }
+}
@@ -0,0 +1,93 @@
+//! Details of the particle selected in the VEL
+//!
+//! Everything here is read from the live context, so the inspector follows
+//! the particle as the simulation runs.
+
+use physlang_core::loops::{LoopKindRuntime, ObservableRuntime};
+use physlang_core::SimulationContext;
+
+/// What the inspector shows for one particle
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleDetails {
+    pub name: String,
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub speed: f32,
+    pub mass: f32,
+    pub pinned: bool,
+    /// Forces acting on the particle, as written in source
+    pub forces: Vec<String>,
+    /// Titles of the loops driving it
+    pub loops: Vec<String>,
+    /// Names of the wells it's in or measured by
+    pub wells: Vec<String>,
+}
+
+/// Describe particle `index` of `ctx`, or None if there's no such particle
+pub fn details(ctx: &SimulationContext, index: usize) -> Option<ParticleDetails> {
+    let particle = ctx.world.particles.get(index)?;
+    let name = &particle.name;
+
+    let forces = ctx
+        .forces()
+        .into_iter()
+        .filter(|force| force.a == *name || force.b == *name)
+        .map(|force| {
+            let call = if force.a == force.b {
+                format!("{}({})", force.kind, force.a)
+            } else {
+                format!("{}({}, {})", force.kind, force.a, force.b)
+            };
+            if force.enabled {
+                call
+            } else {
+                format!("{} (disabled)", call)
+            }
+        })
+        .collect();
+
+    let loops = ctx
+        .loops
+        .iter()
+        .enumerate()
+        .filter(|(_, instance)| {
+            let (LoopKindRuntime::ForCycles { target_index, .. }
+            | LoopKindRuntime::WhileCondition { target_index, .. }) = instance.kind;
+            target_index == index
+        })
+        .map(|(i, instance)| {
+            instance
+                .label
+                .clone()
+                .unwrap_or_else(|| format!("loop {}", i))
+        })
+        .collect();
+
+    let wells = ctx
+        .wells
+        .iter()
+        .filter(|well| {
+            let measured = match well.observable {
+                ObservableRuntime::PositionX(i)
+                | ObservableRuntime::PositionY(i)
+                | ObservableRuntime::Mass(i)
+                | ObservableRuntime::Tag(i, _) => i == index,
+                ObservableRuntime::Distance(a, b) => a == index || b == index,
+            };
+            well.particle_index == index || measured
+        })
+        .map(|well| well.name.clone())
+        .collect();
+
+    Some(ParticleDetails {
+        name: name.clone(),
+        position: (particle.pos.x, particle.pos.y),
+        velocity: (particle.vel.x, particle.vel.y),
+        speed: particle.vel.length(),
+        mass: particle.mass,
+        pinned: particle.pinned,
+        forces,
+        loops,
+        wells,
+    })
+}
@@ -0,0 +1,96 @@
+//! Stderr logger behind `--quiet` and `--verbose`
+//!
+//! Messages go through the `log` facade, so the core crate's phase records,
+//! loop firings, and parser tracing show up here too with `-vv`. Only physlang's own targets are printed; the
+//! windowing crates behind the VEL log plenty of their own.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::{self, IsTerminal, Write};
+
+/// Percentage of the steps between two progress updates
+const PROGRESS_PERCENT: usize = 5;
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && metadata.target().starts_with("physlang")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error | Level::Warn | Level::Info => eprintln!("{}", record.args()),
+            Level::Debug | Level::Trace => {
+                eprintln!("[{}] {}", record.target(), record.args())
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Install the logger: `--quiet` keeps only errors, each `-v` adds a level
+/// above the default of warnings
+pub fn init(quiet: bool, verbose: u8) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Info,
+        (false, _) => LevelFilter::Trace,
+    };
+    // Only fails if a logger is already installed, which would be a bug
+    let _ = log::set_logger(&StderrLogger);
+    log::set_max_level(level);
+}
+
+/// Whether `--quiet` was given
+pub fn quiet() -> bool {
+    log::max_level() < LevelFilter::Warn
+}
+
+/// Progress line for long runs with `--verbose`, redrawn in place on stderr
+pub struct Progress {
+    enabled: bool,
+    /// Percentage at which the next update is drawn
+    next: usize,
+}
+
+impl Progress {
+    /// A progress line, drawn only with `--verbose`
+    pub fn start() -> Self {
+        Self {
+            enabled: log::log_enabled!(Level::Info),
+            next: 0,
+        }
+    }
+
+    /// Draw the line if `step` of `total` reaches the next multiple of
+    /// `PROGRESS_PERCENT`
+    pub fn update(&mut self, step: usize, total: usize) {
+        if !self.enabled {
+            return;
+        }
+        let percent = step * 100 / total.max(1);
+        if percent < self.next {
+            return;
+        }
+        self.next = (percent / PROGRESS_PERCENT + 1) * PROGRESS_PERCENT;
+        let mut stderr = io::stderr().lock();
+        // Redraw in place on a terminal, one line per update otherwise
+        let end = if stderr.is_terminal() { "\r" } else { "\n" };
+        let _ = write!(stderr, "{}/{} steps ({}%){}", step, total, percent, end);
+        let _ = stderr.flush();
+    }
+
+    /// End the line so later output starts on its own
+    pub fn finish(&self) {
+        if self.enabled && self.next > 0 && io::stderr().is_terminal() {
+            eprintln!();
+        }
+    }
+}
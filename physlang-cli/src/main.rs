@@ -1,11 +1,46 @@
+mod bench;
+mod breakpoints;
+mod comparison;
+mod debounce;
+mod detector_panel;
+mod diagnostics_panel;
+mod divergence;
+mod editor;
+mod file_browser;
+mod frames;
+mod gallery;
+mod hot_reload;
+mod inspector;
+mod logging;
+mod perf_stats;
+mod playback;
+mod plot;
+mod recent;
+mod recorder;
+mod render;
+mod status_panel;
+mod suite;
+mod svg;
+mod timeline;
+mod unified_diff;
 mod vel_app;
+mod watch;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use physlang_core::ast::Program;
 use physlang_core::{
-    analyze_program, parse_program, Diagnostic, DiagnosticSeverity,
+    analyze_program_with_limit, apply_fixes, build_simulation_context_from_source, coerce_to_count,
+    evaluate_lets, execute_functions_with_source, format_detector_value, format_source,
+    language_capabilities, lint_source, parse_program, render_diagnostic, run_program_with_observer, run_simulation,
+    try_step_simulation, write_detectors_csv, BuildError, Capabilities, CsvTraceWriter, Diagnostic,
+    DiagnosticSeverity, Diagnostics, ExpansionLimits, OverrideError, ParseError, Pipeline, ReplReply,
+    ReplSession, RunError, RunOptions, SimulationResult,
 };
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use unified_diff::unified_diff;
 
 #[derive(Parser)]
 #[command(name = "physlang")]
@@ -13,56 +48,496 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Command,
+    /// When to color diagnostics; `auto` colors terminals unless NO_COLOR is set
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Print only results and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Print stage timings and progress; twice to also trace phases, loops,
+    /// and the parser
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
 }
 
 #[derive(Subcommand)]
 enum Command {
     /// Run a PhysLang program
     Run {
-        /// Path to the PhysLang source file
-        file: PathBuf,
+        /// PhysLang source files or directories to run, in order
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Abort the simulation after this much wall-clock time (e.g. 30s, 500ms, 2m)
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<Duration>,
+        /// How to print detector results on stdout
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Override a let binding, or `dt`/`steps` of the simulate declaration
+        /// (repeatable, e.g. --set spring_k=12.5 --set steps=500)
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        set: Vec<(String, f32)>,
+        /// Write step, time, and every particle's position and velocity to this CSV file
+        #[arg(long, value_name = "PATH")]
+        trace: Option<PathBuf>,
+        /// Write a trace row for step 0 and every Nth step after it
+        #[arg(long, value_name = "N", default_value_t = 1, requires = "trace",
+              value_parser = parse_positive)]
+        trace_every: usize,
+        /// Only print these detectors, in the program's order (e.g. --detectors gap,final_x)
+        #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+        detectors: Option<Vec<String>>,
+        /// Print text output with this many digits after the decimal point
+        #[arg(long, value_name = "DIGITS")]
+        precision: Option<usize>,
+        /// Fail when functions and loops generate more particles than this
+        #[arg(long, value_name = "N", default_value_t = ExpansionLimits::default().max_particles)]
+        max_particles: usize,
+        /// Fail when functions and loops generate more declarations than this
+        #[arg(long, value_name = "N", default_value_t = ExpansionLimits::default().max_statements)]
+        max_generated: usize,
+        /// Report at most N diagnostics of each kind and summarize the rest
+        #[arg(long, value_name = "N", default_value_t = Diagnostics::DEFAULT_CODE_LIMIT,
+              value_parser = parse_positive)]
+        max_diagnostics: usize,
     },
     /// Check a PhysLang program for errors without running it
     Check {
+        /// PhysLang source files or directories (searched recursively for `.phys` files)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Fail with the analysis exit code when there are warnings
+        #[arg(long)]
+        deny_warnings: bool,
+        /// Rewrite the files to apply the fixes of machine-applicable diagnostics
+        #[arg(long)]
+        fix: bool,
+        /// With --fix, print the fixes as a unified diff instead of applying them
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+        /// Report at most N diagnostics of each kind and summarize the rest
+        #[arg(long, value_name = "N", default_value_t = Diagnostics::DEFAULT_CODE_LIMIT,
+              value_parser = parse_positive)]
+        max_diagnostics: usize,
+    },
+    /// Format PhysLang source files in place
+    Fmt {
+        /// PhysLang source files or directories (searched recursively for `.phys` files)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Only list files whose formatting would change, and fail if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print the parse tree of a PhysLang program
+    Ast {
         /// Path to the PhysLang source file
         file: PathBuf,
+        /// How to print the program
+        #[arg(long, value_enum, default_value_t = AstFormat::Json)]
+        format: AstFormat,
+        /// Run top-level function calls first and print the expanded program
+        #[arg(long)]
+        after_functions: bool,
     },
-    /// Visual Evaluation Loop - interactive visualization of a PhysLang program
-    Visual {
+    /// Time each stage of running a PhysLang program
+    Bench {
+        /// Path to the PhysLang source file
+        file: PathBuf,
+        /// Number of timed runs, after one untimed warm-up run
+        #[arg(long, default_value_t = 10, value_parser = parse_positive)]
+        repeat: usize,
+        /// Run this many steps instead of the program's own `steps`
+        #[arg(long, value_name = "N", value_parser = parse_positive)]
+        steps_override: Option<usize>,
+        /// How to print the timings
+        #[arg(long, value_enum, default_value_t = bench::BenchFormat::Text)]
+        format: bench::BenchFormat,
+    },
+    /// Run a PhysLang program and draw its particle trajectories as an SVG
+    Export {
+        /// Path to the PhysLang source file
+        file: PathBuf,
+        /// Write the trajectories as an SVG to this file
+        #[arg(long, value_name = "PATH", required_unless_present = "frames")]
+        svg: Option<PathBuf>,
+        /// Write each sampled state as a numbered PNG into this directory
+        #[arg(long, value_name = "DIR")]
+        frames: Option<PathBuf>,
+        /// Sample positions at step 0, every Nth step, and the last step
+        #[arg(long, value_name = "N", default_value_t = 1, value_parser = parse_positive)]
+        every: usize,
+        /// Frame size in pixels
+        #[arg(long, value_name = "WxH", default_value = "800x600", requires = "frames",
+              value_parser = parse_size)]
+        size: (u32, u32),
+        /// Scale each particle's area in the frames with its mass
+        #[arg(long, requires = "frames")]
+        mass_radius: bool,
+        /// Label each particle with its name in the frames
+        #[arg(long, requires = "frames")]
+        labels: bool,
+    },
+    /// List the example gallery, or run one of its programs by name
+    Examples {
+        /// Name of the example to run; without one the examples are listed
+        name: Option<String>,
+    },
+    /// Print the language version and the features this build supports
+    Info {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Interactive session for declarations, expressions, and stepping a small world
+    Repl,
+    /// Run programs and check their detectors against the `.expect` file beside each
+    Test {
+        /// PhysLang source files or directories (searched recursively for `.phys` files)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Re-check and re-run a program every time its file changes, without a display
+    Watch {
         /// Path to the PhysLang source file
         file: PathBuf,
+        /// Check and run once, then exit with the usual exit code
+        #[arg(long)]
+        once: bool,
     },
+    /// Visual Evaluation Loop - interactive visualization of a PhysLang program
+    Visual {
+        /// Path to the PhysLang source file; without one the VEL starts with
+        /// the Open dialog
+        file: Option<PathBuf>,
+        /// Render PNG frames without opening a window or needing a display
+        #[arg(long, requires = "file", requires = "output")]
+        headless: bool,
+        /// Write the headless frames as numbered PNGs into this directory
+        #[arg(long, value_name = "DIR", requires = "headless")]
+        output: Option<PathBuf>,
+        /// Headless frames per simulated second
+        #[arg(long, default_value_t = 30, requires = "headless", value_parser = parse_positive)]
+        fps: usize,
+        /// Simulated time to render headlessly (e.g. 10, 10s, 500ms)
+        #[arg(long, default_value = "10s", requires = "headless", value_parser = parse_duration)]
+        duration: Duration,
+        /// Headless frame size in pixels
+        #[arg(long, value_name = "WxH", default_value = "800x600", requires = "headless",
+              value_parser = parse_size)]
+        size: (u32, u32),
+    },
+}
+
+/// Where and how often `physlang run --trace` samples the simulation state
+struct Trace {
+    path: PathBuf,
+    every: usize,
+}
+
+/// Which detector results `physlang run` prints, and how
+struct Report {
+    format: OutputFormat,
+    /// Detector names to keep, or all of them
+    detectors: Option<Vec<String>>,
+    /// Digits after the decimal point in text output
+    precision: Option<usize>,
+}
+
+/// Process exit codes, documented in docs/getting-started.md
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_RUNTIME: i32 = 1;
+const EXIT_PARSE: i32 = 2;
+const EXIT_ANALYSIS: i32 = 3;
+const EXIT_IO: i32 = 4;
+/// `fmt --check` found a file that isn't formatted
+const EXIT_UNFORMATTED: i32 = 1;
+/// `test` had a failing assertion or a program that didn't run
+const EXIT_TEST_FAILED: i32 = 1;
+
+/// `--color` setting for rendered diagnostics
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output going to a stream; `always` wins over NO_COLOR
+    fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+/// Output format for `physlang run`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// One `name = value` line per detector
+    Text,
+    /// A single JSON object with detectors, warnings, and step count
+    Json,
+    /// A header row of detector names and one row of values
+    Csv,
+}
+
+/// What `physlang check` does with fixable diagnostics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FixMode {
+    /// Report them like any other
+    Off,
+    /// Rewrite the file with their fixes
+    Apply,
+    /// Print the fixes as a diff
+    DryRun,
+}
+
+/// Output format for `physlang ast`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum AstFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// Rust's pretty Debug output
+    Debug,
 }
 
 fn main() {
     let cli = Cli::parse();
+    let color = cli.color;
+    logging::init(cli.quiet, cli.verbose);
 
     let exit_code = match cli.command {
-        Command::Run { file } => {
-            match run_file(&file) {
-                Ok(()) => 0,
+        Command::Run {
+            files,
+            timeout,
+            format,
+            set,
+            trace,
+            trace_every,
+            detectors,
+            precision,
+            max_particles,
+            max_generated,
+            max_diagnostics,
+        } => {
+            let report = Report {
+                format,
+                detectors,
+                precision,
+            };
+            let options = RunOptions {
+                timeout,
+                expansion_limits: ExpansionLimits {
+                    max_statements: max_generated,
+                    max_particles,
+                },
+                diagnostic_limit: Some(max_diagnostics),
+                ..Default::default()
+            };
+            let trace = trace.map(|path| Trace {
+                path,
+                every: trace_every,
+            });
+            match apply_assignments(options, set)
+                .and_then(|options| Ok((options, collect_sources(&files)?)))
+                .and_then(|(options, files)| {
+                    if trace.is_some() && files.len() > 1 {
+                        return Err("--trace can only be used with a single file".into());
+                    }
+                    Ok((options, files))
+                })
+            {
+                Ok((options, files)) => {
+                    // With several files, the most severe failure class wins
+                    let mut exit_code = EXIT_SUCCESS;
+                    for file in &files {
+                        // Headers separate the results once there is more than one file
+                        if files.len() > 1 {
+                            println!("== {} ==", file.display());
+                        }
+                        if let Err(e) = run_file(file, &options, &report, trace.as_ref(), color) {
+                            print_run_error(file, &*e);
+                            exit_code = exit_code.max(exit_code_for(&*e));
+                        }
+                    }
+                    exit_code
+                }
                 Err(e) => {
                     eprintln!("Error: {}", e);
-                    1
+                    exit_code_for(&*e)
                 }
             }
         }
-        Command::Check { file } => {
-            match check_file(&file) {
-                Ok(has_errors) => {
-                    if has_errors {
-                        1
-                    } else {
-                        0
+        Command::Check {
+            files,
+            deny_warnings,
+            fix,
+            dry_run,
+            max_diagnostics,
+        } => match collect_sources(&files) {
+            Ok(files) => {
+                let fix = match (fix, dry_run) {
+                    (false, _) => FixMode::Off,
+                    (true, false) => FixMode::Apply,
+                    (true, true) => FixMode::DryRun,
+                };
+                let mut exit_code = EXIT_SUCCESS;
+                for file in &files {
+                    match check_file(file, deny_warnings, fix, max_diagnostics, color) {
+                        Ok(code) => exit_code = exit_code.max(code),
+                        Err(e) => {
+                            eprintln!("Error: {}: {}", file.display(), e);
+                            exit_code = exit_code.max(exit_code_for(&*e));
+                        }
+                    }
+                }
+                exit_code
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                EXIT_IO
+            }
+        },
+        Command::Fmt { files, check } => match collect_sources(&files) {
+            Ok(files) => {
+                let mut exit_code = EXIT_SUCCESS;
+                for file in &files {
+                    match fmt_file(file, check, color) {
+                        Ok(code) => exit_code = exit_code.max(code),
+                        Err(e) => {
+                            eprintln!("Error: {}: {}", file.display(), e);
+                            exit_code = exit_code.max(exit_code_for(&*e));
+                        }
                     }
                 }
+                exit_code
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                EXIT_IO
+            }
+        },
+        Command::Ast {
+            file,
+            format,
+            after_functions,
+        } => match ast_file(&file, format, after_functions, color) {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("Error: {}: {}", file.display(), e);
+                exit_code_for(&*e)
+            }
+        },
+        Command::Bench {
+            file,
+            repeat,
+            steps_override,
+            format,
+        } => match bench_file(&file, repeat, steps_override, format, color) {
+            Ok(()) => EXIT_SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}: {}", file.display(), e);
+                exit_code_for(&*e)
+            }
+        },
+        Command::Export {
+            file,
+            svg,
+            frames,
+            every,
+            size,
+            mass_radius,
+            labels,
+        } => {
+            let frames = frames.map(|dir| {
+                (
+                    dir,
+                    frames::FrameStyle {
+                        width: size.0,
+                        height: size.1,
+                        mass_radius,
+                        labels,
+                    },
+                )
+            });
+            match export_file(&file, svg.as_deref(), frames, every, color) {
+                Ok(()) => EXIT_SUCCESS,
                 Err(e) => {
-                    eprintln!("Error: {}", e);
-                    1
+                    eprintln!("Error: {}: {}", file.display(), e);
+                    exit_code_for(&*e)
                 }
             }
         }
-        Command::Visual { file } => {
+        Command::Examples { name: None } => {
+            print!("{}", gallery::listing());
+            EXIT_SUCCESS
+        }
+        Command::Examples { name: Some(name) } => match gallery::find(&name) {
+            Some(example) => {
+                let report = Report {
+                    format: OutputFormat::Text,
+                    detectors: None,
+                    precision: None,
+                };
+                let file = PathBuf::from(example.file_name());
+                let options = RunOptions::default();
+                match run_source(&file, example.source, &options, &report, None, color) {
+                    Ok(()) => EXIT_SUCCESS,
+                    Err(e) => {
+                        print_run_error(&file, &*e);
+                        exit_code_for(&*e)
+                    }
+                }
+            }
+            None => {
+                eprintln!("Error: no example named '{}'; the examples are:", name);
+                eprint!("{}", gallery::listing());
+                EXIT_IO
+            }
+        },
+        Command::Info { json } => match print_info(json) {
+            Ok(()) => EXIT_SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                EXIT_RUNTIME
+            }
+        },
+        Command::Repl => match repl() {
+            Ok(()) => EXIT_SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                EXIT_IO
+            }
+        },
+        Command::Test { files } => match collect_sources(&files) {
+            Ok(files) => test_files(&files),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                exit_code_for(&e)
+            }
+        },
+        Command::Watch { file, once } => watch::watch(&file, once, color),
+        Command::Visual {
+            file: Some(file),
+            headless: true,
+            output: Some(dir),
+            fps,
+            duration,
+            size,
+        } => match headless_file(&file, &dir, fps, duration, size, color) {
+            Ok(()) => EXIT_SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}: {}", file.display(), e);
+                exit_code_for(&*e)
+            }
+        },
+        Command::Visual { file, .. } => {
             // Check for display before attempting to launch GUI
             // On Windows, native GUI is available, so skip the check
             // On Unix-like systems, check for X11/Wayland display
@@ -98,104 +573,739 @@ fn main() {
     std::process::exit(exit_code);
 }
 
-fn run_file(file: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+/// Print what this build of the language supports
+fn print_info(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let capabilities = language_capabilities();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&capabilities)?);
+    } else {
+        print!("{}", info_text(&capabilities));
+    }
+    Ok(())
+}
+
+/// `physlang info` without `--json`: the versions, then one line per feature list
+fn info_text(capabilities: &Capabilities) -> String {
+    let mut text = format!(
+        "physlang {} (physlang-core {})\n",
+        capabilities.language_version, capabilities.crate_version
+    );
+    let lists = [
+        ("forces", &capabilities.forces),
+        ("detectors", &capabilities.detectors),
+        ("builtin functions", &capabilities.builtin_functions),
+        ("operators", &capabilities.operators),
+        ("statements", &capabilities.statements),
+        ("top level", &capabilities.top_level),
+        ("loops", &capabilities.loops),
+        ("observables", &capabilities.observables),
+        ("integrators", &capabilities.integrators),
+        ("parameter types", &capabilities.parameter_types),
+    ];
+    for (label, items) in lists {
+        text.push_str(&format!("{}: {}\n", label, items.join(", ")));
+    }
+    text
+}
+
+/// Parse a frame size such as `800x600`
+fn parse_size(arg: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("expected a size like 800x600, got '{}'", arg);
+    let (width, height) = arg.split_once('x').ok_or_else(invalid)?;
+    match (width.parse::<u32>(), height.parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m`, or a bare number of seconds
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let arg = arg.trim();
+    let (number, scale) = if let Some(ms) = arg.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = arg.strip_suffix('s') {
+        (s, 1.0)
+    } else if let Some(m) = arg.strip_suffix('m') {
+        (m, 60.0)
+    } else {
+        (arg, 1.0)
+    };
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", arg))?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("invalid duration '{}'", arg));
+    }
+    Duration::try_from_secs_f64(value * scale).map_err(|_| format!("invalid duration '{}'", arg))
+}
+
+/// Map a failure to its exit code; anything not a parse, analysis, or I/O
+/// error counts as a runtime error. A bad `--set` name is an input error, so
+/// it exits like a parse error
+fn exit_code_for(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if error.is::<ParseError>() || error.is::<OverrideError>() {
+        EXIT_PARSE
+    } else if let Some(RunError::Analysis { .. }) = error.downcast_ref::<RunError>() {
+        EXIT_ANALYSIS
+    } else if error.is::<io::Error>() {
+        EXIT_IO
+    } else {
+        EXIT_RUNTIME
+    }
+}
+
+/// Report a failed run on stderr; parse, build and located analysis errors
+/// were already rendered with their source line by `run_source`, the same way
+/// `check` shows them
+pub(crate) fn print_run_error(file: &Path, error: &(dyn std::error::Error + 'static)) {
+    let rendered = error.is::<ParseError>()
+        || error.is::<BuildError>()
+        || matches!(
+            error.downcast_ref::<RunError>(),
+            Some(RunError::Analysis { messages, .. }) if messages.is_empty()
+        );
+    if !rendered {
+        eprintln!("Error: {}: {}", file.display(), error);
+    }
+}
+
+/// `error` without the messages of the diagnostics `run_source` rendered
+fn without_rendered(error: RunError, rendered: &[Diagnostic]) -> RunError {
+    match error {
+        RunError::Analysis { stage, mut messages } => {
+            for diagnostic in rendered {
+                if let Some(i) = messages.iter().position(|m| *m == diagnostic.message) {
+                    messages.remove(i);
+                }
+            }
+            RunError::Analysis { stage, messages }
+        }
+        error => error,
+    }
+}
+
+/// Expand the command-line paths into source files: files are kept as given,
+/// directories are searched recursively for `.phys` files in sorted order
+fn collect_sources(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let start = files.len();
+            collect_dir(path, &mut files)?;
+            if files.len() == start {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no .phys files found in {}", path.display()),
+                ));
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "phys") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Parse an integer that must be at least 1
+fn parse_positive(arg: &str) -> Result<usize, String> {
+    match arg.trim().parse() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("expected an integer >= 1, got '{}'", arg)),
+    }
+}
+
+/// Parse a `--set` argument of the form `name=value`
+fn parse_assignment(arg: &str) -> Result<(String, f32), String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", arg))?;
+    let value: f32 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number '{}' for '{}'", value.trim(), name.trim()))?;
+    Ok((name.trim().to_string(), value))
+}
+
+/// Route `--set` values: `dt` and `steps` override the simulate declaration,
+/// everything else overrides a let binding
+fn apply_assignments(
+    mut options: RunOptions,
+    assignments: Vec<(String, f32)>,
+) -> Result<RunOptions, Box<dyn std::error::Error>> {
+    for (name, value) in assignments {
+        match name.as_str() {
+            "dt" => options.dt = Some(value),
+            "steps" => {
+                let steps = coerce_to_count(value, "steps")?;
+                if steps == 0 {
+                    return Err("steps must be an integer >= 1, got 0".into());
+                }
+                options.steps = Some(steps);
+            }
+            _ => options.let_overrides.push((name, value)),
+        }
+    }
+    Ok(options)
+}
+
+/// Run the program in `file` and print its results
+fn run_file(
+    file: &Path,
+    options: &RunOptions,
+    report: &Report,
+    trace: Option<&Trace>,
+    color: ColorChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
     let source = fs::read_to_string(file)?;
-    
+    run_source(file, &source, options, report, trace, color)
+}
+
+/// Run `source`, reported as coming from `file`, and print its results
+fn run_source(
+    file: &Path,
+    source: &str,
+    options: &RunOptions,
+    report: &Report,
+    trace: Option<&Trace>,
+    color: ColorChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stderr_color = color.enabled(io::stderr().is_terminal());
+
     // First parse the program to get better error messages
-    let _program = match parse_program(&source) {
-        Ok(program) => program,
+    let started = Instant::now();
+    let mut pipeline = match Pipeline::parse(source) {
+        Ok(pipeline) => pipeline,
         Err(parse_error) => {
-            // Print detailed parse error
-            eprintln!("{}", parse_error.format_detailed());
-            
-            // Also print as a diagnostic with source location
-            let diagnostic = Diagnostic::error(
-                format!("{}", parse_error),
-                parse_error.span(),
-            );
-            print_diagnostics(&source, &[diagnostic]);
-            return Err(Box::new(parse_error));
+            // Print as a diagnostic with source location, on stderr so
+            // stdout only ever carries results
+            let diagnostic = Diagnostic::from(&parse_error);
+            write_diagnostics(&mut io::stderr(), file, source, &[diagnostic], stderr_color)?;
+            return Err(Box::new(parse_error.with_file(file)));
         }
     };
     
-    // Run the program (note: this re-parses internally, which is fine for now)
-    let result = physlang_core::run_program(&source)?;
+    // Rows are streamed to the trace file as the simulation runs
+    let mut trace_writer = match trace {
+        Some(trace) => Some((
+            CsvTraceWriter::new(io::BufWriter::new(fs::File::create(&trace.path)?)),
+            trace.every,
+        )),
+        None => None,
+    };
+
+    pipeline.apply_overrides(options)?;
+    log::info!("parse: {:.2?}", started.elapsed());
+
+    let started = Instant::now();
+    let analyzed = pipeline.analyze().and_then(|()| pipeline.expand_functions());
+    let warnings = match analyzed {
+        Ok(warnings) => warnings,
+        Err(error) => {
+            // Point at the declarations the failing stage could locate
+            let located: Vec<Diagnostic> = pipeline
+                .diagnostics()
+                .errors()
+                .filter(|d| d.span.is_some())
+                .cloned()
+                .collect();
+            write_diagnostics(&mut io::stderr(), file, source, &located, stderr_color)?;
+            return Err(Box::new(without_rendered(error, &located)));
+        }
+    };
+    log::info!("analyze: {:.2?}", started.elapsed());
 
-    // Print detector results
-    for detector in result.detectors {
-        println!("{} = {}", detector.name, detector.value);
+    let started = Instant::now();
+    let mut ctx = match pipeline.build(options) {
+        Ok(ctx) => ctx,
+        Err(error) => {
+            if let Some(located) = error.downcast_ref::<BuildError>() {
+                let diagnostic = Diagnostic::error(located.message.clone(), Some(located.span));
+                write_diagnostics(&mut io::stderr(), file, source, &[diagnostic], stderr_color)?;
+            }
+            return Err(error);
+        }
+    };
+    ctx.warnings.extend(warnings);
+    log::info!("build: {:.2?}", started.elapsed());
+
+    let started = Instant::now();
+    let mut progress = logging::Progress::start();
+    let run = run_simulation(ctx, pipeline.program(), options, |ctx| {
+        if let Some((writer, every)) = &mut trace_writer {
+            if ctx.current_step % *every == 0 {
+                writer.write_context(ctx)?;
+            }
+        }
+        progress.update(ctx.current_step, ctx.max_steps);
+        Ok(())
+    });
+    progress.finish();
+    log::info!("simulate: {:.2?}", started.elapsed());
+    // Flush what was traced even if the run failed, it's most useful then
+    if let Some((writer, _)) = &mut trace_writer {
+        writer.flush()?;
+    }
+    let mut result = match run {
+        Ok(result) => result,
+        Err(e) => {
+            // Show where the simulation got to before the budget ran out
+            if let Some(RunError::BudgetExceeded { detectors, .. }) = e.downcast_ref::<RunError>() {
+                for detector in detectors {
+                    eprintln!(
+                        "partial: {} = {}",
+                        detector.name,
+                        format_detector_value(detector.value, None)
+                    );
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    if let Some(names) = &report.detectors {
+        select_detectors(&mut result, names)?;
+    }
+    write_result(&mut io::stdout().lock(), &result, report.format, report.precision)?;
+
+    // Runtime warnings go to stderr so detector output stays machine-readable
+    if !logging::quiet() {
+        write_diagnostics(&mut io::stderr(), file, source, &result.warnings, stderr_color)?;
     }
 
     Ok(())
 }
 
-fn check_file(file: &PathBuf) -> Result<bool, Box<dyn std::error::Error>> {
-    let source = fs::read_to_string(file)?;
+/// Keep only the detectors named in `names`, failing if one doesn't exist
+fn select_detectors(result: &mut SimulationResult, names: &[String]) -> Result<(), String> {
+    for name in names {
+        if !result.detectors.iter().any(|d| &d.name == name) {
+            let available: Vec<&str> = result.detectors.iter().map(|d| d.name.as_str()).collect();
+            return Err(format!(
+                "unknown detector '{}' (the program has: {})",
+                name,
+                available.join(", ")
+            ));
+        }
+    }
+    result.detectors.retain(|d| names.contains(&d.name));
+    Ok(())
+}
+
+/// Print detector results in the requested format
+/// Text output rounds values as `format_detector_value` does, to `precision`
+/// decimal places if given; JSON and CSV keep full precision
+fn write_result(
+    out: &mut impl Write,
+    result: &SimulationResult,
+    format: OutputFormat,
+    precision: Option<usize>,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            let number = |value: f32| format_detector_value(value, precision);
+            for detector in &result.detectors {
+                match detector.initial {
+                    Some(initial) => writeln!(
+                        out,
+                        "{} = {} (initial {})",
+                        detector.name,
+                        number(detector.value),
+                        number(initial)
+                    )?,
+                    None => writeln!(out, "{} = {}", detector.name, number(detector.value))?,
+                }
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, result)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_detectors_csv(&mut *out, &result.detectors)?,
+    }
+    out.flush()
+}
+
+/// Check one file, printing its diagnostics, and return its exit code
+///
+/// With `fix`, machine-applicable fixes are applied (or shown as a diff) first
+/// and only the remaining diagnostics are reported
+fn check_file(
+    file: &Path,
+    deny_warnings: bool,
+    fix: FixMode,
+    max_diagnostics: usize,
+    color: ColorChoice,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut source = fs::read_to_string(file)?;
 
     // Parse the program
     let program = match parse_program(&source) {
         Ok(program) => program,
         Err(parse_error) => {
             // Convert parse error to diagnostic and print
-            let diagnostic = Diagnostic::error(
-                format!("{}", parse_error),
-                parse_error.span(),
-            );
-            print_diagnostics(&source, &[diagnostic]);
-            return Ok(true); // Has errors
+            print_diagnostics(file, &source, &[Diagnostic::from(&parse_error)], color);
+            return Ok(EXIT_PARSE);
         }
     };
 
-    // Analyze the program
-    let diagnostics = analyze_program(&program);
+    // Analyze the program, and lint its source
+    let mut diagnostics = diagnose(&program, &source, max_diagnostics);
+
+    if fix != FixMode::Off {
+        let (fixed, applied) = apply_fixes(&source, &diagnostics);
+        let verb = if fix == FixMode::DryRun { "would fix" } else { "fixed" };
+        for diagnostic in applied.iter().map(|&i| &diagnostics[i]) {
+            let location = diagnostic
+                .location(&source)
+                .map(|l| format!(":{}:{}", l.line, l.column))
+                .unwrap_or_default();
+            println!("{} {}{}: {}", verb, file.display(), location, diagnostic.message);
+        }
+        if fix == FixMode::DryRun {
+            print!("{}", unified_diff(&file.display().to_string(), &source, &fixed));
+            diagnostics = diagnostics
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !applied.contains(i))
+                .map(|(_, d)| d)
+                .collect();
+        } else if !applied.is_empty() {
+            fs::write(file, &fixed)?;
+            // Fixes only remove warnings, but report against the new text
+            let program = parse_program(&fixed)?;
+            diagnostics = diagnose(&program, &fixed, max_diagnostics);
+            source = fixed;
+        }
+    }
 
     if diagnostics.is_empty() {
-        println!("No issues found.");
-        return Ok(false); // No errors
+        if !logging::quiet() {
+            println!("{}: No issues found.", file.display());
+        }
+        return Ok(EXIT_SUCCESS);
     }
 
-    // Print diagnostics
-    let diagnostics_vec: Vec<Diagnostic> = diagnostics.iter().cloned().collect();
-    print_diagnostics(&source, &diagnostics_vec);
+    // Print diagnostics, only the errors with --quiet
+    if logging::quiet() {
+        let errors: Vec<Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.severity == DiagnosticSeverity::Error)
+            .cloned()
+            .collect();
+        print_diagnostics(file, &source, &errors, color);
+    } else {
+        print_diagnostics(file, &source, &diagnostics, color);
+    }
 
-    // Return true if there are any errors
-    Ok(diagnostics.has_errors())
+    let has_errors = diagnostics
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Error);
+    let has_warnings = diagnostics
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Warning);
+    if has_errors || (deny_warnings && has_warnings) {
+        Ok(EXIT_ANALYSIS)
+    } else {
+        Ok(EXIT_SUCCESS)
+    }
 }
 
-/// Print diagnostics with source location information
-fn print_diagnostics(source: &str, diagnostics: &[Diagnostic]) {
-    for diagnostic in diagnostics {
-        let severity_str = match diagnostic.severity {
-            DiagnosticSeverity::Error => "error",
-            DiagnosticSeverity::Warning => "warning",
-        };
+/// Static analysis diagnostics, at most `limit` with the same code, followed
+/// by source lints
+fn diagnose(program: &Program, source: &str, limit: usize) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> =
+        analyze_program_with_limit(program, limit).into_iter().collect();
+    diagnostics.extend(lint_source(source));
+    diagnostics
+}
 
-        print!("{}: {}", severity_str, diagnostic.message);
-
-        if let Some(location) = diagnostic.location(source) {
-            println!(" at line {}, column {}", location.line, location.column);
-
-            // Try to show the line with a caret
-            let lines: Vec<&str> = source.lines().collect();
-            if location.line > 0 && location.line <= lines.len() {
-                let line_content = lines[location.line - 1];
-                println!("  {}", line_content);
-                
-                // Show caret at the column position
-                if location.column > 0 {
-                    let caret_pos = location.column.saturating_sub(1);
-                    let caret = " ".repeat(caret_pos.min(line_content.len())) + "^";
-                    println!("  {}", caret);
+/// Test each program against its `.expect` file, print a summary, and return
+/// the exit code
+fn test_files(files: &[PathBuf]) -> i32 {
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for file in files {
+        match suite::test_program(file) {
+            suite::Outcome::Pass(assertions) => {
+                passed += 1;
+                println!("PASS {} ({} assertions)", file.display(), assertions);
+            }
+            suite::Outcome::Fail(failures) => {
+                failed += 1;
+                println!("FAIL {}", file.display());
+                for failure in failures {
+                    println!("  {}", failure);
                 }
             }
-        } else {
-            println!();
+            suite::Outcome::Error(e) => {
+                failed += 1;
+                println!("FAIL {}", file.display());
+                println!("  {}", e);
+            }
+            suite::Outcome::Skipped => skipped += 1,
+        }
+    }
+    println!(
+        "\n{} passed, {} failed, {} skipped (no .expect file)",
+        passed, failed, skipped
+    );
+    if failed > 0 {
+        EXIT_TEST_FAILED
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+/// Format one file, rewriting it unless `check` is set, and return its exit code
+fn fmt_file(file: &Path, check: bool, color: ColorChoice) -> Result<i32, Box<dyn std::error::Error>> {
+    let source = fs::read_to_string(file)?;
+
+    let formatted = match format_source(&source) {
+        Ok(formatted) => formatted,
+        Err(parse_error) => {
+            let color = color.enabled(io::stderr().is_terminal());
+            let diagnostics = [Diagnostic::from(&parse_error)];
+            write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+            return Ok(EXIT_PARSE);
+        }
+    };
+
+    if formatted == source {
+        return Ok(EXIT_SUCCESS);
+    }
+    if check {
+        println!("{}", file.display());
+        return Ok(EXIT_UNFORMATTED);
+    }
+    fs::write(file, formatted)?;
+    Ok(EXIT_SUCCESS)
+}
+
+/// Print the program in `file`, optionally after expanding its function calls,
+/// and return the exit code
+fn ast_file(
+    file: &Path,
+    format: AstFormat,
+    after_functions: bool,
+    color: ColorChoice,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let source = fs::read_to_string(file)?;
+    let color = color.enabled(io::stderr().is_terminal());
+
+    let mut program = match parse_program(&source) {
+        Ok(program) => program,
+        Err(parse_error) => {
+            let diagnostics = [Diagnostic::from(&parse_error)];
+            write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+            return Ok(EXIT_PARSE);
+        }
+    };
+
+    if after_functions {
+        let lets = program.lets.clone();
+        let (eval_ctx, mut diagnostics) = evaluate_lets(&lets);
+        let has_errors = |diagnostics: &[Diagnostic]| {
+            diagnostics
+                .iter()
+                .any(|d| d.severity == DiagnosticSeverity::Error)
+        };
+        if !has_errors(&diagnostics) {
+            diagnostics.extend(execute_functions_with_source(
+                &mut program,
+                &eval_ctx,
+                Some(&source),
+                &ExpansionLimits::default(),
+            ));
+        }
+        write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+        if has_errors(&diagnostics) {
+            return Ok(EXIT_ANALYSIS);
         }
     }
+
+    match format {
+        AstFormat::Json => println!("{}", serde_json::to_string_pretty(&program)?),
+        AstFormat::Debug => println!("{:#?}", program),
+    }
+    Ok(EXIT_SUCCESS)
+}
+
+/// Benchmark the program in `file`, printing its parse errors as diagnostics
+fn bench_file(
+    file: &Path,
+    repeat: usize,
+    steps: Option<usize>,
+    format: bench::BenchFormat,
+    color: ColorChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = fs::read_to_string(file)?;
+    if let Err(parse_error) = parse_program(&source) {
+        let color = color.enabled(io::stderr().is_terminal());
+        let diagnostics = [Diagnostic::from(&parse_error)];
+        write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+        return Err(Box::new(parse_error));
+    }
+    bench::bench(&source, repeat, steps, format)
+}
+
+/// Run the program in `file` and draw its sampled states as an SVG of
+/// trajectories, a PNG frame sequence, or both
+fn export_file(
+    file: &Path,
+    svg: Option<&Path>,
+    frames: Option<(PathBuf, frames::FrameStyle)>,
+    every: usize,
+    color: ColorChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = fs::read_to_string(file)?;
+    if let Err(parse_error) = parse_program(&source) {
+        let color = color.enabled(io::stderr().is_terminal());
+        let diagnostics = [Diagnostic::from(&parse_error)];
+        write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+        return Err(Box::new(parse_error));
+    }
+
+    let mut trajectories = svg::Trajectories::default();
+    let mut states = frames::Frames::default();
+    run_program_with_observer(&source, &RunOptions::default(), |ctx| {
+        if ctx.current_step % every == 0 || ctx.current_step >= ctx.max_steps {
+            trajectories.record(ctx);
+            states.record(ctx);
+        }
+        Ok(())
+    })?;
+
+    if let Some(svg) = svg {
+        let mut out = io::BufWriter::new(fs::File::create(svg)?);
+        svg::write_svg(&mut out, &trajectories)?;
+        out.flush()?;
+    }
+    if let Some((dir, style)) = frames {
+        let count = states.write_pngs(&dir, style)?;
+        if !logging::quiet() {
+            eprintln!("wrote {} frames to {}", count, dir.display());
+        }
+    }
+    Ok(())
+}
+
+/// Render what the VEL would show as PNG frames, `fps` per simulated second
+/// for `duration`, stopping early if the simulation finishes
+fn headless_file(
+    file: &Path,
+    dir: &Path,
+    fps: usize,
+    duration: Duration,
+    size: (u32, u32),
+    color: ColorChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = fs::read_to_string(file)?;
+    if let Err(parse_error) = parse_program(&source) {
+        let color = color.enabled(io::stderr().is_terminal());
+        let diagnostics = [Diagnostic::from(&parse_error)];
+        write_diagnostics(&mut io::stderr(), file, &source, &diagnostics, color)?;
+        return Err(Box::new(parse_error));
+    }
+    let (mut ctx, _diagnostics) = build_simulation_context_from_source(&source)?;
+
+    fs::create_dir_all(dir)?;
+    let steps_per_frame = ((1.0 / fps as f32) / ctx.dt).round().max(1.0) as usize;
+    let total = (duration.as_secs_f32() * fps as f32).round() as usize;
+    let mut progress = logging::Progress::start();
+    let mut count = 0;
+    loop {
+        let overlays = render::Overlays::default();
+        let pixmap = render::render_world(&ctx.world, &ctx.loops, &ctx.wells, size, &overlays)?;
+        render::write_png(&dir.join(format!("frame_{:05}.png", count)), &pixmap)?;
+        progress.update(count, total);
+        count += 1;
+        if count > total {
+            break;
+        }
+        let before = ctx.current_step;
+        for _ in 0..steps_per_frame {
+            if try_step_simulation(&mut ctx)? {
+                break;
+            }
+        }
+        // Finished: the last frame already shows the final state
+        if ctx.current_step == before {
+            break;
+        }
+    }
+    progress.finish();
+    if !logging::quiet() {
+        eprintln!("wrote {} frames to {}", count, dir.display());
+    }
+    Ok(())
+}
+
+/// Read lines from stdin into a REPL session until `:quit` or end of input,
+/// prompting only when stdin is a terminal
+fn repl() -> io::Result<()> {
+    let interactive = io::stdin().is_terminal();
+    let mut session = ReplSession::new();
+    let mut prompt = "> ";
+    let mut lines = io::stdin().lines();
+    loop {
+        if interactive {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+        }
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        prompt = "> ";
+        match session.handle_line(&line?) {
+            Ok(ReplReply::Output(text)) if text.is_empty() => {}
+            Ok(ReplReply::Output(text)) => println!("{}", text),
+            Ok(ReplReply::Incomplete) => prompt = "... ",
+            Ok(ReplReply::Quit) => return Ok(()),
+            Err(message) => eprintln!("error: {}", message),
+        }
+    }
+}
+
+/// Print diagnostics with source location information
+fn print_diagnostics(file: &Path, source: &str, diagnostics: &[Diagnostic], color: ColorChoice) {
+    let color = color.enabled(io::stdout().is_terminal());
+    // Writing to stdout only fails if it's closed, in which case there's nowhere to report it
+    let _ = write_diagnostics(&mut io::stdout(), file, source, diagnostics, color);
+}
+
+/// Write diagnostics rendered with the source snippet they point at,
+/// separated by blank lines
+fn write_diagnostics(
+    out: &mut impl Write,
+    file: &Path,
+    source: &str,
+    diagnostics: &[Diagnostic],
+    color: bool,
+) -> io::Result<()> {
+    let path = file.display().to_string();
+    for diagnostic in diagnostics {
+        let rendered = render_diagnostic(diagnostic, source, Some(&path), color);
+        writeln!(out, "{}", rendered)?;
+    }
+    Ok(())
 }
 
 /// Launch the Visual Evaluation Loop (VEL) window
-fn main_visual(file: PathBuf) -> eframe::Result<()> {
+fn main_visual(file: Option<PathBuf>) -> eframe::Result<()> {
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
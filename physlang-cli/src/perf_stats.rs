@@ -0,0 +1,71 @@
+//! Wall-clock performance of VEL playback
+//!
+//! Keeps the last `SAMPLES` frames: how long each took, how many steps it
+//! ran, and how long those steps took, so the stats overlay can show whether
+//! a program is too heavy to play in real time.
+
+use std::collections::VecDeque;
+
+/// Frames the rolling averages cover
+pub const SAMPLES: usize = 120;
+
+/// One frame's timings, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSample {
+    /// Time since the previous frame
+    pub frame_time: f64,
+    /// Steps simulated during the frame
+    pub steps: usize,
+    /// Time spent inside `step_simulation`
+    pub step_time: f64,
+}
+
+/// The most recent `SAMPLES` frames, oldest first
+#[derive(Debug, Default)]
+pub struct PerfStats {
+    samples: VecDeque<FrameSample>,
+}
+
+impl PerfStats {
+    pub fn record(&mut self, sample: FrameSample) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Forget every frame, e.g. while the overlay is hidden
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Steps actually simulated per wall-clock second
+    pub fn steps_per_second(&self) -> f64 {
+        let time: f64 = self.samples.iter().map(|s| s.frame_time).sum();
+        let steps: usize = self.samples.iter().map(|s| s.steps).sum();
+        if time > 0.0 {
+            steps as f64 / time
+        } else {
+            0.0
+        }
+    }
+
+    /// Average time of one `step_simulation` call, if any step was taken
+    pub fn step_time(&self) -> Option<f64> {
+        let steps: usize = self.samples.iter().map(|s| s.steps).sum();
+        let time: f64 = self.samples.iter().map(|s| s.step_time).sum();
+        (steps > 0).then(|| time / steps as f64)
+    }
+
+    /// Average time between frames
+    pub fn frame_time(&self) -> Option<f64> {
+        let frames = self.samples.len();
+        let time: f64 = self.samples.iter().map(|s| s.frame_time).sum();
+        (frames > 0).then(|| time / frames as f64)
+    }
+
+    /// Every kept frame's time, oldest first, for the bar graph
+    pub fn frame_times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.samples.iter().map(|s| s.frame_time)
+    }
+}
@@ -0,0 +1,47 @@
+//! Frame-rate independent playback for the VEL
+//!
+//! Each frame adds the wall time since the last one, scaled by the speed, to
+//! a budget of simulated time and runs as many whole steps as it covers,
+//! carrying the rest to the next frame. The speed is simulated seconds per
+//! wall-clock second, whatever the display's refresh rate.
+
+/// Most steps one frame may run; past this the run falls behind rather than
+/// taking ever longer frames
+pub const MAX_STEPS_PER_FRAME: usize = 2000;
+
+/// What one frame should do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub steps: usize,
+    /// Simulated time left over for the next frame
+    pub carry: f64,
+    /// The budget needed more than `max_steps`; the excess was dropped
+    pub capped: bool,
+}
+
+/// Plan a frame that follows `elapsed` wall seconds at `speed` simulated
+/// seconds per second, with `carry` left from the last frame and steps of
+/// `dt` simulated seconds
+pub fn plan(carry: f64, elapsed: f64, speed: f64, dt: f64, max_steps: usize) -> Frame {
+    if dt <= 0.0 || !dt.is_finite() {
+        return Frame {
+            steps: 0,
+            carry: 0.0,
+            capped: false,
+        };
+    }
+    let budget = carry + elapsed.max(0.0) * speed.max(0.0);
+    let whole = (budget / dt).floor();
+    if whole > max_steps as f64 {
+        return Frame {
+            steps: max_steps,
+            carry: 0.0,
+            capped: true,
+        };
+    }
+    Frame {
+        steps: whole as usize,
+        carry: budget - whole * dt,
+        capped: false,
+    }
+}
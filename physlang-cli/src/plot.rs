@@ -0,0 +1,163 @@
+//! Detector time series for the VEL's plot panel
+//!
+//! Every detector is sampled on every step while the series fit; past
+//! `capacity` rows every other row is dropped and sampling halves, so a long
+//! run keeps an evenly spaced history of bounded size.
+
+use physlang_core::{write_detector_series_csv, DetectorResult};
+use std::io::{self, Write};
+
+/// Default number of rows kept before decimating
+pub const MAX_POINTS: usize = 100_000;
+
+/// Line colours, cycled through in the order detectors are selected
+pub const PALETTE: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(31, 119, 180),
+    egui::Color32::from_rgb(255, 127, 14),
+    egui::Color32::from_rgb(44, 160, 44),
+    egui::Color32::from_rgb(214, 39, 40),
+    egui::Color32::from_rgb(148, 103, 189),
+    egui::Color32::from_rgb(140, 86, 75),
+];
+
+/// Samples of every detector, one row per sampled step, oldest first
+#[derive(Debug)]
+pub struct DetectorSeries {
+    names: Vec<String>,
+    rows: Vec<(usize, Vec<f32>)>,
+    /// Steps between samples, doubled on each decimation
+    stride: usize,
+    capacity: usize,
+}
+
+impl Default for DetectorSeries {
+    fn default() -> Self {
+        Self::new(MAX_POINTS)
+    }
+}
+
+impl DetectorSeries {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            names: Vec::new(),
+            rows: Vec::new(),
+            stride: 1,
+            capacity: capacity.max(2),
+        }
+    }
+
+    /// Record `values` taken at `step`. Rows at or after `step` are dropped
+    /// first, so going back in time rewrites the history from there; the
+    /// series restart if the detectors changed
+    pub fn record(&mut self, step: usize, values: &[DetectorResult]) {
+        let same_detectors = self.names.len() == values.len()
+            && self
+                .names
+                .iter()
+                .zip(values)
+                .all(|(name, v)| *name == v.name);
+        if !same_detectors {
+            self.clear();
+            self.names = values.iter().map(|v| v.name.clone()).collect();
+        }
+        if self.names.is_empty() {
+            return;
+        }
+        let kept = self.rows.partition_point(|(s, _)| *s < step);
+        self.rows.truncate(kept);
+        if !step.is_multiple_of(self.stride) {
+            return;
+        }
+        self.rows
+            .push((step, values.iter().map(|v| v.value).collect()));
+        if self.rows.len() > self.capacity {
+            self.stride *= 2;
+            let stride = self.stride;
+            self.rows.retain(|(s, _)| s.is_multiple_of(stride));
+        }
+    }
+
+    /// Forget every sample, e.g. after a reset or reload
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.stride = 1;
+    }
+
+    /// The detectors being sampled
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Steps between the rows currently kept
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// `(step, value)` samples of `name`, oldest first
+    pub fn points(&self, name: &str) -> Vec<(usize, f32)> {
+        let Some(column) = self.names.iter().position(|n| n == name) else {
+            return Vec::new();
+        };
+        self.rows
+            .iter()
+            .map(|(step, values)| (*step, values[column]))
+            .collect()
+    }
+
+    /// Write the samples of `selected` as CSV, one column per detector
+    pub fn write_csv<W: Write>(&self, writer: W, selected: &[String]) -> io::Result<()> {
+        let columns: Vec<usize> = selected
+            .iter()
+            .filter_map(|name| self.names.iter().position(|n| n == name))
+            .collect();
+        let names: Vec<String> = columns.iter().map(|&c| self.names[c].clone()).collect();
+        let rows: Vec<(usize, Vec<f32>)> = self
+            .rows
+            .iter()
+            .map(|(step, values)| (*step, columns.iter().map(|&c| values[c]).collect()))
+            .collect();
+        write_detector_series_csv(writer, &names, &rows)
+    }
+}
+
+/// The range a plot spans, in steps and in detector values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlotBounds {
+    pub steps: (usize, usize),
+    pub values: (f32, f32),
+}
+
+impl PlotBounds {
+    /// Bounds covering every finite sample of `series`; a flat range is
+    /// widened so it has some height
+    pub fn fit(series: &[Vec<(usize, f32)>]) -> Option<Self> {
+        let samples = || series.iter().flatten();
+        let first = samples().map(|(s, _)| *s).min()?;
+        let last = samples().map(|(s, _)| *s).max()?;
+        let finite = || samples().map(|(_, v)| *v).filter(|v| v.is_finite());
+        let mut low = finite().fold(f32::INFINITY, f32::min);
+        let mut high = finite().fold(f32::NEG_INFINITY, f32::max);
+        if low > high {
+            (low, high) = (-1.0, 1.0);
+        } else if low == high {
+            let pad = (low.abs() * 0.1).max(1.0);
+            (low, high) = (low - pad, high + pad);
+        }
+        Some(Self {
+            steps: (first, last.max(first + 1)),
+            values: (low, high),
+        })
+    }
+
+    /// Where `(step, value)` goes in `rect`, lowest value at the bottom
+    pub fn position(&self, rect: egui::Rect, step: usize, value: f32) -> egui::Pos2 {
+        let (first, last) = self.steps;
+        let (low, high) = self.values;
+        let x = step.saturating_sub(first) as f32 / (last - first) as f32;
+        let y = (value - low) / (high - low);
+        egui::pos2(
+            rect.left() + x * rect.width(),
+            rect.bottom() - y * rect.height(),
+        )
+    }
+}
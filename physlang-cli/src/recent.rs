@@ -0,0 +1,73 @@
+//! Recently opened files for the VEL's File menu
+//!
+//! Kept as one path per line in the user's config directory, most recent
+//! first, so the list survives restarts.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most files the list remembers
+pub const MAX_RECENT: usize = 10;
+
+/// Files opened in the VEL, most recent first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Read the list from `file`; a missing or unreadable file is an empty list
+    pub fn load(file: &Path) -> Self {
+        let mut recent = Self::default();
+        if let Ok(text) = fs::read_to_string(file) {
+            for line in text.lines().rev().filter(|line| !line.trim().is_empty()) {
+                recent.push(PathBuf::from(line));
+            }
+        }
+        recent
+    }
+
+    /// Write the list to `file`, creating its directory if needed
+    pub fn save(&self, file: &Path) -> io::Result<()> {
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for path in &self.paths {
+            text.push_str(&path.to_string_lossy());
+            text.push('\n');
+        }
+        fs::write(file, text)
+    }
+
+    /// Put `path` at the top, dropping an earlier entry for it and the oldest
+    /// past `MAX_RECENT`
+    pub fn push(&mut self, path: PathBuf) {
+        self.remove(&path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT);
+    }
+
+    /// Forget `path`, e.g. because it no longer opens
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|p| p != path);
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
+
+/// Where the list is kept: `physlang/recent-files` under the platform's
+/// config directory, or None if there's no home to put it in
+pub fn default_location() -> Option<PathBuf> {
+    let config = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(config.join("physlang").join("recent-files"))
+}
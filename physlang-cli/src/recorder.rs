@@ -0,0 +1,113 @@
+//! Recording the VEL's canvas to a directory of numbered PNG frames
+//!
+//! Frames are written as they're captured rather than kept in memory, so a
+//! recording can run as long as the disk allows.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest side of a recorded frame, in pixels; larger canvases are scaled
+/// down to fit
+pub const MAX_FRAME_SIDE: u32 = 1280;
+
+/// The size frames of a `width` x `height` canvas are recorded at: the same
+/// aspect ratio, at most `MAX_FRAME_SIDE` on either side and at least 1
+pub fn frame_size(width: f32, height: f32) -> (u32, u32) {
+    let longest = width.max(height).max(1.0);
+    let scale = (MAX_FRAME_SIDE as f32 / longest).min(1.0);
+    let side = |length: f32| ((length * scale).round() as u32).clamp(1, MAX_FRAME_SIDE);
+    (side(width), side(height))
+}
+
+/// A recording in progress
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    /// Size of every frame, fixed when recording starts
+    size: (u32, u32),
+    /// Capture every `every`th step
+    every: usize,
+    frames: usize,
+    bytes: u64,
+    last_step: Option<usize>,
+}
+
+impl Recorder {
+    /// Start recording `size` frames into `dir`, creating it if needed
+    pub fn start(dir: PathBuf, size: (u32, u32), every: usize) -> std::io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            size,
+            every: every.max(1),
+            frames: 0,
+            bytes: 0,
+            last_step: None,
+        })
+    }
+
+    /// Whether the frame for `step` should be captured: steps on the stride
+    /// that haven't been already
+    pub fn wants(&self, step: usize) -> bool {
+        step.is_multiple_of(self.every) && self.last_step != Some(step)
+    }
+
+    /// Write the frame for `step` with `write`, which is given the path of
+    /// the next numbered frame
+    pub fn capture<F>(&mut self, step: usize, write: F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnOnce(&Path) -> Result<(), Box<dyn Error>>,
+    {
+        let path = self.dir.join(format!("frame_{:05}.png", self.frames));
+        write(&path)?;
+        self.bytes += fs::metadata(&path)?.len();
+        self.frames += 1;
+        self.last_step = Some(step);
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Frames written so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Bytes written so far
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Bytes the recording will take if it runs another `steps` steps, from
+    /// the average size of the frames so far
+    pub fn estimate(&self, steps: usize) -> u64 {
+        if self.frames == 0 {
+            return 0;
+        }
+        let average = self.bytes / self.frames as u64;
+        self.bytes + average * steps.div_ceil(self.every) as u64
+    }
+}
+
+/// `bytes` in the largest unit that keeps it at least 1, e.g. "4.2 MB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,918 @@
+//! Drawing of a simulation state, shared by the VEL window and headless output
+//!
+//! `scene` lays the world out as a list of shapes in pixel coordinates through a
+//! `Camera`; the VEL paints them with egui, and `render_world` rasterizes them
+//! with tiny-skia.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use physlang_core::loops::{LoopInstance, LoopKindRuntime, ObservableRuntime, WellInstance};
+use physlang_core::{Force, Viewport, World};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tiny_skia::{
+    Color, FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Stroke, Transform,
+};
+
+/// An RGBA color, straight alpha
+pub type Rgba = [u8; 4];
+
+const SPRING: Rgba = [160, 160, 160, 255];
+const LABEL: Rgba = [0, 0, 0, 255];
+const LABEL_DARK: Rgba = [230, 230, 230, 255];
+const COMPRESSED: Rgba = [40, 90, 230, 255];
+const EXTENDED: Rgba = [230, 40, 40, 255];
+const PLACEHOLDER: Rgba = [160, 160, 160, 255];
+const GRAVITY: Rgba = [140, 80, 200, 255];
+const VELOCITY: Rgba = [220, 50, 50, 255];
+const WELL: Rgba = [255, 140, 0, 160];
+const WELL_ACTIVE: Rgba = [255, 140, 0, 30];
+const LOOP: Rgba = [0, 160, 0, 200];
+const SELECTED: Rgba = [255, 200, 0, 255];
+const GRID: Rgba = [225, 225, 225, 255];
+const GRID_MAJOR: Rgba = [200, 200, 200, 255];
+const GRID_DARK: Rgba = [45, 45, 45, 255];
+const GRID_MAJOR_DARK: Rgba = [70, 70, 70, 255];
+const AXIS: Rgba = [120, 120, 120, 255];
+const GRID_LABEL: Rgba = [140, 140, 140, 255];
+
+/// Particle fills, picked by a hash of the name so a particle keeps its color
+/// across reloads and edits that reorder the source
+const PARTICLE_PALETTE: [Rgba; 10] = [
+    [173, 216, 230, 255],
+    [255, 179, 128, 255],
+    [152, 223, 138, 255],
+    [255, 152, 150, 255],
+    [197, 176, 213, 255],
+    [196, 156, 148, 255],
+    [247, 182, 210, 255],
+    [219, 219, 141, 255],
+    [158, 218, 229, 255],
+    [199, 199, 199, 255],
+];
+
+/// Strain at which a spring is drawn fully blue or red
+const FULL_STRAIN: f32 = 0.5;
+
+/// Radius of every particle when radii don't follow mass, in pixels
+const FIXED_RADIUS: f32 = 8.0;
+
+/// Background of headless frames: egui's light panel color, which the black
+/// labels are drawn for
+const BACKGROUND: Rgba = [248, 248, 248, 255];
+
+/// Background of images drawn for the dark theme: egui's dark panel color
+const DARK_BACKGROUND: Rgba = [27, 27, 27, 255];
+
+/// Size of label text in pixels, egui's default
+const TEXT_SIZE: f32 = 14.0;
+
+/// Something to draw, in pixels with y pointing down
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: Rgba,
+    },
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        fill: Option<Rgba>,
+        stroke: Option<(f32, Rgba)>,
+    },
+    /// An axis-aligned filled rectangle
+    Rect {
+        min: (f32, f32),
+        max: (f32, f32),
+        fill: Rgba,
+    },
+    /// Text centered horizontally on `top`
+    Text {
+        top: (f32, f32),
+        text: String,
+        color: Rgba,
+    },
+}
+
+/// Smallest and largest zoom, to keep the transform invertible
+const MIN_PIXELS_PER_UNIT: f32 = 1e-3;
+const MAX_PIXELS_PER_UNIT: f32 = 1e6;
+
+/// How far outside a particle's circle a click still picks it, in pixels
+const PICK_SLOP: f32 = 4.0;
+
+/// Closest grid lines may be drawn, in pixels; the spacing is a power of 10,
+/// so they are also less than ten times this apart
+const MIN_GRID_PIXELS: f32 = 20.0;
+
+/// Spacing in pixels below which only every fifth grid line is labelled
+const LABEL_EVERY_LINE_PIXELS: f32 = 100.0;
+
+/// How world coordinates map onto a canvas: the world point at the canvas
+/// center and the zoom, with y pointing up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub center: egui::Vec2,
+    pub pixels_per_unit: f32,
+    /// Refit to the particles every frame; cleared by any manual pan or zoom
+    pub auto: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: egui::Vec2::ZERO,
+            pixels_per_unit: 1.0,
+            auto: true,
+        }
+    }
+}
+
+impl Camera {
+    /// Fit the suggested viewport of `world` in 90% of `canvas`; an empty
+    /// world leaves the camera where it is
+    pub fn fit(&mut self, world: &World, canvas: egui::Rect) {
+        if let Some(viewport) = world.bounds().map(Viewport::framing) {
+            let scale_x = (canvas.width() * 0.9) / (2.0 * viewport.half_extent.x);
+            let scale_y = (canvas.height() * 0.9) / (2.0 * viewport.half_extent.y);
+            self.center = egui::vec2(viewport.center.x, viewport.center.y);
+            self.pixels_per_unit = scale_x.min(scale_y);
+        }
+    }
+
+    /// The world-to-screen transform, used for everything drawn
+    pub fn world_to_screen(&self, canvas: egui::Rect, x: f32, y: f32) -> egui::Pos2 {
+        canvas.center() + egui::vec2(x - self.center.x, -(y - self.center.y)) * self.pixels_per_unit
+    }
+
+    /// The world point under `pos`
+    pub fn screen_to_world(&self, canvas: egui::Rect, pos: egui::Pos2) -> egui::Vec2 {
+        let offset = (pos - canvas.center()) / self.pixels_per_unit;
+        self.center + egui::vec2(offset.x, -offset.y)
+    }
+
+    /// Zoom in by `factor` (out if below 1), keeping the world point under
+    /// `about` still
+    pub fn zoom(&mut self, canvas: egui::Rect, about: egui::Pos2, factor: f32) {
+        let anchor = self.screen_to_world(canvas, about);
+        self.pixels_per_unit =
+            (self.pixels_per_unit * factor).clamp(MIN_PIXELS_PER_UNIT, MAX_PIXELS_PER_UNIT);
+        self.center += anchor - self.screen_to_world(canvas, about);
+        self.auto = false;
+    }
+
+    /// Radius a particle of `mass` is drawn with, in pixels
+    pub fn particle_radius(&self, mass: f32) -> f32 {
+        (mass.sqrt() * self.pixels_per_unit * 0.5).clamp(3.0, 20.0)
+    }
+
+    /// Put `point` at the center of the view, keeping the zoom
+    pub fn center_on(&mut self, point: egui::Vec2) {
+        self.center = point;
+    }
+
+    /// Move the view so the world follows a drag of `delta` pixels
+    pub fn pan(&mut self, delta: egui::Vec2) {
+        self.center -= egui::vec2(delta.x, -delta.y) / self.pixels_per_unit;
+        self.auto = false;
+    }
+}
+
+/// Which optional layers `scene` draws, and how it draws particles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlays {
+    pub springs: bool,
+    /// Color springs blue when compressed and red when extended
+    pub strain: bool,
+    /// Dashed lines between gravity pairs
+    pub gravity: bool,
+    /// An arrow along each particle's velocity
+    pub velocities: bool,
+    /// Well thresholds, with the side where the well pulls shaded
+    pub wells: bool,
+    /// Particle names under the particles
+    pub labels: bool,
+    /// Size particles by mass rather than all the same
+    pub mass_radius: bool,
+    /// World-space grid, with the major lines labelled
+    pub grid: bool,
+    /// The x and y axes through the origin
+    pub axes: bool,
+    /// Draw for a dark background
+    pub dark: bool,
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Self {
+            springs: true,
+            strain: false,
+            gravity: false,
+            velocities: false,
+            wells: false,
+            labels: true,
+            mass_radius: true,
+            grid: false,
+            axes: false,
+            dark: false,
+        }
+    }
+}
+
+impl Overlays {
+    /// Name and color of each enabled overlay, for a legend
+    pub fn legend(&self) -> Vec<(&'static str, Rgba)> {
+        let plain = self.springs && !self.strain;
+        let strain = self.springs && self.strain;
+        [
+            (plain, "spring", SPRING),
+            (strain, "spring compressed", COMPRESSED),
+            (strain, "spring extended", EXTENDED),
+            (self.gravity, "gravity", GRAVITY),
+            (self.velocities, "velocity", VELOCITY),
+            (self.wells, "well threshold", WELL),
+        ]
+        .into_iter()
+        .filter(|(enabled, _, _)| *enabled)
+        .map(|(_, name, color)| (name, color))
+        .collect()
+    }
+
+    /// Radius of a particle of `mass` as `camera` draws it, in pixels
+    pub fn particle_radius(&self, camera: &Camera, mass: f32) -> f32 {
+        if self.mass_radius {
+            camera.particle_radius(mass)
+        } else {
+            FIXED_RADIUS
+        }
+    }
+}
+
+/// Grid lines for a zoom level: `spacing` world units apart, with every
+/// `major_every`th line (counting from the origin) a labelled major line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub spacing: f32,
+    pub major_every: i64,
+}
+
+impl Grid {
+    /// The power of 10 that puts lines 20 to 200 pixels apart at
+    /// `pixels_per_unit`; lines under 100 pixels apart are labelled every fifth
+    pub fn for_zoom(pixels_per_unit: f32) -> Self {
+        let spacing = 10f32.powf((MIN_GRID_PIXELS / pixels_per_unit).log10().ceil());
+        let major_every = if spacing * pixels_per_unit < LABEL_EVERY_LINE_PIXELS {
+            5
+        } else {
+            1
+        };
+        Self {
+            spacing,
+            major_every,
+        }
+    }
+
+    /// Indices of the lines between `min` and `max`, the line at index `i`
+    /// being at `i * spacing`
+    pub fn lines(&self, min: f32, max: f32) -> std::ops::RangeInclusive<i64> {
+        (min / self.spacing).ceil() as i64..=(max / self.spacing).floor() as i64
+    }
+
+    pub fn is_major(&self, index: i64) -> bool {
+        index % self.major_every == 0
+    }
+
+    /// The coordinate of line `index` with as many decimals as the major
+    /// spacing needs
+    pub fn label(&self, index: i64) -> String {
+        let major = self.spacing * self.major_every as f32;
+        let decimals = (-major.log10()).ceil().max(0.0) as usize;
+        format!("{:.*}", decimals, index as f32 * self.spacing)
+    }
+}
+
+/// Grid lines, axes, and grid labels across `canvas`
+fn grid_shapes(shapes: &mut Vec<Shape>, camera: &Camera, canvas: egui::Rect, overlays: &Overlays) {
+    let grid = Grid::for_zoom(camera.pixels_per_unit);
+    let min = camera.screen_to_world(canvas, canvas.left_bottom());
+    let max = camera.screen_to_world(canvas, canvas.right_top());
+    let (minor, major) = if overlays.dark {
+        (GRID_DARK, GRID_MAJOR_DARK)
+    } else {
+        (GRID, GRID_MAJOR)
+    };
+    let mut line = |from: egui::Pos2, to: egui::Pos2, width: f32, color: Rgba| {
+        shapes.push(Shape::Line {
+            from: (from.x, from.y),
+            to: (to.x, to.y),
+            width,
+            color,
+        })
+    };
+
+    if overlays.grid {
+        for i in grid.lines(min.x, max.x) {
+            let x = camera
+                .world_to_screen(canvas, i as f32 * grid.spacing, 0.0)
+                .x;
+            let color = if grid.is_major(i) { major } else { minor };
+            line(
+                egui::pos2(x, canvas.top()),
+                egui::pos2(x, canvas.bottom()),
+                1.0,
+                color,
+            );
+        }
+        for i in grid.lines(min.y, max.y) {
+            let y = camera
+                .world_to_screen(canvas, 0.0, i as f32 * grid.spacing)
+                .y;
+            let color = if grid.is_major(i) { major } else { minor };
+            line(
+                egui::pos2(canvas.left(), y),
+                egui::pos2(canvas.right(), y),
+                1.0,
+                color,
+            );
+        }
+    }
+    if overlays.axes {
+        let origin = camera.world_to_screen(canvas, 0.0, 0.0);
+        if (canvas.left()..=canvas.right()).contains(&origin.x) {
+            line(
+                egui::pos2(origin.x, canvas.top()),
+                egui::pos2(origin.x, canvas.bottom()),
+                1.5,
+                AXIS,
+            );
+        }
+        if (canvas.top()..=canvas.bottom()).contains(&origin.y) {
+            line(
+                egui::pos2(canvas.left(), origin.y),
+                egui::pos2(canvas.right(), origin.y),
+                1.5,
+                AXIS,
+            );
+        }
+    }
+    if overlays.grid {
+        // x labels along the bottom edge, y labels along the left
+        for i in grid.lines(min.x, max.x).filter(|&i| grid.is_major(i)) {
+            let x = camera
+                .world_to_screen(canvas, i as f32 * grid.spacing, 0.0)
+                .x;
+            shapes.push(Shape::Text {
+                top: (x, canvas.bottom() - TEXT_SIZE - 4.0),
+                text: grid.label(i),
+                color: GRID_LABEL,
+            });
+        }
+        for i in grid.lines(min.y, max.y).filter(|&i| grid.is_major(i)) {
+            let y = camera
+                .world_to_screen(canvas, 0.0, i as f32 * grid.spacing)
+                .y;
+            shapes.push(Shape::Text {
+                top: (canvas.left() + 24.0, y + 2.0),
+                text: grid.label(i),
+                color: GRID_LABEL,
+            });
+        }
+    }
+}
+
+/// The fill of the particle called `name`, the same on every run
+pub fn particle_color(name: &str) -> Rgba {
+    // FNV-1a, which unlike std's hasher is fixed across Rust versions
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    PARTICLE_PALETTE[hash as usize % PARTICLE_PALETTE.len()]
+}
+
+/// `color` darkened, for outlines
+fn darker([r, g, b, a]: Rgba) -> Rgba {
+    let scale = |c: u8| (c as f32 * 0.55) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+/// Color of a spring of `length` and rest length `rest`: gray at rest,
+/// shading to blue as it's compressed and red as it's extended
+pub fn strain_color(length: f32, rest: f32) -> Rgba {
+    let strain = if rest > 0.0 {
+        (length - rest) / rest
+    } else {
+        length - rest
+    };
+    let t = (strain.abs() / FULL_STRAIN).min(1.0);
+    let target = if strain < 0.0 { COMPRESSED } else { EXTENDED };
+    let mut color = SPRING;
+    for (c, end) in color.iter_mut().zip(target) {
+        *c = (*c as f32 + (end as f32 - *c as f32) * t).round() as u8;
+    }
+    color
+}
+
+/// The particle drawn under `pos`, or nearest to it if circles overlap
+pub fn pick(
+    world: &World,
+    camera: &Camera,
+    overlays: &Overlays,
+    canvas: egui::Rect,
+    pos: egui::Pos2,
+) -> Option<usize> {
+    world
+        .particles
+        .iter()
+        .enumerate()
+        .map(|(i, particle)| {
+            let center = camera.world_to_screen(canvas, particle.pos.x, particle.pos.y);
+            (
+                i,
+                center.distance(pos),
+                overlays.particle_radius(camera, particle.mass),
+            )
+        })
+        .filter(|(_, distance, radius)| *distance <= radius + PICK_SLOP)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _, _)| i)
+}
+
+/// Lay out `world` as `camera` sees it in `canvas`: the grid and axes if
+/// enabled, the enabled force and well overlays, rings around particles driven by active loops, then the
+/// particles, their names, and their velocity arrows, with a ring around
+/// the `selected` particle
+pub fn scene(
+    world: &World,
+    loops: &[LoopInstance],
+    wells: &[WellInstance],
+    camera: &Camera,
+    canvas: egui::Rect,
+    overlays: &Overlays,
+    selected: Option<usize>,
+) -> Vec<Shape> {
+    let to_screen = |x: f32, y: f32| {
+        let pos = camera.world_to_screen(canvas, x, y);
+        (pos.x, pos.y)
+    };
+    let particle_at = |i: usize| {
+        let pos = world.particles[i].pos;
+        to_screen(pos.x, pos.y)
+    };
+    let mut shapes = Vec::new();
+    grid_shapes(&mut shapes, camera, canvas, overlays);
+
+    for force in &world.forces {
+        match force {
+            Force::Spring { a, b, rest, .. } if overlays.springs => {
+                let color = if overlays.strain {
+                    let length = world.particles[*a].pos.distance(world.particles[*b].pos);
+                    strain_color(length, *rest)
+                } else {
+                    SPRING
+                };
+                shapes.push(Shape::Line {
+                    from: particle_at(*a),
+                    to: particle_at(*b),
+                    width: if overlays.strain { 2.0 } else { 1.0 },
+                    color,
+                })
+            }
+            Force::Gravity { a, b, .. } if overlays.gravity => {
+                dashed_line(&mut shapes, particle_at(*a), particle_at(*b), GRAVITY)
+            }
+            _ => {}
+        }
+    }
+
+    for well in wells.iter().filter(|_| overlays.wells) {
+        let (left, top) = (canvas.left(), canvas.top());
+        let (right, bottom) = (canvas.right(), canvas.bottom());
+        // The well pulls once the observable reaches the threshold, so the
+        // right of an x threshold and the top of a y threshold are shaded
+        match well.observable {
+            ObservableRuntime::PositionX(_) => {
+                let (x, _) = to_screen(well.threshold, 0.0);
+                shapes.push(Shape::Rect {
+                    min: (x.clamp(left, right), top),
+                    max: (right, bottom),
+                    fill: WELL_ACTIVE,
+                });
+                shapes.push(Shape::Line {
+                    from: (x, top),
+                    to: (x, bottom),
+                    width: 1.0,
+                    color: WELL,
+                });
+            }
+            ObservableRuntime::PositionY(_) => {
+                let (_, y) = to_screen(0.0, well.threshold);
+                shapes.push(Shape::Rect {
+                    min: (left, top),
+                    max: (right, y.clamp(top, bottom)),
+                    fill: WELL_ACTIVE,
+                });
+                shapes.push(Shape::Line {
+                    from: (left, y),
+                    to: (right, y),
+                    width: 1.0,
+                    color: WELL,
+                });
+            }
+            ObservableRuntime::Distance(_, other) => shapes.push(Shape::Circle {
+                center: particle_at(other),
+                radius: well.threshold * camera.pixels_per_unit,
+                fill: None,
+                stroke: Some((1.0, WELL)),
+            }),
+            // Mass and tag wells have no region; ring the particle they hold
+            ObservableRuntime::Mass(_) | ObservableRuntime::Tag(..) => {
+                if well.is_applying(&world.particles) {
+                    let particle = &world.particles[well.particle_index];
+                    shapes.push(Shape::Circle {
+                        center: particle_at(well.particle_index),
+                        radius: overlays.particle_radius(camera, particle.mass) + 2.0,
+                        fill: None,
+                        stroke: Some((1.0, WELL)),
+                    });
+                }
+            }
+        }
+    }
+
+    for instance in loops.iter().filter(|l| l.active) {
+        let target = match instance.kind {
+            LoopKindRuntime::ForCycles { target_index, .. }
+            | LoopKindRuntime::WhileCondition { target_index, .. } => target_index,
+        };
+        shapes.push(Shape::Circle {
+            center: particle_at(target),
+            radius: overlays.particle_radius(camera, world.particles[target].mass) + 4.0,
+            fill: None,
+            stroke: Some((2.0, LOOP)),
+        });
+    }
+
+    for (i, particle) in world.particles.iter().enumerate() {
+        let center = particle_at(i);
+        let radius = overlays.particle_radius(camera, particle.mass);
+        let fill = particle_color(&particle.name);
+        shapes.push(Shape::Circle {
+            center,
+            radius,
+            fill: Some(fill),
+            stroke: Some((1.0, darker(fill))),
+        });
+        if selected == Some(i) {
+            shapes.push(Shape::Circle {
+                center,
+                radius: radius + 7.0,
+                fill: None,
+                stroke: Some((2.5, SELECTED)),
+            });
+        }
+        if overlays.labels {
+            shapes.push(Shape::Text {
+                top: (center.0, center.1 + radius + 10.0),
+                text: particle.name.clone(),
+                color: if overlays.dark { LABEL_DARK } else { LABEL },
+            });
+        }
+        if overlays.velocities {
+            let velocity = egui::vec2(particle.vel.x, -particle.vel.y) * camera.pixels_per_unit;
+            velocity_arrow(&mut shapes, center, radius, velocity);
+        }
+    }
+
+    // Empty world: show a placeholder rather than a blank canvas
+    if world.particles.is_empty() {
+        shapes.push(Shape::Text {
+            top: (canvas.center().x, canvas.top() + canvas.height() * 0.4),
+            text: "No particles".to_string(),
+            color: PLACEHOLDER,
+        });
+    }
+    shapes
+}
+
+/// Push a dashed line from `from` to `to`
+fn dashed_line(shapes: &mut Vec<Shape>, from: (f32, f32), to: (f32, f32), color: Rgba) {
+    const DASH: f32 = 6.0;
+    const GAP: f32 = 4.0;
+    let (from, to) = (egui::pos2(from.0, from.1), egui::pos2(to.0, to.1));
+    let length = from.distance(to);
+    if length == 0.0 {
+        return;
+    }
+    let direction = (to - from) / length;
+    let mut start = 0.0;
+    while start < length {
+        let end = (start + DASH).min(length);
+        let (a, b) = (from + direction * start, from + direction * end);
+        shapes.push(Shape::Line {
+            from: (a.x, a.y),
+            to: (b.x, b.y),
+            width: 1.0,
+            color,
+        });
+        start = end + GAP;
+    }
+}
+
+/// Push an arrow showing one second of travel at `velocity` pixels per
+/// second, starting at the edge of the particle and at most
+/// `MAX_ARROW_LENGTH` long
+fn velocity_arrow(shapes: &mut Vec<Shape>, center: (f32, f32), radius: f32, velocity: egui::Vec2) {
+    const MAX_ARROW_LENGTH: f32 = 60.0;
+    const HEAD: f32 = 6.0;
+    let length = velocity.length().min(MAX_ARROW_LENGTH);
+    if !length.is_finite() || length < 1.0 {
+        return;
+    }
+    let direction = velocity.normalized();
+    let from = egui::pos2(center.0, center.1) + direction * radius;
+    let tip = from + direction * length;
+    let mut line = |a: egui::Pos2, b: egui::Pos2| {
+        shapes.push(Shape::Line {
+            from: (a.x, a.y),
+            to: (b.x, b.y),
+            width: 1.5,
+            color: VELOCITY,
+        })
+    };
+    line(from, tip);
+    for angle in [0.5_f32, -0.5] {
+        let (sin, cos) = angle.sin_cos();
+        let back = egui::vec2(
+            -direction.x * cos + direction.y * sin,
+            -direction.x * sin - direction.y * cos,
+        );
+        line(tip, tip + back * HEAD);
+    }
+}
+
+/// Paint a scene with egui
+pub fn paint(painter: &egui::Painter, shapes: &[Shape]) {
+    let color = |[r, g, b, a]: Rgba| egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+    let pos = |(x, y): (f32, f32)| egui::pos2(x, y);
+    for shape in shapes {
+        match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                color: c,
+            } => {
+                painter.line_segment([pos(*from), pos(*to)], egui::Stroke::new(*width, color(*c)));
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                if let Some(fill) = fill {
+                    painter.circle_filled(pos(*center), *radius, color(*fill));
+                }
+                if let Some((width, c)) = stroke {
+                    painter.circle_stroke(
+                        pos(*center),
+                        *radius,
+                        egui::Stroke::new(*width, color(*c)),
+                    );
+                }
+            }
+            Shape::Rect { min, max, fill } => {
+                let rect = egui::Rect::from_min_max(pos(*min), pos(*max));
+                painter.rect_filled(rect, 0.0, color(*fill));
+            }
+            Shape::Text {
+                top,
+                text,
+                color: c,
+            } => {
+                painter.text(
+                    pos(*top),
+                    egui::Align2::CENTER_TOP,
+                    text,
+                    egui::FontId::proportional(TEXT_SIZE),
+                    color(*c),
+                );
+            }
+        }
+    }
+}
+
+/// Draw the world as the VEL would with its camera fitted into a `width` x
+/// `height` image
+pub fn render_world(
+    world: &World,
+    loops: &[LoopInstance],
+    wells: &[WellInstance],
+    size: (u32, u32),
+    overlays: &Overlays,
+) -> Result<Pixmap, Box<dyn Error>> {
+    let mut camera = Camera::default();
+    camera.fit(world, image_rect(size));
+    render_view(world, loops, wells, &camera, size, overlays, None)
+}
+
+/// Draw the world through `camera` into a `width` x `height` image, with
+/// `selected` marked, exactly as `scene` lays it out on a canvas that size
+pub fn render_view(
+    world: &World,
+    loops: &[LoopInstance],
+    wells: &[WellInstance],
+    camera: &Camera,
+    size: (u32, u32),
+    overlays: &Overlays,
+    selected: Option<usize>,
+) -> Result<Pixmap, Box<dyn Error>> {
+    let shapes = scene(
+        world,
+        loops,
+        wells,
+        camera,
+        image_rect(size),
+        overlays,
+        selected,
+    );
+    let background = if overlays.dark {
+        DARK_BACKGROUND
+    } else {
+        BACKGROUND
+    };
+    rasterize(&shapes, size, background)
+}
+
+/// The canvas covering a `width` x `height` image
+fn image_rect((width, height): (u32, u32)) -> egui::Rect {
+    egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32))
+}
+
+/// Paint `shapes` into a new `width` x `height` image filled with
+/// `background`; the image counterpart of `paint`
+pub fn rasterize(
+    shapes: &[Shape],
+    (width, height): (u32, u32),
+    background: Rgba,
+) -> Result<Pixmap, Box<dyn Error>> {
+    let mut pixmap = Pixmap::new(width, height).ok_or("invalid image size")?;
+    pixmap.fill(to_color(background));
+    let fonts = egui::FontDefinitions::default();
+    let font = label_font(&fonts)?;
+
+    for shape in shapes {
+        match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                let mut path = PathBuilder::new();
+                path.move_to(from.0, from.1);
+                path.line_to(to.0, to.1);
+                if let Some(path) = path.finish() {
+                    let stroke = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, &solid(*color), &stroke, Transform::identity(), None);
+                }
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let Some(circle) = PathBuilder::from_circle(center.0, center.1, *radius) else {
+                    continue;
+                };
+                if let Some(fill) = fill {
+                    pixmap.fill_path(
+                        &circle,
+                        &solid(*fill),
+                        FillRule::Winding,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+                if let Some((width, color)) = stroke {
+                    let stroke = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(
+                        &circle,
+                        &solid(*color),
+                        &stroke,
+                        Transform::identity(),
+                        None,
+                    );
+                }
+            }
+            Shape::Rect { min, max, fill } => {
+                if let Some(rect) = tiny_skia::Rect::from_ltrb(min.0, min.1, max.0, max.1) {
+                    pixmap.fill_rect(rect, &solid(*fill), Transform::identity(), None);
+                }
+            }
+            Shape::Text { top, text, color } => {
+                let scaled = font.as_scaled(PxScale::from(TEXT_SIZE));
+                let width = text_width(&font, text);
+                let baseline = top.1 + scaled.ascent();
+                draw_text(
+                    &mut pixmap,
+                    &font,
+                    text,
+                    top.0 - width / 2.0,
+                    baseline,
+                    *color,
+                );
+            }
+        }
+    }
+    Ok(pixmap)
+}
+
+fn to_color([r, g, b, a]: Rgba) -> Color {
+    Color::from_rgba8(r, g, b, a)
+}
+
+fn solid(color: Rgba) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(to_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+/// The proportional font egui ships with, used for labels
+pub fn label_font(fonts: &egui::FontDefinitions) -> Result<FontRef<'_>, Box<dyn Error>> {
+    let data = fonts
+        .font_data
+        .get("Ubuntu-Light")
+        .ok_or("egui's default font is missing")?;
+    Ok(FontRef::try_from_slice(&data.font)?)
+}
+
+/// Width of `text` in pixels at the label size
+fn text_width(font: &FontRef<'_>, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(TEXT_SIZE));
+    text.chars()
+        .map(|c| scaled.h_advance(scaled.glyph_id(c)))
+        .sum()
+}
+
+/// Draw `text` at the label size with its baseline starting at (`x`, `y`),
+/// blending `color` over the opaque pixels underneath
+pub fn draw_text(pixmap: &mut Pixmap, font: &FontRef<'_>, text: &str, x: f32, y: f32, color: Rgba) {
+    let scaled = font.as_scaled(PxScale::from(TEXT_SIZE));
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    let mut caret = x;
+    for c in text.chars() {
+        let mut glyph = scaled.scaled_glyph(c);
+        let advance = scaled.h_advance(glyph.id);
+        glyph.position = ab_glyph::point(caret, y);
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let origin = outlined.px_bounds().min;
+            outlined.draw(|gx, gy, coverage| {
+                let px = origin.x as i32 + gx as i32;
+                let py = origin.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    return;
+                }
+                let pixel = &mut pixels[(py * width + px) as usize];
+                let alpha = coverage.min(1.0) * color[3] as f32 / 255.0;
+                let blend = |under: u8, over: u8| {
+                    (under as f32 + (over as f32 - under as f32) * alpha).round() as u8
+                };
+                if let Some(blended) = PremultipliedColorU8::from_rgba(
+                    blend(pixel.red(), color[0]),
+                    blend(pixel.green(), color[1]),
+                    blend(pixel.blue(), color[2]),
+                    255,
+                ) {
+                    *pixel = blended;
+                }
+            });
+        }
+        caret += advance;
+    }
+}
+
+/// Encode an opaque pixmap as an 8-bit RGBA PNG
+pub fn write_png(path: &Path, pixmap: &Pixmap) -> Result<(), Box<dyn Error>> {
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, pixmap.width(), pixmap.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    // Every pixel is opaque, so premultiplied and straight alpha agree
+    writer.write_image_data(pixmap.data())?;
+    writer.finish()?;
+    Ok(())
+}
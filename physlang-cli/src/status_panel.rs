@@ -0,0 +1,121 @@
+//! Loop and well status for the VEL's side panel
+//!
+//! Describes each runtime loop and well in terms of the source (particle
+//! names, conditions as written) so the panel can show why a loop does or
+//! doesn't fire.
+
+use physlang_core::loops::{
+    ConditionRuntime, LoopInstance, LoopKindRuntime, ObservableRuntime, WellInstance,
+};
+use physlang_core::World;
+use std::f32::consts::PI;
+
+/// One row of the loop list
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopStatus {
+    /// The loop's label, or its position among the loops
+    pub title: String,
+    pub kind: &'static str,
+    pub target: String,
+    pub frequency: f32,
+    /// Progress towards the next iteration, from 0 to 1
+    pub phase: f32,
+    /// Cycles remaining for a `for` loop, the condition for a `while` loop
+    pub progress: String,
+    pub active: bool,
+}
+
+/// One row of the well list
+#[derive(Debug, Clone, PartialEq)]
+pub struct WellStatus {
+    pub name: String,
+    pub particle: String,
+    pub observable: String,
+    pub threshold: f32,
+    /// Whether the well pushes its particle on the next step
+    pub applying: bool,
+}
+
+/// Describe every loop on the current state of `world`
+pub fn loop_statuses(loops: &[LoopInstance], world: &World) -> Vec<LoopStatus> {
+    loops
+        .iter()
+        .enumerate()
+        .map(|(i, instance)| {
+            let (kind, frequency, phase, progress) = match &instance.kind {
+                LoopKindRuntime::ForCycles {
+                    cycles_remaining,
+                    frequency,
+                    phase,
+                    ..
+                } => (
+                    "for",
+                    *frequency,
+                    *phase,
+                    format!("{} cycles remaining", cycles_remaining),
+                ),
+                LoopKindRuntime::WhileCondition {
+                    condition,
+                    frequency,
+                    phase,
+                    ..
+                } => (
+                    "while",
+                    *frequency,
+                    *phase,
+                    condition_text(condition, world),
+                ),
+            };
+            LoopStatus {
+                title: instance
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| format!("loop {}", i)),
+                kind,
+                target: instance.target_name.clone(),
+                frequency,
+                phase: (phase / (2.0 * PI)).clamp(0.0, 1.0),
+                progress,
+                active: instance.active,
+            }
+        })
+        .collect()
+}
+
+/// Describe every well on the current state of `world`
+pub fn well_statuses(wells: &[WellInstance], world: &World) -> Vec<WellStatus> {
+    wells
+        .iter()
+        .map(|well| WellStatus {
+            name: well.name.clone(),
+            particle: world.particles[well.particle_index].name.clone(),
+            observable: observable_text(&well.observable, world),
+            threshold: well.threshold,
+            applying: well.is_applying(&world.particles),
+        })
+        .collect()
+}
+
+/// An observable as it would be written in source
+pub fn observable_text(observable: &ObservableRuntime, world: &World) -> String {
+    let name = |i: usize| &world.particles[i].name;
+    match observable {
+        ObservableRuntime::PositionX(i) => format!("position({}).x", name(*i)),
+        ObservableRuntime::PositionY(i) => format!("position({}).y", name(*i)),
+        ObservableRuntime::Distance(a, b) => format!("distance({}, {})", name(*a), name(*b)),
+        ObservableRuntime::Mass(i) => format!("mass({})", name(*i)),
+        ObservableRuntime::Tag(i, tag) => format!("tag({}, {})", name(*i), tag),
+    }
+}
+
+/// A `while` condition as it would be written in source
+pub fn condition_text(condition: &ConditionRuntime, world: &World) -> String {
+    match condition {
+        ConditionRuntime::LessThan(observable, value) => {
+            format!("{} < {}", observable_text(observable, world), value)
+        }
+        ConditionRuntime::GreaterThan(observable, value) => {
+            format!("{} > {}", observable_text(observable, world), value)
+        }
+    }
+}
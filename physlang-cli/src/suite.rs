@@ -0,0 +1,195 @@
+//! `physlang test`: run programs and compare their detectors with `.expect` files
+//!
+//! Each line of an `.expect` file is one assertion about a detector:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! gap ~= 2.5 +- 0.01
+//! final_x == 3.0
+//! energy < 10
+//! ```
+
+use physlang_core::{run_program, SimulationResult};
+use std::fmt;
+use std::path::Path;
+
+/// How an assertion compares a detector with its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    /// Within the tolerance, inclusive
+    Approx(f32),
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+/// One line of an `.expect` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expectation {
+    /// 1-based line in the `.expect` file
+    pub line: usize,
+    pub detector: String,
+    pub comparison: Comparison,
+    pub value: f32,
+}
+
+impl Expectation {
+    /// Whether `actual` satisfies the assertion
+    pub fn holds(&self, actual: f32) -> bool {
+        match self.comparison {
+            Comparison::Equal => actual == self.value,
+            Comparison::NotEqual => actual != self.value,
+            Comparison::Approx(tolerance) => (actual - self.value).abs() <= tolerance,
+            Comparison::Less => actual < self.value,
+            Comparison::LessEqual => actual <= self.value,
+            Comparison::Greater => actual > self.value,
+            Comparison::GreaterEqual => actual >= self.value,
+        }
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self.comparison {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Approx(_) => "~=",
+            Comparison::Less => "<",
+            Comparison::LessEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterEqual => ">=",
+        };
+        write!(f, "{} {} {}", self.detector, operator, self.value)?;
+        if let Comparison::Approx(tolerance) = self.comparison {
+            write!(f, " +- {}", tolerance)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operators in the order they're tried, so `<=` isn't read as `<`
+const OPERATORS: [&str; 7] = ["~=", "==", "!=", "<=", ">=", "<", ">"];
+
+/// Parse an `.expect` file; errors name the offending line
+pub fn parse_expectations(text: &str) -> Result<Vec<Expectation>, String> {
+    let mut expectations = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let expectation = parse_line(line, i + 1).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        expectations.push(expectation);
+    }
+    Ok(expectations)
+}
+
+fn parse_line(line: &str, number: usize) -> Result<Expectation, String> {
+    let (position, operator) = OPERATORS
+        .iter()
+        .filter_map(|op| line.find(op).map(|position| (position, *op)))
+        .min_by_key(|&(position, op)| (position, usize::MAX - op.len()))
+        .ok_or_else(|| format!("expected a comparison such as '==' or '~=' in '{}'", line))?;
+    let detector = line[..position].trim();
+    if detector.is_empty() || !detector.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("expected a detector name before '{}'", operator));
+    }
+    let rest = line[position + operator.len()..].trim();
+
+    let (value, tolerance) = match rest.split_once("+-") {
+        Some((value, tolerance)) => (value.trim(), Some(parse_number(tolerance.trim())?)),
+        None => (rest, None),
+    };
+    let value = parse_number(value)?;
+    let comparison = match (operator, tolerance) {
+        ("~=", Some(tolerance)) if tolerance >= 0.0 => Comparison::Approx(tolerance),
+        ("~=", Some(_)) => return Err("the tolerance must not be negative".to_string()),
+        ("~=", None) => return Err("'~=' needs a tolerance, e.g. '~= 2.5 +- 0.01'".to_string()),
+        (_, Some(_)) => return Err(format!("'+-' only goes with '~=', not '{}'", operator)),
+        ("==", None) => Comparison::Equal,
+        ("!=", None) => Comparison::NotEqual,
+        ("<", None) => Comparison::Less,
+        ("<=", None) => Comparison::LessEqual,
+        (">", None) => Comparison::Greater,
+        (_, None) => Comparison::GreaterEqual,
+    };
+    Ok(Expectation {
+        line: number,
+        detector: detector.to_string(),
+        comparison,
+        value,
+    })
+}
+
+fn parse_number(text: &str) -> Result<f32, String> {
+    text.parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("expected a number, got '{}'", text))
+}
+
+/// Outcome of one program
+#[derive(Debug)]
+pub enum Outcome {
+    /// Every assertion held
+    Pass(usize),
+    /// The assertions that failed, each with why
+    Fail(Vec<String>),
+    /// The program or its `.expect` file couldn't be used
+    Error(String),
+    /// There's no `.expect` file next to the program
+    Skipped,
+}
+
+/// Run `program` and check it against the `.expect` file beside it
+pub fn test_program(program: &Path) -> Outcome {
+    let expect_path = program.with_extension("expect");
+    if !expect_path.exists() {
+        return Outcome::Skipped;
+    }
+    let expectations = match std::fs::read_to_string(&expect_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_expectations(&text))
+    {
+        Ok(expectations) => expectations,
+        Err(e) => return Outcome::Error(format!("{}: {}", expect_path.display(), e)),
+    };
+    let result = match std::fs::read_to_string(program)
+        .map_err(|e| e.into())
+        .and_then(|source| run_program(&source))
+    {
+        Ok(result) => result,
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+    check(&expectations, &result)
+}
+
+/// Evaluate the assertions against a run's detectors
+pub fn check(expectations: &[Expectation], result: &SimulationResult) -> Outcome {
+    let mut failures = Vec::new();
+    for expectation in expectations {
+        let actual = result
+            .detectors
+            .iter()
+            .find(|d| d.name == expectation.detector);
+        match actual {
+            Some(actual) if expectation.holds(actual.value) => {}
+            Some(actual) => failures.push(format!(
+                "line {}: {} (got {})",
+                expectation.line, expectation, actual.value
+            )),
+            None => failures.push(format!(
+                "line {}: {} (no detector named '{}')",
+                expectation.line, expectation, expectation.detector
+            )),
+        }
+    }
+    if failures.is_empty() {
+        Outcome::Pass(expectations.len())
+    } else {
+        Outcome::Fail(failures)
+    }
+}
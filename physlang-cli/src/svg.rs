@@ -0,0 +1,143 @@
+//! SVG rendering of particle trajectories for `physlang export`
+
+use physlang_core::SimulationContext;
+use std::io::{self, Write};
+
+/// Trail colors, cycled when there are more particles than colors
+pub const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// Sampled positions of every particle over a run
+#[derive(Debug, Default)]
+pub struct Trajectories {
+    names: Vec<String>,
+    /// `points[i]` are the positions of particle `i`, oldest first
+    points: Vec<Vec<(f32, f32)>>,
+}
+
+impl Trajectories {
+    /// Record the current position of every particle
+    pub fn record(&mut self, ctx: &SimulationContext) {
+        if self.names.is_empty() {
+            self.names = ctx.world.particles.iter().map(|p| p.name.clone()).collect();
+            self.points = vec![Vec::new(); self.names.len()];
+        }
+        for (trail, particle) in self.points.iter_mut().zip(&ctx.world.particles) {
+            trail.push((particle.pos.x, particle.pos.y));
+        }
+    }
+
+    /// Smallest box around every sample as (min x, min y, max x, max y)
+    fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        self.points.iter().flatten().fold(None, |bounds, &(x, y)| {
+            Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            })
+        })
+    }
+}
+
+/// Write the trails as one SVG: a polyline per particle, a hollow marker where
+/// it started, a filled one where it ended, and a legend of names
+///
+/// The y axis points up as in the simulation, so y is negated in the drawing
+pub fn write_svg(out: &mut impl Write, trajectories: &Trajectories) -> io::Result<()> {
+    let (x0, y0, x1, y1) = trajectories.bounds().unwrap_or((0.0, 0.0, 0.0, 0.0));
+    // Pad by 5% of the larger side, and give a motionless world some size
+    let size = (x1 - x0).max(y1 - y0).max(1.0);
+    let pad = size * 0.05;
+    let (left, top) = (x0 - pad, -y1 - pad);
+    let (width, height) = (x1 - x0 + 2.0 * pad, y1 - y0 + 2.0 * pad);
+    let marker = size * 0.01;
+    let font = size * 0.03;
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="800" height="{}">"#,
+        left,
+        top,
+        width,
+        height,
+        (800.0 * height / width).round()
+    )?;
+    writeln!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="white"/>"#,
+        left, top, width, height
+    )?;
+
+    for (i, (name, trail)) in trajectories
+        .names
+        .iter()
+        .zip(&trajectories.points)
+        .enumerate()
+    {
+        let color = PALETTE[i % PALETTE.len()];
+        let points: Vec<String> = trail
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, flip(*y)))
+            .collect();
+        writeln!(out, r#"<g id="particle-{}">"#, i)?;
+        writeln!(out, "<title>{}</title>", escape(name))?;
+        writeln!(
+            out,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+            points.join(" "),
+            color
+        )?;
+        if let (Some(start), Some(end)) = (trail.first(), trail.last()) {
+            writeln!(
+                out,
+                r#"<circle class="start" cx="{}" cy="{}" r="{}" fill="white" stroke="{}" vector-effect="non-scaling-stroke"/>"#,
+                start.0,
+                flip(start.1),
+                marker,
+                color
+            )?;
+            writeln!(
+                out,
+                r#"<circle class="end" cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                end.0,
+                flip(end.1),
+                marker,
+                color
+            )?;
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    // Legend in the top-left corner, one line per particle
+    writeln!(
+        out,
+        r#"<g class="legend" font-family="sans-serif" font-size="{}">"#,
+        font
+    )?;
+    for (i, name) in trajectories.names.iter().enumerate() {
+        let y = top + font * (1.5 + 1.2 * i as f32);
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+            left + font * 0.5,
+            y,
+            PALETTE[i % PALETTE.len()],
+            escape(name)
+        )?;
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")
+}
+
+/// Simulation y to SVG y, which points down (without printing `-0`)
+fn flip(y: f32) -> f32 {
+    0.0 - y
+}
+
+/// Escape text for use in XML content
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,87 @@
+//! Checkpoint history behind the VEL's timeline scrubber
+//!
+//! A checkpoint is kept every `every` steps, up to `capacity` of them with the
+//! oldest dropped first. Seeking restores the nearest checkpoint at or before
+//! the target step and steps forward from there, so the state shown is exactly
+//! the one the run had at that step.
+
+use physlang_core::{step_simulation, Checkpoint, SimulationContext};
+use std::collections::VecDeque;
+
+/// Default number of steps between checkpoints
+pub const DEFAULT_EVERY: usize = 100;
+
+/// Default number of checkpoints kept
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// Checkpoints of one run, oldest first
+pub struct Timeline {
+    pub every: usize,
+    capacity: usize,
+    checkpoints: VecDeque<Checkpoint>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVERY, DEFAULT_CAPACITY)
+    }
+}
+
+impl Timeline {
+    pub fn new(every: usize, capacity: usize) -> Self {
+        Self {
+            every: every.max(1),
+            capacity: capacity.max(1),
+            checkpoints: VecDeque::new(),
+        }
+    }
+
+    /// Keep a checkpoint of `ctx` if it's at a multiple of `every` steps and
+    /// past the last one kept
+    pub fn record(&mut self, ctx: &SimulationContext) {
+        let step = ctx.current_step;
+        let past_last = self
+            .checkpoints
+            .back()
+            .is_none_or(|last| step > last.step());
+        if past_last && step.is_multiple_of(self.every.max(1)) {
+            if self.checkpoints.len() == self.capacity {
+                self.checkpoints.pop_front();
+            }
+            self.checkpoints.push_back(ctx.checkpoint());
+        }
+    }
+
+    /// The earliest step that can still be reached
+    pub fn earliest(&self) -> Option<usize> {
+        self.checkpoints.front().map(Checkpoint::step)
+    }
+
+    /// Forget every checkpoint, e.g. after a reset or reload
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+    }
+
+    /// Move `ctx` to `target` (or as close as the run allows): restore the
+    /// nearest checkpoint at or before it, drop the checkpoints after that,
+    /// which the replay records again, and step forward
+    /// Returns false, leaving `ctx` alone, if `target` is before the earliest
+    /// checkpoint
+    pub fn seek(&mut self, ctx: &mut SimulationContext, target: usize) -> bool {
+        let Some(index) = self.checkpoints.iter().rposition(|c| c.step() <= target) else {
+            return false;
+        };
+        if ctx.restore(&self.checkpoints[index]).is_err() {
+            return false;
+        }
+        self.checkpoints.truncate(index + 1);
+        while ctx.current_step < target {
+            let finished = step_simulation(ctx);
+            self.record(ctx);
+            if finished {
+                break;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,111 @@
+//! Line-based unified diffs, for `physlang check --fix --dry-run`
+
+/// Lines of context around each change
+const CONTEXT: usize = 3;
+
+/// One line of the edit script between two texts
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Remove,
+    Insert,
+}
+
+/// A unified diff from `old` to `new` labelled with `path`, or an empty string
+/// if they're the same
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let script = edit_script(&old, &new);
+    if script.iter().all(|&(edit, _, _)| edit == Edit::Keep) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", path, path);
+    let changes: Vec<usize> = (0..script.len())
+        .filter(|&i| script[i].0 != Edit::Keep)
+        .collect();
+    let mut next_change = 0;
+    while next_change < changes.len() {
+        // Grow the hunk while the next change is within reach of its context
+        let start = changes[next_change].saturating_sub(CONTEXT);
+        let mut end = changes[next_change] + 1;
+        next_change += 1;
+        while next_change < changes.len() && changes[next_change] <= end + 2 * CONTEXT {
+            end = changes[next_change] + 1;
+            next_change += 1;
+        }
+        let end = (end + CONTEXT).min(script.len());
+        let hunk = &script[start..end];
+
+        let old_start = hunk
+            .iter()
+            .find_map(|&(e, o, _)| (e != Edit::Insert).then_some(o));
+        let new_start = hunk
+            .iter()
+            .find_map(|&(e, _, n)| (e != Edit::Remove).then_some(n));
+        let old_len = hunk.iter().filter(|&&(e, _, _)| e != Edit::Insert).count();
+        let new_len = hunk.iter().filter(|&&(e, _, _)| e != Edit::Remove).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start.unwrap_or(script[start].1), old_len),
+            range(new_start.unwrap_or(script[start].2), new_len)
+        ));
+        for &(edit, o, n) in hunk {
+            let (marker, line) = match edit {
+                Edit::Keep => (' ', old[o]),
+                Edit::Remove => ('-', old[o]),
+                Edit::Insert => ('+', new[n]),
+            };
+            out.push(marker);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// `start,len` of a hunk side, 1-based; an empty side names the line before it
+fn range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// The shortest edit script from the longest common subsequence of lines: each
+/// entry is an edit with the old and new line indices it sits at
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<(Edit, usize, usize)> {
+    // lcs[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            script.push((Edit::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals come before insertions, as in diff(1)
+            script.push((Edit::Remove, i, j));
+            i += 1;
+        } else {
+            script.push((Edit::Insert, i, j));
+            j += 1;
+        }
+    }
+    script
+}
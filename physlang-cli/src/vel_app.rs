@@ -3,82 +3,345 @@
 //! This module provides an interactive visualization of PhysLang programs
 //! with live editing support via file watching.
 
+use crate::breakpoints::{self, Breakpoint, Operator, Quantity};
+use crate::comparison::{self, Baseline};
+use crate::detector_panel::{self, DetectorHistory};
+use crate::diagnostics_panel;
+use crate::divergence::{Divergence, DivergenceWatch};
+use crate::editor::{self, ExternalChange, SourceEditor};
+use crate::file_browser::FileBrowser;
+use crate::hot_reload;
+use crate::inspector;
+use crate::perf_stats::{self, FrameSample, PerfStats};
+use crate::playback;
+use crate::plot::{self, DetectorSeries, PlotBounds};
+use crate::recent::{self, RecentFiles};
+use crate::recorder::{self, Recorder};
+use crate::render;
+use crate::status_panel;
+use crate::timeline::Timeline;
 use eframe::egui;
 use notify::{Event, RecommendedWatcher, Watcher};
 use physlang_core::{
-    build_simulation_context_from_source, get_particle_states, step_simulation,
-    SimulationContext,
+    build_simulation_context_with_overrides, export_state_source, format_detector_value,
+    lint_source, parse_program, rebuild_simulation_context_with_overrides, step_simulation,
+    Diagnostic, DiagnosticSeverity, ProgramDiff, RunOptions, SimulationContext, SourceLocation,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
+use std::time::Instant;
 
 /// Visual Evaluation Loop application
 pub struct VelApp {
-    source_path: PathBuf,
+    /// The file being run, once one is open
+    source_path: Option<PathBuf>,
+    recent: RecentFiles,
+    /// Where `recent` is kept, if anywhere
+    recent_location: Option<PathBuf>,
+    /// The Open dialog, while it's shown
+    open_dialog: Option<FileBrowser>,
+    /// The source the current context was built from
     source_text: String,
+    editor: SourceEditor,
+    show_editor: bool,
     ctx_opt: Option<SimulationContext>,
+    /// Replaces the program's dt, e.g. after "Reset with dt/2"
+    dt_override: Option<f32>,
+    /// How the run diverged, while it's stopped on it
+    divergence: Option<Divergence>,
+    divergence_watch: DivergenceWatch,
+    /// The version shown beside the current one in split view
+    baseline: Option<Baseline>,
+    /// The baseline pane moves with the main camera
+    linked_cameras: bool,
+    baseline_camera: render::Camera,
     last_load_error: Option<String>,
+    /// Errors and warnings for `source_text`, kept when loading succeeds
+    diagnostics: Vec<Diagnostic>,
+    /// Where the editor should put its cursor on the next frame
+    jump_to: Option<SourceLocation>,
+    show_diagnostics: bool,
     playing: bool,
+    /// Simulated seconds per wall-clock second
     speed_multiplier: f32,
+    /// Simulated time owed to the next frame
+    playback_carry: f64,
+    /// The last frame ran out of steps before catching up
+    lagging: bool,
+    /// Kept across reloads and resets
+    camera: render::Camera,
+    /// The particle being inspected, by name so it survives reloads
+    selected: Option<String>,
+    /// Keep the selected particle at the center of the view
+    follow: bool,
+    /// The world point under the mouse, as of the last frame
+    cursor_world: Option<egui::Vec2>,
+    show_coordinates: bool,
+    overlays: render::Overlays,
+    show_detectors: bool,
+    show_status: bool,
+    /// Every detector, sampled each step for the plot
+    series: DetectorSeries,
+    show_plot: bool,
+    plotted: Vec<String>,
+    /// Outcome of the last CSV export
+    plot_export: Option<String>,
+    /// Outcome of the last state export
+    state_export: Option<String>,
+    breakpoints: Vec<Breakpoint>,
+    show_breakpoints: bool,
+    /// The recording in progress, if any
+    recorder: Option<Recorder>,
+    show_record: bool,
+    /// Directory the next recording goes to, as typed
+    record_dir: String,
+    /// Steps between recorded frames
+    record_every: usize,
+    /// Outcome of the last recording
+    record_status: Option<String>,
+    /// The canvas as of the last frame, which recordings match
+    canvas: egui::Rect,
+    /// What the last breakpoint to fire said, and when it fired
+    breakpoint_hit: Option<(String, f64)>,
+    show_perf: bool,
+    perf: PerfStats,
+    /// Steps taken so far this frame, and the time spent in them
+    frame_steps: usize,
+    frame_step_time: f64,
+    /// Decimal places of the detector values
+    detector_precision: usize,
+    detector_history: DetectorHistory,
+    /// Checkpoints for scrubbing back through the run
+    timeline: Timeline,
     #[allow(dead_code)] // Kept alive to maintain file watching
     file_watcher: Option<RecommendedWatcher>,
     file_receiver: mpsc::Receiver<notify::Result<Event>>,
-    needs_reload: bool,
+    /// The source the context was built from, once the file changed under it
+    reload_from: Option<String>,
+    /// What the last reload from disk kept, and when it happened
+    reload_toast: Option<(String, f64)>,
 }
 
 impl VelApp {
-    pub fn new(source_path: PathBuf, _cc: &eframe::CreationContext<'_>) -> Self {
-        let source_text = std::fs::read_to_string(&source_path)
-            .unwrap_or_else(|e| format!("Error reading file: {}", e));
-
-        let (tx, rx) = mpsc::channel();
-        let mut watcher = notify::recommended_watcher(move |res| {
-            // Silently ignore broken pipe errors - they can happen during shutdown
-            let _ = tx.send(res);
-        }).ok();
-
-        // Start watching the file
-        if let Some(ref mut w) = watcher {
-            if let Err(_e) = w.watch(&source_path, notify::RecursiveMode::NonRecursive) {
-                // Don't print to stderr here to avoid broken pipe issues
-                // File watching is optional for VEL functionality
-            }
-        }
+    pub fn new(source_path: Option<PathBuf>, _cc: &eframe::CreationContext<'_>) -> Self {
+        let recent_location = recent::default_location();
+        let recent = recent_location
+            .as_deref()
+            .map(RecentFiles::load)
+            .unwrap_or_default();
+        let (_, rx) = mpsc::channel();
 
         let mut app = Self {
-            source_path,
-            source_text: source_text.clone(),
+            source_path: None,
+            recent,
+            recent_location,
+            open_dialog: None,
+            source_text: String::new(),
+            editor: SourceEditor::default(),
+            show_editor: false,
             ctx_opt: None,
+            dt_override: None,
+            divergence: None,
+            divergence_watch: DivergenceWatch::default(),
+            baseline: None,
+            linked_cameras: true,
+            baseline_camera: render::Camera::default(),
             last_load_error: None,
+            diagnostics: Vec::new(),
+            jump_to: None,
+            show_diagnostics: true,
             playing: false,
             speed_multiplier: 1.0,
-            file_watcher: watcher,
+            playback_carry: 0.0,
+            lagging: false,
+            camera: render::Camera::default(),
+            selected: None,
+            follow: false,
+            cursor_world: None,
+            show_coordinates: true,
+            overlays: render::Overlays::default(),
+            show_detectors: true,
+            show_status: false,
+            series: DetectorSeries::default(),
+            show_plot: false,
+            plotted: Vec::new(),
+            plot_export: None,
+            state_export: None,
+            breakpoints: Vec::new(),
+            show_breakpoints: false,
+            recorder: None,
+            show_record: false,
+            record_dir: "frames".to_string(),
+            record_every: 1,
+            record_status: None,
+            canvas: egui::Rect::NOTHING,
+            breakpoint_hit: None,
+            show_perf: false,
+            perf: PerfStats::default(),
+            frame_steps: 0,
+            frame_step_time: 0.0,
+            detector_precision: 3,
+            detector_history: DetectorHistory::default(),
+            timeline: Timeline::default(),
+            file_watcher: None,
             file_receiver: rx,
-            needs_reload: false,
+            reload_from: None,
+            reload_toast: None,
         };
 
-        // Initial load
-        app.reload_context();
+        // Initial load, or the Open dialog if there's nothing to load
+        if let Some(path) = source_path {
+            app.open(path);
+        }
+        if app.source_path.is_none() {
+            app.open_dialog = Some(FileBrowser::new(app.browse_dir()));
+        }
 
         app
     }
 
+    /// Switch to the program in `path`, starting over with a fresh view; if
+    /// it can't be read, the current program stays and the error is shown
+    fn open(&mut self, path: PathBuf) {
+        // File events come with absolute paths
+        let path = path.canonicalize().unwrap_or(path);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                let message = format!("opening {}: {}", path.display(), e);
+                self.diagnostics = vec![Diagnostic::error(message.clone(), None)];
+                self.last_load_error = Some(message);
+                self.recent.remove(&path);
+                self.save_recent();
+                return;
+            }
+        };
+
+        self.stop_recording();
+        self.record_dir = path.with_extension("frames").display().to_string();
+        let (watcher, receiver) = watch_file(&path);
+        self.file_watcher = watcher;
+        self.file_receiver = receiver;
+        self.reload_from = None;
+        self.reload_toast = None;
+        self.recent.push(path.clone());
+        self.save_recent();
+        self.source_path = Some(path);
+        self.source_text = text.clone();
+        self.editor = SourceEditor::new(text);
+
+        self.playing = false;
+        self.playback_carry = 0.0;
+        self.camera = render::Camera::default();
+        self.selected = None;
+        self.follow = false;
+        self.breakpoints.clear();
+        self.plotted.clear();
+        self.plot_export = None;
+        self.state_export = None;
+        self.diagnostics.clear();
+        self.dt_override = None;
+        self.baseline = None;
+        self.reload_context();
+    }
+
+    /// Read the file again, dropping unsaved edits
+    fn reload_file(&mut self) {
+        let Some(path) = self.source_path.clone() else {
+            return;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                self.editor = SourceEditor::new(text.clone());
+                self.source_text = text;
+                self.reload_context();
+            }
+            Err(e) => {
+                self.last_load_error = Some(format!("reading {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    fn save_recent(&self) {
+        if let Some(ref location) = self.recent_location {
+            if let Err(e) = self.recent.save(location) {
+                log::warn!("saving recent files to {}: {}", location.display(), e);
+            }
+        }
+    }
+
+    /// Where the Open dialog starts: the open file's directory, or the
+    /// working directory
+    fn browse_dir(&self) -> PathBuf {
+        self.source_path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default()
+    }
+
     fn reload_context(&mut self) {
-        match build_simulation_context_from_source(&self.source_text) {
-            Ok((mut ctx, _diagnostics)) => {
+        self.detector_history.clear();
+        self.timeline.clear();
+        self.series.clear();
+        self.breakpoint_hit = None;
+        self.breakpoints.iter_mut().for_each(Breakpoint::reset);
+        self.divergence = None;
+        self.divergence_watch.clear();
+        let options = self.run_options();
+        if let Some(ref mut baseline) = self.baseline {
+            baseline.restart(&options);
+        }
+        match build_simulation_context_with_overrides(&self.source_text, &options) {
+            Ok((mut ctx, diagnostics)) => {
                 ctx.current_step = 0;
                 self.ctx_opt = Some(ctx);
+                self.sample();
                 self.last_load_error = None;
+                self.diagnostics = diagnostics.into_iter().collect();
+                self.diagnostics.extend(lint_source(&self.source_text));
             }
             Err(e) => {
+                self.diagnostics = diagnostics_panel::check_source(&self.source_text);
+                // Errors past static analysis only come back as the message
+                let analysis_failed = self
+                    .diagnostics
+                    .iter()
+                    .any(|d| d.severity == DiagnosticSeverity::Error);
+                if !analysis_failed {
+                    self.diagnostics.push(Diagnostic::error(e.to_string(), None));
+                }
                 self.last_load_error = Some(format!("{}", e));
                 self.ctx_opt = None;
                 self.playing = false;
             }
         }
+        self.editor.error_lines = editor::error_lines(&self.source_text, &self.diagnostics);
     }
 
-    fn check_file_changes(&mut self) {
+    /// How both the current run and the baseline are built
+    fn run_options(&self) -> RunOptions {
+        RunOptions {
+            dt: self.dt_override,
+            ..RunOptions::default()
+        }
+    }
+
+    /// Turn split view on, comparing against the source as it is now, or off
+    fn set_split(&mut self, on: bool) {
+        if on {
+            self.baseline = Some(Baseline::new(self.source_text.clone(), &self.run_options()));
+            self.baseline_camera = self.camera;
+            // Start both from step 0 so they stay in step
+            self.reload_context();
+            self.playing = false;
+        } else {
+            self.baseline = None;
+        }
+    }
+
+    fn check_file_changes(&mut self, now: f64) {
         // Check for file change events
         while let Ok(event) = self.file_receiver.try_recv() {
             match event {
@@ -87,11 +350,20 @@ impl VelApp {
                     paths,
                     ..
                 }) => {
-                    if paths.contains(&self.source_path) {
-                        // Re-read the file
-                        if let Ok(new_text) = std::fs::read_to_string(&self.source_path) {
-                            self.source_text = new_text;
-                            self.needs_reload = true;
+                    let Some(ref path) = self.source_path else {
+                        continue;
+                    };
+                    if paths.contains(path) {
+                        // Re-read the file; unsaved edits in the editor wait
+                        // for the user to choose between them and the file
+                        if let Ok(new_text) = std::fs::read_to_string(path) {
+                            if self.editor.external_change(new_text) == ExternalChange::Reloaded {
+                                let previous = std::mem::replace(
+                                    &mut self.source_text,
+                                    self.editor.text.clone(),
+                                );
+                                self.reload_from.get_or_insert(previous);
+                            }
                         }
                     }
                 }
@@ -102,44 +374,1019 @@ impl VelApp {
             }
         }
 
-        if self.needs_reload {
-            self.reload_context();
-            self.needs_reload = false;
+        if let Some(previous) = self.reload_from.take() {
+            self.hot_reload(&previous, now);
+        }
+    }
+
+    /// Reload after the file changed from `previous`: edits that keep the
+    /// declarations continue the run where it is, others restart it with the
+    /// same play state
+    fn hot_reload(&mut self, previous: &str, now: f64) {
+        let diff = hot_reload::classify_edit(previous, &self.source_text);
+        if hot_reload::keeps_world(diff) && self.continue_run(previous) {
+            let step = self.ctx_opt.as_ref().map_or(0, |c| c.current_step);
+            self.reload_toast = Some((hot_reload::toast(diff, step), now));
+            return;
+        }
+        let playing = self.playing;
+        self.reload_context();
+        self.playing = playing && self.ctx_opt.is_some();
+        self.reload_toast = Some((hot_reload::toast(ProgramDiff::Structural, 0), now));
+    }
+
+    /// Carry the running context over to `source_text`, edited from
+    /// `previous` without changing its declarations; false if it couldn't be
+    fn continue_run(&mut self, previous: &str) -> bool {
+        let (Some(ctx), Ok(previous_program)) = (self.ctx_opt.take(), parse_program(previous))
+        else {
+            return false;
+        };
+        let options = self.run_options();
+        match rebuild_simulation_context_with_overrides(
+            &self.source_text,
+            &options,
+            &previous_program,
+            ctx,
+        ) {
+            // A let that changed the generated particles restarts the run
+            Ok(reload) if reload.diff == ProgramDiff::Structural => false,
+            Ok(reload) => {
+                if reload.diff == ProgramDiff::LetsOnly {
+                    // Checkpoints hold the old force parameters
+                    self.timeline.clear();
+                }
+                self.ctx_opt = Some(reload.ctx);
+                self.last_load_error = None;
+                self.diagnostics = reload.diagnostics.into_iter().collect();
+                self.diagnostics.extend(lint_source(&self.source_text));
+                self.editor.error_lines =
+                    editor::error_lines(&self.source_text, &self.diagnostics);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The edit buffer with Apply and Save, error lines shaded
+    fn editor_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Apply").on_hover_text("Rebuild from the edited text").clicked() {
+                self.source_text = self.editor.text.clone();
+                self.reload_context();
+                self.playing = false;
+            }
+            let save = ui
+                .add_enabled(self.source_path.is_some(), egui::Button::new("Save"))
+                .on_hover_text("Write the edited text to the file");
+            if let (true, Some(path)) = (save.clicked(), self.source_path.as_ref()) {
+                if let Err(e) = self.editor.save(path) {
+                    self.last_load_error = Some(format!("saving {}: {}", path.display(), e));
+                }
+            }
+            if self.editor.is_dirty() {
+                ui.label(egui::RichText::new("● unsaved").color(egui::Color32::GRAY));
+            }
+        });
+        let id = egui::Id::new("source editor");
+        let mut scroll = egui::ScrollArea::vertical();
+        if let Some(location) = self.jump_to.take() {
+            let index = editor::char_index(&self.editor.text, location);
+            let mut state = egui::TextEdit::load_state(ui.ctx(), id).unwrap_or_default();
+            let cursor = egui::text::CCursor::new(index);
+            state
+                .cursor
+                .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+            egui::TextEdit::store_state(ui.ctx(), id, state);
+            ui.memory_mut(|memory| memory.request_focus(id));
+            // Put the line a few rows from the top
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+            let offset = location.line.saturating_sub(3) as f32 * row_height;
+            scroll = scroll.vertical_scroll_offset(offset);
+        }
+        let error_lines = self.editor.error_lines.clone();
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = editor::highlight(text, &error_lines, ui.style());
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+        scroll.show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.editor.text)
+                    .id(id)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .layouter(&mut layouter),
+            );
+        });
+    }
+
+    /// Every diagnostic for the source; clicking a located one opens it in
+    /// the editor
+    fn diagnostics_list(&mut self, ui: &mut egui::Ui) {
+        let rows = diagnostics_panel::rows(&self.source_text, &self.diagnostics);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for row in rows {
+                let color = match row.severity {
+                    DiagnosticSeverity::Error => egui::Color32::RED,
+                    DiagnosticSeverity::Warning => egui::Color32::from_rgb(200, 140, 0),
+                };
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(row.icon()).color(color));
+                    match row.location {
+                        Some(location) => {
+                            let place = format!("{}:{}", location.line, location.column);
+                            if ui.link(place).on_hover_text("Show in the editor").clicked() {
+                                self.show_editor = true;
+                                self.jump_to = Some(location);
+                            }
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                    ui.label(row.text);
+                });
+            }
+        });
+    }
+
+    /// Ask whether a change on disk should replace unsaved edits
+    fn conflict_prompt(&mut self, ctx: &egui::Context) {
+        if !self.editor.has_conflict() {
+            return;
+        }
+        egui::Window::new("File changed on disk")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The file changed while the editor has unsaved edits.");
+                ui.horizontal(|ui| {
+                    if ui.button("Load the file").clicked() {
+                        if let Some(text) = self.editor.accept_external() {
+                            self.source_text = text;
+                            self.reload_context();
+                        }
+                    }
+                    if ui.button("Keep my edits").clicked() {
+                        self.editor.keep_local();
+                    }
+                });
+            });
+    }
+
+    /// Open, Open recent, and Reload
+    fn file_menu(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Open…").clicked() {
+            self.open_dialog = Some(FileBrowser::new(self.browse_dir()));
+            ui.close_menu();
+        }
+        ui.menu_button("Open recent", |ui| {
+            if self.recent.paths().is_empty() {
+                ui.label(egui::RichText::new("No recent files").color(egui::Color32::GRAY));
+            }
+            for path in self.recent.paths().to_vec() {
+                if ui.button(path.display().to_string()).clicked() {
+                    self.open(path);
+                    ui.close_menu();
+                }
+            }
+        });
+        let reload = ui
+            .add_enabled(self.source_path.is_some(), egui::Button::new("Reload"))
+            .on_hover_text("Read the file again, dropping unsaved edits");
+        if reload.clicked() {
+            self.reload_file();
+            ui.close_menu();
+        }
+    }
+
+    /// The Open dialog: browse for a `.phys` file or type its path
+    fn open_window(&mut self, ctx: &egui::Context) {
+        let Some(browser) = self.open_dialog.as_mut() else {
+            return;
+        };
+        let mut shown = true;
+        let mut chosen = None;
+        egui::Window::new("Open")
+            .open(&mut shown)
+            .collapsible(false)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("⬆").on_hover_text("Parent directory").clicked() {
+                        browser.up();
+                    }
+                    ui.monospace(browser.dir.display().to_string());
+                });
+                ui.separator();
+                if let Some(ref error) = browser.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                let mut entered = None;
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for entry in &browser.entries {
+                        if ui.selectable_label(false, entry.label()).clicked() {
+                            if entry.is_dir {
+                                entered = Some(entry.path.clone());
+                            } else {
+                                chosen = Some(entry.path.clone());
+                            }
+                        }
+                    }
+                });
+                if let Some(dir) = entered {
+                    browser.enter(dir);
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let field = ui.text_edit_singleline(&mut browser.typed);
+                    let submitted =
+                        field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Open").clicked() || submitted) && !browser.typed.is_empty() {
+                        chosen = Some(browser.dir.join(browser.typed.trim()));
+                    }
+                });
+            });
+        if let Some(path) = chosen {
+            self.open_dialog = None;
+            self.open(path);
+        } else if !shown {
+            self.open_dialog = None;
+        }
+    }
+
+    /// Simulate one step, then check the breakpoints; true if the run
+    /// should stop, because it finished or a breakpoint fired
+    fn advance(&mut self, now: f64) -> bool {
+        // Only timed while the stats are shown
+        let timer = self.show_perf.then(Instant::now);
+        let finished = self.ctx_opt.as_mut().is_none_or(step_simulation);
+        if let Some(started) = timer {
+            self.frame_step_time += started.elapsed().as_secs_f64();
+            self.frame_steps += 1;
+        }
+        if let Some(ref mut baseline) = self.baseline {
+            baseline.step();
+        }
+        self.sample();
+        self.capture_frame();
+        let Some(ref ctx) = self.ctx_opt else {
+            return true;
+        };
+        if let Some(divergence) = self.divergence_watch.check(ctx) {
+            self.divergence = Some(divergence);
+            return true;
+        }
+        if let Some(hit) = breakpoints::check_all(&mut self.breakpoints, ctx) {
+            let message = format!("Breakpoint at step {}: {}", ctx.current_step, hit);
+            self.breakpoint_hit = Some((message, now));
+            return true;
+        }
+        finished
+    }
+
+    /// Start recording into `record_dir` at the canvas's current size, with
+    /// the current step as the first frame
+    fn start_recording(&mut self) {
+        let size = recorder::frame_size(self.canvas.width(), self.canvas.height());
+        let dir = PathBuf::from(self.record_dir.trim());
+        match Recorder::start(dir.clone(), size, self.record_every) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.record_status = None;
+                self.capture_frame();
+            }
+            Err(e) => self.record_status = Some(format!("recording to {}: {}", dir.display(), e)),
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            self.record_status = Some(format!(
+                "wrote {} frames ({}) to {}",
+                recorder.frames(),
+                recorder::format_size(recorder.bytes()),
+                recorder.dir().display()
+            ));
+        }
+    }
+
+    /// Write the current step to the recording if it's due; a failed write
+    /// ends the recording
+    fn capture_frame(&mut self) {
+        let (Some(recorder), Some(ctx)) = (self.recorder.as_mut(), self.ctx_opt.as_ref()) else {
+            return;
+        };
+        if !recorder.wants(ctx.current_step) {
+            return;
+        }
+        // The same view as the canvas, scaled to the frame
+        let size = recorder.size();
+        let frame = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(size.0 as f32, size.1 as f32),
+        );
+        let mut camera = self.camera;
+        if camera.auto {
+            camera.fit(&ctx.world, frame);
+        } else if self.canvas.width() > 0.0 {
+            camera.pixels_per_unit *= frame.width() / self.canvas.width();
+        }
+        let selected = self
+            .selected
+            .as_ref()
+            .and_then(|name| ctx.symbols().resolve(name))
+            .map(|id| id.index());
+        let overlays = &self.overlays;
+        let written = recorder.capture(ctx.current_step, |path| {
+            let pixmap = render::render_view(
+                &ctx.world, &ctx.loops, &ctx.wells, &camera, size, overlays, selected,
+            )?;
+            render::write_png(path, &pixmap)
+        });
+        if let Err(e) = written {
+            let dir = recorder.dir().display().to_string();
+            self.stop_recording();
+            self.record_status = Some(format!("recording to {}: {}", dir, e));
+        }
+    }
+
+    /// Keep what the timeline and the plot need from the current step
+    fn sample(&mut self) {
+        let Some(ref ctx) = self.ctx_opt else {
+            return;
+        };
+        self.timeline.record(ctx);
+        self.divergence_watch.record(ctx);
+        if let Ok(values) = ctx.detector_values() {
+            self.series.record(ctx.current_step, &values);
+        }
+    }
+
+    /// Write the source with every particle where it is now, next to the
+    /// source file or to the clipboard
+    fn export_state(&mut self, ctx: &egui::Context, to_clipboard: bool) {
+        let (Some(sim), Some(source_path)) = (&self.ctx_opt, &self.source_path) else {
+            return;
+        };
+        let text = match export_state_source(&self.source_text, &sim.world) {
+            Ok(text) => text,
+            Err(e) => {
+                self.state_export = Some(format!("exporting state: {}", e));
+                return;
+            }
+        };
+        self.state_export = Some(if to_clipboard {
+            ctx.copy_text(text);
+            format!("copied step {} to the clipboard", sim.current_step)
+        } else {
+            let path = source_path.with_extension("state.phys");
+            match std::fs::write(&path, text) {
+                Ok(()) => format!("wrote {}", path.display()),
+                Err(e) => format!("writing {}: {}", path.display(), e),
+            }
+        });
+    }
+
+    /// The selected detectors against the step number
+    fn plot_panel(&mut self, ui: &mut egui::Ui) {
+        self.plotted.retain(|name| self.series.names().contains(name));
+        ui.horizontal(|ui| {
+            let selected = match self.plotted.len() {
+                0 => "None".to_string(),
+                1 => self.plotted[0].clone(),
+                n => format!("{} detectors", n),
+            };
+            egui::ComboBox::from_label("Detectors")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for name in self.series.names() {
+                        let mut on = self.plotted.contains(name);
+                        if ui.checkbox(&mut on, name).changed() {
+                            if on {
+                                self.plotted.push(name.clone());
+                            } else {
+                                self.plotted.retain(|n| n != name);
+                            }
+                        }
+                    }
+                });
+            let export = ui.add_enabled(!self.plotted.is_empty(), egui::Button::new("Export CSV"));
+            if let (true, Some(source_path)) = (export.clicked(), self.source_path.as_ref()) {
+                let path = source_path.with_extension("series.csv");
+                let written = std::fs::File::create(&path).and_then(|file| {
+                    self.series
+                        .write_csv(std::io::BufWriter::new(file), &self.plotted)
+                });
+                self.plot_export = Some(match written {
+                    Ok(()) => format!("wrote {}", path.display()),
+                    Err(e) => format!("writing {}: {}", path.display(), e),
+                });
+            }
+            if let Some(ref message) = self.plot_export {
+                ui.label(egui::RichText::new(message).color(egui::Color32::GRAY));
+            }
+            if self.series.stride() > 1 {
+                ui.label(
+                    egui::RichText::new(format!("every {} steps", self.series.stride()))
+                        .color(egui::Color32::GRAY),
+                );
+            }
+        });
+
+        let series: Vec<Vec<(usize, f32)>> = self
+            .plotted
+            .iter()
+            .map(|name| self.series.points(name))
+            .collect();
+        let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let text_color = ui.visuals().weak_text_color();
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, text_color));
+        let Some(bounds) = PlotBounds::fit(&series) else {
+            return;
+        };
+
+        // Axis ranges in the corners, the plot itself inset from them
+        let font = egui::FontId::monospace(10.0);
+        let corner = |pos, align, text: String| {
+            painter.text(pos, align, text, font.clone(), text_color);
+        };
+        let (low, high) = bounds.values;
+        let (first, last) = bounds.steps;
+        corner(
+            rect.left_top() + egui::vec2(4.0, 2.0),
+            egui::Align2::LEFT_TOP,
+            high.to_string(),
+        );
+        corner(
+            rect.left_bottom() + egui::vec2(4.0, -2.0),
+            egui::Align2::LEFT_BOTTOM,
+            low.to_string(),
+        );
+        corner(
+            rect.center_bottom() + egui::vec2(0.0, -2.0),
+            egui::Align2::CENTER_BOTTOM,
+            format!("step {}..{}", first, last),
+        );
+        let area = rect.shrink2(egui::vec2(8.0, 16.0));
+
+        for (i, (name, points)) in self.plotted.iter().zip(&series).enumerate() {
+            let color = plot::PALETTE[i % plot::PALETTE.len()];
+            let line: Vec<egui::Pos2> = points
+                .iter()
+                .filter(|(_, v)| v.is_finite())
+                .map(|&(step, v)| bounds.position(area, step, v))
+                .collect();
+            painter.add(egui::Shape::line(line, egui::Stroke::new(1.5, color)));
+            let legend = rect.right_top() + egui::vec2(-4.0, 2.0 + 14.0 * i as f32);
+            painter.text(legend, egui::Align2::RIGHT_TOP, name, font.clone(), color);
+        }
+    }
+
+    /// A slider from the earliest checkpoint to the last step; dragging it
+    /// rewinds or replays the run to the chosen step
+    fn timeline_bar(&mut self, ui: &mut egui::Ui) {
+        let options = self.run_options();
+        let Some(ref mut ctx) = self.ctx_opt else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Checkpoint every");
+            ui.add(egui::DragValue::new(&mut self.timeline.every).range(1..=100_000));
+            ui.label("steps");
+            ui.separator();
+
+            let earliest = self.timeline.earliest().unwrap_or(0);
+            let mut step = ctx.current_step;
+            ui.spacing_mut().slider_width = (ui.available_width() - 80.0).max(100.0);
+            let slider = egui::Slider::new(&mut step, earliest..=ctx.max_steps).text("step");
+            if ui.add(slider).changed() && step != ctx.current_step {
+                self.playing = false;
+                self.timeline.seek(ctx, step);
+                if let Some(ref mut baseline) = self.baseline {
+                    baseline.sync_to(ctx.current_step, &options);
+                }
+                self.breakpoints.iter_mut().for_each(Breakpoint::reset);
+                if let Ok(values) = ctx.detector_values() {
+                    self.series.record(ctx.current_step, &values);
+                }
+            }
+        });
+    }
+
+    /// Each detector's name, current value (red when NaN), and a sparkline of
+    /// its recent values
+    fn detector_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Detectors");
+        ui.horizontal(|ui| {
+            ui.label("Precision:");
+            ui.add(egui::DragValue::new(&mut self.detector_precision).range(0..=9));
+        });
+        ui.separator();
+
+        let Some(ref ctx) = self.ctx_opt else {
+            return;
+        };
+        let values = match ctx.detector_values() {
+            Ok(values) => values,
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", e));
+                return;
+            }
+        };
+        if values.is_empty() {
+            ui.label(egui::RichText::new("No detectors").color(egui::Color32::GRAY));
+            return;
         }
+        self.detector_history.record(ctx.current_step, &values);
+
+        for detector in &values {
+            ui.horizontal(|ui| {
+                ui.label(&detector.name);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let text = format_detector_value(detector.value, Some(self.detector_precision));
+                    let text = egui::RichText::new(text).monospace();
+                    if detector.value.is_nan() {
+                        ui.label(text.color(egui::Color32::RED));
+                    } else {
+                        ui.label(text);
+                    }
+                });
+            });
+            if let Some(samples) = self.detector_history.samples(&detector.name) {
+                let (rect, _) = ui.allocate_exact_size(
+                    egui::vec2(ui.available_width(), 30.0),
+                    egui::Sense::hover(),
+                );
+                let points = detector_panel::sparkline(samples, rect);
+                let stroke = egui::Stroke::new(1.0, ui.visuals().text_color());
+                ui.painter().add(egui::Shape::line(points, stroke));
+            }
+            ui.add_space(4.0);
+        }
+    }
+
+    /// Each loop's target, phase, and progress, and whether each well is
+    /// pulling its particle
+    fn status_panel(&self, ui: &mut egui::Ui) {
+        let Some(ref ctx) = self.ctx_opt else {
+            return;
+        };
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::CollapsingHeader::new(format!("Loops ({})", ctx.loops.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for status in status_panel::loop_statuses(&ctx.loops, &ctx.world) {
+                        let state = if status.active { "active" } else { "inactive" };
+                        ui.label(egui::RichText::new(&status.title).strong());
+                        ui.label(format!(
+                            "{} on {}, {} Hz, {}",
+                            status.kind, status.target, status.frequency, state
+                        ));
+                        ui.label(&status.progress);
+                        ui.add(egui::ProgressBar::new(status.phase).text("phase"));
+                        ui.add_space(4.0);
+                    }
+                });
+            egui::CollapsingHeader::new(format!("Wells ({})", ctx.wells.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for status in status_panel::well_statuses(&ctx.wells, &ctx.world) {
+                        ui.horizontal(|ui| {
+                            let (color, tip) = if status.applying {
+                                (egui::Color32::from_rgb(255, 140, 0), "applying force")
+                            } else {
+                                (egui::Color32::GRAY, "not applying force")
+                            };
+                            ui.label(egui::RichText::new("●").color(color)).on_hover_text(tip);
+                            ui.label(egui::RichText::new(&status.name).strong());
+                        });
+                        ui.label(format!(
+                            "on {} if {} >= {}",
+                            status.particle, status.observable, status.threshold
+                        ));
+                        ui.add_space(4.0);
+                    }
+                });
+        });
+    }
+
+    /// Scroll to zoom about the cursor, drag to pan, `+`/`-` to zoom about
+    /// the center, and the arrow keys to pan
+    fn handle_camera_input(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        rect: egui::Rect,
+    ) {
+        pan_and_zoom(ui, response, rect, &mut self.camera);
+        // Click a particle to inspect it, or empty space to stop
+        if let (true, Some(pos), Some(ctx)) = (
+            response.clicked(),
+            response.interact_pointer_pos(),
+            &self.ctx_opt,
+        ) {
+            self.selected = render::pick(&ctx.world, &self.camera, &self.overlays, rect, pos)
+                .map(|i| ctx.world.particles[i].name.clone());
+        }
+
+        // Keys typed into the editor are not for the camera
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        const KEY_ZOOM: f32 = 1.25;
+        const KEY_PAN: f32 = 40.0;
+        ui.input(|i| {
+            if i.key_pressed(egui::Key::Plus) || i.key_pressed(egui::Key::Equals) {
+                self.camera.zoom(rect, rect.center(), KEY_ZOOM);
+            }
+            if i.key_pressed(egui::Key::Minus) {
+                self.camera.zoom(rect, rect.center(), 1.0 / KEY_ZOOM);
+            }
+            let arrows = [
+                (egui::Key::ArrowLeft, egui::vec2(KEY_PAN, 0.0)),
+                (egui::Key::ArrowRight, egui::vec2(-KEY_PAN, 0.0)),
+                (egui::Key::ArrowUp, egui::vec2(0.0, KEY_PAN)),
+                (egui::Key::ArrowDown, egui::vec2(0.0, -KEY_PAN)),
+            ];
+            for (key, delta) in arrows {
+                if i.key_pressed(key) {
+                    self.camera.pan(delta);
+                }
+            }
+        });
+    }
+}
+
+/// Pan `camera` by dragging `response` and zoom it with the scroll wheel
+/// over it
+fn pan_and_zoom(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    rect: egui::Rect,
+    camera: &mut render::Camera,
+) {
+    if response.dragged() {
+        camera.pan(response.drag_delta());
+    }
+    if let Some(cursor) = response.hover_pos() {
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            camera.zoom(rect, cursor, (scroll * 0.002).exp());
+        }
+    }
+}
+
+/// Draw `ctx`'s world through `camera` into `rect`, with the particle named
+/// `selected` marked; returns its index
+fn draw_world(
+    painter: &egui::Painter,
+    ctx: &SimulationContext,
+    camera: &render::Camera,
+    rect: egui::Rect,
+    overlays: &render::Overlays,
+    selected: Option<&str>,
+) -> Option<usize> {
+    let selected = selected
+        .and_then(|name| ctx.symbols().resolve(name))
+        .map(|id| id.index());
+    let shapes = render::scene(
+        &ctx.world, &ctx.loops, &ctx.wells, camera, rect, overlays, selected,
+    );
+    render::paint(painter, &shapes);
+    selected
+}
+
+/// A pane's title, centered along its top edge
+fn pane_title(painter: &egui::Painter, rect: egui::Rect, title: &str) {
+    painter.text(
+        rect.center_top() + egui::vec2(0.0, 6.0),
+        egui::Align2::CENTER_TOP,
+        title,
+        egui::FontId::proportional(14.0),
+        egui::Color32::GRAY,
+    );
+}
+
+/// Watch `path` for changes, reported on the returned channel; the watcher
+/// is None if the platform couldn't start one, since watching is optional
+fn watch_file(
+    path: &Path,
+) -> (Option<RecommendedWatcher>, mpsc::Receiver<notify::Result<Event>>) {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        // Ignore send errors: the receiver is dropped when another file opens
+        let _ = tx.send(res);
+    })
+    .ok();
+    if let Some(ref mut w) = watcher {
+        if let Err(e) = w.watch(path, notify::RecursiveMode::NonRecursive) {
+            log::warn!("watching {}: {}", path.display(), e);
+        }
+    }
+    (watcher, rx)
+}
+
+/// Rows for editing `breakpoints`, watching any of `choices`
+fn breakpoints_ui(ui: &mut egui::Ui, breakpoints: &mut Vec<Breakpoint>, choices: &[Quantity]) {
+    let mut remove = None;
+    for (i, breakpoint) in breakpoints.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let mut changed = ui.checkbox(&mut breakpoint.enabled, "").changed();
+            egui::ComboBox::from_id_source(("breakpoint quantity", i))
+                .selected_text(breakpoint.quantity.to_string())
+                .show_ui(ui, |ui| {
+                    for quantity in choices {
+                        let text = quantity.to_string();
+                        changed |= ui
+                            .selectable_value(&mut breakpoint.quantity, quantity.clone(), text)
+                            .changed();
+                    }
+                });
+            egui::ComboBox::from_id_source(("breakpoint operator", i))
+                .selected_text(breakpoint.operator.symbol())
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for operator in Operator::ALL {
+                        changed |= ui
+                            .selectable_value(&mut breakpoint.operator, operator, operator.symbol())
+                            .changed();
+                    }
+                });
+            changed |= ui
+                .add(egui::DragValue::new(&mut breakpoint.threshold).speed(0.01))
+                .changed();
+            // A changed condition starts watching afresh
+            if changed {
+                breakpoint.reset();
+            }
+            if ui.button("🗑").on_hover_text("Remove").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        breakpoints.remove(i);
+    }
+    if breakpoints.is_empty() {
+        ui.label(egui::RichText::new("No breakpoints").color(egui::Color32::GRAY));
+    }
+    if ui
+        .add_enabled(!choices.is_empty(), egui::Button::new("➕ Add"))
+        .clicked()
+    {
+        breakpoints.push(Breakpoint::new(choices[0].clone(), Operator::Below, 0.0));
     }
 }
 
+/// Rolling performance figures over a bar graph of recent frame times
+fn perf_overlay(ui: &mut egui::Ui, perf: &PerfStats, particles: usize, loops: usize) {
+    let millis = |seconds: Option<f64>| match seconds {
+        Some(seconds) => format!("{:.3} ms", seconds * 1000.0),
+        None => "–".to_string(),
+    };
+    ui.monospace(format!("steps/s   {:.0}", perf.steps_per_second()));
+    ui.monospace(format!("per step  {}", millis(perf.step_time())));
+    ui.monospace(format!("per frame {}", millis(perf.frame_time())));
+    ui.monospace(format!("particles {}", particles));
+    ui.monospace(format!("loops     {} active", loops));
+
+    // One bar per frame, full height at two 60 Hz frames, with a line at one
+    const BAR_WIDTH: f32 = 1.5;
+    const FULL: f64 = 2.0 / 60.0;
+    let size = egui::vec2(BAR_WIDTH * perf_stats::SAMPLES as f32, 30.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+    for (i, time) in perf.frame_times().enumerate() {
+        let height = (time / FULL).min(1.0) as f32 * rect.height();
+        let x = rect.left() + i as f32 * BAR_WIDTH;
+        let color = if time > 1.0 / 60.0 + 0.001 {
+            egui::Color32::from_rgb(220, 120, 0)
+        } else {
+            egui::Color32::from_rgb(60, 160, 60)
+        };
+        painter.rect_filled(
+            egui::Rect::from_min_max(
+                egui::pos2(x, rect.bottom() - height),
+                egui::pos2(x + BAR_WIDTH, rect.bottom()),
+            ),
+            0.0,
+            color,
+        );
+    }
+    let y = rect.center().y;
+    painter.hline(rect.x_range(), y, egui::Stroke::new(1.0, egui::Color32::GRAY));
+}
+
+/// The inspector's contents for one particle
+fn inspector_box(ui: &mut egui::Ui, details: &inspector::ParticleDetails, follow: &mut bool) {
+    ui.strong(&details.name);
+    egui::Grid::new("inspector grid").num_columns(2).show(ui, |ui| {
+        let (x, y) = details.position;
+        let (vx, vy) = details.velocity;
+        ui.label("Position");
+        ui.monospace(format!("({:.4}, {:.4})", x, y));
+        ui.end_row();
+        ui.label("Velocity");
+        ui.monospace(format!("({:.4}, {:.4})", vx, vy));
+        ui.end_row();
+        ui.label("Speed");
+        ui.monospace(format!("{:.4}", details.speed));
+        ui.end_row();
+        ui.label("Mass");
+        let pinned = if details.pinned { " (pinned)" } else { "" };
+        ui.monospace(format!("{}{}", details.mass, pinned));
+        ui.end_row();
+    });
+    for (title, items) in [
+        ("Forces", &details.forces),
+        ("Loops", &details.loops),
+        ("Wells", &details.wells),
+    ] {
+        if !items.is_empty() {
+            ui.label(egui::RichText::new(title).strong());
+            for item in items {
+                ui.monospace(item);
+            }
+        }
+    }
+    ui.checkbox(follow, "Follow");
+}
+
 impl eframe::App for VelApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check for file changes
-        self.check_file_changes();
+        self.check_file_changes(ctx.input(|i| i.time));
+        self.overlays.dark = ctx.style().visuals.dark_mode;
+        // Seeking back before a divergence undoes it
+        if self.ctx_opt.as_ref().is_none_or(|c| c.divergence.is_none()) {
+            self.divergence = None;
+        }
 
         // Top bar with controls
         egui::TopBottomPanel::top("controls").show(ctx, |ui| {
             ui.horizontal(|ui| {
+                ui.menu_button("File", |ui| self.file_menu(ui));
+                ui.separator();
+
                 // Play/Pause button
                 if ui.button(if self.playing { "⏸ Pause" } else { "▶ Play" }).clicked() {
                     self.playing = !self.playing;
+                    self.breakpoint_hit = None;
                 }
 
-                // Reset button
-                if ui.button("⏮ Reset").clicked() {
+                // Reset button, once there's a program to reset
+                let has_source = !self.source_text.is_empty();
+                if ui.add_enabled(has_source, egui::Button::new("⏮ Reset")).clicked() {
                     self.reload_context();
                     self.playing = false;
                 }
 
                 // Step button
-                if ui.button("⏭ Step").clicked() {
-                    if let Some(ref mut ctx) = self.ctx_opt {
-                        step_simulation(ctx);
+                if ui.button("⏭ Step").clicked() && self.ctx_opt.is_some() {
+                    self.advance(ui.input(|i| i.time));
+                }
+
+                // Record button, or the recording's progress and Stop
+                match self.recorder {
+                    Some(ref recorder) => {
+                        let stop = egui::RichText::new("⏹ Stop recording")
+                            .color(egui::Color32::RED);
+                        let remaining = self
+                            .ctx_opt
+                            .as_ref()
+                            .map_or(0, |ctx| ctx.max_steps.saturating_sub(ctx.current_step));
+                        let progress = format!(
+                            "{} frames, {} (about {} by the last step)",
+                            recorder.frames(),
+                            recorder::format_size(recorder.bytes()),
+                            recorder::format_size(recorder.estimate(remaining))
+                        );
+                        if ui.button(stop).on_hover_text(progress.clone()).clicked() {
+                            self.stop_recording();
+                        }
+                        ui.label(progress);
+                    }
+                    None => {
+                        ui.add_enabled_ui(self.ctx_opt.is_some(), |ui| {
+                            ui.toggle_value(&mut self.show_record, "⏺ Record");
+                        });
+                        if let Some(ref status) = self.record_status {
+                            ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+                        }
                     }
                 }
 
                 ui.separator();
 
+                // Return to following the particles after panning or zooming
+                let fitted = self.camera.auto && self.baseline_camera.auto;
+                if ui.add_enabled(!fitted, egui::Button::new("⛶ Fit")).clicked() {
+                    self.camera.auto = true;
+                    self.baseline_camera.auto = true;
+                }
+
+                // Split view: the version from when it was turned on, beside
+                // the current one
+                let mut split = self.baseline.is_some();
+                let toggle = ui
+                    .toggle_value(&mut split, "◫ Split")
+                    .on_hover_text("Compare later edits with the program as it is now");
+                if toggle.changed() {
+                    self.set_split(split);
+                }
+                if let Some(ref baseline) = self.baseline {
+                    ui.checkbox(&mut self.linked_cameras, "Link cameras");
+                    let edited = baseline.source() != self.source_text;
+                    let promote = ui
+                        .add_enabled(edited, egui::Button::new("Promote"))
+                        .on_hover_text("Make the current version the baseline");
+                    if promote.clicked() {
+                        self.set_split(true);
+                    }
+                }
+
+                // The current positions, as source to start a new run from
+                ui.add_enabled_ui(self.ctx_opt.is_some(), |ui| {
+                    ui.menu_button("Export state", |ui| {
+                        if ui.button("Save to file").clicked() {
+                            self.export_state(ui.ctx(), false);
+                            ui.close_menu();
+                        }
+                        if ui.button("Copy to clipboard").clicked() {
+                            self.export_state(ui.ctx(), true);
+                            ui.close_menu();
+                        }
+                    });
+                });
+                if let Some(ref message) = self.state_export {
+                    ui.label(egui::RichText::new(message).color(egui::Color32::GRAY));
+                }
+
+                ui.separator();
+
+                // Overlays
+                ui.checkbox(&mut self.overlays.springs, "Springs");
+                ui.checkbox(&mut self.overlays.gravity, "Gravity");
+                ui.checkbox(&mut self.overlays.velocities, "Velocities");
+                ui.checkbox(&mut self.overlays.wells, "Wells");
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.overlays.grid, "Grid");
+                    ui.checkbox(&mut self.overlays.axes, "Axes");
+                    ui.checkbox(&mut self.show_coordinates, "Cursor coordinates");
+                    ui.checkbox(&mut self.show_perf, "Performance stats");
+                    ui.checkbox(&mut self.overlays.labels, "Labels");
+                    ui.checkbox(&mut self.overlays.mass_radius, "Size by mass");
+                    ui.add_enabled(
+                        self.overlays.springs,
+                        egui::Checkbox::new(&mut self.overlays.strain, "Color springs by strain"),
+                    );
+                    ui.separator();
+                    let mut dark = self.overlays.dark;
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut dark, false, "☀ Light");
+                        ui.selectable_value(&mut dark, true, "🌙 Dark");
+                    });
+                    if dark != self.overlays.dark {
+                        let visuals = if dark {
+                            egui::Visuals::dark()
+                        } else {
+                            egui::Visuals::light()
+                        };
+                        ui.ctx().set_visuals(visuals);
+                    }
+                });
+
+                ui.separator();
+
+                ui.toggle_value(&mut self.show_detectors, "Detectors");
+                ui.toggle_value(&mut self.show_status, "Loops & wells");
+                ui.toggle_value(&mut self.show_editor, "Editor");
+                ui.toggle_value(&mut self.show_plot, "Plot");
+                ui.toggle_value(&mut self.show_breakpoints, "Breakpoints");
+
+                let warnings = diagnostics_panel::warning_count(&self.diagnostics);
+                if warnings > 0 {
+                    let badge = egui::RichText::new(format!("⚠ {}", warnings))
+                        .color(egui::Color32::from_rgb(200, 140, 0));
+                    ui.toggle_value(&mut self.show_diagnostics, badge)
+                        .on_hover_text("Warnings");
+                }
+
+                ui.separator();
+
                 // Speed control
                 ui.label("Speed:");
-                ui.add(egui::Slider::new(&mut self.speed_multiplier, 0.1..=10.0));
+                ui.add(
+                    egui::Slider::new(&mut self.speed_multiplier, 0.1..=10.0)
+                        .logarithmic(true)
+                        .suffix("×"),
+                )
+                .on_hover_text("Simulated seconds per second");
 
                 ui.separator();
 
@@ -150,116 +1397,333 @@ impl eframe::App for VelApp {
                         ctx.current_step, ctx.max_steps
                     ));
                 }
+                if let Some(dt) = self.dt_override {
+                    ui.label(format!("dt = {} (override)", dt));
+                    let restore = ui
+                        .small_button("✕")
+                        .on_hover_text("Start over with the program's dt");
+                    if restore.clicked() {
+                        self.dt_override = None;
+                        self.reload_context();
+                        self.playing = false;
+                    }
+                }
+                if self.playing && self.lagging {
+                    ui.colored_label(egui::Color32::RED, "⚠ can't keep up")
+                        .on_hover_text("Each frame runs at most a fixed number of steps");
+                }
             });
         });
 
+        // Timeline under the controls: drag to see any earlier step
+        egui::TopBottomPanel::top("timeline").show(ctx, |ui| self.timeline_bar(ui));
+
+        // Status bar with the world coordinates under the mouse
+        if self.show_coordinates {
+            egui::TopBottomPanel::bottom("status bar").show(ctx, |ui| {
+                let text = match self.cursor_world {
+                    Some(pos) => format!("x = {:.4}, y = {:.4}", pos.x, pos.y),
+                    None => String::new(),
+                };
+                ui.monospace(text);
+            });
+        }
+
+        // Diagnostics along the bottom, errors always and warnings on request
+        let has_errors = self.last_load_error.is_some();
+        if has_errors || (self.show_diagnostics && !self.diagnostics.is_empty()) {
+            egui::TopBottomPanel::bottom("diagnostics")
+                .resizable(true)
+                .default_height(100.0)
+                .show(ctx, |ui| self.diagnostics_list(ui));
+        }
+
+        // Detector time series along the bottom
+        if self.show_plot {
+            egui::TopBottomPanel::bottom("plot")
+                .resizable(true)
+                .default_height(200.0)
+                .show(ctx, |ui| self.plot_panel(ui));
+        }
+
+        // Source editor along the bottom
+        if self.show_editor {
+            egui::TopBottomPanel::bottom("editor")
+                .resizable(true)
+                .default_height(220.0)
+                .show(ctx, |ui| self.editor_panel(ui));
+        }
+        self.conflict_prompt(ctx);
+        self.open_window(ctx);
+
+        // Side panel with the detectors' current values
+        if self.show_detectors {
+            egui::SidePanel::right("detectors").show(ctx, |ui| self.detector_panel(ui));
+        }
+
+        // Side panel with what each loop and well is doing
+        if self.show_status {
+            egui::SidePanel::left("status").show(ctx, |ui| self.status_panel(ui));
+        }
+
+        // Breakpoint list, in its own window
+        let choices = self
+            .ctx_opt
+            .as_ref()
+            .map(Quantity::choices)
+            .unwrap_or_default();
+        egui::Window::new("Breakpoints")
+            .open(&mut self.show_breakpoints)
+            .resizable(false)
+            .show(ctx, |ui| breakpoints_ui(ui, &mut self.breakpoints, &choices));
+
+        // Where and how often to record, before recording starts
+        let mut start = false;
+        if self.recorder.is_none() {
+            egui::Window::new("Record")
+                .open(&mut self.show_record)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Directory:");
+                        ui.text_edit_singleline(&mut self.record_dir);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Every");
+                        ui.add(
+                            egui::DragValue::new(&mut self.record_every)
+                                .range(1..=10_000)
+                                .suffix(" steps"),
+                        );
+                    });
+                    ui.label(
+                        egui::RichText::new("Frames are numbered PNGs, written as they're taken")
+                            .color(egui::Color32::GRAY),
+                    );
+                    start = ui.button("⏺ Start").clicked();
+                    if let Some(ref status) = self.record_status {
+                        ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+                    }
+                });
+        }
+        if start {
+            self.show_record = false;
+            self.start_recording();
+        }
+
         // Main canvas area
+        let mut halve_dt = None;
         egui::CentralPanel::default().show(ctx, |ui| {
-            // Draw particles and forces
-            let rect = ui.max_rect();
-            let painter = ui.painter();
-
-            // Calculate dynamic viewport based on actual particle positions
-            let (center, scale, world_center_x, world_center_y) = if let Some(ref ctx) = self.ctx_opt {
-                let particle_states = get_particle_states(ctx);
-                
-                if particle_states.is_empty() {
-                    // No particles: use default view
-                    (rect.center(), 1.0, 0.0, 0.0)
-                } else {
-                    // Calculate bounding box of all particles
-                    let mut min_x = f32::INFINITY;
-                    let mut max_x = f32::NEG_INFINITY;
-                    let mut min_y = f32::INFINITY;
-                    let mut max_y = f32::NEG_INFINITY;
-                    
-                    for particle in &particle_states {
-                        min_x = min_x.min(particle.pos.x);
-                        max_x = max_x.max(particle.pos.x);
-                        min_y = min_y.min(particle.pos.y);
-                        max_y = max_y.max(particle.pos.y);
-                    }
-                    
-                    // Add padding (10% on each side, minimum 2.0 units)
-                    let padding = ((max_x - min_x).max(max_y - min_y) * 0.1).max(2.0);
-                    let world_width = (max_x - min_x + 2.0 * padding).max(1.0);
-                    let world_height = (max_y - min_y + 2.0 * padding).max(1.0);
-                    
-                    // Calculate center of bounding box
-                    let world_center_x = (min_x + max_x) / 2.0;
-                    let world_center_y = (min_y + max_y) / 2.0;
-                    
-                    // Calculate scale to fit bounding box in viewport
-                    let scale_x = (rect.width() * 0.9) / world_width;
-                    let scale_y = (rect.height() * 0.9) / world_height;
-                    let scale = scale_x.min(scale_y);
-                    
-                    // Screen center
-                    let screen_center = rect.center();
-                    
-                    (screen_center, scale, world_center_x, world_center_y)
-                }
-            } else {
-                // No context: use default view
-                (rect.center(), 1.0, 0.0, 0.0)
+            // Draw the world, beside the baseline in split view
+            let full = ui.max_rect();
+            let (baseline_rect, rect) = match self.baseline {
+                Some(_) => comparison::split(full),
+                None => (egui::Rect::NOTHING, full),
             };
+            self.canvas = rect;
+            let response =
+                ui.interact(rect, ui.id().with("canvas"), egui::Sense::click_and_drag());
+            self.handle_camera_input(ui, &response, rect);
+            let hover = response.hover_pos();
+            self.cursor_world = hover.map(|pos| self.camera.screen_to_world(rect, pos));
+            let painter = &ui.painter_at(rect);
 
-            // Draw springs (forces)
             if let Some(ref ctx) = self.ctx_opt {
-                for force in &ctx.world.forces {
-                    match force {
-                        physlang_core::Force::Spring { a, b, .. } => {
-                            let pos_a = ctx.world.particles[*a].pos;
-                            let pos_b = ctx.world.particles[*b].pos;
-
-                            // Transform world coordinates to screen coordinates
-                            let screen_a = center
-                                + egui::vec2(
-                                    (pos_a.x - world_center_x) * scale,
-                                    -(pos_a.y - world_center_y) * scale,
-                                );
-                            let screen_b = center
-                                + egui::vec2(
-                                    (pos_b.x - world_center_x) * scale,
-                                    -(pos_b.y - world_center_y) * scale,
-                                );
+                // A diverged world has no bounds to fit or follow
+                if self.camera.auto && self.divergence.is_none() {
+                    self.camera.fit(&ctx.world, rect);
+                }
+                let followed = self.selected.as_ref().and_then(|name| {
+                    let id = ctx.symbols().resolve(name)?;
+                    ctx.world.particles.get(id.index())
+                });
+                if let (true, Some(particle)) = (self.follow, followed) {
+                    if particle.pos.is_finite() {
+                        self.camera.center_on(egui::vec2(particle.pos.x, particle.pos.y));
+                    }
+                }
+                let selected = draw_world(
+                    painter,
+                    ctx,
+                    &self.camera,
+                    rect,
+                    &self.overlays,
+                    self.selected.as_deref(),
+                );
 
-                            painter.line_segment(
-                                [screen_a, screen_b],
-                                egui::Stroke::new(1.0, egui::Color32::GRAY),
-                            );
+                // Where the diverged particle was last seen
+                if let Some((x, y)) = self.divergence.as_ref().and_then(|d| d.last_position) {
+                    let at = self.camera.world_to_screen(rect, x, y);
+                    let red = egui::Stroke::new(2.5, egui::Color32::RED);
+                    painter.circle_stroke(at, 14.0, red);
+                    let (down, up) = (egui::vec2(6.0, 6.0), egui::vec2(6.0, -6.0));
+                    painter.line_segment([at - down, at + down], red);
+                    painter.line_segment([at - up, at + up], red);
+                }
+
+                // Inspector for the selected particle, in the top-left corner
+                if let Some(details) = selected.and_then(|i| inspector::details(ctx, i)) {
+                    egui::Area::new(egui::Id::new("inspector"))
+                        .fixed_pos(rect.left_top() + egui::vec2(8.0, 8.0))
+                        .show(ui.ctx(), |ui| {
+                            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                inspector_box(ui, &details, &mut self.follow);
+                            });
+                        });
+                }
+            }
+
+            // The baseline, on the left
+            if let Some(ref baseline) = self.baseline {
+                let response = ui.interact(
+                    baseline_rect,
+                    ui.id().with("baseline canvas"),
+                    egui::Sense::drag(),
+                );
+                let camera = if self.linked_cameras {
+                    &mut self.camera
+                } else {
+                    &mut self.baseline_camera
+                };
+                pan_and_zoom(ui, &response, baseline_rect, camera);
+                let baseline_painter = ui.painter_at(baseline_rect);
+                match baseline.ctx() {
+                    Some(ctx) => {
+                        // A linked camera is fitted to the current run
+                        if !self.linked_cameras && camera.auto && ctx.divergence.is_none() {
+                            camera.fit(&ctx.world, baseline_rect);
                         }
-                        _ => {} // Only draw springs for now
+                        let selected = self.selected.as_deref();
+                        draw_world(&baseline_painter, ctx, camera, baseline_rect, &self.overlays, selected);
+                    }
+                    None => {
+                        baseline_painter.text(
+                            baseline_rect.center(),
+                            egui::Align2::CENTER_CENTER,
+                            baseline.error().unwrap_or_default(),
+                            egui::FontId::proportional(14.0),
+                            egui::Color32::RED,
+                        );
                     }
                 }
+                pane_title(&baseline_painter, baseline_rect, "Baseline");
+                pane_title(painter, rect, "Current");
+                let x = (baseline_rect.right() + rect.left()) / 2.0;
+                ui.painter().vline(
+                    x,
+                    full.y_range(),
+                    ui.visuals().widgets.noninteractive.bg_stroke,
+                );
+            }
 
-                // Draw particles
-                let particle_states = get_particle_states(ctx);
-                for particle in particle_states {
-                    // Transform world coordinates to screen coordinates
-                    let screen_pos = center
-                        + egui::vec2(
-                            (particle.pos.x - world_center_x) * scale,
-                            -(particle.pos.y - world_center_y) * scale,
-                        );
+            if self.source_path.is_none() && self.ctx_opt.is_none() {
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Open a .phys file from the File menu",
+                    egui::FontId::proportional(16.0),
+                    egui::Color32::GRAY,
+                );
+            }
 
-                    // Particle radius based on mass (with reasonable bounds)
-                    let radius = (particle.mass.sqrt() * scale * 0.5).max(3.0).min(20.0);
+            // Legend for the enabled overlays, in the bottom-left corner
+            let legend = self.overlays.legend();
+            if !legend.is_empty() {
+                let height = 18.0 * legend.len() as f32;
+                egui::Area::new(egui::Id::new("legend"))
+                    .fixed_pos(rect.left_bottom() + egui::vec2(8.0, -8.0 - height))
+                    .show(ui.ctx(), |ui| {
+                        for (name, [r, g, b, a]) in legend {
+                            ui.horizontal(|ui| {
+                                let color = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+                                ui.label(egui::RichText::new("━━").color(color));
+                                ui.label(name);
+                            });
+                        }
+                    });
+            }
 
-                    painter.circle_filled(screen_pos, radius, egui::Color32::LIGHT_BLUE);
-                    painter.circle_stroke(screen_pos, radius, egui::Stroke::new(1.0, egui::Color32::BLUE));
+            // Banner for the breakpoint that paused the run, flashing at first
+            if let Some((ref message, at)) = self.breakpoint_hit {
+                let elapsed = ui.input(|i| i.time) - at;
+                let flash = if elapsed < 1.5 {
+                    ui.ctx().request_repaint();
+                    ((elapsed * 8.0).cos() * 0.5 + 0.5) as f32
+                } else {
+                    1.0
+                };
+                let fill = egui::Color32::from_rgb(255, 200, 0).gamma_multiply(0.4 + 0.6 * flash);
+                egui::Area::new(egui::Id::new("breakpoint hit"))
+                    .fixed_pos(rect.center_top() + egui::vec2(-150.0, 8.0))
+                    .show(ui.ctx(), |ui| {
+                        egui::Frame::popup(ui.style()).fill(fill).show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new(message)
+                                    .color(egui::Color32::BLACK)
+                                    .strong(),
+                            );
+                        });
+                    });
+            }
 
-                    // Draw particle name
-                    painter.text(
-                        screen_pos + egui::vec2(0.0, radius + 10.0),
-                        egui::Align2::CENTER_TOP,
-                        &particle.name,
-                        egui::FontId::default(),
-                        egui::Color32::BLACK,
-                    );
+            // What the last reload from disk kept, fading out
+            if let Some((ref message, at)) = self.reload_toast {
+                const RELOAD_TOAST_SECONDS: f64 = 3.0;
+                let elapsed = ui.input(|i| i.time) - at;
+                if elapsed < RELOAD_TOAST_SECONDS {
+                    ui.ctx().request_repaint();
+                    let opacity = (RELOAD_TOAST_SECONDS - elapsed).min(1.0) as f32;
+                    egui::Area::new(egui::Id::new("reload toast"))
+                        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -16.0))
+                        .show(ui.ctx(), |ui| {
+                            ui.set_opacity(opacity);
+                            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                ui.label(message.as_str());
+                            });
+                        });
                 }
             }
 
+            // Performance stats, in the top-right corner
+            if self.show_perf {
+                let (particles, loops) = self.ctx_opt.as_ref().map_or((0, 0), |ctx| {
+                    let active = ctx.loops.iter().filter(|l| l.active).count();
+                    (ctx.world.particles.len(), active)
+                });
+                egui::Area::new(egui::Id::new("performance"))
+                    .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, rect.top() + 8.0))
+                    .show(ui.ctx(), |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            perf_overlay(ui, &self.perf, particles, loops);
+                        });
+                    });
+            }
+
+            // Banner for a diverged run, with a quick test of the time step
+            if let Some(ref divergence) = self.divergence {
+                egui::Area::new(egui::Id::new("divergence"))
+                    .fixed_pos(rect.center_top() + egui::vec2(-200.0, 8.0))
+                    .show(ui.ctx(), |ui| {
+                        let fill = egui::Color32::from_rgb(200, 30, 30);
+                        egui::Frame::popup(ui.style()).fill(fill).show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new(divergence.message())
+                                    .color(egui::Color32::WHITE)
+                                    .size(16.0)
+                                    .strong(),
+                            );
+                            let dt = self.ctx_opt.as_ref().map_or(0.0, |c| c.dt) / 2.0;
+                            if ui
+                                .button("Reset with dt/2")
+                                .on_hover_text(format!("Start over with dt = {}", dt))
+                                .clicked()
+                            {
+                                halve_dt = Some(dt);
+                            }
+                        });
+                    });
+            }
+
             // Show error message if any
             if let Some(ref error) = self.last_load_error {
                 ui.vertical_centered(|ui| {
@@ -273,32 +1737,47 @@ impl eframe::App for VelApp {
             }
         });
 
-        // Bottom panel for errors/diagnostics
-        if self.last_load_error.is_some() {
-            egui::TopBottomPanel::bottom("errors").show(ctx, |ui| {
-                ui.set_max_height(100.0);
-                if let Some(ref error) = self.last_load_error {
-                    ui.label(
-                        egui::RichText::new(format!("Error: {}", error))
-                            .color(egui::Color32::RED),
-                    );
-                }
-            });
+        if let Some(dt) = halve_dt {
+            self.dt_override = Some(dt);
+            self.reload_context();
+            self.playing = false;
         }
 
         // Simulation stepping
-        if self.playing {
-            if let Some(ref mut ctx) = self.ctx_opt {
-                let steps_per_frame = self.speed_multiplier.max(0.1).round() as usize;
-                for _ in 0..steps_per_frame {
-                    if step_simulation(ctx) {
-                        // Simulation finished
-                        self.playing = false;
-                        break;
-                    }
+        if let (true, Some(dt)) = (self.playing, self.ctx_opt.as_ref().map(|c| c.dt)) {
+            let (now, elapsed) = ctx.input(|i| (i.time, i.stable_dt));
+            let frame = playback::plan(
+                self.playback_carry,
+                elapsed as f64,
+                self.speed_multiplier as f64,
+                dt as f64,
+                playback::MAX_STEPS_PER_FRAME,
+            );
+            self.playback_carry = frame.carry;
+            self.lagging = frame.capped;
+            for _ in 0..frame.steps {
+                if self.advance(now) {
+                    // Finished, or a breakpoint fired mid-batch
+                    self.playing = false;
+                    break;
                 }
             }
         }
+        if !self.playing {
+            self.playback_carry = 0.0;
+            self.lagging = false;
+        }
+        if self.show_perf {
+            self.perf.record(FrameSample {
+                frame_time: ctx.input(|i| i.unstable_dt) as f64,
+                steps: self.frame_steps,
+                step_time: self.frame_step_time,
+            });
+        } else {
+            self.perf.clear();
+        }
+        self.frame_steps = 0;
+        self.frame_step_time = 0.0;
 
         // Request repaint for animation
         if self.playing {
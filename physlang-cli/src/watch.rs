@@ -0,0 +1,118 @@
+//! Headless watch mode: re-check and re-run a program whenever its file changes
+//!
+//! Unlike the VEL this needs no display, so it works over SSH.
+
+use crate::debounce::Debouncer;
+use crate::{
+    check_file, exit_code_for, print_run_error, run_file, ColorChoice, FixMode, OutputFormat,
+    Report, EXIT_IO, EXIT_SUCCESS,
+};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use physlang_core::{Diagnostics, RunOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long a file must be quiet after an event before it is re-run
+const QUIET: Duration = Duration::from_millis(100);
+
+/// Run `file` now and again after every change until interrupted; with
+/// `once`, run it a single time and return its exit code
+pub fn watch(file: &Path, once: bool, color: ColorChoice) -> i32 {
+    if once {
+        return check_and_run(file, color);
+    }
+
+    // Editors often save by writing a new file and renaming it over the old
+    // one, which a watch on the file itself would lose, so watch its directory
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Error: cannot watch {}: {}", file.display(), e);
+            return EXIT_IO;
+        }
+    };
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        eprintln!("Error: cannot watch {}: {}", dir.display(), e);
+        return EXIT_IO;
+    }
+
+    check_and_run(file, color);
+    let mut debouncer = Debouncer::new(QUIET);
+    loop {
+        let event = match debouncer.deadline() {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match event {
+            Ok(Ok(event)) if touches(&event, file) => debouncer.event(Instant::now()),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => eprintln!("File watcher error: {}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return EXIT_IO,
+        }
+        if debouncer.ready(Instant::now()) {
+            check_and_run(file, color);
+        }
+    }
+}
+
+/// Whether a watcher event changes `file`
+fn touches(event: &Event, file: &Path) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+        && event
+            .paths
+            .iter()
+            .any(|path| path.file_name() == file.file_name())
+}
+
+/// Clear the screen, then print a timestamped header, the diagnostics, and
+/// the detector results if the program is free of errors
+fn check_and_run(file: &Path, color: ColorChoice) -> i32 {
+    let mut stdout = io::stdout();
+    if stdout.is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+    println!("[{}] {}", timestamp(), file.display());
+
+    let options = RunOptions::default();
+    let report = Report {
+        format: OutputFormat::Text,
+        detectors: None,
+        precision: None,
+    };
+    let limit = Diagnostics::DEFAULT_CODE_LIMIT;
+    let exit_code = match check_file(file, false, FixMode::Off, limit, color) {
+        Ok(EXIT_SUCCESS) => match run_file(file, &options, &report, None, color) {
+            Ok(()) => EXIT_SUCCESS,
+            Err(e) => {
+                print_run_error(file, &*e);
+                exit_code_for(&*e)
+            }
+        },
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}: {}", file.display(), e);
+            exit_code_for(&*e)
+        }
+    };
+    let _ = stdout.flush();
+    exit_code
+}
+
+/// The current UTC time of day as `HH:MM:SS`
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+        % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,89 @@
+//! Tests for `physlang ast`
+
+mod common;
+
+use common::write_program;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+let spacing = 2.0
+fn pair(name) {
+    particle name at (spacing, 0.0) mass 1.0
+}
+particle a at (0.0, 0.0) mass 1.0
+pair("b")
+simulate dt = 0.01 steps = 10
+detect x = position(a)
+"#;
+
+fn ast(name: &str, source: &str, args: &[&str]) -> Output {
+    let path = write_program(name, source);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("ast")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    output
+}
+
+/// Names of the top-level particles in a JSON dump
+fn particle_names(json: &serde_json::Value) -> Vec<&str> {
+    json["particles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_json_dump() {
+    let output = ast("json", PROGRAM, &[]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for key in [
+        "lets",
+        "functions",
+        "top_level_calls",
+        "particles",
+        "forces",
+        "simulate",
+        "detectors",
+    ] {
+        assert!(json.get(key).is_some(), "missing key '{}'", key);
+    }
+    assert_eq!(json["lets"][0]["name"], "spacing");
+    assert_eq!(json["functions"][0]["name"], "pair");
+    assert_eq!(json["detectors"][0]["name"], "x");
+    assert_eq!(particle_names(&json), ["a"]);
+}
+
+#[test]
+fn test_after_functions_shows_generated_particles() {
+    let output = ast("expanded", PROGRAM, &["--after-functions"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(particle_names(&json), ["a", "b"]);
+    assert!(json["top_level_calls"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_debug_format() {
+    let output = ast("debug", PROGRAM, &["--format", "debug"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Program {"));
+    assert!(stdout.contains("ParticleDecl {"));
+}
+
+#[test]
+fn test_parse_error_exit_code() {
+    let output = ast("broken", "particle a at", &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("error"));
+}
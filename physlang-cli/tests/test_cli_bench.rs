@@ -0,0 +1,72 @@
+//! Tests for `physlang bench`
+
+use std::process::{Command, Output};
+
+const FIXTURE: &str = "tests/fixtures/bench/spring.phys";
+
+fn bench(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("bench")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_json_report() {
+    let output = bench(&[FIXTURE, "--repeat", "3", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["repeat"], 3);
+    assert_eq!(json["steps"], 200);
+    for stage in [
+        "parse",
+        "analyze",
+        "functions",
+        "build",
+        "simulate_per_step",
+    ] {
+        let stats = &json["stages"][stage];
+        let min = stats["min_ns"].as_u64().unwrap();
+        let median = stats["median_ns"].as_u64().unwrap();
+        let max = stats["max_ns"].as_u64().unwrap();
+        assert!(min <= median && median <= max, "{}: {}", stage, stats);
+    }
+}
+
+#[test]
+fn test_steps_override() {
+    let output = bench(&[
+        FIXTURE,
+        "--repeat",
+        "1",
+        "--steps-override",
+        "50",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["steps"], 50);
+}
+
+#[test]
+fn test_text_report() {
+    let output = bench(&[FIXTURE, "--repeat", "2"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("2 runs of 200 steps"));
+    assert!(stdout
+        .lines()
+        .any(|line| line.starts_with("simulate_per_step")));
+}
+
+#[test]
+fn test_zero_repeat_rejected() {
+    let output = bench(&[FIXTURE, "--repeat", "0"]);
+    assert_eq!(output.status.code(), Some(2));
+}
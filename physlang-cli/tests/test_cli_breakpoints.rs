@@ -0,0 +1,118 @@
+//! Tests for the VEL's conditional breakpoints
+
+#[allow(dead_code)]
+#[path = "../src/breakpoints.rs"]
+mod breakpoints;
+
+use breakpoints::{Breakpoint, Operator, Quantity};
+use physlang_core::{build_simulation_context_from_source, step_simulation, SimulationContext};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 1.0
+simulate dt = 0.01 steps = 1000
+detect gap = distance(a, b)
+";
+
+fn context() -> SimulationContext {
+    build_simulation_context_from_source(SOURCE).unwrap().0
+}
+
+/// Step until a breakpoint fires, returning the step and what it said
+fn run_until_hit(
+    ctx: &mut SimulationContext,
+    breakpoints: &mut [Breakpoint],
+) -> Option<(usize, String)> {
+    breakpoints::check_all(breakpoints, ctx);
+    while ctx.current_step < ctx.max_steps {
+        step_simulation(ctx);
+        if let Some(hit) = breakpoints::check_all(breakpoints, ctx) {
+            return Some((ctx.current_step, hit.to_string()));
+        }
+    }
+    None
+}
+
+#[test]
+fn test_check_fires_when_the_condition_becomes_true() {
+    let mut below = Breakpoint::new(Quantity::Detector("gap".into()), Operator::Below, 1.0);
+    // The first value only primes it, even if the condition holds
+    assert!(!below.check(0.5));
+    assert!(!below.check(0.4));
+    assert!(!below.check(2.0));
+    assert!(below.check(0.9));
+    assert!(!below.check(0.8));
+
+    let mut above = Breakpoint::new(Quantity::Speed("a".into()), Operator::Above, 1.0);
+    assert!(!above.check(0.0));
+    assert!(above.check(1.5));
+
+    let mut crosses = Breakpoint::new(Quantity::PositionX("a".into()), Operator::Crosses, 0.0);
+    assert!(!crosses.check(-1.0));
+    assert!(crosses.check(0.5));
+    assert!(!crosses.check(1.0));
+    assert!(crosses.check(-0.5));
+
+    crosses.reset();
+    assert!(!crosses.check(0.5));
+}
+
+#[test]
+fn test_run_pauses_the_step_a_detector_drops_below() {
+    let mut ctx = context();
+    let mut breakpoints = vec![Breakpoint::new(
+        Quantity::Detector("gap".into()),
+        Operator::Below,
+        1.0,
+    )];
+    let (step, message) = run_until_hit(&mut ctx, &mut breakpoints).unwrap();
+    assert_eq!(message, "gap < 1");
+
+    let gap = ctx.detector_values().unwrap()[0].value;
+    assert!(gap < 1.0);
+    // One step earlier it was still at or above the threshold
+    let mut replay = context();
+    for _ in 0..step - 1 {
+        step_simulation(&mut replay);
+    }
+    assert!(replay.detector_values().unwrap()[0].value >= 1.0);
+}
+
+#[test]
+fn test_disabled_breakpoints_never_fire() {
+    let mut ctx = context();
+    let mut breakpoints = vec![
+        Breakpoint::new(Quantity::Detector("gap".into()), Operator::Below, 1.0),
+        Breakpoint::new(Quantity::PositionX("a".into()), Operator::Above, 0.5),
+    ];
+    breakpoints[0].enabled = false;
+    let (_, message) = run_until_hit(&mut ctx, &mut breakpoints).unwrap();
+    assert_eq!(message, "position(a).x > 0.5");
+    assert!(ctx.world.particles[0].pos.x > 0.5);
+
+    breakpoints[1].enabled = false;
+    assert_eq!(run_until_hit(&mut ctx, &mut breakpoints), None);
+}
+
+#[test]
+fn test_choices_cover_detectors_and_particles() {
+    let ctx = context();
+    let choices: Vec<String> = Quantity::choices(&ctx)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        choices,
+        vec![
+            "gap",
+            "position(a).x",
+            "position(a).y",
+            "speed(a)",
+            "position(b).x",
+            "position(b).y",
+            "speed(b)",
+        ]
+    );
+    assert_eq!(Quantity::Detector("missing".into()).value(&ctx, &[]), None);
+}
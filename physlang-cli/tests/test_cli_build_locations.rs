@@ -0,0 +1,67 @@
+//! Tests that `physlang run` points at the declaration an error came from
+
+use std::process::Command;
+
+fn run_stderr(file: &str) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["run", file])
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_analysis_error_shows_its_line() {
+    let (code, stderr) = run_stderr("tests/fixtures/locations/spring_typo.phys");
+    assert_eq!(code, Some(3));
+    assert!(stderr.contains("spring_typo.phys:4:1"), "{}", stderr);
+    assert!(
+        stderr.contains("4 | force spring(a, b) k = stiffnes"),
+        "{}",
+        stderr
+    );
+    // Shown once, exactly as `check` shows it
+    let check = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["check", "tests/fixtures/locations/spring_typo.phys"])
+        .output()
+        .unwrap();
+    assert_eq!(stderr, String::from_utf8(check.stdout).unwrap());
+    assert!(!stderr.contains("Error:"), "{}", stderr);
+}
+
+#[test]
+fn test_build_error_shows_its_line() {
+    let (code, stderr) = run_stderr("tests/fixtures/locations/spring_div_zero.phys");
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("spring_div_zero.phys:5:1"), "{}", stderr);
+    assert!(
+        stderr.contains("5 | force spring(a, b) k = 1.0 / zero"),
+        "{}",
+        stderr
+    );
+    assert_eq!(
+        stderr
+            .matches("Error evaluating spring k: division by zero")
+            .count(),
+        1,
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_ground_error_shows_its_line() {
+    let (code, stderr) = run_stderr("tests/fixtures/locations/ground_div_zero.phys");
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("ground_div_zero.phys:3:1"), "{}", stderr);
+    assert!(
+        stderr.contains("3 | ground y = 0.0 friction 1.0 / zero"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,120 @@
+//! Tests for the VEL camera's pan and zoom
+
+#[allow(dead_code)]
+#[path = "../src/render.rs"]
+mod render;
+
+use physlang_core::build_simulation_context_from_source;
+use render::Camera;
+
+fn canvas() -> egui::Rect {
+    egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(400.0, 300.0))
+}
+
+fn assert_near(a: egui::Vec2, b: egui::Vec2) {
+    assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn test_screen_to_world_inverts_world_to_screen() {
+    let camera = Camera {
+        center: egui::vec2(3.0, -1.0),
+        pixels_per_unit: 25.0,
+        auto: false,
+    };
+    let screen = camera.world_to_screen(canvas(), 4.0, 2.0);
+    assert_near(
+        camera.screen_to_world(canvas(), screen),
+        egui::vec2(4.0, 2.0),
+    );
+    // y points up in the world and down on screen
+    assert!(screen.y < canvas().center().y);
+}
+
+#[test]
+fn test_zoom_keeps_the_point_under_the_cursor_still() {
+    let mut camera = Camera::default();
+    let cursor = egui::pos2(50.0, 60.0);
+    let before = camera.screen_to_world(canvas(), cursor);
+
+    camera.zoom(canvas(), cursor, 2.0);
+    assert_eq!(camera.pixels_per_unit, 2.0);
+    assert_near(camera.screen_to_world(canvas(), cursor), before);
+    assert!(!camera.auto);
+}
+
+#[test]
+fn test_pan_follows_the_drag() {
+    let mut camera = Camera {
+        center: egui::Vec2::ZERO,
+        pixels_per_unit: 10.0,
+        auto: true,
+    };
+    let before = camera.world_to_screen(canvas(), 1.0, 1.0);
+    camera.pan(egui::vec2(30.0, -20.0));
+    let after = camera.world_to_screen(canvas(), 1.0, 1.0);
+    assert_near(after - before, egui::vec2(30.0, -20.0));
+    assert!(!camera.auto);
+}
+
+#[test]
+fn test_fit_frames_every_particle() {
+    let source = "\
+particle a at (-5.0, 0.0) mass 1.0
+particle b at (5.0, 2.0) mass 1.0
+simulate dt = 0.01 steps = 1
+";
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let mut camera = Camera::default();
+    camera.fit(&ctx.world, canvas());
+    for particle in &ctx.world.particles {
+        let pos = camera.world_to_screen(canvas(), particle.pos.x, particle.pos.y);
+        assert!(canvas().contains(pos), "{:?} outside {:?}", pos, canvas());
+    }
+
+    // An empty world keeps the previous view
+    let (empty, _) = build_simulation_context_from_source("simulate dt = 0.01 steps = 1").unwrap();
+    let before = camera;
+    camera.fit(&empty.world, canvas());
+    assert_eq!(camera, before);
+}
+
+#[test]
+fn test_pick_finds_the_particle_under_the_cursor() {
+    let source = "\
+particle a at (-5.0, 0.0) mass 1.0
+particle b at (5.0, 2.0) mass 4.0
+simulate dt = 0.01 steps = 1
+";
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let camera = Camera {
+        center: egui::Vec2::ZERO,
+        pixels_per_unit: 10.0,
+        auto: false,
+    };
+    let overlays = render::Overlays::default();
+    let b = camera.world_to_screen(canvas(), 5.0, 2.0);
+    assert_eq!(
+        render::pick(&ctx.world, &camera, &overlays, canvas(), b),
+        Some(1)
+    );
+
+    // Within the drawn radius, which grows with mass, plus a little slop
+    let radius = camera.particle_radius(4.0);
+    let edge = b + egui::vec2(radius + 2.0, 0.0);
+    assert_eq!(
+        render::pick(&ctx.world, &camera, &overlays, canvas(), edge),
+        Some(1)
+    );
+    let a_edge = camera.world_to_screen(canvas(), -5.0, 0.0) + egui::vec2(radius + 2.0, 0.0);
+    assert_eq!(
+        render::pick(&ctx.world, &camera, &overlays, canvas(), a_edge),
+        None
+    );
+
+    // Empty space picks nothing
+    assert_eq!(
+        render::pick(&ctx.world, &camera, &overlays, canvas(), canvas().center()),
+        None
+    );
+}
@@ -0,0 +1,72 @@
+//! Tests for the VEL's split view baseline
+
+#[allow(dead_code)]
+#[path = "../src/comparison.rs"]
+mod comparison;
+
+use comparison::{split, Baseline, GAP};
+use physlang_core::RunOptions;
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 50
+";
+
+#[test]
+fn test_baseline_steps_and_syncs() {
+    let options = RunOptions::default();
+    let mut baseline = Baseline::new(SOURCE.to_string(), &options);
+    assert_eq!(baseline.source(), SOURCE);
+    for _ in 0..10 {
+        baseline.step();
+    }
+    assert_eq!(baseline.ctx().unwrap().current_step, 10);
+    let at_ten = baseline.ctx().unwrap().world.particles[0].pos;
+
+    // Forward by stepping, back by restarting and stepping again
+    baseline.sync_to(30, &options);
+    assert_eq!(baseline.ctx().unwrap().current_step, 30);
+    baseline.sync_to(10, &options);
+    assert_eq!(baseline.ctx().unwrap().current_step, 10);
+    assert_eq!(baseline.ctx().unwrap().world.particles[0].pos, at_ten);
+
+    // Never past the end of the run
+    baseline.sync_to(500, &options);
+    assert_eq!(baseline.ctx().unwrap().current_step, 50);
+
+    baseline.restart(&options);
+    assert_eq!(baseline.ctx().unwrap().current_step, 0);
+}
+
+#[test]
+fn test_baseline_uses_the_run_options() {
+    let options = RunOptions {
+        dt: Some(0.005),
+        ..RunOptions::default()
+    };
+    let baseline = Baseline::new(SOURCE.to_string(), &options);
+    assert_eq!(baseline.ctx().unwrap().dt, 0.005);
+}
+
+#[test]
+fn test_broken_baseline_keeps_its_error() {
+    let mut baseline = Baseline::new("particle a at".to_string(), &RunOptions::default());
+    assert!(baseline.ctx().is_none());
+    assert!(baseline.error().is_some());
+    baseline.step();
+    baseline.sync_to(5, &RunOptions::default());
+    assert!(baseline.ctx().is_none());
+}
+
+#[test]
+fn test_split_halves_with_a_gap() {
+    let rect = egui::Rect::from_min_max(egui::pos2(10.0, 20.0), egui::pos2(414.0, 320.0));
+    let (left, right) = split(rect);
+    assert_eq!(left.width(), right.width());
+    assert_eq!(left.left(), rect.left());
+    assert_eq!(right.right(), rect.right());
+    assert_eq!(right.left() - left.right(), GAP);
+    assert_eq!((left.height(), right.height()), (300.0, 300.0));
+}
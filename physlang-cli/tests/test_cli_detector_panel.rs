@@ -0,0 +1,72 @@
+//! Tests for the VEL's live detector panel
+
+#[allow(dead_code)]
+#[path = "../src/detector_panel.rs"]
+mod detector_panel;
+
+use detector_panel::{sparkline, DetectorHistory, SPARKLINE_SAMPLES};
+use physlang_core::DetectorResult;
+use std::collections::VecDeque;
+
+fn values(pairs: &[(&str, f32)]) -> Vec<DetectorResult> {
+    pairs
+        .iter()
+        .map(|(name, value)| DetectorResult {
+            name: name.to_string(),
+            value: *value,
+            initial: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_history_records_once_per_step() {
+    let mut history = DetectorHistory::default();
+    history.record(0, &values(&[("x", 1.0)]));
+    history.record(0, &values(&[("x", 9.0)]));
+    history.record(1, &values(&[("x", 2.0)]));
+    assert_eq!(history.samples("x").unwrap(), &VecDeque::from([1.0, 2.0]));
+    assert!(history.samples("y").is_none());
+}
+
+#[test]
+fn test_history_keeps_the_most_recent_samples() {
+    let mut history = DetectorHistory::default();
+    for step in 0..SPARKLINE_SAMPLES + 10 {
+        history.record(step, &values(&[("x", step as f32)]));
+    }
+    let samples = history.samples("x").unwrap();
+    assert_eq!(samples.len(), SPARKLINE_SAMPLES);
+    assert_eq!(samples[0], 10.0);
+}
+
+#[test]
+fn test_history_restarts_when_the_detectors_change() {
+    let mut history = DetectorHistory::default();
+    history.record(0, &values(&[("x", 1.0)]));
+    history.record(1, &values(&[("y", 2.0)]));
+    assert!(history.samples("x").is_none());
+    assert_eq!(history.samples("y").unwrap().len(), 1);
+
+    history.clear();
+    history.record(1, &values(&[("y", 3.0)]));
+    assert_eq!(history.samples("y").unwrap(), &VecDeque::from([3.0]));
+}
+
+#[test]
+fn test_sparkline_spans_the_rect_and_skips_nan() {
+    let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(299.0, 30.0));
+    let points = sparkline(&VecDeque::from([0.0, f32::NAN, 2.0, 1.0]), rect);
+    assert_eq!(
+        points,
+        [
+            egui::pos2(0.0, 30.0),
+            egui::pos2(2.0, 0.0),
+            egui::pos2(3.0, 15.0)
+        ]
+    );
+
+    // A constant series sits in the middle
+    let flat = sparkline(&VecDeque::from([5.0, 5.0]), rect);
+    assert!(flat.iter().all(|p| p.y == 15.0));
+}
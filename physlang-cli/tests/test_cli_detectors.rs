@@ -0,0 +1,113 @@
+//! Tests for `physlang run --detectors` and `--precision`, and the output of
+//! detectors declared `initial`
+
+mod common;
+
+use common::write_program;
+use physlang_core::format_detector_value;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 250
+detect gap = distance(a, b)
+detect final_x = position(a)
+detect final_b = position(b)
+"#;
+
+fn run(name: &str, args: &[&str]) -> Output {
+    run_source(name, PROGRAM, args)
+}
+
+fn run_source(name: &str, source: &str, args: &[&str]) -> Output {
+    let path = write_program(name, source);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("run")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    output
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_filter_to_a_subset() {
+    // Kept in the program's order, whatever the order on the command line
+    let text = stdout(run("text", &["--detectors", "final_x,gap"]));
+    let names: Vec<&str> = text
+        .lines()
+        .map(|line| line.split_once(" = ").unwrap().0)
+        .collect();
+    assert_eq!(names, ["gap", "final_x"]);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(run(
+        "json",
+        &["--detectors", "gap", "--format", "json"],
+    )))
+    .unwrap();
+    let detectors = json["detectors"].as_object().unwrap();
+    assert_eq!(detectors.keys().collect::<Vec<_>>(), ["gap"]);
+
+    let csv = stdout(run("csv", &["--detectors", "final_b", "--format", "csv"]));
+    assert_eq!(csv.lines().next(), Some("final_b"));
+}
+
+#[test]
+fn test_unknown_detector_is_an_error() {
+    let output = run("unknown", &["--detectors", "gap,nope"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown detector 'nope'"), "{}", stderr);
+    assert!(stderr.contains("gap, final_x, final_b"), "{}", stderr);
+}
+
+#[test]
+fn test_precision_rounds_text_only() {
+    let text = stdout(run("rounded", &["--detectors", "gap", "--precision", "2"]));
+    let value = text.trim().strip_prefix("gap = ").unwrap();
+    assert_eq!(value.split_once('.').unwrap().1.len(), 2, "{}", text);
+
+    // JSON keeps every digit
+    let json: serde_json::Value = serde_json::from_str(&stdout(run(
+        "json_precision",
+        &["--detectors", "gap", "--precision", "2", "--format", "json"],
+    )))
+    .unwrap();
+    let exact = json["detectors"]["gap"].as_f64().unwrap() as f32;
+    assert_eq!(value, format!("{:.2}", exact));
+
+    // Without a precision, text shows six significant digits
+    let full = stdout(run("full", &["--detectors", "gap"]));
+    let full = full.trim().strip_prefix("gap = ").unwrap();
+    assert_eq!(full, format_detector_value(exact, None));
+    assert!(full.trim_start_matches(['0', '.']).replace('.', "").len() <= 6, "{}", full);
+}
+
+#[test]
+fn test_initial_values_are_printed_beside_final_ones() {
+    let source = PROGRAM.replace("distance(a, b)", "distance(a, b) initial");
+    let text = stdout(run_source("initial", &source, &["--precision", "2"]));
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("gap = "), "{}", text);
+    assert!(lines[0].ends_with(" (initial 3.00)"), "{}", text);
+    // Detectors without `initial` print as before
+    assert!(!lines[1].contains("initial"), "{}", text);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(run_source(
+        "initial_json",
+        &source,
+        &["--format", "json"],
+    )))
+    .unwrap();
+    assert_eq!(json["detectors"]["gap"]["initial"], 3.0);
+    assert!(json["detectors"]["gap"]["value"].is_f64());
+    assert!(json["detectors"]["final_x"].is_f64());
+}
@@ -0,0 +1,55 @@
+//! Tests for the VEL's diagnostics list
+
+#[path = "../src/diagnostics_panel.rs"]
+mod diagnostics_panel;
+
+use physlang_core::{DiagnosticSeverity, SourceLocation};
+
+const SOURCE: &str = "\
+let unused = 2.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect ax = position(a)
+";
+
+#[test]
+fn test_check_source_reports_warnings_with_locations() {
+    let diagnostics = diagnostics_panel::check_source(SOURCE);
+    assert_eq!(diagnostics_panel::warning_count(&diagnostics), 2);
+
+    let rows = diagnostics_panel::rows(SOURCE, &diagnostics);
+    let lines: Vec<usize> = rows.iter().map(|row| row.location.unwrap().line).collect();
+    assert_eq!(lines, vec![1, 6]);
+    assert!(rows[0].text.contains("unused let binding 'unused'"));
+    assert!(rows.iter().all(|row| row.icon() == "⚠"));
+}
+
+#[test]
+fn test_check_source_reports_the_parse_error() {
+    let source = SOURCE.replace("mass 1.0\nforce", "mass\nforce");
+    let diagnostics = diagnostics_panel::check_source(&source);
+    assert_eq!(diagnostics.len(), 1);
+
+    let rows = diagnostics_panel::rows(&source, &diagnostics);
+    assert_eq!(rows[0].severity, DiagnosticSeverity::Error);
+    assert_eq!(rows[0].icon(), "⛔");
+    assert_eq!(rows[0].location.map(|l| l.line), Some(3));
+}
+
+#[test]
+fn test_rows_put_errors_first_with_codes() {
+    let source = SOURCE.replace("spring(a, b)", "spring(a, c)");
+    let diagnostics = diagnostics_panel::check_source(&source);
+    let rows = diagnostics_panel::rows(&source, &diagnostics);
+    assert_eq!(rows[0].severity, DiagnosticSeverity::Error);
+    assert!(rows[0].text.starts_with("[E0002] "), "{}", rows[0].text);
+    assert!(rows[1..]
+        .iter()
+        .all(|row| row.severity == DiagnosticSeverity::Warning));
+    assert_eq!(
+        rows.last().unwrap().location,
+        Some(SourceLocation { line: 6, column: 1 })
+    );
+}
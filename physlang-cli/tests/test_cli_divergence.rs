@@ -0,0 +1,65 @@
+//! Tests for noticing a diverged run in the VEL
+
+#[allow(dead_code)]
+#[path = "../src/divergence.rs"]
+mod divergence;
+
+use divergence::DivergenceWatch;
+use physlang_core::{
+    build_simulation_context_from_source, build_simulation_context_with_overrides, step_simulation,
+    RunOptions,
+};
+
+/// Far too stiff for the time step: explodes within a few dozen steps
+const STIFF: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (1.5, 0.0) mass 1.0
+force spring(a, b) k = 1000000.0 rest = 1.0
+simulate dt = 0.1 steps = 1000
+";
+
+#[test]
+fn test_reports_particle_step_and_last_finite_position() {
+    let (mut ctx, _) = build_simulation_context_from_source(STIFF).unwrap();
+    let mut watch = DivergenceWatch::default();
+    watch.record(&ctx);
+    assert_eq!(watch.check(&ctx), None);
+
+    let mut last = Vec::new();
+    while !step_simulation(&mut ctx) {
+        last = ctx
+            .world
+            .particles
+            .iter()
+            .map(|p| (p.name.clone(), (p.pos.x, p.pos.y)))
+            .collect();
+        watch.record(&ctx);
+    }
+    watch.record(&ctx);
+
+    let divergence = watch.check(&ctx).expect("the run should diverge");
+    assert_eq!(divergence.step, ctx.current_step);
+    assert!(divergence.step < 1000);
+    let (_, position) = last
+        .iter()
+        .find(|(name, _)| *name == divergence.particle)
+        .unwrap();
+    assert_eq!(divergence.last_position, Some(*position));
+    assert!(divergence
+        .message()
+        .contains(&format!("'{}'", divergence.particle)));
+
+    watch.clear();
+    assert_eq!(watch.check(&ctx).unwrap().last_position, None);
+}
+
+#[test]
+fn test_halved_dt_comes_from_the_override() {
+    let options = RunOptions {
+        dt: Some(0.05),
+        ..RunOptions::default()
+    };
+    let (ctx, _) = build_simulation_context_with_overrides(STIFF, &options).unwrap();
+    assert_eq!(ctx.dt, 0.05);
+    assert_eq!(ctx.max_steps, 1000);
+}
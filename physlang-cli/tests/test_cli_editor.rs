@@ -0,0 +1,105 @@
+//! Tests for the VEL's in-app source editor
+
+#[allow(dead_code)]
+#[path = "../src/editor.rs"]
+mod editor;
+
+use editor::{ExternalChange, SourceEditor};
+use physlang_core::{parse_program, Diagnostic, SourceLocation, Span};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+";
+
+#[test]
+fn test_editor_tracks_unsaved_edits() {
+    let mut ed = SourceEditor::new(SOURCE.to_string());
+    assert!(!ed.is_dirty());
+    ed.text.push_str("detect d = distance(a, b)\n");
+    assert!(ed.is_dirty());
+
+    let path = std::env::temp_dir().join(format!("physlang_editor_{}.phys", std::process::id()));
+    ed.save(&path).unwrap();
+    assert!(!ed.is_dirty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), ed.text);
+    std::fs::remove_file(&path).unwrap();
+
+    // The watcher seeing our own save is not a change
+    let saved = ed.text.clone();
+    assert_eq!(ed.external_change(saved), ExternalChange::Unchanged);
+}
+
+#[test]
+fn test_external_change_reloads_a_clean_buffer() {
+    let mut ed = SourceEditor::new(SOURCE.to_string());
+    let changed = SOURCE.replace("k = 10.0", "k = 5.0");
+    assert_eq!(
+        ed.external_change(changed.clone()),
+        ExternalChange::Reloaded
+    );
+    assert_eq!(ed.text, changed);
+    assert!(!ed.is_dirty());
+    assert!(!ed.has_conflict());
+}
+
+#[test]
+fn test_external_change_conflicts_with_unsaved_edits() {
+    let changed = SOURCE.replace("k = 10.0", "k = 5.0");
+
+    let mut ed = SourceEditor::new(SOURCE.to_string());
+    ed.text = SOURCE.replace("rest = 2.0", "rest = 1.0");
+    assert_eq!(
+        ed.external_change(changed.clone()),
+        ExternalChange::Conflict
+    );
+    assert!(ed.has_conflict());
+    assert_eq!(ed.accept_external(), Some(changed.clone()));
+    assert_eq!(ed.text, changed);
+    assert!(!ed.is_dirty());
+
+    let mut ed = SourceEditor::new(SOURCE.to_string());
+    let edited = SOURCE.replace("rest = 2.0", "rest = 1.0");
+    ed.text = edited.clone();
+    ed.external_change(changed);
+    ed.keep_local();
+    assert!(!ed.has_conflict());
+    assert_eq!(ed.text, edited);
+    assert!(ed.is_dirty());
+    assert_eq!(ed.accept_external(), None);
+}
+
+#[test]
+fn test_error_lines_from_diagnostic_spans() {
+    let parse_error = SOURCE.replace("mass 1.0\nforce", "mass\nforce");
+    let error = parse_program(&parse_error).unwrap_err();
+    let diagnostics = vec![
+        Diagnostic::from(&error),
+        Diagnostic::warning("not an error", Some(Span::new(0, 4))),
+        Diagnostic::error("nowhere", None),
+    ];
+    assert_eq!(editor::error_lines(&parse_error, &diagnostics), vec![2]);
+}
+
+#[test]
+fn test_char_index_of_location() {
+    let location = |line, column| SourceLocation { line, column };
+    assert_eq!(editor::char_index(SOURCE, location(1, 1)), 0);
+    let third = SOURCE.find("force").unwrap();
+    assert_eq!(editor::char_index(SOURCE, location(3, 1)), third);
+    assert_eq!(editor::char_index(SOURCE, location(3, 7)), third + 6);
+    assert_eq!(editor::char_index(SOURCE, location(99, 1)), SOURCE.len());
+}
+
+#[test]
+fn test_highlight_shades_error_lines() {
+    let job = editor::highlight(SOURCE, &[3], &egui::Style::default());
+    assert_eq!(job.text, SOURCE);
+    assert_eq!(job.sections.len(), 4);
+    for (i, section) in job.sections.iter().enumerate() {
+        let shaded = section.format.background != egui::Color32::TRANSPARENT;
+        assert_eq!(shaded, i == 2, "line {}", i + 1);
+    }
+}
@@ -0,0 +1,89 @@
+//! Tests for the exit code of each failure class
+
+use std::process::{Command, Output};
+
+const DIVERGES: &str = "tests/fixtures/exit/diverges.phys";
+const WARNING: &str = "tests/fixtures/exit/warning.phys";
+const PARSE_ERROR: &str = "tests/fixtures/render/mid_line.phys";
+const ANALYSIS_ERROR: &str = "tests/fixtures/mixed/nested/broken.phys";
+const GOOD: &str = "tests/fixtures/mixed/good.phys";
+const MISSING: &str = "tests/fixtures/exit/missing.phys";
+
+/// Run physlang from the crate root so fixture paths resolve
+fn physlang(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn exit_code(args: &[&str]) -> i32 {
+    physlang(args).status.code().unwrap()
+}
+
+#[test]
+fn test_run_exit_codes() {
+    assert_eq!(exit_code(&["run", GOOD]), 0);
+    assert_eq!(exit_code(&["run", WARNING]), 0);
+    assert_eq!(exit_code(&["run", DIVERGES]), 1);
+    assert_eq!(exit_code(&["run", PARSE_ERROR]), 2);
+    assert_eq!(exit_code(&["run", ANALYSIS_ERROR]), 3);
+    assert_eq!(exit_code(&["run", MISSING]), 4);
+}
+
+#[test]
+fn test_unknown_override_is_an_input_error() {
+    let output = physlang(&["run", GOOD, "--set", "nope=1.0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("unknown let binding 'nope'"));
+}
+
+#[test]
+fn test_divergence_is_a_runtime_error() {
+    let output = physlang(&["run", DIVERGES]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("numerical divergence"));
+}
+
+#[test]
+fn test_check_exit_codes() {
+    assert_eq!(exit_code(&["check", GOOD]), 0);
+    // Divergence only shows up when the program runs
+    assert_eq!(exit_code(&["check", DIVERGES]), 0);
+    assert_eq!(exit_code(&["check", PARSE_ERROR]), 2);
+    assert_eq!(exit_code(&["check", ANALYSIS_ERROR]), 3);
+    assert_eq!(exit_code(&["check", MISSING]), 4);
+}
+
+#[test]
+fn test_deny_warnings() {
+    assert_eq!(exit_code(&["check", WARNING]), 0);
+    assert_eq!(exit_code(&["check", "--deny-warnings", WARNING]), 3);
+    assert_eq!(exit_code(&["check", "--deny-warnings", GOOD]), 0);
+}
+
+#[test]
+fn test_multiple_files_use_most_severe_code() {
+    assert_eq!(exit_code(&["run", GOOD, DIVERGES, PARSE_ERROR]), 2);
+    assert_eq!(exit_code(&["check", PARSE_ERROR, ANALYSIS_ERROR, GOOD]), 3);
+    assert_eq!(exit_code(&["check", "tests/fixtures/exit"]), 0);
+}
+
+#[test]
+fn test_out_of_range_duration_is_an_argument_error() {
+    for args in [
+        &["run", "--timeout", "1e300", GOOD][..],
+        &["visual", GOOD, "--headless", "--duration", "1e300m"][..],
+    ] {
+        let output = physlang(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("invalid duration '1e300"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests for `physlang export --svg`
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::process::Command;
+
+/// Run `physlang export` on a fixture and return the SVG it wrote
+fn export(name: &str, fixture: &str, every: &str) -> String {
+    let svg = std::env::temp_dir().join(format!(
+        "physlang_cli_export_{}_{}.svg",
+        name,
+        std::process::id()
+    ));
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("export")
+        .arg(fixture)
+        .arg("--svg")
+        .arg(&svg)
+        .args(["--every", every])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let contents = std::fs::read_to_string(&svg).unwrap();
+    let _ = std::fs::remove_file(&svg);
+    contents
+}
+
+/// Parse the SVG as XML, returning the point count of every polyline and the
+/// legend text, in document order
+fn polylines_and_legend(svg: &str) -> (Vec<usize>, Vec<String>) {
+    let mut reader = Reader::from_str(svg);
+    let mut polylines = Vec::new();
+    let mut legend = Vec::new();
+    let mut in_legend_text = false;
+    loop {
+        match reader.read_event().unwrap() {
+            Event::Empty(element) if element.name().as_ref() == b"polyline" => {
+                let points = element
+                    .try_get_attribute("points")
+                    .unwrap()
+                    .expect("polyline without points");
+                let points = std::str::from_utf8(&points.value).unwrap();
+                polylines.push(points.split_whitespace().count());
+            }
+            Event::Start(element) if element.name().as_ref() == b"text" => in_legend_text = true,
+            Event::Text(text) if in_legend_text => {
+                legend.push(text.decode().unwrap().into_owned());
+                in_legend_text = false;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    (polylines, legend)
+}
+
+#[test]
+fn test_one_polyline_per_particle() {
+    let svg = export("every10", "tests/fixtures/export/orbit.phys", "10");
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox="));
+
+    let (polylines, legend) = polylines_and_legend(&svg);
+    // Steps 0, 10, ..., 90 and the final step 95
+    assert_eq!(polylines, [11, 11, 11]);
+    assert_eq!(legend, ["sun", "planet", "moon"]);
+}
+
+#[test]
+fn test_every_step() {
+    let svg = export("every1", "tests/fixtures/export/orbit.phys", "1");
+    let (polylines, _) = polylines_and_legend(&svg);
+    assert_eq!(polylines, [96, 96, 96]);
+}
+
+#[test]
+fn test_svg_required() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["export", "tests/fixtures/export/orbit.phys"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,95 @@
+//! Tests for `physlang check --fix`
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const FIXABLE: &str = include_str!("fixtures/fix/fixable.phys");
+const FIXED: &str = include_str!("fixtures/fix/fixable.fixed.phys");
+
+/// Copy the fixable fixture to a temporary file unique to this test and process
+fn copy_fixture(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "physlang_cli_fix_{}_{}.phys",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, FIXABLE).unwrap();
+    path
+}
+
+fn check(path: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("check")
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_fix_rewrites_only_fixable_issues() {
+    let path = copy_fixture("apply");
+    let output = check(&path, &["--fix"]);
+    let fixed = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(fixed, FIXED);
+    // The unknown particle is still reported, and still fails the check
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("fixed "))
+        .collect();
+    assert_eq!(summary.len(), 1, "{}", stdout);
+    assert!(summary[0].ends_with(
+        ":10:1: bare position(a) detector is deprecated; it measures the x coordinate"
+    ));
+    assert!(stdout.contains("error[E0002]: unknown particle 'ghost' in detector"));
+    // Removing a let is only suggested, since `--set` may target it
+    assert_eq!(stdout.matches("warning").count(), 1, "{}", stdout);
+    assert!(stdout.contains("warning: unused let binding 'spare'"));
+
+    // A second run has nothing left to fix
+    let path = copy_fixture("again");
+    std::fs::write(&path, FIXED).unwrap();
+    let output = check(&path, &["--fix"]);
+    let _ = std::fs::remove_file(&path);
+    assert!(!String::from_utf8(output.stdout).unwrap().contains("fixed "));
+}
+
+#[test]
+fn test_dry_run_prints_a_diff_and_leaves_the_file() {
+    let path = copy_fixture("dry_run");
+    let output = check(&path, &["--fix", "--dry-run"]);
+    let after = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(after, FIXABLE);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("--- {}\n", path.display())));
+    assert!(stdout.contains("\n@@ -7,5 +7,5 @@\n"), "{}", stdout);
+    assert!(!stdout.contains("\n-let spare = 1.5\n"));
+    assert!(stdout.contains("\n-detect a_x = position(a)\n+detect a_x = position(a).x\n"));
+    assert_eq!(stdout.matches("would fix ").count(), 1);
+}
+
+#[test]
+fn test_without_fix_the_issues_are_warnings() {
+    let path = copy_fixture("report");
+    let output = check(&path, &[]);
+    let _ = std::fs::remove_file(&path);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("warning: unused let binding 'spare'"));
+    assert!(stdout.contains("help: remove the line, unless it's meant to be set with --set"));
+    assert!(stdout.contains("help: write position(a).x"));
+}
+
+#[test]
+fn test_dry_run_requires_fix() {
+    let path = copy_fixture("requires");
+    let output = check(&path, &["--dry-run"]);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,62 @@
+//! Tests for `physlang fmt`
+
+mod common;
+
+use common::write_program;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const UNFORMATTED: &str = "particle a at (0,0) mass 1\n\n\nsimulate dt = 0.01   steps = 10\n";
+const FORMATTED: &str = "particle a at (0.0, 0.0) mass 1.0\n\nsimulate dt = 0.01 steps = 10\n";
+
+fn fmt(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("fmt")
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_fmt_rewrites_file() {
+    let path = write_program("rewrite", UNFORMATTED);
+    let output = fmt(&[], &path);
+    let contents = std::fs::read_to_string(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(contents, FORMATTED);
+}
+
+#[test]
+fn test_fmt_check_reports_without_writing() {
+    let path = write_program("check", UNFORMATTED);
+    let output = fmt(&["--check"], &path);
+    let contents = std::fs::read_to_string(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(contents, UNFORMATTED);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains(&*path.to_string_lossy()));
+}
+
+#[test]
+fn test_fmt_check_passes_on_formatted_file() {
+    let path = write_program("clean", FORMATTED);
+    let output = fmt(&["--check"], &path);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_fmt_leaves_unparseable_file_alone() {
+    let path = write_program("broken", "particle a at");
+    let output = fmt(&[], &path);
+    let contents = std::fs::read_to_string(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(contents, "particle a at");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("error"));
+}
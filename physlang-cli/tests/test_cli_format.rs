@@ -0,0 +1,123 @@
+//! Tests for `physlang run --format`
+
+mod common;
+
+use common::write_program;
+use physlang_core::format_detector_value;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 250
+detect separation = distance(a, b)
+detect a_x = position(a)
+"#;
+
+fn run(name: &str, source: &str, format: &str) -> Output {
+    let path = write_program(name, source);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("run")
+        .arg(&path)
+        .args(["--format", format])
+        .output()
+        .unwrap();
+    output
+}
+
+/// Detector values from the default text output, in order
+fn text_values(name: &str) -> Vec<(String, String)> {
+    let output = run(name, PROGRAM, "text");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (name, value) = line.split_once(" = ").unwrap();
+            (name.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn test_json_output() {
+    let output = run("json", PROGRAM, "json");
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["steps"], 250);
+    assert!(json["warnings"].as_array().unwrap().is_empty());
+
+    let detectors = json["detectors"].as_object().unwrap();
+    assert_eq!(detectors.len(), 2);
+    for (name, value) in text_values("json_text") {
+        // Full precision: the exact value the text output rounds
+        let exact = detectors[&name].as_f64().unwrap() as f32;
+        assert_eq!(format_detector_value(exact, None), value);
+    }
+}
+
+#[test]
+fn test_csv_output() {
+    let output = run("csv", PROGRAM, "csv");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "separation,a_x");
+
+    let values: Vec<String> = lines[1]
+        .split(',')
+        .map(|v| format_detector_value(v.parse().unwrap(), None))
+        .collect();
+    let expected: Vec<String> = text_values("csv_text")
+        .into_iter()
+        .map(|(_, v)| v)
+        .collect();
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn test_warnings_in_json_and_on_stderr() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+well w on a if distance(a, b) >= 1.0 depth 1.0
+simulate dt = 0.01 steps = 10
+detect separation = distance(a, b)
+"#;
+    let output = run("warnings", source, "json");
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings = json["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["severity"], "warning");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning:"));
+}
+
+#[test]
+fn test_errors_go_to_stderr_only() {
+    for format in ["text", "json", "csv"] {
+        let output = run(&format!("error_{}", format), "particle a at", format);
+        assert!(!output.status.success());
+        assert!(
+            output.stdout.is_empty(),
+            "stdout for {}: {:?}",
+            format,
+            output.stdout
+        );
+        assert!(!output.stderr.is_empty());
+    }
+}
+
+#[test]
+fn test_unknown_format_rejected() {
+    let output = run("unknown", PROGRAM, "xml");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
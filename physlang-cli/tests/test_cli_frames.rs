@@ -0,0 +1,110 @@
+//! Tests for `physlang export --frames`
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Render `fixture` into a fresh temporary directory and return it
+fn export_frames(name: &str, fixture: &str, extra: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "physlang_cli_frames_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("export")
+        .arg(fixture)
+        .arg("--frames")
+        .arg(&dir)
+        .args(extra)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    dir
+}
+
+/// Width and height of a PNG file
+fn dimensions(path: &Path) -> (u32, u32) {
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    (info.width, info.height)
+}
+
+fn frame_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_frames_are_numbered_sized_and_distinct() {
+    let dir = export_frames(
+        "sequence",
+        "tests/fixtures/export/pair.phys",
+        &["--every", "5", "--size", "160x120"],
+    );
+
+    // Steps 0, 5 and 10 of a 10-step run
+    let names = frame_names(&dir);
+    assert_eq!(
+        names,
+        ["frame_00000.png", "frame_00001.png", "frame_00002.png"]
+    );
+    let frames: Vec<Vec<u8>> = names
+        .iter()
+        .map(|name| {
+            assert_eq!(dimensions(&dir.join(name)), (160, 120));
+            std::fs::read(dir.join(name)).unwrap()
+        })
+        .collect();
+    assert_ne!(frames[0], frames[1]);
+    assert_ne!(frames[1], frames[2]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_frames_with_labels_and_mass_radius() {
+    let plain = export_frames(
+        "plain",
+        "tests/fixtures/export/pair.phys",
+        &["--every", "10"],
+    );
+    let styled = export_frames(
+        "styled",
+        "tests/fixtures/export/pair.phys",
+        &["--every", "10", "--labels", "--mass-radius"],
+    );
+
+    assert_eq!(frame_names(&styled).len(), 2);
+    assert_eq!(dimensions(&styled.join("frame_00000.png")), (800, 600));
+    assert_ne!(
+        std::fs::read(plain.join("frame_00000.png")).unwrap(),
+        std::fs::read(styled.join("frame_00000.png")).unwrap()
+    );
+    let _ = std::fs::remove_dir_all(&plain);
+    let _ = std::fs::remove_dir_all(&styled);
+}
+
+#[test]
+fn test_size_requires_frames() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([
+            "export",
+            "tests/fixtures/export/pair.phys",
+            "--svg",
+            "out.svg",
+        ])
+        .args(["--size", "100x100"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("out.svg")
+        .exists());
+}
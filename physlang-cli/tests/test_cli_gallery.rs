@@ -0,0 +1,63 @@
+//! Tests for `physlang examples` and the gallery built into the binary
+
+#[path = "../src/gallery.rs"]
+mod gallery;
+
+use gallery::{find, listing, EXAMPLES};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_gallery_lists_every_file() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/gallery");
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".phys"))
+        .collect();
+    files.sort();
+    let mut listed: Vec<String> = EXAMPLES.iter().map(|e| e.file_name()).collect();
+    listed.sort();
+    assert_eq!(listed, files);
+}
+
+#[test]
+fn test_descriptions_and_lookup() {
+    for example in EXAMPLES {
+        assert!(!example.description().is_empty(), "{}", example.name);
+    }
+    assert_eq!(find("orbit.phys").unwrap().name, "orbit");
+    assert_eq!(find("orbit"), find("orbit.phys"));
+    assert!(find("nonexistent").is_none());
+}
+
+#[test]
+fn test_examples_subcommand() {
+    let physlang = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_physlang"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let list = physlang(&["examples"]);
+    assert!(list.status.success());
+    let stdout = String::from_utf8(list.stdout).unwrap();
+    assert_eq!(stdout, listing());
+    assert_eq!(stdout.lines().count(), EXAMPLES.len());
+    assert!(stdout.starts_with("orbit  "), "{}", stdout);
+
+    let run = physlang(&["examples", "double_spring"]);
+    assert!(run.status.success());
+    let stdout = String::from_utf8(run.stdout).unwrap();
+    assert!(stdout.starts_with("upper_stretch = "), "{}", stdout);
+
+    let missing = physlang(&["examples", "nonexistent"]);
+    assert!(!missing.status.success());
+    let stderr = String::from_utf8(missing.stderr).unwrap();
+    assert!(
+        stderr.contains("no example named 'nonexistent'"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,114 @@
+//! Tests for the VEL's grid and axes
+
+#[allow(dead_code)]
+#[path = "../src/render.rs"]
+mod render;
+
+use physlang_core::build_simulation_context_from_source;
+use render::{scene, Camera, Grid, Overlays, Shape};
+
+#[test]
+fn test_grid_spacing_keeps_lines_20_to_200_pixels_apart() {
+    let mut pixels_per_unit = 1e-3;
+    while pixels_per_unit < 1e6 {
+        let grid = Grid::for_zoom(pixels_per_unit);
+        let pixels = grid.spacing * pixels_per_unit;
+        assert!(
+            (19.99..=200.01).contains(&pixels),
+            "{} px at {} px/unit",
+            pixels,
+            pixels_per_unit
+        );
+        let exponent = grid.spacing.log10();
+        assert!(
+            (exponent - exponent.round()).abs() < 1e-4,
+            "{}",
+            grid.spacing
+        );
+        pixels_per_unit *= 1.37;
+    }
+}
+
+#[test]
+fn test_grid_spacing_at_round_zooms() {
+    assert_eq!(Grid::for_zoom(20.0).spacing, 1.0);
+    assert_eq!(Grid::for_zoom(10.0).spacing, 10.0);
+    assert_eq!(Grid::for_zoom(250.0).spacing, 0.1);
+    // Lines 20 px apart are labelled every fifth, 100 px and wider every one
+    assert_eq!(Grid::for_zoom(20.0).major_every, 5);
+    assert_eq!(Grid::for_zoom(10.0).major_every, 1);
+}
+
+#[test]
+fn test_grid_lines_and_labels() {
+    let grid = Grid {
+        spacing: 0.1,
+        major_every: 5,
+    };
+    assert_eq!(grid.lines(-0.25, 0.31), -2..=3);
+    assert!(grid.is_major(0) && grid.is_major(-5) && !grid.is_major(3));
+    assert_eq!(grid.label(5), "0.5");
+    assert_eq!(grid.label(-10), "-1.0");
+
+    let coarse = Grid {
+        spacing: 100.0,
+        major_every: 1,
+    };
+    assert_eq!(coarse.label(3), "300");
+}
+
+#[test]
+fn test_grid_is_drawn_beneath_the_particles() {
+    let (ctx, _) = build_simulation_context_from_source(
+        "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (4.0, 3.0) mass 1.0
+simulate dt = 0.01 steps = 1
+",
+    )
+    .unwrap();
+    let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 300.0));
+    let camera = Camera {
+        center: egui::vec2(2.0, 1.5),
+        pixels_per_unit: 50.0,
+        auto: false,
+    };
+    let draw = |overlays: Overlays| {
+        scene(
+            &ctx.world, &ctx.loops, &ctx.wells, &camera, canvas, &overlays, None,
+        )
+    };
+
+    let plain = draw(Overlays::default());
+    let gridded = draw(Overlays {
+        grid: true,
+        axes: true,
+        ..Overlays::default()
+    });
+    assert!(gridded.len() > plain.len());
+    // Everything added comes before the first particle
+    let first_circle = gridded
+        .iter()
+        .position(|s| matches!(s, Shape::Circle { .. }))
+        .unwrap();
+    assert_eq!(gridded[first_circle..], plain[..]);
+
+    // The axes are the wider lines through the origin
+    let origin = camera.world_to_screen(canvas, 0.0, 0.0);
+    let axes: Vec<_> = gridded[..first_circle]
+        .iter()
+        .filter_map(|s| match s {
+            Shape::Line {
+                from, to, width, ..
+            } if *width > 1.0 => Some((*from, *to)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        axes,
+        [
+            ((origin.x, 0.0), (origin.x, 300.0)),
+            ((0.0, origin.y), (400.0, origin.y)),
+        ]
+    );
+}
@@ -0,0 +1,189 @@
+//! Tests for the shared world renderer and `physlang visual --headless`
+
+#[allow(dead_code)]
+#[path = "../src/render.rs"]
+mod render;
+
+use physlang_core::build_simulation_context_from_source;
+use render::{render_world, scene, Camera, Overlays, Shape};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (4.0, 1.0) mass 3.0
+force spring(a, b) k = 5.0 rest = 1.0
+well target on a if position(a).x >= 2.0 depth 5.0
+loop for 3 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+simulate dt = 0.01 steps = 100
+";
+
+#[test]
+fn test_scene_draws_each_element() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+    let mut camera = Camera::default();
+    camera.fit(&ctx.world, canvas);
+    let shapes = scene(
+        &ctx.world,
+        &ctx.loops,
+        &ctx.wells,
+        &camera,
+        canvas,
+        &Overlays::default(),
+        None,
+    );
+
+    let lines = shapes
+        .iter()
+        .filter(|s| matches!(s, Shape::Line { .. }))
+        .count();
+    let circles = shapes
+        .iter()
+        .filter(|s| matches!(s, Shape::Circle { .. }))
+        .count();
+    let labels: Vec<&str> = shapes
+        .iter()
+        .filter_map(|s| match s {
+            Shape::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    // The spring, the only overlay on by default; two particles and the
+    // loop's ring
+    assert_eq!(lines, 1);
+    assert_eq!(circles, 3);
+    assert_eq!(labels, ["a", "b"]);
+
+    // Everything fits in the canvas
+    for shape in &shapes {
+        if let Shape::Circle { center, .. } = shape {
+            assert!((0.0..=200.0).contains(&center.0), "{:?}", shape);
+            assert!((0.0..=100.0).contains(&center.1), "{:?}", shape);
+        }
+    }
+}
+
+#[test]
+fn test_render_world_draws_on_the_background() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let pixmap = render_world(
+        &ctx.world,
+        &ctx.loops,
+        &ctx.wells,
+        (64, 48),
+        &Overlays::default(),
+    )
+    .unwrap();
+
+    assert_eq!((pixmap.width(), pixmap.height()), (64, 48));
+    let background = pixmap.pixel(0, 0).unwrap();
+    let drawn = pixmap.pixels().iter().filter(|p| **p != background).count();
+    assert!(drawn > 0);
+}
+
+#[test]
+fn test_render_world_shows_a_placeholder_for_an_empty_world() {
+    let (ctx, _) = build_simulation_context_from_source("simulate dt = 0.01 steps = 1").unwrap();
+    let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+    let mut camera = Camera::default();
+    camera.fit(&ctx.world, canvas);
+    let shapes = scene(
+        &ctx.world,
+        &ctx.loops,
+        &ctx.wells,
+        &camera,
+        canvas,
+        &Overlays::default(),
+        None,
+    );
+    assert!(
+        matches!(&shapes[..], [Shape::Text { text, .. }] if text == "No particles"),
+        "{:?}",
+        shapes
+    );
+    assert!(render_world(
+        &ctx.world,
+        &ctx.loops,
+        &ctx.wells,
+        (200, 100),
+        &Overlays::default(),
+    )
+    .is_ok());
+}
+
+/// Render the fixture headlessly into a fresh temporary directory and return it
+fn headless(name: &str, extra: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "physlang_cli_headless_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .args(["visual", "tests/fixtures/visual/spring.phys", "--headless"])
+        .arg("--output")
+        .arg(&dir)
+        .args(extra)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    dir
+}
+
+fn frame_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_headless_writes_fps_times_duration_frames() {
+    let dir = headless(
+        "count",
+        &["--fps", "10", "--duration", "0.5", "--size", "80x60"],
+    );
+
+    // The initial state, then one frame per tenth of a second
+    let names = frame_names(&dir);
+    assert_eq!(names.len(), 6);
+    assert_eq!(names[0], "frame_00000.png");
+    assert_eq!(names[5], "frame_00005.png");
+
+    let decoder = png::Decoder::new(std::io::BufReader::new(
+        std::fs::File::open(dir.join(&names[5])).unwrap(),
+    ));
+    let reader = decoder.read_info().unwrap();
+    assert_eq!((reader.info().width, reader.info().height), (80, 60));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_headless_stops_when_the_simulation_finishes() {
+    // 100 steps of 0.01 is one second, well short of the duration
+    let dir = headless(
+        "finish",
+        &["--fps", "10", "--duration", "5", "--size", "40x30"],
+    );
+    assert_eq!(frame_names(&dir).len(), 11);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_headless_requires_an_output_directory() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["visual", "tests/fixtures/visual/spring.phys", "--headless"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--output"));
+}
@@ -0,0 +1,69 @@
+//! Tests for how the VEL classifies a file that changed on disk
+
+#[path = "../src/hot_reload.rs"]
+mod hot_reload;
+
+use hot_reload::{classify_edit, keeps_world, toast};
+use physlang_core::ProgramDiff;
+
+const SOURCE: &str = "\
+# Two particles on a spring
+let k = 10.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = k rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+";
+
+#[test]
+fn test_comment_only_edit_keeps_the_world() {
+    let edited = SOURCE.replace("# Two particles on a spring", "# A spring between a and b");
+    let diff = classify_edit(SOURCE, &edited);
+    assert_eq!(diff, ProgramDiff::Unchanged);
+    assert!(keeps_world(diff));
+    assert_eq!(
+        toast(diff, 42),
+        "Reloaded, no simulation changes: continuing at step 42"
+    );
+}
+
+#[test]
+fn test_let_only_edit_keeps_the_world() {
+    let edited = SOURCE.replace("let k = 10.0", "let k = 25.0");
+    let diff = classify_edit(SOURCE, &edited);
+    assert_eq!(diff, ProgramDiff::LetsOnly);
+    assert!(keeps_world(diff));
+    assert_eq!(toast(diff, 7), "Reloaded let values: continuing at step 7");
+}
+
+#[test]
+fn test_simulate_only_edit_keeps_the_world() {
+    let edited = SOURCE.replace("steps = 100", "steps = 1000");
+    let diff = classify_edit(SOURCE, &edited);
+    assert_eq!(diff, ProgramDiff::SimulateOnly);
+    assert!(keeps_world(diff));
+}
+
+#[test]
+fn test_structural_edits_restart() {
+    for edited in [
+        SOURCE.replace("particle b at (3.0, 0.0)", "particle b at (4.0, 0.0)"),
+        SOURCE.replace("rest = 2.0", "rest = 1.0"),
+        format!("{}particle c at (1.0, 1.0) mass 1.0\n", SOURCE),
+    ] {
+        let diff = classify_edit(SOURCE, &edited);
+        assert_eq!(diff, ProgramDiff::Structural, "{}", edited);
+        assert!(!keeps_world(diff));
+    }
+    assert_eq!(
+        toast(ProgramDiff::Structural, 42),
+        "Declarations changed: restarted from step 0"
+    );
+}
+
+#[test]
+fn test_unparsable_edit_restarts() {
+    let edited = SOURCE.replace("mass 1.0", "mass");
+    assert_eq!(classify_edit(SOURCE, &edited), ProgramDiff::Structural);
+}
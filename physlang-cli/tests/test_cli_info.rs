@@ -0,0 +1,37 @@
+//! Tests for `physlang info`
+
+use physlang_core::language_capabilities;
+use std::process::Command;
+
+fn physlang(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_info_json_matches_the_library() {
+    let printed: serde_json::Value = serde_json::from_str(&physlang(&["info", "--json"])).unwrap();
+    assert_eq!(
+        printed,
+        serde_json::to_value(language_capabilities()).unwrap()
+    );
+}
+
+#[test]
+fn test_info_text() {
+    let capabilities = language_capabilities();
+    let text = physlang(&["info"]);
+    let mut lines = text.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        format!(
+            "physlang {} (physlang-core {})",
+            capabilities.language_version, capabilities.crate_version
+        )
+    );
+    assert!(lines.any(|line| line == "forces: gravity, spring, central"));
+}
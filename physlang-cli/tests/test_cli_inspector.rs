@@ -0,0 +1,56 @@
+//! Tests for the VEL's particle inspector
+
+#[path = "../src/inspector.rs"]
+mod inspector;
+
+use physlang_core::{build_simulation_context_from_source, step_simulation};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+particle c at (0.0, 4.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+force gravity(b, c) G = 1.0
+loop for 2 cycles with frequency 1.0 damping 0.0 on b {
+    force push(b) magnitude 1.0 direction (1.0, 0.0)
+}
+loop for 2 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+well catch on c if distance(a, b) >= 5.0 depth 5.0
+simulate dt = 0.01 steps = 100
+";
+
+#[test]
+fn test_details_list_what_references_the_particle() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+
+    let b = inspector::details(&ctx, 1).unwrap();
+    assert_eq!(b.name, "b");
+    assert_eq!(b.position, (3.0, 0.0));
+    assert_eq!(b.mass, 2.0);
+    assert_eq!(b.forces, vec!["spring(a, b)", "gravity(b, c)"]);
+    assert_eq!(b.loops, vec!["loop 0"]);
+    assert_eq!(b.wells, vec!["catch"]);
+
+    let c = inspector::details(&ctx, 2).unwrap();
+    assert_eq!(c.forces, vec!["gravity(b, c)"]);
+    assert!(c.loops.is_empty());
+    assert_eq!(c.wells, vec!["catch"]);
+
+    assert!(inspector::details(&ctx, 3).is_none());
+}
+
+#[test]
+fn test_details_follow_the_simulation() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let before = inspector::details(&ctx, 0).unwrap();
+    assert_eq!(before.speed, 0.0);
+    for _ in 0..10 {
+        step_simulation(&mut ctx);
+    }
+    let after = inspector::details(&ctx, 0).unwrap();
+    assert_ne!(after.position, before.position);
+    let (vx, vy) = after.velocity;
+    assert!((after.speed - (vx * vx + vy * vy).sqrt()).abs() < 1e-6);
+}
@@ -0,0 +1,198 @@
+//! Runs every fixture through both `run_program` and `physlang run`, and
+//! checks the CLI prints exactly what the library returns
+
+use physlang_core::{
+    format_detector_value, run_program, run_program_with_options, ParseError, RunError,
+    RunOptions, SimulationResult,
+};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Directories of fixtures, relative to the workspace root
+const FIXTURE_DIRS: &[&str] = &[
+    "examples",
+    "examples/gallery",
+    "tests/golden",
+    "tests/integration/broken",
+];
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in FIXTURE_DIRS {
+        let mut found: Vec<PathBuf> = std::fs::read_dir(workspace_root().join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "phys"))
+            .collect();
+        found.sort();
+        files.extend(found);
+    }
+    files
+}
+
+fn physlang_run(file: &Path, format: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(["run", "--format", format])
+        .arg(file)
+        .output()
+        .unwrap()
+}
+
+/// `name = value` lines of text output
+fn parse_text(stdout: &str) -> Vec<(String, String)> {
+    stdout
+        .lines()
+        .map(|line| {
+            let (name, value) = line.split_once(" = ").unwrap();
+            (name.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Detector values and run totals of JSON output, in the printed order
+fn parse_json(stdout: &str) -> (Vec<(String, f32)>, u64, usize) {
+    let json: serde_json::Value = serde_json::from_str(stdout).unwrap();
+    let mut detectors: Vec<(String, f32)> = json["detectors"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, value)| (name.clone(), value.as_f64().unwrap() as f32))
+        .collect();
+    // `Value` sorts object keys; the order printed is the order in the text
+    detectors.sort_by_key(|(name, _)| stdout.find(&format!("\"{}\":", name)));
+    let hash = json["state_hash"].as_u64().unwrap();
+    let steps = json["steps"].as_u64().unwrap() as usize;
+    (detectors, hash, steps)
+}
+
+fn detectors(result: &SimulationResult) -> Vec<(String, f32)> {
+    result
+        .detectors
+        .iter()
+        .map(|d| (d.name.clone(), d.value))
+        .collect()
+}
+
+/// Detector values as text output shows them
+fn formatted_detectors(result: &SimulationResult) -> Vec<(String, String)> {
+    result
+        .detectors
+        .iter()
+        .map(|d| (d.name.clone(), format_detector_value(d.value, None)))
+        .collect()
+}
+
+/// The exit code `physlang run` documents for a library error
+fn expected_exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if error.is::<ParseError>() {
+        2
+    } else if let Some(RunError::Analysis { .. }) = error.downcast_ref::<RunError>() {
+        3
+    } else {
+        1
+    }
+}
+
+/// What stderr must show of `error`: each analysis message, whether
+/// rendered at its line or listed after it, and otherwise the whole error
+fn expected_messages(error: &(dyn std::error::Error + 'static)) -> Vec<String> {
+    match error.downcast_ref::<RunError>() {
+        Some(RunError::Analysis { messages, .. }) => messages.clone(),
+        _ => vec![error.to_string()],
+    }
+}
+
+fn check_fixture(file: &Path) {
+    let name = file.display();
+    let source = std::fs::read_to_string(file).unwrap();
+    let library = run_program(&source);
+
+    for format in ["text", "json"] {
+        let output = physlang_run(file, format);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        match &library {
+            Ok(result) => {
+                assert_eq!(
+                    output.status.code(),
+                    Some(0),
+                    "{} ({}): {}",
+                    name,
+                    format,
+                    stderr
+                );
+                if format == "text" {
+                    assert_eq!(parse_text(&stdout), formatted_detectors(result), "{}", name);
+                } else {
+                    let (printed, hash, steps) = parse_json(&stdout);
+                    assert_eq!(printed, detectors(result), "{}", name);
+                    assert_eq!(hash, result.state_hash, "{}", name);
+                    assert_eq!(steps, result.steps, "{}", name);
+                }
+            }
+            Err(error) => {
+                assert_eq!(
+                    output.status.code(),
+                    Some(expected_exit_code(&**error)),
+                    "{} ({}): {}",
+                    name,
+                    format,
+                    stderr
+                );
+                assert!(
+                    stdout.is_empty(),
+                    "{} ({}) printed {}",
+                    name,
+                    format,
+                    stdout
+                );
+                for message in expected_messages(&**error) {
+                    assert!(
+                        stderr.contains(&message),
+                        "{} ({}): expected '{}' in {}",
+                        name,
+                        format,
+                        message,
+                        stderr
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cli_prints_what_the_library_returns() {
+    let files = fixtures();
+    assert!(files.len() > 10, "too few fixtures: {:?}", files);
+    for file in &files {
+        check_fixture(file);
+    }
+}
+
+#[test]
+fn test_cli_overrides_match_run_options() {
+    let options = RunOptions {
+        steps: Some(7),
+        ..RunOptions::default()
+    };
+    for file in fixtures() {
+        let source = std::fs::read_to_string(&file).unwrap();
+        let Ok(result) = run_program_with_options(&source, &options) else {
+            continue;
+        };
+        let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+            .args(["run", "--format", "json", "--set", "steps=7"])
+            .arg(&file)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", file.display());
+        let (printed, hash, steps) = parse_json(&String::from_utf8(output.stdout).unwrap());
+        assert_eq!(printed, detectors(&result), "{}", file.display());
+        assert_eq!((hash, steps), (result.state_hash, 7), "{}", file.display());
+    }
+}
@@ -0,0 +1,99 @@
+//! Tests for the `--max-particles`, `--max-generated` and `--max-diagnostics`
+//! flags of `physlang run`
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> (Option<i32>, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("run")
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_runaway_loop_is_reported_at_its_line() {
+    let (code, stdout, stderr) = run(&["tests/fixtures/limits/runaway.phys"]);
+    assert_eq!(code, Some(3));
+    assert!(stdout.is_empty(), "{}", stdout);
+    assert!(
+        stderr.contains(
+            "too many particles: 100001 exceeds the limit of 100000 \
+             in for i in 0..10000000 at i = 100000"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("runaway.phys:2:1"), "{}", stderr);
+}
+
+#[test]
+fn test_world_within_the_defaults_runs() {
+    let (code, stdout, _) = run(&["tests/fixtures/limits/row.phys"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "origin_x = 0.0\n");
+}
+
+#[test]
+fn test_max_particles_counts_declared_and_generated() {
+    let (code, _, stderr) = run(&["tests/fixtures/limits/row.phys", "--max-particles", "3"]);
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.contains("too many particles: 4 exceeds the limit of 3 in row(\"c\", 2)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_max_generated_counts_generated_declarations() {
+    let (code, _, stderr) = run(&["tests/fixtures/limits/row.phys", "--max-generated", "2"]);
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.contains("too many generated declarations: 3 exceeds the limit of 2"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_raised_limit_allows_a_larger_world() {
+    let (code, _, stderr) = run(&[
+        "tests/fixtures/limits/runaway.phys",
+        "--max-particles",
+        "200000",
+    ]);
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.contains("too many particles: 200001 exceeds the limit of 200000"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_repeated_errors_are_summarized() {
+    let (code, _, stderr) = run(&["tests/fixtures/limits/same_name.phys"]);
+    assert_eq!(code, Some(3));
+    assert_eq!(stderr.matches("duplicate particle name 'node'").count(), 50);
+    assert!(
+        stderr.ends_with("\n9,949 more duplicate particle name errors suppressed\n"),
+        "{}",
+        stderr
+    );
+
+    let (code, _, stderr) = run(&[
+        "tests/fixtures/limits/same_name.phys",
+        "--max-diagnostics",
+        "3",
+    ]);
+    assert_eq!(code, Some(3));
+    assert_eq!(stderr.matches("duplicate particle name 'node'").count(), 3);
+    assert!(stderr.contains("9,996 more duplicate particle name errors suppressed"));
+}
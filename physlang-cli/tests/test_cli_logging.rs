@@ -0,0 +1,74 @@
+//! Tests for the global `--quiet` and `--verbose` flags
+
+use std::process::{Command, Output};
+
+fn physlang(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    output
+}
+
+#[test]
+fn test_quiet_prints_only_detectors() {
+    let output = physlang(&["run", "--quiet", "tests/fixtures/logging/warning.phys"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with("separation = "), "{}", stdout);
+    assert!(output.stderr.is_empty(), "{:?}", output.stderr);
+
+    // Without it the warning is printed
+    let output = physlang(&["run", "tests/fixtures/logging/warning.phys"]);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("warning:"));
+}
+
+#[test]
+fn test_quiet_check_prints_nothing() {
+    let output = physlang(&["-q", "check", "tests/fixtures/logging/long.phys"]);
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_verbose_prints_timings_and_progress() {
+    let output = physlang(&["run", "--verbose", "tests/fixtures/logging/long.phys"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for stage in ["parse: ", "analyze: ", "build: ", "simulate: "] {
+        assert!(stderr.contains(stage), "no {} in {}", stage, stderr);
+    }
+    assert!(stderr.contains("5000/10000 steps (50%)"), "{}", stderr);
+    assert!(stderr.contains("10000/10000 steps (100%)"), "{}", stderr);
+
+    // Results are unchanged on stdout
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.starts_with("separation = "));
+}
+
+#[test]
+fn test_twice_verbose_traces_the_parser() {
+    let output = physlang(&["-vv", "check", "tests/fixtures/logging/long.phys"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("[physlang_core::parser] Starting parse_program"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_quiet_and_verbose_conflict() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(["run", "-q", "-v", "missing.phys"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("cannot be used with"));
+}
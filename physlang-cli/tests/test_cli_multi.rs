@@ -0,0 +1,94 @@
+//! Tests for checking and running several files or directories at once
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+fn physlang(args: &[&str], paths: &[PathBuf]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(args)
+        .args(paths)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_check_directory_reports_every_file() {
+    let output = physlang(&["check"], &[fixture("mixed")]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let good = fixture("mixed/good.phys");
+    let broken = fixture("mixed/nested/broken.phys");
+    assert!(
+        stdout.contains(&format!("{}: No issues found.", good.display())),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!(
+            "error[E0002]: unknown particle 'ghost' in force\n --> {}:2:1\n",
+            broken.display()
+        )),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("ghost"));
+    assert!(!stdout.contains("notes.txt"));
+}
+
+#[test]
+fn test_check_good_files_exit_zero() {
+    let output = physlang(&["check"], &[fixture("mixed/good.phys")]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_check_empty_directory_is_an_error() {
+    let dir = std::env::temp_dir().join(format!("physlang_cli_multi_empty_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = physlang(&["check"], std::slice::from_ref(&dir));
+    let _ = std::fs::remove_dir(&dir);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no .phys files found"));
+}
+
+#[test]
+fn test_run_multiple_files_prints_headers() {
+    let good = fixture("mixed/good.phys");
+    let broken = fixture("mixed/nested/broken.phys");
+    let output = physlang(&["run"], &[good.clone(), broken.clone(), good.clone()]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let headers: Vec<&str> = stdout.lines().filter(|l| l.starts_with("== ")).collect();
+    assert_eq!(
+        headers,
+        [
+            format!("== {} ==", good.display()),
+            format!("== {} ==", broken.display()),
+            format!("== {} ==", good.display()),
+        ]
+    );
+    // The broken file does not stop the files after it
+    assert_eq!(stdout.matches("separation = ").count(), 2);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains(&broken.display().to_string()));
+}
+
+#[test]
+fn test_run_single_file_has_no_header() {
+    let output = physlang(&["run"], &[fixture("mixed/good.phys")]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("separation = "), "{}", stdout);
+}
@@ -0,0 +1,94 @@
+//! Tests for the VEL's Open dialog, recent files, and an optional file argument
+
+#[allow(dead_code)]
+#[path = "../src/file_browser.rs"]
+mod file_browser;
+#[allow(dead_code)]
+#[path = "../src/recent.rs"]
+mod recent;
+
+use file_browser::{list, FileBrowser};
+use recent::{RecentFiles, MAX_RECENT};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("physlang_cli_open_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_lists_directories_then_phys_files() {
+    let dir = scratch_dir("list");
+    std::fs::create_dir(dir.join("zoo")).unwrap();
+    std::fs::create_dir(dir.join(".hidden")).unwrap();
+    for file in ["b.phys", "a.phys", "notes.txt", ".draft.phys"] {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+
+    let labels: Vec<String> = list(&dir).unwrap().iter().map(|e| e.label()).collect();
+    assert_eq!(labels, ["zoo/", "a.phys", "b.phys"]);
+}
+
+#[test]
+fn test_browser_moves_between_directories() {
+    let dir = scratch_dir("browse");
+    std::fs::create_dir(dir.join("inner")).unwrap();
+    std::fs::write(dir.join("inner").join("x.phys"), "").unwrap();
+
+    let mut browser = FileBrowser::new(dir.join("inner"));
+    assert_eq!(browser.entries.len(), 1);
+    browser.up();
+    assert_eq!(browser.dir, dir.canonicalize().unwrap());
+    assert_eq!(browser.entries.len(), 1);
+    assert!(browser.entries[0].is_dir);
+
+    browser.enter(dir.join("missing"));
+    assert!(browser.entries.is_empty());
+    assert!(browser.error.is_some());
+}
+
+#[test]
+fn test_recent_files_most_recent_first_without_repeats() {
+    let mut recent = RecentFiles::default();
+    for name in ["a.phys", "b.phys", "a.phys"] {
+        recent.push(PathBuf::from(name));
+    }
+    assert_eq!(recent.paths(), [Path::new("a.phys"), Path::new("b.phys")]);
+
+    for i in 0..MAX_RECENT + 5 {
+        recent.push(PathBuf::from(format!("{}.phys", i)));
+    }
+    assert_eq!(recent.paths().len(), MAX_RECENT);
+    assert_eq!(recent.paths()[0], Path::new("14.phys"));
+
+    recent.remove(Path::new("14.phys"));
+    assert_eq!(recent.paths()[0], Path::new("13.phys"));
+}
+
+#[test]
+fn test_recent_files_round_trip_through_disk() {
+    let dir = scratch_dir("recent");
+    let file = dir.join("config").join("recent-files");
+    assert_eq!(RecentFiles::load(&file), RecentFiles::default());
+
+    let mut recent = RecentFiles::default();
+    recent.push(PathBuf::from("/tmp/one.phys"));
+    recent.push(PathBuf::from("/tmp/two.phys"));
+    recent.save(&file).unwrap();
+    assert_eq!(RecentFiles::load(&file), recent);
+}
+
+#[test]
+fn test_headless_still_needs_a_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(["visual", "--headless", "--output", "frames"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("<FILE>"), "{}", stderr);
+}
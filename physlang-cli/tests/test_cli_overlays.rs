@@ -0,0 +1,228 @@
+//! Tests for the VEL's velocity, force, and well overlays
+
+#[allow(dead_code)]
+#[path = "../src/render.rs"]
+mod render;
+
+use physlang_core::{build_simulation_context_from_source, SimulationContext};
+use render::{scene, Camera, Overlays, Shape};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (10.0, 0.0) mass 1.0
+force gravity(a, b) G = 1.0
+well right on a if position(a).x >= 5.0 depth 5.0
+simulate dt = 0.01 steps = 100
+";
+
+fn canvas() -> egui::Rect {
+    egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 200.0))
+}
+
+fn context() -> SimulationContext {
+    build_simulation_context_from_source(SOURCE).unwrap().0
+}
+
+fn shapes(ctx: &SimulationContext, overlays: Overlays) -> Vec<Shape> {
+    let mut camera = Camera::default();
+    camera.fit(&ctx.world, canvas());
+    scene(
+        &ctx.world,
+        &ctx.loops,
+        &ctx.wells,
+        &camera,
+        canvas(),
+        &overlays,
+        None,
+    )
+}
+
+fn lines(shapes: &[Shape]) -> Vec<((f32, f32), (f32, f32))> {
+    shapes
+        .iter()
+        .filter_map(|s| match s {
+            Shape::Line { from, to, .. } => Some((*from, *to)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn none() -> Overlays {
+    Overlays {
+        springs: false,
+        gravity: false,
+        velocities: false,
+        wells: false,
+        ..Overlays::default()
+    }
+}
+
+#[test]
+fn test_overlays_are_off_by_default_except_springs() {
+    let ctx = context();
+    assert!(lines(&shapes(&ctx, Overlays::default())).is_empty());
+    assert_eq!(
+        Overlays::default().legend(),
+        [("spring", [160, 160, 160, 255])]
+    );
+}
+
+#[test]
+fn test_gravity_pairs_are_dashed() {
+    let ctx = context();
+    let dashes = lines(&shapes(
+        &ctx,
+        Overlays {
+            gravity: true,
+            ..none()
+        },
+    ));
+    // Many short segments along the pair, with gaps between them
+    assert!(dashes.len() > 5, "{:?}", dashes);
+    for pair in dashes.windows(2) {
+        assert!(pair[1].0 .0 > pair[0].1 .0);
+    }
+}
+
+#[test]
+fn test_velocity_arrows_are_capped() {
+    let mut ctx = context();
+    ctx.world.particles[0].vel.x = 1000.0;
+    let arrows = lines(&shapes(
+        &ctx,
+        Overlays {
+            velocities: true,
+            ..none()
+        },
+    ));
+    // The resting particle gets no arrow; the moving one a shaft and a head
+    assert_eq!(arrows.len(), 3);
+    let (from, to) = arrows[0];
+    assert!(to.0 > from.0);
+    assert!((to.0 - from.0 - 60.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_wells_shade_their_active_side() {
+    let ctx = context();
+    let drawn = shapes(
+        &ctx,
+        Overlays {
+            wells: true,
+            ..none()
+        },
+    );
+    let threshold = lines(&drawn);
+    assert_eq!(threshold.len(), 1);
+    let x = threshold[0].0 .0;
+    assert_eq!(threshold[0].1 .0, x);
+
+    let shaded: Vec<_> = drawn
+        .iter()
+        .filter_map(|s| match s {
+            Shape::Rect { min, max, .. } => Some((*min, *max)),
+            _ => None,
+        })
+        .collect();
+    // A well pulls at x >= threshold, so the right of the line is shaded
+    assert_eq!(shaded, [((x, 0.0), (400.0, 200.0))]);
+}
+
+fn circles(shapes: &[Shape]) -> Vec<(Option<[u8; 4]>, f32)> {
+    shapes
+        .iter()
+        .filter_map(|s| match s {
+            Shape::Circle { fill, radius, .. } => Some((*fill, *radius)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_particle_colors_follow_names_not_order() {
+    let ctx = context();
+    let reordered = build_simulation_context_from_source(
+        "\
+particle b at (10.0, 0.0) mass 1.0
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 100
+",
+    )
+    .unwrap()
+    .0;
+    let fills = |ctx: &SimulationContext| -> Vec<_> {
+        circles(&shapes(ctx, none()))
+            .into_iter()
+            .map(|(fill, _)| fill.unwrap())
+            .collect()
+    };
+    let (original, swapped) = (fills(&ctx), fills(&reordered));
+    assert_eq!(original[0], swapped[1]);
+    assert_eq!(original[1], swapped[0]);
+    assert_eq!(original[0], render::particle_color("a"));
+    assert_ne!(render::particle_color("a"), render::particle_color("b"));
+}
+
+#[test]
+fn test_labels_and_radii_can_change() {
+    let mut ctx = context();
+    ctx.world.particles[1].mass = 100.0;
+
+    let labels = |shapes: &[Shape]| -> Vec<[u8; 4]> {
+        shapes
+            .iter()
+            .filter_map(|s| match s {
+                Shape::Text { color, .. } => Some(*color),
+                _ => None,
+            })
+            .collect()
+    };
+    let light = labels(&shapes(&ctx, none()));
+    assert_eq!(light.len(), 2);
+    let dark = labels(&shapes(
+        &ctx,
+        Overlays {
+            dark: true,
+            ..none()
+        },
+    ));
+    assert_ne!(light[0], dark[0]);
+    let hidden = shapes(
+        &ctx,
+        Overlays {
+            labels: false,
+            ..none()
+        },
+    );
+    assert!(labels(&hidden).is_empty());
+
+    let by_mass = circles(&shapes(&ctx, none()));
+    assert!(by_mass[1].1 > by_mass[0].1);
+    let fixed = circles(&shapes(
+        &ctx,
+        Overlays {
+            mass_radius: false,
+            ..none()
+        },
+    ));
+    assert_eq!(fixed[0].1, fixed[1].1);
+}
+
+#[test]
+fn test_springs_color_by_strain() {
+    assert_eq!(render::strain_color(2.0, 2.0), [160, 160, 160, 255]);
+    let compressed = render::strain_color(1.0, 2.0);
+    let extended = render::strain_color(3.0, 2.0);
+    assert!(compressed[2] > compressed[0], "{:?}", compressed);
+    assert!(extended[0] > extended[2], "{:?}", extended);
+    // Halfway to full strain is halfway to the full color
+    let slight = render::strain_color(2.5, 2.0);
+    assert!(slight[0] > 160 && slight[0] < extended[0]);
+
+    let strain = Overlays {
+        strain: true,
+        ..Overlays::default()
+    };
+    let names: Vec<_> = strain.legend().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["spring compressed", "spring extended"]);
+}
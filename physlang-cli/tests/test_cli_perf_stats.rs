@@ -0,0 +1,50 @@
+//! Tests for the VEL's rolling performance stats
+
+#[path = "../src/perf_stats.rs"]
+mod perf_stats;
+
+use perf_stats::{FrameSample, PerfStats, SAMPLES};
+
+fn frame(frame_time: f64, steps: usize, step_time: f64) -> FrameSample {
+    FrameSample {
+        frame_time,
+        steps,
+        step_time,
+    }
+}
+
+#[test]
+fn test_empty_stats() {
+    let stats = PerfStats::default();
+    assert_eq!(stats.steps_per_second(), 0.0);
+    assert_eq!(stats.step_time(), None);
+    assert_eq!(stats.frame_time(), None);
+    assert_eq!(stats.frame_times().count(), 0);
+}
+
+#[test]
+fn test_rolling_averages() {
+    let mut stats = PerfStats::default();
+    stats.record(frame(0.02, 10, 0.004));
+    stats.record(frame(0.03, 20, 0.008));
+    stats.record(frame(0.05, 0, 0.0));
+
+    assert!((stats.steps_per_second() - 300.0).abs() < 1e-9);
+    assert!((stats.step_time().unwrap() - 0.0004).abs() < 1e-12);
+    assert!((stats.frame_time().unwrap() - 0.1 / 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_keeps_only_the_latest_samples() {
+    let mut stats = PerfStats::default();
+    for i in 0..SAMPLES + 30 {
+        stats.record(frame(i as f64, 1, 0.0));
+    }
+    let times: Vec<f64> = stats.frame_times().collect();
+    assert_eq!(times.len(), SAMPLES);
+    assert_eq!(times[0], 30.0);
+    assert_eq!(times[SAMPLES - 1], (SAMPLES + 29) as f64);
+
+    stats.clear();
+    assert_eq!(stats.frame_time(), None);
+}
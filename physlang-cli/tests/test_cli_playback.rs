@@ -0,0 +1,67 @@
+//! Tests for the VEL's frame-rate independent playback
+
+#[path = "../src/playback.rs"]
+mod playback;
+
+use playback::{plan, MAX_STEPS_PER_FRAME};
+
+/// Run `frames` frames of `elapsed` seconds each, returning the total steps
+fn total_steps(frames: usize, elapsed: f64, speed: f64, dt: f64) -> usize {
+    let mut carry = 0.0;
+    let mut steps = 0;
+    for _ in 0..frames {
+        let frame = plan(carry, elapsed, speed, dt, MAX_STEPS_PER_FRAME);
+        assert!(!frame.capped);
+        carry = frame.carry;
+        steps += frame.steps;
+    }
+    steps
+}
+
+#[test]
+fn test_speed_is_independent_of_the_frame_rate() {
+    // One wall second at 1× with dt = 0.01 is 100 steps, at 60 Hz or 144 Hz
+    let at_60 = total_steps(60, 1.0 / 60.0, 1.0, 0.01);
+    let at_144 = total_steps(144, 1.0 / 144.0, 1.0, 0.01);
+    assert!((99..=100).contains(&at_60), "{}", at_60);
+    assert!((99..=100).contains(&at_144), "{}", at_144);
+
+    // Twice the speed, twice the steps
+    let fast = total_steps(60, 1.0 / 60.0, 2.0, 0.01);
+    assert!((199..=200).contains(&fast), "{}", fast);
+}
+
+#[test]
+fn test_remainder_carries_to_the_next_frame() {
+    let frame = plan(0.0, 0.025, 1.0, 0.01, 10);
+    assert_eq!(frame.steps, 2);
+    assert!((frame.carry - 0.005).abs() < 1e-12);
+
+    // Frames shorter than a step still add up to one
+    let first = plan(0.0, 0.006, 1.0, 0.01, 10);
+    assert_eq!(first.steps, 0);
+    let second = plan(first.carry, 0.006, 1.0, 0.01, 10);
+    assert_eq!(second.steps, 1);
+    assert!((second.carry - 0.002).abs() < 1e-12);
+}
+
+#[test]
+fn test_steps_per_frame_are_capped() {
+    // A one-second hitch at 10× would be 10,000 steps
+    let frame = plan(0.0, 1.0, 10.0, 0.001, 500);
+    assert_eq!(frame.steps, 500);
+    assert!(frame.capped);
+    // The backlog is dropped rather than carried into the next frame
+    assert_eq!(frame.carry, 0.0);
+
+    let exact = plan(0.0, 0.5, 1.0, 0.001, 500);
+    assert_eq!(exact.steps, 500);
+    assert!(!exact.capped);
+}
+
+#[test]
+fn test_degenerate_inputs_run_nothing() {
+    assert_eq!(plan(0.0, 1.0, 1.0, 0.0, 10).steps, 0);
+    assert_eq!(plan(0.0, -1.0, 1.0, 0.01, 10).steps, 0);
+    assert_eq!(plan(0.0, 1.0, 0.0, 0.01, 10).steps, 0);
+}
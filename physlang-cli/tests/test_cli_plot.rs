@@ -0,0 +1,102 @@
+//! Tests for the detector time series behind the VEL's plot
+
+#[allow(dead_code)]
+#[path = "../src/plot.rs"]
+mod plot;
+
+use physlang_core::DetectorResult;
+use plot::{DetectorSeries, PlotBounds};
+
+fn values(pairs: &[(&str, f32)]) -> Vec<DetectorResult> {
+    pairs
+        .iter()
+        .map(|&(name, value)| DetectorResult {
+            name: name.to_string(),
+            value,
+            initial: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_series_samples_every_step() {
+    let mut series = DetectorSeries::default();
+    for step in 0..5 {
+        series.record(step, &values(&[("x", step as f32), ("y", 1.0)]));
+    }
+    assert_eq!(series.names(), ["x", "y"]);
+    assert_eq!(
+        series.points("x"),
+        vec![(0, 0.0), (1, 1.0), (2, 2.0), (3, 3.0), (4, 4.0)]
+    );
+    assert!(series.points("missing").is_empty());
+}
+
+#[test]
+fn test_series_decimates_past_capacity() {
+    let mut series = DetectorSeries::new(10);
+    for step in 0..=100 {
+        series.record(step, &values(&[("x", step as f32)]));
+    }
+    let points = series.points("x");
+    assert!(points.len() <= 10, "{} points", points.len());
+    assert_eq!(series.stride(), 16);
+    assert_eq!(points[0], (0, 0.0));
+    assert!(points
+        .windows(2)
+        .all(|pair| pair[1].0 - pair[0].0 == series.stride()));
+}
+
+#[test]
+fn test_series_rewinds_and_restarts() {
+    let mut series = DetectorSeries::default();
+    for step in 0..10 {
+        series.record(step, &values(&[("x", 1.0)]));
+    }
+    // Going back in time drops the samples after it
+    series.record(4, &values(&[("x", 2.0)]));
+    assert_eq!(series.points("x").len(), 5);
+    assert_eq!(series.points("x")[4], (4, 2.0));
+
+    // Different detectors restart the series
+    series.record(5, &values(&[("y", 3.0)]));
+    assert!(series.points("x").is_empty());
+    assert_eq!(series.points("y"), vec![(5, 3.0)]);
+
+    series.clear();
+    assert!(series.points("y").is_empty());
+}
+
+#[test]
+fn test_series_csv_has_the_selected_columns() {
+    let mut series = DetectorSeries::default();
+    series.record(0, &values(&[("x", 0.5), ("y", 1.0), ("z", 2.0)]));
+    series.record(1, &values(&[("x", 1.5), ("y", 2.0), ("z", 3.0)]));
+    let mut buffer = Vec::new();
+    series
+        .write_csv(&mut buffer, &["z".to_string(), "x".to_string()])
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "step,z,x\n0,2,0.5\n1,3,1.5\n"
+    );
+}
+
+#[test]
+fn test_bounds_scale_to_the_samples() {
+    assert_eq!(PlotBounds::fit(&[]), None);
+
+    let bounds =
+        PlotBounds::fit(&[vec![(0, -1.0), (10, 3.0)], vec![(5, f32::NAN), (20, 1.0)]]).unwrap();
+    assert_eq!(bounds.steps, (0, 20));
+    assert_eq!(bounds.values, (-1.0, 3.0));
+
+    let rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(200.0, 100.0));
+    assert_eq!(bounds.position(rect, 0, -1.0), egui::pos2(0.0, 100.0));
+    assert_eq!(bounds.position(rect, 20, 3.0), egui::pos2(200.0, 0.0));
+
+    // A flat series still has some height
+    let flat = PlotBounds::fit(&[vec![(3, 2.0)]]).unwrap();
+    assert_eq!(flat.steps, (3, 4));
+    assert!(flat.values.0 < 2.0 && flat.values.1 > 2.0);
+}
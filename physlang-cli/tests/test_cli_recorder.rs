@@ -0,0 +1,93 @@
+//! Tests for recording the VEL's canvas to PNG frames
+
+#[allow(dead_code)]
+#[path = "../src/recorder.rs"]
+mod recorder;
+#[allow(dead_code)]
+#[path = "../src/render.rs"]
+mod render;
+
+use physlang_core::build_simulation_context_from_source;
+use recorder::{format_size, frame_size, Recorder, MAX_FRAME_SIDE};
+use render::{render_view, render_world, Camera, Overlays};
+use std::path::PathBuf;
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (4.0, 1.0) mass 3.0
+force spring(a, b) k = 5.0 rest = 1.0
+simulate dt = 0.01 steps = 100
+";
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "physlang_cli_recorder_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_frame_size_keeps_aspect_within_cap() {
+    assert_eq!(frame_size(800.0, 600.0), (800, 600));
+    assert_eq!(frame_size(2560.0, 1440.0), (MAX_FRAME_SIDE, 720));
+    assert_eq!(frame_size(600.0, 3000.0), (256, MAX_FRAME_SIDE));
+    assert_eq!(frame_size(0.0, 0.0), (1, 1));
+}
+
+#[test]
+fn test_records_every_nth_step_once() {
+    let dir = scratch_dir("every");
+    let mut recorder = Recorder::start(dir.clone(), (4, 4), 5).unwrap();
+    for step in [0, 0, 1, 4, 5, 5, 10] {
+        if recorder.wants(step) {
+            recorder
+                .capture(step, |path| Ok(std::fs::write(path, [0u8; 10])?))
+                .unwrap();
+        }
+    }
+    assert_eq!(recorder.frames(), 3);
+    assert_eq!(recorder.bytes(), 30);
+    assert!(dir.join("frame_00002.png").exists());
+    assert!(!dir.join("frame_00003.png").exists());
+
+    // 12 more steps at every 5th step is 3 more frames of 10 bytes
+    assert_eq!(recorder.estimate(12), 60);
+}
+
+#[test]
+fn test_captured_frames_are_pngs_of_the_view() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let overlays = Overlays::default();
+    let size = (160, 120);
+
+    // A fitted camera draws what the headless renderer draws
+    let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(160.0, 120.0));
+    let mut camera = Camera::default();
+    camera.fit(&ctx.world, canvas);
+    let view = render_view(
+        &ctx.world, &ctx.loops, &ctx.wells, &camera, size, &overlays, None,
+    )
+    .unwrap();
+    let fitted = render_world(&ctx.world, &ctx.loops, &ctx.wells, size, &overlays).unwrap();
+    assert_eq!(view.data(), fitted.data());
+
+    let dir = scratch_dir("png");
+    let mut recorder = Recorder::start(dir.clone(), size, 1).unwrap();
+    recorder
+        .capture(0, |path| render::write_png(path, &view))
+        .unwrap();
+    let file = std::fs::File::open(dir.join("frame_00000.png")).unwrap();
+    let decoder = png::Decoder::new(std::io::BufReader::new(file));
+    let info = decoder.read_info().unwrap().info().clone();
+    assert_eq!((info.width, info.height), size);
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(999), "999 B");
+    assert_eq!(format_size(4_200_000), "4.2 MB");
+    assert_eq!(format_size(1_500_000_000), "1.5 GB");
+}
@@ -0,0 +1,93 @@
+//! Snapshot tests for rendered diagnostics and the `--color` flag
+
+use std::process::{Command, Output};
+
+/// Run physlang from the crate root so fixture paths in the output are relative
+fn physlang(args: &[&str], envs: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .envs(envs.iter().copied())
+        .env_remove("NO_COLOR")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_check_parse_error_snapshot() {
+    let output = physlang(
+        &[
+            "check",
+            "tests/fixtures/render/mid_line.phys",
+            "--color",
+            "never",
+        ],
+        &[],
+    );
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        include_str!("fixtures/render/mid_line.check.txt")
+    );
+}
+
+#[test]
+fn test_check_analyzer_error_snapshot() {
+    let output = physlang(
+        &[
+            "check",
+            "tests/fixtures/mixed/nested/broken.phys",
+            "--color",
+            "never",
+        ],
+        &[],
+    );
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        include_str!("fixtures/render/broken.check.txt")
+    );
+}
+
+#[test]
+fn test_run_parse_error_uses_same_renderer() {
+    let output = physlang(
+        &[
+            "run",
+            "tests/fixtures/render/mid_line.phys",
+            "--color",
+            "never",
+        ],
+        &[],
+    );
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    // Exactly what `check` prints, with no second copy of the message
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        include_str!("fixtures/render/mid_line.check.txt")
+    );
+}
+
+#[test]
+fn test_color_choice() {
+    let path = "tests/fixtures/render/mid_line.phys";
+    let escapes = |output: Output| output.stdout.contains(&0x1b);
+
+    assert!(escapes(physlang(
+        &["check", path, "--color", "always"],
+        &[]
+    )));
+    // An explicit `always` wins over NO_COLOR
+    assert!(escapes(physlang(
+        &["check", path, "--color", "always"],
+        &[("NO_COLOR", "1")]
+    )));
+    assert!(!escapes(physlang(
+        &["check", path, "--color", "never"],
+        &[]
+    )));
+    // Output captured by a pipe is not a terminal
+    assert!(!escapes(physlang(&["check", path], &[])));
+    assert!(!escapes(physlang(&["check", path, "--color", "auto"], &[])));
+}
@@ -0,0 +1,29 @@
+//! Tests for `physlang repl` reading a script from stdin
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_repl_reads_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"let k = 2.0\nk * 4.0\n:nope\nparticle a at (1.0, 0.0) mass 1.0\n:detect position(a)\n:quit\nk\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    // No prompts when stdin isn't a terminal, and nothing after :quit
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "8\n1\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("error: unknown command ':nope'"));
+}
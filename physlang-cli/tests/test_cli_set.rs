@@ -0,0 +1,81 @@
+//! Tests for `physlang run --set`
+
+mod common;
+
+use common::write_program;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+let spring_k = 2.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (5.0, 0.0) mass 1.0
+force spring(a, b) k = spring_k rest = 3.0
+simulate dt = 0.01 steps = 100
+detect separation = distance(a, b)
+"#;
+
+fn run(name: &str, args: &[&str]) -> Output {
+    let path = write_program(name, PROGRAM);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("run")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+    output
+}
+
+fn json(output: Output) -> serde_json::Value {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_set_spring_constant_changes_distance() {
+    let soft = json(run("soft", &["--format", "json", "--set", "spring_k=2.0"]));
+    let stiff = json(run(
+        "stiff",
+        &["--format", "json", "--set", "spring_k=12.5"],
+    ));
+    assert_ne!(
+        soft["detectors"]["separation"],
+        stiff["detectors"]["separation"]
+    );
+}
+
+#[test]
+fn test_set_steps_and_dt() {
+    let result = json(run(
+        "steps",
+        &["--format", "json", "--set", "steps=10", "--set", "dt=0.02"],
+    ));
+    assert_eq!(result["steps"], 10);
+    let elapsed = result["elapsed_time"].as_f64().unwrap();
+    assert!((elapsed - 0.2).abs() < 1e-6);
+}
+
+#[test]
+fn test_set_unknown_name_lists_lets() {
+    let output = run("unknown", &["--set", "nope=1.0"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("unknown let binding 'nope' (available: spring_k)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_set_rejects_malformed_values() {
+    for arg in ["spring_k", "spring_k=soft", "steps=2.5", "steps=0"] {
+        let output = run("malformed", &["--set", arg]);
+        assert!(!output.status.success(), "'{}' should be rejected", arg);
+        assert!(output.stdout.is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+//! Tests for the VEL's loop and well status panel
+
+#[allow(dead_code)]
+#[path = "../src/status_panel.rs"]
+mod status_panel;
+
+use physlang_core::{build_simulation_context_from_source, step_simulation};
+use status_panel::{loop_statuses, well_statuses};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+loop for 2 cycles with frequency 1.0 damping 0.0 on b {
+    force push(b) magnitude 0.1 direction (1.0, 0.0)
+}
+loop while distance(a, b) < 10.0 with frequency 2.0 damping 0.0 on a {
+    force push(a) magnitude 0.1 direction (-1.0, 0.0)
+}
+well catch on a if position(a).y >= 1.0 depth 5.0
+simulate dt = 0.01 steps = 100
+";
+
+#[test]
+fn test_loop_statuses_describe_each_loop() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let statuses = loop_statuses(&ctx.loops, &ctx.world);
+    assert_eq!(statuses.len(), 2);
+
+    assert_eq!(statuses[0].title, "loop 0");
+    assert_eq!(statuses[0].kind, "for");
+    assert_eq!(statuses[0].target, "b");
+    assert_eq!(statuses[0].progress, "2 cycles remaining");
+    assert_eq!(statuses[0].phase, 0.0);
+    assert!(statuses[0].active);
+
+    assert_eq!(statuses[1].title, "loop 1");
+    assert_eq!(statuses[1].kind, "while");
+    assert_eq!(statuses[1].target, "a");
+    assert_eq!(statuses[1].frequency, 2.0);
+    assert_eq!(statuses[1].progress, "distance(a, b) < 10");
+
+    // A quarter second into a 1 Hz loop is a quarter of the way to the next cycle
+    for _ in 0..25 {
+        step_simulation(&mut ctx);
+    }
+    let phase = loop_statuses(&ctx.loops, &ctx.world)[0].phase;
+    assert!((phase - 0.25).abs() < 0.01, "{}", phase);
+}
+
+#[test]
+fn test_well_statuses_show_when_a_well_applies() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let statuses = well_statuses(&ctx.wells, &ctx.world);
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].name, "catch");
+    assert_eq!(statuses[0].particle, "a");
+    assert_eq!(statuses[0].observable, "position(a).y");
+    assert_eq!(statuses[0].threshold, 1.0);
+    assert!(!statuses[0].applying);
+
+    ctx.world.particles[0].pos.y = 2.0;
+    assert!(well_statuses(&ctx.wells, &ctx.world)[0].applying);
+}
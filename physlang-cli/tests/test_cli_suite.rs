@@ -0,0 +1,89 @@
+//! Tests for `physlang test` and the `.expect` format
+
+// Only the `.expect` parser is tested directly; the runner through the binary
+#[path = "../src/suite.rs"]
+#[allow(dead_code)]
+mod suite;
+
+use std::process::Command;
+use suite::{parse_expectations, Comparison};
+
+#[test]
+fn test_suite_summary_and_exit_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["test", "tests/fixtures/suite"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "PASS tests/fixtures/suite/spring_rest.phys (3 assertions)"
+    );
+    assert_eq!(lines[1], "FAIL tests/fixtures/suite/spring_stretched.phys");
+    assert!(
+        lines[2].starts_with("  line 2: gap > 4 (got "),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        lines[3],
+        "  line 4: energy ~= 1 +- 0.5 (no detector named 'energy')"
+    );
+    assert_eq!(
+        lines.last(),
+        Some(&"1 passed, 1 failed, 1 skipped (no .expect file)")
+    );
+}
+
+#[test]
+fn test_passing_file_exits_zero() {
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["test", "tests/fixtures/suite/spring_rest.phys"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_parse_expectations() {
+    let expectations = parse_expectations(
+        "# header\n\ngap ~= 2.5 +- 0.01  # close enough\nfinal_x == -3\nn <= 4\nn != 2\nn > 1e3\n",
+    )
+    .unwrap();
+    let parsed: Vec<(usize, &str, Comparison, f32)> = expectations
+        .iter()
+        .map(|e| (e.line, e.detector.as_str(), e.comparison, e.value))
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            (3, "gap", Comparison::Approx(0.01), 2.5),
+            (4, "final_x", Comparison::Equal, -3.0),
+            (5, "n", Comparison::LessEqual, 4.0),
+            (6, "n", Comparison::NotEqual, 2.0),
+            (7, "n", Comparison::Greater, 1000.0),
+        ]
+    );
+    assert!(expectations[0].holds(2.505));
+    assert!(!expectations[0].holds(2.52));
+    assert!(expectations[2].holds(4.0));
+}
+
+#[test]
+fn test_parse_errors_name_the_line() {
+    for (text, error) in [
+        ("gap = 2.0", "line 1: expected a comparison"),
+        ("\ngap ~= 2.0", "line 2: '~=' needs a tolerance"),
+        ("gap == 2.0 +- 0.1", "line 1: '+-' only goes with '~='"),
+        ("gap < far", "line 1: expected a number, got 'far'"),
+        ("== 2.0", "line 1: expected a detector name"),
+    ] {
+        let message = parse_expectations(text).unwrap_err();
+        assert!(message.starts_with(error), "{}: {}", text, message);
+    }
+}
@@ -0,0 +1,96 @@
+//! Tests for the checkpoint history behind the VEL's timeline
+
+#[path = "../src/timeline.rs"]
+mod timeline;
+
+use physlang_core::{build_simulation_context_from_source, step_simulation, SimulationContext};
+use timeline::Timeline;
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+loop for 5 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+simulate dt = 0.01 steps = 500
+";
+
+fn context() -> SimulationContext {
+    build_simulation_context_from_source(SOURCE).unwrap().0
+}
+
+/// Play `ctx` to `step`, recording into `timeline` like the VEL does
+fn play(ctx: &mut SimulationContext, timeline: &mut Timeline, step: usize) {
+    timeline.record(ctx);
+    while ctx.current_step < step {
+        step_simulation(ctx);
+        timeline.record(ctx);
+    }
+}
+
+fn hash_at(step: usize) -> u64 {
+    let mut ctx = context();
+    while ctx.current_step < step {
+        step_simulation(&mut ctx);
+    }
+    ctx.state_hash()
+}
+
+#[test]
+fn test_seek_back_shows_the_exact_step() {
+    let mut ctx = context();
+    let mut timeline = Timeline::new(50, 100);
+    play(&mut ctx, &mut timeline, 400);
+
+    assert!(timeline.seek(&mut ctx, 123));
+    assert_eq!(ctx.current_step, 123);
+    assert_eq!(ctx.state_hash(), hash_at(123));
+}
+
+#[test]
+fn test_seek_forward_replays_and_records() {
+    let mut ctx = context();
+    let mut timeline = Timeline::new(50, 100);
+    play(&mut ctx, &mut timeline, 100);
+
+    assert!(timeline.seek(&mut ctx, 0));
+    assert!(timeline.seek(&mut ctx, 321));
+    assert_eq!(ctx.state_hash(), hash_at(321));
+    // Replaying recorded checkpoints on the way, so going back is cheap again
+    assert!(timeline.seek(&mut ctx, 305));
+    assert_eq!(ctx.state_hash(), hash_at(305));
+}
+
+#[test]
+fn test_seek_stops_at_the_end_of_the_run() {
+    let mut ctx = context();
+    let mut timeline = Timeline::default();
+    play(&mut ctx, &mut timeline, 10);
+    assert!(timeline.seek(&mut ctx, 10_000));
+    assert_eq!(ctx.current_step, 500);
+}
+
+#[test]
+fn test_history_is_bounded() {
+    let mut ctx = context();
+    let mut timeline = Timeline::new(10, 5);
+    play(&mut ctx, &mut timeline, 200);
+
+    // Only the last five checkpoints (steps 160 to 200) are kept
+    assert_eq!(timeline.earliest(), Some(160));
+    assert!(!timeline.seek(&mut ctx, 100));
+    assert_eq!(ctx.current_step, 200);
+    assert!(timeline.seek(&mut ctx, 165));
+    assert_eq!(ctx.state_hash(), hash_at(165));
+}
+
+#[test]
+fn test_clear_forgets_the_history() {
+    let mut ctx = context();
+    let mut timeline = Timeline::new(10, 5);
+    play(&mut ctx, &mut timeline, 20);
+    timeline.clear();
+    assert_eq!(timeline.earliest(), None);
+    assert!(!timeline.seek(&mut ctx, 0));
+}
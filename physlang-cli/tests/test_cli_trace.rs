@@ -0,0 +1,100 @@
+//! Tests for `physlang run --trace`
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect separation = distance(a, b)
+"#;
+
+/// A path in the temp directory unique to this test and process
+fn temp_path(name: &str, extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "physlang_cli_trace_{}_{}.{}",
+        name,
+        std::process::id(),
+        extension
+    ))
+}
+
+/// Run PROGRAM with `args`, returning the output and the trace file contents
+fn run_traced(name: &str, args: &[&str]) -> (Output, Option<String>) {
+    let program = temp_path(name, "phys");
+    let trace = temp_path(name, "csv");
+    std::fs::write(&program, PROGRAM).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("run")
+        .arg(&program)
+        .arg("--trace")
+        .arg(&trace)
+        .args(args)
+        .output()
+        .unwrap();
+    let contents = std::fs::read_to_string(&trace).ok();
+    let _ = std::fs::remove_file(&program);
+    let _ = std::fs::remove_file(&trace);
+    (output, contents)
+}
+
+#[test]
+fn test_trace_every_25_steps() {
+    let (output, trace) = run_traced("every", &["--trace-every", "25"]);
+    assert!(output.status.success());
+
+    // Final detectors are still printed
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("separation = "), "{}", stdout);
+
+    let trace = trace.unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(lines[0], "step,time,a.x,a.y,a.vx,a.vy,b.x,b.y,b.vx,b.vy");
+
+    let rows: Vec<Vec<f32>> = lines[1..]
+        .iter()
+        .map(|line| line.split(',').map(|f| f.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(rows.len(), 5);
+    for (i, row) in rows.iter().enumerate() {
+        assert_eq!(row.len(), 10);
+        assert_eq!(row[0], (i * 25) as f32);
+        assert!((row[1] - i as f32 * 0.25).abs() < 1e-4);
+    }
+
+    // Step 0 holds the initial state
+    assert_eq!(&rows[0][2..], [0.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0]);
+    // The spring pulls the particles together symmetrically
+    assert!(rows[4][2] > 0.0);
+    assert!((rows[4][2] + rows[4][6] - 3.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_trace_defaults_to_every_step() {
+    let (output, trace) = run_traced("default", &[]);
+    assert!(output.status.success());
+    assert_eq!(trace.unwrap().lines().count(), 1 + 101);
+}
+
+#[test]
+fn test_trace_every_rejects_zero() {
+    let (output, trace) = run_traced("zero", &["--trace-every", "0"]);
+    assert!(!output.status.success());
+    assert!(trace.is_none());
+}
+
+#[test]
+fn test_trace_every_requires_trace() {
+    let program = temp_path("requires", "phys");
+    std::fs::write(&program, PROGRAM).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .arg("run")
+        .arg(&program)
+        .args(["--trace-every", "10"])
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&program);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,89 @@
+//! Tests for `physlang watch`
+
+mod common;
+#[path = "../src/debounce.rs"]
+mod debounce;
+
+use common::write_program;
+use debounce::Debouncer;
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 10
+detect separation = distance(a, b)
+"#;
+
+fn watch_once(name: &str, source: &str) -> Output {
+    let path = write_program(name, source);
+    let output = Command::new(env!("CARGO_BIN_EXE_physlang"))
+        .args(["watch", "--once"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    output
+}
+
+#[test]
+fn test_once_runs_and_exits() {
+    let output = watch_once("good", PROGRAM);
+    assert_eq!(output.status.code(), Some(0));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let header = stdout.lines().next().unwrap();
+    assert!(header.starts_with('['), "header: {}", header);
+    assert!(header.ends_with(".phys"), "header: {}", header);
+    assert!(stdout.contains("separation = "));
+}
+
+#[test]
+fn test_once_shows_errors_without_running() {
+    let output = watch_once("broken", "particle a at");
+    assert_eq!(output.status.code(), Some(2));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("error"));
+    assert!(!stdout.contains(" = "));
+}
+
+#[test]
+fn test_debounce_collapses_a_burst() {
+    let quiet = Duration::from_millis(100);
+    let start = Instant::now();
+    let mut debouncer = Debouncer::new(quiet);
+    assert_eq!(debouncer.deadline(), None);
+    assert!(!debouncer.ready(start));
+
+    // Three events 30 ms apart keep pushing the deadline back
+    for i in 0..3 {
+        let at = start + Duration::from_millis(30 * i);
+        debouncer.event(at);
+        assert!(!debouncer.ready(at + Duration::from_millis(20)));
+    }
+    let last = start + Duration::from_millis(60);
+    assert_eq!(debouncer.deadline(), Some(last + quiet));
+    assert!(!debouncer.ready(last + quiet - Duration::from_millis(1)));
+
+    // Fires once when the burst goes quiet, then not again
+    assert!(debouncer.ready(last + quiet));
+    assert!(!debouncer.ready(last + quiet * 2));
+    assert_eq!(debouncer.deadline(), None);
+}
+
+#[test]
+fn test_debounce_fires_again_for_a_later_burst() {
+    let quiet = Duration::from_millis(50);
+    let start = Instant::now();
+    let mut debouncer = Debouncer::new(quiet);
+
+    debouncer.event(start);
+    assert!(debouncer.ready(start + quiet));
+
+    let later = start + Duration::from_secs(1);
+    debouncer.event(later);
+    assert!(!debouncer.ready(later));
+    assert!(debouncer.ready(later + quiet));
+}
@@ -0,0 +1,226 @@
+//! Benchmarks for parsing, function expansion, context building, and stepping
+//!
+//! Run with `cargo bench -p physlang-core [-- <filter>]`. Inputs are
+//! generated here, so the repo carries no large fixtures. The 10k-particle
+//! stepping case takes minutes and only runs with `PHYSLANG_BENCH_HEAVY=1`.
+//!
+//! Each line shows the median time per iteration next to a baseline from
+//! the machine the suite was written on, so a regression stands out without
+//! keeping old runs around. Update a baseline when a change is meant to move it.
+
+use physlang_core::{
+    build_simulation_context, evaluate_lets, execute_functions, parse_program, step_simulation,
+    SimulationContext,
+};
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Stop sampling a case after this long, however few samples it has
+const TIME_PER_CASE: Duration = Duration::from_secs(3);
+
+/// Declarations for `count` particles in a row, joined by springs
+fn chain_source(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        writeln!(source, "particle p{} at ({}.0, 0.0) mass 1.0", i, i * 2).unwrap();
+    }
+    for i in 1..count {
+        writeln!(
+            source,
+            "force spring(p{}, p{}) k = 5.0 rest = 2.0",
+            i - 1,
+            i
+        )
+        .unwrap();
+    }
+    source.push_str("simulate dt = 0.01 steps = 1000\n");
+    source.push_str("detect first = position(p0)\n");
+    source
+}
+
+/// Declarations for `count` particles with no forces, loops or wells, so a
+/// step is little more than the per-step bookkeeping
+fn free_source(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        writeln!(source, "particle p{} at ({}.0, 0.0) mass 1.0", i, i * 2).unwrap();
+    }
+    source.push_str("simulate dt = 0.01 steps = 1000\n");
+    source
+}
+
+/// A program whose nested for loops generate a `side` × `side` grid. Loop
+/// bodies can't name particles uniquely yet, so the expanded program fails
+/// analysis; building a context from it still works
+fn grid_source(side: usize) -> String {
+    format!(
+        r#"fn row(y) {{
+    for i in 0..{side} {{
+        particle "cell" at (i, y) mass 1.0
+    }}
+}}
+for j in 0..{side} {{
+    row(j)
+}}
+simulate dt = 0.01 steps = 1000
+"#
+    )
+}
+
+/// A built context for `source`
+fn context(source: &str) -> SimulationContext {
+    let program = parse_program(source).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    build_simulation_context(&program, &eval_ctx).unwrap()
+}
+
+struct Runner {
+    filter: Option<String>,
+    heavy: bool,
+}
+
+impl Runner {
+    /// Time `routine` on fresh input from `setup` (not timed) until
+    /// `TIME_PER_CASE` runs out and print its median beside `baseline_ms`.
+    /// Light cases get a warm-up run and at least 3 samples; heavy ones
+    /// neither
+    fn bench<I, T>(
+        &self,
+        name: &str,
+        baseline_ms: f64,
+        heavy: bool,
+        mut setup: impl FnMut() -> I,
+        mut routine: impl FnMut(I) -> T,
+    ) {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|f| !name.contains(f.as_str()))
+        {
+            return;
+        }
+        if heavy && !self.heavy {
+            println!("{:<32} skipped (set PHYSLANG_BENCH_HEAVY=1)", name);
+            return;
+        }
+        let min_samples = if heavy { 1 } else { 3 };
+        if !heavy {
+            black_box(routine(setup()));
+        }
+        let started = Instant::now();
+        let mut samples = Vec::new();
+        while samples.len() < min_samples
+            || (started.elapsed() < TIME_PER_CASE && samples.len() < 100)
+        {
+            let input = setup();
+            let start = Instant::now();
+            black_box(routine(input));
+            samples.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        samples.sort_by(f64::total_cmp);
+        let median = samples[samples.len() / 2];
+        println!(
+            "{:<32} {:>10.3} ms  (min {:.3}, {} samples)  baseline {:.3} ms  {:+.0}%",
+            name,
+            median,
+            samples[0],
+            samples.len(),
+            baseline_ms,
+            (median / baseline_ms - 1.0) * 100.0
+        );
+    }
+}
+
+fn main() {
+    let runner = Runner {
+        filter: std::env::args().skip(1).find(|arg| !arg.starts_with('-')),
+        heavy: std::env::var_os("PHYSLANG_BENCH_HEAVY").is_some(),
+    };
+
+    let source = chain_source(5_000);
+    runner.bench(
+        "parse/5k_particles",
+        8.0,
+        false,
+        || (),
+        |()| parse_program(black_box(&source)).unwrap(),
+    );
+
+    let grid = parse_program(&grid_source(100)).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&grid.lets);
+    runner.bench(
+        "execute_functions/10k_particles",
+        2.1,
+        false,
+        || grid.clone(),
+        |mut program| {
+            execute_functions(&mut program, &eval_ctx);
+            program
+        },
+    );
+
+    let mut expanded = grid.clone();
+    execute_functions(&mut expanded, &eval_ctx);
+    assert_eq!(expanded.particles.len(), 10_000);
+    runner.bench(
+        "build_context/10k_particles",
+        0.79,
+        false,
+        || (),
+        |()| build_simulation_context(&expanded, &eval_ctx).unwrap(),
+    );
+
+    // Every particle visits every force, so stepping is quadratic in the
+    // chain length for now
+    for (count, baseline_ms, heavy) in [
+        (100, 21.0, false),
+        (1_000, 1_590.0, false),
+        (10_000, 176_000.0, true),
+    ] {
+        let name = format!("step_1000/{}_particles", count);
+        runner.bench(
+            &name,
+            baseline_ms,
+            heavy,
+            || context(&chain_source(count)),
+            |mut ctx| {
+                for _ in 0..1000 {
+                    step_simulation(&mut ctx);
+                }
+                ctx
+            },
+        );
+    }
+
+    // A world without loops or wells steps without their phases
+    let free = free_source(10_000);
+    runner.bench(
+        "step_1000/10k_free_particles",
+        96.0,
+        false,
+        || context(&free),
+        |mut ctx| {
+            for _ in 0..1000 {
+                step_simulation(&mut ctx);
+            }
+            ctx
+        },
+    );
+
+    // With only two particles the per-step bookkeeping is most of the cost;
+    // running the empty loop and well phases took about 5.3 ms here
+    let pair = free_source(2).replace("steps = 1000", "steps = 100000");
+    runner.bench(
+        "step_100k/2_free_particles",
+        4.8,
+        false,
+        || context(&pair),
+        |mut ctx| {
+            for _ in 0..100_000 {
+                step_simulation(&mut ctx);
+            }
+            ctx
+        },
+    );
+}
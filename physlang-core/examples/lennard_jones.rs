@@ -0,0 +1,60 @@
+//! Adding a Lennard-Jones force law from the host application
+//!
+//! Run with `cargo run -p physlang-core --example lennard_jones`
+
+use glam::Vec2;
+use physlang_core::{build_simulation_context_from_source, step_simulation, CustomForce, Particle};
+
+/// Pairwise Lennard-Jones potential V(r) = 4ε((σ/r)¹² − (σ/r)⁶) between all particles
+struct LennardJones {
+    epsilon: f32,
+    sigma: f32,
+}
+
+impl CustomForce for LennardJones {
+    fn accumulate(&self, particles: &[Particle], out: &mut [Vec2]) {
+        for i in 0..particles.len() {
+            for j in (i + 1)..particles.len() {
+                let r = particles[j].pos - particles[i].pos;
+                let dist = r.length();
+                if dist <= 0.0 {
+                    continue;
+                }
+                let sr6 = (self.sigma / dist).powi(6);
+                // -dV/dr: positive pushes the pair apart
+                let magnitude = 24.0 * self.epsilon * (2.0 * sr6 * sr6 - sr6) / dist;
+                let f = r / dist * magnitude;
+                out[i] -= f;
+                out[j] += f;
+            }
+        }
+    }
+}
+
+const SOURCE: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (1.5, 0.0) mass 1.0
+simulate dt = 0.001 steps = 5000
+"#;
+
+fn main() {
+    let (mut ctx, diagnostics) = build_simulation_context_from_source(SOURCE)
+        .unwrap_or_else(|e| panic!("failed to build example: {}", e));
+    for diagnostic in diagnostics.warnings() {
+        eprintln!("warning: {}", diagnostic.message);
+    }
+
+    ctx.world.add_custom_force(Box::new(LennardJones {
+        epsilon: 1.0,
+        sigma: 1.0,
+    }));
+
+    while !step_simulation(&mut ctx) {
+        if ctx.current_step % 500 == 0 {
+            let separation = ctx.world.particles[0]
+                .pos
+                .distance(ctx.world.particles[1].pos);
+            println!("t = {:.2}  separation = {:.4}", ctx.time, separation);
+        }
+    }
+}
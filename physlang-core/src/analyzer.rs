@@ -4,15 +4,30 @@
 //! errors before execution.
 
 use crate::ast::{
-    ConditionExpr, DetectorKind, Expr, FuncName, FunctionDecl, LetDecl, LoopKind,
-    ObservableExpr, Program, Stmt,
+    ConditionExpr, DetectorDecl, Expr, ForceDecl, ForceKind, FuncName, FunctionDecl, GroundDecl,
+    LetDecl, LoopBodyStmt, LoopDecl, LoopKind, ObservableExpr, ParticleDecl, Program,
+    SimulateDecl, Stmt, WellDecl,
 };
-use crate::diagnostics::{Diagnostic, Diagnostics, Span};
+use crate::diagnostics::{Diagnostic, Diagnostics};
+use crate::eval::{
+    evaluate_lets, observable_outside_detector, resolve_observables, try_const_fold,
+};
+use crate::phase;
+use crate::symbols::{SymbolTable, UnknownParticle};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::OnceLock;
 
 /// Analyze a program and return diagnostics
 pub fn analyze_program(program: &Program) -> Diagnostics {
-    let mut diagnostics = Diagnostics::new();
+    analyze_program_with_limit(program, Diagnostics::DEFAULT_CODE_LIMIT)
+}
+
+/// Analyze a program, keeping at most `code_limit` diagnostics with the same
+/// code (see `Diagnostics::with_code_limit`)
+pub fn analyze_program_with_limit(program: &Program, code_limit: usize) -> Diagnostics {
+    let _phase = phase::enter("analyze");
+    let mut diagnostics = Diagnostics::with_code_limit(code_limit);
 
     // Check let bindings: duplicate names
     let mut let_names = HashMap::new();
@@ -20,7 +35,7 @@ pub fn analyze_program(program: &Program) -> Diagnostics {
         if let_names.insert(let_decl.name.clone(), idx).is_some() {
             diagnostics.push(Diagnostic::error(
                 format!("duplicate let binding '{}'", let_decl.name),
-                None, // TODO: Add spans to AST
+                program.spans.lets.get(idx).copied(),
             ));
         }
     }
@@ -28,6 +43,7 @@ pub fn analyze_program(program: &Program) -> Diagnostics {
     // Check functions: duplicate names and name collisions
     let mut function_names = HashMap::new();
     for (idx, func_decl) in program.functions.iter().enumerate() {
+        let first = diagnostics.len();
         if function_names.insert(func_decl.name.clone(), idx).is_some() {
             diagnostics.push(Diagnostic::error(
                 format!("duplicate function name '{}'", func_decl.name),
@@ -53,40 +69,31 @@ pub fn analyze_program(program: &Program) -> Diagnostics {
                 ));
             }
         }
-        
+        diagnostics.locate_from(first, program.spans.functions.get(idx).copied());
     }
 
-    // Build environment for expression checking (needed for function body checks)
-    let env_lets: HashMap<String, &LetDecl> = program
-        .lets
-        .iter()
-        .map(|let_decl| (let_decl.name.clone(), let_decl))
-        .collect();
-    
-    // Build function map for call validation
-    let function_map: HashMap<String, &FunctionDecl> = program
-        .functions
-        .iter()
-        .map(|func| (func.name.clone(), func))
-        .collect();
-    
-    // Now check function bodies (after we have env_lets)
-    for func_decl in &program.functions {
-        let func_diagnostics = check_function_body(func_decl, &program.functions, &env_lets);
+    let env = AnalysisEnv::of_program(program);
+
+    // Now check function bodies (after we have the let bindings)
+    for (idx, func_decl) in program.functions.iter().enumerate() {
+        let first = diagnostics.len();
+        let func_diagnostics = check_function_body(func_decl, &program.functions, &env.lets);
         diagnostics.extend(func_diagnostics.into());
+        diagnostics.locate_from(first, program.spans.functions.get(idx).copied());
     }
     
     // Build a scope for top-level statements that includes global lets
     let mut top_level_scope: HashMap<String, ()> = HashMap::new();
-    for name in env_lets.keys() {
+    for name in env.lets.keys() {
         top_level_scope.insert(name.clone(), ());
     }
     
     // Check top-level statements (function calls and control flow)
-    for stmt in &program.top_level_calls {
+    for (idx, stmt) in program.top_level_calls.iter().enumerate() {
+        let first = diagnostics.len();
         match stmt {
             Stmt::ExprCall { name, args } => {
-                if let Some(func) = function_map.get(name) {
+                if let Some(func) = env.functions.get(name) {
                     if args.len() != func.params.len() {
                         diagnostics.push(Diagnostic::error(
                             format!(
@@ -98,6 +105,7 @@ pub fn analyze_program(program: &Program) -> Diagnostics {
                             None,
                         ));
                     }
+                    check_argument_types(func, args, &mut diagnostics);
                     // Check argument expressions
                     for arg in args {
                         let expr_diagnostics = check_expr_with_scope(arg, &top_level_scope);
@@ -107,209 +115,380 @@ pub fn analyze_program(program: &Program) -> Diagnostics {
                     diagnostics.push(Diagnostic::error(
                         format!("unknown function '{}'", name),
                         None,
-                    ));
+                    )
+                    .with_code("E0003"));
                 }
             }
             // v0.8: Check top-level control flow statements
             _ => {
-                let stmt_diagnostics = check_stmt(stmt, &function_map, &mut top_level_scope);
+                let stmt_diagnostics = check_stmt(stmt, &env.functions, &mut top_level_scope);
                 diagnostics.extend(stmt_diagnostics.into());
             }
         }
+        diagnostics.locate_from(first, program.spans.top_level_calls.get(idx).copied());
     }
 
     // Check all let expressions
-    for let_decl in &program.lets {
-        let expr_diagnostics = check_expr(&let_decl.expr, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
+    for (idx, let_decl) in program.lets.iter().enumerate() {
+        let first = diagnostics.len();
+        diagnostics.extend(check_let_decl(let_decl, &env));
+        diagnostics.locate_from(first, program.spans.lets.get(idx).copied());
     }
 
-    // Build particle name map for validation
-    let mut particle_names = std::collections::HashMap::new();
-    let mut particle_spans = std::collections::HashMap::<String, Option<Span>>::new();
-
     // Check 1: Unique particle names and validate expressions
     for (idx, particle) in program.particles.iter().enumerate() {
-        if particle_names.insert(particle.name.clone(), idx).is_some() {
-            diagnostics.push(Diagnostic::error(
-                format!("duplicate particle name '{}'", particle.name),
-                particle_spans.get(&particle.name).copied().flatten(),
-            ));
-        } else {
-            // Store span for first occurrence (we don't have spans in AST yet, so None for now)
-            particle_spans.insert(particle.name.clone(), None);
+        let first = diagnostics.len();
+        let span = program.spans.particles.get(idx).copied();
+        let first_idx = env.particles.resolve(&particle.name).map_or(idx, |id| id.index());
+        if first_idx != idx {
+            // Each duplicate points at itself; past the limit they are only
+            // counted
+            diagnostics.push_with_code("E0501", || {
+                Diagnostic::error(format!("duplicate particle name '{}'", particle.name), span)
+                    .with_note(format!(
+                        "references to '{}' resolve to its first declaration",
+                        particle.name
+                    ))
+            });
         }
-
-        // Check particle expressions
-        let expr_diagnostics = check_expr(&particle.position.0, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
-        let expr_diagnostics = check_expr(&particle.position.1, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
-        let expr_diagnostics = check_expr(&particle.mass, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
+        diagnostics.extend(check_particle_decl(particle, &env));
+        diagnostics.locate_from(first, span);
     }
 
     // Check 2: Forces reference existing particles and validate expressions
-    for force in &program.forces {
-        if !particle_names.contains_key(&force.a) {
-            diagnostics.push(Diagnostic::error(
-                format!("unknown particle '{}' in force", force.a),
-                None, // TODO: Add spans to AST
-            ));
+    for (idx, force) in program.forces.iter().enumerate() {
+        let first = diagnostics.len();
+        diagnostics.extend(check_force_decl(force, &env));
+        diagnostics.locate_from(first, program.spans.forces.get(idx).copied());
+    }
+
+    // Check 3: Loops reference existing particles and validate expressions
+    for (idx, loop_decl) in program.loops.iter().enumerate() {
+        let first = diagnostics.len();
+        diagnostics.extend(check_loop_decl(loop_decl, &env));
+        diagnostics.locate_from(first, program.spans.loops.get(idx).copied());
+    }
+
+    // Check 4: Wells reference existing particles and validate expressions
+    for (idx, well) in program.wells.iter().enumerate() {
+        let first = diagnostics.len();
+        diagnostics.extend(check_well_decl(well, &env));
+        diagnostics.locate_from(first, program.spans.wells.get(idx).copied());
+    }
+
+    // Check 4b: Ground expressions and friction
+    if let Some(ground) = &program.ground {
+        diagnostics.extend(check_ground_decl(ground, &env));
+    }
+
+    // Check 5: Detectors reference existing particles
+    for (idx, detector) in program.detectors.iter().enumerate() {
+        let first = diagnostics.len();
+        diagnostics.extend(check_detector_decl(detector, &env));
+        diagnostics.locate_from(first, program.spans.detectors.get(idx).copied());
+    }
+
+    // Check 6: Simulate block exists and is unique, and validate expressions
+    // This is already checked in the parser, but we verify here too
+    // (The parser ensures exactly one simulate block exists)
+    diagnostics.extend(check_simulate_decl(&program.simulate, &env));
+
+    // Check 7: An empty world is allowed, but almost certainly not intended
+    // (pending top-level calls may still generate particles)
+    if program.particles.is_empty() && program.top_level_calls.is_empty() {
+        diagnostics.push(Diagnostic::warning("simulation has no particles", None));
+    }
+
+    diagnostics
+}
+
+/// What a single declaration is checked against: the let bindings, functions
+/// and particles of a program
+///
+/// `analyze_program` runs the per-declaration checks below against the
+/// environment of the whole program; the REPL and the LSP can run them to
+/// check one new declaration without analyzing everything again.
+#[derive(Debug, Clone)]
+pub struct AnalysisEnv<'a> {
+    lets: HashMap<String, &'a LetDecl>,
+    /// The lets plus `time`, which force parameters may also read unless a
+    /// let shadows it
+    force_lets: HashMap<String, &'a LetDecl>,
+    /// Values of the lets, for the checks of constant expressions; let errors
+    /// are reported with the let expressions
+    constants: HashMap<&'a str, f32>,
+    functions: HashMap<String, &'a FunctionDecl>,
+    /// References resolve exactly as they will when the simulation is built
+    particles: SymbolTable,
+    /// The tags each particle declares, by particle index
+    tags: Vec<&'a [(String, Expr)]>,
+}
+
+impl<'a> AnalysisEnv<'a> {
+    /// The names `program` declares
+    pub fn of_program(program: &'a Program) -> Self {
+        let lets: HashMap<String, &LetDecl> = program
+            .lets
+            .iter()
+            .map(|let_decl| (let_decl.name.clone(), let_decl))
+            .collect();
+        let mut force_lets = lets.clone();
+        force_lets.entry("time".to_string()).or_insert(time_let());
+        let (let_ctx, _) = evaluate_lets(&program.lets);
+        Self {
+            lets,
+            force_lets,
+            constants: let_ctx.values,
+            functions: program
+                .functions
+                .iter()
+                .map(|func| (func.name.clone(), func))
+                .collect(),
+            particles: SymbolTable::of_program(program),
+            tags: program
+                .particles
+                .iter()
+                .map(|particle| particle.tags.as_slice())
+                .collect(),
         }
-        if !particle_names.contains_key(&force.b) {
+    }
+
+    /// Add a particle without tags after those of the program, e.g. one the
+    /// REPL accepted
+    pub fn declare_particle(&mut self, name: &str) {
+        self.particles.push(name);
+        self.tags.push(&[]);
+    }
+
+    /// The particles, in declaration order
+    pub fn particles(&self) -> &SymbolTable {
+        &self.particles
+    }
+
+    pub fn has_let(&self, name: &str) -> bool {
+        self.lets.contains_key(name)
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+
+/// The `time` a force parameter reads
+fn time_let() -> &'static LetDecl {
+    static TIME: OnceLock<LetDecl> = OnceLock::new();
+    TIME.get_or_init(|| LetDecl {
+        name: "time".to_string(),
+        expr: Expr::Literal(0.0),
+    })
+}
+
+/// Check a let binding's expression
+pub fn check_let_decl(let_decl: &LetDecl, env: &AnalysisEnv) -> Diagnostics {
+    check_expr(&let_decl.expr, &env.lets)
+}
+
+/// Check a particle's expressions, and that a constant mass or damping is
+/// not negative
+///
+/// Duplicate names are found by `analyze_program`, which knows the order of
+/// the declarations.
+pub fn check_particle_decl(particle: &ParticleDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    let expr_diagnostics = check_expr(&particle.position.0, &env.lets);
+    diagnostics.extend(expr_diagnostics.into());
+    let expr_diagnostics = check_expr(&particle.position.1, &env.lets);
+    diagnostics.extend(expr_diagnostics.into());
+    let expr_diagnostics = check_expr(&particle.mass, &env.lets);
+    diagnostics.extend(expr_diagnostics.into());
+    if let Some(value) = try_const_fold(&particle.mass, &env.constants).filter(|v| *v < 0.0) {
+        diagnostics.push(Diagnostic::error(
+            format!("particle '{}' has negative mass {}", particle.name, value),
+            None,
+        ));
+    }
+    if let Some(damping) = &particle.damping {
+        let expr_diagnostics = check_expr(damping, &env.lets);
+        diagnostics.extend(expr_diagnostics);
+        // Values that depend on function calls are checked when the simulation is built
+        if let Some(value) = try_const_fold(damping, &env.constants).filter(|v| *v < 0.0) {
             diagnostics.push(Diagnostic::error(
-                format!("unknown particle '{}' in force", force.b),
-                None, // TODO: Add spans to AST
+                format!("particle '{}' has negative damping {}", particle.name, value),
+                None,
             ));
         }
+    }
+    for (_, value) in &particle.tags {
+        diagnostics.extend(check_expr(value, &env.lets));
+    }
+    diagnostics
+}
 
-        // Check force expressions
-        match &force.kind {
-            crate::ast::ForceKind::Gravity { g } => {
-                let expr_diagnostics = check_expr(g, &env_lets);
-                diagnostics.extend(expr_diagnostics.into());
-            }
-            crate::ast::ForceKind::Spring { k, rest } => {
-                let expr_diagnostics = check_expr(k, &env_lets);
-                diagnostics.extend(expr_diagnostics.into());
-                let expr_diagnostics = check_expr(rest, &env_lets);
-                diagnostics.extend(expr_diagnostics.into());
+/// Check that a force references known particles, and its expressions
+pub fn check_force_decl(force: &ForceDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    check_particle(&env.particles, &force.a, "force", &mut diagnostics);
+    if force.b != force.a {
+        check_particle(&env.particles, &force.b, "force", &mut diagnostics);
+    }
+
+    // Check force expressions
+    match &force.kind {
+        ForceKind::Gravity { g } => {
+            let expr_diagnostics = check_expr(g, &env.force_lets);
+            diagnostics.extend(expr_diagnostics.into());
+        }
+        ForceKind::Spring { k, rest } => {
+            let expr_diagnostics = check_expr(k, &env.force_lets);
+            diagnostics.extend(expr_diagnostics.into());
+            let expr_diagnostics = check_expr(rest, &env.force_lets);
+            diagnostics.extend(expr_diagnostics.into());
+        }
+        ForceKind::Central { point, gm } => {
+            for expr in [&point.0, &point.1, gm] {
+                diagnostics.extend(check_expr(expr, &env.force_lets));
             }
         }
     }
+    diagnostics
+}
 
-    // Check 3: Loops reference existing particles and validate expressions
-    for loop_decl in &program.loops {
-        // Check target particle
-        let target = match &loop_decl.kind {
-            LoopKind::ForCycles { target, .. } => target,
-            LoopKind::WhileCondition { target, .. } => target,
-        };
-
-        if !particle_names.contains_key(target) {
-            diagnostics.push(Diagnostic::error(
-                format!("unknown particle '{}' in loop target", target),
-                None, // TODO: Add spans to AST
-            ));
+/// Check that a loop's target, condition and pushes reference known
+/// particles, and its expressions
+pub fn check_loop_decl(loop_decl: &LoopDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    let symbols = &env.particles;
+    let env_lets = &env.lets;
+    // Check target particle
+    let target = match &loop_decl.kind {
+        LoopKind::ForCycles { target, .. } => target,
+        LoopKind::WhileCondition { target, .. } => target,
+    };
+
+    check_particle(symbols, target, "loop target", &mut diagnostics);
+
+    // Check loop expressions
+    match &loop_decl.kind {
+        LoopKind::ForCycles {
+            cycles,
+            frequency,
+            damping,
+            ..
+        } => {
+            let expr_diagnostics = check_expr(cycles, env_lets);
+            diagnostics.extend(expr_diagnostics.into());
+            let expr_diagnostics = check_expr(frequency, env_lets);
+            diagnostics.extend(expr_diagnostics.into());
+            let expr_diagnostics = check_expr(damping, env_lets);
+            diagnostics.extend(expr_diagnostics.into());
         }
+        LoopKind::WhileCondition {
+            condition,
+            frequency,
+            damping,
+            ..
+        } => {
+            let expr_diagnostics = check_expr(frequency, env_lets);
+            diagnostics.extend(expr_diagnostics.into());
+            let expr_diagnostics = check_expr(damping, env_lets);
+            diagnostics.extend(expr_diagnostics.into());
+            check_observable_in_condition(condition, env, &mut diagnostics);
+            // Check condition threshold expressions
+            match condition {
+                ConditionExpr::LessThan(_, threshold) | ConditionExpr::GreaterThan(_, threshold) => {
+                    let expr_diagnostics = check_expr(threshold, env_lets);
+                    diagnostics.extend(expr_diagnostics.into());
+                }
+            }
+        }
+    }
 
-        // Check loop expressions
-        match &loop_decl.kind {
-            LoopKind::ForCycles {
-                cycles,
-                frequency,
-                damping,
-                ..
+    // Check loop body push targets and expressions
+    for stmt in &loop_decl.body {
+        match stmt {
+            LoopBodyStmt::ForcePush {
+                particle,
+                magnitude,
+                direction,
             } => {
-                let expr_diagnostics = check_expr(cycles, &env_lets);
+                check_particle(symbols, particle, "loop body push", &mut diagnostics);
+                let expr_diagnostics = check_expr(magnitude, env_lets);
                 diagnostics.extend(expr_diagnostics.into());
-                let expr_diagnostics = check_expr(frequency, &env_lets);
+                let expr_diagnostics = check_expr(&direction.0, env_lets);
                 diagnostics.extend(expr_diagnostics.into());
-                let expr_diagnostics = check_expr(damping, &env_lets);
+                let expr_diagnostics = check_expr(&direction.1, env_lets);
                 diagnostics.extend(expr_diagnostics.into());
             }
-            LoopKind::WhileCondition {
-                condition,
-                frequency,
-                damping,
-                ..
-            } => {
-                let expr_diagnostics = check_expr(frequency, &env_lets);
-                diagnostics.extend(expr_diagnostics.into());
-                let expr_diagnostics = check_expr(damping, &env_lets);
-                diagnostics.extend(expr_diagnostics.into());
-                check_observable_in_condition(condition, &particle_names, &mut diagnostics);
-                // Check condition threshold expressions
-                match condition {
-                    ConditionExpr::LessThan(_, threshold) | ConditionExpr::GreaterThan(_, threshold) => {
-                        let expr_diagnostics = check_expr(threshold, &env_lets);
-                        diagnostics.extend(expr_diagnostics.into());
-                    }
-                }
-            }
-        }
-
-        // Check loop body push targets and expressions
-        for stmt in &loop_decl.body {
-            match stmt {
-                crate::ast::LoopBodyStmt::ForcePush {
-                    particle,
-                    magnitude,
-                    direction,
-                } => {
-                    if !particle_names.contains_key(particle) {
-                        diagnostics.push(Diagnostic::error(
-                            format!("unknown particle '{}' in loop body push", particle),
-                            None, // TODO: Add spans to AST
-                        ));
-                    }
-                    let expr_diagnostics = check_expr(magnitude, &env_lets);
-                    diagnostics.extend(expr_diagnostics.into());
-                    let expr_diagnostics = check_expr(&direction.0, &env_lets);
-                    diagnostics.extend(expr_diagnostics.into());
-                    let expr_diagnostics = check_expr(&direction.1, &env_lets);
-                    diagnostics.extend(expr_diagnostics.into());
-                }
-            }
         }
     }
+    diagnostics
+}
 
-    // Check 4: Wells reference existing particles and validate expressions
-    for well in &program.wells {
-        if !particle_names.contains_key(&well.particle) {
-            diagnostics.push(Diagnostic::error(
-                format!("unknown particle '{}' in well", well.particle),
-                None, // TODO: Add spans to AST
-            ));
-        }
+/// Check that a well and its observable reference known particles, and its
+/// expressions
+pub fn check_well_decl(well: &WellDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    check_particle(&env.particles, &well.particle, "well", &mut diagnostics);
 
-        // Check observable in well
-        check_observable(&well.observable, &particle_names, &mut diagnostics);
+    // Check observable in well
+    check_observable(&well.observable, env, &mut diagnostics);
 
-        // Check well expressions
-        let expr_diagnostics = check_expr(&well.threshold, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
-        let expr_diagnostics = check_expr(&well.depth, &env_lets);
-        diagnostics.extend(expr_diagnostics.into());
-    }
+    // Check well expressions
+    let expr_diagnostics = check_expr(&well.threshold, &env.lets);
+    diagnostics.extend(expr_diagnostics.into());
+    let expr_diagnostics = check_expr(&well.depth, &env.lets);
+    diagnostics.extend(expr_diagnostics.into());
+    diagnostics
+}
 
-    // Check 5: Detectors reference existing particles
-    for detector in &program.detectors {
-        match &detector.kind {
-            DetectorKind::Position(name) => {
-                if !particle_names.contains_key(name) {
-                    diagnostics.push(Diagnostic::error(
-                        format!("unknown particle '{}' in detector", name),
-                        None, // TODO: Add spans to AST
-                    ));
-                }
-            }
-            DetectorKind::Distance { a, b } => {
-                if !particle_names.contains_key(a) {
-                    diagnostics.push(Diagnostic::error(
-                        format!("unknown particle '{}' in detector", a),
-                        None, // TODO: Add spans to AST
-                    ));
-                }
-                if !particle_names.contains_key(b) {
-                    diagnostics.push(Diagnostic::error(
-                        format!("unknown particle '{}' in detector", b),
-                        None, // TODO: Add spans to AST
-                    ));
-                }
-            }
-        }
+/// Check the ground's expressions, and that a constant friction coefficient
+/// is not negative
+pub fn check_ground_decl(ground: &GroundDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.extend(check_expr(&ground.y, &env.lets));
+    diagnostics.extend(check_expr(&ground.friction, &env.lets));
+    if let Some(gravity) = &ground.gravity {
+        diagnostics.extend(check_expr(gravity, &env.lets));
     }
+    if let Some(value) = try_const_fold(&ground.friction, &env.constants).filter(|v| *v < 0.0) {
+        diagnostics.push(Diagnostic::error(
+            format!("ground has negative friction {}", value),
+            None,
+        ));
+    }
+    diagnostics
+}
 
-    // Check 6: Simulate block exists and is unique, and validate expressions
-    // This is already checked in the parser, but we verify here too
-    // (The parser ensures exactly one simulate block exists)
-    let expr_diagnostics = check_expr(&program.simulate.dt, &env_lets);
-    diagnostics.extend(expr_diagnostics.into());
-    let expr_diagnostics = check_expr(&program.simulate.steps, &env_lets);
-    diagnostics.extend(expr_diagnostics.into());
+/// Check that a detector's observables reference known particles, and the
+/// arithmetic around them
+pub fn check_detector_decl(detector: &DetectorDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    let resolved = resolve_observables(&detector.expr, &mut |kind| {
+        for name in kind.particles() {
+            check_particle(&env.particles, name, "detector", &mut diagnostics);
+        }
+        Ok::<_, Infallible>(0.0)
+    });
+    let Ok(resolved) = resolved;
+    diagnostics.extend(check_expr(&resolved, &env.lets));
+    diagnostics
+}
 
+/// Check the `simulate` expressions, which may also call functions, and that
+/// a constant dt is positive
+pub fn check_simulate_decl(simulate: &SimulateDecl, env: &AnalysisEnv) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    for expr in [&simulate.dt, &simulate.steps] {
+        let expr_diagnostics = check_expr(expr, &env.lets);
+        diagnostics.extend(expr_diagnostics.into());
+        check_user_calls(expr, &env.functions, &mut diagnostics);
+    }
+    if let Some(dt) = try_const_fold(&simulate.dt, &env.constants).filter(|v| *v <= 0.0) {
+        diagnostics.push(Diagnostic::error(
+            format!("simulate dt must be positive, got {}", dt),
+            None,
+        ));
+    }
     diagnostics
 }
 
@@ -326,7 +505,8 @@ pub fn check_expr(expr: &Expr, env_lets: &HashMap<String, &LetDecl>) -> Diagnost
                 diagnostics.push(Diagnostic::error(
                     format!("unknown variable '{}'", name),
                     None, // TODO: Add spans to AST
-                ));
+                )
+                .with_code("E0001"));
             }
         }
         Expr::UnaryMinus(e) => {
@@ -379,52 +559,148 @@ pub fn check_expr(expr: &Expr, env_lets: &HashMap<String, &LetDecl>) -> Diagnost
         Expr::StringLiteral(_) => {
             // String literals are always valid
         }
+        Expr::Observable(kind) => {
+            // Detectors resolve their observables before checking the rest
+            diagnostics.push(Diagnostic::error(observable_outside_detector(kind), None));
+        }
     }
 
     diagnostics
 }
 
-/// Check an observable expression for valid particle references
-fn check_observable(
-    obs: &ObservableExpr,
-    particle_names: &std::collections::HashMap<String, usize>,
+/// Report the arguments of a call to `func` that don't match the types its
+/// parameters declare; unannotated parameters accept anything
+fn check_argument_types(func: &FunctionDecl, args: &[Expr], diagnostics: &mut Diagnostics) {
+    for (idx, arg) in args.iter().enumerate().take(func.params.len()) {
+        if let Some(message) = func.argument_mismatch(idx, arg) {
+            diagnostics.push(Diagnostic::error(message, None));
+        }
+    }
+}
+
+/// Report the calls in `expr` to functions that do not exist, or with the
+/// wrong number of arguments or argument types
+fn check_user_calls(
+    expr: &Expr,
+    function_map: &HashMap<String, &FunctionDecl>,
     diagnostics: &mut Diagnostics,
 ) {
+    match expr {
+        Expr::UserCall { name, args } => {
+            match function_map.get(name) {
+                Some(func) if args.len() != func.params.len() => {
+                    diagnostics.push(Diagnostic::error(
+                        format!(
+                            "function '{}' expects {} argument(s), got {}",
+                            name,
+                            func.params.len(),
+                            args.len()
+                        ),
+                        None,
+                    ));
+                }
+                Some(func) => check_argument_types(func, args, diagnostics),
+                None => diagnostics.push(
+                    Diagnostic::error(format!("unknown function '{}'", name), None)
+                        .with_code("E0003"),
+                ),
+            }
+            for arg in args {
+                check_user_calls(arg, function_map, diagnostics);
+            }
+        }
+        Expr::UnaryMinus(e) => check_user_calls(e, function_map, diagnostics),
+        Expr::Binary { left, right, .. } => {
+            check_user_calls(left, function_map, diagnostics);
+            check_user_calls(right, function_map, diagnostics);
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                check_user_calls(arg, function_map, diagnostics);
+            }
+        }
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Var(_) | Expr::Observable(_) => {}
+    }
+}
+
+/// Report `name` if it does not resolve to a particle, with the same message
+/// the simulation builder would fail with
+fn check_particle(
+    symbols: &SymbolTable,
+    name: &str,
+    place: &'static str,
+    diagnostics: &mut Diagnostics,
+) {
+    if let Err(unknown) = symbols.particle(name, place) {
+        diagnostics.push(unknown_particle(&unknown, symbols));
+    }
+}
+
+/// An unknown particle reference, listing the declared particles as help
+fn unknown_particle(unknown: &UnknownParticle, symbols: &SymbolTable) -> Diagnostic {
+    // A duplicated name is listed once, where it resolves to
+    let declared: Vec<&str> = symbols
+        .iter()
+        .filter(|&(id, name)| symbols.resolve(name) == Some(id))
+        .map(|(_, name)| name)
+        .collect();
+    let help = if declared.is_empty() {
+        "no particles are declared".to_string()
+    } else {
+        format!("declared particles: {}", declared.join(", "))
+    };
+    Diagnostic::error(unknown.to_string(), None)
+        .with_code("E0002")
+        .with_help(help)
+}
+
+/// Check an observable expression for valid particle and tag references
+fn check_observable(obs: &ObservableExpr, env: &AnalysisEnv, diagnostics: &mut Diagnostics) {
+    let symbols = &env.particles;
     match obs {
         ObservableExpr::PositionX(name) | ObservableExpr::PositionY(name) => {
-            if !particle_names.contains_key(name) {
-                diagnostics.push(Diagnostic::error(
-                    format!("unknown particle '{}' in observable", name),
-                    None, // TODO: Add spans to AST
-                ));
-            }
+            check_particle(symbols, name, "observable", diagnostics);
         }
         ObservableExpr::Distance(a, b) => {
-            if !particle_names.contains_key(a) {
-                diagnostics.push(Diagnostic::error(
-                    format!("unknown particle '{}' in distance observable", a),
-                    None, // TODO: Add spans to AST
-                ));
-            }
-            if !particle_names.contains_key(b) {
-                diagnostics.push(Diagnostic::error(
-                    format!("unknown particle '{}' in distance observable", b),
-                    None, // TODO: Add spans to AST
-                ));
-            }
+            check_particle(symbols, a, "distance observable", diagnostics);
+            check_particle(symbols, b, "distance observable", diagnostics);
         }
+        ObservableExpr::Mass(name) => {
+            check_particle(symbols, name, "mass observable", diagnostics);
+        }
+        ObservableExpr::Tag(name, tag) => match symbols.particle(name, "tag observable") {
+            Ok(id) => {
+                let tags = env.tags[id.index()];
+                if !tags.iter().any(|(declared, _)| declared == tag) {
+                    let help = if tags.is_empty() {
+                        format!("particle '{}' declares no tags", name)
+                    } else {
+                        let names: Vec<&str> = tags.iter().map(|(t, _)| t.as_str()).collect();
+                        format!("declared tags: {}", names.join(", "))
+                    };
+                    diagnostics.push(
+                        Diagnostic::error(
+                            format!("unknown tag '{}' on particle '{}'", tag, name),
+                            None,
+                        )
+                        .with_help(help),
+                    );
+                }
+            }
+            Err(unknown) => diagnostics.push(unknown_particle(&unknown, symbols)),
+        },
     }
 }
 
 /// Check observables in a condition expression
 fn check_observable_in_condition(
     condition: &ConditionExpr,
-    particle_names: &std::collections::HashMap<String, usize>,
+    env: &AnalysisEnv,
     diagnostics: &mut Diagnostics,
 ) {
     match condition {
         ConditionExpr::LessThan(obs, _) | ConditionExpr::GreaterThan(obs, _) => {
-            check_observable(obs, particle_names, diagnostics);
+            check_observable(obs, env, diagnostics);
         }
     }
 }
@@ -502,6 +778,7 @@ fn check_stmt(
                         None,
                     ));
                 }
+                check_argument_types(called_func, args, &mut diagnostics);
                 // Check argument expressions
                 for arg in args {
                     let expr_diagnostics = check_expr_with_scope(arg, scope);
@@ -511,7 +788,8 @@ fn check_stmt(
                 diagnostics.push(Diagnostic::error(
                     format!("unknown function '{}'", name),
                     None,
-                ));
+                )
+                .with_code("E0003"));
             }
         }
         Stmt::Return(expr) => {
@@ -527,6 +805,10 @@ fn check_stmt(
             diagnostics.extend(expr_diagnostics.into());
             let expr_diagnostics = check_expr_with_scope(&particle.mass, scope);
             diagnostics.extend(expr_diagnostics.into());
+            if let Some(damping) = &particle.damping {
+                let expr_diagnostics = check_expr_with_scope(damping, scope);
+                diagnostics.extend(expr_diagnostics);
+            }
         }
         Stmt::ForceDecl(force) => {
             match &force.kind {
@@ -540,6 +822,11 @@ fn check_stmt(
                     let expr_diagnostics = check_expr_with_scope(rest, scope);
                     diagnostics.extend(expr_diagnostics.into());
                 }
+                crate::ast::ForceKind::Central { point, gm } => {
+                    for expr in [&point.0, &point.1, gm] {
+                        diagnostics.extend(check_expr_with_scope(expr, scope));
+                    }
+                }
             }
         }
         Stmt::LoopDecl(loop_decl) => {
@@ -686,7 +973,8 @@ fn check_expr_with_scope(expr: &Expr, scope: &HashMap<String, ()>) -> Diagnostic
                 diagnostics.push(Diagnostic::error(
                     format!("unknown variable '{}'", name),
                     None,
-                ));
+                )
+                .with_code("E0001"));
             }
         }
         Expr::UnaryMinus(e) => {
@@ -730,6 +1018,9 @@ fn check_expr_with_scope(expr: &Expr, scope: &HashMap<String, ()>) -> Diagnostic
                 diagnostics.extend(arg_diagnostics.into());
             }
         }
+        Expr::Observable(kind) => {
+            diagnostics.push(Diagnostic::error(observable_outside_detector(kind), None));
+        }
     }
 
     diagnostics
@@ -1,9 +1,12 @@
+use crate::diagnostics::Span;
+
 // ============================================================================
 // v0.6: Expressions & Variables
 // ============================================================================
 
 /// Expression AST node
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     Literal(f32),
     /// String literal (for particle names in function calls)
@@ -25,10 +28,33 @@ pub enum Expr {
         name: String,
         args: Vec<Expr>,
     },
+    /// A quantity of the simulated world, such as `position(a).x`; only
+    /// detectors can read one
+    Observable(DetectorKind),
+}
+
+impl Expr {
+    /// The observables `self` reads, in the order written
+    pub fn observables(&self) -> Vec<&DetectorKind> {
+        match self {
+            Expr::Observable(kind) => vec![kind],
+            Expr::UnaryMinus(e) => e.observables(),
+            Expr::Binary { left, right, .. } => {
+                let mut kinds = left.observables();
+                kinds.extend(right.observables());
+                kinds
+            }
+            Expr::Call { args, .. } | Expr::UserCall { args, .. } => {
+                args.iter().flat_map(Expr::observables).collect()
+            }
+            Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Var(_) => Vec::new(),
+        }
+    }
 }
 
 /// Binary operators
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOp {
     Add,
     Sub,
@@ -44,7 +70,8 @@ pub enum BinaryOp {
 }
 
 /// Built-in function names
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FuncName {
     Sin,
     Cos,
@@ -53,7 +80,8 @@ pub enum FuncName {
 }
 
 /// Let binding declaration: `let name = expr`
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LetDecl {
     pub name: String,
     pub expr: Expr,
@@ -64,15 +92,53 @@ pub struct LetDecl {
 // ============================================================================
 
 /// Function declaration: `fn name(params) { body }`
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionDecl {
     pub name: String,
     pub params: Vec<String>,
+    /// Declared type of each parameter; `None` (or missing) accepts either
+    #[cfg_attr(feature = "ast-serde", serde(default))]
+    pub param_types: Vec<Option<ParamType>>,
     pub body: Vec<Stmt>,
 }
 
+impl FunctionDecl {
+    /// The declared type of parameter `idx`, if it has one
+    pub fn param_type(&self, idx: usize) -> Option<ParamType> {
+        self.param_types.get(idx).copied().flatten()
+    }
+
+    /// Why `arg` can't be passed as parameter `idx`, if it can't
+    pub fn argument_mismatch(&self, idx: usize, arg: &Expr) -> Option<String> {
+        let expected = self.param_type(idx)?;
+        let is_name = matches!(arg, Expr::StringLiteral(_));
+        if is_name == (expected == ParamType::Name) {
+            return None;
+        }
+        Some(format!(
+            "call to '{}' passes {} to parameter '{}: {}'",
+            self.name,
+            if is_name { "a particle name" } else { "a number" },
+            self.params[idx],
+            crate::capabilities::param_type_keyword(&expected)
+        ))
+    }
+}
+
+/// Type annotation of a function parameter: `fn probe(p: name, k: number)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParamType {
+    /// A particle name, passed as a string literal
+    Name,
+    /// A numeric expression
+    Number,
+}
+
 /// Statement AST node
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
     /// Local let binding: `let name = expr`
     Let {
@@ -114,7 +180,8 @@ pub enum Stmt {
 }
 
 /// A PhysLang program AST
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
     pub lets: Vec<LetDecl>,          // v0.6
     pub functions: Vec<FunctionDecl>, // v0.7
@@ -125,18 +192,78 @@ pub struct Program {
     pub detectors: Vec<DetectorDecl>,
     pub loops: Vec<LoopDecl>,      // v0.2
     pub wells: Vec<WellDecl>,       // v0.2
+    pub ground: Option<GroundDecl>, // at most one
+    /// Where the declarations above were written
+    #[cfg_attr(feature = "ast-serde", serde(default))]
+    pub spans: DeclSpans,
+}
+
+/// Source spans of top-level declarations, by index into the matching
+/// `Program` field. Declarations built by hand or generated by functions
+/// have none, so look spans up with `get`; `simulate` and `ground` are
+/// single declarations, so their spans are optional instead
+///
+/// Spans say where a declaration was written, not what it means: they're
+/// ignored when comparing programs, so re-indenting a file changes nothing.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeclSpans {
+    pub lets: Vec<Span>,
+    pub functions: Vec<Span>,
+    pub top_level_calls: Vec<Span>,
+    pub particles: Vec<Span>,
+    pub forces: Vec<Span>,
+    pub detectors: Vec<Span>,
+    pub loops: Vec<Span>,
+    pub wells: Vec<Span>,
+    pub simulate: Option<Span>,
+    pub ground: Option<Span>,
+}
+
+impl PartialEq for DeclSpans {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "ast-serde")]
+impl Program {
+    /// The program as JSON, to cache it or hand it to another process;
+    /// `from_json` rebuilds it exactly
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Rebuild a program from the JSON of `to_json`, or an equivalent one
+    /// written by another tool
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Ground declaration: `ground y = h friction mu [gravity g]`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroundDecl {
+    pub y: Expr,
+    pub friction: Expr,
+    pub gravity: Option<Expr>, // uniform downward pull, 0 if omitted
 }
 
-/// Particle declaration: `particle name at (x, y) mass m`
-#[derive(Debug, Clone)]
+/// Particle declaration: `particle name at (x, y) mass m [damping c] [tag name = v]...`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParticleDecl {
     pub name: String,
     pub position: (Expr, Expr), // v0.6: x, y as expressions
     pub mass: Expr,             // v0.6: mass as expression
+    pub damping: Option<Expr>,  // optional `damping c` clause
+    pub tags: Vec<(String, Expr)>, // `tag name = v` clauses, in source order
 }
 
-/// Force declaration: `force kind(a, b) params...`
-#[derive(Debug, Clone)]
+/// Force declaration: `force kind(a, b) params...` or `force central(a) at (x, y) GM = m`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ForceDecl {
     pub a: String,
     pub b: String,
@@ -144,31 +271,106 @@ pub struct ForceDecl {
 }
 
 /// Force kinds
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ForceKind {
     Gravity { g: Expr },           // v0.6: expression
     Spring { k: Expr, rest: Expr }, // v0.6: expressions
+    Central { point: (Expr, Expr), gm: Expr }, // fixed point mass; `b` repeats `a`
 }
 
 /// Simulation configuration: `simulate dt = x steps = n`
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimulateDecl {
     pub dt: Expr,     // v0.6: expression
     pub steps: Expr,  // v0.6: expression (will be coerced to usize)
+    pub frame: Frame, // optional `frame = ... [recenter = ...]`
 }
 
-/// Detector declaration: `detect name = kind(...)`
-#[derive(Debug, Clone)]
+/// Reference frame chosen with `simulate ... frame = lab|center_of_mass`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Frame {
+    /// Positions and velocities as declared
+    #[default]
+    Lab,
+    /// Center of mass held at rest at the origin
+    CenterOfMass { recenter: Recenter },
+}
+
+/// When the center-of-mass frame is applied: `recenter = once|always`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Recenter {
+    /// At t = 0 only
+    #[default]
+    Once,
+    /// Before every step
+    Always,
+}
+
+/// Detector declaration: `detect name = expr`, where `expr` reads observables
+/// such as `position(a).x`, with a trailing `initial` to also report its
+/// value before the first step
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DetectorDecl {
     pub name: String,
-    pub kind: DetectorKind,
+    pub expr: Expr,
+    #[cfg_attr(feature = "ast-serde", serde(default))]
+    pub initial: bool,
+}
+
+impl DetectorDecl {
+    /// The observable a detector reads when its expression is just that
+    /// observable, as every detector was written before they took expressions
+    pub fn kind(&self) -> Option<&DetectorKind> {
+        match &self.expr {
+            Expr::Observable(kind) => Some(kind),
+            _ => None,
+        }
+    }
 }
 
-/// Detector kinds
-#[derive(Debug, Clone)]
+/// Detector kinds: the observables of the world a detector can read
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DetectorKind {
-    Position(String), // particle name
+    Position(String), // particle name; x coordinate
+    PositionY(String),
+    VelocityX(String),
+    VelocityY(String),
     Distance { a: String, b: String },
+    KineticEnergy,
+    PotentialEnergy,
+    TotalEnergy,
+    Momentum,     // magnitude of total momentum
+    CenterOfMass, // x coordinate, like Position
+    CenterOfMassY,
+    Time,         // simulated time
+    CollisionCount { a: String, b: String }, // collisions between a and b so far
+}
+
+impl DetectorKind {
+    /// The particles this observable reads, by name
+    pub fn particles(&self) -> Vec<&str> {
+        match self {
+            DetectorKind::Position(name)
+            | DetectorKind::PositionY(name)
+            | DetectorKind::VelocityX(name)
+            | DetectorKind::VelocityY(name) => vec![name],
+            DetectorKind::Distance { a, b } | DetectorKind::CollisionCount { a, b } => vec![a, b],
+            // Whole-world observables read no particles
+            DetectorKind::KineticEnergy
+            | DetectorKind::PotentialEnergy
+            | DetectorKind::TotalEnergy
+            | DetectorKind::Momentum
+            | DetectorKind::CenterOfMass
+            | DetectorKind::CenterOfMassY
+            | DetectorKind::Time => Vec::new(),
+        }
+    }
 }
 
 // ============================================================================
@@ -176,7 +378,8 @@ pub enum DetectorKind {
 // ============================================================================
 
 /// Match pattern for match statements
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MatchPattern {
     /// Integer literal pattern
     Literal(i64),
@@ -185,7 +388,8 @@ pub enum MatchPattern {
 }
 
 /// Match arm: pattern => body
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MatchArm {
     pub pattern: MatchPattern,
     pub body: Vec<Stmt>,
@@ -196,7 +400,8 @@ pub struct MatchArm {
 // ============================================================================
 
 /// Loop declaration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LoopDecl {
     pub name: Option<String>,         // optional loop label, v0.2 can ignore
     pub kind: LoopKind,
@@ -204,7 +409,8 @@ pub struct LoopDecl {
 }
 
 /// Loop kinds
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopKind {
     ForCycles {
         cycles: Expr,     // v0.6: expression (must evaluate to integer >= 0)
@@ -221,7 +427,8 @@ pub enum LoopKind {
 }
 
 /// Loop body statements (v0.2, minimal)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopBodyStmt {
     ForcePush {
         particle: String,
@@ -231,22 +438,27 @@ pub enum LoopBodyStmt {
 }
 
 /// Condition expressions for while-loops
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConditionExpr {
     LessThan(ObservableExpr, Expr),   // v0.6: threshold as expression
     GreaterThan(ObservableExpr, Expr), // v0.6: threshold as expression
 }
 
-/// Observable expressions (positions, distances)
-#[derive(Debug, Clone)]
+/// Observable expressions (positions, distances, masses, tags)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ObservableExpr {
     PositionX(String),          // position(a).x
     PositionY(String),          // position(a).y
     Distance(String, String),   // distance(a,b)
+    Mass(String),               // mass(a)
+    Tag(String, String),        // tag(a, name)
 }
 
 /// Potential well declaration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ast-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WellDecl {
     pub name: String,
     pub particle: String,
@@ -0,0 +1,146 @@
+//! What this build of the language supports
+//!
+//! Tools that generate or accept programs (editors, the WASM playground,
+//! hosts of the C API) ask `language_capabilities` instead of guessing from
+//! a version number. The keyword lists come from exhaustive matches over the
+//! AST enums, so a new variant doesn't compile until it's listed here.
+
+use crate::ast::{
+    BinaryOp, DetectorKind, ForceKind, FuncName, LoopKind, ObservableExpr, ParamType, Stmt,
+};
+use crate::integrator::Integrator;
+use serde::Serialize;
+
+/// Version of the language this crate implements
+pub const LANGUAGE_VERSION: &str = "0.8";
+
+/// The language features this build supports, by their source keywords
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub language_version: &'static str,
+    /// Version of physlang-core
+    pub crate_version: &'static str,
+    pub forces: Vec<&'static str>,
+    pub detectors: Vec<&'static str>,
+    pub builtin_functions: Vec<&'static str>,
+    pub operators: Vec<&'static str>,
+    /// Statements allowed in function bodies
+    pub statements: Vec<&'static str>,
+    /// Declarations allowed only at the top level
+    pub top_level: Vec<&'static str>,
+    pub loops: Vec<&'static str>,
+    /// What wells and while-loop conditions can observe
+    pub observables: Vec<&'static str>,
+    pub integrators: Vec<&'static str>,
+    /// Annotations a function parameter can declare
+    pub parameter_types: Vec<&'static str>,
+}
+
+/// Define `$name`, the keyword of each variant of `$ty`, and `$all`, every
+/// keyword in the order of the match arms
+macro_rules! keywords {
+    ($name:ident, $all:ident, $ty:ty { $($pattern:pat => $keyword:literal,)* }) => {
+        pub fn $name(value: &$ty) -> &'static str {
+            match value {
+                $($pattern => $keyword,)*
+            }
+        }
+
+        const $all: &[&str] = &[$($keyword),*];
+    };
+}
+
+keywords!(force_keyword, FORCES, ForceKind {
+    ForceKind::Gravity { .. } => "gravity",
+    ForceKind::Spring { .. } => "spring",
+    ForceKind::Central { .. } => "central",
+});
+
+keywords!(detector_keyword, DETECTORS, DetectorKind {
+    DetectorKind::Position(_) => "position",
+    DetectorKind::PositionY(_) => "position_y",
+    DetectorKind::VelocityX(_) => "velocity_x",
+    DetectorKind::VelocityY(_) => "velocity_y",
+    DetectorKind::Distance { .. } => "distance",
+    DetectorKind::KineticEnergy => "kinetic_energy",
+    DetectorKind::PotentialEnergy => "potential_energy",
+    DetectorKind::TotalEnergy => "total_energy",
+    DetectorKind::Momentum => "momentum",
+    DetectorKind::CenterOfMass => "center_of_mass",
+    DetectorKind::CenterOfMassY => "center_of_mass_y",
+    DetectorKind::Time => "time",
+    DetectorKind::CollisionCount { .. } => "collision_count",
+});
+
+keywords!(builtin_keyword, BUILTINS, FuncName {
+    FuncName::Sin => "sin",
+    FuncName::Cos => "cos",
+    FuncName::Sqrt => "sqrt",
+    FuncName::Clamp => "clamp",
+});
+
+keywords!(operator_symbol, OPERATORS, BinaryOp {
+    BinaryOp::Add => "+",
+    BinaryOp::Sub => "-",
+    BinaryOp::Mul => "*",
+    BinaryOp::Div => "/",
+    BinaryOp::GreaterThan => ">",
+    BinaryOp::LessThan => "<",
+    BinaryOp::GreaterEqual => ">=",
+    BinaryOp::LessEqual => "<=",
+    BinaryOp::Equal => "==",
+    BinaryOp::NotEqual => "!=",
+});
+
+keywords!(statement_keyword, STATEMENTS, Stmt {
+    Stmt::Let { .. } => "let",
+    Stmt::ExprCall { .. } => "call",
+    Stmt::ParticleDecl(_) => "particle",
+    Stmt::ForceDecl(_) => "force",
+    Stmt::LoopDecl(_) => "loop",
+    Stmt::WellDecl(_) => "well",
+    Stmt::DetectorDecl(_) => "detect",
+    Stmt::Return(_) => "return",
+    Stmt::If { .. } => "if",
+    Stmt::For { .. } => "for",
+    Stmt::Match { .. } => "match",
+});
+
+keywords!(loop_keyword, LOOPS, LoopKind {
+    LoopKind::ForCycles { .. } => "for_cycles",
+    LoopKind::WhileCondition { .. } => "while",
+});
+
+keywords!(observable_keyword, OBSERVABLES, ObservableExpr {
+    ObservableExpr::PositionX(_) => "position_x",
+    ObservableExpr::PositionY(_) => "position_y",
+    ObservableExpr::Distance(..) => "distance",
+    ObservableExpr::Mass(_) => "mass",
+    ObservableExpr::Tag(..) => "tag",
+});
+
+keywords!(param_type_keyword, PARAM_TYPES, ParamType {
+    ParamType::Name => "name",
+    ParamType::Number => "number",
+});
+
+/// Fields of `Program` with no `Stmt` counterpart
+const TOP_LEVEL: &[&str] = &["fn", "simulate", "ground"];
+
+/// Everything this build of the language supports
+pub fn language_capabilities() -> Capabilities {
+    Capabilities {
+        language_version: LANGUAGE_VERSION,
+        crate_version: env!("CARGO_PKG_VERSION"),
+        forces: FORCES.to_vec(),
+        detectors: DETECTORS.to_vec(),
+        builtin_functions: BUILTINS.to_vec(),
+        operators: OPERATORS.to_vec(),
+        statements: STATEMENTS.to_vec(),
+        top_level: TOP_LEVEL.to_vec(),
+        loops: LOOPS.to_vec(),
+        observables: OBSERVABLES.to_vec(),
+        integrators: Integrator::ALL.iter().map(|i| i.name()).collect(),
+        parameter_types: PARAM_TYPES.to_vec(),
+    }
+}
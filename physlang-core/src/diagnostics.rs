@@ -3,8 +3,11 @@
 //! This module provides utilities for reporting parse errors,
 //! runtime errors, and static analysis diagnostics.
 
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
 /// A span in the source code (byte offsets)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize, // byte offset
     pub end: usize,
@@ -50,39 +53,85 @@ pub struct SourceLocation {
 }
 
 /// Diagnostic severity
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DiagnosticSeverity {
     Error,
     Warning,
 }
 
 /// A diagnostic message with location information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Diagnostic {
     pub severity: DiagnosticSeverity,
     pub message: String,
     pub span: Option<Span>,
+    /// Error code from the table in docs/semantics.md, e.g. `E0002`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    /// Extra `note:` lines shown below the source snippet
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// A `help:` line suggesting a fix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// An edit to the source that resolves the diagnostic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<Fix>,
+}
+
+/// Replace the text at `span` with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fix {
+    pub span: Span,
+    pub replacement: String,
+    /// Safe to apply without review, as `physlang check --fix` does
+    pub machine_applicable: bool,
 }
 
 impl Diagnostic {
     /// Create an error diagnostic
     pub fn error(message: impl Into<String>, span: Option<Span>) -> Self {
-        Self {
-            severity: DiagnosticSeverity::Error,
-            message: message.into(),
-            span,
-        }
+        Self::new(DiagnosticSeverity::Error, message.into(), span)
     }
 
     /// Create a warning diagnostic
     pub fn warning(message: impl Into<String>, span: Option<Span>) -> Self {
+        Self::new(DiagnosticSeverity::Warning, message.into(), span)
+    }
+
+    fn new(severity: DiagnosticSeverity, message: String, span: Option<Span>) -> Self {
         Self {
-            severity: DiagnosticSeverity::Warning,
-            message: message.into(),
+            severity,
+            message,
             span,
+            code: None,
+            notes: Vec::new(),
+            help: None,
+            fix: None,
         }
     }
 
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
     /// Convert span to source location if available
     pub fn location(&self, source: &str) -> Option<SourceLocation> {
         self.span.map(|s| s.to_location(source))
@@ -90,24 +139,120 @@ impl Diagnostic {
 }
 
 /// Collection of diagnostics
+///
+/// At most `code_limit` diagnostics with the same code are kept; the rest are
+/// counted in one summary entry, so a generated program that repeats a
+/// mistake thousands of times reports it a readable number of times
 #[derive(Debug, Clone)]
 pub struct Diagnostics {
     diagnostics: Vec<Diagnostic>,
+    code_limit: usize,
+    codes: HashMap<&'static str, CodeCount>,
+}
+
+/// How many diagnostics with one code were kept and suppressed
+#[derive(Debug, Clone)]
+struct CodeCount {
+    reported: usize,
+    /// Severity of the last one reported
+    severity: DiagnosticSeverity,
+    suppressed: usize,
+    /// Index of the summary entry, once something was suppressed
+    summary: Option<usize>,
+}
+
+impl CodeCount {
+    fn new() -> Self {
+        Self {
+            reported: 0,
+            severity: DiagnosticSeverity::Error,
+            suppressed: 0,
+            summary: None,
+        }
+    }
 }
 
 impl Diagnostics {
+    /// Diagnostics with the same code kept unless configured otherwise
+    pub const DEFAULT_CODE_LIMIT: usize = 50;
+
     pub fn new() -> Self {
+        Self::with_code_limit(Self::DEFAULT_CODE_LIMIT)
+    }
+
+    /// Keep at most `limit` diagnostics with the same code (at least one)
+    pub fn with_code_limit(limit: usize) -> Self {
         Self {
             diagnostics: Vec::new(),
+            code_limit: limit.max(1),
+            codes: HashMap::new(),
         }
     }
 
+    pub fn code_limit(&self) -> usize {
+        self.code_limit
+    }
+
     pub fn push(&mut self, diagnostic: Diagnostic) {
-        self.diagnostics.push(diagnostic);
+        let Some(code) = diagnostic.code else {
+            self.diagnostics.push(diagnostic);
+            return;
+        };
+        let count = self.codes.entry(code).or_insert_with(CodeCount::new);
+        if count.reported < self.code_limit {
+            count.reported += 1;
+            count.severity = diagnostic.severity;
+            self.diagnostics.push(diagnostic);
+        } else {
+            self.suppress(code, diagnostic.severity, diagnostic.span);
+        }
+    }
+
+    /// Push the diagnostic `make` builds with `code`, only building it while
+    /// diagnostics with that code are under the limit
+    ///
+    /// Lets a check that may fire for every generated declaration stop
+    /// formatting messages once they would be suppressed anyway
+    pub fn push_with_code(&mut self, code: &'static str, make: impl FnOnce() -> Diagnostic) {
+        let count = self.codes.entry(code).or_insert_with(CodeCount::new);
+        if count.reported < self.code_limit {
+            self.push(make().with_code(code));
+        } else {
+            // Suppressed diagnostics share the severity of the ones reported
+            let severity = count.severity;
+            self.suppress(code, severity, None);
+        }
+    }
+
+    /// Count one more suppressed diagnostic with `code` in its summary entry
+    fn suppress(&mut self, code: &'static str, severity: DiagnosticSeverity, span: Option<Span>) {
+        let count = self.codes.entry(code).or_insert_with(CodeCount::new);
+        count.suppressed += 1;
+        let message = format!(
+            "{} more {} {} suppressed",
+            thousands(count.suppressed),
+            describe_code(code).unwrap_or(code),
+            match (severity, count.suppressed) {
+                (DiagnosticSeverity::Error, 1) => "error",
+                (DiagnosticSeverity::Error, _) => "errors",
+                (DiagnosticSeverity::Warning, 1) => "warning",
+                (DiagnosticSeverity::Warning, _) => "warnings",
+            }
+        );
+        match count.summary {
+            Some(index) => self.diagnostics[index].message = message,
+            None => {
+                // The summary points at the first diagnostic it stands for
+                count.summary = Some(self.diagnostics.len());
+                self.diagnostics.push(Diagnostic::new(severity, message, span));
+            }
+        }
     }
 
     pub fn extend(&mut self, other: Diagnostics) {
-        self.diagnostics.extend(other.diagnostics);
+        for diagnostic in other.diagnostics {
+            self.push(diagnostic);
+        }
     }
 
     pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
@@ -130,6 +275,20 @@ impl Diagnostics {
         self.diagnostics.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Point the diagnostics from index `start` on that have no span at
+    /// `span`, e.g. the declaration they were found in
+    pub(crate) fn locate_from(&mut self, start: usize, span: Option<Span>) {
+        for diagnostic in &mut self.diagnostics[start..] {
+            if diagnostic.span.is_none() {
+                diagnostic.span = span;
+            }
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
         self.diagnostics.iter()
     }
@@ -143,8 +302,37 @@ impl Default for Diagnostics {
 
 impl From<Vec<Diagnostic>> for Diagnostics {
     fn from(diagnostics: Vec<Diagnostic>) -> Self {
-        Self { diagnostics }
+        let mut collected = Self::new();
+        for diagnostic in diagnostics {
+            collected.push(diagnostic);
+        }
+        collected
+    }
+}
+
+/// What a diagnostic code reports, from the table in docs/semantics.md, for
+/// the codes the analyzer emits
+fn describe_code(code: &str) -> Option<&'static str> {
+    match code {
+        "E0001" => Some("unknown variable"),
+        "E0002" => Some("unknown particle"),
+        "E0003" => Some("unknown function"),
+        "E0501" => Some("duplicate particle name"),
+        _ => None,
+    }
+}
+
+/// `n` with commas between groups of three digits, e.g. `9,949`
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
     }
+    out
 }
 
 impl IntoIterator for Diagnostics {
@@ -156,26 +344,146 @@ impl IntoIterator for Diagnostics {
     }
 }
 
+impl From<&crate::parser::ParseError> for Diagnostic {
+    fn from(error: &crate::parser::ParseError) -> Self {
+        Diagnostic::error(error.to_string(), error.span())
+    }
+}
+
 /// Format a parse error with context from the source code
 pub fn format_parse_error(error: &crate::parser::ParseError, source: &str) -> String {
-    let mut msg = format!("Parse error: {}", error);
-    
-    if let Some(span) = error.span() {
-        let location = span.to_location(source);
-        msg.push_str(&format!("\n  at line {}, column {}", location.line, location.column));
-        
-        // Try to show the line
-        let lines: Vec<&str> = source.lines().collect();
-        if location.line > 0 && location.line <= lines.len() {
-            msg.push_str(&format!("\n  {}", lines[location.line - 1]));
-            if location.column > 0 {
-                let caret = " ".repeat(location.column.saturating_sub(1)) + "^";
-                msg.push_str(&format!("\n  {}", caret));
-            }
+    render_diagnostic(&Diagnostic::from(error), source, None, false)
+}
+
+/// Columns a tab advances when source lines are displayed
+const TAB_WIDTH: usize = 4;
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+/// Render a diagnostic in the style of rustc: an `error[CODE]: message` header,
+/// a `-->` line with the path and line:col, the source line in a numbered gutter
+/// with the span underlined, then any notes and help
+///
+/// `color` adds ANSI escapes; tabs in the source line are expanded so the
+/// underline stays aligned either way
+pub fn render_diagnostic(
+    diagnostic: &Diagnostic,
+    source: &str,
+    path: Option<&str>,
+    color: bool,
+) -> String {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
         }
+    };
+    let (label, severity_style) = match diagnostic.severity {
+        DiagnosticSeverity::Error => ("error", RED),
+        DiagnosticSeverity::Warning => ("warning", YELLOW),
+    };
+    let header = match diagnostic.code {
+        Some(code) => format!("{}[{}]", label, code),
+        None => label.to_string(),
+    };
+
+    let mut out = format!(
+        "{}{}\n",
+        paint(severity_style, &header),
+        paint(BOLD, &format!(": {}", diagnostic.message))
+    );
+
+    let snippet = diagnostic.span.and_then(|span| Snippet::new(source, span));
+    let line_label = snippet.as_ref().map(|s| s.line.to_string()).unwrap_or_default();
+    let pad = " ".repeat(line_label.len().max(1));
+
+    let location = match (path, &snippet) {
+        (Some(path), Some(snippet)) => Some(format!("{}:{}:{}", path, snippet.line, snippet.column)),
+        (None, Some(snippet)) => Some(format!("line {}:{}", snippet.line, snippet.column)),
+        (Some(path), None) => Some(path.to_string()),
+        (None, None) => None,
+    };
+    if let Some(location) = location {
+        out.push_str(&format!("{}{} {}\n", pad, paint(BLUE, "-->"), location));
     }
-    
-    msg
+
+    if let Some(snippet) = &snippet {
+        let bar = paint(BLUE, "|");
+        out.push_str(&format!("{} {}\n", pad, bar));
+        out.push_str(&format!("{} {} {}\n", paint(BLUE, &line_label), bar, snippet.text));
+        out.push_str(&format!(
+            "{} {} {}{}\n",
+            pad,
+            bar,
+            " ".repeat(snippet.underline_start),
+            paint(severity_style, &"^".repeat(snippet.underline_len))
+        ));
+    }
+
+    let annotations = diagnostic
+        .notes
+        .iter()
+        .map(|note| ("note", note.as_str()))
+        .chain(diagnostic.help.as_deref().map(|help| ("help", help)));
+    for (kind, text) in annotations {
+        out.push_str(&format!("{} {} {}: {}\n", pad, paint(BLUE, "="), paint(BOLD, kind), text));
+    }
+    out
+}
+
+/// The source line a span starts on, with tabs expanded, and where to underline it
+struct Snippet {
+    line: usize,
+    column: usize,
+    text: String,
+    underline_start: usize,
+    underline_len: usize,
+}
+
+impl Snippet {
+    fn new(source: &str, span: Span) -> Option<Self> {
+        let start = span.start.min(source.len());
+        if !source.is_char_boundary(start) {
+            return None;
+        }
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // Underline the part of the span on this line, ignoring surrounding
+        // whitespace so whole-line spans mark just the statement
+        let content_start = line.len() - line.trim_start().len();
+        let content_end = line.trim_end().len();
+        let mut from = (start - line_start).max(content_start).min(content_end);
+        let mut to = span.end.saturating_sub(line_start).min(content_end);
+        while !line.is_char_boundary(from) {
+            from -= 1;
+        }
+        while !line.is_char_boundary(to) {
+            to += 1;
+        }
+
+        Some(Self {
+            line: source[..line_start].matches('\n').count() + 1,
+            column: line[..from].chars().count() + 1,
+            text: expand_tabs(line),
+            underline_start: display_width(&line[..from]),
+            underline_len: display_width(&line[from..to.max(from)]).max(1),
+        })
+    }
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
 }
 
 /// Format a runtime error with context
@@ -0,0 +1,39 @@
+//! Classifying source edits so a running simulation can be reloaded incrementally
+
+use crate::ast::Program;
+
+/// How a program changed between two versions of its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramDiff {
+    /// Nothing that affects the simulation changed
+    Unchanged,
+    /// Only `simulate` (dt/steps) changed; the running world can be kept as-is
+    SimulateOnly,
+    /// Let-bindings changed (and possibly `simulate`), but no declarations did
+    LetsOnly,
+    /// Particles, forces, loops, wells, detectors, the ground, or functions changed
+    Structural,
+}
+
+/// Classify the change from `old` to `new`
+/// Both programs should be parsed but not yet function-expanded
+pub fn diff_programs(old: &Program, new: &Program) -> ProgramDiff {
+    let structural = old.functions != new.functions
+        || old.top_level_calls != new.top_level_calls
+        || old.particles != new.particles
+        || old.forces != new.forces
+        || old.detectors != new.detectors
+        || old.loops != new.loops
+        || old.wells != new.wells
+        || old.ground != new.ground;
+
+    if structural {
+        ProgramDiff::Structural
+    } else if old.lets != new.lets {
+        ProgramDiff::LetsOnly
+    } else if old.simulate != new.simulate {
+        ProgramDiff::SimulateOnly
+    } else {
+        ProgramDiff::Unchanged
+    }
+}
@@ -1,4 +1,16 @@
+use crate::hash::StateHasher;
+use crate::snapshot::{SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::symbols::{SymbolId, SymbolTable};
 use glam::Vec2;
+use std::collections::{BTreeMap, HashSet};
+use thiserror::Error;
+
+/// Errors raised when merging one world into another
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MergeError {
+    #[error("merged particle name '{0}' is already in use")]
+    DuplicateParticle(String),
+}
 
 /// A particle in the physics simulation
 #[derive(Debug, Clone)]
@@ -7,6 +19,27 @@ pub struct Particle {
     pub pos: Vec2,
     pub vel: Vec2,
     pub mass: f32,
+    /// Pinned particles never move, but still exert forces on others
+    pub pinned: bool,
+    /// Linear damping coefficient c: velocity is scaled by max(0, 1 − c·dt) each step
+    pub damping: f32,
+    /// Numeric tags declared with `tag name = v`, readable by `tag(a, name)`
+    pub tags: BTreeMap<String, f32>,
+}
+
+/// An unnamed, free particle of unit mass at rest at the origin
+impl Default for Particle {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            pos: Vec2::ZERO,
+            vel: Vec2::ZERO,
+            mass: 1.0,
+            pinned: false,
+            damping: 0.0,
+            tags: BTreeMap::new(),
+        }
+    }
 }
 
 /// A force acting between particles
@@ -23,13 +56,154 @@ pub enum Force {
         k: f32,   // spring constant
         rest: f32, // rest length
     },
+    /// Inverse-square attraction toward a fixed point of strength GM
+    Central {
+        target: usize, // particle index
+        point: Vec2,
+        gm: f32,
+    },
+}
+
+/// Descriptive metadata for a force, for listing in a UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForceInfo {
+    pub index: usize,
+    pub kind: &'static str,
+    pub a: String,
+    pub b: String,
+    pub parameters: Vec<(&'static str, f32)>,
+    pub enabled: bool,
+}
+
+/// A force law supplied by the embedding application rather than by source
+///
+/// `accumulate` adds the force (not acceleration) on each particle into `out`,
+/// which is indexed like `particles`. It is called once per step alongside the
+/// built-in forces, so it should not depend on call order with other forces.
+pub trait CustomForce: Send + Sync {
+    fn accumulate(&self, particles: &[Particle], out: &mut [Vec2]);
+
+    /// Potential energy stored in this force, counted by `World::potential_energy`
+    /// Defaults to zero; implement it to make energy-drift checks meaningful
+    fn potential_energy(&self, _particles: &[Particle]) -> f32 {
+        0.0
+    }
 }
 
 /// The physics world containing particles and forces
-#[derive(Debug)]
 pub struct World {
+    /// Add particles with `add_particle`, so `symbols` can resolve their names
     pub particles: Vec<Particle>,
     pub forces: Vec<Force>,
+    /// Indexed like `forces`; forces past the end are enabled
+    disabled_forces: Vec<bool>,
+    custom_forces: Vec<Box<dyn CustomForce>>,
+    /// Particles closer than twice this radius collide; 0 disables collisions
+    collision_radius: f32,
+    /// Collisions so far per particle pair, keyed with the lower index first
+    collision_counts: BTreeMap<(usize, usize), usize>,
+    ground: Option<Ground>,
+    /// Indexed like `particles`; set by `resolve_ground` for particles resting on it
+    ground_contacts: Vec<bool>,
+    /// The particle names, each resolving to the index of its particle
+    symbols: SymbolTable,
+}
+
+/// A world's contact bookkeeping, saved with a `Checkpoint`
+#[derive(Debug, Clone)]
+pub(crate) struct Contacts {
+    collision_counts: BTreeMap<(usize, usize), usize>,
+    ground_contacts: Vec<bool>,
+}
+
+/// Particles within this height of the ground count as touching it
+pub const GROUND_CONTACT_EPSILON: f32 = 1e-4;
+
+/// Horizontal floor at height `y` with kinetic friction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ground {
+    pub y: f32,
+    /// Kinetic friction coefficient μ
+    pub friction: f32,
+    /// Uniform downward acceleration on every free particle
+    pub gravity: f32,
+}
+
+/// Axis-aligned box around particle positions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Bounds {
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// Grow the box by `padding` on every side
+    pub fn padded(&self, padding: f32) -> Bounds {
+        Bounds {
+            min: self.min - Vec2::splat(padding),
+            max: self.max + Vec2::splat(padding),
+        }
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// A custom force from a merged sub-world, applied to its slice of particles
+struct OffsetForce {
+    inner: Box<dyn CustomForce>,
+    offset: usize,
+    len: usize,
+}
+
+impl CustomForce for OffsetForce {
+    fn accumulate(&self, particles: &[Particle], out: &mut [Vec2]) {
+        let range = self.offset..self.offset + self.len;
+        self.inner
+            .accumulate(&particles[range.clone()], &mut out[range]);
+    }
+
+    fn potential_energy(&self, particles: &[Particle]) -> f32 {
+        self.inner
+            .potential_energy(&particles[self.offset..self.offset + self.len])
+    }
+}
+
+/// A collision resolved by `World::resolve_collisions`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collision {
+    pub a: usize,
+    pub b: usize,
+    /// Magnitude of the momentum exchanged along the contact normal
+    pub impulse: f32,
+}
+
+impl std::fmt::Debug for World {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("World")
+            .field("particles", &self.particles)
+            .field("forces", &self.forces)
+            .field("disabled_forces", &self.disabled_forces)
+            .field("custom_forces", &self.custom_forces.len())
+            .field("collision_radius", &self.collision_radius)
+            .field("collision_counts", &self.collision_counts)
+            .field("ground", &self.ground)
+            .field("ground_contacts", &self.ground_contacts)
+            .finish()
+    }
 }
 
 impl World {
@@ -37,15 +211,610 @@ impl World {
         Self {
             particles: Vec::new(),
             forces: Vec::new(),
+            disabled_forces: Vec::new(),
+            custom_forces: Vec::new(),
+            collision_radius: 0.0,
+            collision_counts: BTreeMap::new(),
+            ground: None,
+            ground_contacts: Vec::new(),
+            symbols: SymbolTable::new(),
+        }
+    }
+
+    /// Append a particle, returning the id its name resolves to
+    pub fn add_particle(&mut self, particle: Particle) -> SymbolId {
+        self.sync_symbols();
+        let id = self.symbols.push(&particle.name);
+        self.particles.push(particle);
+        id
+    }
+
+    /// The names of the particles, resolving each to the index of its particle
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Name the particles pushed onto `particles` directly since the table was
+    /// last extended
+    fn sync_symbols(&mut self) {
+        for particle in &self.particles[self.symbols.len().min(self.particles.len())..] {
+            self.symbols.push(&particle.name);
+        }
+    }
+
+    /// Append another world's particles and forces, renaming each particle to
+    /// `{prefix}{name}`; the result maps `other`'s particle indices to new ones
+    ///
+    /// Force endpoints, enable flags, collision counts, and custom forces are
+    /// carried over; the collision radius and ground stay `self`'s. Loops and wells
+    /// built against `other` can follow with `rebase(offset)`, where `offset` is the
+    /// new index of `other`'s particle 0, and `SimulationContext::add_loop`/`add_well`.
+    /// Nothing is changed if a renamed particle would clash with an existing name.
+    pub fn merge(&mut self, other: World, prefix: &str) -> Result<Vec<usize>, MergeError> {
+        self.sync_symbols();
+        let mut names = HashSet::new();
+        for particle in &other.particles {
+            let name = format!("{}{}", prefix, particle.name);
+            if self.symbols.resolve(&name).is_some() || !names.insert(name.clone()) {
+                return Err(MergeError::DuplicateParticle(name));
+            }
+        }
+
+        let offset = self.particles.len();
+        let World {
+            particles,
+            forces,
+            disabled_forces,
+            custom_forces,
+            collision_radius: _,
+            collision_counts,
+            ground: _,
+            ground_contacts: _,
+            symbols: _,
+        } = other;
+
+        let len = particles.len();
+        for mut particle in particles {
+            particle.name = format!("{}{}", prefix, particle.name);
+            self.add_particle(particle);
+        }
+
+        let force_offset = self.forces.len();
+        self.forces
+            .extend(forces.into_iter().map(|force| match force {
+                Force::Gravity { a, b, g } => Force::Gravity {
+                    a: a + offset,
+                    b: b + offset,
+                    g,
+                },
+                Force::Spring { a, b, k, rest } => Force::Spring {
+                    a: a + offset,
+                    b: b + offset,
+                    k,
+                    rest,
+                },
+                Force::Central { target, point, gm } => Force::Central {
+                    target: target + offset,
+                    point,
+                    gm,
+                },
+            }));
+        for (i, disabled) in disabled_forces.into_iter().enumerate() {
+            if disabled {
+                self.set_force_enabled(force_offset + i, false);
+            }
+        }
+
+        for ((a, b), count) in collision_counts {
+            self.collision_counts
+                .insert((a + offset, b + offset), count);
+        }
+        for inner in custom_forces {
+            self.custom_forces
+                .push(Box::new(OffsetForce { inner, offset, len }));
+        }
+
+        Ok((offset..offset + len).collect())
+    }
+
+    /// Treat every particle as a disc of `radius` that bounces elastically off
+    /// the others; 0 (the default) disables collisions
+    pub fn set_collision_radius(&mut self, radius: f32) {
+        self.collision_radius = radius.max(0.0);
+    }
+
+    pub fn collision_radius(&self) -> f32 {
+        self.collision_radius
+    }
+
+    /// Collision counts and ground contacts, which steps update besides the particles
+    pub(crate) fn contacts(&self) -> Contacts {
+        Contacts {
+            collision_counts: self.collision_counts.clone(),
+            ground_contacts: self.ground_contacts.clone(),
+        }
+    }
+
+    pub(crate) fn set_contacts(&mut self, contacts: Contacts) {
+        self.collision_counts = contacts.collision_counts;
+        self.ground_contacts = contacts.ground_contacts;
+    }
+
+    /// Append the whole world to a snapshot; custom forces are code, not
+    /// data, so a world with any can't be saved
+    pub(crate) fn write_snapshot(&self, w: &mut SnapshotWriter) -> Result<(), SnapshotError> {
+        if !self.custom_forces.is_empty() {
+            return Err(SnapshotError::Unsupported("the world has custom forces"));
         }
+        w.write(&self.particles);
+        w.write(&self.forces);
+        w.write(&self.disabled_forces);
+        w.write(&self.collision_radius);
+        let counts: Vec<((usize, usize), usize)> = self
+            .collision_counts
+            .iter()
+            .map(|(&k, &v)| (k, v))
+            .collect();
+        w.write(&counts);
+        w.write(&self.ground);
+        w.write(&self.ground_contacts);
+        Ok(())
+    }
+
+    /// Read back a world written by `write_snapshot`
+    pub(crate) fn read_snapshot(r: &mut SnapshotReader<'_>) -> Result<World, SnapshotError> {
+        let mut world = World::new();
+        world.particles = r.read()?;
+        world.forces = r.read()?;
+        world.disabled_forces = r.read()?;
+        world.collision_radius = r.read()?;
+        let counts: Vec<((usize, usize), usize)> = r.read()?;
+        world.collision_counts = counts.into_iter().collect();
+        world.ground = r.read()?;
+        world.ground_contacts = r.read()?;
+        world.sync_symbols();
+        let count = world.particles.len();
+        let in_range = |i: &usize| *i < count;
+        let forces_valid = world.forces.iter().all(|force| match force {
+            Force::Gravity { a, b, .. } | Force::Spring { a, b, .. } => in_range(a) && in_range(b),
+            Force::Central { target, .. } => in_range(target),
+        });
+        if !forces_valid {
+            return Err(SnapshotError::Corrupt(
+                "force refers to a missing particle".into(),
+            ));
+        }
+        Ok(world)
+    }
+
+    /// Number of collisions between particles `a` and `b` so far, in either order
+    pub fn collision_count(&self, a: usize, b: usize) -> usize {
+        let key = (a.min(b), a.max(b));
+        self.collision_counts.get(&key).copied().unwrap_or(0)
+    }
+
+    /// Resolve overlapping, approaching pairs with an elastic impulse along the
+    /// line between their centres; pinned particles act as infinitely heavy
+    /// Pairs are visited in index order, so the result is deterministic
+    pub fn resolve_collisions(&mut self) -> Vec<Collision> {
+        let mut collisions = Vec::new();
+        if self.collision_radius <= 0.0 {
+            return collisions;
+        }
+
+        let contact = 2.0 * self.collision_radius;
+        for a in 0..self.particles.len() {
+            for b in (a + 1)..self.particles.len() {
+                let (pa, pb) = (&self.particles[a], &self.particles[b]);
+                let offset = pb.pos - pa.pos;
+                let dist = offset.length();
+                if dist >= contact || dist <= 0.0 {
+                    continue;
+                }
+                let normal = offset / dist;
+                let approach = (pb.vel - pa.vel).dot(normal);
+                if approach >= 0.0 {
+                    continue;
+                }
+
+                let inverse_mass = |p: &Particle| if p.pinned { 0.0 } else { 1.0 / p.mass };
+                let (wa, wb) = (inverse_mass(pa), inverse_mass(pb));
+                if wa + wb <= 0.0 {
+                    continue;
+                }
+                let impulse = -2.0 * approach / (wa + wb);
+                self.particles[a].vel -= normal * (impulse * wa);
+                self.particles[b].vel += normal * (impulse * wb);
+
+                *self.collision_counts.entry((a, b)).or_insert(0) += 1;
+                collisions.push(Collision { a, b, impulse });
+            }
+        }
+        collisions
+    }
+
+    /// Set (or with `None`, remove) the ground line
+    pub fn set_ground(&mut self, ground: Option<Ground>) {
+        self.ground = ground;
+        self.ground_contacts.clear();
+    }
+
+    pub fn ground(&self) -> Option<Ground> {
+        self.ground
+    }
+
+    /// Whether the particle at `index` rested on the ground after the last step
+    pub fn in_ground_contact(&self, index: usize) -> bool {
+        self.ground_contacts.get(index).copied().unwrap_or(false)
     }
 
-    /// Compute the acceleration vector for a particle at the given index
+    /// Keep particles on or above the ground and apply kinetic friction to those
+    /// resting on it; runs after integration and damping
+    ///
+    /// A particle that sank below the ground is lifted back onto it and loses its
+    /// downward velocity. A particle within `GROUND_CONTACT_EPSILON` of the ground and
+    /// not moving up is in contact, and its horizontal speed drops by μ·g_eff·dt, where
+    /// g_eff is its downward acceleration. Friction can stop a particle but never
+    /// reverses it, so it only ever removes energy.
+    pub fn resolve_ground(&mut self, dt: f32) {
+        let Some(ground) = self.ground else {
+            return;
+        };
+        let accelerations = self.compute_accelerations();
+        self.ground_contacts.resize(self.particles.len(), false);
+
+        let bodies = self.particles.iter_mut().zip(&mut self.ground_contacts);
+        for ((particle, contact), accel) in bodies.zip(accelerations) {
+            if particle.pinned {
+                *contact = false;
+                continue;
+            }
+            if particle.pos.y < ground.y {
+                particle.pos.y = ground.y;
+                particle.vel.y = particle.vel.y.max(0.0);
+            }
+
+            *contact = particle.pos.y - ground.y <= GROUND_CONTACT_EPSILON && particle.vel.y <= 0.0;
+            if !*contact {
+                continue;
+            }
+            let slowdown = ground.friction * (-accel.y).max(0.0) * dt;
+            if particle.vel.x.abs() <= slowdown {
+                particle.vel.x = 0.0;
+            } else {
+                particle.vel.x -= slowdown * particle.vel.x.signum();
+            }
+        }
+    }
+
+    /// Pin the particle at `index` in place and zero its velocity
+    /// Returns false if there is no particle at `index`
+    pub fn pin(&mut self, index: usize) -> bool {
+        match self.particles.get_mut(index) {
+            Some(particle) => {
+                particle.pinned = true;
+                particle.vel = Vec2::ZERO;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Let a pinned particle move again, starting from rest
+    /// Returns false if there is no particle at `index`
+    pub fn unpin(&mut self, index: usize) -> bool {
+        match self.particles.get_mut(index) {
+            Some(particle) => {
+                particle.pinned = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Register a force law implemented outside the language
+    /// Custom forces are not included in `state_hash` or `force_info`
+    pub fn add_custom_force(&mut self, force: Box<dyn CustomForce>) {
+        self.custom_forces.push(force);
+    }
+
+    /// Number of registered custom forces
+    pub fn custom_force_count(&self) -> usize {
+        self.custom_forces.len()
+    }
+
+    /// Enable or disable the force at `index`; disabled forces exert nothing
+    /// Returns false if there is no force at `index`
+    pub fn set_force_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        if index >= self.forces.len() {
+            return false;
+        }
+        if self.disabled_forces.len() <= index {
+            self.disabled_forces.resize(index + 1, false);
+        }
+        self.disabled_forces[index] = !enabled;
+        true
+    }
+
+    pub fn is_force_enabled(&self, index: usize) -> bool {
+        !self.disabled_forces.get(index).copied().unwrap_or(false)
+    }
+
+    /// Metadata for every force, in declaration order
+    pub fn force_info(&self) -> Vec<ForceInfo> {
+        self.forces
+            .iter()
+            .enumerate()
+            .map(|(index, force)| {
+                let (kind, a, b, parameters) = match force {
+                    Force::Gravity { a, b, g } => ("gravity", *a, *b, vec![("G", *g)]),
+                    Force::Spring { a, b, k, rest } => {
+                        ("spring", *a, *b, vec![("k", *k), ("rest", *rest)])
+                    }
+                    // Single-particle force: both endpoints name the target
+                    Force::Central { target, point, gm } => (
+                        "central",
+                        *target,
+                        *target,
+                        vec![("x", point.x), ("y", point.y), ("GM", *gm)],
+                    ),
+                };
+                ForceInfo {
+                    index,
+                    kind,
+                    a: self.particles[a].name.clone(),
+                    b: self.particles[b].name.clone(),
+                    parameters,
+                    enabled: self.is_force_enabled(index),
+                }
+            })
+            .collect()
+    }
+
+    /// Deterministic hash of the world state (names, bit patterns of
+    /// positions/velocities/masses, and forces) for regression testing
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.hash_state(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Feed the world state into a hasher in a defined order
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.particles.len());
+        for particle in &self.particles {
+            hasher.write_str(&particle.name);
+            hasher.write_vec2(particle.pos);
+            hasher.write_vec2(particle.vel);
+            hasher.write_f32(particle.mass);
+            hasher.write_bool(particle.pinned);
+            hasher.write_f32(particle.damping);
+            hasher.write_usize(particle.tags.len());
+            for (tag, value) in &particle.tags {
+                hasher.write_str(tag);
+                hasher.write_f32(*value);
+            }
+        }
+
+        hasher.write_f32(self.collision_radius);
+        hasher.write_bool(self.ground.is_some());
+        if let Some(ground) = self.ground {
+            hasher.write_f32(ground.y);
+            hasher.write_f32(ground.friction);
+            hasher.write_f32(ground.gravity);
+            for i in 0..self.particles.len() {
+                hasher.write_bool(self.in_ground_contact(i));
+            }
+        }
+        hasher.write_usize(self.forces.len());
+        for (i, force) in self.forces.iter().enumerate() {
+            hasher.write_bool(self.is_force_enabled(i));
+            match force {
+                Force::Gravity { a, b, g } => {
+                    hasher.write_u8(0);
+                    hasher.write_usize(*a);
+                    hasher.write_usize(*b);
+                    hasher.write_f32(*g);
+                }
+                Force::Spring { a, b, k, rest } => {
+                    hasher.write_u8(1);
+                    hasher.write_usize(*a);
+                    hasher.write_usize(*b);
+                    hasher.write_f32(*k);
+                    hasher.write_f32(*rest);
+                }
+                Force::Central { target, point, gm } => {
+                    hasher.write_u8(2);
+                    hasher.write_usize(*target);
+                    hasher.write_vec2(*point);
+                    hasher.write_f32(*gm);
+                }
+            }
+        }
+    }
+
+    /// Smallest box containing every particle position, or None for an empty world
+    pub fn bounds(&self) -> Option<Bounds> {
+        let first = self.particles.first()?.pos;
+        let (min, max) = self
+            .particles
+            .iter()
+            .fold((first, first), |(min, max), p| (min.min(p.pos), max.max(p.pos)));
+        Some(Bounds { min, max })
+    }
+
+    /// `bounds()` grown by `padding` on every side
+    pub fn bounds_with_padding(&self, padding: f32) -> Option<Bounds> {
+        self.bounds().map(|bounds| bounds.padded(padding))
+    }
+
+    /// Find the first particle whose position or velocity is not finite
+    /// Returns the particle index and the name of the offending quantity
+    pub fn find_non_finite(&self) -> Option<(usize, &'static str)> {
+        self.particles.iter().enumerate().find_map(|(i, p)| {
+            if !p.pos.is_finite() {
+                Some((i, "position"))
+            } else if !p.vel.is_finite() {
+                Some((i, "velocity"))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Total kinetic energy: sum of ½mv²
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles
+            .iter()
+            .map(|p| 0.5 * p.mass * p.vel.length_squared())
+            .sum()
+    }
+
+    /// Total potential energy stored in the enabled forces, custom forces, and
+    /// ground gravity (mg·h above the ground)
+    /// Springs contribute ½k(x−rest)², gravity −Gm₁m₂/r, central forces −GM·m/r
+    /// (gravity and central are skipped at r = 0)
+    pub fn potential_energy(&self) -> f32 {
+        let builtin: f32 = self
+            .forces
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.is_force_enabled(*i))
+            .map(|(_, force)| match force {
+                Force::Gravity { a, b, g } => {
+                    let (pa, pb) = (&self.particles[*a], &self.particles[*b]);
+                    let dist = pa.pos.distance(pb.pos);
+                    if dist > 0.0 {
+                        -g * pa.mass * pb.mass / dist
+                    } else {
+                        0.0
+                    }
+                }
+                Force::Spring { a, b, k, rest } => {
+                    let dist = self.particles[*a].pos.distance(self.particles[*b].pos);
+                    let displacement = dist - rest;
+                    0.5 * k * displacement * displacement
+                }
+                Force::Central { target, point, gm } => {
+                    let particle = &self.particles[*target];
+                    let dist = particle.pos.distance(*point);
+                    if dist > 0.0 {
+                        -gm * particle.mass / dist
+                    } else {
+                        0.0
+                    }
+                }
+            })
+            .sum();
+
+        let custom: f32 = self
+            .custom_forces
+            .iter()
+            .map(|force| force.potential_energy(&self.particles))
+            .sum();
+
+        let ground: f32 = match self.ground {
+            Some(ground) => self
+                .particles
+                .iter()
+                .filter(|p| !p.pinned)
+                .map(|p| p.mass * ground.gravity * (p.pos.y - ground.y))
+                .sum(),
+            None => 0.0,
+        };
+
+        builtin + custom + ground
+    }
+
+    /// Kinetic plus potential energy
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy() + self.potential_energy()
+    }
+
+    /// Total linear momentum: sum of mv
+    pub fn total_momentum(&self) -> Vec2 {
+        self.particles.iter().map(|p| p.vel * p.mass).sum()
+    }
+
+    /// Mass-weighted mean position (origin if the world has no mass)
+    pub fn center_of_mass(&self) -> Vec2 {
+        let total_mass: f32 = self.particles.iter().map(|p| p.mass).sum();
+        if total_mass == 0.0 {
+            return Vec2::ZERO;
+        }
+        self.particles.iter().map(|p| p.pos * p.mass).sum::<Vec2>() / total_mass
+    }
+
+    /// Move into the center-of-mass frame: shift positions so the center of mass
+    /// is at the origin and subtract the center-of-mass velocity
+    /// Pinned particles are left untouched, so the center of mass and velocity
+    /// are those of the unpinned particles; a world with no unpinned mass is
+    /// unchanged
+    pub fn recenter(&mut self) {
+        let free = || self.particles.iter().filter(|p| !p.pinned);
+        let total_mass: f32 = free().map(|p| p.mass).sum();
+        if total_mass == 0.0 {
+            return;
+        }
+        let center = free().map(|p| p.pos * p.mass).sum::<Vec2>() / total_mass;
+        let velocity = free().map(|p| p.vel * p.mass).sum::<Vec2>() / total_mass;
+        for particle in self.particles.iter_mut().filter(|p| !p.pinned) {
+            particle.pos -= center;
+            particle.vel -= velocity;
+        }
+    }
+
+    /// Compute the acceleration of every particle from built-in and custom forces
+    /// and ground gravity
+    ///
+    /// This is what the integrator uses each step; the result is indexed like
+    /// `particles`. Pinned particles get zero acceleration.
+    pub fn compute_accelerations(&self) -> Vec<Vec2> {
+        let mut accelerations: Vec<Vec2> = (0..self.particles.len())
+            .map(|i| self.compute_acceleration(i))
+            .collect();
+
+        if !self.custom_forces.is_empty() {
+            let mut forces = vec![Vec2::ZERO; self.particles.len()];
+            for custom in &self.custom_forces {
+                custom.accumulate(&self.particles, &mut forces);
+            }
+            for ((accel, force), particle) in
+                accelerations.iter_mut().zip(forces).zip(&self.particles)
+            {
+                *accel += force / particle.mass;
+            }
+        }
+
+        if let Some(ground) = self.ground {
+            for accel in &mut accelerations {
+                accel.y -= ground.gravity;
+            }
+        }
+
+        for (accel, particle) in accelerations.iter_mut().zip(&self.particles) {
+            if particle.pinned {
+                *accel = Vec2::ZERO;
+            }
+        }
+
+        accelerations
+    }
+
+    /// Compute the acceleration of the particle at `particle_idx` from the
+    /// built-in (source-declared, enabled) forces only
+    /// Pinned particles are included as sources; the pin itself is applied by
+    /// `compute_accelerations`
+    ///
+    /// Panics if `particle_idx` is out of range.
     pub fn compute_acceleration(&self, particle_idx: usize) -> Vec2 {
         let particle = &self.particles[particle_idx];
         let mut accel = Vec2::ZERO;
 
-        for force in &self.forces {
+        for (i, force) in self.forces.iter().enumerate() {
+            if !self.is_force_enabled(i) {
+                continue;
+            }
             match force {
                 Force::Gravity { a, b, g } => {
                     if *a == particle_idx {
@@ -87,6 +856,15 @@ impl World {
                         }
                     }
                 }
+                Force::Central { target, point, gm } => {
+                    if *target == particle_idx {
+                        let r = *point - particle.pos;
+                        let dist_sq = r.length_squared();
+                        if dist_sq > 0.0 {
+                            accel += r.normalize() * (gm / dist_sq);
+                        }
+                    }
+                }
             }
         }
 
@@ -2,9 +2,31 @@
 //!
 //! This module evaluates expressions to concrete f32 values before simulation.
 
-use crate::ast::{BinaryOp, Expr, FuncName, LetDecl};
-use crate::diagnostics::Diagnostic;
+use crate::ast::{BinaryOp, DetectorKind, Expr, FuncName, LetDecl};
+use crate::diagnostics::{Diagnostic, DiagnosticSeverity};
+use crate::parser::{parse_expression, ParseError};
 use std::collections::HashMap;
+use thiserror::Error;
+
+/// How far a computed count may be from a whole number, e.g. `36.0000001`
+/// from `6.0 * 6.0000000`
+const COUNT_TOLERANCE: f32 = 1e-4;
+
+/// The whole number `value` stands for, such as `steps` or loop `cycles`
+///
+/// Values within `COUNT_TOLERANCE` of an integer are rounded to it; negative,
+/// fractional, non-finite and out-of-range values are rejected with a message
+/// naming `what`
+pub fn coerce_to_count(value: f32, what: &str) -> Result<usize, String> {
+    let rounded = value.round();
+    if !value.is_finite() || (value - rounded).abs() > COUNT_TOLERANCE || rounded < 0.0 {
+        return Err(format!("{} must be an integer >= 0, got {}", what, value));
+    }
+    if rounded >= usize::MAX as f32 {
+        return Err(format!("{} is too large, got {}", what, value));
+    }
+    Ok(rounded as usize)
+}
 
 /// Evaluation context storing variable values
 pub struct EvalContext<'a> {
@@ -96,6 +118,32 @@ impl std::fmt::Display for EvalError {
 
 impl std::error::Error for EvalError {}
 
+/// Errors from evaluating a standalone source expression
+#[derive(Debug, Error)]
+pub enum ExprError {
+    #[error("parse error: {0}")]
+    Parse(#[from] ParseError),
+    #[error("let binding error: {0}")]
+    Let(String),
+    #[error("evaluation error: {0}")]
+    Eval(#[from] EvalError),
+}
+
+/// Parse and evaluate an expression against a program's let-bindings,
+/// without running a simulation
+/// Only built-in functions are available; user-defined functions are not called
+pub fn eval_source_expr(expr_src: &str, lets: &[LetDecl]) -> Result<f32, ExprError> {
+    let expr = parse_expression(expr_src)?;
+    let (ctx, diagnostics) = evaluate_lets(lets);
+    if let Some(error) = diagnostics
+        .iter()
+        .find(|d| matches!(d.severity, DiagnosticSeverity::Error))
+    {
+        return Err(ExprError::Let(error.message.clone()));
+    }
+    Ok(eval_expr(&expr, &ctx)?)
+}
+
 /// Evaluate all let-bindings in order
 /// Returns the evaluation context and any diagnostics
 pub fn evaluate_lets<'a>(
@@ -173,25 +221,7 @@ pub fn eval_expr_with_function_ctx(
         Expr::Binary { op, left, right } => {
             let left_val = eval_expr_with_function_ctx(left, global_ctx, func_ctx)?;
             let right_val = eval_expr_with_function_ctx(right, global_ctx, func_ctx)?;
-            
-            match op {
-                BinaryOp::Add => Ok(left_val + right_val),
-                BinaryOp::Sub => Ok(left_val - right_val),
-                BinaryOp::Mul => Ok(left_val * right_val),
-                BinaryOp::Div => {
-                    if right_val == 0.0 {
-                        return Err(EvalError::DivByZero);
-                    }
-                    Ok(left_val / right_val)
-                }
-                // v0.8: Comparison operators (return 1.0 for true, 0.0 for false)
-                BinaryOp::GreaterThan => Ok(if left_val > right_val { 1.0 } else { 0.0 }),
-                BinaryOp::LessThan => Ok(if left_val < right_val { 1.0 } else { 0.0 }),
-                BinaryOp::GreaterEqual => Ok(if left_val >= right_val { 1.0 } else { 0.0 }),
-                BinaryOp::LessEqual => Ok(if left_val <= right_val { 1.0 } else { 0.0 }),
-                BinaryOp::Equal => Ok(if left_val == right_val { 1.0 } else { 0.0 }),
-                BinaryOp::NotEqual => Ok(if left_val != right_val { 1.0 } else { 0.0 }),
-            }
+            apply_binary(*op, left_val, right_val)
         }
         
         Expr::Call { func, args } => {
@@ -199,52 +229,7 @@ pub fn eval_expr_with_function_ctx(
                 .iter()
                 .map(|arg| eval_expr_with_function_ctx(arg, global_ctx, func_ctx))
                 .collect();
-            let arg_values = arg_values?;
-            
-            match func {
-                FuncName::Sin => {
-                    if arg_values.len() != 1 {
-                        return Err(EvalError::InvalidArgs(
-                            format!("sin expects 1 argument, got {}", arg_values.len())
-                        ));
-                    }
-                    Ok(arg_values[0].sin())
-                }
-                FuncName::Cos => {
-                    if arg_values.len() != 1 {
-                        return Err(EvalError::InvalidArgs(
-                            format!("cos expects 1 argument, got {}", arg_values.len())
-                        ));
-                    }
-                    Ok(arg_values[0].cos())
-                }
-                FuncName::Sqrt => {
-                    if arg_values.len() != 1 {
-                        return Err(EvalError::InvalidArgs(
-                            format!("sqrt expects 1 argument, got {}", arg_values.len())
-                        ));
-                    }
-                    let x = arg_values[0];
-                    if x < 0.0 {
-                        return Err(EvalError::InvalidArgs(
-                            format!("sqrt of negative number: {}", x)
-                        ));
-                    }
-                    Ok(x.sqrt())
-                }
-                FuncName::Clamp => {
-                    if arg_values.len() != 3 {
-                        return Err(EvalError::InvalidArgs(
-                            format!("clamp expects 3 arguments, got {}", arg_values.len())
-                        ));
-                    }
-                    let x = arg_values[0];
-                    let min = arg_values[1];
-                    let max = arg_values[2];
-                    // Allow min > max, just clamp in given order
-                    Ok(x.max(min).min(max))
-                }
-            }
+            apply_builtin(*func, &arg_values?)
         }
         
         Expr::UserCall { name, args: _ } => {
@@ -264,6 +249,209 @@ pub fn eval_expr_with_function_ctx(
                 format!("String literal '{}' cannot be evaluated as a number", s)
             ))
         }
+        
+        Expr::Observable(kind) => Err(EvalError::InvalidArgs(observable_outside_detector(kind))),
+    }
+}
+
+/// Why `kind` can't be read where it is: only detectors see the world
+pub(crate) fn observable_outside_detector(kind: &DetectorKind) -> String {
+    format!(
+        "observable '{}' can only be read by a detector",
+        crate::capabilities::detector_keyword(kind)
+    )
+}
+
+/// `expr` with every observable replaced by the value `observe` gives it
+pub fn resolve_observables<E>(
+    expr: &Expr,
+    observe: &mut impl FnMut(&DetectorKind) -> Result<f32, E>,
+) -> Result<Expr, E> {
+    Ok(match expr {
+        Expr::Observable(kind) => Expr::Literal(observe(kind)?),
+        Expr::UnaryMinus(e) => Expr::UnaryMinus(Box::new(resolve_observables(e, observe)?)),
+        Expr::Binary { op, left, right } => Expr::Binary {
+            op: *op,
+            left: Box::new(resolve_observables(left, observe)?),
+            right: Box::new(resolve_observables(right, observe)?),
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func: *func,
+            args: args
+                .iter()
+                .map(|arg| resolve_observables(arg, observe))
+                .collect::<Result<_, _>>()?,
+        },
+        Expr::UserCall { name, args } => Expr::UserCall {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|arg| resolve_observables(arg, observe))
+                .collect::<Result<_, _>>()?,
+        },
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Var(_) => expr.clone(),
+    })
+}
+
+
+/// `left op right`, as the evaluator computes it
+fn apply_binary(op: BinaryOp, left_val: f32, right_val: f32) -> Result<f32, EvalError> {
+    match op {
+        BinaryOp::Add => Ok(left_val + right_val),
+        BinaryOp::Sub => Ok(left_val - right_val),
+        BinaryOp::Mul => Ok(left_val * right_val),
+        BinaryOp::Div => {
+            if right_val == 0.0 {
+                return Err(EvalError::DivByZero);
+            }
+            Ok(left_val / right_val)
+        }
+        // v0.8: Comparison operators (return 1.0 for true, 0.0 for false)
+        BinaryOp::GreaterThan => Ok(if left_val > right_val { 1.0 } else { 0.0 }),
+        BinaryOp::LessThan => Ok(if left_val < right_val { 1.0 } else { 0.0 }),
+        BinaryOp::GreaterEqual => Ok(if left_val >= right_val { 1.0 } else { 0.0 }),
+        BinaryOp::LessEqual => Ok(if left_val <= right_val { 1.0 } else { 0.0 }),
+        BinaryOp::Equal => Ok(if left_val == right_val { 1.0 } else { 0.0 }),
+        BinaryOp::NotEqual => Ok(if left_val != right_val { 1.0 } else { 0.0 }),
     }
 }
 
+/// A built-in function applied to evaluated arguments
+fn apply_builtin(func: FuncName, arg_values: &[f32]) -> Result<f32, EvalError> {
+    match func {
+        FuncName::Sin => {
+            if arg_values.len() != 1 {
+                return Err(EvalError::InvalidArgs(
+                    format!("sin expects 1 argument, got {}", arg_values.len())
+                ));
+            }
+            Ok(arg_values[0].sin())
+        }
+        FuncName::Cos => {
+            if arg_values.len() != 1 {
+                return Err(EvalError::InvalidArgs(
+                    format!("cos expects 1 argument, got {}", arg_values.len())
+                ));
+            }
+            Ok(arg_values[0].cos())
+        }
+        FuncName::Sqrt => {
+            if arg_values.len() != 1 {
+                return Err(EvalError::InvalidArgs(
+                    format!("sqrt expects 1 argument, got {}", arg_values.len())
+                ));
+            }
+            let x = arg_values[0];
+            if x < 0.0 {
+                return Err(EvalError::InvalidArgs(
+                    format!("sqrt of negative number: {}", x)
+                ));
+            }
+            Ok(x.sqrt())
+        }
+        FuncName::Clamp => {
+            if arg_values.len() != 3 {
+                return Err(EvalError::InvalidArgs(
+                    format!("clamp expects 3 arguments, got {}", arg_values.len())
+                ));
+            }
+            let x = arg_values[0];
+            let min = arg_values[1];
+            let max = arg_values[2];
+            // Allow min > max, just clamp in given order
+            Ok(x.max(min).min(max))
+        }
+    }
+}
+
+/// The value of `expr` if it is constant: literals, variables bound in
+/// `lets`, built-in calls and arithmetic on them
+///
+/// `None` when `expr` reads an unbound variable (such as `time`), calls a
+/// user-defined function, or fails to evaluate; the evaluator reports those
+pub fn try_const_fold(expr: &Expr, lets: &HashMap<&str, f32>) -> Option<f32> {
+    match expr {
+        Expr::Literal(value) => Some(*value),
+        Expr::Var(name) => lets.get(name.as_str()).copied(),
+        Expr::UnaryMinus(e) => try_const_fold(e, lets).map(|v| -v),
+        Expr::Binary { op, left, right } => {
+            let left_val = try_const_fold(left, lets)?;
+            let right_val = try_const_fold(right, lets)?;
+            apply_binary(*op, left_val, right_val).ok()
+        }
+        Expr::Call { func, args } => {
+            let arg_values: Option<Vec<f32>> =
+                args.iter().map(|arg| try_const_fold(arg, lets)).collect();
+            apply_builtin(*func, &arg_values?).ok()
+        }
+        Expr::UserCall { .. } | Expr::StringLiteral(_) | Expr::Observable(_) => None,
+    }
+}
+
+/// `expr` with every constant subexpression replaced by its value, as
+/// `try_const_fold` computes it
+pub fn fold_constants(expr: &Expr, lets: &HashMap<&str, f32>) -> Expr {
+    if let Some(value) = try_const_fold(expr, lets) {
+        return Expr::Literal(value);
+    }
+    match expr {
+        Expr::UnaryMinus(e) => Expr::UnaryMinus(Box::new(fold_constants(e, lets))),
+        Expr::Binary { op, left, right } => Expr::Binary {
+            op: *op,
+            left: Box::new(fold_constants(left, lets)),
+            right: Box::new(fold_constants(right, lets)),
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func: *func,
+            args: args.iter().map(|arg| fold_constants(arg, lets)).collect(),
+        },
+        Expr::UserCall { name, args } => Expr::UserCall {
+            name: name.clone(),
+            args: args.iter().map(|arg| fold_constants(arg, lets)).collect(),
+        },
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Var(_) | Expr::Observable(_) => {
+            expr.clone()
+        }
+    }
+}
+
+/// Replace every variable bound in `ctx` with its value, leaving unbound ones
+/// (such as the per-step `time`) in place
+pub fn substitute_vars(expr: &Expr, ctx: &EvalContext<'_>) -> Expr {
+    match expr {
+        Expr::Var(name) => match ctx.values.get(name.as_str()) {
+            Some(value) => Expr::Literal(*value),
+            None => expr.clone(),
+        },
+        Expr::UnaryMinus(e) => Expr::UnaryMinus(Box::new(substitute_vars(e, ctx))),
+        Expr::Binary { op, left, right } => Expr::Binary {
+            op: *op,
+            left: Box::new(substitute_vars(left, ctx)),
+            right: Box::new(substitute_vars(right, ctx)),
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func: *func,
+            args: args.iter().map(|arg| substitute_vars(arg, ctx)).collect(),
+        },
+        Expr::UserCall { name, args } => Expr::UserCall {
+            name: name.clone(),
+            args: args.iter().map(|arg| substitute_vars(arg, ctx)).collect(),
+        },
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Observable(_) => expr.clone(),
+    }
+}
+
+/// Whether `expr` reads the variable `name` anywhere
+pub fn references_var(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Var(var) => var == name,
+        Expr::UnaryMinus(e) => references_var(e, name),
+        Expr::Binary { left, right, .. } => {
+            references_var(left, name) || references_var(right, name)
+        }
+        Expr::Call { args, .. } | Expr::UserCall { args, .. } => {
+            args.iter().any(|arg| references_var(arg, name))
+        }
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Observable(_) => false,
+    }
+}
@@ -0,0 +1,183 @@
+//! Trajectory export in CSV form
+
+use crate::engine::World;
+use crate::runtime::{
+    run_program_with_observer, DetectorResult, RunOptions, SimulationContext, SimulationResult,
+};
+use std::io::{self, Write};
+
+/// Writes one CSV row per sampled step: `step,time`, then `x,y,vx,vy` per particle
+/// The header is written before the first row, from that world's particle names
+pub struct CsvTraceWriter<W: Write> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvTraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Write the header row; called automatically by the first `write_row`
+    pub fn write_header(&mut self, world: &World) -> io::Result<()> {
+        let mut fields = vec!["step".to_string(), "time".to_string()];
+        for particle in &world.particles {
+            for column in ["x", "y", "vx", "vy"] {
+                fields.push(format!("{}.{}", particle.name, column));
+            }
+        }
+        self.write_fields(&fields)?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// Write the state of `world` at the given step and simulation time
+    pub fn write_row(&mut self, step: usize, time: f32, world: &World) -> io::Result<()> {
+        if !self.header_written {
+            self.write_header(world)?;
+        }
+
+        let mut fields = vec![step.to_string(), time.to_string()];
+        for particle in &world.particles {
+            fields.push(particle.pos.x.to_string());
+            fields.push(particle.pos.y.to_string());
+            fields.push(particle.vel.x.to_string());
+            fields.push(particle.vel.y.to_string());
+        }
+        self.write_fields(&fields)
+    }
+
+    /// Write the current state of a simulation context
+    pub fn write_context(&mut self, ctx: &SimulationContext) -> io::Result<()> {
+        self.write_row(ctx.current_step, ctx.time, &ctx.world)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_fields(&mut self, fields: &[String]) -> io::Result<()> {
+        let line: Vec<String> = fields.iter().map(|f| escape_field(f)).collect();
+        writeln!(self.writer, "{}", line.join(","))
+    }
+}
+
+/// Significant digits a detector value is shown with unless a precision is asked for
+pub const DETECTOR_SIGNIFICANT_DIGITS: usize = 6;
+
+/// A detector value as every text output shows it: `precision` digits after
+/// the decimal point, or rounded to `DETECTOR_SIGNIFICANT_DIGITS` without
+/// trailing zeros, so float noise such as `0.30000001` reads `0.3`
+///
+/// Zero and negative zero show as `0.0`, and non-finite values as `NaN`,
+/// `inf` or `-inf`.
+pub fn format_detector_value(value: f32, precision: Option<usize>) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let text = match precision {
+        Some(digits) => format!("{:.*}", digits, value),
+        None => significant_digits(f64::from(value), DETECTOR_SIGNIFICANT_DIGITS),
+    };
+    // Values that round to zero lose their sign, like -0.0 itself
+    match text.strip_prefix('-') {
+        Some(magnitude) if magnitude.chars().all(|c| c == '0' || c == '.') => {
+            magnitude.to_string()
+        }
+        _ => text,
+    }
+}
+
+/// `value` rounded to `digits` significant digits, written out in full with
+/// no exponent and no trailing zeros after the point
+fn significant_digits(value: f64, digits: usize) -> String {
+    if value == 0.0 {
+        return "0.0".to_string();
+    }
+    // `{:e}` rounds correctly: "-1.23457e-7" is the sign, digits and exponent
+    let scientific = format!("{:.*e}", digits - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(magnitude) => ("-", magnitude),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+
+    let point = exponent + 1;
+    let (whole, fraction) = if point <= 0 {
+        ("0".to_string(), "0".repeat(point.unsigned_abs() as usize) + &digits)
+    } else if point as usize >= digits.len() {
+        (digits.clone() + &"0".repeat(point as usize - digits.len()), String::new())
+    } else {
+        let (whole, fraction) = digits.split_at(point as usize);
+        (whole.to_string(), fraction.to_string())
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Write detector results as CSV: a header row of names, then one row of values
+pub fn write_detectors_csv<W: Write>(mut writer: W, detectors: &[DetectorResult]) -> io::Result<()> {
+    let names: Vec<String> = detectors.iter().map(|d| escape_field(&d.name)).collect();
+    let values: Vec<String> = detectors.iter().map(|d| d.value.to_string()).collect();
+    writeln!(writer, "{}", names.join(","))?;
+    writeln!(writer, "{}", values.join(","))
+}
+
+/// Write detector samples as CSV: a `step,<names>` header, then one row per
+/// sampled step with a value per name
+pub fn write_detector_series_csv<W: Write>(
+    mut writer: W,
+    names: &[String],
+    rows: &[(usize, Vec<f32>)],
+) -> io::Result<()> {
+    let header: Vec<String> = names.iter().map(|name| escape_field(name)).collect();
+    writeln!(writer, "step,{}", header.join(","))?;
+    for (step, values) in rows {
+        let values: Vec<String> = values.iter().map(f32::to_string).collect();
+        writeln!(writer, "{},{}", step, values.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or line break (RFC 4180)
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Run a program, writing a CSV row for step 0 and every `sample_every`th step after it
+pub fn run_program_to_csv<W: Write>(
+    source: &str,
+    writer: W,
+    sample_every: usize,
+) -> Result<SimulationResult, Box<dyn std::error::Error>> {
+    let sample_every = sample_every.max(1);
+    let mut csv = CsvTraceWriter::new(writer);
+    let result = run_program_with_observer(source, &RunOptions::default(), |ctx| {
+        if ctx.current_step % sample_every == 0 {
+            csv.write_context(ctx)?;
+        }
+        Ok(())
+    })?;
+    csv.flush()?;
+    Ok(result)
+}
@@ -0,0 +1,95 @@
+//! Constant folding over a whole program, so the builder reads values
+//! instead of evaluating the same arithmetic again for every declaration
+
+use crate::ast::{ConditionExpr, Expr, ForceKind, LoopBodyStmt, LoopKind, Program};
+use crate::eval::{evaluate_lets, fold_constants};
+
+/// Replace every constant subexpression of `program`'s declarations with its
+/// value, reading its let bindings
+///
+/// Parts that read `time`, call user functions or fail to evaluate are kept,
+/// so the folded program builds and runs exactly like the original. Let
+/// bindings and function bodies are left as written.
+pub fn fold_program_constants(program: &mut Program) {
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let fold = |expr: &mut Expr| *expr = fold_constants(expr, &eval_ctx.values);
+
+    for particle in &mut program.particles {
+        fold(&mut particle.position.0);
+        fold(&mut particle.position.1);
+        fold(&mut particle.mass);
+        if let Some(damping) = &mut particle.damping {
+            fold(damping);
+        }
+        for (_, value) in &mut particle.tags {
+            fold(value);
+        }
+    }
+    for force in &mut program.forces {
+        match &mut force.kind {
+            ForceKind::Gravity { g } => fold(g),
+            ForceKind::Spring { k, rest } => {
+                fold(k);
+                fold(rest);
+            }
+            ForceKind::Central { point, gm } => {
+                fold(&mut point.0);
+                fold(&mut point.1);
+                fold(gm);
+            }
+        }
+    }
+    fold(&mut program.simulate.dt);
+    fold(&mut program.simulate.steps);
+    for loop_decl in &mut program.loops {
+        match &mut loop_decl.kind {
+            LoopKind::ForCycles {
+                cycles,
+                frequency,
+                damping,
+                ..
+            } => {
+                fold(cycles);
+                fold(frequency);
+                fold(damping);
+            }
+            LoopKind::WhileCondition {
+                condition,
+                frequency,
+                damping,
+                ..
+            } => {
+                match condition {
+                    ConditionExpr::LessThan(_, threshold)
+                    | ConditionExpr::GreaterThan(_, threshold) => fold(threshold),
+                }
+                fold(frequency);
+                fold(damping);
+            }
+        }
+        for stmt in &mut loop_decl.body {
+            let LoopBodyStmt::ForcePush {
+                magnitude,
+                direction,
+                ..
+            } = stmt;
+            fold(magnitude);
+            fold(&mut direction.0);
+            fold(&mut direction.1);
+        }
+    }
+    for well in &mut program.wells {
+        fold(&mut well.threshold);
+        fold(&mut well.depth);
+    }
+    for detector in &mut program.detectors {
+        fold(&mut detector.expr);
+    }
+    if let Some(ground) = &mut program.ground {
+        fold(&mut ground.y);
+        fold(&mut ground.friction);
+        if let Some(gravity) = &mut ground.gravity {
+            fold(gravity);
+        }
+    }
+}
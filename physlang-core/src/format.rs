@@ -0,0 +1,442 @@
+//! Source formatting for `physlang fmt`
+//!
+//! The parsed `Program` groups declarations by kind and drops comments, so the
+//! formatter works line by line instead: each single-line declaration is parsed
+//! and printed back from its AST, while comments, blank lines, and the order of
+//! the file are kept, and blocks are re-indented by brace depth.
+
+use crate::ast::{
+    BinaryOp, DetectorDecl, DetectorKind, Expr, ForceDecl, ForceKind, Frame, FuncName,
+    GroundDecl, LetDecl, LoopBodyStmt, ObservableExpr, ParticleDecl, Recenter, SimulateDecl,
+    WellDecl,
+};
+use crate::engine::World;
+use crate::symbols::SymbolTable;
+use crate::parser::{self, parse_program, ParseError};
+
+/// Spaces per block level
+const INDENT: &str = "    ";
+
+/// Format a whole program, or return the parse error if it doesn't parse
+///
+/// Formatting never changes what the program means: if the formatted source
+/// would parse to a different program, it's an error rather than a silent change
+pub fn format_source(source: &str) -> Result<String, ParseError> {
+    let program = parse_program(source)?;
+
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut pending_blank = false;
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            // Runs of blank lines collapse to one, and none lead the file
+            pending_blank = !out.is_empty();
+            continue;
+        }
+
+        let (opens, closes) = count_braces(line);
+        let leading_closes = line.chars().take_while(|&c| c == '}').count();
+        depth = depth.saturating_sub(leading_closes);
+        if pending_blank {
+            out.push('\n');
+            pending_blank = false;
+        }
+
+        out.push_str(&INDENT.repeat(depth));
+        let (code, comment) = split_comment(line);
+        if !code.is_empty() {
+            out.push_str(&format_line(code));
+        }
+        if let Some(comment) = comment {
+            if !code.is_empty() {
+                out.push_str("  ");
+            }
+            out.push_str(comment);
+        }
+        out.push('\n');
+        depth = (depth + opens).saturating_sub(closes - leading_closes);
+    }
+
+    if parse_program(&out).ok().as_ref() != Some(&program) {
+        return Err(ParseError::message(
+            "formatting would change the meaning of the program; the file was left unchanged",
+        ));
+    }
+    Ok(out)
+}
+
+/// Rewrite `source` so it starts from the state of `world`
+///
+/// Each top-level particle declaration gets the particle's current position;
+/// everything else, comments included, is kept as written. Particles made by
+/// functions start where their call puts them, and velocities aren't exported
+/// since the language has no syntax for them
+pub fn export_state_source(source: &str, world: &World) -> Result<String, ParseError> {
+    parse_program(source)?;
+
+    let symbols = world.symbols();
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        let (opens, closes) = count_braces(trimmed);
+        let (code, comment) = split_comment(trimmed);
+        let exported = if depth == 0 && code.starts_with("particle ") {
+            export_particle(code, world, symbols)
+        } else {
+            None
+        };
+        match exported {
+            Some(code) => {
+                out.push_str(&line[..line.len() - line.trim_start().len()]);
+                out.push_str(&code);
+                if let Some(comment) = comment {
+                    out.push_str("  ");
+                    out.push_str(comment);
+                }
+            }
+            None => out.push_str(line),
+        }
+        out.push('\n');
+        depth = (depth + opens).saturating_sub(closes);
+    }
+
+    parse_program(&out)?;
+    Ok(out)
+}
+
+/// A particle declaration moved to where `world` has the particle now
+fn export_particle(code: &str, world: &World, symbols: &SymbolTable) -> Option<String> {
+    let mut decl = parser::parse_particle(code, None).ok()?;
+    let particle = &world.particles[symbols.resolve(&decl.name)?.index()];
+    decl.position = (
+        Expr::Literal(particle.pos.x),
+        Expr::Literal(particle.pos.y),
+    );
+    Some(print_particle(&decl))
+}
+
+/// Print one line of code in canonical form
+///
+/// Declarations are printed from their AST when that round-trips through the
+/// parser; anything else (block headers, calls) only has its spacing normalized
+fn format_line(line: &str) -> String {
+    let (code, semicolon) = match line.strip_suffix(';') {
+        Some(code) => (code.trim_end(), ";"),
+        None => (line, ""),
+    };
+    match print_declaration(code) {
+        Some(printed) if print_declaration(&printed).as_ref() == Some(&printed) => {
+            format!("{}{}", printed, semicolon)
+        }
+        _ => normalize_spacing(line),
+    }
+}
+
+/// Parse a single-line declaration and print it back, or `None` if the line
+/// isn't one (or doesn't parse on its own)
+fn print_declaration(line: &str) -> Option<String> {
+    let printed = if line.starts_with("let ") {
+        print_let(&parser::parse_let(line, None).ok()?)
+    } else if line.starts_with("particle ") {
+        print_particle(&parser::parse_particle(line, None).ok()?)
+    } else if line.starts_with("force push(") {
+        print_push(&parser::parse_loop_body_stmt(line, None).ok()?)
+    } else if line.starts_with("force ") {
+        print_force(&parser::parse_force(line, None).ok()?)
+    } else if line.starts_with("simulate ") {
+        print_simulate(&parser::parse_simulate(line, None).ok()?)
+    } else if line.starts_with("detect ") {
+        print_detector(&parser::parse_detector(line, None).ok()?)
+    } else if line.starts_with("well ") {
+        print_well(&parser::parse_well(line, None).ok()?)
+    } else if line.starts_with("ground ") {
+        print_ground(&parser::parse_ground(line, None).ok()?)
+    } else if let Some(expr) = line.strip_prefix("return ") {
+        format!("return {}", print_expr(&parser::parse_expression(expr).ok()?))
+    } else {
+        return None;
+    };
+    Some(printed)
+}
+
+fn print_let(decl: &LetDecl) -> String {
+    format!("let {} = {}", decl.name, print_expr(&decl.expr))
+}
+
+fn print_particle(decl: &ParticleDecl) -> String {
+    let mut out = format!(
+        "particle {} at ({}, {}) mass {}",
+        decl.name,
+        print_expr(&decl.position.0),
+        print_expr(&decl.position.1),
+        print_expr(&decl.mass)
+    );
+    if let Some(damping) = &decl.damping {
+        out.push_str(&format!(" damping {}", print_expr(damping)));
+    }
+    for (tag, value) in &decl.tags {
+        out.push_str(&format!(" tag {} = {}", tag, print_expr(value)));
+    }
+    out
+}
+
+fn print_force(decl: &ForceDecl) -> String {
+    match &decl.kind {
+        ForceKind::Gravity { g } => {
+            format!("force gravity({}, {}) G = {}", decl.a, decl.b, print_expr(g))
+        }
+        ForceKind::Spring { k, rest } => format!(
+            "force spring({}, {}) k = {} rest = {}",
+            decl.a,
+            decl.b,
+            print_expr(k),
+            print_expr(rest)
+        ),
+        ForceKind::Central { point, gm } => format!(
+            "force central({}) at ({}, {}) GM = {}",
+            decl.a,
+            print_expr(&point.0),
+            print_expr(&point.1),
+            print_expr(gm)
+        ),
+    }
+}
+
+fn print_push(stmt: &LoopBodyStmt) -> String {
+    match stmt {
+        LoopBodyStmt::ForcePush {
+            particle,
+            magnitude,
+            direction,
+        } => format!(
+            "force push({}) magnitude {} direction ({}, {})",
+            particle,
+            print_expr(magnitude),
+            print_expr(&direction.0),
+            print_expr(&direction.1)
+        ),
+    }
+}
+
+fn print_simulate(decl: &SimulateDecl) -> String {
+    let mut out = format!(
+        "simulate dt = {} steps = {}",
+        print_expr(&decl.dt),
+        print_count(&decl.steps)
+    );
+    if let Frame::CenterOfMass { recenter } = decl.frame {
+        out.push_str(" frame = center_of_mass");
+        if recenter == Recenter::Always {
+            out.push_str(" recenter = always");
+        }
+    }
+    out
+}
+
+fn print_detector(decl: &DetectorDecl) -> String {
+    let expr = print_expr(&decl.expr);
+    if decl.initial {
+        format!("detect {} = {} initial", decl.name, expr)
+    } else {
+        format!("detect {} = {}", decl.name, expr)
+    }
+}
+
+fn print_observable(kind: &DetectorKind) -> String {
+    match kind {
+        DetectorKind::Position(name) => format!("position({})", name),
+        DetectorKind::PositionY(name) => format!("position({}).y", name),
+        DetectorKind::VelocityX(name) => format!("velocity({}).x", name),
+        DetectorKind::VelocityY(name) => format!("velocity({}).y", name),
+        DetectorKind::Distance { a, b } => format!("distance({}, {})", a, b),
+        DetectorKind::CollisionCount { a, b } => format!("collision_count({}, {})", a, b),
+        DetectorKind::KineticEnergy => "kinetic_energy()".to_string(),
+        DetectorKind::PotentialEnergy => "potential_energy()".to_string(),
+        DetectorKind::TotalEnergy => "total_energy()".to_string(),
+        DetectorKind::Momentum => "momentum()".to_string(),
+        DetectorKind::CenterOfMass => "center_of_mass()".to_string(),
+        DetectorKind::CenterOfMassY => "center_of_mass().y".to_string(),
+        DetectorKind::Time => "time()".to_string(),
+    }
+}
+
+fn print_well(decl: &WellDecl) -> String {
+    let observable = match &decl.observable {
+        ObservableExpr::PositionX(name) => format!("position({}).x", name),
+        ObservableExpr::PositionY(name) => format!("position({}).y", name),
+        ObservableExpr::Distance(a, b) => format!("distance({}, {})", a, b),
+        ObservableExpr::Mass(name) => format!("mass({})", name),
+        ObservableExpr::Tag(name, tag) => format!("tag({}, {})", name, tag),
+    };
+    format!(
+        "well {} on {} if {} >= {} depth {}",
+        decl.name,
+        decl.particle,
+        observable,
+        print_expr(&decl.threshold),
+        print_expr(&decl.depth)
+    )
+}
+
+fn print_ground(decl: &GroundDecl) -> String {
+    let mut out = format!(
+        "ground y = {} friction {}",
+        print_expr(&decl.y),
+        print_expr(&decl.friction)
+    );
+    if let Some(gravity) = &decl.gravity {
+        out.push_str(&format!(" gravity {}", print_expr(gravity)));
+    }
+    out
+}
+
+/// Print an expression with single spaces around binary operators
+pub fn print_expr(expr: &Expr) -> String {
+    print_expr_at(expr, 0)
+}
+
+/// Binding strength of each level, matching the parser's precedence
+fn precedence(op: BinaryOp) -> u8 {
+    match op {
+        BinaryOp::GreaterThan
+        | BinaryOp::LessThan
+        | BinaryOp::GreaterEqual
+        | BinaryOp::LessEqual
+        | BinaryOp::Equal
+        | BinaryOp::NotEqual => 1,
+        BinaryOp::Add | BinaryOp::Sub => 2,
+        BinaryOp::Mul | BinaryOp::Div => 3,
+    }
+}
+
+const UNARY_PRECEDENCE: u8 = 4;
+
+/// Print `expr`, parenthesized if it binds looser than `min_precedence`
+fn print_expr_at(expr: &Expr, min_precedence: u8) -> String {
+    match expr {
+        Expr::Literal(value) => print_number(*value),
+        Expr::StringLiteral(text) => format!("\"{}\"", text),
+        Expr::Var(name) => name.clone(),
+        Expr::UnaryMinus(inner) => format!("-{}", print_expr_at(inner, UNARY_PRECEDENCE)),
+        Expr::Binary { op, left, right } => {
+            let own = precedence(*op);
+            // Operators associate to the left, so only the right operand
+            // needs parentheses at equal precedence
+            let text = format!(
+                "{} {} {}",
+                print_expr_at(left, own),
+                operator(*op),
+                print_expr_at(right, own + 1)
+            );
+            if own < min_precedence {
+                format!("({})", text)
+            } else {
+                text
+            }
+        }
+        Expr::Call { func, args } => {
+            let name = match func {
+                FuncName::Sin => "sin",
+                FuncName::Cos => "cos",
+                FuncName::Sqrt => "sqrt",
+                FuncName::Clamp => "clamp",
+            };
+            format!("{}({})", name, print_args(args))
+        }
+        Expr::UserCall { name, args } => format!("{}({})", name, print_args(args)),
+        Expr::Observable(kind) => print_observable(kind),
+    }
+}
+
+fn print_args(args: &[Expr]) -> String {
+    args.iter().map(print_expr).collect::<Vec<_>>().join(", ")
+}
+
+fn operator(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::GreaterThan => ">",
+        BinaryOp::LessThan => "<",
+        BinaryOp::GreaterEqual => ">=",
+        BinaryOp::LessEqual => "<=",
+        BinaryOp::Equal => "==",
+        BinaryOp::NotEqual => "!=",
+    }
+}
+
+/// Print a number without exponent notation (the parser would split `1e-7`
+/// at the minus sign), always with a decimal point
+fn print_number(value: f32) -> String {
+    let text = value.to_string();
+    if value.is_finite() && !text.contains('.') {
+        format!("{}.0", text)
+    } else {
+        text
+    }
+}
+
+/// Print a step count as a whole number when it is one
+fn print_count(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(value) if value.fract() == 0.0 && value.abs() < 1e7 => {
+            format!("{}", *value as i64)
+        }
+        _ => print_expr(expr),
+    }
+}
+
+/// Collapse runs of whitespace outside string literals to single spaces
+fn normalize_spacing(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_string = false;
+    let mut pending_space = false;
+    for c in line.chars() {
+        if !in_string && c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space && !out.is_empty() {
+            out.push(' ');
+        }
+        pending_space = false;
+        if c == '"' {
+            in_string = !in_string;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Split a trimmed line into its code and its `#` comment, if any
+pub(crate) fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return (line[..i].trim_end(), Some(&line[i..])),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// Count `{` and `}` outside string literals and comments
+pub(crate) fn count_braces(line: &str) -> (usize, usize) {
+    let mut opens = 0;
+    let mut closes = 0;
+    let mut in_string = false;
+    for c in split_comment(line).0.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' if !in_string => opens += 1,
+            '}' if !in_string => closes += 1,
+            _ => {}
+        }
+    }
+    (opens, closes)
+}
@@ -2,11 +2,125 @@
 //!
 //! This module executes user-defined functions before simulation,
 //! generating world-building statements (particles, forces, etc.)
+//!
+//! Generated statements are appended in execution order, which is source order:
+//! top-level statements in the order written, loop iterations in ascending order,
+//! and statements within a body in the order written. Particle indices, detectors,
+//! state hashes, and golden outputs all depend on this, so nothing here may iterate
+//! a hash-ordered collection.
 
 use crate::ast::{Expr, FunctionDecl, Program, Stmt};
-use crate::diagnostics::Diagnostic;
-use crate::eval::{eval_expr_with_function_ctx, EvalContext, FunctionEvalContext};
-use std::collections::HashMap;
+use crate::diagnostics::{Diagnostic, Span};
+use crate::eval::{eval_expr, eval_expr_with_function_ctx, EvalContext, FunctionEvalContext};
+use crate::phase;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+/// Function lookup by name (ordered, so any iteration is deterministic)
+type FunctionMap<'a> = BTreeMap<String, &'a FunctionDecl>;
+
+/// How much world functions may generate before expansion is aborted, so a
+/// runaway loop fails fast instead of exhausting memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpansionLimits {
+    /// Declarations generated by function calls and top-level control flow
+    pub max_statements: usize,
+    /// Particles in the expanded world, declared or generated
+    pub max_particles: usize,
+}
+
+impl Default for ExpansionLimits {
+    fn default() -> Self {
+        Self {
+            max_statements: 1_000_000,
+            max_particles: 100_000,
+        }
+    }
+}
+
+/// What function execution shares: the functions, and how much has been
+/// generated against the limits
+struct Expansion<'a> {
+    functions: FunctionMap<'a>,
+    limits: ExpansionLimits,
+    generated: Cell<usize>,
+    /// Set once a limit is exceeded, which aborts the whole expansion
+    exceeded: Cell<bool>,
+}
+
+impl<'a> Expansion<'a> {
+    fn get(&self, name: &str) -> Option<&'a FunctionDecl> {
+        self.functions.get(name).copied()
+    }
+
+    /// Count a declaration just added to `program`, failing once it puts the
+    /// world over a limit
+    fn generated(&self, program: &Program) -> Result<(), CallError> {
+        let generated = self.generated.get() + 1;
+        self.generated.set(generated);
+        if generated > self.limits.max_statements {
+            self.exceeded.set(true);
+            return Err(format!(
+                "too many generated declarations: {} exceeds the limit of {}",
+                generated, self.limits.max_statements
+            )
+            .into());
+        }
+        let particles = program.particles.len();
+        if particles > self.limits.max_particles {
+            self.exceeded.set(true);
+            return Err(format!(
+                "too many particles: {} exceeds the limit of {}",
+                particles, self.limits.max_particles
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// An error raised while executing functions, with the calls it unwound
+/// through, innermost first, e.g. `exposure("A", 0)`
+#[derive(Debug, Clone, PartialEq)]
+struct CallError {
+    message: String,
+    calls: Vec<String>,
+}
+
+impl From<String> for CallError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            calls: Vec::new(),
+        }
+    }
+}
+
+impl CallError {
+    /// Record that the error unwound through `call`
+    fn called_from(mut self, call: String) -> Self {
+        self.calls.push(call);
+        self
+    }
+
+    /// The message and its backtrace, ending at the top-level statement
+    /// `origin` describes
+    fn trace(&self, origin: &str) -> String {
+        let mut trace = self.message.clone();
+        for (i, call) in self.calls.iter().enumerate() {
+            trace.push_str(if i == 0 { " in " } else { " called from " });
+            trace.push_str(call);
+        }
+        trace.push_str(if self.calls.is_empty() { " at " } else { " called from " });
+        trace.push_str(origin);
+        trace
+    }
+}
+
+/// `name(args)` as a backtrace shows a call
+fn describe_call(name: &str, args: &[String]) -> String {
+    format!("{}({})", name, args.join(", "))
+}
 
 /// Evaluate an expression that may contain user-defined function calls
 /// This function handles both built-in functions (via eval_expr_with_function_ctx)
@@ -15,20 +129,20 @@ fn eval_expr_with_user_calls(
     expr: &Expr,
     func_ctx: &mut FunctionEvalContext<'_>,
     program: &mut Program,
-    function_map: &HashMap<String, &FunctionDecl>,
-) -> Result<f32, String> {
+    expansion: &Expansion<'_>,
+) -> Result<f32, CallError> {
     match expr {
         Expr::UserCall { name, args } => {
             // This is a user-defined function call that should return a value
             // Evaluate arguments first
             let mut arg_values = Vec::new();
             for arg in args {
-                let value = eval_expr_with_user_calls(arg, func_ctx, program, function_map)?;
+                let value = eval_expr_with_user_calls(arg, func_ctx, program, expansion)?;
                 arg_values.push(value);
             }
             
             // Look up the function
-            let func = function_map
+            let func = expansion
                 .get(name.as_str())
                 .ok_or_else(|| format!("Unknown function '{}'", name))?;
             
@@ -38,9 +152,17 @@ fn eval_expr_with_user_calls(
                     name,
                     func.params.len(),
                     arg_values.len()
-                ));
+                )
+                .into());
             }
             
+            // Arguments of expression calls are all numbers
+            for (idx, arg_value) in arg_values.iter().enumerate() {
+                if let Some(message) = func.argument_mismatch(idx, &Expr::Literal(*arg_value)) {
+                    return Err(message.into());
+                }
+            }
+
             // Create new function context for the called function
             let mut new_func_ctx = FunctionEvalContext::new(func_ctx.global);
             for (param_name, arg_value) in func.params.iter().zip(arg_values.iter()) {
@@ -48,14 +170,19 @@ fn eval_expr_with_user_calls(
             }
             
             // Execute function body and get return value
-            match execute_statements_with_user_calls(&func.body, &mut new_func_ctx, program, function_map)? {
+            let args: Vec<String> = arg_values.iter().map(|v| v.to_string()).collect();
+            let call = describe_call(name, &args);
+            match execute_statements_with_user_calls(&func.body, &mut new_func_ctx, program, expansion)
+                .map_err(|e| e.called_from(call.clone()))?
+            {
                 Some(value) => Ok(value),
-                None => Err(format!("Function '{}' did not return a value", name)),
+                None => Err(CallError::from(format!("Function '{}' did not return a value", name))
+                    .called_from(call)),
             }
         }
         Expr::Binary { op, left, right } => {
-            let left_val = eval_expr_with_user_calls(left, func_ctx, program, function_map)?;
-            let right_val = eval_expr_with_user_calls(right, func_ctx, program, function_map)?;
+            let left_val = eval_expr_with_user_calls(left, func_ctx, program, expansion)?;
+            let right_val = eval_expr_with_user_calls(right, func_ctx, program, expansion)?;
             
             use crate::ast::BinaryOp;
             match op {
@@ -64,7 +191,7 @@ fn eval_expr_with_user_calls(
                 BinaryOp::Mul => Ok(left_val * right_val),
                 BinaryOp::Div => {
                     if right_val == 0.0 {
-                        return Err("Division by zero".to_string());
+                        return Err("Division by zero".to_string().into());
                     }
                     Ok(left_val / right_val)
                 }
@@ -77,12 +204,12 @@ fn eval_expr_with_user_calls(
             }
         }
         Expr::UnaryMinus(inner) => {
-            let val = eval_expr_with_user_calls(inner, func_ctx, program, function_map)?;
+            let val = eval_expr_with_user_calls(inner, func_ctx, program, expansion)?;
             Ok(-val)
         }
         // For other expressions, fall back to the standard eval
         _ => eval_expr_with_function_ctx(expr, func_ctx.global, Some(func_ctx))
-            .map_err(|e| format!("{}", e))
+            .map_err(|e| format!("{}", e).into())
     }
 }
 
@@ -91,22 +218,22 @@ fn execute_statements_with_user_calls(
     stmts: &[Stmt],
     func_ctx: &mut FunctionEvalContext<'_>,
     program: &mut Program,
-    function_map: &HashMap<String, &FunctionDecl>,
-) -> Result<Option<f32>, String> {
+    expansion: &Expansion<'_>,
+) -> Result<Option<f32>, CallError> {
     for stmt in stmts {
         match stmt {
             Stmt::Let { name, expr } => {
-                let value = eval_expr_with_user_calls(expr, func_ctx, program, function_map)?;
+                let value = eval_expr_with_user_calls(expr, func_ctx, program, expansion)?;
                 func_ctx.local_lets.insert(name.clone(), value);
             }
             Stmt::Return(expr) => {
-                let value = eval_expr_with_user_calls(expr, func_ctx, program, function_map)?;
+                let value = eval_expr_with_user_calls(expr, func_ctx, program, expansion)?;
                 return Ok(Some(value));
             }
             // For other statements, delegate to execute_statements
             other => {
                 // We need to handle this specially to support user calls
-                if let Some(result) = execute_single_statement_with_user_calls(other, func_ctx, program, function_map)? {
+                if let Some(result) = execute_single_statement_with_user_calls(other, func_ctx, program, expansion)? {
                     return Ok(Some(result));
                 }
             }
@@ -120,11 +247,11 @@ fn execute_single_statement_with_user_calls(
     stmt: &Stmt,
     func_ctx: &mut FunctionEvalContext<'_>,
     program: &mut Program,
-    function_map: &HashMap<String, &FunctionDecl>,
-) -> Result<Option<f32>, String> {
+    expansion: &Expansion<'_>,
+) -> Result<Option<f32>, CallError> {
     match stmt {
         Stmt::Let { name, expr } => {
-            let value = eval_expr_with_user_calls(expr, func_ctx, program, function_map)?;
+            let value = eval_expr_with_user_calls(expr, func_ctx, program, expansion)?;
             func_ctx.local_lets.insert(name.clone(), value);
             Ok(None)
         }
@@ -140,7 +267,7 @@ fn execute_single_statement_with_user_calls(
                     }
                     _ => {
                         // Evaluate numeric expressions
-                        let value = eval_expr_with_user_calls(arg, func_ctx, program, function_map)?;
+                        let value = eval_expr_with_user_calls(arg, func_ctx, program, expansion)?;
                         arg_exprs.push(Expr::Literal(value));
                     }
                 }
@@ -150,7 +277,7 @@ fn execute_single_statement_with_user_calls(
             execute_function_call(
                 name,
                 &arg_exprs,
-                function_map,
+                expansion,
                 func_ctx.global,
                 program,
                 Some(func_ctx),
@@ -158,13 +285,22 @@ fn execute_single_statement_with_user_calls(
             Ok(None)
         }
         Stmt::Return(expr) => {
-            let value = eval_expr_with_user_calls(expr, func_ctx, program, function_map)?;
+            let value = eval_expr_with_user_calls(expr, func_ctx, program, expansion)?;
             Ok(Some(value))
         }
         Stmt::ParticleDecl(particle) => {
-            let x = eval_expr_with_user_calls(&particle.position.0, func_ctx, program, function_map)?;
-            let y = eval_expr_with_user_calls(&particle.position.1, func_ctx, program, function_map)?;
-            let mass = eval_expr_with_user_calls(&particle.mass, func_ctx, program, function_map)?;
+            let x = eval_expr_with_user_calls(&particle.position.0, func_ctx, program, expansion)?;
+            let y = eval_expr_with_user_calls(&particle.position.1, func_ctx, program, expansion)?;
+            let mass = eval_expr_with_user_calls(&particle.mass, func_ctx, program, expansion)?;
+            let damping = match &particle.damping {
+                Some(expr) => Some(eval_expr_with_user_calls(expr, func_ctx, program, expansion)?),
+                None => None,
+            };
+            let mut tags = Vec::with_capacity(particle.tags.len());
+            for (tag, expr) in &particle.tags {
+                let value = eval_expr_with_user_calls(expr, func_ctx, program, expansion)?;
+                tags.push((tag.clone(), Expr::Literal(value)));
+            }
             
             let mut new_particle = particle.clone();
             
@@ -175,8 +311,11 @@ fn execute_single_statement_with_user_calls(
             
             new_particle.position = (Expr::Literal(x), Expr::Literal(y));
             new_particle.mass = Expr::Literal(mass);
+            new_particle.damping = damping.map(Expr::Literal);
+            new_particle.tags = tags;
             
             program.particles.push(new_particle);
+            expansion.generated(program)?;
             Ok(None)
         }
         Stmt::ForceDecl(force) => {
@@ -192,31 +331,41 @@ fn execute_single_statement_with_user_calls(
             
             match &mut new_force.kind {
                 crate::ast::ForceKind::Gravity { g } => {
-                    let g_val = eval_expr_with_user_calls(g, func_ctx, program, function_map)?;
+                    let g_val = eval_expr_with_user_calls(g, func_ctx, program, expansion)?;
                     *g = Expr::Literal(g_val);
                 }
                 crate::ast::ForceKind::Spring { k, rest } => {
-                    let k_val = eval_expr_with_user_calls(k, func_ctx, program, function_map)?;
-                    let rest_val = eval_expr_with_user_calls(rest, func_ctx, program, function_map)?;
+                    let k_val = eval_expr_with_user_calls(k, func_ctx, program, expansion)?;
+                    let rest_val = eval_expr_with_user_calls(rest, func_ctx, program, expansion)?;
                     *k = Expr::Literal(k_val);
                     *rest = Expr::Literal(rest_val);
                 }
+                crate::ast::ForceKind::Central { point, gm } => {
+                    let x_val = eval_expr_with_user_calls(&point.0, func_ctx, program, expansion)?;
+                    let y_val = eval_expr_with_user_calls(&point.1, func_ctx, program, expansion)?;
+                    let gm_val = eval_expr_with_user_calls(gm, func_ctx, program, expansion)?;
+                    *point = (Expr::Literal(x_val), Expr::Literal(y_val));
+                    *gm = Expr::Literal(gm_val);
+                }
             }
             program.forces.push(new_force);
+            expansion.generated(program)?;
             Ok(None)
         }
         Stmt::If { condition, then_branch, else_branch } => {
-            let cond_val = eval_expr_with_user_calls(condition, func_ctx, program, function_map)?;
+            let cond_val = eval_expr_with_user_calls(condition, func_ctx, program, expansion)?;
             let branch = if cond_val != 0.0 { then_branch } else { else_branch };
-            execute_statements_with_user_calls(branch, func_ctx, program, function_map)
+            execute_statements_with_user_calls(branch, func_ctx, program, expansion)
         }
         Stmt::For { var_name, start, end, body } => {
-            let start_val = eval_expr_with_user_calls(start, func_ctx, program, function_map)? as i64;
-            let end_val = eval_expr_with_user_calls(end, func_ctx, program, function_map)? as i64;
+            let start_val = eval_expr_with_user_calls(start, func_ctx, program, expansion)? as i64;
+            let end_val = eval_expr_with_user_calls(end, func_ctx, program, expansion)? as i64;
             
             for i in start_val..end_val {
                 func_ctx.local_lets.insert(var_name.clone(), i as f32);
-                if let Some(result) = execute_statements_with_user_calls(body, func_ctx, program, function_map)? {
+                let result = execute_statements_with_user_calls(body, func_ctx, program, expansion)
+                    .map_err(|e| e.called_from(describe_loop(var_name, start_val, end_val, i)))?;
+                if let Some(result) = result {
                     return Ok(Some(result));
                 }
             }
@@ -224,7 +373,7 @@ fn execute_single_statement_with_user_calls(
             Ok(None)
         }
         Stmt::Match { scrutinee, arms } => {
-            let scrutinee_val = eval_expr_with_user_calls(scrutinee, func_ctx, program, function_map)? as i64;
+            let scrutinee_val = eval_expr_with_user_calls(scrutinee, func_ctx, program, expansion)? as i64;
             
             for arm in arms {
                 let matches = match &arm.pattern {
@@ -233,7 +382,7 @@ fn execute_single_statement_with_user_calls(
                 };
                 
                 if matches {
-                    return execute_statements_with_user_calls(&arm.body, func_ctx, program, function_map);
+                    return execute_statements_with_user_calls(&arm.body, func_ctx, program, expansion);
                 }
             }
             Ok(None)
@@ -241,42 +390,62 @@ fn execute_single_statement_with_user_calls(
         // Delegate remaining statements to old handler
         _ => {
             // Use the old execute_statements for remaining cases
-            execute_statements(&[stmt.clone()], func_ctx, program, function_map)
+            execute_statements(&[stmt.clone()], func_ctx, program, expansion)
         }
     }
 }
 
 /// Execute all functions in the program, generating world-building statements
+/// Statements are generated in source order (see the module docs)
 pub fn execute_functions(
     program: &mut Program,
     eval_ctx: &EvalContext<'_>,
 ) -> Vec<Diagnostic> {
+    execute_functions_with_source(program, eval_ctx, None, &ExpansionLimits::default())
+}
+
+/// `execute_functions` for a program parsed from `source`, whose error
+/// backtraces end at the line of the top-level statement, generating no more
+/// than `limits` allow
+pub fn execute_functions_with_source(
+    program: &mut Program,
+    eval_ctx: &EvalContext<'_>,
+    source: Option<&str>,
+    limits: &ExpansionLimits,
+) -> Vec<Diagnostic> {
+    let _phase = phase::enter("functions");
     let mut diagnostics = Vec::new();
 
     // Clone what we need before taking mutable borrows
     // Use a block to ensure the borrow of program.functions ends
-    let (functions, top_level_calls) = {
+    let (functions, top_level_calls, call_spans) = {
         let functions = program.functions.clone();
         let calls = std::mem::take(&mut program.top_level_calls);
-        (functions, calls)
+        let spans = std::mem::take(&mut program.spans.top_level_calls);
+        (functions, calls, spans)
     };
     
     // Build function map from cloned functions
-    let function_map: HashMap<String, &FunctionDecl> = functions
-        .iter()
-        .map(|f| (f.name.clone(), f))
-        .collect();
+    let expansion = Expansion {
+        functions: functions.iter().map(|f| (f.name.clone(), f)).collect(),
+        limits: *limits,
+        generated: Cell::new(0),
+        exceeded: Cell::new(false),
+    };
 
     // Execute top-level statements (function calls and control flow)
-    for stmt in top_level_calls {
+    for (idx, stmt) in top_level_calls.into_iter().enumerate() {
+        let span = call_spans.get(idx).copied();
+        // Only located on error, which is a scan of the source
+        let origin = || top_level(span, source);
         match stmt {
             Stmt::ExprCall { name, args } => {
-                match execute_function_call(&name, &args, &function_map, eval_ctx, program, None) {
+                match execute_function_call(&name, &args, &expansion, eval_ctx, program, None) {
                     Ok(()) => {}
                     Err(e) => {
                         diagnostics.push(Diagnostic::error(
-                            format!("Error executing function '{}': {}", name, e),
-                            None,
+                            format!("Error executing function '{}': {}", name, e.trace(&origin())),
+                            span,
                         ));
                     }
                 }
@@ -285,32 +454,96 @@ pub fn execute_functions(
             _ => {
                 // Create a minimal function context for top-level execution
                 let mut top_ctx = FunctionEvalContext::new(eval_ctx);
-                match execute_statements_with_user_calls(&[stmt], &mut top_ctx, program, &function_map) {
+                match execute_statements_with_user_calls(&[stmt], &mut top_ctx, program, &expansion) {
                     Ok(_) => {}
                     Err(e) => {
                         diagnostics.push(Diagnostic::error(
-                            format!("Error executing top-level statement: {}", e),
-                            None,
+                            format!("Error executing top-level statement: {}", e.trace(&origin())),
+                            span,
                         ));
                     }
                 }
             }
         }
+        if expansion.exceeded.get() {
+            break;
+        }
     }
 
     diagnostics
 }
 
+/// Evaluate `expr` outside any function, such as the `steps` of `simulate`,
+/// calling the functions of `program` if it calls any
+///
+/// Declarations those calls make are not added to the world; `place` ends
+/// the backtrace of a failing call
+pub(crate) fn eval_global_expr(
+    expr: &Expr,
+    program: &Program,
+    eval_ctx: &EvalContext<'_>,
+    place: &str,
+) -> Result<f32, String> {
+    if !calls_user_function(expr) {
+        return eval_expr(expr, eval_ctx).map_err(|e| e.to_string());
+    }
+    let mut func_ctx = FunctionEvalContext::new(eval_ctx);
+    let expansion = Expansion {
+        functions: program.functions.iter().map(|f| (f.name.clone(), f)).collect(),
+        limits: ExpansionLimits::default(),
+        generated: Cell::new(0),
+        exceeded: Cell::new(false),
+    };
+    let mut scratch = Program {
+        lets: program.lets.clone(),
+        functions: Vec::new(),
+        top_level_calls: Vec::new(),
+        particles: Vec::new(),
+        forces: Vec::new(),
+        simulate: program.simulate.clone(),
+        detectors: Vec::new(),
+        loops: Vec::new(),
+        wells: Vec::new(),
+        ground: None,
+        spans: Default::default(),
+    };
+    eval_expr_with_user_calls(expr, &mut func_ctx, &mut scratch, &expansion)
+        .map_err(|e| e.trace(place))
+}
+
+fn calls_user_function(expr: &Expr) -> bool {
+    match expr {
+        Expr::UserCall { .. } => true,
+        Expr::UnaryMinus(e) => calls_user_function(e),
+        Expr::Binary { left, right, .. } => calls_user_function(left) || calls_user_function(right),
+        Expr::Call { args, .. } => args.iter().any(calls_user_function),
+        Expr::Literal(_) | Expr::StringLiteral(_) | Expr::Var(_) | Expr::Observable(_) => false,
+    }
+}
+
+/// `for i in 0..n at i = 3` as a backtrace shows a loop iteration
+fn describe_loop(var_name: &str, start: i64, end: i64, i: i64) -> String {
+    format!("for {} in {}..{} at {} = {}", var_name, start, end, var_name, i)
+}
+
+/// "top level", with the line of `span` when the source is known
+fn top_level(span: Option<Span>, source: Option<&str>) -> String {
+    match span.zip(source) {
+        Some((span, source)) => format!("top level (line {})", span.to_location(source).line),
+        None => "top level".to_string(),
+    }
+}
+
 /// Execute a single function call
 fn execute_function_call(
     func_name: &str,
     args: &[Expr],
-    function_map: &HashMap<String, &FunctionDecl>,
+    expansion: &Expansion<'_>,
     global_ctx: &EvalContext<'_>,
     program: &mut Program,
     caller_ctx: Option<&FunctionEvalContext<'_>>,
-) -> Result<(), String> {
-    let func = function_map
+) -> Result<(), CallError> {
+    let func = expansion
         .get(func_name)
         .ok_or_else(|| format!("Unknown function '{}'", func_name))?;
 
@@ -320,7 +553,8 @@ fn execute_function_call(
             func_name,
             func.params.len(),
             args.len()
-        ));
+        )
+        .into());
     }
 
     // Create new function execution context for the called function
@@ -328,23 +562,30 @@ fn execute_function_call(
     
     // Evaluate arguments and store in context
     // Handle string literals separately
-    for (param_name, arg) in func.params.iter().zip(args.iter()) {
+    let mut shown_args = Vec::new();
+    for (idx, (param_name, arg)) in func.params.iter().zip(args.iter()).enumerate() {
+        if let Some(message) = func.argument_mismatch(idx, arg) {
+            return Err(message.into());
+        }
         match arg {
             Expr::StringLiteral(s) => {
                 // Store string parameter
                 func_ctx.string_params.insert(param_name.clone(), s.clone());
+                shown_args.push(format!("{:?}", s));
             }
             _ => {
                 // Evaluate numeric expression
                 let value = eval_expr_with_function_ctx(arg, global_ctx, caller_ctx)
                     .map_err(|e| format!("Error evaluating argument '{}': {}", param_name, e))?;
                 func_ctx.params.insert(param_name.clone(), value);
+                shown_args.push(value.to_string());
             }
         }
     }
 
     // Execute function body with support for user-defined function calls
-    execute_statements_with_user_calls(&func.body, &mut func_ctx, program, function_map)?;
+    execute_statements_with_user_calls(&func.body, &mut func_ctx, program, expansion)
+        .map_err(|e| e.called_from(describe_call(func_name, &shown_args)))?;
 
     Ok(())
 }
@@ -354,8 +595,8 @@ fn execute_statements(
     stmts: &[Stmt],
     func_ctx: &mut FunctionEvalContext<'_>,
     program: &mut Program,
-    function_map: &HashMap<String, &FunctionDecl>,
-) -> Result<Option<f32>, String> {
+    expansion: &Expansion<'_>,
+) -> Result<Option<f32>, CallError> {
     for stmt in stmts {
         match stmt {
             Stmt::Let { name, expr } => {
@@ -382,7 +623,7 @@ fn execute_statements(
                 execute_function_call(
                     name,
                     &arg_exprs,
-                    function_map,
+                    expansion,
                     func_ctx.global,
                     program,
                     Some(func_ctx),
@@ -401,13 +642,29 @@ fn execute_statements(
                     .map_err(|e| format!("Error evaluating particle y position: {}", e))?;
                 let mass = eval_expr_with_function_ctx(&particle.mass, func_ctx.global, Some(func_ctx))
                     .map_err(|e| format!("Error evaluating particle mass: {}", e))?;
+                let damping = match &particle.damping {
+                    Some(expr) => Some(
+                        eval_expr_with_function_ctx(expr, func_ctx.global, Some(func_ctx))
+                            .map_err(|e| format!("Error evaluating particle damping: {}", e))?,
+                    ),
+                    None => None,
+                };
+                let mut tags = Vec::with_capacity(particle.tags.len());
+                for (tag, expr) in &particle.tags {
+                    let value = eval_expr_with_function_ctx(expr, func_ctx.global, Some(func_ctx))
+                        .map_err(|e| format!("Error evaluating particle tag {}: {}", tag, e))?;
+                    tags.push((tag.clone(), Expr::Literal(value)));
+                }
                 
                 // Create new particle with evaluated expressions
                 let mut new_particle = particle.clone();
                 new_particle.position = (Expr::Literal(x), Expr::Literal(y));
                 new_particle.mass = Expr::Literal(mass);
+                new_particle.damping = damping.map(Expr::Literal);
+                new_particle.tags = tags;
                 
                 program.particles.push(new_particle);
+                expansion.generated(program)?;
             }
             Stmt::ForceDecl(force) => {
                 // Evaluate force expressions
@@ -426,8 +683,19 @@ fn execute_statements(
                         *k = Expr::Literal(k_val);
                         *rest = Expr::Literal(rest_val);
                     }
+                    crate::ast::ForceKind::Central { point, gm } => {
+                        let x_val = eval_expr_with_function_ctx(&point.0, func_ctx.global, Some(func_ctx))
+                            .map_err(|e| format!("Error evaluating central force x: {}", e))?;
+                        let y_val = eval_expr_with_function_ctx(&point.1, func_ctx.global, Some(func_ctx))
+                            .map_err(|e| format!("Error evaluating central force y: {}", e))?;
+                        let gm_val = eval_expr_with_function_ctx(gm, func_ctx.global, Some(func_ctx))
+                            .map_err(|e| format!("Error evaluating central force GM: {}", e))?;
+                        *point = (Expr::Literal(x_val), Expr::Literal(y_val));
+                        *gm = Expr::Literal(gm_val);
+                    }
                 }
                 program.forces.push(new_force);
+                expansion.generated(program)?;
             }
             Stmt::LoopDecl(loop_decl) => {
                 // Evaluate loop expressions
@@ -474,6 +742,7 @@ fn execute_statements(
                     }
                 }
                 program.loops.push(new_loop);
+                expansion.generated(program)?;
             }
             Stmt::WellDecl(well) => {
                 // Evaluate well expressions
@@ -485,9 +754,11 @@ fn execute_statements(
                 new_well.threshold = Expr::Literal(threshold_val);
                 new_well.depth = Expr::Literal(depth_val);
                 program.wells.push(new_well);
+                expansion.generated(program)?;
             }
             Stmt::DetectorDecl(detector) => {
                 program.detectors.push(detector.clone());
+                expansion.generated(program)?;
             }
             // v0.8: Control flow statements
             Stmt::If {
@@ -505,11 +776,11 @@ fn execute_statements(
                 if cond_true {
                     // Execute then branch in new scope
                     let mut then_ctx = func_ctx.clone_scope();
-                    execute_statements(then_branch, &mut then_ctx, program, function_map)?;
+                    execute_statements(then_branch, &mut then_ctx, program, expansion)?;
                 } else if !else_branch.is_empty() {
                     // Execute else branch in new scope
                     let mut else_ctx = func_ctx.clone_scope();
-                    execute_statements(else_branch, &mut else_ctx, program, function_map)?;
+                    execute_statements(else_branch, &mut else_ctx, program, expansion)?;
                 }
             }
             Stmt::For {
@@ -536,7 +807,7 @@ fn execute_statements(
                     loop_ctx.local_lets.insert(var_name.clone(), i as f32);
                     
                     // Execute body
-                    match execute_statements(body, &mut loop_ctx, program, function_map) {
+                    match execute_statements(body, &mut loop_ctx, program, expansion) {
                         Ok(Some(return_val)) => {
                             // Return from function
                             return Ok(Some(return_val));
@@ -544,7 +815,9 @@ fn execute_statements(
                         Ok(None) => {
                             // Continue loop
                         }
-                        Err(e) => return Err(e),
+                        Err(e) => {
+                            return Err(e.called_from(describe_loop(var_name, start_i, end_i, i)))
+                        }
                     }
                 }
             }
@@ -566,7 +839,7 @@ fn execute_statements(
                     if matches {
                         // Execute arm body in new scope
                         let mut arm_ctx = func_ctx.clone_scope();
-                        match execute_statements(&arm.body, &mut arm_ctx, program, function_map) {
+                        match execute_statements(&arm.body, &mut arm_ctx, program, expansion) {
                             Ok(Some(return_val)) => {
                                 // Return from function
                                 return Ok(Some(return_val));
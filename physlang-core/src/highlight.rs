@@ -0,0 +1,240 @@
+//! Token classification for syntax highlighting
+//!
+//! A standalone scanner, independent of the statement parser so it works on
+//! broken input: it never fails and classifies everything it can. Strings
+//! run from `"` to the next `"` on the same line, and comments from `#`
+//! outside a string to the end of the line, as in the parser and formatter.
+//!
+//! Words like `mass` or `k` are keywords where a declaration expects them
+//! but names in an expression (`k = k`), so words are classified by the
+//! tokens around them.
+
+use crate::diagnostics::Span;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Keywords that start or structure statements, keywords wherever they appear
+const KEYWORDS: [&str; 20] = [
+    "particle", "force", "simulate", "detect", "loop", "for", "while", "well", "if", "else",
+    "ground", "let", "fn", "return", "match", "in", "world", "module", "import", "as",
+];
+
+/// Keywords naming the parts of a declaration, which are plain names in an
+/// expression
+const CONTEXTUAL_KEYWORDS: [&str; 23] = [
+    "at",
+    "mass",
+    "damping",
+    "spring",
+    "gravity",
+    "push",
+    "central",
+    "G",
+    "GM",
+    "k",
+    "rest",
+    "magnitude",
+    "direction",
+    "friction",
+    "dt",
+    "steps",
+    "frame",
+    "recenter",
+    "cycles",
+    "with",
+    "frequency",
+    "on",
+    "initial",
+];
+
+/// Values of the `frame =` and `recenter =` options of `simulate`
+const OPTION_VALUES: [&str; 4] = ["lab", "center_of_mass", "once", "always"];
+
+/// What a token is, for choosing its colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenClass {
+    Keyword,
+    /// A particle, where declared or where it's referred to by name
+    ParticleName,
+    /// A function where declared, or any call: user functions, `sin`,
+    /// `distance`, ...
+    FunctionName,
+    /// Any other name: let bindings, parameters, detectors, wells, ...
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    /// Brackets, commas, and anything the language doesn't use
+    Punctuation,
+}
+
+/// A classified token: `span` is a byte range of the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HighlightToken {
+    pub span: Span,
+    pub class: TokenClass,
+}
+
+/// The tokens of `source` in order, skipping whitespace
+pub fn tokenize_for_highlighting(source: &str) -> Vec<HighlightToken> {
+    let mut tokens = scan(source);
+    let declared: HashSet<&str> = tokens
+        .windows(2)
+        .filter(|pair| {
+            is_word(source, &pair[0], "particle") && pair[1].class == TokenClass::Identifier
+        })
+        .map(|pair| text(source, &pair[1]))
+        .collect();
+    for i in 0..tokens.len() {
+        if tokens[i].class == TokenClass::Identifier {
+            tokens[i].class = classify_word(source, &tokens, i, &declared);
+        }
+    }
+    tokens
+}
+
+/// Split `source` into tokens, every word as an identifier
+fn scan(source: &str) -> Vec<HighlightToken> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = source[i..].chars().next().unwrap();
+        let start = i;
+        let class = if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        } else if c == '#' {
+            i = line_end(source, i);
+            TokenClass::Comment
+        } else if c == '"' {
+            let end = line_end(source, i);
+            i = source[i + 1..end].find('"').map_or(end, |j| i + j + 2);
+            TokenClass::String
+        } else if c.is_ascii_digit() {
+            i = number_end(bytes, i);
+            TokenClass::Number
+        } else if c.is_alphabetic() || c == '_' {
+            i += source[i..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(source.len() - i);
+            TokenClass::Identifier
+        } else if let Some(op) = ["==", "!=", "<=", ">=", ".."]
+            .iter()
+            .find(|op| source[i..].starts_with(*op))
+        {
+            i += op.len();
+            TokenClass::Operator
+        } else if "+-*/<>=!.".contains(c) {
+            i += 1;
+            TokenClass::Operator
+        } else {
+            i += c.len_utf8();
+            TokenClass::Punctuation
+        };
+        tokens.push(HighlightToken {
+            span: Span::new(start, i),
+            class,
+        });
+    }
+    tokens
+}
+
+/// The end of the line containing byte `i`, before the newline
+fn line_end(source: &str, i: usize) -> usize {
+    source[i..].find('\n').map_or(source.len(), |j| i + j)
+}
+
+/// The end of the number starting at byte `i`: digits, an optional
+/// fraction, and an optional exponent
+fn number_end(bytes: &[u8], mut i: usize) -> usize {
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    i = digits(i);
+    if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+        if bytes.get(i + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            i = digits(i + 1 + sign);
+        }
+    }
+    i
+}
+
+/// Classify the word at `tokens[i]` by the tokens around it
+fn classify_word(
+    source: &str,
+    tokens: &[HighlightToken],
+    i: usize,
+    declared: &HashSet<&str>,
+) -> TokenClass {
+    let word = text(source, &tokens[i]);
+    if KEYWORDS.contains(&word) {
+        return TokenClass::Keyword;
+    }
+
+    // The code tokens before and after on the same line
+    let same_line = |j: usize| !source[tokens[j].span.end..tokens[i].span.start].contains('\n');
+    let previous: Vec<usize> = (0..i)
+        .rev()
+        .filter(|&j| tokens[j].class != TokenClass::Comment)
+        .take(2)
+        .collect();
+    let previous = |n: usize| {
+        previous
+            .get(n)
+            .filter(|&&j| same_line(j))
+            .map(|&j| text(source, &tokens[j]))
+    };
+    // Only a `(` right after the word makes it a call
+    let next = tokens.get(i + 1).map(|t| text(source, t));
+
+    match previous(0) {
+        Some("particle") => return TokenClass::ParticleName,
+        Some("fn") => return TokenClass::FunctionName,
+        Some("let" | "detect" | "well") => return TokenClass::Identifier,
+        Some("ground") if word == "y" => return TokenClass::Keyword,
+        _ => {}
+    }
+    if OPTION_VALUES.contains(&word)
+        && previous(0) == Some("=")
+        && matches!(previous(1), Some("frame" | "recenter"))
+    {
+        return TokenClass::Keyword;
+    }
+
+    // After an operator, `(`, or `,` a word is part of an expression
+    let in_expression = previous(0)
+        .is_some_and(|p| p == "(" || p == "," || p.starts_with(|c: char| "+-*/<>=!.".contains(c)));
+    let contextual = CONTEXTUAL_KEYWORDS.contains(&word) && !in_expression;
+    if next == Some("(") {
+        return if contextual {
+            TokenClass::Keyword
+        } else {
+            TokenClass::FunctionName
+        };
+    }
+    if declared.contains(word) {
+        TokenClass::ParticleName
+    } else if contextual {
+        TokenClass::Keyword
+    } else {
+        TokenClass::Identifier
+    }
+}
+
+fn text<'a>(source: &'a str, token: &HighlightToken) -> &'a str {
+    &source[token.span.start..token.span.end]
+}
+
+fn is_word(source: &str, token: &HighlightToken, word: &str) -> bool {
+    token.class == TokenClass::Identifier && text(source, token) == word
+}
@@ -1,15 +1,171 @@
 use crate::engine::World;
+use glam::Vec2;
+
+/// Numerical method used to advance the world by one time step
+///
+/// `step` has always used semi-implicit Euler, so golden outputs recorded with
+/// the default are unaffected by the other methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// x += v·dt, then v += a·dt: positions use the old velocity (first order,
+    /// gains energy on undamped oscillators; provided for comparison)
+    ExplicitEuler,
+    /// v += a·dt, then x += v·dt: positions use the new velocity (first order,
+    /// symplectic, the historical default)
+    #[default]
+    SemiImplicitEuler,
+    /// Velocity Verlet (second order, symplectic)
+    Verlet,
+    /// Classical fourth-order Runge-Kutta
+    Rk4,
+}
+
+impl Integrator {
+    /// Every method, in declaration order
+    pub const ALL: [Integrator; 4] = [
+        Integrator::ExplicitEuler,
+        Integrator::SemiImplicitEuler,
+        Integrator::Verlet,
+        Integrator::Rk4,
+    ];
+
+    /// Kebab-case name, as accepted by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::ExplicitEuler => "explicit-euler",
+            Integrator::SemiImplicitEuler => "semi-implicit-euler",
+            Integrator::Verlet => "verlet",
+            Integrator::Rk4 => "rk4",
+        }
+    }
+}
+
+impl std::str::FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Integrator::ALL
+            .into_iter()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Integrator::ALL.iter().map(|m| m.name()).collect();
+                format!("unknown integrator '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
 
 /// Step the simulation forward by dt using semi-implicit Euler integration
 pub fn step(world: &mut World, dt: f32) {
+    step_with(world, dt, Integrator::default());
+}
+
+/// Step the simulation forward by dt using the given method
+/// Pinned particles keep their position and velocity under every method
+/// Damping, then the ground (if any), are applied after the method's update
+pub fn step_with(world: &mut World, dt: f32, method: Integrator) {
+    match method {
+        Integrator::ExplicitEuler => step_explicit_euler(world, dt),
+        Integrator::SemiImplicitEuler => step_semi_implicit_euler(world, dt),
+        Integrator::Verlet => step_verlet(world, dt),
+        Integrator::Rk4 => step_rk4(world, dt),
+    }
+    apply_damping(world, dt);
+    world.resolve_ground(dt);
+}
+
+/// Scale each damped particle's velocity by max(0, 1 − c·dt)
+/// Undamped particles are skipped so their velocities stay bit-identical
+fn apply_damping(world: &mut World, dt: f32) {
+    for particle in &mut world.particles {
+        if particle.damping != 0.0 && !particle.pinned {
+            particle.vel *= (1.0 - particle.damping * dt).max(0.0);
+        }
+    }
+}
+
+fn step_explicit_euler(world: &mut World, dt: f32) {
+    let accelerations = world.compute_accelerations();
+
+    // Position first, with the velocity from the start of the step
+    for (i, particle) in world.particles.iter_mut().enumerate() {
+        if particle.pinned {
+            continue;
+        }
+        particle.pos += particle.vel * dt;
+        particle.vel += accelerations[i] * dt;
+    }
+}
+
+fn step_semi_implicit_euler(world: &mut World, dt: f32) {
     // First, compute all accelerations
-    let accelerations: Vec<_> = (0..world.particles.len())
-        .map(|i| world.compute_acceleration(i))
-        .collect();
+    let accelerations = world.compute_accelerations();
 
     // Update velocities and positions (semi-implicit Euler: v += a*dt, then x += v*dt)
     for (i, particle) in world.particles.iter_mut().enumerate() {
+        if particle.pinned {
+            continue;
+        }
         particle.vel += accelerations[i] * dt;
         particle.pos += particle.vel * dt;
     }
 }
+
+fn step_verlet(world: &mut World, dt: f32) {
+    // x += v·dt + ½a·dt², then v += ½(a + a')·dt with a' at the new positions
+    let before = world.compute_accelerations();
+    for (particle, accel) in world.particles.iter_mut().zip(&before) {
+        if particle.pinned {
+            continue;
+        }
+        particle.pos += particle.vel * dt + *accel * (0.5 * dt * dt);
+    }
+
+    let after = world.compute_accelerations();
+    for (i, particle) in world.particles.iter_mut().enumerate() {
+        if particle.pinned {
+            continue;
+        }
+        particle.vel += (before[i] + after[i]) * (0.5 * dt);
+    }
+}
+
+fn step_rk4(world: &mut World, dt: f32) {
+    let pos0: Vec<Vec2> = world.particles.iter().map(|p| p.pos).collect();
+    let vel0: Vec<Vec2> = world.particles.iter().map(|p| p.vel).collect();
+
+    // Each stage evaluates accelerations with the world moved to a trial state
+    let stage = |world: &mut World, dx: &[Vec2], dv: &[Vec2], h: f32| {
+        let pos = advance(&pos0, dx, h);
+        let vel = advance(&vel0, dv, h);
+        for (i, particle) in world.particles.iter_mut().enumerate() {
+            if particle.pinned {
+                continue;
+            }
+            particle.pos = pos[i];
+            particle.vel = vel[i];
+        }
+        world.compute_accelerations()
+    };
+
+    let v1 = vel0.clone();
+    let a1 = stage(world, &v1, &v1, 0.0);
+    let v2 = advance(&vel0, &a1, 0.5 * dt);
+    let a2 = stage(world, &v1, &a1, 0.5 * dt);
+    let v3 = advance(&vel0, &a2, 0.5 * dt);
+    let a3 = stage(world, &v2, &a2, 0.5 * dt);
+    let v4 = advance(&vel0, &a3, dt);
+    let a4 = stage(world, &v3, &a3, dt);
+
+    for (i, particle) in world.particles.iter_mut().enumerate() {
+        if particle.pinned {
+            continue;
+        }
+        particle.pos = pos0[i] + (v1[i] + 2.0 * v2[i] + 2.0 * v3[i] + v4[i]) * (dt / 6.0);
+        particle.vel = vel0[i] + (a1[i] + 2.0 * a2[i] + 2.0 * a3[i] + a4[i]) * (dt / 6.0);
+    }
+}
+
+/// base + rate·h, element-wise
+fn advance(base: &[Vec2], rate: &[Vec2], h: f32) -> Vec<Vec2> {
+    base.iter().zip(rate).map(|(b, r)| *b + *r * h).collect()
+}
@@ -1,28 +1,72 @@
 pub mod analyzer;
 pub mod ast;
+pub mod capabilities;
 pub mod diagnostics;
+pub mod diff;
 pub mod engine;
 pub mod eval;
+pub mod export;
+pub mod fold;
+pub mod format;
 pub mod functions;
+pub mod hash;
+pub mod highlight;
 pub mod integrator;
+pub mod lint;
 pub mod loops;
 pub mod parser;
+pub mod phase;
+pub mod repl;
 pub mod runtime;
+pub mod snapshot;
+pub mod state;
+pub mod symbols;
 
-pub use analyzer::analyze_program;
-pub use diagnostics::{Diagnostic, DiagnosticSeverity, Diagnostics, SourceLocation, Span};
-pub use engine::{Force, Particle, World};
+pub use analyzer::{
+    analyze_program, analyze_program_with_limit, check_detector_decl, check_force_decl,
+    check_ground_decl, check_let_decl, check_loop_decl, check_particle_decl, check_simulate_decl,
+    check_well_decl, AnalysisEnv,
+};
+pub use capabilities::{language_capabilities, Capabilities, LANGUAGE_VERSION};
+pub use diagnostics::{
+    render_diagnostic, Diagnostic, DiagnosticSeverity, Diagnostics, Fix, SourceLocation, Span,
+};
+pub use diff::{diff_programs, ProgramDiff};
+pub use engine::{
+    Bounds, Collision, CustomForce, Force, ForceInfo, Ground, MergeError, Particle, World,
+    GROUND_CONTACT_EPSILON,
+};
 pub use eval::{
-    eval_expr, eval_expr_with_function_ctx, evaluate_lets, EvalContext, EvalError,
-    FunctionEvalContext,
+    coerce_to_count, eval_expr, eval_expr_with_function_ctx, eval_source_expr, evaluate_lets,
+    fold_constants, references_var, resolve_observables, substitute_vars, try_const_fold,
+    EvalContext, EvalError, ExprError, FunctionEvalContext,
+};
+pub use export::{
+    format_detector_value, run_program_to_csv, write_detector_series_csv, write_detectors_csv,
+    CsvTraceWriter, DETECTOR_SIGNIFICANT_DIGITS,
 };
-pub use functions::execute_functions;
-pub use parser::{parse_program, ParseError};
+pub use fold::fold_program_constants;
+pub use format::{export_state_source, format_source};
+pub use functions::{execute_functions, execute_functions_with_source, ExpansionLimits};
+pub use hash::StateHasher;
+pub use highlight::{tokenize_for_highlighting, HighlightToken, TokenClass};
+pub use integrator::{step_with, Integrator};
+pub use lint::{apply_fixes, lint_source};
+pub use parser::{parse_expression, parse_program, ParseError};
+pub use repl::{ReplReply, ReplSession};
 pub use runtime::{
-    build_simulation_context, build_simulation_context_from_source, get_particle_states,
-    run_program, step_simulation, DetectorResult, ParticleState, SimulationContext,
-    SimulationResult,
+    apply_overrides, build_run_context, build_simulation_context,
+    build_simulation_context_from_source, build_simulation_context_with_overrides, check_program,
+    expand_functions, get_particle_states, rebuild_simulation_context_from_source,
+    rebuild_simulation_context_with_overrides, run_program,
+    run_program_with_observer, run_program_with_options, run_simulation, step_simulation,
+    try_step_simulation, Checkpoint,
+    BuildError, CollisionEvent, DetectorResult, OverrideError, ParticleState, Pipeline, Reload, RunError, RunOptions,
+    SimulationContext, SimulationResult, Viewport, COLLISION_EVENT_CAPACITY,
 };
+pub use snapshot::{SnapshotError, SNAPSHOT_MAGIC, SNAPSHOT_VERSION};
+pub use state::{ParticleSnapshot, StateError, WorldState};
+pub use symbols::{SymbolId, SymbolTable, UnknownParticle};
 
 // Test helpers module (public for integration tests)
 // Always compiled - integration tests are separate crates and need access
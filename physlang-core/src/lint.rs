@@ -0,0 +1,207 @@
+//! Source-level lints with mechanical fixes, for `physlang check --fix`
+//!
+//! Most of these work on the source text line by line, like the formatter,
+//! and attach a `Fix` to each diagnostic.
+
+use crate::analyzer::analyze_program;
+use crate::ast::{DetectorKind, Program};
+use crate::diagnostics::{Diagnostic, Fix, Span};
+use crate::format::{count_braces, split_comment};
+use crate::parser;
+
+/// Lint `source`, returning warnings that each carry a fix
+pub fn lint_source(source: &str) -> Vec<Diagnostic> {
+    let lines = Lines::new(source);
+    let mut diagnostics = Vec::new();
+    if let Ok(program) = parser::parse_program(source) {
+        unused_lets(&lines, &program, &mut diagnostics);
+    }
+    bare_position_detectors(&lines, &mut diagnostics);
+    matches_without_wildcard(&lines, &mut diagnostics);
+    diagnostics.sort_by_key(|d| d.span.map(|s| s.start));
+    diagnostics
+}
+
+/// Apply the machine-applicable fixes among `diagnostics` to `source`
+///
+/// Returns the new source and the indices of the diagnostics whose fixes were
+/// applied. A fix that overlaps one already applied is skipped.
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> (String, Vec<usize>) {
+    let mut fixes: Vec<(usize, &Fix)> = diagnostics
+        .iter()
+        .enumerate()
+        .filter_map(|(i, d)| d.fix.as_ref().map(|fix| (i, fix)))
+        .filter(|(_, fix)| fix.machine_applicable)
+        .collect();
+    fixes.sort_by_key(|(_, fix)| (fix.span.start, fix.span.end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut applied = Vec::new();
+    for (i, fix) in fixes {
+        if fix.span.start < copied || fix.span.end > source.len() {
+            continue;
+        }
+        out.push_str(&source[copied..fix.span.start]);
+        out.push_str(&fix.replacement);
+        copied = fix.span.end;
+        applied.push(i);
+    }
+    out.push_str(&source[copied..]);
+    applied.sort_unstable();
+    (out, applied)
+}
+
+/// One source line: its byte range, its code without the comment, and the
+/// brace depth it starts at
+struct Line<'a> {
+    start: usize,
+    end: usize,
+    indent: &'a str,
+    code: &'a str,
+    depth: usize,
+}
+
+impl Line<'_> {
+    /// Span of the code on this line
+    fn code_span(&self) -> Span {
+        let start = self.start + self.indent.len();
+        Span::new(start, start + self.code.len())
+    }
+}
+
+struct Lines<'a> {
+    lines: Vec<Line<'a>>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut depth = 0usize;
+        for raw in source.split_inclusive('\n') {
+            let trimmed = raw.trim();
+            let indent = &raw[..raw.len() - raw.trim_start().len()];
+            let (opens, closes) = count_braces(trimmed);
+            lines.push(Line {
+                start,
+                end: start + raw.len(),
+                indent,
+                code: split_comment(trimmed).0,
+                depth,
+            });
+            depth = (depth + opens).saturating_sub(closes);
+            start += raw.len();
+        }
+        Self { lines }
+    }
+
+    /// The line holding byte `offset`
+    fn containing(&self, offset: usize) -> Option<&Line<'a>> {
+        self.lines
+            .iter()
+            .find(|line| line.start <= offset && offset < line.end)
+    }
+}
+
+/// Top-level `let` bindings nothing in the program refers to
+///
+/// A let counts as used when removing it would leave an unknown variable. The
+/// fix is only a suggestion: an unused let may still be meant for `--set`.
+fn unused_lets(lines: &Lines, program: &Program, diagnostics: &mut Vec<Diagnostic>) {
+    for (i, decl) in program.lets.iter().enumerate() {
+        let Some(span) = program.spans.lets.get(i) else {
+            continue;
+        };
+        let Some(line) = lines.containing(span.start) else {
+            continue;
+        };
+        let mut without = program.clone();
+        without.lets.remove(i);
+        without.spans.lets.remove(i);
+        let unknown = format!("unknown variable '{}'", decl.name);
+        if analyze_program(&without).errors().any(|d| d.message == unknown) {
+            continue;
+        }
+        diagnostics.push(
+            Diagnostic::warning(
+                format!("unused let binding '{}'", decl.name),
+                Some(line.code_span()),
+            )
+            .with_help("remove the line, unless it's meant to be set with --set")
+            .with_fix(Fix {
+                span: Span::new(line.start, line.end),
+                replacement: String::new(),
+                machine_applicable: false,
+            }),
+        );
+    }
+}
+
+/// `detect name = position(a)`, which measures x without saying so
+fn bare_position_detectors(lines: &Lines, diagnostics: &mut Vec<Diagnostic>) {
+    for line in &lines.lines {
+        if !line.code.starts_with("detect ") || !line.code.ends_with(')') {
+            continue;
+        }
+        let Ok(decl) = parser::parse_detector(line.code, None) else {
+            continue;
+        };
+        let Some(DetectorKind::Position(particle)) = decl.kind() else {
+            continue;
+        };
+        let end = line.code_span().end;
+        diagnostics.push(
+            Diagnostic::warning(
+                format!(
+                    "bare position({}) detector is deprecated; it measures the x coordinate",
+                    particle
+                ),
+                Some(line.code_span()),
+            )
+            .with_help(format!("write position({}).x", particle))
+            .with_fix(Fix {
+                span: Span::new(end, end),
+                replacement: ".x".to_string(),
+                machine_applicable: true,
+            }),
+        );
+    }
+}
+
+/// `match` statements with no `_` arm, where an unmatched value silently does
+/// nothing
+fn matches_without_wildcard(lines: &Lines, diagnostics: &mut Vec<Diagnostic>) {
+    for (i, header) in lines.lines.iter().enumerate() {
+        if !header.code.starts_with("match ") || !header.code.ends_with('{') {
+            continue;
+        }
+        let arms = header.depth + 1;
+        let mut has_wildcard = false;
+        let closing = lines.lines[i + 1..].iter().find(|line| {
+            if line.depth == arms && line.code.starts_with('_') {
+                has_wildcard |= line.code[1..].trim_start().starts_with("=>");
+            }
+            line.depth == arms && line.code == "}"
+        });
+        let Some(closing) = closing else {
+            continue;
+        };
+        if has_wildcard {
+            continue;
+        }
+        let indent = format!("{}    ", closing.indent);
+        diagnostics.push(
+            Diagnostic::warning(
+                "match has no '_' arm; values matching no arm do nothing",
+                Some(header.code_span()),
+            )
+            .with_help("add an empty '_' arm to make that explicit")
+            .with_fix(Fix {
+                span: Span::new(closing.start, closing.start),
+                replacement: format!("{}_ => {{\n{}}}\n", indent, indent),
+                machine_applicable: true,
+            }),
+        );
+    }
+}
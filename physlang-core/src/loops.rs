@@ -1,19 +1,24 @@
 //! Runtime structures and logic for loops and wells (v0.2+)
 
 use crate::engine::Particle;
+use crate::hash::StateHasher;
 use glam::Vec2;
 use std::f32::consts::PI;
 
 /// Runtime loop instance (resolved indices instead of names)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LoopInstance {
     pub kind: LoopKindRuntime,
     pub body: Vec<LoopBodyRuntime>,
     pub active: bool,
+    /// Name of the `on` particle, for display (not part of the state hash)
+    pub target_name: String,
+    /// The loop's label, if it has one
+    pub label: Option<String>,
 }
 
 /// Runtime loop kind
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LoopKindRuntime {
     ForCycles {
         target_index: usize,
@@ -44,6 +49,9 @@ pub enum ObservableRuntime {
     PositionX(usize),
     PositionY(usize),
     Distance(usize, usize),
+    Mass(usize),
+    /// A particle's tag by name; NaN if the particle no longer has it
+    Tag(usize, String),
 }
 
 /// Runtime loop body action
@@ -63,6 +71,185 @@ pub struct WellInstance {
     pub observable: ObservableRuntime,
     pub threshold: f32,
     pub depth: f32,
+    /// The well's declared name, for display (not part of the state hash)
+    pub name: String,
+}
+
+impl LoopInstance {
+    /// Feed the loop state (including oscillator phase) into a hasher
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        match &self.kind {
+            LoopKindRuntime::ForCycles {
+                target_index,
+                cycles_remaining,
+                frequency,
+                damping,
+                phase,
+            } => {
+                hasher.write_u8(0);
+                hasher.write_usize(*target_index);
+                hasher.write_u32(*cycles_remaining);
+                hasher.write_f32(*frequency);
+                hasher.write_f32(*damping);
+                hasher.write_f32(*phase);
+            }
+            LoopKindRuntime::WhileCondition {
+                target_index,
+                condition,
+                frequency,
+                damping,
+                phase,
+            } => {
+                hasher.write_u8(1);
+                hasher.write_usize(*target_index);
+                condition.hash_state(hasher);
+                hasher.write_f32(*frequency);
+                hasher.write_f32(*damping);
+                hasher.write_f32(*phase);
+            }
+        }
+
+        hasher.write_usize(self.body.len());
+        for action in &self.body {
+            match action {
+                LoopBodyRuntime::ForcePush {
+                    particle_index,
+                    magnitude,
+                    direction,
+                } => {
+                    hasher.write_u8(0);
+                    hasher.write_usize(*particle_index);
+                    hasher.write_f32(*magnitude);
+                    hasher.write_vec2(*direction);
+                }
+            }
+        }
+
+        hasher.write_bool(self.active);
+    }
+
+    /// Shift every particle index by `offset`, e.g. after `World::merge`
+    pub fn rebase(&mut self, offset: usize) {
+        match &mut self.kind {
+            LoopKindRuntime::ForCycles { target_index, .. } => *target_index += offset,
+            LoopKindRuntime::WhileCondition {
+                target_index,
+                condition,
+                ..
+            } => {
+                *target_index += offset;
+                condition.rebase(offset);
+            }
+        }
+        for action in &mut self.body {
+            match action {
+                LoopBodyRuntime::ForcePush { particle_index, .. } => *particle_index += offset,
+            }
+        }
+    }
+}
+
+impl ConditionRuntime {
+    /// Feed the condition into a hasher
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        match self {
+            ConditionRuntime::LessThan(obs, threshold) => {
+                hasher.write_u8(0);
+                obs.hash_state(hasher);
+                hasher.write_f32(*threshold);
+            }
+            ConditionRuntime::GreaterThan(obs, threshold) => {
+                hasher.write_u8(1);
+                obs.hash_state(hasher);
+                hasher.write_f32(*threshold);
+            }
+        }
+    }
+
+    /// Shift every particle index by `offset`
+    pub fn rebase(&mut self, offset: usize) {
+        match self {
+            ConditionRuntime::LessThan(obs, _) | ConditionRuntime::GreaterThan(obs, _) => {
+                obs.rebase(offset)
+            }
+        }
+    }
+}
+
+impl ObservableRuntime {
+    /// Feed the observable into a hasher
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        match self {
+            ObservableRuntime::PositionX(idx) => {
+                hasher.write_u8(0);
+                hasher.write_usize(*idx);
+            }
+            ObservableRuntime::PositionY(idx) => {
+                hasher.write_u8(1);
+                hasher.write_usize(*idx);
+            }
+            ObservableRuntime::Distance(a, b) => {
+                hasher.write_u8(2);
+                hasher.write_usize(*a);
+                hasher.write_usize(*b);
+            }
+            ObservableRuntime::Mass(idx) => {
+                hasher.write_u8(3);
+                hasher.write_usize(*idx);
+            }
+            ObservableRuntime::Tag(idx, tag) => {
+                hasher.write_u8(4);
+                hasher.write_usize(*idx);
+                hasher.write_str(tag);
+            }
+        }
+    }
+
+    /// Shift every particle index by `offset`
+    pub fn rebase(&mut self, offset: usize) {
+        match self {
+            ObservableRuntime::PositionX(idx)
+            | ObservableRuntime::PositionY(idx)
+            | ObservableRuntime::Mass(idx)
+            | ObservableRuntime::Tag(idx, _) => *idx += offset,
+            ObservableRuntime::Distance(a, b) => {
+                *a += offset;
+                *b += offset;
+            }
+        }
+    }
+}
+
+impl WellInstance {
+    /// Feed the well state into a hasher
+    pub fn hash_state(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.particle_index);
+        self.observable.hash_state(hasher);
+        hasher.write_f32(self.threshold);
+        hasher.write_f32(self.depth);
+    }
+
+    /// Shift every particle index by `offset`, e.g. after `World::merge`
+    pub fn rebase(&mut self, offset: usize) {
+        self.particle_index += offset;
+        self.observable.rebase(offset);
+    }
+
+    /// Whether the next step will push the particle: the observable has
+    /// reached the threshold, the particle isn't pinned, and the well isn't a
+    /// distance well (which applies no force yet)
+    pub fn is_applying(&self, particles: &[Particle]) -> bool {
+        !particles[self.particle_index].pinned
+            && !matches!(self.observable, ObservableRuntime::Distance(_, _))
+            && evaluate_observable(&self.observable, particles) >= self.threshold
+    }
+}
+
+impl ObservableRuntime {
+    /// Current value of the observable
+    pub fn evaluate(&self, particles: &[Particle]) -> f32 {
+        evaluate_observable(self, particles)
+    }
 }
 
 /// Update loops and apply loop body actions
@@ -99,6 +286,11 @@ pub fn update_and_apply_loops(
                     if *cycles_remaining > 0 {
                         *cycles_remaining -= 1;
                     }
+                    log::debug!(
+                        "loop on {} fired, {} cycles left",
+                        loop_inst.target_name,
+                        cycles_remaining
+                    );
 
                     // Deactivate if cycles exhausted
                     if *cycles_remaining == 0 {
@@ -127,6 +319,7 @@ pub fn update_and_apply_loops(
                     if condition_met {
                         // Apply loop body
                         apply_loop_body(&loop_inst.body, particles);
+                        log::debug!("loop on {} fired", loop_inst.target_name);
                     } else {
                         // Condition false, deactivate loop
                         loop_inst.active = false;
@@ -147,6 +340,9 @@ fn apply_loop_body(body: &[LoopBodyRuntime], particles: &mut [Particle]) {
                 direction,
             } => {
                 let particle = &mut particles[*particle_index];
+                if particle.pinned {
+                    continue;
+                }
                 let dir_normalized = direction.normalize_or_zero();
                 // Apply impulse: directly modify velocity
                 particle.vel += dir_normalized * (*magnitude);
@@ -177,6 +373,10 @@ fn evaluate_observable(obs: &ObservableRuntime, particles: &[Particle]) -> f32 {
         ObservableRuntime::Distance(a_idx, b_idx) => {
             particles[*a_idx].pos.distance(particles[*b_idx].pos)
         }
+        ObservableRuntime::Mass(idx) => particles[*idx].mass,
+        ObservableRuntime::Tag(idx, tag) => {
+            particles[*idx].tags.get(tag).copied().unwrap_or(f32::NAN)
+        }
     }
 }
 
@@ -184,15 +384,12 @@ fn evaluate_observable(obs: &ObservableRuntime, particles: &[Particle]) -> f32 {
 pub fn apply_wells(wells: &[WellInstance], particles: &mut [Particle], dt: f32) {
     for well in wells {
         // Evaluate observable first (before mutable borrow)
-        let value = match &well.observable {
-            ObservableRuntime::PositionX(idx) => particles[*idx].pos.x,
-            ObservableRuntime::PositionY(idx) => particles[*idx].pos.y,
-            ObservableRuntime::Distance(a_idx, b_idx) => {
-                particles[*a_idx].pos.distance(particles[*b_idx].pos)
-            }
-        };
+        let value = evaluate_observable(&well.observable, particles);
         
         let particle = &mut particles[well.particle_index];
+        if particle.pinned {
+            continue;
+        }
 
         // If value >= threshold, apply well force
         if value >= well.threshold {
@@ -216,6 +413,12 @@ pub fn apply_wells(wells: &[WellInstance], particles: &mut [Particle], dt: f32)
                     // For distance wells, we'd need to compute direction
                     // v0.2: skip distance wells for simplicity
                 }
+                ObservableRuntime::Mass(_) | ObservableRuntime::Tag(_, _) => {
+                    // No position to pull towards: drag the particle to a stop,
+                    // F = -depth * v, so heavier particles slow down less per step
+                    let brake = (1.0 - well.depth * dt / particle.mass).max(0.0);
+                    particle.vel *= brake;
+                }
             }
         }
     }
@@ -1,9 +1,11 @@
 use crate::ast::{
-    BinaryOp, ConditionExpr, DetectorDecl, DetectorKind, Expr, ForceDecl, ForceKind, FuncName,
-    FunctionDecl, LetDecl, LoopBodyStmt, LoopDecl, LoopKind, MatchArm, MatchPattern,
-    ObservableExpr, ParticleDecl, Program, SimulateDecl, Stmt, WellDecl,
+    BinaryOp, ConditionExpr, DeclSpans, DetectorDecl, DetectorKind, Expr, ForceDecl, ForceKind, FuncName,
+    Frame, FunctionDecl, GroundDecl, LetDecl, LoopBodyStmt, LoopDecl, LoopKind, MatchArm, MatchPattern,
+    ObservableExpr, ParamType, ParticleDecl, Program, Recenter, SimulateDecl, Stmt, WellDecl,
 };
 use crate::diagnostics::Span;
+use crate::phase;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Parse error with detailed location information
@@ -16,6 +18,10 @@ pub enum ParseError {
         line_number: Option<usize>,
         line_content: Option<String>,
         context: Option<String>,
+        /// The offending text, used to narrow `span` to just that part of the line
+        fragment: Option<String>,
+        /// The file the source was read from, when the caller knows it
+        file: Option<PathBuf>,
     },
 }
 
@@ -27,6 +33,8 @@ impl ParseError {
             line_number: None,
             line_content: None,
             context: None,
+            fragment: None,
+            file: None,
         }
     }
     
@@ -42,6 +50,8 @@ impl ParseError {
             line_number: Some(line_number + 1), // Convert 0-indexed to 1-indexed
             line_content: Some(line_content.into()),
             context: None,
+            fragment: None,
+            file: None,
         }
     }
     
@@ -58,6 +68,8 @@ impl ParseError {
             line_number: Some(line_number + 1), // Convert 0-indexed to 1-indexed
             line_content: Some(line_content.into()),
             context: Some(context.into()),
+            fragment: None,
+            file: None,
         }
     }
 
@@ -68,9 +80,54 @@ impl ParseError {
             line_number: None,
             line_content: None,
             context: None,
+            fragment: None,
+            file: None,
         }
     }
 
+    /// Mark the offending text; `parse_program` narrows the span to it
+    fn with_fragment(mut self, text: &str) -> Self {
+        let Self::SyntaxError { fragment, .. } = &mut self;
+        *fragment = Some(text.to_string());
+        self
+    }
+
+    /// Record the file the source was read from, shown by `format_detailed`
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let Self::SyntaxError { file, .. } = &mut self;
+        *file = Some(path.into());
+        self
+    }
+
+    /// Narrow a line span to the first occurrence of the fragment within it
+    fn narrowed(mut self, source: &str) -> Self {
+        let Self::SyntaxError { span, fragment, .. } = &mut self;
+        if let (Some(current), Some(text)) = (span.as_mut(), fragment.as_deref()) {
+            let found = source
+                .get(current.start..current.end.min(source.len()))
+                .filter(|_| !text.is_empty())
+                .and_then(|line| line.find(text));
+            if let Some(offset) = found {
+                let start = current.start + offset;
+                *current = Span::new(start, start + text.len());
+            }
+        }
+        self
+    }
+
+    /// Fill in the line number and text from the span, for errors raised
+    /// with only a span
+    fn located(mut self, source: &str) -> Self {
+        let Self::SyntaxError { span, line_number, line_content, .. } = &mut self;
+        if let (Some(span), None) = (*span, *line_number) {
+            let start = span.start.min(source.len());
+            let line = source[..start].matches('\n').count();
+            *line_number = Some(line + 1);
+            *line_content = source.lines().nth(line).map(str::to_string);
+        }
+        self
+    }
+
     pub fn span(&self) -> Option<Span> {
         match self {
             Self::SyntaxError { span, .. } => *span,
@@ -94,6 +151,12 @@ impl ParseError {
             Self::SyntaxError { context, .. } => context.as_deref(),
         }
     }
+
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Self::SyntaxError { file, .. } => file.as_deref(),
+        }
+    }
     
     /// Format a detailed error message with source location
     pub fn format_detailed(&self) -> String {
@@ -111,9 +174,14 @@ impl ParseError {
             }
         }
         
-        // Add line info
-        if let Some(line_num) = self.line_number() {
-            result.push_str(&format!("\n  --> line {}", line_num));
+        // Add location info
+        match (self.file(), self.line_number()) {
+            (Some(file), Some(line_num)) => {
+                result.push_str(&format!("\n  --> {}:{}", file.display(), line_num))
+            }
+            (Some(file), None) => result.push_str(&format!("\n  --> {}", file.display())),
+            (None, Some(line_num)) => result.push_str(&format!("\n  --> line {}", line_num)),
+            (None, None) => {}
         }
         
         // Add line content
@@ -125,20 +193,38 @@ impl ParseError {
     }
 }
 
-/// Check if verbose parsing is enabled via PHYSLANG_PARSE_TRACE env var
+/// Whether PHYSLANG_PARSE_TRACE asks for parser traces on stderr, the
+/// behaviour before the `log` facade. Read once: looking the variable up on
+/// every traced line was measurable on big files
+#[cfg(not(target_arch = "wasm32"))]
 fn is_trace_enabled() -> bool {
-    std::env::var("PHYSLANG_PARSE_TRACE").is_ok()
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var_os("PHYSLANG_PARSE_TRACE").is_some())
 }
 
-/// Log a trace message if tracing is enabled
+/// There's no environment in the browser; trace through `log` instead
+#[cfg(target_arch = "wasm32")]
+fn is_trace_enabled() -> bool {
+    false
+}
+
+/// Log a trace message: on stderr if PHYSLANG_PARSE_TRACE is set, otherwise
+/// through the `log` facade (e.g. `physlang -vv`)
 macro_rules! trace_parse {
     ($($arg:tt)*) => {
         if is_trace_enabled() {
             eprintln!("[PARSE] {}", format!($($arg)*));
+        } else {
+            log::trace!($($arg)*);
         }
     };
 }
 
+/// Log a top-level declaration as it's parsed
+fn declared(kind: &str, name: &str, line: usize) {
+    log::debug!("line {}: {}", line + 1, format!("{} {}", kind, name).trim_end());
+}
+
 /// Helper to track byte offsets while parsing
 struct ParseContext {
     #[allow(dead_code)]
@@ -186,6 +272,13 @@ impl ParseContext {
         Span::new(start, end)
     }
     
+    /// Span of a line without its indentation and trailing whitespace
+    fn content_span(&self, line: usize) -> Span {
+        let text = self.get_line(line);
+        let start = text.len() - text.trim_start().len();
+        self.line_span(line, start, text.trim_end().len())
+    }
+
     /// Get line content by index (0-indexed)
     fn get_line(&self, line: usize) -> &str {
         self.lines.get(line).map(|s| s.as_str()).unwrap_or("")
@@ -215,6 +308,11 @@ impl ParseContext {
 
 /// Parse a PhysLang program from source code
 pub fn parse_program(source: &str) -> Result<Program, ParseError> {
+    let _phase = phase::enter("parse");
+    parse_program_lines(source).map_err(|e| e.narrowed(source).located(source))
+}
+
+fn parse_program_lines(source: &str) -> Result<Program, ParseError> {
     trace_parse!("Starting parse_program");
     let ctx = ParseContext::new(source);
     let mut lets = Vec::new();
@@ -226,12 +324,15 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
     let mut detectors = Vec::new();
     let mut loops = Vec::new();
     let mut wells = Vec::new();
+    let mut ground = None;
+    let mut spans = DeclSpans::default();
 
     let lines: Vec<&str> = source.lines().collect();
     let mut i = 0;
     while i < lines.len() {
         let line = lines[i].trim();
         let line_span = ctx.full_line_span(i);
+        let decl_span = ctx.content_span(i);
         
         if line.is_empty() || line.starts_with('#') {
             i += 1;
@@ -242,45 +343,79 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
 
         if line.starts_with("let ") {
             trace_parse!("  -> let declaration");
-            lets.push(parse_let(line, Some(line_span))?);
+            let let_decl = parse_let(line, Some(line_span))?;
+            declared("let", &let_decl.name, i);
+            spans.lets.push(decl_span);
+            lets.push(let_decl);
             i += 1;
         } else if line.starts_with("fn ") {
             trace_parse!("  -> function declaration");
             let (func_decl, next_line) = parse_function(&lines, i, &ctx)?;
             trace_parse!("  -> function '{}' parsed, next line: {}", func_decl.name, next_line + 1);
+            declared("fn", &func_decl.name, i);
+            spans.functions.push(decl_span);
             functions.push(func_decl);
             i = next_line;
         } else if line.starts_with("particle ") {
             trace_parse!("  -> particle declaration");
-            particles.push(parse_particle(line, Some(line_span))?);
+            let particle = parse_particle(line, Some(line_span))?;
+            declared("particle", &particle.name, i);
+            spans.particles.push(decl_span);
+            particles.push(particle);
             i += 1;
         } else if line.starts_with("force ") && !line.contains("push") {
             trace_parse!("  -> force declaration");
-            forces.push(parse_force(line, Some(line_span))?);
+            let force = parse_force(line, Some(line_span))?;
+            declared("force", &format!("({}, {})", force.a, force.b), i);
+            spans.forces.push(decl_span);
+            forces.push(force);
             i += 1;
         } else if line.starts_with("simulate ") {
             trace_parse!("  -> simulate declaration");
             simulate = Some(parse_simulate(line, Some(line_span))?);
+            declared("simulate", "", i);
+            spans.simulate = Some(decl_span);
             i += 1;
         } else if line.starts_with("detect ") {
             trace_parse!("  -> detect declaration");
-            detectors.push(parse_detector(line, Some(line_span))?);
+            let detector = parse_detector(line, Some(line_span))?;
+            declared("detect", &detector.name, i);
+            spans.detectors.push(decl_span);
+            detectors.push(detector);
             i += 1;
         } else if line.starts_with("loop ") {
             trace_parse!("  -> loop declaration");
             let (loop_decl, next_line) = parse_loop(&lines, i, &ctx)?;
             trace_parse!("  -> loop parsed, next line: {}", next_line + 1);
+            declared("loop", loop_decl.name.as_deref().unwrap_or(""), i);
+            spans.loops.push(decl_span);
             loops.push(loop_decl);
             i = next_line;
         } else if line.starts_with("well ") {
             trace_parse!("  -> well declaration");
-            wells.push(parse_well(line, Some(line_span))?);
+            let well = parse_well(line, Some(line_span))?;
+            declared("well", &well.name, i);
+            spans.wells.push(decl_span);
+            wells.push(well);
+            i += 1;
+        } else if line.starts_with("ground ") {
+            trace_parse!("  -> ground declaration");
+            if ground.is_some() {
+                return Err(ParseError::new(
+                    "Duplicate 'ground' declaration",
+                    Some(line_span),
+                ));
+            }
+            ground = Some(parse_ground(line, Some(line_span))?);
+            declared("ground", "", i);
+            spans.ground = Some(decl_span);
             i += 1;
         } else if line.starts_with("if ") {
             // v0.8: Top-level if statement
             trace_parse!("  -> if statement");
             let (stmt, next_line) = parse_if_stmt(&lines, i, &ctx)?;
             trace_parse!("  -> if parsed, next line: {}", next_line + 1);
+            spans.top_level_calls.push(decl_span);
             top_level_calls.push(stmt);
             i = next_line;
         } else if line.starts_with("for ") {
@@ -288,13 +423,17 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
             trace_parse!("  -> for loop");
             let (stmt, next_line) = parse_for_stmt(&lines, i, &ctx)?;
             trace_parse!("  -> for parsed, next line: {}", next_line + 1);
+            spans.top_level_calls.push(decl_span);
             top_level_calls.push(stmt);
             i = next_line;
+        } else if line.starts_with("while ") {
+            return Err(bare_while_error(line, line_span));
         } else if line.starts_with("match ") {
             // v0.8: Top-level match statement
             trace_parse!("  -> match statement");
             let (stmt, next_line) = parse_match_stmt(&lines, i, &ctx)?;
             trace_parse!("  -> match parsed, next line: {}", next_line + 1);
+            spans.top_level_calls.push(decl_span);
             top_level_calls.push(stmt);
             i = next_line;
         } else {
@@ -316,6 +455,7 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
                                 .collect::<Result<Vec<_>, _>>()?
                         };
                         // Store top-level function calls for execution
+                        spans.top_level_calls.push(decl_span);
                         top_level_calls.push(Stmt::ExprCall {
                             name: func_name.to_string(),
                             args,
@@ -326,11 +466,10 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
                 }
             }
             
-            return Err(ctx.error(
-                format!("Unexpected token: '{}'", line.split_whitespace().next().unwrap_or("")),
-                i,
-                "top-level parsing",
-            ));
+            let token = line.split_whitespace().next().unwrap_or("");
+            return Err(ctx
+                .error(format!("Unexpected token: '{}'", token), i, "top-level parsing")
+                .with_fragment(token));
         }
     }
 
@@ -348,11 +487,13 @@ pub fn parse_program(source: &str) -> Result<Program, ParseError> {
         detectors,
         loops,
         wells,
+        ground,
+        spans,
     })
 }
 
-/// Parse a particle declaration: `particle name at (x, y) mass m`
-fn parse_particle(line: &str, span: Option<Span>) -> Result<ParticleDecl, ParseError> {
+/// Parse a particle declaration: `particle name at (x, y) mass m [damping c] [tag name = v]...`
+pub(crate) fn parse_particle(line: &str, span: Option<Span>) -> Result<ParticleDecl, ParseError> {
     // Remove "particle " prefix
     let rest = line.strip_prefix("particle ").ok_or_else(|| {
         ParseError::new("Expected 'particle' keyword", span)
@@ -370,7 +511,7 @@ fn parse_particle(line: &str, span: Option<Span>) -> Result<ParticleDecl, ParseE
     let pos_start = rest.find('(').ok_or_else(|| {
         ParseError::new(format!("Expected '(' in position: {}", line), span)
     })?;
-    let pos_end = rest.find(')').ok_or_else(|| {
+    let pos_end = rest[pos_start..].find(')').map(|end| pos_start + end).ok_or_else(|| {
         ParseError::new(format!("Expected ')' in position: {}", line), span)
     })?;
     
@@ -393,18 +534,66 @@ fn parse_particle(line: &str, span: Option<Span>) -> Result<ParticleDecl, ParseE
         ParseError::new(format!("Expected 'mass' in particle declaration: {}", line), span)
     })?;
     
-    let mass_str = &rest[mass_start + 5..].trim();
-    let mass_expr = parse_expr(mass_str, span)?;
+    let mass_str = rest[mass_start + 5..].trim();
+
+    // Optional trailing `tag name = v` clauses
+    let (mass_str, tags) = match mass_str.find(" tag ") {
+        Some(tags_start) => (
+            &mass_str[..tags_start],
+            parse_particle_tags(&mass_str[tags_start + 1..], line, span)?,
+        ),
+        None => (mass_str, Vec::new()),
+    };
+
+    // Optional trailing `damping c`
+    let (mass_str, damping_expr) = match mass_str.find(" damping ") {
+        Some(damping_start) => {
+            let damping_str = mass_str[damping_start + 9..].trim();
+            (&mass_str[..damping_start], Some(parse_expr(damping_str, span)?))
+        }
+        None => (mass_str, None),
+    };
+    let mass_expr = parse_expr(mass_str.trim(), span)?;
     
     Ok(ParticleDecl {
         name,
         position: (x_expr, y_expr),
         mass: mass_expr,
+        damping: damping_expr,
+        tags,
     })
 }
 
-/// Parse a force declaration: `force gravity(a, b) G = x` or `force spring(a, b) k = x rest = y`
-fn parse_force(line: &str, span: Option<Span>) -> Result<ForceDecl, ParseError> {
+/// Parse the `tag name = v` clauses ending a particle declaration
+fn parse_particle_tags(
+    clauses: &str,
+    line: &str,
+    span: Option<Span>,
+) -> Result<Vec<(String, Expr)>, ParseError> {
+    let mut tags: Vec<(String, Expr)> = Vec::new();
+    for clause in clauses.split(" tag ") {
+        let clause = clause.trim().strip_prefix("tag ").unwrap_or(clause).trim();
+        let (name, value) = clause
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, value)| is_valid_identifier(name) && !value.is_empty())
+            .ok_or_else(|| {
+                ParseError::new(format!("Expected 'tag name = value' in: {}", line), span)
+            })?;
+        if tags.iter().any(|(existing, _)| existing == name) {
+            return Err(ParseError::new(
+                format!("Duplicate tag '{}' in: {}", name, line),
+                span,
+            ));
+        }
+        tags.push((name.to_string(), parse_expr(value, span)?));
+    }
+    Ok(tags)
+}
+
+/// Parse a force declaration: `force gravity(a, b) G = x`, `force spring(a, b) k = x rest = y`,
+/// or `force central(a) at (x, y) GM = m`
+pub(crate) fn parse_force(line: &str, span: Option<Span>) -> Result<ForceDecl, ParseError> {
     // Remove "force " prefix
     let rest = line.strip_prefix("force ").ok_or_else(|| {
         ParseError::new("Expected 'force' keyword", span)
@@ -425,6 +614,23 @@ fn parse_force(line: &str, span: Option<Span>) -> Result<ForceDecl, ParseError>
     
     let args_str = &rest[..paren_end];
     let args: Vec<&str> = args_str.split(',').map(|s| s.trim()).collect();
+    let rest = &rest[paren_end + 1..].trim();
+
+    if force_type == "central" {
+        if args.len() != 1 {
+            return Err(ParseError::new(
+                format!("Expected one particle name in central force: {}", line),
+                span,
+            ));
+        }
+        let a = strip_quotes(args[0]);
+        return Ok(ForceDecl {
+            b: a.clone(),
+            a,
+            kind: parse_central_force(rest, line, span)?,
+        });
+    }
+
     if args.len() != 2 {
         return Err(ParseError::new(
             format!("Expected two particle names in force: {}", line),
@@ -436,8 +642,6 @@ fn parse_force(line: &str, span: Option<Span>) -> Result<ForceDecl, ParseError>
     let a = strip_quotes(args[0]);
     let b = strip_quotes(args[1]);
     
-    let rest = &rest[paren_end + 1..].trim();
-    
     let kind = match force_type {
         "gravity" => {
             // Parse: G = value
@@ -469,15 +673,84 @@ fn parse_force(line: &str, span: Option<Span>) -> Result<ForceDecl, ParseError>
             return Err(ParseError::new(
                 format!("Unknown force type: {}", force_type),
                 span,
-            ));
+            )
+            .with_fragment(force_type));
         }
     };
     
     Ok(ForceDecl { a, b, kind })
 }
 
-/// Parse a simulate declaration: `simulate dt = x steps = n`
-fn parse_simulate(line: &str, span: Option<Span>) -> Result<SimulateDecl, ParseError> {
+/// Parse the tail of a central force: `at (x, y) GM = value`
+fn parse_central_force(rest: &str, line: &str, span: Option<Span>) -> Result<ForceKind, ParseError> {
+    let rest = rest.strip_prefix("at ").ok_or_else(|| {
+        ParseError::new(format!("Expected 'at' in central force: {}", line), span)
+    })?;
+    let rest = rest.trim_start().strip_prefix('(').ok_or_else(|| {
+        ParseError::new(format!("Expected '(' in central force point: {}", line), span)
+    })?;
+    let point_end = rest.find(')').ok_or_else(|| {
+        ParseError::new(format!("Expected ')' in central force point: {}", line), span)
+    })?;
+
+    let coords: Vec<&str> = rest[..point_end].split(',').map(|s| s.trim()).collect();
+    if coords.len() != 2 {
+        return Err(ParseError::new(
+            format!("Expected two coordinates in central force point: {}", line),
+            span,
+        ));
+    }
+    let x_expr = parse_expr(coords[0], span)?;
+    let y_expr = parse_expr(coords[1], span)?;
+
+    let gm_str = rest[point_end + 1..].trim().strip_prefix("GM = ").ok_or_else(|| {
+        ParseError::new(format!("Expected 'GM =' in central force: {}", line), span)
+    })?;
+    let gm_expr = parse_expr(gm_str.trim(), span)?;
+
+    Ok(ForceKind::Central {
+        point: (x_expr, y_expr),
+        gm: gm_expr,
+    })
+}
+
+/// Parse a ground declaration: `ground y = h friction mu [gravity g]`
+pub(crate) fn parse_ground(line: &str, span: Option<Span>) -> Result<GroundDecl, ParseError> {
+    let rest = line.strip_prefix("ground ").ok_or_else(|| {
+        ParseError::new("Expected 'ground' keyword", span)
+    })?;
+    let rest = rest.trim_start().strip_prefix("y = ").ok_or_else(|| {
+        ParseError::new(format!("Expected 'y =' in ground: {}", line), span)
+    })?;
+    let friction_start = rest.find(" friction ").ok_or_else(|| {
+        ParseError::new(format!("Expected 'friction' in ground: {}", line), span)
+    })?;
+    let y_expr = parse_expr(rest[..friction_start].trim(), span)?;
+
+    // Optional trailing `gravity g`
+    let after_friction = &rest[friction_start + 10..];
+    let (friction_str, gravity_expr) = match after_friction.find(" gravity ") {
+        Some(gravity_start) => {
+            let gravity_str = after_friction[gravity_start + 9..].trim();
+            (
+                &after_friction[..gravity_start],
+                Some(parse_expr(gravity_str, span)?),
+            )
+        }
+        None => (after_friction, None),
+    };
+    let friction_expr = parse_expr(friction_str.trim(), span)?;
+
+    Ok(GroundDecl {
+        y: y_expr,
+        friction: friction_expr,
+        gravity: gravity_expr,
+    })
+}
+
+/// Parse a simulate declaration:
+/// `simulate dt = x steps = n [frame = lab|center_of_mass] [recenter = once|always]`
+pub(crate) fn parse_simulate(line: &str, span: Option<Span>) -> Result<SimulateDecl, ParseError> {
     // Remove "simulate " prefix
     let rest = line.strip_prefix("simulate ").ok_or_else(|| {
         ParseError::new("Expected 'simulate' keyword", span)
@@ -495,14 +768,74 @@ fn parse_simulate(line: &str, span: Option<Span>) -> Result<SimulateDecl, ParseE
     let dt_str = &after_dt[..dt_end].trim();
     let dt_expr = parse_expr(dt_str, span)?;
     
-    let steps_str = &after_dt[dt_end + 9..].trim();
+    // Optional `frame = ...` and `recenter = ...` follow the step count
+    let after_steps = &after_dt[dt_end + 9..];
+    let options_start = [" frame = ", " recenter = "]
+        .iter()
+        .filter_map(|option| after_steps.find(option))
+        .min()
+        .unwrap_or(after_steps.len());
+    let steps_str = &after_steps[..options_start].trim();
     let steps_expr = parse_expr(steps_str, span)?;
-    
-    Ok(SimulateDecl { dt: dt_expr, steps: steps_expr })
+    let frame = parse_frame(&after_steps[options_start..], line, span)?;
+
+    Ok(SimulateDecl {
+        dt: dt_expr,
+        steps: steps_expr,
+        frame,
+    })
+}
+
+/// Parse the simulate options `frame = ...` and `recenter = ...`, each at most once
+fn parse_frame(options: &str, line: &str, span: Option<Span>) -> Result<Frame, ParseError> {
+    let tokens: Vec<&str> = options.split_whitespace().collect();
+    let mut frame = None;
+    let mut recenter = None;
+    for option in tokens.chunks(3) {
+        match option {
+            ["frame", "=", value] if frame.is_none() => frame = Some(*value),
+            ["recenter", "=", value] if recenter.is_none() => recenter = Some(*value),
+            _ => {
+                return Err(ParseError::new(
+                    format!("Unexpected simulate option '{}': {}", option.join(" "), line),
+                    span,
+                ))
+            }
+        }
+    }
+
+    let recenter_mode = match recenter {
+        None | Some("once") => Recenter::Once,
+        Some("always") => Recenter::Always,
+        Some(other) => {
+            return Err(ParseError::new(
+                format!("Expected 'once' or 'always' for recenter, got '{}': {}", other, line),
+                span,
+            )
+            .with_fragment(other))
+        }
+    };
+    match frame {
+        None | Some("lab") if recenter.is_some() => Err(ParseError::new(
+            format!("'recenter' requires 'frame = center_of_mass': {}", line),
+            span,
+        )),
+        None | Some("lab") => Ok(Frame::Lab),
+        Some("center_of_mass") => Ok(Frame::CenterOfMass {
+            recenter: recenter_mode,
+        }),
+        Some(other) => Err(ParseError::new(
+            format!("Expected 'lab' or 'center_of_mass' for frame, got '{}': {}", other, line),
+            span,
+        )
+        .with_fragment(other)),
+    }
 }
 
-/// Parse a detector declaration: `detect name = position(a)` or `detect name = distance(a, b)`
-fn parse_detector(line: &str, span: Option<Span>) -> Result<DetectorDecl, ParseError> {
+/// Parse a detector declaration: `detect name = position(a)`, `detect name = distance(a, b)`,
+/// or a whole-world quantity such as `detect name = total_energy()`, each
+/// optionally followed by `initial`
+pub(crate) fn parse_detector(line: &str, span: Option<Span>) -> Result<DetectorDecl, ParseError> {
     // Remove "detect " prefix
     let rest = line.strip_prefix("detect ").ok_or_else(|| {
         ParseError::new("Expected 'detect' keyword", span)
@@ -514,48 +847,132 @@ fn parse_detector(line: &str, span: Option<Span>) -> Result<DetectorDecl, ParseE
     })?;
     
     let name = rest[..eq_pos].trim().to_string();
-    let rest = &rest[eq_pos + 3..].trim();
+    // Detectors have always allowed a trailing comment
+    let rest = rest[eq_pos + 3..].split('#').next().unwrap_or_default().trim();
+    let (rest, initial) = match rest.strip_suffix("initial") {
+        Some(kind) if kind.ends_with(char::is_whitespace) => (&kind.trim_end(), true),
+        _ => (&rest, false),
+    };
     
-    let kind = if rest.starts_with("position(") {
-        // Parse: position(name)
-        let start = rest.find('(').unwrap();
-        let end = rest.find(')').ok_or_else(|| {
-            ParseError::new(format!("Expected ')' in position detector: {}", line), span)
-        })?;
-        let particle_name = rest[start + 1..end].trim().to_string();
-        DetectorKind::Position(particle_name)
-    } else if rest.starts_with("distance(") {
-        // Parse: distance(a, b)
-        let start = rest.find('(').unwrap();
-        let end = rest.find(')').ok_or_else(|| {
-            ParseError::new(format!("Expected ')' in distance detector: {}", line), span)
-        })?;
-        let args_str = &rest[start + 1..end];
-        let args: Vec<&str> = args_str.split(',').map(|s| s.trim()).collect();
-        if args.len() != 2 {
+    let expr = parse_expr(rest, span)?;
+    
+    Ok(DetectorDecl {
+        name,
+        expr,
+        initial,
+    })
+}
+
+/// The observable `name(args)suffix` reads, or `None` if `name` isn't one,
+/// as in `position(a).y` or `distance(a, b)`; `text` is the whole call
+fn parse_observable(
+    name: &str,
+    args: &str,
+    suffix: &str,
+    text: &str,
+    span: Option<Span>,
+) -> Result<Option<DetectorKind>, ParseError> {
+    let particles: Vec<String> = if args.trim().is_empty() {
+        Vec::new()
+    } else {
+        args.split(',').map(|arg| arg.trim().to_string()).collect()
+    };
+    let arity = match name {
+        "position" | "velocity" => 1,
+        "distance" | "collision_count" => 2,
+        "kinetic_energy" | "potential_energy" | "total_energy" | "momentum"
+        | "center_of_mass" | "time" => 0,
+        _ => return Ok(None),
+    };
+    if particles.len() != arity || !particles.iter().all(|p| is_valid_identifier(p)) {
+        let expected = match arity {
+            0 => "no arguments".to_string(),
+            1 => "a particle name".to_string(),
+            n => format!("{} particle names", n),
+        };
+        return Err(ParseError::new(
+            format!("Expected {} in {}(...): {}", expected, name, text),
+            span,
+        )
+        .with_fragment(text));
+    }
+    let suffix = suffix.trim();
+    let mut particles = particles.into_iter();
+    let mut particle = || particles.next().unwrap();
+    let kind = match (name, suffix) {
+        // A bare `position(a)` is the x coordinate, as it always was
+        ("position", "" | ".x") => DetectorKind::Position(particle()),
+        ("position", ".y") => DetectorKind::PositionY(particle()),
+        ("velocity", ".x") => DetectorKind::VelocityX(particle()),
+        ("velocity", ".y") => DetectorKind::VelocityY(particle()),
+        // So is a bare `center_of_mass()`
+        ("center_of_mass", "" | ".x") => DetectorKind::CenterOfMass,
+        ("center_of_mass", ".y") => DetectorKind::CenterOfMassY,
+        ("position" | "velocity" | "center_of_mass", _) => {
             return Err(ParseError::new(
-                format!("Expected two particle names in distance detector: {}", line),
+                format!("Expected '.x' or '.y' after {}(...): {}", name, text),
                 span,
-            ));
+            )
+            .with_fragment(text));
         }
-        DetectorKind::Distance {
-            a: args[0].to_string(),
-            b: args[1].to_string(),
+        (_, suffix) if !suffix.is_empty() => {
+            return Err(ParseError::new(
+                format!("Unexpected '{}' after {}(...): {}", suffix, name, text),
+                span,
+            )
+            .with_fragment(text));
         }
-    } else {
-        return Err(ParseError::new(
-            format!("Unknown detector type: {}", rest),
-            span,
-        ));
+        ("distance", _) => DetectorKind::Distance {
+            a: particle(),
+            b: particle(),
+        },
+        ("collision_count", _) => DetectorKind::CollisionCount {
+            a: particle(),
+            b: particle(),
+        },
+        ("kinetic_energy", _) => DetectorKind::KineticEnergy,
+        ("potential_energy", _) => DetectorKind::PotentialEnergy,
+        ("total_energy", _) => DetectorKind::TotalEnergy,
+        ("momentum", _) => DetectorKind::Momentum,
+        ("time", _) => DetectorKind::Time,
+        _ => unreachable!("'{}' was matched above", name),
     };
-    
-    Ok(DetectorDecl { name, kind })
+    Ok(Some(kind))
 }
 
 // ============================================================================
 // v0.2: Loop and Well Parsing
 // ============================================================================
 
+/// The `i in 0..5` of a `loop for i in 0..5 {` written for a general for loop
+fn range_for_header(after_for: &str) -> Option<&str> {
+    let header = after_for.split('{').next().unwrap_or(after_for).trim();
+    (header.contains(" in ") && header.contains("..") && !header.contains(" cycles"))
+        .then_some(header)
+}
+
+/// The error for a `while` written without `loop`, which only exists as a
+/// physics loop
+fn bare_while_error(line: &str, span: Span) -> ParseError {
+    let condition = line
+        .strip_prefix("while")
+        .unwrap_or(line)
+        .split('{')
+        .next()
+        .unwrap_or("")
+        .trim();
+    let condition = if condition.is_empty() { "<condition>" } else { condition };
+    ParseError::new(
+        format!(
+            "'while' is only available as a physics loop: write 'loop while {} with frequency \
+             <f> damping <d> on <particle> {{ ... }}', or 'for i in 0..n {{ ... }}' to repeat \
+             statements",
+            condition
+        ),
+        Some(span),
+    )
+}
+
 /// Parse a loop declaration (handles multi-line bodies)
 fn parse_loop(
     lines: &[&str],
@@ -571,6 +988,16 @@ fn parse_loop(
     let (kind, body_start) = if rest.starts_with("for ") {
         // `loop for <integer> cycles with frequency <float> damping <float> on <ident> {`
         let after_for = rest.strip_prefix("for ").unwrap();
+        if let Some(header) = range_for_header(after_for) {
+            return Err(ParseError::new(
+                format!(
+                    "'loop for' declares a physics oscillator; for iteration over a range, \
+                     write 'for {} {{ ... }}' without the 'loop' keyword",
+                    header
+                ),
+                Some(line_span),
+            ));
+        }
         
         // Find " cycles"
         let cycles_end = after_for.find(" cycles").ok_or_else(|| {
@@ -671,35 +1098,39 @@ fn parse_loop(
         return Err(ParseError::new(format!("Unknown loop type: {}", line), Some(line_span)));
     };
 
-    // Parse loop body (lines until closing brace)
+    // Parse loop body (lines until closing brace); pushes are the only
+    // statements a physics loop runs, so anything else is a mistake
     let mut body = Vec::new();
     let mut i = body_start;
-    let mut brace_count = 1; // We've seen the opening brace
+    let mut closed = false;
     
-    while i < lines.len() && brace_count > 0 {
+    while i < lines.len() {
         let body_line = lines[i].trim();
         let body_span = ctx.full_line_span(i);
+        i += 1;
         
         if body_line == "}" {
-            brace_count -= 1;
-            if brace_count == 0 {
-                i += 1;
-                break;
-            }
-        } else if body_line.ends_with('{') {
-            brace_count += 1;
+            closed = true;
+            break;
         }
-        
-        if brace_count > 0 && !body_line.is_empty() && !body_line.starts_with('#') {
-            if body_line.starts_with("force push(") {
-                body.push(parse_loop_body_stmt(body_line, Some(body_span))?);
-            }
+        if body_line.is_empty() || body_line.starts_with('#') {
+            continue;
         }
-        
-        i += 1;
+        if !body_line.starts_with("force push(") {
+            return Err(ParseError::new(
+                format!(
+                    "Unsupported statement in loop body: {}; a physics loop body may only \
+                     contain 'force push(<particle>) magnitude <m> direction (<x>, <y>)' \
+                     statements and comments",
+                    body_line
+                ),
+                Some(body_span),
+            ));
+        }
+        body.push(parse_loop_body_stmt(body_line, Some(body_span))?);
     }
     
-    if brace_count > 0 {
+    if !closed {
         return Err(ParseError::new("Unclosed loop body".to_string(), Some(line_span)));
     }
     
@@ -715,7 +1146,7 @@ fn parse_condition(cond_str: &str, span: Option<Span>) -> Result<ConditionExpr,
     // Try position(<ident>).x < float or position(<ident>).x > float
     if cond_str.contains("position(") {
         let pos_start = cond_str.find("position(").unwrap();
-        let pos_end = cond_str.find(')').ok_or_else(|| {
+        let pos_end = cond_str[pos_start..].find(')').map(|end| pos_start + end).ok_or_else(|| {
             ParseError::new(format!("Expected ')' in position condition: {}", cond_str), span)
         })?;
         let particle_name = cond_str[pos_start + 9..pos_end].trim().to_string();
@@ -779,11 +1210,57 @@ fn parse_condition(cond_str: &str, span: Option<Span>) -> Result<ConditionExpr,
         }
     }
     
+    // Try mass(a) < float or tag(a, name) > float
+    if let Some((observable, rest)) = parse_particle_observable(cond_str, span)? {
+        let rest = rest.trim();
+        if let Some(threshold) = rest.strip_prefix("< ") {
+            return Ok(ConditionExpr::LessThan(observable, parse_expr(threshold.trim(), span)?));
+        } else if let Some(threshold) = rest.strip_prefix("> ") {
+            return Ok(ConditionExpr::GreaterThan(observable, parse_expr(threshold.trim(), span)?));
+        }
+    }
+    
     Err(ParseError::new(format!("Unknown condition format: {}", cond_str), span))
 }
 
+/// Parse a leading `mass(a)` or `tag(a, name)` observable, returning it with
+/// the text after its closing paren
+fn parse_particle_observable(
+    text: &str,
+    span: Option<Span>,
+) -> Result<Option<(ObservableExpr, &str)>, ParseError> {
+    let (args_str, rest) = match text.strip_prefix("mass(").or_else(|| text.strip_prefix("tag(")) {
+        Some(after_paren) => {
+            let paren_end = after_paren.find(')').ok_or_else(|| {
+                ParseError::new(format!("Expected ')' in: {}", text), span)
+            })?;
+            (&after_paren[..paren_end], &after_paren[paren_end + 1..])
+        }
+        None => return Ok(None),
+    };
+    let args: Vec<&str> = args_str.split(',').map(|s| s.trim()).collect();
+    let observable = if text.starts_with("mass(") {
+        if args.len() != 1 || args[0].is_empty() {
+            return Err(ParseError::new(
+                format!("Expected a particle name in mass(...): {}", text),
+                span,
+            ));
+        }
+        ObservableExpr::Mass(args[0].to_string())
+    } else {
+        if args.len() != 2 || args[0].is_empty() || !is_valid_identifier(args[1]) {
+            return Err(ParseError::new(
+                format!("Expected a particle name and a tag name in tag(...): {}", text),
+                span,
+            ));
+        }
+        ObservableExpr::Tag(args[0].to_string(), args[1].to_string())
+    };
+    Ok(Some((observable, rest)))
+}
+
 /// Parse a loop body statement
-fn parse_loop_body_stmt(line: &str, span: Option<Span>) -> Result<LoopBodyStmt, ParseError> {
+pub(crate) fn parse_loop_body_stmt(line: &str, span: Option<Span>) -> Result<LoopBodyStmt, ParseError> {
     // `force push(<ident>) magnitude <float> direction (<float>, <float>)`
     let rest = line.strip_prefix("force push(").ok_or_else(|| {
         ParseError::new("Expected 'force push('", span)
@@ -811,7 +1288,7 @@ fn parse_loop_body_stmt(line: &str, span: Option<Span>) -> Result<LoopBodyStmt,
     let dir_start = after_dir.find('(').ok_or_else(|| {
         ParseError::new(format!("Expected '(' in direction: {}", line), span)
     })?;
-    let dir_end = after_dir.find(')').ok_or_else(|| {
+    let dir_end = after_dir[dir_start..].find(')').map(|end| dir_start + end).ok_or_else(|| {
         ParseError::new(format!("Expected ')' in direction: {}", line), span)
     })?;
     let dir_str = &after_dir[dir_start + 1..dir_end];
@@ -833,7 +1310,7 @@ fn parse_loop_body_stmt(line: &str, span: Option<Span>) -> Result<LoopBodyStmt,
 }
 
 /// Parse a well declaration: `well <name> on <ident> if position(<ident>).x >= <float> depth <float>`
-fn parse_well(line: &str, span: Option<Span>) -> Result<WellDecl, ParseError> {
+pub(crate) fn parse_well(line: &str, span: Option<Span>) -> Result<WellDecl, ParseError> {
     // Remove "well " prefix
     let rest = line.strip_prefix("well ").ok_or_else(|| {
         ParseError::new("Expected 'well' keyword", span)
@@ -938,9 +1415,30 @@ fn parse_well(line: &str, span: Option<Span>) -> Result<WellDecl, ParseError> {
             threshold: threshold_expr,
             depth: depth_expr,
         });
+    } else if let Some((observable, after_paren)) = parse_particle_observable(after_if, span)? {
+        // Parse mass(p) >= threshold or tag(p, name) >= threshold
+        let after_ge = after_paren.strip_prefix(" >= ").ok_or_else(|| {
+            ParseError::new(format!("Expected ' >= ' after observable: {}", line), span)
+        })?;
+        let depth_pos = after_ge.find(" depth ").ok_or_else(|| {
+            ParseError::new(format!("Expected 'depth' in well: {}", line), span)
+        })?;
+        let threshold_expr = parse_expr(after_ge[..depth_pos].trim(), span)?;
+        let depth_expr = parse_expr(after_ge[depth_pos + 7..].trim(), span)?;
+
+        Ok(WellDecl {
+            name,
+            particle,
+            observable,
+            threshold: threshold_expr,
+            depth: depth_expr,
+        })
     } else {
         return Err(ParseError::new(
-            format!("Expected 'position(' or 'distance(' in well: {}", line),
+            format!(
+                "Expected 'position(', 'distance(', 'mass(' or 'tag(' in well: {}",
+                line
+            ),
             span,
         ));
     }
@@ -951,7 +1449,7 @@ fn parse_well(line: &str, span: Option<Span>) -> Result<WellDecl, ParseError> {
 // ============================================================================
 
 /// Parse a let declaration: `let name = expr`
-fn parse_let(line: &str, span: Option<Span>) -> Result<LetDecl, ParseError> {
+pub(crate) fn parse_let(line: &str, span: Option<Span>) -> Result<LetDecl, ParseError> {
     let rest = line.strip_prefix("let ").ok_or_else(|| {
         ParseError::new("Expected 'let' keyword", span)
     })?;
@@ -975,6 +1473,11 @@ fn parse_let(line: &str, span: Option<Span>) -> Result<LetDecl, ParseError> {
     Ok(LetDecl { name, expr })
 }
 
+/// Parse a standalone expression, e.g. `sqrt(2) * spring_k`
+pub fn parse_expression(s: &str) -> Result<Expr, ParseError> {
+    parse_expr(s, None)
+}
+
 /// Parse an expression from a string
 /// Grammar: ExprAdd (with precedence: add/sub < mul/div < unary < primary)
 fn parse_expr(s: &str, span: Option<Span>) -> Result<Expr, ParseError> {
@@ -1010,15 +1513,13 @@ fn parse_expr_comparison(s: &str, span: Option<Span>) -> Result<Expr, ParseError
         }
         
         if paren_depth == 0 {
-            // Check for two-character operators
-            if i + 1 < s.len() {
-                let two_char = &s[i..i + 2];
-                for (op_str, op_type) in &operators[..4] {
-                    if two_char == *op_str {
-                        op_pos = Some(i);
-                        op = Some(*op_type);
-                        break;
-                    }
+            // Check for two-character operators (by prefix: `i + 2` may not
+            // be a char boundary)
+            for (op_str, op_type) in &operators[..4] {
+                if s[i..].starts_with(*op_str) {
+                    op_pos = Some(i);
+                    op = Some(*op_type);
+                    break;
                 }
             }
             
@@ -1185,7 +1686,7 @@ fn parse_expr_primary(s: &str, span: Option<Span>) -> Result<Expr, ParseError> {
         // Find matching closing paren
         let mut paren_count = 0;
         let mut end_pos = None;
-        for (i, ch) in rest.chars().enumerate() {
+        for (i, ch) in rest.char_indices() {
             match ch {
                 '(' => paren_count += 1,
                 ')' => {
@@ -1202,6 +1703,10 @@ fn parse_expr_primary(s: &str, span: Option<Span>) -> Result<Expr, ParseError> {
         if let Some(end) = end_pos {
             let args_str = &rest[1..end];
             
+            if let Some(kind) = parse_observable(func_name, args_str, &rest[end + 1..], s, span)? {
+                return Ok(Expr::Observable(kind));
+            }
+            
             // Parse arguments (handle nested parentheses correctly)
             let args = if args_str.trim().is_empty() {
                 Vec::new()
@@ -1230,7 +1735,8 @@ fn parse_expr_primary(s: &str, span: Option<Span>) -> Result<Expr, ParseError> {
                 return Err(ParseError::new(
                     format!("Invalid function name: '{}'", func_name),
                     span,
-                ));
+                )
+                .with_fragment(func_name));
             }
         }
     }
@@ -1249,7 +1755,8 @@ fn parse_expr_primary(s: &str, span: Option<Span>) -> Result<Expr, ParseError> {
     Err(ParseError::new(
         format!("Invalid expression: {}", s),
         span,
-    ))
+    )
+    .with_fragment(s))
 }
 
 
@@ -1358,22 +1865,15 @@ fn parse_function(
     
     // Parse parameters
     let params_str = rest[..paren_end].trim();
-    let params = if params_str.is_empty() {
-        Vec::new()
+    let (params, param_types): (Vec<String>, Vec<Option<ParamType>>) = if params_str.is_empty() {
+        (Vec::new(), Vec::new())
     } else {
         params_str
             .split(',')
-            .map(|p| {
-                let p = p.trim();
-                if !is_valid_identifier(p) {
-                    return Err(ParseError::new(
-                        format!("Invalid parameter name: {}", p),
-                        Some(line_span),
-                    ));
-                }
-                Ok(p.to_string())
-            })
+            .map(|p| parse_param(p, line_span))
             .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip()
     };
     
     // Check for duplicate parameter names
@@ -1425,12 +1925,42 @@ fn parse_function(
         FunctionDecl {
             name,
             params,
+            param_types,
             body,
         },
         next_line,
     ))
 }
 
+/// Parse one parameter of a function declaration: `p`, `p: name` or `p: number`
+fn parse_param(param: &str, span: Span) -> Result<(String, Option<ParamType>), ParseError> {
+    let (name, annotation) = match param.split_once(':') {
+        Some((name, annotation)) => (name.trim(), Some(annotation.trim())),
+        None => (param.trim(), None),
+    };
+    if !is_valid_identifier(name) {
+        return Err(ParseError::new(
+            format!("Invalid parameter name: {}", name),
+            Some(span),
+        ));
+    }
+    let param_type = match annotation {
+        None => None,
+        Some("name") => Some(ParamType::Name),
+        Some("number") => Some(ParamType::Number),
+        Some(other) => {
+            return Err(ParseError::new(
+                format!(
+                    "Unknown type '{}' for parameter '{}': expected 'name' or 'number'",
+                    other, name
+                ),
+                Some(span),
+            ))
+        }
+    };
+    Ok((name.to_string(), param_type))
+}
+
 /// Parse a block of statements: `{ stmt1 stmt2 ... }`
 fn parse_block(
     lines: &[&str],
@@ -1469,7 +1999,13 @@ fn parse_block(
             brace_count = 1;
             i += 1;
         } else {
-            trace_parse!("  -> WARNING: block doesn't start with '{{', first_line = '{}'", first_line);
+            // Returning an empty block here would leave callers on the same
+            // line forever
+            return Err(ctx.error(
+                format!("Expected '{{' to open a block, got: '{}'", first_line),
+                i,
+                "parse_block",
+            ));
         }
     }
     
@@ -1866,6 +2402,8 @@ fn parse_stmt(
     } else if line_no_semi.starts_with("match ") {
         trace_parse!("  -> match statement");
         return parse_match_stmt(lines, start_idx, ctx);
+    } else if line_no_semi.starts_with("while ") {
+        Err(bare_while_error(line_no_semi, line_span))
     } else if line_no_semi.starts_with("let ") {
         trace_parse!("  -> let declaration");
         let let_decl = parse_let(line_no_semi, Some(line_span))?;
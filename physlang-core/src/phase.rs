@@ -0,0 +1,58 @@
+//! Phase records for the `log` facade
+//!
+//! Parsing, analysis, function execution, building, and simulating each log
+//! a debug record under the `physlang_core::phase` target when they start
+//! and another with their duration when they end. With no logger installed,
+//! or debug filtered out, a phase costs one level check.
+
+use log::Level;
+use std::time::Instant;
+
+/// Target of every phase record
+pub const PHASE_TARGET: &str = "physlang_core::phase";
+
+/// An open phase; dropping it logs the exit
+pub(crate) struct Phase {
+    name: &'static str,
+    enabled: bool,
+    started: Option<Instant>,
+}
+
+/// Log the start of phase `name`
+pub(crate) fn enter(name: &'static str) -> Phase {
+    let enabled = log::log_enabled!(target: PHASE_TARGET, Level::Debug);
+    if enabled {
+        log::debug!(target: PHASE_TARGET, "enter {}", name);
+    }
+    Phase {
+        name,
+        enabled,
+        started: if enabled { clock() } else { None },
+    }
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        match self.started {
+            Some(started) => {
+                log::debug!(target: PHASE_TARGET, "exit {} ({:.2?})", self.name, started.elapsed())
+            }
+            None => log::debug!(target: PHASE_TARGET, "exit {}", self.name),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn clock() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+/// `Instant::now` panics on wasm32-unknown-unknown; phases there log no
+/// durations
+#[cfg(target_arch = "wasm32")]
+fn clock() -> Option<Instant> {
+    None
+}
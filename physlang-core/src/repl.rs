@@ -0,0 +1,226 @@
+//! Line-oriented session behind `physlang repl`
+//!
+//! Declarations accumulate into a program, plain expressions are evaluated at
+//! once, and `:` commands build and step a simulation of what has been declared.
+//! The session only deals in lines of text, so the terminal loop in the CLI and
+//! the tests drive it the same way.
+
+use crate::ast::{Expr, Program};
+use crate::eval::eval_source_expr;
+use crate::format::count_braces;
+use crate::parser::{self, parse_expression, parse_program};
+use crate::runtime::{
+    build_run_context, check_program, evaluate_detectors, expand_functions, try_step_simulation,
+    RunOptions, SimulationContext,
+};
+
+/// `simulate` used until the session declares its own
+const DEFAULT_SIMULATE: &str = "simulate dt = 0.01 steps = 1";
+
+/// Keywords that start a declaration rather than an expression
+const DECLARATION_KEYWORDS: [&str; 12] = [
+    "let", "particle", "force", "fn", "simulate", "detect", "loop", "well", "ground", "for", "if",
+    "match",
+];
+
+/// Help text for `:help`
+const HELP: &str = "\
+declarations (let, particle, force, fn, ...) add to the program
+expressions are evaluated against the let bindings and printed
+:run [N]        step the simulation N times (default 1)
+:show           print the time and every particle's position and velocity
+:detect KIND    evaluate a detector now, e.g. :detect distance(a, b)
+:source         print the program declared so far
+:reset          forget everything declared
+:quit           leave the session";
+
+/// What the caller should do after a line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplReply {
+    /// Print this text (nothing if it's empty)
+    Output(String),
+    /// The line opened a block; read more lines before anything happens
+    Incomplete,
+    /// End the session
+    Quit,
+}
+
+/// An interactive session: the program declared so far and its simulation
+#[derive(Default)]
+pub struct ReplSession {
+    /// Accepted declarations in order, one source chunk each
+    declarations: Vec<String>,
+    /// The latest `simulate` declaration
+    simulate: Option<String>,
+    /// Lines of a block that isn't closed yet, and its brace depth
+    pending: Vec<String>,
+    depth: usize,
+    /// The simulation of the current declarations, built on first use
+    run: Option<(Program, SimulationContext)>,
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle one line of input; errors leave the session as it was
+    pub fn handle_line(&mut self, line: &str) -> Result<ReplReply, String> {
+        let trimmed = line.trim();
+        if !self.pending.is_empty() || trimmed.ends_with('{') {
+            return self.continue_block(line);
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return Ok(ReplReply::Output(String::new()));
+        }
+        if let Some(command) = trimmed.strip_prefix(':') {
+            return self.command(command.trim());
+        }
+        if self.is_declaration(trimmed) {
+            self.declare(trimmed.to_string())
+        } else {
+            let value =
+                eval_source_expr(trimmed, &self.program()?.lets).map_err(|e| e.to_string())?;
+            Ok(ReplReply::Output(value.to_string()))
+        }
+    }
+
+    /// The source of everything declared so far
+    pub fn source(&self) -> String {
+        let mut source = String::new();
+        for declaration in &self.declarations {
+            source.push_str(declaration);
+            source.push('\n');
+        }
+        source.push_str(self.simulate.as_deref().unwrap_or(DEFAULT_SIMULATE));
+        source.push('\n');
+        source
+    }
+
+    /// Add a line to the open block, declaring the block once it closes
+    fn continue_block(&mut self, line: &str) -> Result<ReplReply, String> {
+        let (opens, closes) = count_braces(line.trim());
+        self.depth = (self.depth + opens).saturating_sub(closes);
+        self.pending.push(line.trim_end().to_string());
+        if self.depth > 0 {
+            return Ok(ReplReply::Incomplete);
+        }
+        let block = std::mem::take(&mut self.pending).join("\n");
+        self.declare(block)
+    }
+
+    /// A line starting with a keyword, or calling a declared function
+    fn is_declaration(&self, line: &str) -> bool {
+        let word: String = line
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        if DECLARATION_KEYWORDS.contains(&word.as_str()) {
+            return true;
+        }
+        match parse_expression(line) {
+            Ok(Expr::UserCall { name, .. }) => self
+                .program()
+                .is_ok_and(|program| program.functions.iter().any(|f| f.name == name)),
+            _ => false,
+        }
+    }
+
+    /// Accept a declaration if the program still parses and checks with it
+    fn declare(&mut self, declaration: String) -> Result<ReplReply, String> {
+        let mut candidate = Self {
+            declarations: self.declarations.clone(),
+            simulate: self.simulate.clone(),
+            ..Self::default()
+        };
+        if declaration.starts_with("simulate") {
+            candidate.simulate = Some(declaration);
+        } else {
+            candidate.declarations.push(declaration);
+        }
+        let program = candidate.program()?;
+        check_program(&program).map_err(|e| e.to_string())?;
+
+        self.declarations = candidate.declarations;
+        self.simulate = candidate.simulate;
+        self.run = None;
+        Ok(ReplReply::Output(String::new()))
+    }
+
+    fn program(&self) -> Result<Program, String> {
+        parse_program(&self.source()).map_err(|e| e.to_string())
+    }
+
+    fn command(&mut self, command: &str) -> Result<ReplReply, String> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+        let output = match name {
+            "run" => {
+                let steps = if argument.is_empty() {
+                    1
+                } else {
+                    argument
+                        .parse::<usize>()
+                        .map_err(|_| format!("expected a number of steps, got '{}'", argument))?
+                };
+                let (_, ctx) = self.simulation()?;
+                ctx.max_steps = ctx.current_step + steps;
+                while !try_step_simulation(ctx).map_err(|e| e.to_string())? {}
+                format!("t = {} after {} steps", ctx.time, ctx.current_step)
+            }
+            "show" => {
+                let (_, ctx) = self.simulation()?;
+                let mut lines = vec![format!("t = {} after {} steps", ctx.time, ctx.current_step)];
+                for particle in &ctx.world.particles {
+                    lines.push(format!(
+                        "{}: pos = ({}, {}) vel = ({}, {})",
+                        particle.name,
+                        particle.pos.x,
+                        particle.pos.y,
+                        particle.vel.x,
+                        particle.vel.y
+                    ));
+                }
+                lines.join("\n")
+            }
+            "detect" => {
+                let detector =
+                    parser::parse_detector(&format!("detect value = {}", argument), None)
+                        .map_err(|e| e.to_string())?;
+                let (program, ctx) = self.simulation()?;
+                let probe = Program {
+                    detectors: vec![detector],
+                    ..program.clone()
+                };
+                let mut warnings = Vec::new();
+                let results = evaluate_detectors(&probe, &ctx.world, ctx.time, &mut warnings)
+                    .map_err(|e| e.to_string())?;
+                results[0].value.to_string()
+            }
+            "source" => self.source().trim_end().to_string(),
+            "reset" => {
+                *self = Self::new();
+                String::new()
+            }
+            "help" => HELP.to_string(),
+            "quit" | "q" => return Ok(ReplReply::Quit),
+            _ => return Err(format!("unknown command ':{}' (try :help)", name)),
+        };
+        Ok(ReplReply::Output(output))
+    }
+
+    /// The simulation of the current declarations, building it if needed
+    fn simulation(&mut self) -> Result<&mut (Program, SimulationContext), String> {
+        if self.run.is_none() {
+            let mut program = self.program()?;
+            check_program(&program).map_err(|e| e.to_string())?;
+            expand_functions(&mut program).map_err(|e| e.to_string())?;
+            let ctx =
+                build_run_context(&program, &RunOptions::default()).map_err(|e| e.to_string())?;
+            self.run = Some((program, ctx));
+        }
+        Ok(self.run.as_mut().expect("built above"))
+    }
+}
@@ -1,127 +1,1018 @@
-use crate::analyzer::analyze_program;
+use crate::analyzer::analyze_program_with_limit;
+use crate::diff::{diff_programs, ProgramDiff};
 use crate::ast::{
-    ConditionExpr, DetectorKind, ForceKind, LoopKind, ObservableExpr, Program,
+    ConditionExpr, DetectorDecl, DetectorKind, Expr, ForceKind, Frame, LoopKind, ObservableExpr, Program,
+    Recenter,
 };
-use crate::engine::{Force, Particle, World};
-use crate::eval::{eval_expr, evaluate_lets, EvalContext};
-use crate::functions::execute_functions;
-use crate::integrator::step;
+use crate::engine::{Bounds, Collision, Contacts, Force, ForceInfo, Ground, Particle, World};
+use crate::eval::{
+    coerce_to_count, eval_expr, evaluate_lets, references_var, resolve_observables,
+    substitute_vars, EvalContext,
+};
+use crate::fold::fold_program_constants;
+use crate::functions::{eval_global_expr, execute_functions_with_source, ExpansionLimits};
+use crate::hash::StateHasher;
+use crate::state::{StateError, WorldState};
+use crate::symbols::{SymbolId, SymbolTable, UnknownParticle};
+use crate::integrator::{step_with, Integrator};
 use crate::loops::{
     apply_wells, evaluate_loop_conditions, update_and_apply_loops, ConditionRuntime,
     LoopBodyRuntime, LoopInstance, LoopKindRuntime, ObservableRuntime, WellInstance,
 };
-use crate::parser::parse_program;
-use crate::diagnostics::Diagnostics;
+use crate::parser::{parse_program, ParseError};
+use crate::phase;
+use crate::snapshot::{self, Encode, SnapshotError, SnapshotReader, SnapshotWriter};
+use crate::diagnostics::{Diagnostic, Diagnostics, Span};
 use glam::Vec2;
-use std::collections::HashMap;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+use thiserror::Error;
 
 /// Result of a detector evaluation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DetectorResult {
     pub name: String,
     pub value: f32,
+    /// Value before the first step, for detectors declared `initial`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial: Option<f32>,
 }
 
 /// Final result of running a program
-#[derive(Debug)]
+/// Serializes with `detectors` as an object from name to value, in declaration order;
+/// the value of a detector with an initial value is an object of both
+#[derive(Debug, Serialize)]
 pub struct SimulationResult {
+    #[serde(serialize_with = "serialize_detectors")]
     pub detectors: Vec<DetectorResult>,
+    /// `SimulationContext::state_hash` of the final state
+    pub state_hash: u64,
+    /// Non-fatal problems found while building, running, or evaluating detectors
+    pub warnings: Vec<Diagnostic>,
+    /// Simulated time at the end of the run
+    pub elapsed_time: f32,
+    /// Steps taken by the run
+    pub steps: usize,
+}
+
+fn serialize_detectors<S: Serializer>(
+    detectors: &[DetectorResult],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(detectors.len()))?;
+    for detector in detectors {
+        match detector.initial {
+            Some(initial) => map.serialize_entry(
+                &detector.name,
+                &InitialAndFinal {
+                    value: detector.value,
+                    initial,
+                },
+            )?,
+            None => map.serialize_entry(&detector.name, &detector.value)?,
+        }
+    }
+    map.end()
+}
+
+#[derive(Serialize)]
+struct InitialAndFinal {
+    value: f32,
+    initial: f32,
+}
+
+/// Errors raised while applying `RunOptions` overrides to a program
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum OverrideError {
+    #[error("unknown let binding '{name}' (available: {})", list_or_none(.available))]
+    UnknownLet { name: String, available: Vec<String> },
+}
+
+fn list_or_none(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Errors raised while preparing or stepping a simulation
+#[derive(Debug, Clone, Error)]
+pub enum RunError {
+    #[error("numerical divergence at step {step}: {quantity} of particle '{particle}' is not finite")]
+    NumericalDivergence {
+        step: usize,
+        particle: String,
+        quantity: String,
+    },
+    /// The program was rejected before simulating: by static analysis, let
+    /// evaluation, or function execution
+    #[error("{stage} errors:\n{}", .messages.join("\n"))]
+    Analysis {
+        stage: &'static str,
+        messages: Vec<String>,
+    },
+    #[error("run budget exceeded after {steps_completed} steps ({elapsed:?} elapsed)")]
+    BudgetExceeded {
+        steps_completed: usize,
+        elapsed: Duration,
+        /// Detector values evaluated at the point of interruption
+        detectors: Vec<DetectorResult>,
+    },
+}
+
+/// An error building the simulation from a declaration written in the source
+///
+/// `line` is known when the program was parsed by the same `Pipeline` that
+/// built it, since a span alone doesn't say which line it is on
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{message}{}", .line.map(|line| format!(" at line {}", line)).unwrap_or_default())]
+pub struct BuildError {
+    pub message: String,
+    /// Span of the declaration that failed
+    pub span: Span,
+    pub line: Option<usize>,
+}
+
+/// Attach `span` to a build error, if the declaration has one
+fn located(
+    span: Option<Span>,
+) -> impl FnOnce(Box<dyn std::error::Error>) -> Box<dyn std::error::Error> {
+    move |error| match span {
+        Some(span) => Box::new(BuildError {
+            message: error.to_string(),
+            span,
+            line: None,
+        }),
+        None => error,
+    }
+}
+
+/// A collision between two particles, recorded by the step that resolved it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    /// Step number (1-based, like `current_step` after the step)
+    pub step: usize,
+    pub a: usize,
+    pub b: usize,
+    /// Magnitude of the momentum exchanged
+    pub impulse: f32,
+}
+
+/// Region of the world a viewer should show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub center: Vec2,
+    /// Half the width and height of the region
+    pub half_extent: Vec2,
+}
+
+impl Viewport {
+    /// Region that frames `bounds` with a margin of 10% of the larger side
+    /// (at least 2 units)
+    pub fn framing(bounds: Bounds) -> Self {
+        let size = bounds.size();
+        let padding = (size.x.max(size.y) * 0.1).max(2.0);
+        let padded = bounds.padded(padding);
+        Self {
+            center: padded.center(),
+            half_extent: (padded.size() / 2.0).max(Vec2::splat(0.5)),
+        }
+    }
+}
+
+/// Collision events kept before the oldest are dropped
+pub const COLLISION_EVENT_CAPACITY: usize = 1024;
+
+/// Options controlling how a program is run
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Keep stepping after a position or velocity becomes NaN or infinite
+    pub allow_divergence: bool,
+    /// Stop with `RunError::BudgetExceeded` once this much wall-clock time has passed
+    /// (ignored on wasm32, which has no clock)
+    pub timeout: Option<Duration>,
+    /// Stop with `RunError::BudgetExceeded` once this many steps have been taken
+    pub max_steps: Option<usize>,
+    /// Start from this state instead of the particle declarations
+    pub initial_state: Option<WorldState>,
+    /// Integration method; golden outputs are recorded with the default
+    pub integrator: Integrator,
+    /// Replace the named let-bindings with these values; later lets see the new values
+    pub let_overrides: Vec<(String, f32)>,
+    /// Replace the `simulate` time step
+    pub dt: Option<f32>,
+    /// Replace the `simulate` step count
+    pub steps: Option<usize>,
+    /// How much world the program's functions may generate
+    pub expansion_limits: ExpansionLimits,
+    /// Report at most this many diagnostics with the same code and summarize
+    /// the rest (`Diagnostics::DEFAULT_CODE_LIMIT` unless set)
+    pub diagnostic_limit: Option<usize>,
 }
 
 /// Simulation context containing world, loops, and wells
 pub struct SimulationContext {
     pub world: World,
+    /// Loops and wells added to a built context go through `add_loop` and
+    /// `add_well`, or stepping won't run their phases
     pub loops: Vec<LoopInstance>,
     pub wells: Vec<WellInstance>,
     pub dt: f32,
     pub max_steps: usize,
     pub current_step: usize,
+    /// Simulated time, accumulated from dt every step
+    pub time: f32,
+    /// Method used by the integration phase of each step
+    pub integrator: Integrator,
+    /// Reference frame from `simulate ... frame = ...`
+    pub frame: Frame,
+    /// Check particle state for NaN/infinity after every step
+    pub check_divergence: bool,
+    /// Set once the simulation has diverged; no further steps are taken
+    pub divergence: Option<RunError>,
+    /// Non-fatal problems found while building or running the simulation
+    pub warnings: Vec<Diagnostic>,
+    /// The program's detectors with their let bindings substituted, for reading
+    /// their values mid-run
+    pub detectors: Vec<DetectorDecl>,
+    /// The particles each of `detectors` reads, resolved when the context is
+    /// built. A detector naming an unknown particle fails when it's evaluated
+    detector_particles: Vec<Resolved>,
+    /// External forces and impulses per particle, consumed by the next step
+    external_forces: Vec<Vec2>,
+    external_impulses: Vec<Vec2>,
+    /// Most recent collisions, oldest first, bounded by `COLLISION_EVENT_CAPACITY`
+    collision_events: VecDeque<CollisionEvent>,
+    /// Largest particle speed seen after any step so far
+    pub max_speed: f32,
+    /// Largest distance any particle has moved from where it started
+    pub max_displacement: f32,
+    /// Particle positions when the context was built, for `max_displacement`
+    initial_positions: Vec<Vec2>,
+    /// Forces whose parameters read `time`; static forces are never re-evaluated
+    force_drivers: Vec<ForceDriver>,
+    /// Whether a step runs the loop and well phases, decided when the context
+    /// is built and whenever loops or wells are added
+    has_loops: bool,
+    has_wells: bool,
+}
+
+// The context owns all of its state (no EvalContext borrows, no Rc), so it can
+// be built on one thread and stepped on another
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SimulationContext>();
+    assert_send_sync::<World>();
+    assert_send_sync::<LoopInstance>();
+    assert_send_sync::<WellInstance>();
+};
+
+impl SimulationContext {
+    /// Evaluate every detector on the current state, as `run` would if the
+    /// simulation ended now
+    pub fn detector_values(&self) -> Result<Vec<DetectorResult>, Box<dyn std::error::Error>> {
+        self.evaluate_detectors(&mut Vec::new())
+    }
+
+    /// Evaluate every detector on the current state, pushing a warning for
+    /// each NaN value
+    fn evaluate_detectors(
+        &self,
+        warnings: &mut Vec<Diagnostic>,
+    ) -> Result<Vec<DetectorResult>, Box<dyn std::error::Error>> {
+        // Detectors changed since the build are resolved again
+        let resolved;
+        let particles = if self.detector_particles.len() == self.detectors.len() {
+            &self.detector_particles
+        } else {
+            resolved = resolve_detectors(&self.detectors, self.world.symbols());
+            &resolved
+        };
+        evaluate_detector_decls(
+            &self.detectors,
+            particles,
+            &EvalContext::new(),
+            &self.world,
+            self.time,
+            warnings,
+        )
+    }
+
+    /// The names of the world's particles, resolving each to the index of its
+    /// particle
+    pub fn symbols(&self) -> &SymbolTable {
+        self.world.symbols()
+    }
+
+    /// Deterministic hash of the world plus loop/well state and step counter
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.world.hash_state(&mut hasher);
+        hasher.write_usize(self.loops.len());
+        for loop_inst in &self.loops {
+            loop_inst.hash_state(&mut hasher);
+        }
+        hasher.write_usize(self.wells.len());
+        for well in &self.wells {
+            well.hash_state(&mut hasher);
+        }
+        hasher.write_f32(self.dt);
+        hasher.write_usize(self.current_step);
+        hasher.write_f32(self.time);
+        hasher.finish()
+    }
+
+    /// Add a force to `particle` for the next step only (integrated as a += f/m)
+    /// Returns false if there is no particle at that index
+    pub fn apply_external_force(&mut self, particle: usize, force: Vec2) -> bool {
+        if particle >= self.world.particles.len() {
+            return false;
+        }
+        self.external_forces.resize(self.world.particles.len(), Vec2::ZERO);
+        self.external_forces[particle] += force;
+        true
+    }
+
+    /// Add a velocity change to `particle`, applied at the start of the next step
+    /// Returns false if there is no particle at that index
+    pub fn apply_external_impulse(&mut self, particle: usize, dv: Vec2) -> bool {
+        if particle >= self.world.particles.len() {
+            return false;
+        }
+        self.external_impulses.resize(self.world.particles.len(), Vec2::ZERO);
+        self.external_impulses[particle] += dv;
+        true
+    }
+
+    /// External force queued for `particle` that the next step will consume
+    pub fn pending_external_force(&self, particle: usize) -> Vec2 {
+        self.external_forces.get(particle).copied().unwrap_or(Vec2::ZERO)
+    }
+
+    /// External impulse queued for `particle` that the next step will consume
+    pub fn pending_external_impulse(&self, particle: usize) -> Vec2 {
+        self.external_impulses.get(particle).copied().unwrap_or(Vec2::ZERO)
+    }
+
+    /// Re-apply a center-of-mass frame before the first step, or before every
+    /// step with `recenter = always`
+    fn apply_frame(&mut self) {
+        if let Frame::CenterOfMass { recenter } = self.frame {
+            if recenter == Recenter::Always || self.current_step == 0 {
+                self.world.recenter();
+            }
+        }
+    }
+
+    /// Number of forces whose parameters are re-evaluated from `time` every step
+    pub fn time_driven_force_count(&self) -> usize {
+        self.force_drivers.len()
+    }
+
+    /// Re-evaluate the parameters of time-driven forces at the current time
+    /// A force whose parameters fail to evaluate (e.g. division by zero) keeps
+    /// its previous values, with a warning the first time it happens
+    fn drive_forces(&mut self) {
+        if self.force_drivers.is_empty() {
+            return;
+        }
+        let mut time_ctx = EvalContext::new();
+        time_ctx.values.insert(TIME_VAR, self.time);
+        for driver in &self.force_drivers {
+            match eval_force(&driver.kind, driver.a, driver.b, &time_ctx) {
+                Ok(force) => self.world.forces[driver.index] = force,
+                Err(error) => {
+                    let a = &self.world.particles[driver.a].name;
+                    let b = &self.world.particles[driver.b].name;
+                    let force = match driver.kind {
+                        ForceKind::Gravity { .. } => format!("gravity between '{}' and '{}'", a, b),
+                        ForceKind::Spring { .. } => format!("spring between '{}' and '{}'", a, b),
+                        ForceKind::Central { .. } => format!("central force on '{}'", a),
+                    };
+                    let prefix = format!("{} stopped updating", force);
+                    if self.warnings.iter().any(|w| w.message.starts_with(&prefix)) {
+                        continue;
+                    }
+                    self.warnings.push(
+                        Diagnostic::warning(format!("{} at time {}", prefix, self.time), None)
+                            .with_note(error)
+                            .with_help("it keeps the parameters it had before"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Apply and clear queued external forces and impulses
+    /// Anything queued for a pinned particle is discarded
+    fn apply_external(&mut self) {
+        let particles = self.world.particles.iter_mut();
+        for (particle, dv) in particles.zip(self.external_impulses.drain(..)) {
+            if !particle.pinned {
+                particle.vel += dv;
+            }
+        }
+        let particles = self.world.particles.iter_mut();
+        for (particle, force) in particles.zip(self.external_forces.drain(..)) {
+            if !particle.pinned {
+                particle.vel += force / particle.mass * self.dt;
+            }
+        }
+    }
+
+    /// Region that frames every particle with a margin of 10% of the larger
+    /// side (at least 2 units), or None for an empty world
+    pub fn suggested_viewport(&self) -> Option<Viewport> {
+        self.world.bounds().map(Viewport::framing)
+    }
+
+    /// Fold the current particle state into `max_speed` and `max_displacement`
+    fn update_running_stats(&mut self) {
+        for (i, particle) in self.world.particles.iter().enumerate() {
+            self.max_speed = self.max_speed.max(particle.vel.length());
+            if let Some(start) = self.initial_positions.get(i) {
+                self.max_displacement = self.max_displacement.max(particle.pos.distance(*start));
+            }
+        }
+    }
+
+    /// Take the collision events recorded since the last drain, oldest first
+    /// At most `COLLISION_EVENT_CAPACITY` are kept; older ones are dropped
+    pub fn drain_collision_events(&mut self) -> Vec<CollisionEvent> {
+        self.collision_events.drain(..).collect()
+    }
+
+    /// Record the collisions resolved by the step that is finishing
+    fn record_collisions(&mut self, collisions: Vec<Collision>) {
+        for collision in collisions {
+            if self.collision_events.len() == COLLISION_EVENT_CAPACITY {
+                self.collision_events.pop_front();
+            }
+            self.collision_events.push_back(CollisionEvent {
+                step: self.current_step + 1,
+                a: collision.a,
+                b: collision.b,
+                impulse: collision.impulse,
+            });
+        }
+    }
+
+    /// Metadata for every force in the world, including whether it's enabled
+    pub fn forces(&self) -> Vec<ForceInfo> {
+        self.world.force_info()
+    }
+
+    /// Update dt and step count in place, keeping the world and loop state
+    pub fn apply_simulate_change(&mut self, dt: f32, steps: usize) {
+        self.dt = dt;
+        self.max_steps = steps;
+    }
+
+    /// Add a loop to the running simulation, e.g. one built against another
+    /// world and rebased after `World::merge`
+    pub fn add_loop(&mut self, loop_inst: LoopInstance) {
+        self.loops.push(loop_inst);
+        self.has_loops = true;
+    }
+
+    /// Add a well to the running simulation, like `add_loop`
+    pub fn add_well(&mut self, well: WellInstance) {
+        self.wells.push(well);
+        self.has_wells = true;
+    }
+
+    /// Snapshot the current particle state
+    pub fn export_state(&self) -> WorldState {
+        WorldState::from_world(&self.world)
+    }
+
+    /// Replace particle state from a snapshot, matching particles by name
+    ///
+    /// The imported positions become the start `max_displacement` is measured
+    /// from, so a warm-started run reports how far it moved, not how far the
+    /// state it was given had already moved
+    pub fn import_state(&mut self, state: &WorldState) -> Result<(), StateError> {
+        state.apply_to(&mut self.world)?;
+        self.initial_positions = self.world.particles.iter().map(|p| p.pos).collect();
+        self.max_displacement = 0.0;
+        Ok(())
+    }
+
+    /// Save everything a step changes, so the context can later be rewound here
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            step: self.current_step,
+            time: self.time,
+            particles: self.export_state(),
+            contacts: self.world.contacts(),
+            loops: self.loops.clone(),
+            collision_events: self.collision_events.clone(),
+            max_speed: self.max_speed,
+            max_displacement: self.max_displacement,
+            divergence: self.divergence.clone(),
+        }
+    }
+
+    /// Rewind (or fast-forward) to a checkpoint taken from this context;
+    /// stepping on from there repeats the original run exactly
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<(), StateError> {
+        checkpoint.particles.apply_to(&mut self.world)?;
+        self.world.set_contacts(checkpoint.contacts.clone());
+        self.loops = checkpoint.loops.clone();
+        self.has_loops = !self.loops.is_empty();
+        self.collision_events = checkpoint.collision_events.clone();
+        self.max_speed = checkpoint.max_speed;
+        self.max_displacement = checkpoint.max_displacement;
+        self.divergence = checkpoint.divergence.clone();
+        self.current_step = checkpoint.step;
+        self.time = checkpoint.time;
+        self.drive_forces();
+        Ok(())
+    }
+
+    /// Write the whole context as a binary snapshot; `load` continues it
+    /// bit-identically. Warnings aren't saved
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), SnapshotError> {
+        let divergence = match &self.divergence {
+            None => None,
+            Some(RunError::NumericalDivergence {
+                step,
+                particle,
+                quantity,
+            }) => Some((*step, (particle.clone(), quantity.clone()))),
+            Some(_) => return Err(SnapshotError::Unsupported("the run stopped with an error")),
+        };
+        let mut w = SnapshotWriter::default();
+        self.world.write_snapshot(&mut w)?;
+        w.write(&self.loops);
+        w.write(&self.wells);
+        w.write(&self.dt);
+        w.write(&self.max_steps);
+        w.write(&self.current_step);
+        w.write(&self.time);
+        w.write(&self.integrator);
+        w.write(&self.frame);
+        w.write(&self.check_divergence);
+        w.write(&divergence);
+        w.write(&self.detectors);
+        w.write(&self.external_forces);
+        w.write(&self.external_impulses);
+        w.write(&Vec::from(self.collision_events.clone()));
+        w.write(&self.max_speed);
+        w.write(&self.max_displacement);
+        w.write(&self.initial_positions);
+        w.write(&self.force_drivers);
+        writer.write_all(&snapshot::frame(&w.bytes))?;
+        Ok(())
+    }
+
+    /// Read a context written by `save`
+    pub fn load<R: Read>(mut reader: R) -> Result<SimulationContext, SnapshotError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut r = SnapshotReader::new(snapshot::unframe(&bytes)?);
+        let world = World::read_snapshot(&mut r)?;
+        let loops: Vec<LoopInstance> = r.read()?;
+        let wells: Vec<WellInstance> = r.read()?;
+        let dt = r.read()?;
+        let max_steps = r.read()?;
+        let current_step = r.read()?;
+        let time = r.read()?;
+        let integrator = r.read()?;
+        let frame = r.read()?;
+        let check_divergence = r.read()?;
+        let divergence: Option<(usize, (String, String))> = r.read()?;
+        let detectors: Vec<DetectorDecl> = r.read()?;
+        let external_forces = r.read()?;
+        let external_impulses = r.read()?;
+        let collision_events: Vec<CollisionEvent> = r.read()?;
+        let max_speed = r.read()?;
+        let max_displacement = r.read()?;
+        let initial_positions = r.read()?;
+        let force_drivers: Vec<ForceDriver> = r.read()?;
+        r.finish()?;
+        let (has_loops, has_wells) = (!loops.is_empty(), !wells.is_empty());
+        let detector_particles = resolve_detectors(&detectors, world.symbols());
+        let forces = world.forces.len();
+        if force_drivers.iter().any(|driver| driver.index >= forces) {
+            return Err(SnapshotError::Corrupt(
+                "force driver refers to a missing force".into(),
+            ));
+        }
+        Ok(SimulationContext {
+            world,
+            loops,
+            wells,
+            dt,
+            max_steps,
+            current_step,
+            time,
+            integrator,
+            frame,
+            check_divergence,
+            divergence: divergence.map(|(step, (particle, quantity))| {
+                RunError::NumericalDivergence {
+                    step,
+                    particle,
+                    quantity,
+                }
+            }),
+            warnings: Vec::new(),
+            detectors,
+            detector_particles,
+            external_forces,
+            external_impulses,
+            collision_events: collision_events.into(),
+            max_speed,
+            max_displacement,
+            initial_positions,
+            force_drivers,
+            has_loops,
+            has_wells,
+        })
+    }
+}
+
+/// The state of a `SimulationContext` at one step, from `checkpoint`
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    step: usize,
+    time: f32,
+    particles: WorldState,
+    contacts: Contacts,
+    loops: Vec<LoopInstance>,
+    collision_events: VecDeque<CollisionEvent>,
+    max_speed: f32,
+    max_displacement: f32,
+    divergence: Option<RunError>,
+}
+
+impl Checkpoint {
+    /// The step the checkpoint was taken at
+    pub fn step(&self) -> usize {
+        self.step
+    }
 }
 
 /// Main entry point: parse and run a PhysLang program
 pub fn run_program(source: &str) -> Result<SimulationResult, Box<dyn std::error::Error>> {
-    let mut program = parse_program(source)?;
-    
-    // Perform static analysis
-    let diagnostics = analyze_program(&program);
-    if diagnostics.has_errors() {
-        let error_messages: Vec<String> = diagnostics
-            .errors()
-            .map(|d| d.message.clone())
-            .collect();
-        return Err(format!("Static analysis errors:\n{}", error_messages.join("\n")).into());
+    run_program_with_options(source, &RunOptions::default())
+}
+
+/// Parse and run a PhysLang program with the given options
+pub fn run_program_with_options(
+    source: &str,
+    options: &RunOptions,
+) -> Result<SimulationResult, Box<dyn std::error::Error>> {
+    run_program_with_observer(source, options, |_| Ok(()))
+}
+
+/// Apply the let-binding and `simulate` overrides in `options` to a parsed program
+pub fn apply_overrides(program: &mut Program, options: &RunOptions) -> Result<(), OverrideError> {
+    for (name, value) in &options.let_overrides {
+        match program.lets.iter_mut().find(|let_decl| &let_decl.name == name) {
+            Some(let_decl) => let_decl.expr = Expr::Literal(*value),
+            None => {
+                return Err(OverrideError::UnknownLet {
+                    name: name.clone(),
+                    available: program.lets.iter().map(|l| l.name.clone()).collect(),
+                })
+            }
+        }
     }
-    
+    if let Some(dt) = options.dt {
+        program.simulate.dt = Expr::Literal(dt);
+    }
+    if let Some(steps) = options.steps {
+        program.simulate.steps = Expr::Literal(steps as f32);
+    }
+    Ok(())
+}
+
+/// Parse and run a PhysLang program, calling `observer` with the initial
+/// state and again after every step; an observer error aborts the run
+///
+/// This runs the stages of a `Pipeline`, then `run_simulation`
+pub fn run_program_with_observer<F>(
+    source: &str,
+    options: &RunOptions,
+    observer: F,
+) -> Result<SimulationResult, Box<dyn std::error::Error>>
+where
+    F: FnMut(&SimulationContext) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let mut pipeline = Pipeline::parse(source)?;
+    pipeline.apply_overrides(options)?;
+    pipeline.analyze()?;
+    let warnings = pipeline.expand_functions()?;
+    pipeline.fold_constants();
+    let mut ctx = pipeline.build(options)?;
+    ctx.warnings.extend(warnings);
+    run_simulation(ctx, pipeline.program(), options, observer)
+}
+
+/// The stages between source and a ready simulation context, run one at a
+/// time so a caller can stop after any of them
+///
+/// Diagnostics accumulate across the stages, errors included, and stay
+/// readable after a stage fails. A failing stage returns
+/// `RunError::Analysis` naming it, so every entry point reports the same
+/// broken program with the same message.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    program: Program,
+    diagnostics: Diagnostics,
+    /// Source of the program, to put line numbers in build and function errors
+    source: Option<String>,
+    limits: ExpansionLimits,
+}
+
+impl Pipeline {
+    /// Parse stage
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            source: Some(source.to_string()),
+            ..Self::from_program(parse_program(source)?)
+        })
+    }
+
+    /// Start from an already parsed program
+    pub fn from_program(program: Program) -> Self {
+        Self {
+            program,
+            diagnostics: Diagnostics::new(),
+            source: None,
+            limits: ExpansionLimits::default(),
+        }
+    }
+
+    /// Limit what the function stage may generate (`ExpansionLimits::default()`
+    /// unless set)
+    pub fn limit_expansion(&mut self, limits: ExpansionLimits) {
+        self.limits = limits;
+    }
+
+    /// Apply the let-binding and `simulate` overrides, the expansion limits
+    /// and the diagnostic limit in `options`
+    pub fn apply_overrides(&mut self, options: &RunOptions) -> Result<(), OverrideError> {
+        self.limits = options.expansion_limits;
+        if let Some(limit) = options.diagnostic_limit {
+            let mut diagnostics = Diagnostics::with_code_limit(limit);
+            diagnostics.extend(std::mem::take(&mut self.diagnostics));
+            self.diagnostics = diagnostics;
+        }
+        apply_overrides(&mut self.program, options)
+    }
+
+    /// Static analysis stage: reject the parsed program if it has errors
+    pub fn analyze(&mut self) -> Result<(), RunError> {
+        analysis_stage("Static analysis", &self.program, &mut self.diagnostics).map(|_| ())
+    }
+
+    /// Function stage: evaluate the let bindings, run the top-level function
+    /// calls to generate their declarations, and analyze the result again
+    ///
+    /// Returns the warnings about the expanded program
+    pub fn expand_functions(&mut self) -> Result<Vec<Diagnostic>, RunError> {
+        expansion_stage(
+            &mut self.program,
+            &mut self.diagnostics,
+            self.source.as_deref(),
+            &self.limits,
+        )
+    }
+
+    /// Optional stage after the function stage: replace the constant
+    /// expressions of the expanded program with their values, so building
+    /// evaluates each one once (see `fold_program_constants`)
+    pub fn fold_constants(&mut self) {
+        fold_program_constants(&mut self.program);
+    }
+
+    /// Build stage: the initial simulation context of the expanded program,
+    /// configured by `options`
+    ///
+    /// A `BuildError` names its line if the pipeline parsed the source
+    pub fn build(
+        &self,
+        options: &RunOptions,
+    ) -> Result<SimulationContext, Box<dyn std::error::Error>> {
+        let result = build_run_context(&self.program, options);
+        let Some(source) = &self.source else {
+            return result;
+        };
+        result.map_err(|error| match error.downcast::<BuildError>() {
+            Ok(mut error) => {
+                error.line = Some(error.span.to_location(source).line);
+                error
+            }
+            Err(error) => error,
+        })
+    }
+
+    /// The program as the stages so far left it
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Everything the stages so far reported
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// The program and the diagnostics, e.g. to keep after building
+    pub fn into_parts(self) -> (Program, Diagnostics) {
+        (self.program, self.diagnostics)
+    }
+}
+
+/// Messages of the errors among `diagnostics`, failing `stage` if there are any
+fn fail_on_errors<'a>(
+    stage: &'static str,
+    diagnostics: impl IntoIterator<Item = &'a Diagnostic>,
+) -> Result<(), RunError> {
+    let messages: Vec<String> = diagnostics
+        .into_iter()
+        .filter(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Error))
+        .map(|d| d.message.clone())
+        .collect();
+    if messages.is_empty() {
+        Ok(())
+    } else {
+        Err(RunError::Analysis { stage, messages })
+    }
+}
+
+/// Record `found` in `diagnostics`, failing `stage` if it has errors
+///
+/// Both keep only as many diagnostics with the same code as `diagnostics` allows
+fn record_stage(
+    stage: &'static str,
+    found: Vec<Diagnostic>,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), RunError> {
+    let mut limited = Diagnostics::with_code_limit(diagnostics.code_limit());
+    for diagnostic in found {
+        limited.push(diagnostic);
+    }
+    let result = fail_on_errors(stage, limited.iter());
+    diagnostics.extend(limited);
+    result
+}
+
+/// Analyze `program` as `stage`, returning the warnings it raised
+fn analysis_stage(
+    stage: &'static str,
+    program: &Program,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Diagnostic>, RunError> {
+    let found: Vec<Diagnostic> = analyze_program_with_limit(program, diagnostics.code_limit())
+        .into_iter()
+        .collect();
+    let warnings = found
+        .iter()
+        .filter(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Warning))
+        .cloned()
+        .collect();
+    record_stage(stage, found, diagnostics)?;
+    Ok(warnings)
+}
+
+fn expansion_stage(
+    program: &mut Program,
+    diagnostics: &mut Diagnostics,
+    source: Option<&str>,
+    limits: &ExpansionLimits,
+) -> Result<Vec<Diagnostic>, RunError> {
     // Evaluate let bindings (borrow ends here)
     let lets = program.lets.clone();
     let (eval_ctx, eval_diagnostics) = evaluate_lets(&lets);
-    if eval_diagnostics.iter().any(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Error)) {
-        let error_messages: Vec<String> = eval_diagnostics
-            .iter()
-            .filter(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Error))
-            .map(|d| d.message.clone())
-            .collect();
-        return Err(format!("Expression evaluation errors:\n{}", error_messages.join("\n")).into());
-    }
-    
+    record_stage("Expression evaluation", eval_diagnostics, diagnostics)?;
+
     // Execute functions to generate world-building statements
-    let func_diagnostics = execute_functions(&mut program, &eval_ctx);
-    if func_diagnostics.iter().any(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Error)) {
-        let error_messages: Vec<String> = func_diagnostics
-            .iter()
-            .filter(|d| matches!(d.severity, crate::diagnostics::DiagnosticSeverity::Error))
-            .map(|d| d.message.clone())
-            .collect();
-        return Err(format!("Function execution errors:\n{}", error_messages.join("\n")).into());
-    }
-    
+    let func_diagnostics = execute_functions_with_source(program, &eval_ctx, source, limits);
+    record_stage("Function execution", func_diagnostics, diagnostics)?;
+
     // Re-analyze program after function execution to validate generated world
-    let post_func_diagnostics = analyze_program(&program);
-    if post_func_diagnostics.has_errors() {
-        let error_messages: Vec<String> = post_func_diagnostics
-            .errors()
-            .map(|d| d.message.clone())
-            .collect();
-        return Err(format!("Post-function analysis errors:\n{}", error_messages.join("\n")).into());
-    }
-    
-    let mut ctx = build_simulation_context(&program, &eval_ctx)?;
+    analysis_stage("Post-function analysis", program, diagnostics)
+}
 
-    // Evaluate steps expression
-    let steps_value = eval_expr(&program.simulate.steps, &eval_ctx)
-        .map_err(|e| format!("Error evaluating steps: {}", e))?;
-    let steps_usize = steps_value as usize;
-    if steps_value < 1.0 || steps_value != steps_usize as f32 {
-        return Err(format!(
-            "steps must be an integer >= 1, got {}",
-            steps_value
-        )
-        .into());
+/// Static analysis stage: reject a parsed program with errors
+pub fn check_program(program: &Program) -> Result<(), RunError> {
+    analysis_stage("Static analysis", program, &mut Diagnostics::new()).map(|_| ())
+}
+
+/// Function stage of `Pipeline` for a program held elsewhere
+///
+/// Returns the warnings about the expanded program
+pub fn expand_functions(program: &mut Program) -> Result<Vec<Diagnostic>, RunError> {
+    expansion_stage(program, &mut Diagnostics::new(), None, &ExpansionLimits::default())
+}
+
+/// Build stage: the initial simulation context of an expanded program,
+/// configured by `options`
+pub fn build_run_context(
+    program: &Program,
+    options: &RunOptions,
+) -> Result<SimulationContext, Box<dyn std::error::Error>> {
+    // The let bindings evaluated without errors in `expand_functions`
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let mut ctx = build_simulation_context(program, &eval_ctx)?;
+    ctx.check_divergence = !options.allow_divergence;
+    ctx.integrator = options.integrator;
+    if let Some(state) = &options.initial_state {
+        ctx.import_state(state)?;
     }
-    
-    // Evaluate dt expression
-    let dt_value = eval_expr(&program.simulate.dt, &eval_ctx)
-        .map_err(|e| format!("Error evaluating dt: {}", e))?;
-    
-    // Run the simulation
-    for _ in 0..steps_usize {
-        // 1. Update loops (advance oscillators, fire iterations)
-        update_and_apply_loops(&mut ctx.loops, &mut ctx.world.particles, dt_value);
+    Ok(ctx)
+}
 
-        // 2. Apply wells (convert wells into forces/accelerations)
-        apply_wells(&ctx.wells, &mut ctx.world.particles, dt_value);
+/// Simulation stage: step `ctx` to the end within the budget of `options` and
+/// evaluate the detectors of `program`, calling `observer` with the initial
+/// state and again after every step
+pub fn run_simulation<F>(
+    mut ctx: SimulationContext,
+    program: &Program,
+    options: &RunOptions,
+    mut observer: F,
+) -> Result<SimulationResult, Box<dyn std::error::Error>>
+where
+    F: FnMut(&SimulationContext) -> Result<(), Box<dyn std::error::Error>>,
+{
+    let _phase = phase::enter("simulate");
+    let (lets, _) = evaluate_lets(&program.lets);
+    let particles = resolve_detectors(&program.detectors, ctx.world.symbols());
+    let initial = initial_detector_values(&program.detectors, &particles, &lets, &ctx)?;
+    observer(&ctx)?;
 
-        // 3. Integrate physics
-        step(&mut ctx.world, dt_value);
+    // Run the simulation, checking the budget after every step
+    let started = clock();
+    loop {
+        let step_before = ctx.current_step;
+        let finished = try_step_simulation(&mut ctx)?;
+        if ctx.current_step != step_before {
+            observer(&ctx)?;
+        }
+        if finished {
+            break;
+        }
 
-        // 4. Evaluate while-loop conditions to deactivate finished loops
-        evaluate_loop_conditions(&mut ctx.loops, &ctx.world.particles);
+        let elapsed = started.map_or(Duration::ZERO, |started| started.elapsed());
+        let over_steps = options.max_steps.is_some_and(|max| ctx.current_step >= max);
+        let over_time = options.timeout.is_some_and(|limit| elapsed >= limit);
+        if over_steps || over_time {
+            let mut detectors = evaluate_detector_decls(
+                &program.detectors,
+                &particles,
+                &lets,
+                &ctx.world,
+                ctx.time,
+                &mut ctx.warnings,
+            )?;
+            attach_initial_values(&mut detectors, &initial);
+            return Err(RunError::BudgetExceeded {
+                steps_completed: ctx.current_step,
+                elapsed,
+                detectors,
+            }
+            .into());
+        }
     }
 
     // Evaluate detectors
-    let detectors = evaluate_detectors(&program, &ctx.world)?;
+    let mut detectors = evaluate_detector_decls(
+        &program.detectors,
+        &particles,
+        &lets,
+        &ctx.world,
+        ctx.time,
+        &mut ctx.warnings,
+    )?;
+    attach_initial_values(&mut detectors, &initial);
+
+    Ok(SimulationResult {
+        detectors,
+        state_hash: ctx.state_hash(),
+        elapsed_time: ctx.time,
+        steps: ctx.current_step,
+        warnings: ctx.warnings,
+    })
+}
 
-    Ok(SimulationResult { detectors })
+/// The current time, for run budgets
+#[cfg(not(target_arch = "wasm32"))]
+fn clock() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+/// wasm32-unknown-unknown has no clock (`Instant::now` panics), so no
+/// time passes and only step budgets apply
+#[cfg(target_arch = "wasm32")]
+fn clock() -> Option<Instant> {
+    None
 }
 
 /// Build simulation context from a parsed Program
@@ -129,88 +1020,100 @@ pub fn build_simulation_context(
     program: &Program,
     eval_ctx: &EvalContext<'_>,
 ) -> Result<SimulationContext, Box<dyn std::error::Error>> {
+    let _phase = phase::enter("build");
     let mut world = World::new();
-    let mut name_to_idx: HashMap<String, usize> = HashMap::new();
-
-    // Add particles
-    for particle_decl in &program.particles {
-        let idx = world.particles.len();
-        name_to_idx.insert(particle_decl.name.clone(), idx);
-        
-        // Evaluate position expressions
-        let x = eval_expr(&particle_decl.position.0, eval_ctx)
-            .map_err(|e| format!("Error evaluating particle {} x position: {}", particle_decl.name, e))?;
-        let y = eval_expr(&particle_decl.position.1, eval_ctx)
-            .map_err(|e| format!("Error evaluating particle {} y position: {}", particle_decl.name, e))?;
-        
-        // Evaluate mass expression
-        let mass = eval_expr(&particle_decl.mass, eval_ctx)
-            .map_err(|e| format!("Error evaluating particle {} mass: {}", particle_decl.name, e))?;
-        
-        world.particles.push(Particle {
-            name: particle_decl.name.clone(),
-            pos: Vec2::new(x, y),
-            vel: Vec2::ZERO,
-            mass,
-        });
+
+    // Add particles; their names resolve as static analysis checked them
+    for (idx, particle_decl) in program.particles.iter().enumerate() {
+        let particle = build_particle(particle_decl, eval_ctx)
+            .map_err(located(program.spans.particles.get(idx).copied()))?;
+        world.add_particle(particle);
     }
 
-    // Add forces
-    for force_decl in &program.forces {
-        let a_idx = name_to_idx
-            .get(&force_decl.a)
-            .ok_or_else(|| format!("Particle '{}' not found", force_decl.a))?;
-        let b_idx = name_to_idx
-            .get(&force_decl.b)
-            .ok_or_else(|| format!("Particle '{}' not found", force_decl.b))?;
-
-        let force = match &force_decl.kind {
-            ForceKind::Gravity { g } => {
-                let g_value = eval_expr(g, eval_ctx)
-                    .map_err(|e| format!("Error evaluating gravity G: {}", e))?;
-                Force::Gravity {
-                    a: *a_idx,
-                    b: *b_idx,
-                    g: g_value,
-                }
-            }
-            ForceKind::Spring { k, rest } => {
-                let k_value = eval_expr(k, eval_ctx)
-                    .map_err(|e| format!("Error evaluating spring k: {}", e))?;
-                let rest_value = eval_expr(rest, eval_ctx)
-                    .map_err(|e| format!("Error evaluating spring rest: {}", e))?;
-                Force::Spring {
-                    a: *a_idx,
-                    b: *b_idx,
-                    k: k_value,
-                    rest: rest_value,
-                }
-            }
-        };
+    // Add the ground
+    if let Some(ground_decl) = &program.ground {
+        let ground = build_ground(ground_decl, eval_ctx).map_err(located(program.spans.ground))?;
+        world.set_ground(Some(ground));
+    }
+
+    // Add forces; their parameters see `time` = 0 here, and the ones that read it
+    // are re-evaluated every step
+    let mut force_ctx = EvalContext {
+        values: eval_ctx.values.clone(),
+    };
+    force_ctx.values.entry(TIME_VAR).or_insert(0.0);
+    let mut force_drivers = Vec::new();
+    for (idx, force_decl) in program.forces.iter().enumerate() {
+        let (a_idx, b_idx, force) = build_force(force_decl, world.symbols(), &force_ctx)
+            .map_err(located(program.spans.forces.get(idx).copied()))?;
+        if !eval_ctx.values.contains_key(TIME_VAR)
+            && force_kind_exprs(&force_decl.kind)
+                .iter()
+                .any(|expr| references_var(expr, TIME_VAR))
+        {
+            force_drivers.push(ForceDriver {
+                index: world.forces.len(),
+                a: a_idx,
+                b: b_idx,
+                kind: substitute_force_vars(&force_decl.kind, eval_ctx),
+            });
+        }
 
         world.forces.push(force);
     }
 
     // Build loops
-    let loops = build_loops(&program.loops, &name_to_idx, eval_ctx)?;
+    let loops = build_loops(
+        &program.loops,
+        &program.spans.loops,
+        world.symbols(),
+        &world.particles,
+        eval_ctx,
+    )?;
 
     // Build wells
-    let wells = build_wells(&program.wells, &name_to_idx, eval_ctx)?;
+    let wells = build_wells(
+        &program.wells,
+        &program.spans.wells,
+        world.symbols(),
+        &world.particles,
+        eval_ctx,
+    )?;
 
-    // Evaluate dt and steps
-    let dt_value = eval_expr(&program.simulate.dt, eval_ctx)
-        .map_err(|e| format!("Error evaluating dt: {}", e))?;
-    let steps_value = eval_expr(&program.simulate.steps, eval_ctx)
-        .map_err(|e| format!("Error evaluating steps: {}", e))?;
-    let steps_usize = steps_value as usize;
-    if steps_value < 1.0 || steps_value != steps_usize as f32 {
-        return Err(format!(
-            "steps must be an integer >= 1, got {}",
-            steps_value
-        )
-        .into());
+    // v0.2 applies no force for distance wells; say so rather than ignoring them silently
+    let warnings = wells
+        .iter()
+        .filter(|well| matches!(well.observable, ObservableRuntime::Distance(_, _)))
+        .map(|well| {
+            Diagnostic::warning(
+                format!(
+                    "distance well on '{}' has no effect (distance wells are not applied yet)",
+                    world.particles[well.particle_index].name
+                ),
+                None,
+            )
+        })
+        .collect();
+
+    // Evaluate dt and steps, which may call functions
+    let (dt_value, steps_usize) =
+        simulate_params(program, eval_ctx).map_err(located(program.spans.simulate))?;
+
+    if let Frame::CenterOfMass { .. } = program.simulate.frame {
+        world.recenter();
     }
 
+    let initial_positions = world.particles.iter().map(|p| p.pos).collect();
+    let (has_loops, has_wells) = (!loops.is_empty(), !wells.is_empty());
+    let detectors: Vec<DetectorDecl> = program
+        .detectors
+        .iter()
+        .map(|detector| DetectorDecl {
+            expr: substitute_vars(&detector.expr, eval_ctx),
+            ..detector.clone()
+        })
+        .collect();
+    let detector_particles = resolve_detectors(&detectors, world.symbols());
     Ok(SimulationContext {
         world,
         loops,
@@ -218,100 +1121,332 @@ pub fn build_simulation_context(
         dt: dt_value,
         max_steps: steps_usize,
         current_step: 0,
+        time: 0.0,
+        integrator: Integrator::default(),
+        frame: program.simulate.frame,
+        check_divergence: true,
+        divergence: None,
+        warnings,
+        detectors,
+        detector_particles,
+        external_forces: Vec::new(),
+        external_impulses: Vec::new(),
+        collision_events: VecDeque::new(),
+        max_speed: 0.0,
+        max_displacement: 0.0,
+        initial_positions,
+        force_drivers,
+        has_loops,
+        has_wells,
+    })
+}
+
+/// The ground of a `ground` declaration
+fn build_ground(
+    ground_decl: &crate::ast::GroundDecl,
+    eval_ctx: &EvalContext<'_>,
+) -> Result<Ground, Box<dyn std::error::Error>> {
+    let y = eval_expr(&ground_decl.y, eval_ctx)
+        .map_err(|e| format!("Error evaluating ground y: {}", e))?;
+    let friction = eval_expr(&ground_decl.friction, eval_ctx)
+        .map_err(|e| format!("Error evaluating ground friction: {}", e))?;
+    let gravity = match &ground_decl.gravity {
+        Some(expr) => eval_expr(expr, eval_ctx)
+            .map_err(|e| format!("Error evaluating ground gravity: {}", e))?,
+        None => 0.0,
+    };
+    if friction < 0.0 {
+        return Err(format!("ground friction must be >= 0, got {}", friction).into());
+    }
+    Ok(Ground {
+        y,
+        friction,
+        gravity,
+    })
+}
+
+/// The time step and step count of the `simulate` declaration
+fn simulate_params(
+    program: &Program,
+    eval_ctx: &EvalContext<'_>,
+) -> Result<(f32, usize), Box<dyn std::error::Error>> {
+    let dt_value = eval_global_expr(&program.simulate.dt, program, eval_ctx, "simulate dt")
+        .map_err(|e| format!("Error evaluating dt: {}", e))?;
+    let steps_value = eval_global_expr(&program.simulate.steps, program, eval_ctx, "simulate steps")
+        .map_err(|e| format!("Error evaluating steps: {}", e))?;
+    let steps_usize = coerce_to_count(steps_value, "steps")?;
+    if steps_usize == 0 {
+        return Err("steps must be an integer >= 1, got 0".into());
+    }
+    Ok((dt_value, steps_usize))
+}
+
+/// The particle `particle_decl` declares
+fn build_particle(
+    particle_decl: &crate::ast::ParticleDecl,
+    eval_ctx: &EvalContext<'_>,
+) -> Result<Particle, Box<dyn std::error::Error>> {
+    // Evaluate position expressions
+    let x = eval_expr(&particle_decl.position.0, eval_ctx)
+        .map_err(|e| format!("Error evaluating particle {} x position: {}", particle_decl.name, e))?;
+    let y = eval_expr(&particle_decl.position.1, eval_ctx)
+        .map_err(|e| format!("Error evaluating particle {} y position: {}", particle_decl.name, e))?;
+    
+    // Evaluate mass expression
+    let mass = eval_expr(&particle_decl.mass, eval_ctx)
+        .map_err(|e| format!("Error evaluating particle {} mass: {}", particle_decl.name, e))?;
+
+    // Evaluate optional damping coefficient
+    let damping = match &particle_decl.damping {
+        Some(expr) => eval_expr(expr, eval_ctx).map_err(|e| {
+            format!("Error evaluating particle {} damping: {}", particle_decl.name, e)
+        })?,
+        None => 0.0,
+    };
+    if damping < 0.0 {
+        return Err(format!(
+            "particle {} damping must be >= 0, got {}",
+            particle_decl.name, damping
+        )
+        .into());
+    }
+
+    let mut tags = BTreeMap::new();
+    for (tag, expr) in &particle_decl.tags {
+        let value = eval_expr(expr, eval_ctx).map_err(|e| {
+            format!("Error evaluating particle {} tag {}: {}", particle_decl.name, tag, e)
+        })?;
+        tags.insert(tag.clone(), value);
+    }
+
+    Ok(Particle {
+        name: particle_decl.name.clone(),
+        pos: Vec2::new(x, y),
+        vel: Vec2::ZERO,
+        mass,
+        pinned: false,
+        damping,
+        tags,
     })
 }
 
-/// Build runtime loops from AST loops
+/// The particle indices `force_decl` acts between, and its force at time 0
+fn build_force(
+    force_decl: &crate::ast::ForceDecl,
+    symbols: &SymbolTable,
+    force_ctx: &EvalContext<'_>,
+) -> Result<(usize, usize, Force), Box<dyn std::error::Error>> {
+    let a_idx = symbols.particle(&force_decl.a, "force")?.index();
+    let b_idx = symbols.particle(&force_decl.b, "force")?.index();
+    let force = eval_force(&force_decl.kind, a_idx, b_idx, force_ctx)?;
+    Ok((a_idx, b_idx, force))
+}
+
+/// Name under which force parameters can read the simulation time
+const TIME_VAR: &str = "time";
+
+/// A force whose parameters read `time`, re-evaluated at the start of every step
+#[derive(Debug, Clone)]
+struct ForceDriver {
+    /// Index into `world.forces`
+    index: usize,
+    a: usize,
+    b: usize,
+    /// Parameter expressions with let-bindings already substituted
+    kind: ForceKind,
+}
+
+impl Encode for ForceDriver {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.index);
+        w.write(&self.a);
+        w.write(&self.b);
+        w.write(&self.kind);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(ForceDriver {
+            index: r.read()?,
+            a: r.read()?,
+            b: r.read()?,
+            kind: r.read()?,
+        })
+    }
+}
+
+/// Evaluate a force declaration's parameters into an engine force between `a` and `b`
+fn eval_force(
+    kind: &ForceKind,
+    a: usize,
+    b: usize,
+    eval_ctx: &EvalContext<'_>,
+) -> Result<Force, String> {
+    let force = match kind {
+        ForceKind::Gravity { g } => {
+            let g_value = eval_expr(g, eval_ctx)
+                .map_err(|e| format!("Error evaluating gravity G: {}", e))?;
+            Force::Gravity {
+                a,
+                b,
+                g: g_value,
+            }
+        }
+        ForceKind::Spring { k, rest } => {
+            let k_value = eval_expr(k, eval_ctx)
+                .map_err(|e| format!("Error evaluating spring k: {}", e))?;
+            let rest_value = eval_expr(rest, eval_ctx)
+                .map_err(|e| format!("Error evaluating spring rest: {}", e))?;
+            Force::Spring {
+                a,
+                b,
+                k: k_value,
+                rest: rest_value,
+            }
+        }
+        ForceKind::Central { point, gm } => {
+            let x = eval_expr(&point.0, eval_ctx)
+                .map_err(|e| format!("Error evaluating central force x: {}", e))?;
+            let y = eval_expr(&point.1, eval_ctx)
+                .map_err(|e| format!("Error evaluating central force y: {}", e))?;
+            let gm_value = eval_expr(gm, eval_ctx)
+                .map_err(|e| format!("Error evaluating central force GM: {}", e))?;
+            Force::Central {
+                target: a,
+                point: Vec2::new(x, y),
+                gm: gm_value,
+            }
+        }
+    };
+    Ok(force)
+}
+
+fn force_kind_exprs(kind: &ForceKind) -> Vec<&Expr> {
+    match kind {
+        ForceKind::Gravity { g } => vec![g],
+        ForceKind::Spring { k, rest } => vec![k, rest],
+        ForceKind::Central { point, gm } => vec![&point.0, &point.1, gm],
+    }
+}
+
+fn substitute_force_vars(kind: &ForceKind, eval_ctx: &EvalContext<'_>) -> ForceKind {
+    match kind {
+        ForceKind::Gravity { g } => ForceKind::Gravity {
+            g: substitute_vars(g, eval_ctx),
+        },
+        ForceKind::Spring { k, rest } => ForceKind::Spring {
+            k: substitute_vars(k, eval_ctx),
+            rest: substitute_vars(rest, eval_ctx),
+        },
+        ForceKind::Central { point, gm } => ForceKind::Central {
+            point: (
+                substitute_vars(&point.0, eval_ctx),
+                substitute_vars(&point.1, eval_ctx),
+            ),
+            gm: substitute_vars(gm, eval_ctx),
+        },
+    }
+}
+
+/// Build runtime loops from AST loops; `spans` locates their errors
 fn build_loops(
     loop_decls: &[crate::ast::LoopDecl],
-    name_to_idx: &HashMap<String, usize>,
+    spans: &[Span],
+    symbols: &SymbolTable,
+    particles: &[Particle],
     eval_ctx: &EvalContext<'_>,
 ) -> Result<Vec<LoopInstance>, Box<dyn std::error::Error>> {
     let mut loops = Vec::new();
 
-    for loop_decl in loop_decls {
-        let kind = match &loop_decl.kind {
-            LoopKind::ForCycles {
-                cycles,
-                frequency,
-                damping,
-                target,
-            } => {
-                let target_idx = name_to_idx
-                    .get(target)
-                    .ok_or_else(|| format!("Particle '{}' not found for loop", target))?;
-                
-                // Evaluate expressions
-                let cycles_value = eval_expr(cycles, eval_ctx)
-                    .map_err(|e| format!("Error evaluating cycles: {}", e))?;
-                let cycles_u32 = cycles_value as u32;
-                if cycles_value < 0.0 || cycles_value != cycles_u32 as f32 {
-                    return Err(format!(
-                        "cycles must be an integer >= 0, got {}",
-                        cycles_value
-                    )
-                    .into());
-                }
-                
-                let frequency_value = eval_expr(frequency, eval_ctx)
-                    .map_err(|e| format!("Error evaluating frequency: {}", e))?;
-                let damping_value = eval_expr(damping, eval_ctx)
-                    .map_err(|e| format!("Error evaluating damping: {}", e))?;
-                
-                LoopKindRuntime::ForCycles {
-                    target_index: *target_idx,
-                    cycles_remaining: cycles_u32,
-                    frequency: frequency_value,
-                    damping: damping_value,
-                    phase: 0.0,
-                }
+    for (idx, loop_decl) in loop_decls.iter().enumerate() {
+        let instance = build_loop(loop_decl, symbols, particles, eval_ctx)
+            .map_err(located(spans.get(idx).copied()))?;
+        loops.push(instance);
+    }
+
+    Ok(loops)
+}
+
+/// Build the runtime loop of one AST loop
+fn build_loop(
+    loop_decl: &crate::ast::LoopDecl,
+    symbols: &SymbolTable,
+    particles: &[Particle],
+    eval_ctx: &EvalContext<'_>,
+) -> Result<LoopInstance, Box<dyn std::error::Error>> {
+    let kind = match &loop_decl.kind {
+        LoopKind::ForCycles {
+            cycles,
+            frequency,
+            damping,
+            target,
+        } => {
+            let target_idx = symbols.particle(target, "loop target")?.index();
+            
+            // Evaluate expressions
+            let cycles_value = eval_expr(cycles, eval_ctx)
+                .map_err(|e| format!("Error evaluating cycles: {}", e))?;
+            let cycles_u32 = u32::try_from(coerce_to_count(cycles_value, "cycles")?)
+                .map_err(|_| format!("cycles is too large, got {}", cycles_value))?;
+            
+            let frequency_value = eval_expr(frequency, eval_ctx)
+                .map_err(|e| format!("Error evaluating frequency: {}", e))?;
+            let damping_value = eval_expr(damping, eval_ctx)
+                .map_err(|e| format!("Error evaluating damping: {}", e))?;
+            
+            LoopKindRuntime::ForCycles {
+                target_index: target_idx,
+                cycles_remaining: cycles_u32,
+                frequency: frequency_value,
+                damping: damping_value,
+                phase: 0.0,
             }
-            LoopKind::WhileCondition {
-                condition,
-                frequency,
-                damping,
-                target,
-            } => {
-                let target_idx = name_to_idx
-                    .get(target)
-                    .ok_or_else(|| format!("Particle '{}' not found for loop", target))?;
-                
-                // Evaluate expressions
-                let frequency_value = eval_expr(frequency, eval_ctx)
-                    .map_err(|e| format!("Error evaluating frequency: {}", e))?;
-                let damping_value = eval_expr(damping, eval_ctx)
-                    .map_err(|e| format!("Error evaluating damping: {}", e))?;
-                
-                LoopKindRuntime::WhileCondition {
-                    target_index: *target_idx,
-                    condition: convert_condition(condition, name_to_idx, eval_ctx)?,
-                    frequency: frequency_value,
-                    damping: damping_value,
-                    phase: 0.0,
-                }
+        }
+        LoopKind::WhileCondition {
+            condition,
+            frequency,
+            damping,
+            target,
+        } => {
+            let target_idx = symbols.particle(target, "loop target")?.index();
+            
+            // Evaluate expressions
+            let frequency_value = eval_expr(frequency, eval_ctx)
+                .map_err(|e| format!("Error evaluating frequency: {}", e))?;
+            let damping_value = eval_expr(damping, eval_ctx)
+                .map_err(|e| format!("Error evaluating damping: {}", e))?;
+            
+            LoopKindRuntime::WhileCondition {
+                target_index: target_idx,
+                condition: convert_condition(condition, symbols, particles, eval_ctx)?,
+                frequency: frequency_value,
+                damping: damping_value,
+                phase: 0.0,
             }
-        };
-
-        let body = loop_decl
-            .body
-            .iter()
-            .map(|stmt| convert_loop_body_stmt(stmt, name_to_idx, eval_ctx))
-            .collect::<Result<Vec<_>, _>>()?;
+        }
+    };
 
-        loops.push(LoopInstance {
-            kind,
-            body,
-            active: true,
-        });
-    }
+    let body = loop_decl
+        .body
+        .iter()
+        .map(|stmt| convert_loop_body_stmt(stmt, symbols, eval_ctx))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(loops)
+    let target = match &loop_decl.kind {
+        LoopKind::ForCycles { target, .. } | LoopKind::WhileCondition { target, .. } => target,
+    };
+    Ok(LoopInstance {
+        kind,
+        body,
+        active: true,
+        target_name: target.clone(),
+        label: loop_decl.name.clone(),
+    })
 }
 
 /// Convert AST condition to runtime condition
 fn convert_condition(
     condition: &ConditionExpr,
-    name_to_idx: &HashMap<String, usize>,
+    symbols: &SymbolTable,
+    particles: &[Particle],
     eval_ctx: &EvalContext<'_>,
 ) -> Result<ConditionRuntime, Box<dyn std::error::Error>> {
     match condition {
@@ -319,7 +1454,7 @@ fn convert_condition(
             let threshold_value = eval_expr(threshold, eval_ctx)
                 .map_err(|e| format!("Error evaluating condition threshold: {}", e))?;
             Ok(ConditionRuntime::LessThan(
-                convert_observable(obs, name_to_idx)?,
+                convert_observable(obs, symbols, particles)?,
                 threshold_value,
             ))
         }
@@ -327,7 +1462,7 @@ fn convert_condition(
             let threshold_value = eval_expr(threshold, eval_ctx)
                 .map_err(|e| format!("Error evaluating condition threshold: {}", e))?;
             Ok(ConditionRuntime::GreaterThan(
-                convert_observable(obs, name_to_idx)?,
+                convert_observable(obs, symbols, particles)?,
                 threshold_value,
             ))
         }
@@ -337,29 +1472,33 @@ fn convert_condition(
 /// Convert AST observable to runtime observable
 fn convert_observable(
     obs: &ObservableExpr,
-    name_to_idx: &HashMap<String, usize>,
+    symbols: &SymbolTable,
+    particles: &[Particle],
 ) -> Result<ObservableRuntime, Box<dyn std::error::Error>> {
     match obs {
         ObservableExpr::PositionX(name) => {
-            let idx = name_to_idx
-                .get(name)
-                .ok_or_else(|| format!("Particle '{}' not found", name))?;
-            Ok(ObservableRuntime::PositionX(*idx))
+            let idx = symbols.particle(name, "observable")?.index();
+            Ok(ObservableRuntime::PositionX(idx))
         }
         ObservableExpr::PositionY(name) => {
-            let idx = name_to_idx
-                .get(name)
-                .ok_or_else(|| format!("Particle '{}' not found", name))?;
-            Ok(ObservableRuntime::PositionY(*idx))
+            let idx = symbols.particle(name, "observable")?.index();
+            Ok(ObservableRuntime::PositionY(idx))
         }
         ObservableExpr::Distance(a, b) => {
-            let a_idx = name_to_idx
-                .get(a)
-                .ok_or_else(|| format!("Particle '{}' not found", a))?;
-            let b_idx = name_to_idx
-                .get(b)
-                .ok_or_else(|| format!("Particle '{}' not found", b))?;
-            Ok(ObservableRuntime::Distance(*a_idx, *b_idx))
+            let a_idx = symbols.particle(a, "distance observable")?.index();
+            let b_idx = symbols.particle(b, "distance observable")?.index();
+            Ok(ObservableRuntime::Distance(a_idx, b_idx))
+        }
+        ObservableExpr::Mass(name) => {
+            let idx = symbols.particle(name, "mass observable")?.index();
+            Ok(ObservableRuntime::Mass(idx))
+        }
+        ObservableExpr::Tag(name, tag) => {
+            let idx = symbols.particle(name, "tag observable")?.index();
+            if !particles[idx].tags.contains_key(tag) {
+                return Err(format!("unknown tag '{}' on particle '{}'", tag, name).into());
+            }
+            Ok(ObservableRuntime::Tag(idx, tag.clone()))
         }
     }
 }
@@ -367,7 +1506,7 @@ fn convert_observable(
 /// Convert AST loop body statement to runtime
 fn convert_loop_body_stmt(
     stmt: &crate::ast::LoopBodyStmt,
-    name_to_idx: &HashMap<String, usize>,
+    symbols: &SymbolTable,
     eval_ctx: &EvalContext<'_>,
 ) -> Result<LoopBodyRuntime, Box<dyn std::error::Error>> {
     match stmt {
@@ -376,9 +1515,7 @@ fn convert_loop_body_stmt(
             magnitude,
             direction,
         } => {
-            let particle_idx = name_to_idx
-                .get(particle)
-                .ok_or_else(|| format!("Particle '{}' not found", particle))?;
+            let particle_idx = symbols.particle(particle, "loop body push")?.index();
             
             // Evaluate expressions
             let magnitude_value = eval_expr(magnitude, eval_ctx)
@@ -389,7 +1526,7 @@ fn convert_loop_body_stmt(
                 .map_err(|e| format!("Error evaluating push direction y: {}", e))?;
             
             Ok(LoopBodyRuntime::ForcePush {
-                particle_index: *particle_idx,
+                particle_index: particle_idx,
                 magnitude: magnitude_value,
                 direction: Vec2::new(x_value, y_value),
             })
@@ -397,81 +1534,205 @@ fn convert_loop_body_stmt(
     }
 }
 
-/// Build runtime wells from AST wells
+/// Build runtime wells from AST wells; `spans` locates their errors
 fn build_wells(
     well_decls: &[crate::ast::WellDecl],
-    name_to_idx: &HashMap<String, usize>,
+    spans: &[Span],
+    symbols: &SymbolTable,
+    particles: &[Particle],
     eval_ctx: &EvalContext<'_>,
 ) -> Result<Vec<WellInstance>, Box<dyn std::error::Error>> {
     let mut wells = Vec::new();
 
-    for well_decl in well_decls {
-        let particle_idx = name_to_idx
-            .get(&well_decl.particle)
-            .ok_or_else(|| format!("Particle '{}' not found for well", well_decl.particle))?;
+    for (idx, well_decl) in well_decls.iter().enumerate() {
+        let well = build_well(well_decl, symbols, particles, eval_ctx)
+            .map_err(located(spans.get(idx).copied()))?;
+        wells.push(well);
+    }
 
-        let observable = convert_observable(&well_decl.observable, name_to_idx)?;
+    Ok(wells)
+}
 
-        // Evaluate expressions
-        let threshold_value = eval_expr(&well_decl.threshold, eval_ctx)
-            .map_err(|e| format!("Error evaluating well threshold: {}", e))?;
-        let depth_value = eval_expr(&well_decl.depth, eval_ctx)
-            .map_err(|e| format!("Error evaluating well depth: {}", e))?;
+/// Build the runtime well of one AST well
+fn build_well(
+    well_decl: &crate::ast::WellDecl,
+    symbols: &SymbolTable,
+    particles: &[Particle],
+    eval_ctx: &EvalContext<'_>,
+) -> Result<WellInstance, Box<dyn std::error::Error>> {
+    let particle_idx = symbols.particle(&well_decl.particle, "well")?.index();
 
-        wells.push(WellInstance {
-            particle_index: *particle_idx,
-            observable,
-            threshold: threshold_value,
-            depth: depth_value,
-        });
-    }
+    let observable = convert_observable(&well_decl.observable, symbols, particles)?;
 
-    Ok(wells)
+    // Evaluate expressions
+    let threshold_value = eval_expr(&well_decl.threshold, eval_ctx)
+        .map_err(|e| format!("Error evaluating well threshold: {}", e))?;
+    let depth_value = eval_expr(&well_decl.depth, eval_ctx)
+        .map_err(|e| format!("Error evaluating well depth: {}", e))?;
+
+    Ok(WellInstance {
+        particle_index: particle_idx,
+        observable,
+        threshold: threshold_value,
+        depth: depth_value,
+        name: well_decl.name.clone(),
+    })
 }
 
-/// Evaluate all detectors on the final world state
+/// Evaluate all detectors on the final world state at simulated time `time`
+/// A warning is pushed for every detector whose value is NaN
 pub fn evaluate_detectors(
     program: &Program,
     world: &World,
+    time: f32,
+    warnings: &mut Vec<Diagnostic>,
 ) -> Result<Vec<DetectorResult>, Box<dyn std::error::Error>> {
-    let name_to_particle: HashMap<String, &Particle> = world
-        .particles
+    let (lets, _) = evaluate_lets(&program.lets);
+    let particles = resolve_detectors(&program.detectors, world.symbols());
+    evaluate_detector_decls(&program.detectors, &particles, &lets, world, time, warnings)
+}
+
+/// The particles a detector reads, in the order its observables name them
+type Resolved = Result<Vec<SymbolId>, UnknownParticle>;
+
+/// Resolve the particles every one of `detectors` reads through `symbols`
+fn resolve_detectors(detectors: &[DetectorDecl], symbols: &SymbolTable) -> Vec<Resolved> {
+    detectors
         .iter()
-        .map(|p| (p.name.clone(), p))
-        .collect();
+        .map(|detector| {
+            let mut particles = Vec::new();
+            resolve_observables(&detector.expr, &mut |kind| {
+                for name in kind.particles() {
+                    particles.push(symbols.particle(name, "detector")?);
+                }
+                Ok(0.0)
+            })?;
+            Ok(particles)
+        })
+        .collect()
+}
 
-    let mut results = Vec::new();
-
-    for detector in &program.detectors {
-        let value = match &detector.kind {
-            DetectorKind::Position(name) => {
-                let particle = name_to_particle
-                    .get(name)
-                    .ok_or_else(|| format!("Particle '{}' not found for detector", name))?;
-                // For position, we return the x coordinate
-                // In the future, we might want to support x, y separately
-                particle.pos.x
-            }
-            DetectorKind::Distance { a, b } => {
-                let particle_a = name_to_particle
-                    .get(a)
-                    .ok_or_else(|| format!("Particle '{}' not found for detector", a))?;
-                let particle_b = name_to_particle
-                    .get(b)
-                    .ok_or_else(|| format!("Particle '{}' not found for detector", b))?;
-                particle_a.pos.distance(particle_b.pos)
-            }
-        };
+/// Evaluate `detectors`, reading the particles `particles` resolved for
+/// them and their variables through `lets`
+fn evaluate_detector_decls(
+    detectors: &[DetectorDecl],
+    particles: &[Resolved],
+    lets: &EvalContext<'_>,
+    world: &World,
+    time: f32,
+    warnings: &mut Vec<Diagnostic>,
+) -> Result<Vec<DetectorResult>, Box<dyn std::error::Error>> {
+    let observables = Observables { world, time };
+    let mut results = Vec::with_capacity(detectors.len());
+
+    for (detector, particles) in detectors.iter().zip(particles) {
+        let mut particles = particles.clone()?.into_iter();
+        let expr = resolve_observables(&detector.expr, &mut |kind| {
+            observables.value(kind, &mut particles)
+        })?;
+        let value = eval_expr(&expr, lets)
+            .map_err(|e| format!("Error evaluating detector '{}': {}", detector.name, e))?;
+
+        if value.is_nan() {
+            warnings.push(Diagnostic::warning(
+                format!("detector '{}' evaluated to NaN", detector.name),
+                None,
+            ));
+        }
 
         results.push(DetectorResult {
             name: detector.name.clone(),
             value,
+            initial: None,
         });
     }
 
     Ok(results)
 }
 
+/// The observables of `world` at simulated time `time`
+struct Observables<'a> {
+    world: &'a World,
+    time: f32,
+}
+
+impl Observables<'_> {
+    /// The value of `kind`, taking the particles it reads from `particles`
+    fn value(
+        &self,
+        kind: &DetectorKind,
+        particles: &mut impl Iterator<Item = SymbolId>,
+    ) -> Result<f32, UnknownParticle> {
+        let world = self.world;
+        // Particles removed from the world since they were resolved are unknown
+        let mut index = |name: &str| {
+            particles
+                .next()
+                .map(SymbolId::index)
+                .filter(|&index| index < world.particles.len())
+                .ok_or_else(|| UnknownParticle {
+                    name: name.to_string(),
+                    place: "detector",
+                })
+        };
+        Ok(match kind {
+            DetectorKind::Position(name) => world.particles[index(name)?].pos.x,
+            DetectorKind::PositionY(name) => world.particles[index(name)?].pos.y,
+            DetectorKind::VelocityX(name) => world.particles[index(name)?].vel.x,
+            DetectorKind::VelocityY(name) => world.particles[index(name)?].vel.y,
+            DetectorKind::Distance { a, b } => {
+                let (a, b) = (index(a)?, index(b)?);
+                world.particles[a].pos.distance(world.particles[b].pos)
+            }
+            DetectorKind::KineticEnergy => world.kinetic_energy(),
+            DetectorKind::PotentialEnergy => world.potential_energy(),
+            DetectorKind::TotalEnergy => world.total_energy(),
+            DetectorKind::Momentum => world.total_momentum().length(),
+            DetectorKind::CenterOfMass => world.center_of_mass().x,
+            DetectorKind::CenterOfMassY => world.center_of_mass().y,
+            DetectorKind::Time => self.time,
+            DetectorKind::CollisionCount { a, b } => {
+                let (a, b) = (index(a)?, index(b)?);
+                world.collision_count(a, b) as f32
+            }
+        })
+    }
+}
+
+/// The values of the detectors declared `initial` on the state of `ctx`, by
+/// detector index; `None` for the others
+///
+/// NaN values are only warned about once, when the final values are evaluated
+fn initial_detector_values(
+    detectors: &[DetectorDecl],
+    particles: &[Resolved],
+    lets: &EvalContext<'_>,
+    ctx: &SimulationContext,
+) -> Result<Vec<Option<f32>>, Box<dyn std::error::Error>> {
+    if !detectors.iter().any(|detector| detector.initial) {
+        return Ok(Vec::new());
+    }
+    let results = evaluate_detector_decls(
+        detectors,
+        particles,
+        lets,
+        &ctx.world,
+        ctx.time,
+        &mut Vec::new(),
+    )?;
+    Ok(detectors
+        .iter()
+        .zip(results)
+        .map(|(detector, result)| detector.initial.then_some(result.value))
+        .collect())
+}
+
+fn attach_initial_values(results: &mut [DetectorResult], initial: &[Option<f32>]) {
+    for (result, initial) in results.iter_mut().zip(initial) {
+        result.initial = *initial;
+    }
+}
+
 // ============================================================================
 // VEL (Visual Evaluation Loop) API - v0.5+
 // ============================================================================
@@ -488,100 +1749,176 @@ pub struct ParticleState {
 pub fn build_simulation_context_from_source(
     source: &str,
 ) -> Result<(SimulationContext, Diagnostics), Box<dyn std::error::Error>> {
-    let mut program = parse_program(source)?;
-    
-    // Perform static analysis
-    let mut diagnostics = analyze_program(&program);
-    
-    // If there are errors, return them
-    if diagnostics.has_errors() {
-        return Err(format!(
-            "Static analysis errors:\n{}",
-            diagnostics
-                .errors()
-                .map(|d| d.message.clone())
-                .collect::<Vec<_>>()
-                .join("\n")
-        ).into());
-    }
-    
-    // Evaluate let bindings (clone to avoid borrow conflict)
-    let lets = program.lets.clone();
-    let (eval_ctx, eval_diagnostics) = evaluate_lets(&lets);
-    diagnostics.extend(eval_diagnostics.into());
-    
-    // If there are evaluation errors, return them
-    if diagnostics.has_errors() {
-        return Err(format!(
-            "Expression evaluation errors:\n{}",
-            diagnostics
-                .errors()
-                .map(|d| d.message.clone())
-                .collect::<Vec<_>>()
-                .join("\n")
-        ).into());
-    }
-    
-    // Execute functions to generate world-building statements
-    let func_diagnostics = execute_functions(&mut program, &eval_ctx);
-    diagnostics.extend(func_diagnostics.into());
-    
-    // If there are function execution errors, return them
-    if diagnostics.has_errors() {
-        return Err(format!(
-            "Function execution errors:\n{}",
-            diagnostics
-                .errors()
-                .map(|d| d.message.clone())
-                .collect::<Vec<_>>()
-                .join("\n")
-        ).into());
-    }
-    
-    // Re-analyze program after function execution
-    let post_func_diagnostics = analyze_program(&program);
-    diagnostics.extend(post_func_diagnostics.into());
-    
-    // If there are post-function analysis errors, return them
-    if diagnostics.has_errors() {
-        return Err(format!(
-            "Post-function analysis errors:\n{}",
-            diagnostics
-                .errors()
-                .map(|d| d.message.clone())
-                .collect::<Vec<_>>()
-                .join("\n")
-        ).into());
+    build_simulation_context_with_overrides(source, &RunOptions::default())
+}
+
+/// Build simulation context from source code with the let-binding and
+/// `simulate` overrides of `options` applied, returning diagnostics
+pub fn build_simulation_context_with_overrides(
+    source: &str,
+    options: &RunOptions,
+) -> Result<(SimulationContext, Diagnostics), Box<dyn std::error::Error>> {
+    let mut pipeline = Pipeline::parse(source)?;
+    pipeline.apply_overrides(options)?;
+    pipeline.analyze()?;
+    pipeline.expand_functions()?;
+    pipeline.fold_constants();
+    // Only the overrides apply; the caller configures the context itself
+    let ctx = pipeline.build(&RunOptions::default())?;
+    Ok((ctx, pipeline.into_parts().1))
+}
+
+/// Result of rebuilding a simulation context after a source edit
+pub struct Reload {
+    pub ctx: SimulationContext,
+    /// Newly parsed program, to diff against on the next edit
+    pub program: Program,
+    pub diagnostics: Diagnostics,
+    pub diff: ProgramDiff,
+}
+
+/// Rebuild a simulation context after a source edit, keeping as much of the
+/// previous run as the edit allows:
+/// - `Unchanged`/`SimulateOnly`: the previous context continues with the new dt/steps/frame
+/// - `LetsOnly`: a fresh context with the previous positions, velocities, step, and time
+///   (loop and well state restart from the new declarations); a let that changes
+///   which particles functions generate makes the edit `Structural`
+/// - `Structural`: a fresh context from step 0
+///
+/// The previous context's integrator is kept in every case.
+pub fn rebuild_simulation_context_from_source(
+    source: &str,
+    previous_program: &Program,
+    previous: SimulationContext,
+) -> Result<Reload, Box<dyn std::error::Error>> {
+    rebuild_simulation_context_with_overrides(
+        source,
+        &RunOptions::default(),
+        previous_program,
+        previous,
+    )
+}
+
+/// Like `rebuild_simulation_context_from_source`, with the let-binding and
+/// `simulate` overrides of `options` applied to the edited source
+pub fn rebuild_simulation_context_with_overrides(
+    source: &str,
+    options: &RunOptions,
+    previous_program: &Program,
+    mut previous: SimulationContext,
+) -> Result<Reload, Box<dyn std::error::Error>> {
+    let program = parse_program(source)?;
+    let mut diff = diff_programs(previous_program, &program);
+
+    // Always run the full pipeline so the new source is validated and dt/steps are evaluated
+    let (mut fresh, diagnostics) = build_simulation_context_with_overrides(source, options)?;
+    fresh.integrator = previous.integrator;
+
+    // Lets can drive function expansion, so the particles are only carried
+    // over when the expanded worlds have the same ones
+    let same_particles = fresh.world.particles.len() == previous.world.particles.len()
+        && fresh
+            .world
+            .particles
+            .iter()
+            .zip(&previous.world.particles)
+            .all(|(new, old)| new.name == old.name);
+    if diff == ProgramDiff::LetsOnly && !same_particles {
+        diff = ProgramDiff::Structural;
     }
-    
-    let ctx = build_simulation_context(&program, &eval_ctx)?;
-    Ok((ctx, diagnostics))
+
+    let ctx = match diff {
+        ProgramDiff::Unchanged | ProgramDiff::SimulateOnly => {
+            previous.apply_simulate_change(fresh.dt, fresh.max_steps);
+            previous.frame = fresh.frame;
+            previous
+        }
+        ProgramDiff::LetsOnly => {
+            for particle in &mut fresh.world.particles {
+                if let Some(id) = previous.world.symbols().resolve(&particle.name) {
+                    let old = &previous.world.particles[id.index()];
+                    particle.pos = old.pos;
+                    particle.vel = old.vel;
+                }
+            }
+            fresh.current_step = previous.current_step;
+            fresh.time = previous.time;
+            fresh
+        }
+        ProgramDiff::Structural => fresh,
+    };
+
+    Ok(Reload {
+        ctx,
+        program,
+        diagnostics,
+        diff,
+    })
 }
 
 /// Step the simulation forward by one step
 /// Returns true if the simulation is finished (current_step >= max_steps)
+/// or has stopped because of numerical divergence
 pub fn step_simulation(ctx: &mut SimulationContext) -> bool {
-    if ctx.current_step >= ctx.max_steps {
-        return true;
+    try_step_simulation(ctx).unwrap_or(true)
+}
+
+/// Step the simulation forward by one step, reporting numerical divergence
+/// Returns Ok(true) if the simulation is finished (current_step >= max_steps)
+pub fn try_step_simulation(ctx: &mut SimulationContext) -> Result<bool, RunError> {
+    if ctx.current_step >= ctx.max_steps || ctx.divergence.is_some() {
+        return Ok(true);
     }
 
+    // Hold the center-of-mass frame (picking up velocities set since the build)
+    ctx.apply_frame();
+
     // 1. Update loops (advance oscillators, fire iterations)
-    update_and_apply_loops(&mut ctx.loops, &mut ctx.world.particles, ctx.dt);
+    if ctx.has_loops {
+        update_and_apply_loops(&mut ctx.loops, &mut ctx.world.particles, ctx.dt);
+    }
 
     // 2. Apply wells (convert wells into forces/accelerations)
-    apply_wells(&ctx.wells, &mut ctx.world.particles, ctx.dt);
+    if ctx.has_wells {
+        apply_wells(&ctx.wells, &mut ctx.world.particles, ctx.dt);
+    }
+
+    // 3. Apply external forces/impulses queued by the host since the last step
+    ctx.apply_external();
 
-    // 3. Integrate physics
-    step(&mut ctx.world, ctx.dt);
+    // 4. Integrate physics, with time-driven force parameters taken at the step's start
+    ctx.drive_forces();
+    step_with(&mut ctx.world, ctx.dt, ctx.integrator);
 
-    // 4. Evaluate while-loop conditions to deactivate finished loops
-    evaluate_loop_conditions(&mut ctx.loops, &ctx.world.particles);
+    // 5. Resolve collisions (only when a collision radius is set)
+    let collisions = ctx.world.resolve_collisions();
+    ctx.record_collisions(collisions);
 
-    // 5. Increment step counter
+    // 6. Evaluate while-loop conditions to deactivate finished loops
+    if ctx.has_loops {
+        evaluate_loop_conditions(&mut ctx.loops, &ctx.world.particles);
+    }
+
+    // 7. Increment step counter and simulated time, update running statistics
     ctx.current_step += 1;
+    ctx.time += ctx.dt;
+    ctx.update_running_stats();
+
+    // 8. Stop on the first non-finite position or velocity
+    if ctx.check_divergence {
+        if let Some((idx, quantity)) = ctx.world.find_non_finite() {
+            let error = RunError::NumericalDivergence {
+                step: ctx.current_step,
+                particle: ctx.world.particles[idx].name.clone(),
+                quantity: quantity.to_string(),
+            };
+            ctx.divergence = Some(error.clone());
+            return Err(error);
+        }
+    }
 
     // Return true if finished
-    ctx.current_step >= ctx.max_steps
+    Ok(ctx.current_step >= ctx.max_steps)
 }
 
 /// Get particle states for visualization
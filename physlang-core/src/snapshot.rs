@@ -0,0 +1,845 @@
+//! Binary snapshots of a `SimulationContext`, for checkpointing long runs
+//!
+//! A snapshot is the magic bytes `PHYSSNAP`, a format version byte, the
+//! encoded context, and a 64-bit FNV-1a checksum of everything before it.
+//! Values are written in explicit little-endian encodings like the state
+//! hash, so a snapshot taken on one machine continues bit-identically on
+//! another.
+
+use crate::ast::{
+    BinaryOp, DetectorDecl, DetectorKind, Expr, ForceKind, Frame, FuncName, Recenter,
+};
+use crate::engine::{Force, Ground, Particle};
+use crate::hash::StateHasher;
+use crate::integrator::Integrator;
+use crate::loops::{
+    ConditionRuntime, LoopBodyRuntime, LoopInstance, LoopKindRuntime, ObservableRuntime,
+    WellInstance,
+};
+use crate::runtime::CollisionEvent;
+use glam::Vec2;
+use std::collections::BTreeMap;
+use std::io;
+use thiserror::Error;
+
+/// First bytes of every snapshot
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"PHYSSNAP";
+
+/// Version of the snapshot format written by `SimulationContext::save`
+pub const SNAPSHOT_VERSION: u8 = 4;
+
+/// Errors raised when saving or loading a snapshot
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a PhysLang snapshot")]
+    NotASnapshot,
+    #[error("snapshot format version {found} is not supported (expected {SNAPSHOT_VERSION})")]
+    UnsupportedVersion { found: u8 },
+    #[error("corrupt snapshot: {0}")]
+    Corrupt(String),
+    #[error("cannot snapshot this simulation: {0}")]
+    Unsupported(&'static str),
+}
+
+/// Frame a snapshot body: magic, version, body, checksum
+pub(crate) fn frame(body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(body.len() + 17);
+    bytes.extend_from_slice(&SNAPSHOT_MAGIC);
+    bytes.push(SNAPSHOT_VERSION);
+    bytes.extend_from_slice(body);
+    let checksum = checksum(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// The body of a framed snapshot, after checking its header and checksum
+pub(crate) fn unframe(bytes: &[u8]) -> Result<&[u8], SnapshotError> {
+    if bytes.len() < SNAPSHOT_MAGIC.len() || bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    match bytes.get(SNAPSHOT_MAGIC.len()) {
+        Some(&SNAPSHOT_VERSION) => {}
+        Some(&found) => return Err(SnapshotError::UnsupportedVersion { found }),
+        None => return Err(corrupt("missing version")),
+    }
+    let header = SNAPSHOT_MAGIC.len() + 1;
+    if bytes.len() < header + 8 {
+        return Err(corrupt("truncated"));
+    }
+    let (framed, stored) = bytes.split_at(bytes.len() - 8);
+    if checksum(framed).to_le_bytes() != stored {
+        return Err(corrupt("checksum mismatch"));
+    }
+    Ok(&framed[header..])
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = StateHasher::new();
+    hasher.write_bytes(bytes);
+    hasher.finish()
+}
+
+fn corrupt(reason: impl Into<String>) -> SnapshotError {
+    SnapshotError::Corrupt(reason.into())
+}
+
+/// Appends encoded values to a snapshot body
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotWriter {
+    pub bytes: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn write<T: Encode>(&mut self, value: &T) {
+        value.encode(self);
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// An enum variant or other small tag
+    fn tag(&mut self, tag: u8) {
+        self.put(&[tag]);
+    }
+}
+
+/// Reads encoded values back from a snapshot body
+#[derive(Debug)]
+pub(crate) struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn read<T: Encode>(&mut self) -> Result<T, SnapshotError> {
+        T::decode(self)
+    }
+
+    /// Fail unless every byte has been read
+    pub fn finish(&self) -> Result<(), SnapshotError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(corrupt(format!(
+                "{} unexpected trailing bytes",
+                self.bytes.len()
+            )))
+        }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        if self.bytes.len() < N {
+            return Err(corrupt("unexpected end of data"));
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn tag(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    /// A length prefix, checked against the bytes left so corrupt data
+    /// can't request a huge allocation
+    fn len(&mut self) -> Result<usize, SnapshotError> {
+        let len: usize = self.read()?;
+        if len > self.bytes.len() {
+            return Err(corrupt(format!("length {} exceeds the data left", len)));
+        }
+        Ok(len)
+    }
+}
+
+fn bad_tag(what: &str, tag: u8) -> SnapshotError {
+    corrupt(format!("unknown {} tag {}", what, tag))
+}
+
+/// A value with a snapshot encoding
+pub(crate) trait Encode: Sized {
+    fn encode(&self, w: &mut SnapshotWriter);
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError>;
+}
+
+impl Encode for u8 {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.tag(*self);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        r.tag()
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.tag(u8::from(*self));
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        match r.tag()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(bad_tag("bool", tag)),
+        }
+    }
+}
+
+impl Encode for u32 {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.put(&self.to_le_bytes());
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(u32::from_le_bytes(r.take()?))
+    }
+}
+
+impl Encode for i64 {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.put(&self.to_le_bytes());
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(i64::from_le_bytes(r.take()?))
+    }
+}
+
+/// Written as a u64 so 32- and 64-bit targets agree
+impl Encode for usize {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.put(&(*self as u64).to_le_bytes());
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        let value = u64::from_le_bytes(r.take()?);
+        usize::try_from(value).map_err(|_| corrupt(format!("{} is too large", value)))
+    }
+}
+
+/// The exact bit pattern, so restored floats are identical
+impl Encode for f32 {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.to_bits());
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(f32::from_bits(r.read()?))
+    }
+}
+
+impl Encode for Vec2 {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.x);
+        w.write(&self.y);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(Vec2::new(r.read()?, r.read()?))
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.len());
+        w.put(self.as_bytes());
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        let len = r.len()?;
+        let (head, rest) = r.bytes.split_at(len);
+        r.bytes = rest;
+        String::from_utf8(head.to_vec()).map_err(|_| corrupt("invalid UTF-8 in a string"))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.len());
+        for item in self {
+            w.write(item);
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        let len = r.len()?;
+        (0..len).map(|_| r.read()).collect()
+    }
+}
+
+impl<T: Encode> Encode for Box<T> {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&**self);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(Box::new(r.read()?))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            None => w.tag(0),
+            Some(value) => {
+                w.tag(1);
+                w.write(value);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        match r.tag()? {
+            0 => Ok(None),
+            1 => Ok(Some(r.read()?)),
+            tag => Err(bad_tag("option", tag)),
+        }
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.0);
+        w.write(&self.1);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok((r.read()?, r.read()?))
+    }
+}
+
+impl<K: Encode + Ord, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.len());
+        for (key, value) in self {
+            w.write(key);
+            w.write(value);
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        let len = r.len()?;
+        (0..len).map(|_| Ok((r.read()?, r.read()?))).collect()
+    }
+}
+
+impl Encode for Particle {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.name);
+        w.write(&self.pos);
+        w.write(&self.vel);
+        w.write(&self.mass);
+        w.write(&self.pinned);
+        w.write(&self.damping);
+        w.write(&self.tags);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(Particle {
+            name: r.read()?,
+            pos: r.read()?,
+            vel: r.read()?,
+            mass: r.read()?,
+            pinned: r.read()?,
+            damping: r.read()?,
+            tags: r.read()?,
+        })
+    }
+}
+
+impl Encode for Force {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            Force::Gravity { a, b, g } => {
+                w.tag(0);
+                w.write(a);
+                w.write(b);
+                w.write(g);
+            }
+            Force::Spring { a, b, k, rest } => {
+                w.tag(1);
+                w.write(a);
+                w.write(b);
+                w.write(k);
+                w.write(rest);
+            }
+            Force::Central { target, point, gm } => {
+                w.tag(2);
+                w.write(target);
+                w.write(point);
+                w.write(gm);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => Force::Gravity {
+                a: r.read()?,
+                b: r.read()?,
+                g: r.read()?,
+            },
+            1 => Force::Spring {
+                a: r.read()?,
+                b: r.read()?,
+                k: r.read()?,
+                rest: r.read()?,
+            },
+            2 => Force::Central {
+                target: r.read()?,
+                point: r.read()?,
+                gm: r.read()?,
+            },
+            tag => return Err(bad_tag("force", tag)),
+        })
+    }
+}
+
+impl Encode for Ground {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.y);
+        w.write(&self.friction);
+        w.write(&self.gravity);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(Ground {
+            y: r.read()?,
+            friction: r.read()?,
+            gravity: r.read()?,
+        })
+    }
+}
+
+impl Encode for ObservableRuntime {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            ObservableRuntime::PositionX(i) => {
+                w.tag(0);
+                w.write(i);
+            }
+            ObservableRuntime::PositionY(i) => {
+                w.tag(1);
+                w.write(i);
+            }
+            ObservableRuntime::Distance(a, b) => {
+                w.tag(2);
+                w.write(a);
+                w.write(b);
+            }
+            ObservableRuntime::Mass(i) => {
+                w.tag(3);
+                w.write(i);
+            }
+            ObservableRuntime::Tag(i, tag) => {
+                w.tag(4);
+                w.write(i);
+                w.write(tag);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => ObservableRuntime::PositionX(r.read()?),
+            1 => ObservableRuntime::PositionY(r.read()?),
+            2 => ObservableRuntime::Distance(r.read()?, r.read()?),
+            3 => ObservableRuntime::Mass(r.read()?),
+            4 => ObservableRuntime::Tag(r.read()?, r.read()?),
+            tag => return Err(bad_tag("observable", tag)),
+        })
+    }
+}
+
+impl Encode for ConditionRuntime {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        let (tag, observable, threshold) = match self {
+            ConditionRuntime::LessThan(o, t) => (0, o, t),
+            ConditionRuntime::GreaterThan(o, t) => (1, o, t),
+        };
+        w.tag(tag);
+        w.write(observable);
+        w.write(threshold);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => ConditionRuntime::LessThan(r.read()?, r.read()?),
+            1 => ConditionRuntime::GreaterThan(r.read()?, r.read()?),
+            tag => return Err(bad_tag("condition", tag)),
+        })
+    }
+}
+
+impl Encode for LoopKindRuntime {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            LoopKindRuntime::ForCycles {
+                target_index,
+                cycles_remaining,
+                frequency,
+                damping,
+                phase,
+            } => {
+                w.tag(0);
+                w.write(target_index);
+                w.write(cycles_remaining);
+                w.write(frequency);
+                w.write(damping);
+                w.write(phase);
+            }
+            LoopKindRuntime::WhileCondition {
+                target_index,
+                condition,
+                frequency,
+                damping,
+                phase,
+            } => {
+                w.tag(1);
+                w.write(target_index);
+                w.write(condition);
+                w.write(frequency);
+                w.write(damping);
+                w.write(phase);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => LoopKindRuntime::ForCycles {
+                target_index: r.read()?,
+                cycles_remaining: r.read()?,
+                frequency: r.read()?,
+                damping: r.read()?,
+                phase: r.read()?,
+            },
+            1 => LoopKindRuntime::WhileCondition {
+                target_index: r.read()?,
+                condition: r.read()?,
+                frequency: r.read()?,
+                damping: r.read()?,
+                phase: r.read()?,
+            },
+            tag => return Err(bad_tag("loop kind", tag)),
+        })
+    }
+}
+
+impl Encode for LoopBodyRuntime {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        let LoopBodyRuntime::ForcePush {
+            particle_index,
+            magnitude,
+            direction,
+        } = self;
+        w.tag(0);
+        w.write(particle_index);
+        w.write(magnitude);
+        w.write(direction);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        match r.tag()? {
+            0 => Ok(LoopBodyRuntime::ForcePush {
+                particle_index: r.read()?,
+                magnitude: r.read()?,
+                direction: r.read()?,
+            }),
+            tag => Err(bad_tag("loop body", tag)),
+        }
+    }
+}
+
+impl Encode for LoopInstance {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.kind);
+        w.write(&self.body);
+        w.write(&self.active);
+        w.write(&self.target_name);
+        w.write(&self.label);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(LoopInstance {
+            kind: r.read()?,
+            body: r.read()?,
+            active: r.read()?,
+            target_name: r.read()?,
+            label: r.read()?,
+        })
+    }
+}
+
+impl Encode for WellInstance {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.particle_index);
+        w.write(&self.observable);
+        w.write(&self.threshold);
+        w.write(&self.depth);
+        w.write(&self.name);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(WellInstance {
+            particle_index: r.read()?,
+            observable: r.read()?,
+            threshold: r.read()?,
+            depth: r.read()?,
+            name: r.read()?,
+        })
+    }
+}
+
+impl Encode for CollisionEvent {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.step);
+        w.write(&self.a);
+        w.write(&self.b);
+        w.write(&self.impulse);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(CollisionEvent {
+            step: r.read()?,
+            a: r.read()?,
+            b: r.read()?,
+            impulse: r.read()?,
+        })
+    }
+}
+
+impl Encode for Integrator {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        let index = Integrator::ALL.iter().position(|i| i == self).unwrap();
+        w.tag(index as u8);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        let tag = r.tag()?;
+        Integrator::ALL
+            .get(usize::from(tag))
+            .copied()
+            .ok_or_else(|| bad_tag("integrator", tag))
+    }
+}
+
+impl Encode for Frame {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.tag(match self {
+            Frame::Lab => 0,
+            Frame::CenterOfMass {
+                recenter: Recenter::Once,
+            } => 1,
+            Frame::CenterOfMass {
+                recenter: Recenter::Always,
+            } => 2,
+        });
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => Frame::Lab,
+            1 => Frame::CenterOfMass {
+                recenter: Recenter::Once,
+            },
+            2 => Frame::CenterOfMass {
+                recenter: Recenter::Always,
+            },
+            tag => return Err(bad_tag("frame", tag)),
+        })
+    }
+}
+
+impl Encode for DetectorDecl {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        w.write(&self.name);
+        w.write(&self.expr);
+        w.write(&self.initial);
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(DetectorDecl {
+            name: r.read()?,
+            expr: r.read()?,
+            initial: r.read()?,
+        })
+    }
+}
+
+impl Encode for DetectorKind {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            DetectorKind::Position(a) => {
+                w.tag(0);
+                w.write(a);
+            }
+            DetectorKind::Distance { a, b } => {
+                w.tag(1);
+                w.write(a);
+                w.write(b);
+            }
+            DetectorKind::KineticEnergy => w.tag(2),
+            DetectorKind::PotentialEnergy => w.tag(3),
+            DetectorKind::TotalEnergy => w.tag(4),
+            DetectorKind::Momentum => w.tag(5),
+            DetectorKind::CenterOfMass => w.tag(6),
+            DetectorKind::Time => w.tag(7),
+            DetectorKind::CollisionCount { a, b } => {
+                w.tag(8);
+                w.write(a);
+                w.write(b);
+            }
+            DetectorKind::PositionY(a) => {
+                w.tag(9);
+                w.write(a);
+            }
+            DetectorKind::VelocityX(a) => {
+                w.tag(10);
+                w.write(a);
+            }
+            DetectorKind::VelocityY(a) => {
+                w.tag(11);
+                w.write(a);
+            }
+            DetectorKind::CenterOfMassY => w.tag(12),
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => DetectorKind::Position(r.read()?),
+            1 => DetectorKind::Distance {
+                a: r.read()?,
+                b: r.read()?,
+            },
+            2 => DetectorKind::KineticEnergy,
+            3 => DetectorKind::PotentialEnergy,
+            4 => DetectorKind::TotalEnergy,
+            5 => DetectorKind::Momentum,
+            6 => DetectorKind::CenterOfMass,
+            7 => DetectorKind::Time,
+            8 => DetectorKind::CollisionCount {
+                a: r.read()?,
+                b: r.read()?,
+            },
+            9 => DetectorKind::PositionY(r.read()?),
+            10 => DetectorKind::VelocityX(r.read()?),
+            11 => DetectorKind::VelocityY(r.read()?),
+            12 => DetectorKind::CenterOfMassY,
+            tag => return Err(bad_tag("detector", tag)),
+        })
+    }
+}
+
+const BINARY_OPS: [BinaryOp; 10] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::GreaterThan,
+    BinaryOp::LessThan,
+    BinaryOp::GreaterEqual,
+    BinaryOp::LessEqual,
+    BinaryOp::Equal,
+    BinaryOp::NotEqual,
+];
+
+const FUNC_NAMES: [FuncName; 4] = [
+    FuncName::Sin,
+    FuncName::Cos,
+    FuncName::Sqrt,
+    FuncName::Clamp,
+];
+
+impl Encode for Expr {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            Expr::Literal(value) => {
+                w.tag(0);
+                w.write(value);
+            }
+            Expr::StringLiteral(s) => {
+                w.tag(1);
+                w.write(s);
+            }
+            Expr::Var(name) => {
+                w.tag(2);
+                w.write(name);
+            }
+            Expr::UnaryMinus(inner) => {
+                w.tag(3);
+                w.write(inner);
+            }
+            Expr::Binary { op, left, right } => {
+                w.tag(4);
+                w.tag(BINARY_OPS.iter().position(|o| o == op).unwrap() as u8);
+                w.write(left);
+                w.write(right);
+            }
+            Expr::Call { func, args } => {
+                w.tag(5);
+                w.tag(FUNC_NAMES.iter().position(|f| f == func).unwrap() as u8);
+                w.write(args);
+            }
+            Expr::UserCall { name, args } => {
+                w.tag(6);
+                w.write(name);
+                w.write(args);
+            }
+            Expr::Observable(kind) => {
+                w.tag(7);
+                w.write(kind);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => Expr::Literal(r.read()?),
+            1 => Expr::StringLiteral(r.read()?),
+            2 => Expr::Var(r.read()?),
+            3 => Expr::UnaryMinus(r.read()?),
+            4 => {
+                let tag = r.tag()?;
+                let op = *BINARY_OPS
+                    .get(usize::from(tag))
+                    .ok_or_else(|| bad_tag("operator", tag))?;
+                Expr::Binary {
+                    op,
+                    left: r.read()?,
+                    right: r.read()?,
+                }
+            }
+            5 => {
+                let tag = r.tag()?;
+                let func = *FUNC_NAMES
+                    .get(usize::from(tag))
+                    .ok_or_else(|| bad_tag("function", tag))?;
+                Expr::Call {
+                    func,
+                    args: r.read()?,
+                }
+            }
+            6 => Expr::UserCall {
+                name: r.read()?,
+                args: r.read()?,
+            },
+            7 => Expr::Observable(r.read()?),
+            tag => return Err(bad_tag("expression", tag)),
+        })
+    }
+}
+
+impl Encode for ForceKind {
+    fn encode(&self, w: &mut SnapshotWriter) {
+        match self {
+            ForceKind::Gravity { g } => {
+                w.tag(0);
+                w.write(g);
+            }
+            ForceKind::Spring { k, rest } => {
+                w.tag(1);
+                w.write(k);
+                w.write(rest);
+            }
+            ForceKind::Central { point, gm } => {
+                w.tag(2);
+                w.write(point);
+                w.write(gm);
+            }
+        }
+    }
+    fn decode(r: &mut SnapshotReader<'_>) -> Result<Self, SnapshotError> {
+        Ok(match r.tag()? {
+            0 => ForceKind::Gravity { g: r.read()? },
+            1 => ForceKind::Spring {
+                k: r.read()?,
+                rest: r.read()?,
+            },
+            2 => ForceKind::Central {
+                point: r.read()?,
+                gm: r.read()?,
+            },
+            tag => return Err(bad_tag("force kind", tag)),
+        })
+    }
+}
@@ -0,0 +1,94 @@
+//! Serializable snapshots of world state for export and warm-starting
+
+use crate::engine::World;
+use crate::symbols::SymbolId;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors raised when importing a world state
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StateError {
+    #[error("state contains unknown particle '{0}'")]
+    UnknownParticle(String),
+    #[error("state is missing particle '{0}'")]
+    MissingParticle(String),
+    #[error("state contains particle '{0}' more than once")]
+    DuplicateParticle(String),
+}
+
+/// Snapshot of a single particle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParticleSnapshot {
+    pub name: String,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub mass: f32,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub damping: f32,
+}
+
+/// Snapshot of every particle in a world
+/// Loop and well state are not included; they restart with the new simulation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldState {
+    pub particles: Vec<ParticleSnapshot>,
+}
+
+impl WorldState {
+    /// Capture the particles of a world
+    pub fn from_world(world: &World) -> Self {
+        Self {
+            particles: world
+                .particles
+                .iter()
+                .map(|p| ParticleSnapshot {
+                    name: p.name.clone(),
+                    pos: p.pos,
+                    vel: p.vel,
+                    mass: p.mass,
+                    pinned: p.pinned,
+                    damping: p.damping,
+                })
+                .collect(),
+        }
+    }
+
+    /// Overwrite the matching particles of a world, by name
+    /// The world is left untouched if the names don't match exactly
+    pub fn apply_to(&self, world: &mut World) -> Result<(), StateError> {
+        let symbols = world.symbols();
+        let mut indices = Vec::with_capacity(self.particles.len());
+        for snapshot in &self.particles {
+            let idx = symbols
+                .resolve(&snapshot.name)
+                .map(SymbolId::index)
+                .ok_or_else(|| StateError::UnknownParticle(snapshot.name.clone()))?;
+            if indices.contains(&idx) {
+                return Err(StateError::DuplicateParticle(snapshot.name.clone()));
+            }
+            indices.push(idx);
+        }
+
+        if let Some(missing) = world
+            .particles
+            .iter()
+            .enumerate()
+            .find(|(i, _)| !indices.contains(i))
+        {
+            return Err(StateError::MissingParticle(missing.1.name.clone()));
+        }
+
+        for (snapshot, idx) in self.particles.iter().zip(indices) {
+            let particle = &mut world.particles[idx];
+            particle.pos = snapshot.pos;
+            particle.vel = snapshot.vel;
+            particle.mass = snapshot.mass;
+            particle.pinned = snapshot.pinned;
+            particle.damping = snapshot.damping;
+        }
+        Ok(())
+    }
+}
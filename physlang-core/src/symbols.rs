@@ -0,0 +1,109 @@
+//! Interned particle names
+//!
+//! Declarations refer to particles by name, but the simulation works with
+//! indices. A `SymbolTable` is the one place names are resolved: built from
+//! a world's particles, the `SymbolId` of a name is the index of its
+//! particle, so resolved structures can index `world.particles` directly.
+//! Static analysis resolves through the same table, built from the program,
+//! so a reference it accepts is one the simulation builder accepts too.
+
+use crate::ast::Program;
+use crate::engine::Particle;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// A reference to a particle that no declaration names
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown particle '{name}' in {place}")]
+pub struct UnknownParticle {
+    pub name: String,
+    /// What refers to it, e.g. `force` or `detector`
+    pub place: &'static str,
+}
+
+/// An interned name; its index is the order it was added to its table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(u32);
+
+impl SymbolId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Names and their ids, resolvable both ways
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    names: Vec<String>,
+    ids: HashMap<String, SymbolId>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A table with the id of every particle equal to its index
+    pub fn of_particles(particles: &[Particle]) -> Self {
+        let mut table = Self::new();
+        for particle in particles {
+            table.push(&particle.name);
+        }
+        table
+    }
+
+    /// The table of the world `program` builds: its particle declarations, in
+    /// order
+    pub fn of_program(program: &Program) -> Self {
+        let mut table = Self::new();
+        for particle in &program.particles {
+            table.push(&particle.name);
+        }
+        table
+    }
+
+    /// Add `name` under the next id, which is returned
+    /// A name added twice keeps resolving to its first id
+    pub fn push(&mut self, name: &str) -> SymbolId {
+        let id = SymbolId(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.ids.entry(name.to_string()).or_insert(id);
+        id
+    }
+
+    /// The id of `name`, if it was added
+    pub fn resolve(&self, name: &str) -> Option<SymbolId> {
+        self.ids.get(name).copied()
+    }
+
+    /// The id of the particle `name`, referred to from `place`
+    pub fn particle(&self, name: &str, place: &'static str) -> Result<SymbolId, UnknownParticle> {
+        self.resolve(name).ok_or_else(|| UnknownParticle {
+            name: name.to_string(),
+            place,
+        })
+    }
+
+    /// The name added under `id`
+    ///
+    /// Panics if `id` comes from another, larger table
+    pub fn name(&self, id: SymbolId) -> &str {
+        &self.names[id.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every id and its name, in id order
+    pub fn iter(&self) -> impl Iterator<Item = (SymbolId, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (SymbolId(i as u32), name.as_str()))
+    }
+}
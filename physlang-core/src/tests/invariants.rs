@@ -0,0 +1,124 @@
+//! Reusable invariant checks for PhysLang programs
+//!
+//! Each check builds a context from source, steps it with the normal step
+//! API, and panics at the first step that breaks the invariant, naming the
+//! step and the values involved.
+
+use crate::engine::Force;
+use crate::runtime::{build_simulation_context_from_source, step_simulation, SimulationContext};
+
+fn build(source: &str) -> SimulationContext {
+    match build_simulation_context_from_source(source) {
+        Ok((ctx, _)) => ctx,
+        Err(e) => panic!("failed to build invariant subject: {}", e),
+    }
+}
+
+/// Assert that total momentum stays within `tolerance` of its initial value
+/// for `steps` steps. Only pair forces conserve momentum, so the program may
+/// not use central forces, ground, loops, wells, or pinned particles
+pub fn assert_momentum_conserved(source: &str, steps: usize, tolerance: f32) {
+    let mut ctx = build(source);
+    let world = &ctx.world;
+    let external = world
+        .forces
+        .iter()
+        .any(|force| matches!(force, Force::Central { .. }));
+    assert!(
+        !external
+            && world.custom_force_count() == 0
+            && world.ground().is_none()
+            && world.particles.iter().all(|p| !p.pinned)
+            && ctx.loops.is_empty()
+            && ctx.wells.is_empty(),
+        "momentum is only conserved by programs with nothing but pair forces"
+    );
+
+    ctx.max_steps = steps;
+    let initial = ctx.world.total_momentum();
+    for step in 1..=steps {
+        step_simulation(&mut ctx);
+        let momentum = ctx.world.total_momentum();
+        let drift = (momentum - initial).length();
+        assert!(
+            drift <= tolerance,
+            "momentum drifted by {} (more than {}) at step {}: {:?} -> {:?}",
+            drift,
+            tolerance,
+            step,
+            initial,
+            momentum
+        );
+    }
+}
+
+/// Assert that two runs of `source` pass through bit-identical states
+pub fn assert_deterministic(source: &str) {
+    let mut first = build(source);
+    let mut second = build(source);
+    assert_eq!(
+        first.state_hash(),
+        second.state_hash(),
+        "built contexts differ before the first step"
+    );
+    loop {
+        let finished = step_simulation(&mut first);
+        step_simulation(&mut second);
+        assert_eq!(
+            first.state_hash(),
+            second.state_hash(),
+            "runs diverged at step {}",
+            first.current_step
+        );
+        if finished {
+            break;
+        }
+    }
+}
+
+/// Assert that |E| stays within `factor` × |E₀| for `steps` steps (within
+/// `factor` absolute if E₀ = 0), where E is total energy
+pub fn assert_energy_bounded(source: &str, steps: usize, factor: f32) {
+    let mut ctx = build(source);
+    ctx.max_steps = steps;
+    let initial = ctx.world.total_energy();
+    let bound = factor * if initial != 0.0 { initial.abs() } else { 1.0 };
+    for step in 1..=steps {
+        step_simulation(&mut ctx);
+        let energy = ctx.world.total_energy();
+        assert!(
+            energy.abs() <= bound,
+            "energy {} exceeds the bound {} ({} × E₀ = {}) at step {}",
+            energy,
+            bound,
+            factor,
+            initial,
+            step
+        );
+    }
+}
+
+/// Assert that no particle position or velocity becomes NaN or infinite at
+/// any step of the program's run
+pub fn assert_no_nan(source: &str) {
+    let mut ctx = build(source);
+    ctx.check_divergence = false;
+    if let Some((index, quantity)) = ctx.world.find_non_finite() {
+        panic!(
+            "{} of particle '{}' is not finite before the first step",
+            quantity, ctx.world.particles[index].name
+        );
+    }
+    loop {
+        let finished = step_simulation(&mut ctx);
+        if let Some((index, quantity)) = ctx.world.find_non_finite() {
+            panic!(
+                "{} of particle '{}' is not finite at step {}",
+                quantity, ctx.world.particles[index].name, ctx.current_step
+            );
+        }
+        if finished {
+            break;
+        }
+    }
+}
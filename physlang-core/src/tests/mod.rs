@@ -1,2 +1,2 @@
+pub mod invariants;
 pub mod test_helpers;
-
@@ -0,0 +1,154 @@
+//! Tests for checking single declarations against an existing program
+
+use physlang_core::ast::Program;
+use physlang_core::{
+    analyze_program, check_detector_decl, check_force_decl, check_ground_decl, check_let_decl,
+    check_loop_decl, check_particle_decl, check_simulate_decl, check_well_decl, parse_program,
+    AnalysisEnv, Diagnostics,
+};
+
+/// The program new declarations are checked against
+const WORLD: &str = "\
+let k = 10.0
+fn half(x) {
+    return x / 2.0
+}
+particle a at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+";
+
+/// A program of just `decl`, to take the parsed declaration from
+fn declaration(decl: &str) -> Program {
+    let simulate = if decl.starts_with("simulate") {
+        ""
+    } else {
+        "simulate dt = 0.01 steps = 1\n"
+    };
+    parse_program(&format!("{}\n{}", decl, simulate)).unwrap()
+}
+
+fn messages(diagnostics: Diagnostics) -> Vec<String> {
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn test_env_captures_the_program_names() {
+    let world = parse_program(WORLD).unwrap();
+    let env = AnalysisEnv::of_program(&world);
+    assert!(env.has_let("k"));
+    assert!(!env.has_let("half"));
+    assert!(env.has_function("half"));
+    let particles: Vec<&str> = env.particles().iter().map(|(_, name)| name).collect();
+    assert_eq!(particles, ["a", "b"]);
+}
+
+#[test]
+fn test_force_against_known_particles() {
+    let world = parse_program(WORLD).unwrap();
+    let env = AnalysisEnv::of_program(&world);
+
+    let spring = declaration("force spring(a, b) k = k rest = 1.0");
+    assert!(check_force_decl(&spring.forces[0], &env).is_empty());
+
+    let unknown = declaration("force spring(a, c) k = stiffness rest = time");
+    let diagnostics = check_force_decl(&unknown.forces[0], &env);
+    let errors: Vec<_> = diagnostics.iter().collect();
+    assert_eq!(errors[0].message, "unknown particle 'c' in force");
+    assert_eq!(errors[0].code, Some("E0002"));
+    assert_eq!(errors[0].help.as_deref(), Some("declared particles: a, b"));
+    // Forces may read `time`, but not an undeclared let
+    assert_eq!(errors[1].message, "unknown variable 'stiffness'");
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn test_declared_particle_becomes_known() {
+    let world = parse_program(WORLD).unwrap();
+    let mut env = AnalysisEnv::of_program(&world);
+    let well = declaration("well w on c if position(c).x >= 1.0 depth 2.0");
+    assert_eq!(
+        messages(check_well_decl(&well.wells[0], &env)),
+        [
+            "unknown particle 'c' in well",
+            "unknown particle 'c' in observable"
+        ]
+    );
+
+    env.declare_particle("c");
+    assert!(check_well_decl(&well.wells[0], &env).is_empty());
+}
+
+#[test]
+fn test_particle_constants_read_the_lets() {
+    let world = parse_program(WORLD).unwrap();
+    let env = AnalysisEnv::of_program(&world);
+    let particle = declaration("particle c at (k, 0.0) mass 1.0 - k");
+    assert_eq!(
+        messages(check_particle_decl(&particle.particles[0], &env)),
+        ["particle 'c' has negative mass -9"]
+    );
+    let particle = declaration("particle c at (x, 0.0) mass 1.0");
+    assert_eq!(
+        messages(check_particle_decl(&particle.particles[0], &env)),
+        ["unknown variable 'x'"]
+    );
+}
+
+#[test]
+fn test_loop_detector_and_let() {
+    let world = parse_program(WORLD).unwrap();
+    let env = AnalysisEnv::of_program(&world);
+
+    let looped = declaration(
+        "loop while position(z).x < k with frequency 1.0 damping 0.0 on a {\n    \
+         force push(b) magnitude 1.0 direction (1.0, 0.0)\n}",
+    );
+    assert_eq!(
+        messages(check_loop_decl(&looped.loops[0], &env)),
+        ["unknown particle 'z' in observable"]
+    );
+
+    let detector = declaration("detect gap = distance(a, b) - k * scale");
+    assert_eq!(
+        messages(check_detector_decl(&detector.detectors[0], &env)),
+        ["unknown variable 'scale'"]
+    );
+
+    let let_decl = declaration("let soft = k / 2.0 + hard");
+    assert_eq!(
+        messages(check_let_decl(&let_decl.lets[0], &env)),
+        ["unknown variable 'hard'"]
+    );
+}
+
+#[test]
+fn test_ground_and_simulate_constants() {
+    let world = parse_program(WORLD).unwrap();
+    let env = AnalysisEnv::of_program(&world);
+
+    let ground = declaration("ground y = 0.0 friction 0.5 - k");
+    assert_eq!(
+        messages(check_ground_decl(ground.ground.as_ref().unwrap(), &env)),
+        ["ground has negative friction -9.5"]
+    );
+
+    let simulate = declaration("simulate dt = 0.0 steps = half(k)");
+    assert_eq!(
+        messages(check_simulate_decl(&simulate.simulate, &env)),
+        ["simulate dt must be positive, got 0"]
+    );
+}
+
+#[test]
+fn test_program_analysis_is_the_declaration_checks() {
+    let source = format!(
+        "{}force spring(a, c) k = k rest = 1.0\ndetect gap = distance(a, d)\n",
+        WORLD
+    );
+    let program = parse_program(&source).unwrap();
+    let env = AnalysisEnv::of_program(&program);
+    let mut expected = messages(check_force_decl(&program.forces[0], &env));
+    expected.extend(messages(check_detector_decl(&program.detectors[0], &env)));
+    assert_eq!(messages(analyze_program(&program)), expected);
+}
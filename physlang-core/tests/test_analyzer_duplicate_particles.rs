@@ -16,6 +16,29 @@ simulate dt = 0.01 steps = 100
     assert!(errors.iter().any(|e| e.message.contains("duplicate particle name 'a'")));
 }
 
+#[test]
+fn test_duplicate_points_at_the_later_declaration() {
+    let source = "\
+particle a at (0.0, 0.0) mass 1.0
+particle a at (5.0, 0.0) mass 2.0
+particle b at (1.0, 0.0) mass 1.0
+particle a at (9.0, 0.0) mass 3.0
+simulate dt = 0.01 steps = 100
+";
+    let program = parse_program(source).unwrap();
+    let diagnostics = analyze_program(&program);
+    let lines: Vec<_> = diagnostics
+        .errors()
+        .map(|e| e.span.unwrap().to_location(source).line)
+        .collect();
+    assert_eq!(lines, [2, 4]);
+    let error = diagnostics.errors().next().unwrap();
+    assert_eq!(
+        error.notes,
+        ["references to 'a' resolve to its first declaration"]
+    );
+}
+
 #[test]
 fn test_unique_particle_names() {
     let source = r#"
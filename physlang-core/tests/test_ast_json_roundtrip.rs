@@ -0,0 +1,64 @@
+//! Tests for the JSON round-trip of the Program AST
+#![cfg(feature = "ast-serde")]
+
+use physlang_core::ast::Program;
+use physlang_core::{
+    build_run_context, check_program, expand_functions, parse_program, run_simulation, RunOptions,
+};
+use std::path::{Path, PathBuf};
+
+/// Every `.phys` file under `dir`, recursively
+fn fixtures(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path.extension().is_some_and(|e| e == "phys") {
+            found.push(path);
+        }
+    }
+}
+
+/// Detector values as bits, or the error message
+fn run(mut program: Program) -> Result<Vec<(String, u32)>, String> {
+    let options = RunOptions::default();
+    check_program(&program).map_err(|e| e.to_string())?;
+    expand_functions(&mut program).map_err(|e| e.to_string())?;
+    let ctx = build_run_context(&program, &options).map_err(|e| e.to_string())?;
+    let result = run_simulation(ctx, &program, &options, |_| Ok(())).map_err(|e| e.to_string())?;
+    Ok(result
+        .detectors
+        .into_iter()
+        .map(|d| (d.name, d.value.to_bits()))
+        .collect())
+}
+
+#[test]
+fn test_every_fixture_round_trips() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut paths = Vec::new();
+    for dir in ["examples", "tests", "physlang-cli/tests/fixtures"] {
+        fixtures(&root.join(dir), &mut paths);
+    }
+    assert!(paths.len() > 20, "found only {:?}", paths);
+
+    let mut round_tripped = 0;
+    for path in &paths {
+        let source = std::fs::read_to_string(path).unwrap();
+        let Ok(program) = parse_program(&source) else {
+            continue;
+        };
+        let json = program.to_json().unwrap();
+        let rebuilt = Program::from_json(&json).unwrap();
+        assert_eq!(rebuilt, program, "{}", path.display());
+        assert_eq!(run(rebuilt), run(program), "{}", path.display());
+        round_tripped += 1;
+    }
+    assert!(round_tripped > 20, "only {} fixtures parsed", round_tripped);
+}
+
+#[test]
+fn test_from_json_rejects_malformed_programs() {
+    assert!(Program::from_json("{}").is_err());
+    assert!(Program::from_json("not json").is_err());
+}
@@ -0,0 +1,71 @@
+//! Regression tests for inputs found by fuzzing the parser and analyzer
+//!
+//! Each of these once panicked (or hung) instead of returning an error.
+
+use physlang_core::{analyze_program, parse_program};
+
+/// Parse and analyze `source`; it must not panic
+fn check(source: &str) {
+    if let Ok(program) = parse_program(source) {
+        analyze_program(&program);
+    }
+}
+
+#[test]
+fn test_multibyte_char_before_comparison_scan() {
+    check("let x = 1 + →\n");
+    check("let x = 2→0 < 1\n");
+}
+
+#[test]
+fn test_multibyte_char_in_call_arguments() {
+    check("let y = sqrt(2→0)\n");
+    check("let y = clamp(日本, 0.0, 1.0)\n");
+}
+
+#[test]
+fn test_close_paren_before_particle_position() {
+    check("particle a at )(0.0, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 10\n");
+}
+
+#[test]
+fn test_close_paren_before_push_direction() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+loop for 10 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.5 direction )(1.0, 0.0)
+}
+"#;
+    check(source);
+}
+
+#[test]
+fn test_close_paren_before_position_condition() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+loop while ) position(a).x < 1.0 with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+"#;
+    check(source);
+}
+
+#[test]
+fn test_block_without_opening_brace_is_an_error() {
+    let source = r#"
+for i in 0..2 {
+    match i {
+        0 => {>
+            particle "a" at (0.0, 0.0) mass 1.0
+        }
+    }
+}
+simulate dt = 0.01 steps = 100
+"#;
+    let err = parse_program(source).unwrap_err();
+    assert!(err.to_string().contains("Expected '{'"), "{}", err);
+
+    check("for i in 0..2 {x\n    particle \"a\" at (0.0, 0.0) mass 1.0\n}\n");
+}
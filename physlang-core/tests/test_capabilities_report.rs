@@ -0,0 +1,104 @@
+//! Tests for the language capability report
+
+use physlang_core::ast::{DetectorKind, FuncName};
+use physlang_core::capabilities::{builtin_keyword, detector_keyword};
+use physlang_core::{language_capabilities, LANGUAGE_VERSION};
+
+/// One value of every `FuncName` variant; the match stops this compiling
+/// when a variant is added
+fn all_builtins() -> Vec<FuncName> {
+    let all = vec![
+        FuncName::Sin,
+        FuncName::Cos,
+        FuncName::Sqrt,
+        FuncName::Clamp,
+    ];
+    for func in &all {
+        match func {
+            FuncName::Sin | FuncName::Cos | FuncName::Sqrt | FuncName::Clamp => {}
+        }
+    }
+    all
+}
+
+/// One value of every `DetectorKind` variant, kept complete the same way
+fn all_detectors() -> Vec<DetectorKind> {
+    let name = || "a".to_string();
+    let all = vec![
+        DetectorKind::Position(name()),
+        DetectorKind::PositionY(name()),
+        DetectorKind::VelocityX(name()),
+        DetectorKind::VelocityY(name()),
+        DetectorKind::Distance {
+            a: name(),
+            b: name(),
+        },
+        DetectorKind::KineticEnergy,
+        DetectorKind::PotentialEnergy,
+        DetectorKind::TotalEnergy,
+        DetectorKind::Momentum,
+        DetectorKind::CenterOfMass,
+        DetectorKind::CenterOfMassY,
+        DetectorKind::Time,
+        DetectorKind::CollisionCount {
+            a: name(),
+            b: name(),
+        },
+    ];
+    for kind in &all {
+        match kind {
+            DetectorKind::Position(_)
+            | DetectorKind::PositionY(_)
+            | DetectorKind::VelocityX(_)
+            | DetectorKind::VelocityY(_)
+            | DetectorKind::Distance { .. }
+            | DetectorKind::KineticEnergy
+            | DetectorKind::PotentialEnergy
+            | DetectorKind::TotalEnergy
+            | DetectorKind::Momentum
+            | DetectorKind::CenterOfMass
+            | DetectorKind::CenterOfMassY
+            | DetectorKind::Time
+            | DetectorKind::CollisionCount { .. } => {}
+        }
+    }
+    all
+}
+
+#[test]
+fn test_every_builtin_is_reported() {
+    let capabilities = language_capabilities();
+    let builtins = all_builtins();
+    assert_eq!(capabilities.builtin_functions.len(), builtins.len());
+    for func in builtins {
+        assert!(capabilities
+            .builtin_functions
+            .contains(&builtin_keyword(&func)));
+    }
+}
+
+#[test]
+fn test_every_detector_is_reported() {
+    let capabilities = language_capabilities();
+    let detectors = all_detectors();
+    assert_eq!(capabilities.detectors.len(), detectors.len());
+    for kind in detectors {
+        assert!(capabilities.detectors.contains(&detector_keyword(&kind)));
+    }
+}
+
+#[test]
+fn test_report_serializes() {
+    let json = serde_json::to_value(language_capabilities()).unwrap();
+    assert_eq!(json["language_version"], LANGUAGE_VERSION);
+    assert_eq!(json["crate_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        json["forces"],
+        serde_json::json!(["gravity", "spring", "central"])
+    );
+    assert!(json["statements"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("match")));
+    assert_eq!(json["integrators"][0], "explicit-euler");
+}
@@ -0,0 +1,142 @@
+//! Tests for the limit on diagnostics with the same code
+
+use physlang_core::{
+    analyze_program_with_limit, parse_program, Diagnostic, DiagnosticSeverity, Diagnostics,
+    Pipeline, RunError, RunOptions, Span,
+};
+use std::time::{Duration, Instant};
+
+/// A buggy loop: ten thousand particles, all named `node`
+const SAME_NAME: &str = "\
+for i in 0..10000 {
+    particle node at (i, 0.0) mass 1.0
+}
+simulate dt = 0.01 steps = 10
+";
+
+fn unknown_variable(index: usize) -> Diagnostic {
+    Diagnostic::error("unknown variable 'm'", Some(Span::new(index, index + 1))).with_code("E0001")
+}
+
+fn messages(diagnostics: &Diagnostics) -> Vec<&str> {
+    diagnostics.iter().map(|d| d.message.as_str()).collect()
+}
+
+/// The post-function analysis errors of `source`
+fn expansion_errors(source: &str, options: &RunOptions) -> Vec<String> {
+    let mut pipeline = Pipeline::parse(source).unwrap();
+    pipeline.apply_overrides(options).unwrap();
+    pipeline.analyze().unwrap();
+    match pipeline.expand_functions() {
+        Err(RunError::Analysis { stage, messages }) => {
+            assert_eq!(stage, "Post-function analysis");
+            messages
+        }
+        other => panic!("expected analysis errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_overflow_is_summarized_once() {
+    let mut diagnostics = Diagnostics::with_code_limit(2);
+    for index in 0..5 {
+        diagnostics.push(unknown_variable(index));
+    }
+    diagnostics.push(Diagnostic::error("uncoded", None));
+    assert_eq!(
+        messages(&diagnostics),
+        [
+            "unknown variable 'm'",
+            "unknown variable 'm'",
+            "3 more unknown variable errors suppressed",
+            "uncoded",
+        ]
+    );
+    // The summary points at the first diagnostic it stands for
+    let summary = diagnostics.iter().nth(2).unwrap();
+    assert_eq!(summary.span, Some(Span::new(2, 3)));
+    assert_eq!(summary.severity, DiagnosticSeverity::Error);
+    assert!(diagnostics.has_errors());
+}
+
+#[test]
+fn test_only_codes_are_limited() {
+    let mut diagnostics = Diagnostics::with_code_limit(1);
+    for _ in 0..3 {
+        diagnostics.push(Diagnostic::warning("no code", None));
+    }
+    diagnostics.push(Diagnostic::warning("odd", None).with_code("W9999"));
+    diagnostics.push(Diagnostic::warning("odd", None).with_code("W9999"));
+    assert_eq!(diagnostics.warnings().count(), 5);
+    assert_eq!(
+        diagnostics.iter().last().unwrap().message,
+        "1 more W9999 warning suppressed"
+    );
+}
+
+#[test]
+fn test_default_limit_applies_when_collecting() {
+    let diagnostics = Diagnostics::from((0..60).map(unknown_variable).collect::<Vec<_>>());
+    assert_eq!(Diagnostics::DEFAULT_CODE_LIMIT, 50);
+    assert_eq!(diagnostics.len(), 51);
+    assert_eq!(
+        diagnostics.iter().last().unwrap().message,
+        "10 more unknown variable errors suppressed"
+    );
+
+    // Extending counts against the limit of the collection extended
+    let mut merged = Diagnostics::with_code_limit(55);
+    merged.extend(diagnostics);
+    assert_eq!(merged.len(), 51);
+    let mut merged = Diagnostics::with_code_limit(5);
+    merged.extend(Diagnostics::from(
+        (0..10).map(unknown_variable).collect::<Vec<_>>(),
+    ));
+    assert_eq!(
+        messages(&merged)[5],
+        "5 more unknown variable errors suppressed"
+    );
+}
+
+#[test]
+fn test_generated_duplicates_are_summarized_quickly() {
+    let started = Instant::now();
+    let errors = expansion_errors(SAME_NAME, &RunOptions::default());
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(errors.len(), 51);
+    assert!(errors[..50]
+        .iter()
+        .all(|message| message == "duplicate particle name 'node'"));
+    assert_eq!(
+        errors[50],
+        "9,949 more duplicate particle name errors suppressed"
+    );
+}
+
+#[test]
+fn test_limit_is_configurable() {
+    let options = RunOptions {
+        diagnostic_limit: Some(3),
+        ..Default::default()
+    };
+    let errors = expansion_errors(SAME_NAME, &options);
+    assert_eq!(errors.len(), 4);
+    assert_eq!(
+        errors[3],
+        "9,996 more duplicate particle name errors suppressed"
+    );
+
+    let program = parse_program(
+        "particle a at (0.0, 0.0) mass m\n\
+         particle b at (1.0, 0.0) mass m\n\
+         simulate dt = 0.01 steps = 1\n",
+    )
+    .unwrap();
+    assert_eq!(
+        messages(&analyze_program_with_limit(&program, 1)),
+        [
+            "unknown variable 'm'",
+            "1 more unknown variable error suppressed"
+        ]
+    );
+}
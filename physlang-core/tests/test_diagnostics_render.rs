@@ -0,0 +1,115 @@
+//! Tests for rustc-style diagnostic rendering
+
+use physlang_core::diagnostics::format_parse_error;
+use physlang_core::{analyze_program, parse_program, render_diagnostic, Diagnostic, Span};
+
+const SOURCE: &str = "particle a at (0.0, 0.0) mass 1.0\n\tforce spring(a, b) k = 2.0 rest = 1.0\n";
+
+#[test]
+fn test_span_range_underlined_with_tabs_expanded() {
+    // The `spring(a, b)` call on line 2
+    let start = SOURCE.find("spring").unwrap();
+    let diagnostic = Diagnostic::error("bad force", Some(Span::new(start, start + 12)));
+    let rendered = render_diagnostic(&diagnostic, SOURCE, Some("main.phys"), false);
+    assert_eq!(
+        rendered,
+        "error: bad force\n \
+         --> main.phys:2:8\n  \
+         |\n\
+         2 |     force spring(a, b) k = 2.0 rest = 1.0\n  \
+         |           ^^^^^^^^^^^^\n"
+    );
+}
+
+#[test]
+fn test_whole_line_span_underlines_statement_only() {
+    let line_start = SOURCE.find('\t').unwrap();
+    let diagnostic = Diagnostic::warning("odd", Some(Span::new(line_start, SOURCE.len())));
+    let rendered = render_diagnostic(&diagnostic, SOURCE, None, false);
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[0], "warning: odd");
+    assert_eq!(lines[1], " --> line 2:2");
+    assert_eq!(lines[4], format!("  |     {}", "^".repeat(37)));
+}
+
+#[test]
+fn test_code_notes_and_help() {
+    let diagnostic = Diagnostic::error("unknown particle 'b' in force", None)
+        .with_code("E0002")
+        .with_note("forces are resolved after all particles")
+        .with_help("declared particles: a");
+    let rendered = render_diagnostic(&diagnostic, SOURCE, Some("main.phys"), false);
+    assert_eq!(
+        rendered,
+        "error[E0002]: unknown particle 'b' in force\n \
+         --> main.phys\n  \
+         = note: forces are resolved after all particles\n  \
+         = help: declared particles: a\n"
+    );
+}
+
+#[test]
+fn test_gutter_widens_with_line_number() {
+    let source = format!("{}particle z at (0.0, 0.0) mass 1.0\n", "\n".repeat(11));
+    let start = source.find('z').unwrap();
+    let diagnostic = Diagnostic::error("here", Some(Span::new(start, start + 1)));
+    let rendered = render_diagnostic(&diagnostic, &source, Some("f.phys"), false);
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[1], "  --> f.phys:12:10");
+    assert_eq!(lines[2], "   |");
+    assert_eq!(lines[3], "12 | particle z at (0.0, 0.0) mass 1.0");
+    assert_eq!(lines[4], "   |          ^");
+}
+
+#[test]
+fn test_color_adds_escapes_only_when_enabled() {
+    let diagnostic = Diagnostic::error("bad", Some(Span::new(0, 8))).with_code("E0001");
+    let plain = render_diagnostic(&diagnostic, SOURCE, Some("f.phys"), false);
+    let colored = render_diagnostic(&diagnostic, SOURCE, Some("f.phys"), true);
+    assert!(!plain.contains('\x1b'));
+    assert!(colored.contains("\x1b[1;31merror[E0001]\x1b[0m"));
+
+    // Stripping the escapes gives back the plain rendering
+    let mut stripped = String::new();
+    let mut in_escape = false;
+    for c in colored.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => stripped.push(c),
+        }
+    }
+    assert_eq!(stripped, plain);
+}
+
+#[test]
+fn test_parse_errors_narrowed_to_offending_text() {
+    let source =
+        "particle a at (0.0, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 10 frame = rotating\n";
+    let error = parse_program(source).unwrap_err();
+    let span = error.span().unwrap();
+    assert_eq!(&source[span.start..span.end], "rotating");
+
+    let rendered = format_parse_error(&error, source);
+    assert!(rendered.starts_with("error: Expected 'lab' or 'center_of_mass'"));
+    assert!(rendered.contains(" --> line 2:39\n"));
+    assert!(rendered.ends_with(&format!("  | {}^^^^^^^^\n", " ".repeat(38))));
+}
+
+#[test]
+fn test_analyzer_errors_carry_codes() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0 + missing
+particle c at (1.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 1.0
+simulate dt = 0.01 steps = 10
+"#;
+    let diagnostics = analyze_program(&parse_program(source).unwrap());
+    let errors: Vec<&Diagnostic> = diagnostics.errors().collect();
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(errors[0].code, Some("E0001"));
+    assert_eq!(errors[1].code, Some("E0002"));
+    assert_eq!(errors[1].help.as_deref(), Some("declared particles: a, c"));
+}
@@ -0,0 +1,133 @@
+//! Tests for world bounds, suggested viewports, and running motion statistics
+
+use glam::Vec2;
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{build_simulation_context_from_source, step_simulation, Bounds, World};
+
+#[test]
+fn test_empty_world_has_no_bounds() {
+    let world = World::new();
+    assert_eq!(world.bounds(), None);
+    assert_eq!(world.bounds_with_padding(1.0), None);
+
+    let (ctx, _) = build_simulation_context_from_source("simulate dt = 0.01 steps = 1").unwrap();
+    assert_eq!(ctx.suggested_viewport(), None);
+}
+
+#[test]
+fn test_single_particle_bounds_are_a_point() {
+    let source = r#"
+particle a at (3.0, -2.0) mass 1.0
+simulate dt = 0.01 steps = 1
+"#;
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let bounds = ctx.world.bounds().unwrap();
+    assert_eq!(bounds.min, Vec2::new(3.0, -2.0));
+    assert_eq!(bounds.max, Vec2::new(3.0, -2.0));
+    assert_eq!(bounds.size(), Vec2::ZERO);
+
+    // A point still gets the minimum 2-unit margin
+    let viewport = ctx.suggested_viewport().unwrap();
+    assert_eq!(viewport.center, Vec2::new(3.0, -2.0));
+    assert_eq!(viewport.half_extent, Vec2::new(2.0, 2.0));
+}
+
+#[test]
+fn test_bounds_cover_all_particles() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (-4.0, 10.0) mass 1.0
+particle c at (6.0, 5.0) mass 1.0
+simulate dt = 0.01 steps = 1
+"#;
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let bounds = ctx.world.bounds().unwrap();
+    assert_eq!(bounds.min, Vec2::new(-4.0, 0.0));
+    assert_eq!(bounds.max, Vec2::new(6.0, 10.0));
+    assert_eq!(bounds.center(), Vec2::new(1.0, 5.0));
+
+    // Larger side is 10, so the margin is 10% of it but at least 2
+    let viewport = ctx.suggested_viewport().unwrap();
+    assert_eq!(viewport.center, Vec2::new(1.0, 5.0));
+    assert_eq!(viewport.half_extent, Vec2::new(7.0, 7.0));
+}
+
+#[test]
+fn test_padding_math() {
+    let bounds = Bounds {
+        min: Vec2::new(-1.0, 2.0),
+        max: Vec2::new(3.0, 4.0),
+    };
+    let padded = bounds.padded(0.5);
+    assert_eq!(padded.min, Vec2::new(-1.5, 1.5));
+    assert_eq!(padded.max, Vec2::new(3.5, 4.5));
+    assert_eq!(padded.center(), bounds.center());
+    assert_eq!(padded.size(), bounds.size() + Vec2::splat(1.0));
+
+    let mut world = World::new();
+    world.particles.push(physlang_core::Particle {
+        name: "p".to_string(),
+        pos: Vec2::new(1.0, 1.0),
+        vel: Vec2::ZERO,
+        mass: 1.0,
+        ..Default::default()
+    });
+    let padded = world.bounds_with_padding(2.0).unwrap();
+    assert_eq!(padded.min, Vec2::new(-1.0, -1.0));
+    assert_eq!(padded.max, Vec2::new(3.0, 3.0));
+}
+
+#[test]
+fn test_running_max_speed_and_displacement() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 1000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    assert_eq!(ctx.max_speed, 0.0);
+    assert_eq!(ctx.max_displacement, 0.0);
+
+    let mut observed_speed = 0.0f32;
+    let mut observed_displacement = 0.0f32;
+    let start: Vec<_> = ctx.world.particles.iter().map(|p| p.pos).collect();
+    for _ in 0..1000 {
+        step_simulation(&mut ctx);
+        for (p, start) in ctx.world.particles.iter().zip(&start) {
+            observed_speed = observed_speed.max(p.vel.length());
+            observed_displacement = observed_displacement.max(p.pos.distance(*start));
+        }
+        // Running maxima never shrink, even as the oscillator turns around
+        assert_eq!(ctx.max_speed, observed_speed);
+        assert_eq!(ctx.max_displacement, observed_displacement);
+    }
+
+    // Symmetric oscillator: each particle swings by up to the 1-unit stretch
+    assert!(approx_eq_f32(ctx.max_speed, observed_speed, 1e-6));
+    assert!(approx_eq_f32(
+        ctx.max_displacement,
+        observed_displacement,
+        1e-6
+    ));
+    assert!(approx_eq_f32(ctx.max_displacement, 1.0, 0.05));
+}
+
+#[test]
+fn test_displacement_is_measured_from_imported_state() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 3.0
+simulate dt = 0.01 steps = 100
+"#;
+    let (mut moved, _) = build_simulation_context_from_source(source).unwrap();
+    for particle in &mut moved.world.particles {
+        particle.pos += Vec2::new(10.0, 0.0);
+    }
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.import_state(&moved.export_state()).unwrap();
+    while !step_simulation(&mut ctx) {}
+    // The spring is at rest, so nothing moves from where it was imported
+    assert_eq!(ctx.max_displacement, 0.0);
+}
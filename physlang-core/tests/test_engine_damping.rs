@@ -0,0 +1,122 @@
+//! Tests for per-particle linear damping
+
+use glam::Vec2;
+use physlang_core::ast::Expr;
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    analyze_program, build_simulation_context, build_simulation_context_from_source,
+    evaluate_lets, parse_program, run_program, step_simulation,
+};
+
+#[test]
+fn test_parse_damping_clause() {
+    let program = parse_program(
+        r#"
+particle a at (0.0, 0.0) mass 2.0 damping 0.5
+particle b at (1.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 1
+"#,
+    )
+    .unwrap();
+    assert_eq!(program.particles[0].mass, Expr::Literal(2.0));
+    assert_eq!(program.particles[0].damping, Some(Expr::Literal(0.5)));
+    assert_eq!(program.particles[1].damping, None);
+}
+
+#[test]
+fn test_damped_velocity_decays_exponentially() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0 damping 1.0
+simulate dt = 0.001 steps = 2000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(4.0, 0.0));
+    step_simulation(&mut ctx);
+    let v0 = ctx.world.particles[0].vel.x;
+    assert!(approx_eq_f32(v0, 4.0 * (1.0 - 0.001), 1e-6));
+
+    let mut previous = v0;
+    for n in 1..2000 {
+        step_simulation(&mut ctx);
+        let v = ctx.world.particles[0].vel.x;
+        assert!(v < previous && v > 0.0);
+        previous = v;
+        // Discrete decay (1 − c·dt)ⁿ tracks e^(−c·t)
+        let t = n as f32 * 0.001;
+        assert!(approx_eq_f32(v, v0 * (-t).exp(), 2e-3), "step {}: {}", n, v);
+    }
+}
+
+#[test]
+fn test_zero_damping_is_bit_identical() {
+    let undamped = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 1000
+detect d = distance(a, b)
+"#;
+    let explicit = undamped.replace("mass 1.0\n", "mass 1.0 damping 0.0\n");
+    assert_ne!(explicit, undamped);
+
+    let a = run_program(undamped).unwrap();
+    let b = run_program(&explicit).unwrap();
+    assert_eq!(a.state_hash, b.state_hash);
+    assert_eq!(
+        a.detectors[0].value.to_bits(),
+        b.detectors[0].value.to_bits()
+    );
+}
+
+#[test]
+fn test_large_damping_clamps_to_rest() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0 damping 1000.0
+simulate dt = 0.01 steps = 10
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(4.0, 0.0));
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[0].vel, Vec2::ZERO);
+}
+
+#[test]
+fn test_negative_damping_rejected() {
+    let literal = r#"
+particle a at (0.0, 0.0) mass 1.0 damping -1.0
+simulate dt = 0.01 steps = 10
+"#;
+    let diagnostics = analyze_program(&parse_program(literal).unwrap());
+    assert!(diagnostics.has_errors());
+
+    let computed = r#"
+let c = 0.0 - 2.0
+particle a at (0.0, 0.0) mass 1.0 damping c
+simulate dt = 0.01 steps = 10
+"#;
+    let err = run_program(computed).unwrap_err();
+    assert!(
+        err.to_string().contains("particle 'a' has negative damping -2"),
+        "{}",
+        err
+    );
+
+    // The builder rejects it too, for programs that skip analysis
+    let program = parse_program(computed).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let err = build_simulation_context(&program, &eval_ctx).err().unwrap();
+    assert!(err.to_string().contains("damping must be >= 0"), "{}", err);
+}
+
+#[test]
+fn test_damping_on_function_generated_particle() {
+    let source = r#"
+fn make(c) {
+    particle p at (0.0, 0.0) mass 1.0 damping c
+}
+make(0.25)
+simulate dt = 0.01 steps = 1
+"#;
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    assert_eq!(ctx.world.particles[0].damping, 0.25);
+}
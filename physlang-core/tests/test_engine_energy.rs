@@ -0,0 +1,138 @@
+//! Unit tests for energy, momentum, and center-of-mass accounting on World
+
+use physlang_core::engine::{Force, Particle, World};
+use physlang_core::tests::test_helpers::{approx_eq_f32, run_phys_source};
+use physlang_core::{build_simulation_context_from_source, parse_program, step_simulation};
+use glam::Vec2;
+
+fn particle(name: &str, pos: Vec2, vel: Vec2, mass: f32) -> Particle {
+    Particle {
+        name: name.to_string(),
+        pos,
+        vel,
+        mass,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_kinetic_energy_two_particles() {
+    let mut world = World::new();
+    world.particles.push(particle("a", Vec2::ZERO, Vec2::new(3.0, 4.0), 2.0));
+    world.particles.push(particle("b", Vec2::ONE, Vec2::new(-1.0, 0.0), 4.0));
+
+    // ½·2·25 + ½·4·1 = 27
+    assert!(approx_eq_f32(world.kinetic_energy(), 27.0, 1e-5));
+}
+
+#[test]
+fn test_spring_potential_energy() {
+    let mut world = World::new();
+    world.particles.push(particle("a", Vec2::ZERO, Vec2::ZERO, 1.0));
+    world.particles.push(particle("b", Vec2::new(3.0, 0.0), Vec2::ZERO, 1.0));
+    world.forces.push(Force::Spring { a: 0, b: 1, k: 10.0, rest: 2.0 });
+
+    // ½·10·(3−2)² = 5
+    assert!(approx_eq_f32(world.potential_energy(), 5.0, 1e-5));
+    assert!(approx_eq_f32(world.total_energy(), 5.0, 1e-5));
+}
+
+#[test]
+fn test_gravity_potential_energy() {
+    let mut world = World::new();
+    world.particles.push(particle("a", Vec2::ZERO, Vec2::new(1.0, 0.0), 2.0));
+    world.particles.push(particle("b", Vec2::new(0.0, 4.0), Vec2::ZERO, 3.0));
+    world.forces.push(Force::Gravity { a: 0, b: 1, g: 2.0 });
+
+    // −2·2·3/4 = −3, plus kinetic ½·2·1 = 1
+    assert!(approx_eq_f32(world.potential_energy(), -3.0, 1e-5));
+    assert!(approx_eq_f32(world.total_energy(), -2.0, 1e-5));
+}
+
+#[test]
+fn test_momentum_and_center_of_mass() {
+    let mut world = World::new();
+    world.particles.push(particle("a", Vec2::new(0.0, 0.0), Vec2::new(1.0, 2.0), 1.0));
+    world.particles.push(particle("b", Vec2::new(4.0, 2.0), Vec2::new(-1.0, 0.0), 3.0));
+
+    assert_eq!(world.total_momentum(), Vec2::new(-2.0, 2.0));
+    assert_eq!(world.center_of_mass(), Vec2::new(3.0, 1.5));
+    assert_eq!(World::new().center_of_mass(), Vec2::ZERO);
+}
+
+#[test]
+fn test_energy_detectors() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 25
+detect ke = kinetic_energy()
+detect pe = potential_energy()
+detect e = total_energy()
+detect p = momentum()
+detect com = center_of_mass()
+"#;
+    let result = run_phys_source(source).unwrap();
+    let values: Vec<f32> = result.detectors.iter().map(|d| d.value).collect();
+
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    while !step_simulation(&mut ctx) {}
+    let world = &ctx.world;
+    assert!(world.kinetic_energy() > 0.0);
+    assert_eq!(
+        values,
+        vec![
+            world.kinetic_energy(),
+            world.potential_energy(),
+            world.total_energy(),
+            world.total_momentum().length(),
+            world.center_of_mass().x,
+        ]
+    );
+}
+
+#[test]
+fn test_energy_is_not_a_condition_observable() {
+    let program = |well: &str, condition: &str| {
+        format!(
+            "particle a at (0.0, 0.0) mass 1.0\n\
+             simulate dt = 0.01 steps = 10\n\
+             well w on a if {well} >= 1.0 depth 1.0\n\
+             loop while {condition} < 1.0 with frequency 1.0 damping 0.0 on a {{\n\
+             force push(a) magnitude 1.0 direction (1.0, 0.0)\n\
+             }}\n"
+        )
+    };
+    let observable = "position(a).x";
+    assert!(parse_program(&program(observable, observable)).is_ok());
+    for energy in ["kinetic_energy()", "potential_energy()", "total_energy()"] {
+        assert!(parse_program(&program(energy, observable)).is_err());
+        assert!(parse_program(&program(observable, energy)).is_err());
+    }
+}
+
+#[test]
+fn test_undamped_spring_energy_bounded() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.001 steps = 50000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let initial = ctx.world.total_energy();
+    let mut max_drift: f32 = 0.0;
+    while !step_simulation(&mut ctx) {
+        max_drift = max_drift.max((ctx.world.total_energy() - initial).abs());
+    }
+
+    assert!(
+        max_drift / initial < 0.01,
+        "energy drifted by {} from {}",
+        max_drift,
+        initial
+    );
+    // Internal forces conserve momentum
+    assert!(ctx.world.total_momentum().length() < 1e-3);
+}
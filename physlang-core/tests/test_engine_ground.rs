@@ -0,0 +1,194 @@
+//! Tests for the ground line and kinetic friction
+
+use glam::Vec2;
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    analyze_program, build_simulation_context, build_simulation_context_from_source,
+    evaluate_lets, parse_program, step_simulation, Ground,
+};
+
+/// μ = 0.5 and g = 10 decelerate a sliding block at 5 units/s²
+const SLIDING_BLOCK: &str = r#"
+particle block at (0.0, 0.0) mass 2.0
+ground y = 0.0 friction 0.5 gravity 10.0
+simulate dt = 0.01 steps = 1000
+"#;
+
+#[test]
+fn test_parse_ground() {
+    let program = parse_program(SLIDING_BLOCK).unwrap();
+    let ground = program.ground.expect("ground should be parsed");
+    assert!(ground.gravity.is_some());
+
+    let (ctx, _) = build_simulation_context_from_source(SLIDING_BLOCK).unwrap();
+    assert_eq!(
+        ctx.world.ground(),
+        Some(Ground {
+            y: 0.0,
+            friction: 0.5,
+            gravity: 10.0,
+        })
+    );
+}
+
+#[test]
+fn test_parse_ground_gravity_defaults_to_zero() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+ground y = -1.0 friction 0.3
+simulate dt = 0.01 steps = 10
+"#;
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let ground = ctx.world.ground().unwrap();
+    assert_eq!(ground.y, -1.0);
+    assert_eq!(ground.gravity, 0.0);
+}
+
+#[test]
+fn test_duplicate_ground_is_a_parse_error() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+ground y = 0.0 friction 0.3
+ground y = 1.0 friction 0.3
+simulate dt = 0.01 steps = 10
+"#;
+    assert!(parse_program(source).is_err());
+}
+
+#[test]
+fn test_negative_friction_rejected() {
+    let literal = r#"
+particle a at (0.0, 0.0) mass 1.0
+ground y = 0.0 friction -0.3
+simulate dt = 0.01 steps = 10
+"#;
+    let diagnostics = analyze_program(&parse_program(literal).unwrap());
+    assert!(diagnostics
+        .errors()
+        .any(|e| e.message.contains("negative friction")));
+
+    let computed = r#"
+let mu = 0.0 - 0.3
+particle a at (0.0, 0.0) mass 1.0
+ground y = 0.0 friction mu
+simulate dt = 0.01 steps = 10
+"#;
+    match build_simulation_context_from_source(computed) {
+        Ok(_) => panic!("negative computed friction should be rejected"),
+        Err(err) => assert!(err.to_string().contains("ground has negative friction -0.3")),
+    }
+
+    // The builder rejects it too, for programs that skip analysis
+    let program = parse_program(computed).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    match build_simulation_context(&program, &eval_ctx) {
+        Ok(_) => panic!("negative computed friction should be rejected"),
+        Err(err) => assert!(err.to_string().contains("ground friction must be >= 0")),
+    }
+}
+
+#[test]
+fn test_pushed_block_decelerates_linearly_and_stops() {
+    let (mut ctx, _) = build_simulation_context_from_source(SLIDING_BLOCK).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(5.0, 0.0));
+
+    // v(n) = 5 − 5·n·dt until it reaches zero after one second
+    for n in 1..=120 {
+        step_simulation(&mut ctx);
+        let block = &ctx.world.particles[0];
+        let expected = (5.0 - 5.0 * 0.01 * n as f32).max(0.0);
+        assert!(
+            approx_eq_f32(block.vel.x, expected, 1e-3),
+            "step {}: vx = {}, expected {}",
+            n,
+            block.vel.x,
+            expected
+        );
+        assert!(
+            block.vel.x >= 0.0,
+            "friction reversed the block at step {}",
+            n
+        );
+        assert_eq!(block.pos.y, 0.0);
+        assert!(ctx.world.in_ground_contact(0));
+    }
+
+    // Stopping distance v²/2a = 2.5
+    let stopped_at = ctx.world.particles[0].pos;
+    assert!(
+        approx_eq_f32(stopped_at.x, 2.5, 0.05),
+        "stopped at {}",
+        stopped_at.x
+    );
+
+    // It stays stopped
+    for _ in 0..500 {
+        step_simulation(&mut ctx);
+    }
+    let block = &ctx.world.particles[0];
+    assert_eq!(block.vel.x, 0.0);
+    assert_eq!(block.pos, stopped_at);
+}
+
+#[test]
+fn test_friction_needs_contact() {
+    let source = r#"
+particle block at (0.0, 5.0) mass 1.0
+ground y = 0.0 friction 0.5 gravity 10.0
+simulate dt = 0.01 steps = 1000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(3.0, 0.0));
+
+    // Airborne: horizontal speed is untouched while the block falls
+    for _ in 0..50 {
+        step_simulation(&mut ctx);
+        assert!(!ctx.world.in_ground_contact(0));
+        assert_eq!(ctx.world.particles[0].vel.x, 3.0);
+    }
+
+    // After landing it rests on the ground and friction starts to act
+    for _ in 0..100 {
+        step_simulation(&mut ctx);
+    }
+    let block = &ctx.world.particles[0];
+    assert!(ctx.world.in_ground_contact(0));
+    assert_eq!(block.pos.y, 0.0);
+    assert!(block.vel.x < 3.0);
+}
+
+#[test]
+fn test_no_downward_pull_means_no_friction() {
+    let source = r#"
+particle block at (0.0, 0.0) mass 1.0
+ground y = 0.0 friction 0.5
+simulate dt = 0.01 steps = 1000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(2.0, 0.0));
+
+    for _ in 0..100 {
+        step_simulation(&mut ctx);
+    }
+    assert_eq!(ctx.world.particles[0].vel.x, 2.0);
+}
+
+#[test]
+fn test_friction_never_adds_energy() {
+    let (mut ctx, _) = build_simulation_context_from_source(SLIDING_BLOCK).unwrap();
+    ctx.apply_external_impulse(0, Vec2::new(5.0, 0.0));
+    step_simulation(&mut ctx);
+
+    let mut previous = ctx.world.total_energy();
+    for _ in 0..200 {
+        step_simulation(&mut ctx);
+        let energy = ctx.world.total_energy();
+        assert!(
+            energy <= previous + 1e-5,
+            "energy rose from {} to {}",
+            previous,
+            energy
+        );
+        previous = energy;
+    }
+}
@@ -0,0 +1,153 @@
+//! Tests for composing worlds with World::merge
+
+use glam::Vec2;
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, step_with, CustomForce, Integrator,
+    MergeError, Particle, World,
+};
+
+const OSCILLATOR: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 500
+"#;
+
+const STIFF_OSCILLATOR: &str = r#"
+particle a at (0.0, 5.0) mass 2.0
+particle b at (0.0, 9.0) mass 1.0
+force spring(a, b) k = 40.0 rest = 3.0
+simulate dt = 0.01 steps = 500
+"#;
+
+fn world_from(source: &str) -> World {
+    build_simulation_context_from_source(source)
+        .unwrap()
+        .0
+        .world
+}
+
+#[test]
+fn test_merged_oscillators_move_independently() {
+    let mut merged = World::new();
+    let left = merged.merge(world_from(OSCILLATOR), "left.").unwrap();
+    let right = merged
+        .merge(world_from(STIFF_OSCILLATOR), "right.")
+        .unwrap();
+    assert_eq!(left, vec![0, 1]);
+    assert_eq!(right, vec![2, 3]);
+
+    let names: Vec<_> = merged.particles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["left.a", "left.b", "right.a", "right.b"]);
+    assert_eq!(merged.force_info()[1].a, "right.a");
+
+    let mut alone_left = world_from(OSCILLATOR);
+    let mut alone_right = world_from(STIFF_OSCILLATOR);
+    for _ in 0..500 {
+        step_with(&mut merged, 0.01, Integrator::default());
+        step_with(&mut alone_left, 0.01, Integrator::default());
+        step_with(&mut alone_right, 0.01, Integrator::default());
+    }
+
+    let expected = alone_left.particles.iter().chain(&alone_right.particles);
+    for (merged, alone) in merged.particles.iter().zip(expected) {
+        assert_eq!(merged.pos, alone.pos, "{}", merged.name);
+        assert_eq!(merged.vel, alone.vel, "{}", merged.name);
+    }
+}
+
+#[test]
+fn test_name_clash_is_an_error_and_leaves_world_unchanged() {
+    let mut merged = world_from(OSCILLATOR);
+    let err = merged.merge(world_from(OSCILLATOR), "").unwrap_err();
+    assert_eq!(err, MergeError::DuplicateParticle("a".to_string()));
+    assert_eq!(merged.particles.len(), 2);
+    assert_eq!(merged.forces.len(), 1);
+
+    // Distinct prefixes that still produce the same name also clash
+    let mut merged = World::new();
+    merged.merge(world_from(OSCILLATOR), "x").unwrap();
+    let mut other = World::new();
+    other.particles.push(Particle {
+        name: "a".to_string(),
+        pos: Vec2::ZERO,
+        vel: Vec2::ZERO,
+        mass: 1.0,
+        ..Default::default()
+    });
+    assert!(merged.merge(other, "x").is_err());
+}
+
+#[test]
+fn test_force_flags_carried_over() {
+    let mut sub = world_from(STIFF_OSCILLATOR);
+    sub.set_force_enabled(0, false);
+    let mut merged = world_from(OSCILLATOR);
+    merged.merge(sub, "sub_").unwrap();
+    assert!(merged.is_force_enabled(0));
+    assert!(!merged.is_force_enabled(1));
+}
+
+/// Constant push on the first particle of whatever slice it is given
+struct PushFirst(Vec2);
+
+impl CustomForce for PushFirst {
+    fn accumulate(&self, _particles: &[Particle], out: &mut [Vec2]) {
+        out[0] += self.0;
+    }
+}
+
+#[test]
+fn test_custom_forces_follow_their_particles() {
+    let mut sub = World::new();
+    sub.particles.push(Particle {
+        name: "p".to_string(),
+        pos: Vec2::ZERO,
+        vel: Vec2::ZERO,
+        mass: 1.0,
+        ..Default::default()
+    });
+    sub.add_custom_force(Box::new(PushFirst(Vec2::new(0.0, 1.0))));
+
+    let mut merged = world_from(OSCILLATOR);
+    merged.merge(sub, "sub.").unwrap();
+    let accelerations = merged.compute_accelerations();
+    assert_eq!(accelerations[2], Vec2::new(0.0, 1.0));
+    assert_eq!(merged.custom_force_count(), 1);
+}
+
+#[test]
+fn test_rebased_loops_and_wells_drive_merged_particles() {
+    let pushed = r#"
+particle p at (6.0, 0.0) mass 1.0
+well target on p if position(p).x >= 5.0 depth 10.0
+simulate dt = 0.01 steps = 300
+loop for 3 cycles with frequency 2.0 damping 0.0 on p {
+    force push(p) magnitude 0.5 direction (0.0, 1.0)
+}
+"#;
+    let (mut reference, _) = build_simulation_context_from_source(pushed).unwrap();
+    let (mut host, _) = build_simulation_context_from_source(OSCILLATOR).unwrap();
+    let (sub, _) = build_simulation_context_from_source(pushed).unwrap();
+
+    let mapping = host.world.merge(sub.world, "sub.").unwrap();
+    let offset = mapping[0];
+    for mut loop_inst in sub.loops {
+        loop_inst.rebase(offset);
+        host.add_loop(loop_inst);
+    }
+    for mut well in sub.wells {
+        well.rebase(offset);
+        host.add_well(well);
+    }
+
+    for _ in 0..300 {
+        step_simulation(&mut host);
+        step_simulation(&mut reference);
+    }
+    assert_eq!(
+        host.world.particles[offset].pos,
+        reference.world.particles[0].pos
+    );
+    assert_ne!(reference.world.particles[0].pos, Vec2::new(6.0, 0.0));
+}
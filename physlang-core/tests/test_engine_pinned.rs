@@ -0,0 +1,129 @@
+//! Tests for pinned (immovable) particles
+
+use glam::Vec2;
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, Integrator, WorldState,
+};
+
+#[test]
+fn test_pinned_sun_stays_put_while_planet_orbits() {
+    let source = r#"
+particle sun at (0.0, 0.0) mass 100.0
+particle planet at (10.0, 0.0) mass 1.0
+force gravity(sun, planet) G = 1.0
+simulate dt = 0.01 steps = 2000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    assert!(ctx.world.pin(0));
+    // Circular speed around a fixed mass: v = sqrt(G·M / r)
+    ctx.world.particles[1].vel = Vec2::new(0.0, (100.0f32 / 10.0).sqrt());
+
+    let mut min_y = 0.0f32;
+    while !step_simulation(&mut ctx) {
+        let planet = &ctx.world.particles[1];
+        assert!((planet.pos.length() - 10.0).abs() < 0.5, "{:?}", planet.pos);
+        min_y = min_y.min(planet.pos.y);
+    }
+
+    let sun = &ctx.world.particles[0];
+    assert_eq!(sun.pos, Vec2::ZERO);
+    assert_eq!(sun.vel, Vec2::ZERO);
+    // The planet made it at least half way round, so the sun's pull acted on it
+    assert!(min_y < -9.0, "{}", min_y);
+}
+
+const SPRING: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 1000
+"#;
+
+#[test]
+fn test_pinning_mid_run_freezes_exactly() {
+    for method in Integrator::ALL {
+        let (mut ctx, _) = build_simulation_context_from_source(SPRING).unwrap();
+        ctx.integrator = method;
+        for _ in 0..37 {
+            step_simulation(&mut ctx);
+        }
+        let frozen = ctx.world.particles[0].pos;
+        assert_ne!(frozen, Vec2::ZERO);
+
+        ctx.world.pin(0);
+        let b_before = ctx.world.particles[1].pos;
+        for _ in 0..100 {
+            step_simulation(&mut ctx);
+        }
+        assert_eq!(ctx.world.particles[0].pos, frozen, "{:?}", method);
+        assert_eq!(ctx.world.particles[0].vel, Vec2::ZERO, "{:?}", method);
+        assert_ne!(ctx.world.particles[1].pos, b_before, "{:?}", method);
+    }
+}
+
+#[test]
+fn test_unpin_resumes_motion() {
+    let (mut ctx, _) = build_simulation_context_from_source(SPRING).unwrap();
+    ctx.world.pin(0);
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[0].pos, Vec2::ZERO);
+
+    assert!(ctx.world.unpin(0));
+    step_simulation(&mut ctx);
+    assert!(ctx.world.particles[0].pos.x > 0.0);
+}
+
+#[test]
+fn test_loop_pushes_and_wells_skip_pinned() {
+    let source = r#"
+particle a at (6.0, 0.0) mass 1.0
+well target on a if position(a).x >= 5.0 depth 10.0
+simulate dt = 0.01 steps = 500
+loop for 3 cycles with frequency 5.0 damping 0.0 on a {
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.world.pin(0);
+    while !step_simulation(&mut ctx) {}
+    assert_eq!(ctx.world.particles[0].pos, Vec2::new(6.0, 0.0));
+    assert_eq!(ctx.world.particles[0].vel, Vec2::ZERO);
+}
+
+#[test]
+fn test_external_forces_skip_pinned() {
+    let (mut ctx, _) = build_simulation_context_from_source(SPRING).unwrap();
+    ctx.world.pin(1);
+    ctx.apply_external_force(1, Vec2::new(100.0, 0.0));
+    ctx.apply_external_impulse(1, Vec2::new(0.0, 100.0));
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[1].pos, Vec2::new(3.0, 0.0));
+}
+
+#[test]
+fn test_pin_state_round_trips_and_hashes() {
+    let (mut ctx, _) = build_simulation_context_from_source(SPRING).unwrap();
+    let unpinned_hash = ctx.world.state_hash();
+    ctx.world.pin(0);
+    assert_ne!(ctx.world.state_hash(), unpinned_hash);
+
+    let state = ctx.export_state();
+    assert!(state.particles[0].pinned);
+    let (mut other, _) = build_simulation_context_from_source(SPRING).unwrap();
+    other.import_state(&state).unwrap();
+    assert!(other.world.particles[0].pinned);
+
+    // Older snapshots without the field load as unpinned
+    let json = r#"{"particles":[
+        {"name":"a","pos":[0.0,0.0],"vel":[0.0,0.0],"mass":1.0},
+        {"name":"b","pos":[3.0,0.0],"vel":[0.0,0.0],"mass":1.0}]}"#;
+    let legacy: WorldState = serde_json::from_str(json).unwrap();
+    assert!(!legacy.particles[0].pinned);
+}
+
+#[test]
+fn test_pin_out_of_range() {
+    let (mut ctx, _) = build_simulation_context_from_source(SPRING).unwrap();
+    assert!(!ctx.world.pin(2));
+    assert!(!ctx.world.unpin(2));
+}
@@ -0,0 +1,89 @@
+//! Tests for `coerce_to_count`, which turns computed `steps` and loop
+//! `cycles` into whole numbers
+
+use physlang_core::{coerce_to_count, run_program};
+
+/// `0.09 * 300` is `27.000002` in f32
+const NEAR_INTEGER_STEPS: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 0.09 * 300
+detect t = time()
+";
+
+fn run_error(source: &str) -> String {
+    run_program(source).err().unwrap().to_string()
+}
+
+#[test]
+fn test_near_integers_are_rounded() {
+    assert_eq!(coerce_to_count(27.000002, "steps"), Ok(27));
+    assert_eq!(coerce_to_count(35.99999, "steps"), Ok(36));
+    assert_eq!(coerce_to_count(0.0, "cycles"), Ok(0));
+    assert_eq!(coerce_to_count(1e7, "steps"), Ok(10_000_000));
+}
+
+#[test]
+fn test_fractions_negatives_and_non_finite_values_are_rejected() {
+    for value in [2.5, 3.001, -1.0, f32::NAN, f32::INFINITY] {
+        assert_eq!(
+            coerce_to_count(value, "steps"),
+            Err(format!("steps must be an integer >= 0, got {}", value))
+        );
+    }
+}
+
+#[test]
+fn test_huge_values_are_rejected() {
+    assert_eq!(
+        coerce_to_count(1e30, "cycles"),
+        Err("cycles is too large, got 1000000000000000000000000000000".to_string())
+    );
+}
+
+#[test]
+fn test_computed_steps_run() {
+    let result = run_program(NEAR_INTEGER_STEPS).unwrap();
+    assert_eq!(result.steps, 27);
+}
+
+#[test]
+fn test_invalid_steps_are_reported() {
+    let negative = NEAR_INTEGER_STEPS.replace("0.09 * 300", "0 - 5");
+    assert!(
+        run_error(&negative).contains("steps must be an integer >= 0, got -5"),
+        "{}",
+        run_error(&negative)
+    );
+    let zero = NEAR_INTEGER_STEPS.replace("0.09 * 300", "0");
+    assert!(
+        run_error(&zero).contains("steps must be an integer >= 1, got 0"),
+        "{}",
+        run_error(&zero)
+    );
+}
+
+#[test]
+fn test_computed_cycles_run_and_invalid_cycles_are_reported() {
+    let source = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+loop for 0.09 * 300 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+detect t = time()
+";
+    assert!(run_program(source).is_ok());
+
+    let fractional = source.replace("0.09 * 300", "2.5");
+    assert!(
+        run_error(&fractional).contains("cycles must be an integer >= 0, got 2.5"),
+        "{}",
+        run_error(&fractional)
+    );
+    let huge = source.replace("0.09 * 300", "1e10");
+    assert!(
+        run_error(&huge).contains("cycles is too large, got 10000000000"),
+        "{}",
+        run_error(&huge)
+    );
+}
@@ -0,0 +1,149 @@
+//! Tests for constant folding of expressions, and for folding whole programs
+//! before they are built
+
+use physlang_core::ast::{Expr, Program};
+use physlang_core::{
+    analyze_program, build_run_context, check_program, expand_functions, fold_constants,
+    fold_program_constants, parse_expression, parse_program, run_simulation, try_const_fold,
+    RunOptions,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn fold(source: &str, lets: &[(&str, f32)]) -> Option<f32> {
+    let lets: HashMap<&str, f32> = lets.iter().copied().collect();
+    try_const_fold(&parse_expression(source).unwrap(), &lets)
+}
+
+#[test]
+fn test_constants_fold_to_their_value() {
+    assert_eq!(fold("2.0 * 3.0 + sqrt(16.0)", &[]), Some(10.0));
+    assert_eq!(fold("k * 4.0 - 1.0", &[("k", 2.5)]), Some(9.0));
+    assert_eq!(fold("-k", &[("k", 2.5)]), Some(-2.5));
+    assert_eq!(fold("clamp(7.0, 0.0, 5.0)", &[]), Some(5.0));
+    assert_eq!(fold("cos(0.0)", &[]), Some(1.0));
+    assert_eq!(fold("3.0 > 2.0", &[]), Some(1.0));
+}
+
+#[test]
+fn test_non_constants_do_not_fold() {
+    // Unbound variables, such as the simulation time
+    assert_eq!(fold("time * 2.0", &[]), None);
+    assert_eq!(fold("k + x", &[("k", 1.0)]), None);
+    // User-defined functions
+    assert_eq!(fold("spacing(2.0)", &[]), None);
+    // Errors are left for the evaluator to report
+    assert_eq!(fold("1.0 / 0.0", &[]), None);
+    assert_eq!(fold("sqrt(0.0 - 4.0)", &[]), None);
+}
+
+#[test]
+fn test_folding_keeps_what_cannot_be_folded() {
+    let lets = HashMap::from([("k", 2.0)]);
+    let folded = fold_constants(&parse_expression("k * 3.0 * time").unwrap(), &lets);
+    assert_eq!(folded, parse_expression("6.0 * time").unwrap());
+
+    let folded = fold_constants(&parse_expression("spacing(k + 1.0)").unwrap(), &lets);
+    assert_eq!(folded, parse_expression("spacing(3.0)").unwrap());
+}
+
+#[test]
+fn test_program_declarations_fold_to_literals() {
+    let source = "\
+let spacing = 1.5
+particle a at (0.0, 0.0) mass 1.0
+particle b at (spacing * 2.0, 0.0) mass spacing + 0.5
+force spring(a, b) k = spacing * 4.0 + time rest = spacing
+simulate dt = 0.1 / 10.0 steps = 10 * 10
+detect gap = distance(a, b)
+";
+    let mut program = parse_program(source).unwrap();
+    fold_program_constants(&mut program);
+    let b = &program.particles[1];
+    assert_eq!(b.position.0, Expr::Literal(3.0));
+    assert_eq!(b.mass, Expr::Literal(2.0));
+    assert_eq!(program.simulate.steps, Expr::Literal(100.0));
+    match &program.forces[0].kind {
+        physlang_core::ast::ForceKind::Spring { k, rest } => {
+            assert_eq!(k, &parse_expression("6.0 + time").unwrap());
+            assert_eq!(rest, &Expr::Literal(1.5));
+        }
+        other => panic!("expected a spring, got {:?}", other),
+    }
+    // Lets are left as written
+    assert_eq!(program.lets[0].expr, Expr::Literal(1.5));
+}
+
+#[test]
+fn test_analysis_checks_constant_expressions() {
+    let source = "\
+let d = 0.5
+particle a at (0.0, 0.0) mass 1.0 damping 0.0 - d
+particle b at (1.0, 0.0) mass 0.0 - 2.0
+simulate dt = 0.01 - 0.01 steps = 10
+";
+    let program = parse_program(source).unwrap();
+    let errors: Vec<String> = analyze_program(&program)
+        .errors()
+        .map(|d| d.message.clone())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "particle 'a' has negative damping -0.5",
+            "particle 'b' has negative mass -2",
+            "simulate dt must be positive, got 0",
+        ]
+    );
+}
+
+/// Every `.phys` file under `dir`, recursively
+fn fixtures(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path.extension().is_some_and(|e| e == "phys") {
+            found.push(path);
+        }
+    }
+}
+
+/// The final state hash and detector values as bits, or the error message
+fn run(mut program: Program, fold: bool) -> Result<(u64, Vec<u32>), String> {
+    let options = RunOptions::default();
+    check_program(&program).map_err(|e| e.to_string())?;
+    expand_functions(&mut program).map_err(|e| e.to_string())?;
+    if fold {
+        fold_program_constants(&mut program);
+    }
+    let ctx = build_run_context(&program, &options).map_err(|e| e.to_string())?;
+    let result = run_simulation(ctx, &program, &options, |_| Ok(())).map_err(|e| e.to_string())?;
+    let values = result.detectors.iter().map(|d| d.value.to_bits()).collect();
+    Ok((result.state_hash, values))
+}
+
+#[test]
+fn test_folding_never_changes_simulation_output() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut paths = Vec::new();
+    for dir in ["examples", "physlang-cli/tests/fixtures"] {
+        fixtures(&root.join(dir), &mut paths);
+    }
+
+    let mut compared = 0;
+    for path in &paths {
+        let source = std::fs::read_to_string(path).unwrap();
+        let Ok(program) = parse_program(&source) else {
+            continue;
+        };
+        assert_eq!(
+            run(program.clone(), true),
+            run(program, false),
+            "{}",
+            path.display()
+        );
+        compared += 1;
+    }
+    assert!(compared > 20, "only {} programs parsed", compared);
+}
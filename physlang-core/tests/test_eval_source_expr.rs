@@ -0,0 +1,57 @@
+//! Tests for evaluating standalone expressions against a program's lets
+
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{eval_source_expr, parse_program, EvalError, ExprError};
+
+const PROGRAM: &str = r#"
+let spring_k = 4.0
+let half_k = spring_k / 2.0
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+"#;
+
+#[test]
+fn test_arithmetic_against_lets() {
+    let program = parse_program(PROGRAM).unwrap();
+    let value = eval_source_expr("sqrt(2) * spring_k", &program.lets).unwrap();
+    assert!(approx_eq_f32(value, 2.0_f32.sqrt() * 4.0, 1e-6));
+
+    let value = eval_source_expr("half_k + 1", &program.lets).unwrap();
+    assert!(approx_eq_f32(value, 3.0, 1e-6));
+}
+
+#[test]
+fn test_no_lets() {
+    let value = eval_source_expr("1 + 2 * 3", &[]).unwrap();
+    assert!(approx_eq_f32(value, 7.0, 1e-6));
+    let value = eval_source_expr("-3 * 3", &[]).unwrap();
+    assert!(approx_eq_f32(value, -9.0, 1e-6));
+}
+
+#[test]
+fn test_unknown_variable() {
+    let program = parse_program(PROGRAM).unwrap();
+    let err = eval_source_expr("spring_q * 2", &program.lets).unwrap_err();
+    assert!(matches!(err, ExprError::Eval(EvalError::UnknownVar(ref name)) if name == "spring_q"));
+    assert!(err.to_string().contains("spring_q"));
+}
+
+#[test]
+fn test_parse_error() {
+    let err = eval_source_expr("(1 + 2", &[]).unwrap_err();
+    assert!(matches!(err, ExprError::Parse(_)), "got {:?}", err);
+}
+
+#[test]
+fn test_failing_let_is_reported() {
+    let program = parse_program(
+        r#"
+let bad = 1.0 / 0.0
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+"#,
+    )
+    .unwrap();
+    let err = eval_source_expr("1 + 1", &program.lets).unwrap_err();
+    assert!(matches!(err, ExprError::Let(_)), "got {:?}", err);
+}
@@ -0,0 +1,44 @@
+//! Runs every program in examples/gallery, so the showcase keeps compiling
+//! as the language changes
+
+use physlang_core::run_program;
+use std::path::Path;
+
+#[test]
+fn test_gallery_programs_run() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/gallery");
+    let mut paths: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "phys"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no examples in {}", dir.display());
+
+    for path in paths {
+        let source = std::fs::read_to_string(&path).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(
+            source.starts_with("# "),
+            "{} should open with a description comment",
+            name
+        );
+        let result = run_program(&source).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(!result.detectors.is_empty(), "{} has no detectors", name);
+        for detector in &result.detectors {
+            assert!(
+                detector.value.is_finite(),
+                "{}: {} = {}",
+                name,
+                detector.name,
+                detector.value
+            );
+        }
+        assert!(
+            result.warnings.is_empty(),
+            "{}: {:?}",
+            name,
+            result.warnings
+        );
+    }
+}
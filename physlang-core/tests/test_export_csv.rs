@@ -0,0 +1,94 @@
+//! Tests for CSV trajectory export
+
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    build_simulation_context_from_source, run_program_to_csv, step_simulation,
+    write_detector_series_csv, CsvTraceWriter,
+};
+use glam::Vec2;
+
+const SOURCE: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+particle drifter at (5.0, 1.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+"#;
+
+#[test]
+fn test_run_program_to_csv_header_and_rows() {
+    let mut buffer = Vec::new();
+    let result = run_program_to_csv(SOURCE, &mut buffer, 10).unwrap();
+    assert_eq!(result.detectors.len(), 1);
+
+    let csv = String::from_utf8(buffer).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "step,time,a.x,a.y,a.vx,a.vy,b.x,b.y,b.vx,b.vy,drifter.x,drifter.y,drifter.vx,drifter.vy"
+    );
+    // Header + step 0 + steps 10, 20, ..., 100
+    assert_eq!(lines.len(), 1 + 11);
+    assert!(lines[1].starts_with("0,0,"));
+    assert!(lines[11].starts_with("100,"));
+}
+
+#[test]
+fn test_constant_velocity_particle_spot_check() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    ctx.world.particles[2].vel = Vec2::new(2.0, 0.0);
+
+    let mut csv = CsvTraceWriter::new(Vec::new());
+    csv.write_context(&ctx).unwrap();
+    for _ in 0..50 {
+        step_simulation(&mut ctx);
+        csv.write_context(&ctx).unwrap();
+    }
+    let csv = String::from_utf8(csv.into_inner()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 1 + 51);
+
+    // drifter.x is column 10; after 50 steps at 2.0/s with dt 0.01 it has moved 1.0
+    let row: Vec<&str> = lines[51].split(',').collect();
+    assert_eq!(row[0], "50");
+    let time: f32 = row[1].parse().unwrap();
+    let x: f32 = row[10].parse().unwrap();
+    let vx: f32 = row[12].parse().unwrap();
+    assert!(approx_eq_f32(time, 0.5, 1e-6));
+    assert!(approx_eq_f32(x, 6.0, 1e-4));
+    assert_eq!(vx, 2.0);
+}
+
+#[test]
+fn test_particle_names_are_escaped() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    ctx.world.particles[0].name = "left,end".to_string();
+    ctx.world.particles[1].name = "say \"hi\"".to_string();
+
+    let mut csv = CsvTraceWriter::new(Vec::new());
+    csv.write_header(&ctx.world).unwrap();
+    let csv = String::from_utf8(csv.into_inner()).unwrap();
+
+    assert!(csv.starts_with(
+        "step,time,\"left,end.x\",\"left,end.y\",\"left,end.vx\",\"left,end.vy\",\"say \"\"hi\"\".x\","
+    ));
+}
+
+#[test]
+fn test_sample_every_zero_samples_every_step() {
+    let mut buffer = Vec::new();
+    run_program_to_csv(SOURCE, &mut buffer, 0).unwrap();
+    let csv = String::from_utf8(buffer).unwrap();
+    assert_eq!(csv.lines().count(), 1 + 101);
+}
+
+#[test]
+fn test_detector_series_csv() {
+    let names = vec!["gap".to_string(), "a,x".to_string()];
+    let rows = vec![(0, vec![3.0, 0.0]), (10, vec![2.5, 0.25])];
+    let mut buffer = Vec::new();
+    write_detector_series_csv(&mut buffer, &names, &rows).unwrap();
+    let csv = String::from_utf8(buffer).unwrap();
+    assert_eq!(csv, "step,gap,\"a,x\"\n0,3,0\n10,2.5,0.25\n");
+}
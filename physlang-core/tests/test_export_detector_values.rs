@@ -0,0 +1,53 @@
+//! Tests for the text form of detector values
+
+use physlang_core::format_detector_value;
+
+#[test]
+fn test_six_significant_digits_by_default() {
+    assert_eq!(format_detector_value(0.1 + 0.2, None), "0.3");
+    assert_eq!(format_detector_value(1.0 / 3.0, None), "0.333333");
+    assert_eq!(format_detector_value(2.0 / 3.0, None), "0.666667");
+    assert_eq!(format_detector_value(123.456_79, None), "123.457");
+    assert_eq!(format_detector_value(-42.5, None), "-42.5");
+    assert_eq!(format_detector_value(2.0, None), "2");
+}
+
+#[test]
+fn test_no_exponents_for_large_or_small_values() {
+    assert_eq!(format_detector_value(1e-7, None), "0.0000001");
+    assert_eq!(format_detector_value(-1.234_567e-5, None), "-0.0000123457");
+    assert_eq!(format_detector_value(1_234_567.0, None), "1234570");
+    assert_eq!(format_detector_value(1e10, None), "10000000000");
+}
+
+#[test]
+fn test_rounding_carries_into_the_next_digit() {
+    assert_eq!(format_detector_value(9.999_999, None), "10");
+    assert_eq!(format_detector_value(0.099_999_99, None), "0.1");
+}
+
+#[test]
+fn test_negative_zero_shows_as_zero() {
+    assert_eq!(format_detector_value(-0.0, None), "0.0");
+    assert_eq!(format_detector_value(0.0, None), "0.0");
+    assert_eq!(format_detector_value(-0.0, Some(2)), "0.00");
+    // Rounding a tiny negative value to zero drops its sign too
+    assert_eq!(format_detector_value(-0.001, Some(2)), "0.00");
+    assert_eq!(format_detector_value(-0.01, Some(2)), "-0.01");
+}
+
+#[test]
+fn test_non_finite_values() {
+    for precision in [None, Some(3)] {
+        assert_eq!(format_detector_value(f32::NAN, precision), "NaN");
+        assert_eq!(format_detector_value(f32::INFINITY, precision), "inf");
+        assert_eq!(format_detector_value(f32::NEG_INFINITY, precision), "-inf");
+    }
+}
+
+#[test]
+fn test_precision_fixes_decimal_places() {
+    assert_eq!(format_detector_value(1.23456, Some(2)), "1.23");
+    assert_eq!(format_detector_value(2.0, Some(0)), "2");
+    assert_eq!(format_detector_value(3.0, Some(2)), "3.00");
+}
@@ -0,0 +1,138 @@
+//! Tests for the central force toward a fixed point mass
+
+use glam::Vec2;
+use physlang_core::ast::{Expr, ForceKind};
+use physlang_core::engine::{Force, Particle, World};
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, parse_program, step_simulation,
+};
+
+fn single_particle_world(pos: Vec2) -> World {
+    let mut world = World::new();
+    world.particles.push(Particle {
+        name: "a".to_string(),
+        pos,
+        vel: Vec2::ZERO,
+        mass: 2.0,
+        ..Default::default()
+    });
+    world
+}
+
+#[test]
+fn test_parse_central_force() {
+    let source = r#"
+particle a at (10.0, 0.0) mass 1.0
+force central(a) at (1.0, -2.0) GM = 100.0
+simulate dt = 0.01 steps = 100
+"#;
+    let program = parse_program(source).unwrap();
+    assert_eq!(program.forces.len(), 1);
+    let force = &program.forces[0];
+    assert_eq!(force.a, "a");
+    assert_eq!(force.b, "a");
+    match &force.kind {
+        ForceKind::Central { point, gm } => {
+            assert_eq!(point.0, Expr::Literal(1.0));
+            assert!(matches!(point.1, Expr::Literal(_) | Expr::UnaryMinus(_)));
+            assert_eq!(*gm, Expr::Literal(100.0));
+        }
+        other => panic!("expected central force, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_central_force_rejects_two_particles() {
+    let source = r#"
+particle a at (10.0, 0.0) mass 1.0
+particle b at (0.0, 0.0) mass 1.0
+force central(a, b) at (0.0, 0.0) GM = 100.0
+simulate dt = 0.01 steps = 100
+"#;
+    assert!(parse_program(source).is_err());
+}
+
+#[test]
+fn test_central_force_unknown_particle_reported_once() {
+    let source = r#"
+particle a at (10.0, 0.0) mass 1.0
+force central(ghost) at (0.0, 0.0) GM = 100.0
+simulate dt = 0.01 steps = 100
+"#;
+    let program = parse_program(source).unwrap();
+    let diagnostics = analyze_program(&program);
+    let errors: Vec<_> = diagnostics
+        .errors()
+        .filter(|e| e.message.contains("unknown particle 'ghost'"))
+        .collect();
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_central_force_acceleration() {
+    let mut world = single_particle_world(Vec2::new(3.0, 4.0));
+    world.forces.push(Force::Central {
+        target: 0,
+        point: Vec2::ZERO,
+        gm: 50.0,
+    });
+
+    // |a| = GM / r² = 50 / 25 = 2, independent of the particle's own mass
+    let accel = world.compute_acceleration(0);
+    let expected = Vec2::new(-0.6, -0.8) * 2.0;
+    assert!(approx_eq_f32(accel.x, expected.x, 1e-5));
+    assert!(approx_eq_f32(accel.y, expected.y, 1e-5));
+
+    // Potential energy is -GM·m/r
+    assert!(approx_eq_f32(world.potential_energy(), -50.0 * 2.0 / 5.0, 1e-5));
+}
+
+#[test]
+fn test_central_force_skipped_at_point() {
+    let mut world = single_particle_world(Vec2::new(1.0, 1.0));
+    world.forces.push(Force::Central {
+        target: 0,
+        point: Vec2::new(1.0, 1.0),
+        gm: 50.0,
+    });
+
+    assert_eq!(world.compute_acceleration(0), Vec2::ZERO);
+    assert_eq!(world.potential_energy(), 0.0);
+}
+
+#[test]
+fn test_circular_orbit_keeps_radius() {
+    // r = 10, GM = 100: v = sqrt(GM / r), period T = 2π·sqrt(r³ / GM) ≈ 19.87
+    let radius = 10.0f32;
+    let gm = 100.0f32;
+    let speed = (gm / radius).sqrt();
+    let period = 2.0 * std::f32::consts::PI * (radius.powi(3) / gm).sqrt();
+    let dt = 0.01f32;
+    let steps = (period / dt).ceil() as usize;
+
+    let source = format!(
+        r#"
+particle a at ({radius}, 0.0) mass 1.0
+force central(a) at (0.0, 0.0) GM = {gm}
+simulate dt = {dt} steps = {steps}
+"#
+    );
+    let (mut ctx, _) = build_simulation_context_from_source(&source).unwrap();
+    ctx.world.particles[0].vel = Vec2::new(0.0, speed);
+
+    for _ in 0..steps {
+        step_simulation(&mut ctx);
+        let r = ctx.world.particles[0].pos.length();
+        assert!(
+            (r - radius).abs() / radius < 0.01,
+            "radius drifted to {} at step {}",
+            r,
+            ctx.current_step
+        );
+    }
+
+    // After one period the particle is back near its starting point
+    let end = ctx.world.particles[0].pos;
+    assert!(end.distance(Vec2::new(radius, 0.0)) < 0.1 * radius);
+}
@@ -0,0 +1,134 @@
+//! Tests for host-supplied custom force laws
+
+use glam::Vec2;
+use physlang_core::tests::test_helpers::assert_energy_drift_below;
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, CustomForce, Integrator, Particle, World,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Hooke spring between two particles, written the same way as the built-in one
+struct CustomSpring {
+    a: usize,
+    b: usize,
+    k: f32,
+    rest: f32,
+}
+
+impl CustomForce for CustomSpring {
+    fn accumulate(&self, particles: &[Particle], out: &mut [Vec2]) {
+        // Each endpoint is evaluated from its own side, as the built-in spring does,
+        // so the floating-point results agree bit for bit
+        for (this, other) in [(self.a, self.b), (self.b, self.a)] {
+            let r = particles[other].pos - particles[this].pos;
+            let dist = r.length();
+            if dist > 0.0 {
+                out[this] += r.normalize() * (self.k * (dist - self.rest));
+            }
+        }
+    }
+
+    fn potential_energy(&self, particles: &[Particle]) -> f32 {
+        let displacement = particles[self.a].pos.distance(particles[self.b].pos) - self.rest;
+        0.5 * self.k * displacement * displacement
+    }
+}
+
+const WITH_SPRING: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.5) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 500
+"#;
+
+#[test]
+fn test_custom_spring_matches_builtin() {
+    let (mut builtin, _) = build_simulation_context_from_source(WITH_SPRING).unwrap();
+    let (mut custom, _) = build_simulation_context_from_source(
+        &WITH_SPRING.replace("force spring(a, b) k = 10.0 rest = 2.0\n", ""),
+    )
+    .unwrap();
+    custom.world.add_custom_force(Box::new(CustomSpring {
+        a: 0,
+        b: 1,
+        k: 10.0,
+        rest: 2.0,
+    }));
+
+    for _ in 0..500 {
+        step_simulation(&mut builtin);
+        step_simulation(&mut custom);
+    }
+    for (expected, actual) in builtin.world.particles.iter().zip(&custom.world.particles) {
+        assert_eq!(expected.pos, actual.pos);
+        assert_eq!(expected.vel, actual.vel);
+    }
+}
+
+struct CountingForce(Arc<AtomicUsize>);
+
+impl CustomForce for CountingForce {
+    fn accumulate(&self, _particles: &[Particle], _out: &mut [Vec2]) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_custom_force_called_once_per_step() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (mut ctx, _) = build_simulation_context_from_source(WITH_SPRING).unwrap();
+    ctx.world
+        .add_custom_force(Box::new(CountingForce(Arc::clone(&calls))));
+    assert_eq!(ctx.world.custom_force_count(), 1);
+
+    for _ in 0..10 {
+        step_simulation(&mut ctx);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn test_compute_accelerations_combines_builtin_and_custom() {
+    let (mut ctx, _) = build_simulation_context_from_source(WITH_SPRING).unwrap();
+    let builtin = ctx.world.compute_accelerations();
+    ctx.world.add_custom_force(Box::new(CustomSpring {
+        a: 0,
+        b: 1,
+        k: 10.0,
+        rest: 2.0,
+    }));
+    let combined = ctx.world.compute_accelerations();
+    for (i, accel) in combined.iter().enumerate() {
+        assert_eq!(builtin[i], ctx.world.compute_acceleration(i));
+        assert!((*accel - builtin[i] * 2.0).length() < 1e-5);
+    }
+}
+
+#[test]
+fn test_world_without_custom_forces() {
+    let world = World::new();
+    assert_eq!(world.custom_force_count(), 0);
+    assert!(world.compute_accelerations().is_empty());
+}
+
+#[test]
+fn test_custom_force_potential_energy_validates_with_drift_harness() {
+    let (mut ctx, _) = build_simulation_context_from_source(
+        &WITH_SPRING.replace("force spring(a, b) k = 10.0 rest = 2.0\n", ""),
+    )
+    .unwrap();
+    ctx.world.add_custom_force(Box::new(CustomSpring {
+        a: 0,
+        b: 1,
+        k: 10.0,
+        rest: 2.0,
+    }));
+    let (builtin, _) = build_simulation_context_from_source(WITH_SPRING).unwrap();
+    assert_eq!(
+        ctx.world.potential_energy(),
+        builtin.world.potential_energy()
+    );
+
+    assert_energy_drift_below((ctx.world, ctx.dt), Integrator::Verlet, 500, 1e-3);
+}
@@ -0,0 +1,69 @@
+//! Tests for host-injected external forces and impulses
+
+use glam::Vec2;
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{build_simulation_context_from_source, step_simulation};
+
+const FREE_PARTICLE: &str = r#"
+particle p at (0.0, 0.0) mass 2.0
+simulate dt = 0.01 steps = 1000
+"#;
+
+#[test]
+fn test_constant_external_force_matches_analytic_trajectory() {
+    let (mut ctx, _) = build_simulation_context_from_source(FREE_PARTICLE).unwrap();
+    let force = Vec2::new(4.0, -2.0);
+    let accel = force / 2.0;
+    let dt = 0.01;
+    let n = 100;
+
+    for _ in 0..n {
+        assert!(ctx.apply_external_force(0, force));
+        step_simulation(&mut ctx);
+    }
+
+    let p = &ctx.world.particles[0];
+    let t = n as f32 * dt;
+    assert!(approx_eq_f32(p.vel.x, accel.x * t, 1e-4));
+    assert!(approx_eq_f32(p.vel.y, accel.y * t, 1e-4));
+
+    // Semi-implicit Euler: x_n = a·dt²·n(n+1)/2, which approaches ½at² as dt → 0
+    let discrete = accel * dt * dt * (n * (n + 1)) as f32 / 2.0;
+    assert!(approx_eq_f32(p.pos.x, discrete.x, 1e-4));
+    assert!(approx_eq_f32(p.pos.y, discrete.y, 1e-4));
+    assert!(approx_eq_f32(p.pos.x, 0.5 * accel.x * t * t, accel.x.abs() * dt * t));
+}
+
+#[test]
+fn test_buffer_cleared_after_step() {
+    let (mut ctx, _) = build_simulation_context_from_source(FREE_PARTICLE).unwrap();
+    ctx.apply_external_force(0, Vec2::new(1.0, 0.0));
+    ctx.apply_external_force(0, Vec2::new(1.0, 0.0));
+    assert_eq!(ctx.pending_external_force(0), Vec2::new(2.0, 0.0));
+
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.pending_external_force(0), Vec2::ZERO);
+    let vel = ctx.world.particles[0].vel;
+
+    // No further acceleration once the buffer is consumed
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[0].vel, vel);
+}
+
+#[test]
+fn test_external_impulse() {
+    let (mut ctx, _) = build_simulation_context_from_source(FREE_PARTICLE).unwrap();
+    assert!(ctx.apply_external_impulse(0, Vec2::new(0.0, 3.0)));
+    assert_eq!(ctx.pending_external_impulse(0), Vec2::new(0.0, 3.0));
+
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[0].vel, Vec2::new(0.0, 3.0));
+    assert_eq!(ctx.pending_external_impulse(0), Vec2::ZERO);
+}
+
+#[test]
+fn test_unknown_particle_rejected() {
+    let (mut ctx, _) = build_simulation_context_from_source(FREE_PARTICLE).unwrap();
+    assert!(!ctx.apply_external_force(1, Vec2::ONE));
+    assert!(!ctx.apply_external_impulse(1, Vec2::ONE));
+}
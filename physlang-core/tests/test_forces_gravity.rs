@@ -11,12 +11,14 @@ fn create_test_world() -> World {
         pos: Vec2::new(0.0, 0.0),
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.particles.push(Particle {
         name: "b".to_string(),
         pos: Vec2::new(3.0, 4.0), // distance = 5.0
         vel: Vec2::ZERO,
         mass: 2.0,
+        ..Default::default()
     });
     world
 }
@@ -91,12 +93,14 @@ fn test_gravity_force_zero_distance() {
         pos: Vec2::new(0.0, 0.0),
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.particles.push(Particle {
         name: "b".to_string(),
         pos: Vec2::new(0.0, 0.0), // Same position
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.forces.push(Force::Gravity {
         a: 0,
@@ -13,6 +13,7 @@ fn test_push_force_applies_velocity_change() {
             pos: Vec2::new(0.0, 0.0),
             vel: Vec2::ZERO,
             mass: 1.0,
+            ..Default::default()
         }
     ];
     
@@ -50,6 +51,7 @@ fn test_push_force_diagonal() {
             pos: Vec2::new(0.0, 0.0),
             vel: Vec2::ZERO,
             mass: 1.0,
+            ..Default::default()
         }
     ];
     
@@ -88,6 +90,7 @@ fn test_push_force_accumulates() {
             pos: Vec2::new(0.0, 0.0),
             vel: Vec2::new(1.0, 0.0), // Initial velocity
             mass: 1.0,
+            ..Default::default()
         }
     ];
     
@@ -124,6 +127,7 @@ fn test_push_force_zero_direction() {
             pos: Vec2::new(0.0, 0.0),
             vel: Vec2::ZERO,
             mass: 1.0,
+            ..Default::default()
         }
     ];
     
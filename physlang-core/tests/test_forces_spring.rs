@@ -11,12 +11,14 @@ fn create_test_world() -> World {
         pos: Vec2::new(0.0, 0.0),
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.particles.push(Particle {
         name: "b".to_string(),
         pos: Vec2::new(5.0, 0.0), // distance = 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world
 }
@@ -124,12 +126,14 @@ fn test_spring_force_diagonal() {
         pos: Vec2::new(0.0, 0.0),
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.particles.push(Particle {
         name: "b".to_string(),
         pos: Vec2::new(3.0, 4.0), // distance = 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     });
     world.forces.push(Force::Spring {
         a: 0,
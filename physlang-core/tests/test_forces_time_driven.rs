@@ -0,0 +1,155 @@
+//! Tests for force parameters that read the simulation time (motor springs)
+
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, parse_program, step_simulation, Force,
+};
+
+/// A spring whose rest length oscillates at 2 rad/s around 2.0, pulling `b` from
+/// an anchor that is pinned by the tests
+const MOTOR_SPRING: &str = r#"
+particle anchor at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0 damping 1.0
+force spring(anchor, b) k = 50.0 rest = 2.0 + 0.5 * sin(time * 2.0)
+simulate dt = 0.001 steps = 30000
+"#;
+
+fn spring_rest(force: &Force) -> f32 {
+    match force {
+        Force::Spring { rest, .. } => *rest,
+        other => panic!("expected a spring, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_time_allowed_in_force_parameters_only() {
+    let diagnostics = analyze_program(&parse_program(MOTOR_SPRING).unwrap());
+    assert!(!diagnostics.has_errors());
+
+    let in_mass = r#"
+particle a at (0.0, 0.0) mass 1.0 + time
+simulate dt = 0.01 steps = 10
+"#;
+    let diagnostics = analyze_program(&parse_program(in_mass).unwrap());
+    assert!(diagnostics
+        .errors()
+        .any(|e| e.message.contains("unknown variable 'time'")));
+}
+
+#[test]
+fn test_only_time_dependent_forces_are_driven() {
+    let (ctx, _) = build_simulation_context_from_source(MOTOR_SPRING).unwrap();
+    assert_eq!(ctx.time_driven_force_count(), 1);
+
+    let static_spring = r#"
+let rest_length = 2.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0
+force spring(a, b) k = 50.0 rest = rest_length * sin(1.0)
+simulate dt = 0.01 steps = 10
+"#;
+    let (ctx, _) = build_simulation_context_from_source(static_spring).unwrap();
+    assert_eq!(ctx.time_driven_force_count(), 0);
+}
+
+#[test]
+fn test_let_named_time_shadows_simulation_time() {
+    let source = r#"
+let time = 0.25
+particle a at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0
+force spring(a, b) k = 50.0 rest = 2.0 + time
+simulate dt = 0.01 steps = 10
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    assert_eq!(ctx.time_driven_force_count(), 0);
+    for _ in 0..10 {
+        step_simulation(&mut ctx);
+    }
+    assert_eq!(spring_rest(&ctx.world.forces[0]), 2.25);
+}
+
+#[test]
+fn test_rest_length_follows_time() {
+    let (mut ctx, _) = build_simulation_context_from_source(MOTOR_SPRING).unwrap();
+
+    // Evaluated with time = 0 when the world is built
+    assert_eq!(spring_rest(&ctx.world.forces[0]), 2.0);
+
+    for _ in 0..500 {
+        let start = ctx.time;
+        step_simulation(&mut ctx);
+        let expected = 2.0 + 0.5 * (start * 2.0).sin();
+        assert_eq!(spring_rest(&ctx.world.forces[0]), expected);
+    }
+}
+
+#[test]
+fn test_motor_spring_drives_motion_at_driving_frequency() {
+    let (mut ctx, _) = build_simulation_context_from_source(MOTOR_SPRING).unwrap();
+    ctx.world.pin(0);
+
+    // Let the natural (ω = √50) transient decay before measuring
+    while ctx.time < 10.0 {
+        step_simulation(&mut ctx);
+    }
+
+    // Upward crossings of the mean extension, 2.0
+    let mut crossings = Vec::new();
+    let mut previous = ctx.world.particles[1].pos.x - 2.0;
+    while !step_simulation(&mut ctx) {
+        let current = ctx.world.particles[1].pos.x - 2.0;
+        if previous < 0.0 && current >= 0.0 {
+            crossings.push(ctx.time);
+        }
+        previous = current;
+    }
+
+    // 20 s at a 2 rad/s drive is about six periods of π
+    assert!(crossings.len() >= 5, "crossings: {:?}", crossings);
+    let periods: Vec<f32> = crossings.windows(2).map(|w| w[1] - w[0]).collect();
+    for period in &periods {
+        assert!(
+            (period - std::f32::consts::PI).abs() < 0.02 * std::f32::consts::PI,
+            "period {} differs from the driving period, all: {:?}",
+            period,
+            periods
+        );
+    }
+
+    // The response has a real amplitude, not just numerical noise
+    let amplitude = ctx.world.particles[1].pos.x - 2.0;
+    let mut peak = amplitude.abs();
+    for _ in 0..4000 {
+        ctx.max_steps += 1;
+        step_simulation(&mut ctx);
+        peak = peak.max((ctx.world.particles[1].pos.x - 2.0).abs());
+    }
+    assert!(peak > 0.4, "peak displacement {}", peak);
+}
+
+#[test]
+fn test_failed_reevaluation_keeps_parameters_and_warns_once() {
+    // k is 2.0 until t = 0.055, then divides by zero
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 / clamp(55000.0 - time * 1000000.0, 0.0, 1.0) rest = 2.0
+simulate dt = 0.01 steps = 20
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    while !step_simulation(&mut ctx) {
+        if let Force::Spring { k, .. } = ctx.world.forces[0] {
+            assert_eq!(k, 2.0);
+        }
+    }
+    assert_eq!(ctx.current_step, 20);
+    assert_eq!(ctx.warnings.len(), 1, "{:?}", ctx.warnings);
+    let warning = &ctx.warnings[0];
+    assert!(warning
+        .message
+        .starts_with("spring between 'a' and 'b' stopped updating at time"));
+    assert_eq!(
+        warning.notes,
+        ["Error evaluating spring k: division by zero"]
+    );
+}
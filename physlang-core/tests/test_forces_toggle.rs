@@ -0,0 +1,100 @@
+//! Tests for enabling/disabling forces at runtime and force metadata
+
+use physlang_core::{build_simulation_context_from_source, step_simulation, ForceInfo};
+
+const SOURCE: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+force gravity(a, b) G = 0.5
+simulate dt = 0.01 steps = 1000
+"#;
+
+#[test]
+fn test_forces_metadata() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let forces = ctx.forces();
+    assert_eq!(
+        forces,
+        vec![
+            ForceInfo {
+                index: 0,
+                kind: "spring",
+                a: "a".to_string(),
+                b: "b".to_string(),
+                parameters: vec![("k", 10.0), ("rest", 2.0)],
+                enabled: true,
+            },
+            ForceInfo {
+                index: 1,
+                kind: "gravity",
+                a: "a".to_string(),
+                b: "b".to_string(),
+                parameters: vec![("G", 0.5)],
+                enabled: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_disabling_all_forces_freezes_velocities() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    for _ in 0..20 {
+        step_simulation(&mut ctx);
+    }
+
+    assert!(ctx.world.set_force_enabled(0, false));
+    assert!(ctx.world.set_force_enabled(1, false));
+    assert!(!ctx.forces()[0].enabled);
+
+    let velocities: Vec<_> = ctx.world.particles.iter().map(|p| p.vel).collect();
+    for _ in 0..20 {
+        step_simulation(&mut ctx);
+    }
+    let after: Vec<_> = ctx.world.particles.iter().map(|p| p.vel).collect();
+    assert_eq!(velocities, after);
+    assert_eq!(ctx.world.potential_energy(), 0.0);
+}
+
+#[test]
+fn test_reenabling_resumes_influence() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    ctx.world.set_force_enabled(1, false);
+
+    // Spring stretched past rest length: re-enabling it pulls the particles together
+    ctx.world.set_force_enabled(0, false);
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.world.particles[0].vel.x, 0.0);
+
+    ctx.world.set_force_enabled(0, true);
+    step_simulation(&mut ctx);
+    assert!(ctx.world.particles[0].vel.x > 0.0);
+    assert!(ctx.world.particles[1].vel.x < 0.0);
+}
+
+#[test]
+fn test_disabling_one_force_leaves_others() {
+    let (mut spring_only, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    spring_only.world.set_force_enabled(1, false);
+    let (mut reference, _) = build_simulation_context_from_source(
+        &SOURCE.replace("force gravity(a, b) G = 0.5\n", ""),
+    )
+    .unwrap();
+
+    for _ in 0..100 {
+        step_simulation(&mut spring_only);
+        step_simulation(&mut reference);
+    }
+    assert_eq!(
+        spring_only.world.particles[0].pos,
+        reference.world.particles[0].pos
+    );
+}
+
+#[test]
+fn test_out_of_range_index() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    assert!(!ctx.world.set_force_enabled(2, false));
+    assert!(ctx.world.is_force_enabled(0));
+}
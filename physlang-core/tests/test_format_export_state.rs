@@ -0,0 +1,62 @@
+//! Tests for exporting a running simulation back to source
+
+use physlang_core::{build_simulation_context_from_source, export_state_source, step_simulation};
+
+const SOURCE: &str = r#"
+# Two masses on a spring
+let k = 10.0
+
+particle a at (0.0, 0.0) mass 1.0
+# b is the heavier one
+particle b at (3.0, -1.0) mass 2.0 damping 0.1
+force spring(a, b) k = k rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+"#;
+
+#[test]
+fn test_exported_positions_round_trip() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    for _ in 0..37 {
+        step_simulation(&mut ctx);
+    }
+
+    let exported = export_state_source(SOURCE, &ctx.world).unwrap();
+    let (reloaded, _) = build_simulation_context_from_source(&exported).unwrap();
+    assert_eq!(reloaded.world.particles.len(), ctx.world.particles.len());
+    for (before, after) in ctx.world.particles.iter().zip(&reloaded.world.particles) {
+        assert_eq!(before.name, after.name);
+        assert_eq!(before.pos, after.pos);
+        assert_eq!(before.mass, after.mass);
+        assert_eq!(before.damping, after.damping);
+    }
+}
+
+#[test]
+fn test_keeps_everything_but_particle_positions() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    step_simulation(&mut ctx);
+
+    let exported = export_state_source(SOURCE, &ctx.world).unwrap();
+    let kept: Vec<&str> = SOURCE
+        .lines()
+        .filter(|line| !line.starts_with("particle "))
+        .collect();
+    let exported_kept: Vec<&str> = exported
+        .lines()
+        .filter(|line| !line.starts_with("particle "))
+        .collect();
+    assert_eq!(exported_kept, kept);
+
+    let b = exported
+        .lines()
+        .find(|line| line.starts_with("particle b "))
+        .unwrap();
+    assert!(b.ends_with(" mass 2.0 damping 0.1"), "{}", b);
+}
+
+#[test]
+fn test_unparsable_source_is_an_error() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    assert!(export_state_source("particle a at (0.0", &ctx.world).is_err());
+}
@@ -0,0 +1,136 @@
+//! Tests for the source formatter behind `physlang fmt`
+
+use physlang_core::{format_source, parse_program};
+use std::path::{Path, PathBuf};
+
+/// Every `.phys` file under `dir`, recursively
+fn phys_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            phys_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "phys") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_normalizes_spacing_and_indentation() {
+    let source = "let   k = 2*3.5\n\n\n\nparticle a at (0,0) mass 1\n  particle b at ( 1.5 , 0.0 ) mass 2.0   damping 0.5\nforce spring(a,b)  k =  k rest = 1.0\nsimulate dt = 0.01   steps = 100\n";
+    let expected = "\
+let k = 2.0 * 3.5
+
+particle a at (0.0, 0.0) mass 1.0
+particle b at (1.5, 0.0) mass 2.0 damping 0.5
+force spring(a, b) k = k rest = 1.0
+simulate dt = 0.01 steps = 100
+";
+    assert_eq!(format_source(source).unwrap(), expected);
+}
+
+#[test]
+fn test_preserves_comments_and_order() {
+    let source = r#"
+# Header comment
+
+simulate dt = 0.01 steps = 10
+particle a at (0.0, 0.0) mass 1.0
+      # indented comment
+detect x = position(a)
+"#;
+    let expected = "\
+# Header comment
+
+simulate dt = 0.01 steps = 10
+particle a at (0.0, 0.0) mass 1.0
+# indented comment
+detect x = position(a)
+";
+    assert_eq!(format_source(source).unwrap(), expected);
+}
+
+#[test]
+fn test_reindents_blocks() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+fn kick(n) {
+if n > 1.0 {
+  return n*2.0
+}
+        return n
+}
+loop for 3 cycles with frequency 1.0 damping 0.0 on a {
+force push(a)   magnitude 1.0 direction (1.0,0.0)
+}
+simulate dt = 0.01 steps = 10
+"#;
+    let expected = "\
+particle a at (0.0, 0.0) mass 1.0
+fn kick(n) {
+    if n > 1.0 {
+        return n * 2.0
+    }
+    return n
+}
+loop for 3 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+simulate dt = 0.01 steps = 10
+";
+    assert_eq!(format_source(source).unwrap(), expected);
+}
+
+#[test]
+fn test_expressions_keep_their_grouping() {
+    let source = r#"
+let a = 1.0 - 2.0 - 3.0
+let b = -a * 2.0 + 4.0 / 2.0
+let c = sqrt(a*a+b*b)
+particle p at (a, b) mass c
+simulate dt = 0.01 steps = 10
+"#;
+    let formatted = format_source(source).unwrap();
+    assert!(formatted.contains("let a = 1.0 - 2.0 - 3.0\n"));
+    assert!(formatted.contains("let b = -a * 2.0 + 4.0 / 2.0\n"));
+    assert!(formatted.contains("let c = sqrt(a * a + b * b)\n"));
+    assert_eq!(
+        parse_program(&formatted).unwrap(),
+        parse_program(source).unwrap()
+    );
+}
+
+#[test]
+fn test_parse_errors_are_returned() {
+    assert!(format_source("particle a at").is_err());
+}
+
+#[test]
+fn test_idempotent_over_all_fixtures() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut files = Vec::new();
+    phys_files(&root.join("examples"), &mut files);
+    phys_files(&root.join("physlang-cli/tests/fixtures"), &mut files);
+    assert!(files.len() > 10, "found only {:?}", files);
+
+    let mut formatted_count = 0;
+    for file in &files {
+        let source = std::fs::read_to_string(file).unwrap();
+        let Ok(program) = parse_program(&source) else {
+            // Fixtures for error reporting are expected not to parse
+            continue;
+        };
+        let once = format_source(&source)
+            .unwrap_or_else(|e| panic!("{} failed to format: {}", file.display(), e));
+        let twice = format_source(&once).unwrap();
+        assert_eq!(once, twice, "{} is not idempotent", file.display());
+        assert_eq!(
+            parse_program(&once).unwrap(),
+            program,
+            "{} changed meaning",
+            file.display()
+        );
+        formatted_count += 1;
+    }
+    assert!(formatted_count > 10);
+}
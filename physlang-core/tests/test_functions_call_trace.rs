@@ -0,0 +1,104 @@
+//! Tests for the call backtraces of function execution errors
+
+use physlang_core::{
+    evaluate_lets, execute_functions, execute_functions_with_source, parse_program,
+    ExpansionLimits, Pipeline, RunError,
+};
+
+/// `exposure` divides by zero when `make_pair` is called with 3
+const CHAIN: &str = r#"fn exposure(p, q, n) {
+    let share = 1.0 / n
+    particle p at (share, 0.0) mass 1.0
+}
+
+fn make_pair(i) {
+    exposure("A", "B", i - 3.0)
+}
+
+fn outer(k) {
+    make_pair(k)
+}
+
+outer(1.0)
+outer(3.0)
+simulate dt = 0.01 steps = 10
+"#;
+
+/// The same call site reached from a loop, on its third iteration
+const LOOPED: &str = r#"fn inverse(n) {
+    let x = 1.0 / n
+    particle p at (x, 0.0) mass 1.0
+}
+
+for i in 0..4 {
+    inverse(i - 2.0)
+}
+simulate dt = 0.01 steps = 10
+"#;
+
+fn function_errors(source: &str, with_source: bool) -> Vec<String> {
+    let mut program = parse_program(source).unwrap();
+    let lets = program.lets.clone();
+    let (eval_ctx, _) = evaluate_lets(&lets);
+    let diagnostics = if with_source {
+        execute_functions_with_source(
+            &mut program,
+            &eval_ctx,
+            Some(source),
+            &ExpansionLimits::default(),
+        )
+    } else {
+        execute_functions(&mut program, &eval_ctx)
+    };
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn test_trace_names_every_frame() {
+    let errors = function_errors(CHAIN, true);
+    assert_eq!(
+        errors,
+        [
+            "Error executing function 'outer': Division by zero in exposure(\"A\", \"B\", 0) \
+             called from make_pair(3) called from outer(3) called from top level (line 15)"
+        ]
+    );
+}
+
+#[test]
+fn test_trace_without_source_has_no_line() {
+    let errors = function_errors(CHAIN, false);
+    assert_eq!(errors.len(), 1);
+    assert!(
+        errors[0].ends_with("called from outer(3) called from top level"),
+        "{}",
+        errors[0]
+    );
+}
+
+#[test]
+fn test_trace_shows_the_loop_iteration() {
+    let errors = function_errors(LOOPED, true);
+    assert_eq!(
+        errors,
+        [
+            "Error executing top-level statement: Division by zero in inverse(0) \
+             called from for i in 0..4 at i = 2 \
+             called from top level (line 6)"
+        ]
+    );
+}
+
+#[test]
+fn test_pipeline_reports_the_trace() {
+    let mut pipeline = Pipeline::parse(CHAIN).unwrap();
+    pipeline.analyze().unwrap();
+    match pipeline.expand_functions() {
+        Err(RunError::Analysis { stage, messages }) => {
+            assert_eq!(stage, "Function execution");
+            assert!(messages[0].contains("in exposure(\"A\", \"B\", 0)"));
+            assert!(messages[0].ends_with("(line 15)"), "{}", messages[0]);
+        }
+        other => panic!("expected a function execution error, got {:?}", other),
+    }
+}
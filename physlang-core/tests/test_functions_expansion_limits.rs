@@ -0,0 +1,110 @@
+//! Tests for the limits on how much world generated code may build
+
+use physlang_core::{
+    build_simulation_context_with_overrides, evaluate_lets, execute_functions_with_source,
+    parse_program, ExpansionLimits, RunError, RunOptions,
+};
+use std::time::{Duration, Instant};
+
+/// A typo'd bound: ten million particles
+const RUNAWAY: &str = "\
+for i in 0..10000000 {
+    particle p at (i, 0.0) mass 1.0
+}
+simulate dt = 0.01 steps = 10
+";
+
+/// `make` generates one particle per call
+const MAKE: &str = "\
+fn make(name, x) {
+    particle name at (x, 0.0) mass 1.0
+}
+";
+
+/// A world of `count` particles, each generated by its own call to `make`
+fn generated_world(count: usize) -> String {
+    let mut source = MAKE.to_string();
+    for i in 0..count {
+        source.push_str(&format!("make(\"p{}\", {}.0)\n", i, i));
+    }
+    source.push_str("simulate dt = 0.01 steps = 1\n");
+    source
+}
+
+fn function_errors(source: &str, limits: &ExpansionLimits) -> Vec<String> {
+    let mut program = parse_program(source).unwrap();
+    let lets = program.lets.clone();
+    let (eval_ctx, _) = evaluate_lets(&lets);
+    execute_functions_with_source(&mut program, &eval_ctx, Some(source), limits)
+        .into_iter()
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test]
+fn test_runaway_loop_fails_fast() {
+    let started = Instant::now();
+    let errors = function_errors(RUNAWAY, &ExpansionLimits::default());
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(
+        errors,
+        [
+            "Error executing top-level statement: too many particles: 100001 exceeds the \
+             limit of 100000 in for i in 0..10000000 at i = 100000 called from top level (line 1)"
+        ]
+    );
+}
+
+#[test]
+fn test_statement_limit_names_the_function_and_aborts() {
+    let limits = ExpansionLimits {
+        max_statements: 3,
+        ..Default::default()
+    };
+    let errors = function_errors(&generated_world(5), &limits);
+    assert_eq!(
+        errors,
+        [
+            "Error executing function 'make': too many generated declarations: 4 exceeds the \
+             limit of 3 in make(\"p3\", 3) called from top level (line 7)"
+        ]
+    );
+}
+
+#[test]
+fn test_world_within_limits_expands() {
+    let errors = function_errors(&generated_world(10), &ExpansionLimits::default());
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_raised_limit_builds_a_large_world() {
+    let source = generated_world(50_000);
+    let lowered = RunOptions {
+        expansion_limits: ExpansionLimits {
+            max_particles: 40_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    match build_simulation_context_with_overrides(&source, &lowered) {
+        Err(error) => match error.downcast_ref::<RunError>() {
+            Some(RunError::Analysis { stage, messages }) => {
+                assert_eq!(*stage, "Function execution");
+                assert!(messages[0].contains("too many particles: 40001"));
+            }
+            other => panic!("expected a function execution error, got {:?}", other),
+        },
+        Ok(_) => panic!("expected the lowered limit to be exceeded"),
+    }
+
+    let raised = RunOptions {
+        expansion_limits: ExpansionLimits {
+            max_particles: 50_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (ctx, _) = build_simulation_context_with_overrides(&source, &raised).unwrap();
+    assert_eq!(ctx.world.particles.len(), 50_000);
+}
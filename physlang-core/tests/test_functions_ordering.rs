@@ -0,0 +1,105 @@
+//! Tests that function-generated worlds come out in a stable, source-defined order
+
+use physlang_core::ast::{Expr, Program};
+use physlang_core::{evaluate_lets, execute_functions, parse_program};
+
+/// Generates exactly 100 particles from functions, nested loops, if/else, and match
+const SOURCE: &str = r#"
+let base = 10.0
+fn cell(name, x, y) {
+    particle name at (x, y) mass 1.0
+}
+fn row(y) {
+    for i in 0..10 {
+        particle "row" at (i, y) mass 1.0
+    }
+}
+for j in 0..4 {
+    row(j)
+}
+for i in 0..6 {
+    for j in 0..5 {
+        if i > 2 {
+            particle "hi" at (i, j) mass 2.0
+        } else {
+            particle "lo" at (i, j) mass 1.0
+        }
+    }
+}
+cell("first", base, 0.0)
+for k in 0..28 {
+    match k {
+        0 => {
+            particle "zero" at (k, 0.0) mass 1.0
+        }
+        _ => {
+            particle "k" at (k, 1.0) mass 1.0
+        }
+    }
+}
+cell("last", base, 1.0)
+simulate dt = 0.01 steps = 1
+"#;
+
+fn literal(expr: &Expr) -> f32 {
+    match expr {
+        Expr::Literal(v) => *v,
+        other => panic!("expected literal after execution, got {:?}", other),
+    }
+}
+
+fn generate() -> Vec<(String, f32, f32)> {
+    let mut program: Program = parse_program(SOURCE).unwrap();
+    let lets = program.lets.clone();
+    let (eval_ctx, _) = evaluate_lets(&lets);
+    let diagnostics = execute_functions(&mut program, &eval_ctx);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    program
+        .particles
+        .iter()
+        .map(|p| (p.name.clone(), literal(&p.position.0), literal(&p.position.1)))
+        .collect()
+}
+
+/// The order the language guarantees, spelled out independently of the executor
+fn expected() -> Vec<(String, f32, f32)> {
+    let mut expected = Vec::new();
+    for j in 0..4 {
+        for i in 0..10 {
+            expected.push(("\"row\"".to_string(), i as f32, j as f32));
+        }
+    }
+    for i in 0..6 {
+        for j in 0..5 {
+            let name = if i > 2 { "\"hi\"" } else { "\"lo\"" };
+            expected.push((name.to_string(), i as f32, j as f32));
+        }
+    }
+    expected.push(("first".to_string(), 10.0, 0.0));
+    for k in 0..28 {
+        if k == 0 {
+            expected.push(("\"zero\"".to_string(), 0.0, 0.0));
+        } else {
+            expected.push(("\"k\"".to_string(), k as f32, 1.0));
+        }
+    }
+    expected.push(("last".to_string(), 10.0, 1.0));
+    expected
+}
+
+#[test]
+fn test_generated_order_is_source_order() {
+    let generated = generate();
+    assert_eq!(generated.len(), 100);
+    // A fixed expected sequence also pins the order across process runs
+    assert_eq!(generated, expected());
+}
+
+#[test]
+fn test_generated_order_is_repeatable() {
+    let first = generate();
+    for _ in 0..5 {
+        assert_eq!(generate(), first);
+    }
+}
@@ -0,0 +1,130 @@
+//! Tests for the `name` and `number` annotations of function parameters
+
+use physlang_core::ast::ParamType;
+use physlang_core::{analyze_program, evaluate_lets, execute_functions, parse_program};
+
+const PROBE: &str = r#"fn probe(p: name, k: number) {
+    particle p at (k, 0.0) mass 1.0
+}
+
+probe("a", 2.0)
+simulate dt = 0.01 steps = 10
+"#;
+
+/// Messages and lines of the errors analysis reports for `source`
+fn analysis_errors(source: &str) -> Vec<(String, Option<usize>)> {
+    let program = parse_program(source).unwrap();
+    analyze_program(&program)
+        .errors()
+        .map(|d| {
+            let line = d.span.map(|span| span.to_location(source).line);
+            (d.message.clone(), line)
+        })
+        .collect()
+}
+
+#[test]
+fn test_annotations_are_parsed() {
+    let program = parse_program(PROBE).unwrap();
+    let probe = &program.functions[0];
+    assert_eq!(probe.params, ["p", "k"]);
+    assert_eq!(
+        probe.param_types,
+        [Some(ParamType::Name), Some(ParamType::Number)]
+    );
+
+    let untyped = parse_program(&PROBE.replace("k: number", "k")).unwrap();
+    assert_eq!(untyped.functions[0].param_type(1), None);
+}
+
+#[test]
+fn test_unknown_annotation_is_a_parse_error() {
+    let error = parse_program(&PROBE.replace("k: number", "k: vector")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unknown type 'vector' for parameter 'k': expected 'name' or 'number'"
+    );
+}
+
+#[test]
+fn test_matching_call_checks_and_runs() {
+    assert!(analysis_errors(PROBE).is_empty());
+    let mut program = parse_program(PROBE).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&[]);
+    assert!(execute_functions(&mut program, &eval_ctx).is_empty());
+    assert_eq!(program.particles[0].name, "a");
+}
+
+#[test]
+fn test_mismatched_call_names_parameter_and_call_site() {
+    let swapped = PROBE.replace(r#"probe("a", 2.0)"#, r#"probe(2.0, "a")"#);
+    assert_eq!(
+        analysis_errors(&swapped),
+        [
+            (
+                "call to 'probe' passes a number to parameter 'p: name'".to_string(),
+                Some(5)
+            ),
+            (
+                "call to 'probe' passes a particle name to parameter 'k: number'".to_string(),
+                Some(5)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_calls_in_function_bodies_are_checked() {
+    // Reported at the function making the call
+    let source = format!("{}fn row() {{\n    probe(\"b\", \"c\")\n}}\n", PROBE);
+    assert_eq!(
+        analysis_errors(&source),
+        [(
+            "call to 'probe' passes a particle name to parameter 'k: number'".to_string(),
+            Some(7)
+        )]
+    );
+}
+
+#[test]
+fn test_calls_in_expressions_are_checked() {
+    let source = "\
+fn count(p: name) {
+    return 10
+}
+
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = count(3)
+";
+    assert_eq!(
+        analysis_errors(source),
+        [(
+            "call to 'count' passes a number to parameter 'p: name'".to_string(),
+            None
+        )]
+    );
+}
+
+#[test]
+fn test_unannotated_parameters_stay_dynamic() {
+    let source = PROBE.replace("p: name, k: number", "p, k");
+    let swapped = source.replace(r#"probe("a", 2.0)"#, r#"probe(2.0, "a")"#);
+    assert!(analysis_errors(&swapped).is_empty());
+}
+
+#[test]
+fn test_execution_enforces_annotations() {
+    let swapped = PROBE.replace(r#"probe("a", 2.0)"#, r#"probe(2.0, "a")"#);
+    let mut program = parse_program(&swapped).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&[]);
+    let diagnostics = execute_functions(&mut program, &eval_ctx);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(
+        diagnostics[0]
+            .message
+            .contains("call to 'probe' passes a number to parameter 'p: name'"),
+        "{}",
+        diagnostics[0].message
+    );
+    assert!(program.particles.is_empty());
+}
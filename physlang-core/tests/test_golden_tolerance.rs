@@ -0,0 +1,118 @@
+//! Tests for tolerance-based golden output comparison
+
+use physlang_core::tests::test_helpers::{
+    compare_golden, golden_matches, load_expected, parse_golden, regenerate_expected,
+    result_to_golden_json, result_to_json, run_phys_source, GoldenTolerance,
+};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 200
+detect gap = distance(a, b)
+detect where = position(a)
+"#;
+
+/// Golden JSON whose `gap` detector is scaled by 1 + `relative`
+fn perturbed(json: &str, relative: f64) -> String {
+    let mut golden = parse_golden(json).unwrap();
+    let gap = golden
+        .detectors
+        .iter_mut()
+        .find(|d| d.name == "gap")
+        .unwrap();
+    let value = gap.value * (1.0 + relative);
+    json.lines()
+        .map(|line| {
+            if line.contains("\"gap\"") {
+                format!("    {{\"name\": \"gap\", \"value\": {:.17}}},", value)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_tiny_perturbation_passes_and_large_fails() {
+    let expected = result_to_json(&run_phys_source(PROGRAM).unwrap());
+    assert!(golden_matches(&perturbed(&expected, 1e-9), &expected));
+
+    let diff = compare_golden(&perturbed(&expected, 1e-2), &expected).unwrap_err();
+    assert!(diff.contains("gap"), "{}", diff);
+    assert!(!diff.contains("where"), "{}", diff);
+}
+
+#[test]
+fn test_formatting_and_order_do_not_matter() {
+    let expected = result_to_json(&run_phys_source(PROGRAM).unwrap());
+    let golden = parse_golden(&expected).unwrap();
+    let reordered = format!(
+        "{{\"state_hash\": \"0\", \"detectors\": [{}]}}",
+        golden
+            .detectors
+            .iter()
+            .rev()
+            .map(|d| format!("{{\"value\": {:e}, \"name\": \"{}\"}}", d.value, d.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    assert!(golden_matches(&reordered, &expected));
+}
+
+#[test]
+fn test_missing_and_extra_detectors_are_reported() {
+    let result = run_phys_source(PROGRAM).unwrap();
+    let expected = result_to_json(&result);
+    let renamed = expected.replace("\"where\"", "\"there\"");
+    let diff = compare_golden(&renamed, &expected).unwrap_err();
+    assert!(diff.contains("where: missing"), "{}", diff);
+    assert!(diff.contains("there: unexpected"), "{}", diff);
+}
+
+#[test]
+fn test_expected_file_tolerance_is_used() {
+    let result = run_phys_source(PROGRAM).unwrap();
+    let loose = GoldenTolerance {
+        relative: 0.05,
+        absolute: 0.0,
+    };
+    let expected = result_to_golden_json(&result, Some(loose));
+    assert_eq!(parse_golden(&expected).unwrap().tolerance, Some(loose));
+    assert!(golden_matches(&perturbed(&expected, 1e-2), &expected));
+    assert!(!golden_matches(&perturbed(&expected, 1e-1), &expected));
+}
+
+#[test]
+fn test_regenerate_keeps_tolerance() {
+    let result = run_phys_source(PROGRAM).unwrap();
+    let path =
+        std::env::temp_dir().join(format!("physlang-golden-{}.expected", std::process::id()));
+    let path = path.to_str().unwrap();
+    let tolerance = GoldenTolerance {
+        relative: 1e-3,
+        absolute: 1e-6,
+    };
+    std::fs::write(path, result_to_golden_json(&result, Some(tolerance))).unwrap();
+
+    regenerate_expected(path, &result).unwrap();
+    let golden = parse_golden(&load_expected(path).unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(golden.tolerance, Some(tolerance));
+}
+
+#[test]
+fn test_non_finite_values_round_trip() {
+    let mut result = run_phys_source(PROGRAM).unwrap();
+    result.detectors[0].value = f32::NAN;
+    result.detectors[1].value = f32::NEG_INFINITY;
+    let json = result_to_json(&result);
+    assert!(json.contains("\"value\": \"NaN\""), "{}", json);
+
+    let golden = parse_golden(&json).unwrap();
+    assert!(golden.detectors[0].value.is_nan());
+    assert_eq!(golden.detectors[1].value, f64::NEG_INFINITY);
+    assert!(golden_matches(&json, &json));
+}
@@ -37,10 +37,23 @@ pub fn run_phys_source(source: &str) -> Result<physlang_core::runtime::Simulatio
     physlang_core::run_program(source)
 }
 
+/// Run a PhysLang program from source string and return its final state hash
+pub fn hash_phys_source(source: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    Ok(physlang_core::run_program(source)?.state_hash)
+}
+
 /// Convert simulation result to JSON string for golden tests
 pub fn result_to_json(result: &physlang_core::runtime::SimulationResult) -> String {
+    result_to_golden_json(result, None)
+}
+
+/// Like `result_to_json`, with a `tolerance` entry when one is given
+pub fn result_to_golden_json(
+    result: &physlang_core::runtime::SimulationResult,
+    tolerance: Option<GoldenTolerance>,
+) -> String {
     use std::fmt::Write;
-    
+
     let mut json = String::from("{\n  \"detectors\": [\n");
     for (i, detector) in result.detectors.iter().enumerate() {
         if i > 0 {
@@ -48,10 +61,140 @@ pub fn result_to_json(result: &physlang_core::runtime::SimulationResult) -> Stri
         }
         write!(json, "    {{\"name\": \"{}\", \"value\": {:.12}}}", detector.name, detector.value).unwrap();
     }
-    json.push_str("\n  ]\n}");
+    json.push_str("\n  ],\n");
+    if let Some(tolerance) = tolerance {
+        writeln!(
+            json,
+            "  \"tolerance\": {{\"relative\": {:e}, \"absolute\": {:e}}},",
+            tolerance.relative, tolerance.absolute
+        )
+        .unwrap();
+    }
+    write!(json, "  \"state_hash\": \"{:016x}\"\n}}", result.state_hash).unwrap();
     json
 }
 
+/// Extract the `state_hash` entry from golden JSON output, if present
+pub fn extract_state_hash(json: &str) -> Option<u64> {
+    let rest = &json[json.find("\"state_hash\"")?..];
+    let start = rest.find(": \"")? + 3;
+    let end = start + rest[start..].find('"')?;
+    u64::from_str_radix(&rest[start..end], 16).ok()
+}
+
+/// How far a detector may drift from its golden value: it passes if within
+/// `absolute` or within `relative` times the expected magnitude
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct GoldenTolerance {
+    pub relative: f64,
+    pub absolute: f64,
+}
+
+impl Default for GoldenTolerance {
+    fn default() -> Self {
+        Self {
+            relative: 1e-6,
+            absolute: 1e-9,
+        }
+    }
+}
+
+impl GoldenTolerance {
+    pub fn accepts(&self, actual: f64, expected: f64) -> bool {
+        if actual.is_nan() || expected.is_nan() {
+            return actual.is_nan() && expected.is_nan();
+        }
+        actual == expected
+            || (actual - expected).abs() <= self.absolute.max(self.relative * expected.abs())
+    }
+}
+
+/// A golden output parsed back from JSON
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct GoldenOutput {
+    pub detectors: Vec<GoldenDetector>,
+    #[serde(default)]
+    pub tolerance: Option<GoldenTolerance>,
+    #[serde(default)]
+    pub state_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct GoldenDetector {
+    pub name: String,
+    pub value: f64,
+}
+
+/// Parse golden JSON, as written by `result_to_golden_json`
+pub fn parse_golden(json: &str) -> Result<GoldenOutput, String> {
+    serde_json::from_str(json).map_err(|e| format!("invalid golden JSON: {}", e))
+}
+
+/// Compare golden outputs detector by detector, within the tolerance the
+/// expected output sets (or the default). The error lists only the detectors
+/// that are missing, unexpected, or off, and by how much
+pub fn compare_golden(actual_json: &str, expected_json: &str) -> Result<(), String> {
+    let actual = parse_golden(actual_json)?;
+    let expected = parse_golden(expected_json)?;
+    let tolerance = expected.tolerance.unwrap_or_default();
+
+    let mut problems = Vec::new();
+    for want in &expected.detectors {
+        match actual.detectors.iter().find(|d| d.name == want.name) {
+            None => problems.push(format!("  {}: missing (expected {})", want.name, want.value)),
+            Some(got) if !tolerance.accepts(got.value, want.value) => {
+                let diff = (got.value - want.value).abs();
+                let relative = if want.value != 0.0 {
+                    format!(", {:.3e} relative", diff / want.value.abs())
+                } else {
+                    String::new()
+                };
+                problems.push(format!(
+                    "  {}: expected {}, got {} (off by {:.3e}{})",
+                    want.name, want.value, got.value, diff, relative
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for got in &actual.detectors {
+        if !expected.detectors.iter().any(|d| d.name == got.name) {
+            problems.push(format!("  {}: unexpected (got {})", got.name, got.value));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} detector(s) deviate from the golden output (relative {:e}, absolute {:e}):\n{}",
+            problems.len(),
+            tolerance.relative,
+            tolerance.absolute,
+            problems.join("\n")
+        ))
+    }
+}
+
+/// Whether `actual_json` matches `expected_json` under `compare_golden`
+pub fn golden_matches(actual_json: &str, expected_json: &str) -> bool {
+    compare_golden(actual_json, expected_json).is_ok()
+}
+
+/// Write the golden output for `result` to `path`, keeping the tolerance
+/// the file already sets
+pub fn regenerate_expected(
+    path: &str,
+    result: &physlang_core::runtime::SimulationResult,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tolerance = load_expected(path)
+        .ok()
+        .and_then(|json| parse_golden(&json).ok())
+        .and_then(|golden| golden.tolerance);
+    write_expected(path, &result_to_golden_json(result, tolerance))
+}
+
 /// Compare two simulation results with tolerance
 pub fn results_approx_equal(
     a: &physlang_core::runtime::SimulationResult,
@@ -80,3 +223,61 @@ pub fn results_approx_equal(
     true
 }
 
+
+/// A world whose energy drift is to be characterized: PhysLang source (using
+/// its `simulate dt`) or a prepared world with an explicit dt
+pub enum DriftSubject<'a> {
+    Source(&'a str),
+    World(Box<physlang_core::engine::World>, f32),
+}
+
+impl<'a> From<&'a str> for DriftSubject<'a> {
+    fn from(source: &'a str) -> Self {
+        DriftSubject::Source(source)
+    }
+}
+
+impl From<(physlang_core::engine::World, f32)> for DriftSubject<'_> {
+    fn from((world, dt): (physlang_core::engine::World, f32)) -> Self {
+        DriftSubject::World(Box::new(world), dt)
+    }
+}
+
+/// Step a world with `method` and assert that the relative total-energy drift
+/// |E − E₀| / |E₀| stays below `tolerance` at every step (absolute if E₀ = 0)
+/// Returns the largest drift seen, so callers can compare integrators
+pub fn assert_energy_drift_below<'a>(
+    subject: impl Into<DriftSubject<'a>>,
+    method: physlang_core::integrator::Integrator,
+    steps: usize,
+    tolerance: f32,
+) -> f32 {
+    let (mut world, dt) = match subject.into() {
+        DriftSubject::Source(source) => {
+            let (ctx, _) = physlang_core::build_simulation_context_from_source(source)
+                .unwrap_or_else(|e| panic!("failed to build drift subject: {}", e));
+            (ctx.world, ctx.dt)
+        }
+        DriftSubject::World(world, dt) => (*world, dt),
+    };
+
+    let initial = world.total_energy();
+    let scale = if initial != 0.0 { initial.abs() } else { 1.0 };
+    let mut max_drift = 0.0f32;
+    for step in 1..=steps {
+        physlang_core::integrator::step_with(&mut world, dt, method);
+        let drift = (world.total_energy() - initial).abs() / scale;
+        assert!(
+            drift < tolerance,
+            "{:?}: energy drift {} exceeds {} at step {} (E₀ = {}, E = {})",
+            method,
+            drift,
+            tolerance,
+            step,
+            initial,
+            world.total_energy()
+        );
+        max_drift = max_drift.max(drift);
+    }
+    max_drift
+}
@@ -0,0 +1,165 @@
+//! Tests for the syntax highlighting scanner
+
+use physlang_core::{tokenize_for_highlighting, TokenClass};
+use std::path::Path;
+
+/// `(text, class)` of every token of `source`
+fn classify(source: &str) -> Vec<(&str, TokenClass)> {
+    tokenize_for_highlighting(source)
+        .into_iter()
+        .map(|t| (&source[t.span.start..t.span.end], t.class))
+        .collect()
+}
+
+fn class_of(tokens: &[(&str, TokenClass)], text: &str) -> Vec<TokenClass> {
+    tokens
+        .iter()
+        .filter(|(t, _)| *t == text)
+        .map(|(_, class)| *class)
+        .collect()
+}
+
+fn fixture(path: &str) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    std::fs::read_to_string(root.join(path)).unwrap()
+}
+
+#[test]
+fn test_simple_fixture() {
+    use TokenClass::*;
+    let source = fixture("examples/simple.phys");
+    let tokens = classify(&source);
+    assert_eq!(
+        &tokens[..12],
+        [
+            ("particle", Keyword),
+            ("a", ParticleName),
+            ("at", Keyword),
+            ("(", Punctuation),
+            ("0.0", Number),
+            (",", Punctuation),
+            ("0.0", Number),
+            (")", Punctuation),
+            ("mass", Keyword),
+            ("3.0", Number),
+            ("particle", Keyword),
+            ("b", ParticleName),
+        ]
+    );
+    let detect = tokens.iter().rposition(|(t, _)| *t == "detect").unwrap();
+    assert_eq!(
+        &tokens[detect..],
+        [
+            ("detect", Keyword),
+            ("dist_ab", Identifier),
+            ("=", Operator),
+            ("distance", FunctionName),
+            ("(", Punctuation),
+            ("a", ParticleName),
+            (",", Punctuation),
+            ("b", ParticleName),
+            (")", Punctuation),
+        ]
+    );
+    assert_eq!(class_of(&tokens, "gravity"), [Keyword]);
+    assert_eq!(class_of(&tokens, "G"), [Keyword]);
+}
+
+#[test]
+fn test_broken_fixtures_still_classify() {
+    use TokenClass::*;
+    // Missing `(` in the position
+    let source = fixture("tests/integration/broken/syntax_error.phys");
+    let tokens = classify(&source);
+    assert_eq!(tokens[0], ("# Syntax error - missing parentheses", Comment));
+    assert_eq!(
+        &tokens[1..7],
+        [
+            ("particle", Keyword),
+            ("a", ParticleName),
+            ("at", Keyword),
+            ("0.0", Number),
+            (",", Punctuation),
+            ("0.0", Number),
+        ]
+    );
+
+    let source = fixture("examples/invalid.phys");
+    let tokens = classify(&source);
+    assert_eq!(class_of(&tokens, "# Duplicate name"), [Comment]);
+    // Undeclared names in particle positions aren't particles
+    assert_eq!(class_of(&tokens, "nonexistent"), [Identifier]);
+    assert_eq!(class_of(&tokens, "position"), [FunctionName]);
+
+    // Unterminated strings end at the line
+    let tokens = classify("particle \"p at (0.0\nmass @");
+    assert_eq!(
+        tokens,
+        [
+            ("particle", Keyword),
+            ("\"p at (0.0", String),
+            ("mass", Keyword),
+            ("@", Punctuation),
+        ]
+    );
+}
+
+#[test]
+fn test_contextual_keywords_and_expressions() {
+    use TokenClass::*;
+    let source = "\
+let k = 2.5e-3 * sqrt(4)
+fn make(name, m) world {
+    particle \"p#1\" at (0.0, 0.0) mass m  # trailing
+    for i in 0..3 {
+        make_one(i)
+    }
+}
+force spring(a, b) k = k rest = -1.0
+simulate dt = 0.01 steps = 10 frame = center_of_mass
+detect c = center_of_mass()
+detect px = position(a).x
+ground y = 0.0 friction 0.5
+";
+    let tokens = classify(source);
+    assert_eq!(class_of(&tokens, "k"), [Identifier, Keyword, Identifier]);
+    assert_eq!(class_of(&tokens, "2.5e-3"), [Number]);
+    assert_eq!(class_of(&tokens, "sqrt"), [FunctionName]);
+    assert_eq!(class_of(&tokens, "make"), [FunctionName]);
+    assert_eq!(class_of(&tokens, "make_one"), [FunctionName]);
+    assert_eq!(class_of(&tokens, "world"), [Keyword]);
+    assert_eq!(class_of(&tokens, "\"p#1\""), [String]);
+    assert_eq!(class_of(&tokens, "# trailing"), [Comment]);
+    assert_eq!(class_of(&tokens, "m"), [Identifier, Identifier]);
+    assert_eq!(class_of(&tokens, "0"), [Number]);
+    assert_eq!(class_of(&tokens, ".."), [Operator]);
+    assert_eq!(class_of(&tokens, "spring"), [Keyword]);
+    assert_eq!(class_of(&tokens, "center_of_mass"), [Keyword, FunctionName]);
+    assert_eq!(class_of(&tokens, "y"), [Keyword]);
+    assert_eq!(class_of(&tokens, "friction"), [Keyword]);
+    assert_eq!(class_of(&tokens, "x"), [Identifier]);
+    assert_eq!(class_of(&tokens, "."), [Operator]);
+}
+
+#[test]
+fn test_tokens_cover_every_fixture_in_order() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    for dir in ["examples", "examples/vel", "tests/integration/broken"] {
+        for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "phys") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let mut covered = 0;
+            for token in tokenize_for_highlighting(&source) {
+                assert!(token.span.start < token.span.end, "{}", path.display());
+                // Only whitespace between tokens
+                let gap = &source[covered..token.span.start];
+                assert!(gap.trim().is_empty(), "{}: {:?}", path.display(), gap);
+                covered = token.span.end;
+            }
+            assert!(source[covered..].trim().is_empty(), "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Energy drift characterization of each integrator on standard fixtures
+
+use glam::Vec2;
+use physlang_core::tests::test_helpers::assert_energy_drift_below;
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, step_with, Integrator, World,
+};
+
+const SPRING_OSCILLATOR: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 2000
+"#;
+
+/// Light body on an eccentric orbit (70% of circular speed) around a heavy one,
+/// with the centre of mass at rest
+fn two_body_orbit() -> (World, f32) {
+    let source = r#"
+particle sun at (0.0, 0.0) mass 100.0
+particle planet at (10.0, 0.0) mass 1.0
+force gravity(sun, planet) G = 1.0
+simulate dt = 0.01 steps = 2000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let (big, small, r) = (100.0f32, 1.0f32, 10.0f32);
+    let relative_speed = 0.7 * ((big + small) / r).sqrt();
+    ctx.world.particles[0].vel = Vec2::new(0.0, -relative_speed * small / (big + small));
+    ctx.world.particles[1].vel = Vec2::new(0.0, relative_speed * big / (big + small));
+    (ctx.world, ctx.dt)
+}
+
+#[test]
+fn test_semi_implicit_euler_drift() {
+    assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::SemiImplicitEuler, 2000, 0.05);
+    assert_energy_drift_below(two_body_orbit(), Integrator::SemiImplicitEuler, 2000, 0.02);
+}
+
+#[test]
+fn test_verlet_drift() {
+    assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::Verlet, 2000, 1e-3);
+    assert_energy_drift_below(two_body_orbit(), Integrator::Verlet, 2000, 5e-4);
+}
+
+#[test]
+fn test_rk4_drift() {
+    assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::Rk4, 2000, 5e-5);
+    assert_energy_drift_below(two_body_orbit(), Integrator::Rk4, 2000, 5e-5);
+}
+
+#[test]
+fn test_higher_order_methods_drift_less() {
+    let euler =
+        assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::SemiImplicitEuler, 2000, 1.0);
+    let verlet = assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::Verlet, 2000, 1.0);
+    let rk4 = assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::Rk4, 2000, 1.0);
+    assert!(verlet < euler);
+    assert!(rk4 < verlet);
+}
+
+#[test]
+#[should_panic(expected = "energy drift")]
+fn test_harness_reports_excess_drift() {
+    assert_energy_drift_below(SPRING_OSCILLATOR, Integrator::SemiImplicitEuler, 2000, 1e-6);
+}
+
+#[test]
+fn test_default_integrator_is_semi_implicit_euler() {
+    assert_eq!(Integrator::default(), Integrator::SemiImplicitEuler);
+
+    let (mut ctx, _) = build_simulation_context_from_source(SPRING_OSCILLATOR).unwrap();
+    let (mut manual, _) = build_simulation_context_from_source(SPRING_OSCILLATOR).unwrap();
+    for _ in 0..100 {
+        step_simulation(&mut ctx);
+        step_with(&mut manual.world, manual.dt, Integrator::SemiImplicitEuler);
+    }
+    for (a, b) in ctx.world.particles.iter().zip(&manual.world.particles) {
+        assert_eq!(a.pos, b.pos);
+        assert_eq!(a.vel, b.vel);
+    }
+}
@@ -0,0 +1,106 @@
+//! Tests for explicit vs semi-implicit Euler update ordering
+
+use physlang_core::{
+    build_simulation_context_from_source, parse_program, rebuild_simulation_context_from_source,
+    run_program, run_program_with_options, step_simulation, Integrator, RunOptions,
+};
+
+const OSCILLATOR: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.001 steps = 100000
+detect separation = distance(a, b)
+"#;
+
+/// Largest |separation − rest| seen over the first and last 10k steps
+fn amplitudes(integrator: Integrator) -> (f32, f32) {
+    let (mut ctx, _) = build_simulation_context_from_source(OSCILLATOR).unwrap();
+    ctx.integrator = integrator;
+    let (mut early, mut late) = (0.0f32, 0.0f32);
+    loop {
+        let finished = step_simulation(&mut ctx);
+        let stretch = (ctx.world.particles[0]
+            .pos
+            .distance(ctx.world.particles[1].pos)
+            - 2.0)
+            .abs();
+        if ctx.current_step <= 10_000 {
+            early = early.max(stretch);
+        } else if ctx.current_step > 90_000 {
+            late = late.max(stretch);
+        }
+        if finished {
+            break;
+        }
+    }
+    assert!(ctx.divergence.is_none());
+    (early, late)
+}
+
+#[test]
+fn test_semi_implicit_amplitude_bounded() {
+    let (early, late) = amplitudes(Integrator::SemiImplicitEuler);
+    assert!((early - 1.0).abs() < 0.01, "early amplitude {}", early);
+    assert!(
+        (late - early).abs() < 0.01,
+        "amplitude drifted from {} to {}",
+        early,
+        late
+    );
+}
+
+#[test]
+fn test_explicit_amplitude_grows() {
+    let (early, late) = amplitudes(Integrator::ExplicitEuler);
+    assert!(
+        late > 2.0 * early,
+        "amplitude only went from {} to {}",
+        early,
+        late
+    );
+}
+
+#[test]
+fn test_default_is_semi_implicit() {
+    let default = run_program(OSCILLATOR).unwrap();
+    let explicit = run_program_with_options(
+        OSCILLATOR,
+        &RunOptions {
+            integrator: Integrator::ExplicitEuler,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let semi_implicit = run_program_with_options(
+        OSCILLATOR,
+        &RunOptions {
+            integrator: Integrator::SemiImplicitEuler,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(default.state_hash, semi_implicit.state_hash);
+    assert_ne!(default.state_hash, explicit.state_hash);
+}
+
+#[test]
+fn test_integrator_names_round_trip() {
+    for method in Integrator::ALL {
+        assert_eq!(method.name().parse::<Integrator>(), Ok(method));
+    }
+    let err = "leapfrog".parse::<Integrator>().unwrap_err();
+    assert!(err.contains("explicit-euler"), "{}", err);
+}
+
+#[test]
+fn test_reload_keeps_integrator() {
+    let program = parse_program(OSCILLATOR).unwrap();
+    let (mut ctx, _) = build_simulation_context_from_source(OSCILLATOR).unwrap();
+    ctx.integrator = Integrator::Rk4;
+
+    let edited = OSCILLATOR.replace("k = 10.0", "k = 12.0");
+    let reload = rebuild_simulation_context_from_source(&edited, &program, ctx).unwrap();
+    assert_eq!(reload.ctx.integrator, Integrator::Rk4);
+}
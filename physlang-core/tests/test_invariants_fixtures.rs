@@ -0,0 +1,65 @@
+//! Invariant checks run over the example and golden fixtures
+
+use physlang_core::tests::invariants::{
+    assert_deterministic, assert_energy_bounded, assert_momentum_conserved, assert_no_nan,
+};
+use std::path::PathBuf;
+
+fn fixture(path: &str) -> String {
+    let mut full = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    full.push("..");
+    full.push(path);
+    std::fs::read_to_string(&full).unwrap_or_else(|e| panic!("{}: {}", full.display(), e))
+}
+
+#[test]
+fn test_simple_example_invariants() {
+    let source = fixture("examples/simple.phys");
+    assert_momentum_conserved(&source, 5000, 1e-3);
+    assert_no_nan(&source);
+    assert_deterministic(&source);
+}
+
+#[test]
+fn test_graph_layout_invariants() {
+    let source = fixture("examples/graph_layout.phys");
+    assert_momentum_conserved(&source, 2000, 1e-3);
+    assert_no_nan(&source);
+    assert_deterministic(&source);
+}
+
+#[test]
+fn test_golden_oscillator_invariants() {
+    let source = fixture("tests/golden/oscillator_test.phys");
+    assert_momentum_conserved(&source, 1000, 1e-4);
+    assert_energy_bounded(&source, 1000, 1.1);
+    assert_no_nan(&source);
+    assert_deterministic(&source);
+}
+
+#[test]
+fn test_golden_spring_equilibrium_invariants() {
+    let source = fixture("tests/golden/spring_equilibrium.phys");
+    assert_momentum_conserved(&source, 5000, 1e-4);
+    assert_energy_bounded(&source, 5000, 1.1);
+    assert_deterministic(&source);
+}
+
+#[test]
+fn test_loop_example_invariants() {
+    let source = fixture("examples/loop_example.phys");
+    assert_no_nan(&source);
+    assert_deterministic(&source);
+}
+
+#[test]
+#[should_panic(expected = "at step")]
+fn test_energy_bound_reports_failing_step() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+"#;
+    assert_energy_bounded(source, 100, 0.5);
+}
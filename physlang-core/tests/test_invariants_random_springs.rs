@@ -0,0 +1,55 @@
+//! Property test: random small spring networks conserve momentum
+
+use physlang_core::tests::invariants::{assert_momentum_conserved, assert_no_nan};
+use std::fmt::Write;
+
+const CASES: u64 = 64;
+
+/// A network of 2-6 particles joined by random springs, with random masses,
+/// positions, and velocities from `rng`
+fn spring_network(rng: &mut fastrand::Rng) -> String {
+    let count = rng.usize(2..=6);
+    let mut source = String::new();
+    for i in 0..count {
+        writeln!(
+            source,
+            "particle p{} at ({:.3}, {:.3}) mass {:.3}",
+            i,
+            rng.f32() * 10.0 - 5.0,
+            rng.f32() * 10.0 - 5.0,
+            0.5 + rng.f32() * 4.5
+        )
+        .unwrap();
+    }
+    for a in 0..count {
+        for b in a + 1..count {
+            if rng.bool() || b == a + 1 {
+                writeln!(
+                    source,
+                    "force spring(p{}, p{}) k = {:.3} rest = {:.3}",
+                    a,
+                    b,
+                    0.5 + rng.f32() * 9.5,
+                    0.5 + rng.f32() * 4.5
+                )
+                .unwrap();
+            }
+        }
+    }
+    source.push_str("simulate dt = 0.005 steps = 400\n");
+    source
+}
+
+#[test]
+fn test_random_spring_networks_conserve_momentum() {
+    for seed in 0..CASES {
+        let source = spring_network(&mut fastrand::Rng::with_seed(seed));
+        let outcome = std::panic::catch_unwind(|| {
+            assert_no_nan(&source);
+            assert_momentum_conserved(&source, 400, 1e-3);
+        });
+        if outcome.is_err() {
+            panic!("seed {} failed for program:\n{}", seed, source);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Tests for source lints and `apply_fixes`
+
+use physlang_core::{analyze_program, apply_fixes, lint_source, parse_program};
+
+const SOURCE: &str = r#"let k = 2.0
+let unused = 5.0
+
+fn pick(mode) {
+    match mode {
+        0 => {
+            particle c at (0.0, 0.0) mass 1.0
+        }
+    }
+}
+
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = k rest = 2.0
+pick(0)
+simulate dt = 0.01 steps = 10
+detect a_x = position(a)  # x only
+"#;
+
+const FIXED: &str = r#"let k = 2.0
+let unused = 5.0
+
+fn pick(mode) {
+    match mode {
+        0 => {
+            particle c at (0.0, 0.0) mass 1.0
+        }
+        _ => {
+        }
+    }
+}
+
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = k rest = 2.0
+pick(0)
+simulate dt = 0.01 steps = 10
+detect a_x = position(a).x  # x only
+"#;
+
+#[test]
+fn test_lints_in_source_order() {
+    let diagnostics = lint_source(SOURCE);
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "unused let binding 'unused'",
+            "match has no '_' arm; values matching no arm do nothing",
+            "bare position(a) detector is deprecated; it measures the x coordinate",
+        ]
+    );
+    for diagnostic in &diagnostics {
+        let span = diagnostic.span.unwrap();
+        assert!(!SOURCE[span.start..span.end].contains('\n'));
+    }
+    // Removing a let is only suggested, since `--set` may target it
+    let applicable: Vec<bool> = diagnostics
+        .iter()
+        .map(|d| d.fix.as_ref().unwrap().machine_applicable)
+        .collect();
+    assert_eq!(applicable, [false, true, true]);
+}
+
+#[test]
+fn test_apply_fixes() {
+    let diagnostics = lint_source(SOURCE);
+    let (fixed, applied) = apply_fixes(SOURCE, &diagnostics);
+    assert_eq!(fixed, FIXED);
+    assert_eq!(applied, [1, 2]);
+
+    // The result still parses, and only the suggestion is left
+    let program = parse_program(&fixed).unwrap();
+    assert_eq!(program.detectors.len(), 1);
+    let left: Vec<String> = lint_source(&fixed).into_iter().map(|d| d.message).collect();
+    assert_eq!(left, ["unused let binding 'unused'"]);
+    assert!(!analyze_program(&program).has_errors());
+}
+
+#[test]
+fn test_only_machine_applicable_fixes_apply() {
+    let mut diagnostics = lint_source(SOURCE);
+    for diagnostic in &mut diagnostics {
+        if diagnostic.message.starts_with("match") {
+            diagnostic.fix.as_mut().unwrap().machine_applicable = false;
+        }
+        if diagnostic.message.starts_with("unused") {
+            diagnostic.fix.as_mut().unwrap().machine_applicable = true;
+        }
+    }
+    let (fixed, applied) = apply_fixes(SOURCE, &diagnostics);
+    assert_eq!(applied, [0, 2]);
+    assert!(!fixed.contains("let unused = 5.0"));
+    assert!(!fixed.contains("_ =>"));
+}
+
+#[test]
+fn test_used_lets_and_wildcards_are_fine() {
+    let source = r#"let k = 2.0
+let rest = k * 2.0
+fn pick(mode) {
+    match mode {
+        _ => {
+        }
+    }
+}
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 1.0 rest = rest
+simulate dt = 0.01 steps = 10
+detect gap = distance(a, b)
+"#;
+    assert!(lint_source(source).is_empty(), "{:?}", lint_source(source));
+}
+
+#[test]
+fn test_let_usage_comes_from_references() {
+    // Each name appears more than once, but only `k` is read
+    let source = r#"let k = 2.0
+let g = 1.0
+let m = 3.0
+fn place(g) {
+    particle "c" at (g, 0.0) mass 1.0
+}
+particle a at (0.0, 0.0) mass 1.0
+particle m at (3.0, 0.0) mass 1.0
+force spring(a, m) k = k rest = 2.0
+place(0.5)
+simulate dt = 0.01 steps = 10
+detect gap = distance(a, m)
+"#;
+    parse_program(source).unwrap();
+    let messages: Vec<String> = lint_source(source).into_iter().map(|d| d.message).collect();
+    assert_eq!(
+        messages,
+        ["unused let binding 'g'", "unused let binding 'm'"]
+    );
+}
@@ -0,0 +1,92 @@
+//! Tests for the phase and declaration records sent through `log`
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use physlang_core::phase::PHASE_TARGET;
+use physlang_core::{parse_program, run_program};
+use std::sync::{Mutex, Once};
+
+/// Every record logged so far, as (level, target, message)
+static RECORDS: Mutex<Vec<(Level, String, String)>> = Mutex::new(Vec::new());
+
+struct Capture;
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        RECORDS.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        log::set_logger(&Capture).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+}
+
+fn logged(predicate: impl Fn(&(Level, String, String)) -> bool) -> bool {
+    RECORDS.lock().unwrap().iter().any(predicate)
+}
+
+const LOOP_PROGRAM: &str = r#"
+particle marker_a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 200
+loop for 2 cycles with frequency 1.0 damping 0.0 on marker_a {
+    force push(marker_a) magnitude 0.5 direction (1.0, 0.0)
+}
+detect marker_x = position(marker_a).x
+"#;
+
+#[test]
+fn test_run_logs_every_phase() {
+    install();
+    run_program(LOOP_PROGRAM).unwrap();
+
+    for phase in ["parse", "analyze", "functions", "build", "simulate"] {
+        let enter = format!("enter {}", phase);
+        let exit = format!("exit {} (", phase);
+        assert!(
+            logged(|(_, t, m)| t == PHASE_TARGET && *m == enter),
+            "no enter record for {}",
+            phase
+        );
+        assert!(
+            logged(|(_, t, m)| t == PHASE_TARGET && m.starts_with(&exit)),
+            "no exit record for {}",
+            phase
+        );
+    }
+    assert!(logged(
+        |(l, _, m)| *l == Level::Debug && m == "line 2: particle marker_a"
+    ));
+    assert!(logged(
+        |(l, _, m)| *l == Level::Debug && m.starts_with("loop on marker_a fired")
+    ));
+}
+
+#[test]
+fn test_parse_trace_variable_read_once() {
+    install();
+    parse_program("particle warmup at (0.0, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 1\n")
+        .unwrap();
+
+    // Set after the first parse, so the parser keeps tracing through `log`
+    std::env::set_var("PHYSLANG_PARSE_TRACE", "1");
+    parse_program("particle trace_marker at (0.0, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 1\n")
+        .unwrap();
+    std::env::remove_var("PHYSLANG_PARSE_TRACE");
+
+    assert!(logged(
+        |(l, _, m)| *l == Level::Trace && m.contains("particle trace_marker")
+    ));
+}
@@ -20,6 +20,8 @@ fn test_for_loop_iteration_count() {
             direction: Vec2::new(1.0, 0.0),
         }],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let mut particles = vec![Particle {
@@ -27,6 +29,7 @@ fn test_for_loop_iteration_count() {
         pos: Vec2::ZERO,
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let dt = 0.01;
@@ -79,6 +82,8 @@ fn test_for_loop_body_triggers_once_per_cycle() {
             direction: Vec2::new(1.0, 0.0),
         }],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let mut particles = vec![Particle {
@@ -86,6 +91,7 @@ fn test_for_loop_body_triggers_once_per_cycle() {
         pos: Vec2::ZERO,
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let dt = 0.01;
@@ -128,6 +134,8 @@ fn test_while_loop_stops_when_condition_false() {
             direction: Vec2::new(1.0, 0.0),
         }],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let mut particles = vec![Particle {
@@ -135,6 +143,7 @@ fn test_while_loop_stops_when_condition_false() {
         pos: Vec2::new(0.0, 0.0),
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let dt = 0.01;
@@ -181,6 +190,8 @@ fn test_inactive_loop_does_not_iterate() {
             direction: Vec2::new(1.0, 0.0),
         }],
         active: false, // Inactive
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let mut particles = vec![Particle {
@@ -188,6 +199,7 @@ fn test_inactive_loop_does_not_iterate() {
         pos: Vec2::ZERO,
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let initial_vel = particles[0].vel;
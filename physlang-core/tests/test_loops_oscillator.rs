@@ -16,6 +16,8 @@ fn test_phase_accumulation() {
         },
         body: vec![],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let dt = 0.1;
@@ -49,6 +51,8 @@ fn test_phase_wrap() {
         },
         body: vec![],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let dt = 0.2;
@@ -87,6 +91,8 @@ fn test_phase_wrap_exactly_2pi() {
         },
         body: vec![],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let dt = 0.02;
@@ -124,6 +130,8 @@ fn test_damping_reduces_phase() {
         },
         body: vec![],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let dt = 0.1;
@@ -159,6 +167,8 @@ fn test_damping_prevents_negative() {
         },
         body: vec![],
         active: true,
+        target_name: "a".to_string(),
+        label: None,
     };
     
     let dt = 0.1;
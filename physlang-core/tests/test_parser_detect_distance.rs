@@ -16,7 +16,7 @@ detect dist_ab = distance(a, b)
     let program = result.unwrap();
     assert_eq!(program.detectors.len(), 1);
     assert_eq!(program.detectors[0].name, "dist_ab");
-    match &program.detectors[0].kind {
+    match program.detectors[0].kind().unwrap() {
         physlang_core::ast::DetectorKind::Distance { a, b } => {
             assert_eq!(a, "a");
             assert_eq!(b, "b");
@@ -41,7 +41,7 @@ detect dist_bc = distance(b, c)
     
     let program = result.unwrap();
     assert_eq!(program.detectors.len(), 3);
-    match &program.detectors[0].kind {
+    match program.detectors[0].kind().unwrap() {
         physlang_core::ast::DetectorKind::Distance { a, b } => {
             assert_eq!(a, "a");
             assert_eq!(b, "b");
@@ -64,11 +64,11 @@ detect dist_ab = distance(a, b)
     
     let program = result.unwrap();
     assert_eq!(program.detectors.len(), 2);
-    match &program.detectors[0].kind {
+    match program.detectors[0].kind().unwrap() {
         physlang_core::ast::DetectorKind::Position(_) => {}
         _ => panic!("First detector should be position"),
     }
-    match &program.detectors[1].kind {
+    match program.detectors[1].kind().unwrap() {
         physlang_core::ast::DetectorKind::Distance { .. } => {}
         _ => panic!("Second detector should be distance"),
     }
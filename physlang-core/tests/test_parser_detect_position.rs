@@ -15,7 +15,7 @@ detect a_pos = position(a)
     let program = result.unwrap();
     assert_eq!(program.detectors.len(), 1);
     assert_eq!(program.detectors[0].name, "a_pos");
-    match &program.detectors[0].kind {
+    match program.detectors[0].kind().unwrap() {
         physlang_core::ast::DetectorKind::Position(name) => {
             assert_eq!(name, "a");
         }
@@ -52,7 +52,7 @@ detect my_detector = position(my_particle)
     assert!(result.is_ok());
     
     let program = result.unwrap();
-    match &program.detectors[0].kind {
+    match program.detectors[0].kind().unwrap() {
         physlang_core::ast::DetectorKind::Position(name) => {
             assert_eq!(name, "my_particle");
         }
@@ -0,0 +1,55 @@
+//! Tests for the file a parse error reports
+
+use physlang_core::{parse_program, run_program, ParseError};
+use std::path::{Path, PathBuf};
+
+const BROKEN: &str = "particle a at (0.0, 0.0) mass 1.0\nforce spring(a, b k = 1.0\n";
+
+#[test]
+fn test_file_is_unset_by_default() {
+    let error = parse_program(BROKEN).unwrap_err();
+    assert_eq!(error.file(), None);
+    assert!(error.format_detailed().contains("\n  --> line 2\n"));
+}
+
+#[test]
+fn test_detailed_format_names_the_file() {
+    let error = parse_program(BROKEN)
+        .unwrap_err()
+        .with_file("models/broken.phys");
+    assert_eq!(error.file(), Some(Path::new("models/broken.phys")));
+    assert_eq!(error.line_number(), Some(2));
+    assert!(error
+        .format_detailed()
+        .contains("\n  --> models/broken.phys:2\n  | force spring(a, b k = 1.0"));
+
+    // Errors without a line still say which file they came from
+    let error = ParseError::message("Missing 'simulate' declaration").with_file("a.phys");
+    assert_eq!(
+        error.format_detailed(),
+        "Missing 'simulate' declaration\n  --> a.phys"
+    );
+}
+
+#[test]
+fn test_run_program_keeps_the_structured_error() {
+    let error = run_program(BROKEN).unwrap_err();
+    let parse_error = error.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(parse_error.line_number(), Some(2));
+    assert!(parse_error.span().is_some());
+    assert!(std::error::Error::source(parse_error).is_none());
+}
+
+#[test]
+fn test_file_is_a_syntax_error_field() {
+    let error = parse_program(BROKEN).unwrap_err().with_file("broken.phys");
+    let ParseError::SyntaxError {
+        line_number,
+        line_content,
+        file,
+        ..
+    } = error;
+    assert_eq!(line_number, Some(2));
+    assert_eq!(line_content.as_deref(), Some("force spring(a, b k = 1.0"));
+    assert_eq!(file, Some(PathBuf::from("broken.phys")));
+}
@@ -0,0 +1,70 @@
+//! Parser tests for statements a physics loop body doesn't support, which
+//! used to be dropped without a word
+
+use physlang_core::parse_program;
+
+const LOOP: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+loop for 5 cycles with frequency 1.0 damping 0.0 on a {
+    # Kick to the right
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+";
+
+/// The message and line of the error parsing `LOOP` with `line` in its body
+fn body_error(line: &str) -> (String, usize) {
+    let source = LOOP.replace("    # Kick to the right", line);
+    let error = parse_program(&source).unwrap_err();
+    let line = error.span().unwrap().to_location(&source).line;
+    (error.to_string(), line)
+}
+
+#[test]
+fn test_supported_body_parses() {
+    let program = parse_program(LOOP).unwrap();
+    assert_eq!(program.loops[0].body.len(), 1);
+}
+
+#[test]
+fn test_typo_in_push_is_an_error() {
+    let (message, line) = body_error("    forcepush(a) magnitude 0.5 direction (1.0, 0.0)");
+    assert_eq!(
+        message,
+        "Unsupported statement in loop body: forcepush(a) magnitude 0.5 direction (1.0, 0.0); \
+         a physics loop body may only contain 'force push(<particle>) magnitude <m> \
+         direction (<x>, <y>)' statements and comments"
+    );
+    assert_eq!(line, 4);
+}
+
+#[test]
+fn test_let_in_body_is_an_error() {
+    let (message, line) = body_error("    let strength = 0.5");
+    assert!(
+        message.starts_with("Unsupported statement in loop body: let strength = 0.5;"),
+        "{}",
+        message
+    );
+    assert_eq!(line, 4);
+}
+
+#[test]
+fn test_nested_block_in_body_is_an_error() {
+    let (message, line) = body_error(
+        "    if 1 > 0 {\n        force push(a) magnitude 0.5 direction (1.0, 0.0)\n    }",
+    );
+    assert!(
+        message.starts_with("Unsupported statement in loop body: if 1 > 0 {;"),
+        "{}",
+        message
+    );
+    assert_eq!(line, 4);
+}
+
+#[test]
+fn test_unclosed_body_is_still_reported() {
+    let source = LOOP.trim_end().trim_end_matches('}');
+    let error = parse_program(source).unwrap_err();
+    assert_eq!(error.to_string(), "Unclosed loop body");
+}
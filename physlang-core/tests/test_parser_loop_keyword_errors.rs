@@ -0,0 +1,101 @@
+//! Parser tests for the errors of loops written with the wrong keyword: a
+//! general for loop after `loop`, or a `while` without it
+
+use physlang_core::parse_program;
+
+const LOOP_FOR_RANGE: &str = "\
+simulate dt = 0.01 steps = 10
+loop for i in 0..5 {
+    particle p at (i, 0.0) mass 1.0
+}
+";
+
+const BARE_WHILE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+while position(a).x < 5.0 {
+    force push(a) magnitude 0.5 direction (1.0, 0.0)
+}
+";
+
+fn line_of(source: &str, error: &physlang_core::ParseError) -> usize {
+    error.span().unwrap().to_location(source).line
+}
+
+#[test]
+fn test_loop_for_over_a_range_suggests_plain_for() {
+    let error = parse_program(LOOP_FOR_RANGE).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "'loop for' declares a physics oscillator; for iteration over a range, \
+         write 'for i in 0..5 { ... }' without the 'loop' keyword"
+    );
+    assert_eq!(line_of(LOOP_FOR_RANGE, &error), 2);
+}
+
+#[test]
+fn test_loop_for_in_function_body_suggests_plain_for() {
+    let source = "\
+fn row(n) {
+    loop for j in 0..n {
+        particle p at (j, 0.0) mass 1.0
+    }
+}
+simulate dt = 0.01 steps = 10
+";
+    let error = parse_program(source).unwrap_err();
+    assert!(
+        error.to_string().contains("write 'for j in 0..n { ... }'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_oscillator_loop_keeps_its_errors() {
+    let source = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+loop for 5 with frequency 1.0 damping 0.0 on a {
+}
+";
+    let error = parse_program(source).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Expected 'cycles' in for loop"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_bare_while_suggests_loop_while() {
+    let error = parse_program(BARE_WHILE).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "'while' is only available as a physics loop: write 'loop while position(a).x < 5.0 \
+         with frequency <f> damping <d> on <particle> { ... }', or 'for i in 0..n { ... }' \
+         to repeat statements"
+    );
+    assert_eq!(line_of(BARE_WHILE, &error), 3);
+}
+
+#[test]
+fn test_bare_while_in_function_body() {
+    let source = "\
+fn spin(n) {
+    while n > 0 {
+    }
+}
+simulate dt = 0.01 steps = 10
+";
+    let error = parse_program(source).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("write 'loop while n > 0 with frequency"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,120 @@
+//! Tests for the REPL session behind `physlang repl`
+
+use physlang_core::{ReplReply, ReplSession};
+
+/// Feed `lines` to a fresh session and collect the non-empty outputs, with
+/// errors prefixed by `error: `
+fn script(lines: &[&str]) -> Vec<String> {
+    let mut session = ReplSession::new();
+    let mut outputs = Vec::new();
+    for line in lines {
+        match session.handle_line(line) {
+            Ok(ReplReply::Output(text)) if text.is_empty() => {}
+            Ok(ReplReply::Output(text)) => outputs.push(text),
+            Ok(ReplReply::Incomplete) => {}
+            Ok(ReplReply::Quit) => outputs.push("<quit>".to_string()),
+            Err(message) => outputs.push(format!("error: {}", message)),
+        }
+    }
+    outputs
+}
+
+#[test]
+fn test_expressions_see_let_bindings() {
+    let outputs = script(&["1.0 + 2.0 * 3.0", "let k = 2.0", "k * 3.0", "sqrt(k * 8.0)"]);
+    assert_eq!(outputs, ["7", "6", "4"]);
+}
+
+#[test]
+fn test_run_show_and_detect() {
+    let outputs = script(&[
+        "particle a at (0.0, 0.0) mass 1.0",
+        "particle b at (3.0, 0.0) mass 1.0",
+        ":show",
+        ":detect distance(a, b)",
+        "force spring(a, b) k = 2.0 rest = 2.0",
+        ":run 100",
+        ":run 50",
+        ":detect time()",
+    ]);
+    assert_eq!(
+        outputs[0],
+        "t = 0 after 0 steps\na: pos = (0, 0) vel = (0, 0)\nb: pos = (3, 0) vel = (0, 0)"
+    );
+    assert_eq!(outputs[1], "3");
+    assert!(outputs[2].ends_with("after 100 steps"), "{}", outputs[2]);
+    // Stepping continues the same simulation until the program changes
+    assert!(outputs[3].ends_with("after 150 steps"), "{}", outputs[3]);
+    let time: f32 = outputs[4].parse().unwrap();
+    assert!((time - 1.5).abs() < 1e-4);
+}
+
+#[test]
+fn test_declarations_restart_the_simulation() {
+    let outputs = script(&[
+        "particle a at (0.0, 0.0) mass 1.0",
+        ":run 10",
+        "particle b at (1.0, 0.0) mass 1.0",
+        ":run 10",
+    ]);
+    assert_eq!(outputs[1], outputs[0]);
+}
+
+#[test]
+fn test_multi_line_function_and_call() {
+    let mut session = ReplSession::new();
+    assert_eq!(
+        session.handle_line("fn pair(p, x) {").unwrap(),
+        ReplReply::Incomplete
+    );
+    assert_eq!(
+        session
+            .handle_line("    particle p at (x, 0.0) mass 1.0")
+            .unwrap(),
+        ReplReply::Incomplete
+    );
+    assert_eq!(
+        session.handle_line("}").unwrap(),
+        ReplReply::Output(String::new())
+    );
+    session.handle_line("pair(\"a\", 2.0)").unwrap();
+
+    match session.handle_line(":detect position(a)").unwrap() {
+        ReplReply::Output(text) => assert_eq!(text, "2"),
+        other => panic!("unexpected reply {:?}", other),
+    }
+}
+
+#[test]
+fn test_rejected_declarations_leave_the_session_unchanged() {
+    let mut session = ReplSession::new();
+    session
+        .handle_line("particle a at (0.0, 0.0) mass 1.0")
+        .unwrap();
+    let before = session.source();
+
+    let error = session
+        .handle_line("force spring(a, ghost) k = 1.0 rest = 1.0")
+        .unwrap_err();
+    assert!(error.contains("ghost"), "{}", error);
+    assert!(session.handle_line("particle c at").is_err());
+    assert_eq!(session.source(), before);
+}
+
+#[test]
+fn test_simulate_replaces_and_reset_clears() {
+    let outputs = script(&[
+        "let k = 1.0",
+        "simulate dt = 0.1 steps = 5",
+        "simulate dt = 0.5 steps = 5",
+        ":source",
+        ":reset",
+        "k",
+        ":bogus",
+        ":quit",
+    ]);
+    assert_eq!(outputs[0], "let k = 1.0\nsimulate dt = 0.5 steps = 5");
+    assert!(outputs[1].starts_with("error: "), "{}", outputs[1]);
+    assert!(outputs[2].contains("unknown command ':bogus'"));
+    assert_eq!(outputs[3], "<quit>");
+}
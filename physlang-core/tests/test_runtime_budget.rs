@@ -0,0 +1,74 @@
+//! Tests for the wall-clock and step budget guard in run_program_with_options
+
+use physlang_core::{run_program, run_program_with_options, RunError, RunOptions};
+use std::time::Duration;
+
+const LONG_RUN: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.001 steps = 100000000
+detect gap = distance(a, b)
+"#;
+
+const SHORT_RUN: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+"#;
+
+#[test]
+fn test_timeout_interrupts_long_simulation() {
+    let options = RunOptions {
+        timeout: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    let err = run_program_with_options(LONG_RUN, &options).expect_err("should exceed budget");
+
+    match err.downcast_ref::<RunError>() {
+        Some(RunError::BudgetExceeded {
+            steps_completed,
+            elapsed,
+            detectors,
+        }) => {
+            assert!(*steps_completed > 0);
+            assert!(*steps_completed < 100_000_000);
+            assert!(*elapsed >= Duration::from_millis(10));
+            assert_eq!(detectors.len(), 1);
+            assert_eq!(detectors[0].name, "gap");
+            assert!(detectors[0].value.is_finite());
+        }
+        other => panic!("expected BudgetExceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_step_budget_stops_at_limit() {
+    let options = RunOptions {
+        max_steps: Some(250),
+        ..Default::default()
+    };
+    let err = run_program_with_options(LONG_RUN, &options).expect_err("should exceed budget");
+
+    match err.downcast_ref::<RunError>() {
+        Some(RunError::BudgetExceeded { steps_completed, .. }) => {
+            assert_eq!(*steps_completed, 250);
+        }
+        other => panic!("expected BudgetExceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_short_run_unaffected_by_budget() {
+    let options = RunOptions {
+        timeout: Some(Duration::from_secs(30)),
+        max_steps: Some(100),
+        ..Default::default()
+    };
+    let budgeted = run_program_with_options(SHORT_RUN, &options).expect("short run should finish");
+    let unbudgeted = run_program(SHORT_RUN).unwrap();
+
+    assert_eq!(budgeted.detectors[0].value, unbudgeted.detectors[0].value);
+}
@@ -0,0 +1,110 @@
+//! Tests for the source locations of analysis and build errors
+
+use physlang_core::{
+    analyze_program, build_simulation_context, evaluate_lets, parse_program, run_program,
+    BuildError, Pipeline, RunOptions,
+};
+
+/// A typo in a spring stiffness, on line 4
+const TYPO: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (1.0, 0.0) mass 1.0
+
+force spring(a, b) k = stiffnes rest = 1.0
+simulate dt = 0.01 steps = 10
+";
+
+const GENERATED: &str = "\
+fn pair(p, q) {
+    particle p at (0.0, 0.0) mass 1.0
+    particle q at (1.0, 0.0) mass 1.0
+}
+pair(\"a\", \"b\")
+pair(\"a\", \"c\", 2.0)
+simulate dt = 0.01 steps = 10
+";
+
+fn line_of(error: &BuildError, source: &str) -> usize {
+    error.span.to_location(source).line
+}
+
+#[test]
+fn test_analysis_locates_the_typo() {
+    let program = parse_program(TYPO).unwrap();
+    let diagnostics = analyze_program(&program);
+    let error = diagnostics.errors().next().unwrap();
+    assert_eq!(error.message, "unknown variable 'stiffnes'");
+    assert_eq!(error.span.unwrap().to_location(TYPO).line, 4);
+}
+
+#[test]
+fn test_build_reports_the_line_of_the_typo() {
+    // Skip analysis so the typo reaches the build
+    let pipeline = Pipeline::parse(TYPO).unwrap();
+    let error = pipeline.build(&RunOptions::default()).err().unwrap();
+    let error = error.downcast_ref::<BuildError>().unwrap();
+    assert_eq!(error.line, Some(4));
+    assert_eq!(line_of(error, TYPO), 4);
+    assert_eq!(
+        error.to_string(),
+        "Error evaluating spring k: unknown variable 'stiffnes' at line 4"
+    );
+}
+
+#[test]
+fn test_build_without_source_keeps_the_span() {
+    let program = parse_program(TYPO).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let error = build_simulation_context(&program, &eval_ctx).err().unwrap();
+    let error = error.downcast_ref::<BuildError>().unwrap();
+    assert_eq!(error.line, None);
+    assert_eq!(line_of(error, TYPO), 4);
+    assert!(!error.to_string().contains("at line"));
+}
+
+#[test]
+fn test_run_names_the_line_of_an_eval_error() {
+    let source = "let zero = 0.0\n".to_string() + &TYPO.replace("stiffnes", "1.0 / zero");
+    let message = run_program(&source).unwrap_err().to_string();
+    assert_eq!(
+        message,
+        "Error evaluating spring k: division by zero at line 5"
+    );
+}
+
+#[test]
+fn test_function_call_errors_point_at_the_call() {
+    let mut pipeline = Pipeline::parse(GENERATED).unwrap();
+    assert!(pipeline.analyze().is_err());
+    let error = pipeline.diagnostics().errors().next().unwrap();
+    assert_eq!(error.span.unwrap().to_location(GENERATED).line, 6);
+}
+
+#[test]
+fn test_spans_do_not_affect_equality() {
+    let indented = TYPO.replace("force", "    force");
+    assert_eq!(
+        parse_program(TYPO).unwrap(),
+        parse_program(&indented).unwrap()
+    );
+}
+
+#[test]
+fn test_ground_and_simulate_errors_point_at_their_lines() {
+    let ground = "particle a at (0.0, 1.0) mass 1.0\nground y = 0.0 friction -0.5\n\
+                  simulate dt = 0.01 steps = 10\n";
+    let program = parse_program(ground).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let error = build_simulation_context(&program, &eval_ctx).err().unwrap();
+    let error = error.downcast_ref::<BuildError>().unwrap();
+    assert_eq!(error.message, "ground friction must be >= 0, got -0.5");
+    assert_eq!(line_of(error, ground), 2);
+
+    let simulate = "particle a at (0.0, 0.0) mass 1.0\n\nsimulate dt = 0.01 steps = 0\n";
+    let program = parse_program(simulate).unwrap();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let error = build_simulation_context(&program, &eval_ctx).err().unwrap();
+    let error = error.downcast_ref::<BuildError>().unwrap();
+    assert_eq!(error.message, "steps must be an integer >= 1, got 0");
+    assert_eq!(line_of(error, simulate), 3);
+}
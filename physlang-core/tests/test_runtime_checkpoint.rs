@@ -0,0 +1,54 @@
+//! Tests for rewinding a simulation context to a checkpoint
+
+use physlang_core::{build_simulation_context_from_source, step_simulation};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+force spring(a, b) k = 1.0 + time rest = 2.0
+simulate dt = 0.01 steps = 300
+loop for 5 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+well target on b if position(b).x >= 4.0 depth 10.0
+detect gap = distance(a, b)
+"#;
+
+fn step_to(ctx: &mut physlang_core::SimulationContext, step: usize) {
+    while ctx.current_step < step {
+        step_simulation(ctx);
+    }
+}
+
+#[test]
+fn test_restoring_a_checkpoint_repeats_the_run() {
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    step_to(&mut ctx, 120);
+    let checkpoint = ctx.checkpoint();
+    assert_eq!(checkpoint.step(), 120);
+    step_to(&mut ctx, 250);
+    let expected = ctx.state_hash();
+    let expected_cycles = format!("{:?}", ctx.loops[0].kind);
+
+    // Rewind and replay the same steps
+    ctx.restore(&checkpoint).unwrap();
+    assert_eq!(ctx.current_step, 120);
+    assert!((ctx.time - 1.2).abs() < 1e-4);
+    step_to(&mut ctx, 250);
+    assert_eq!(ctx.state_hash(), expected);
+    assert_eq!(format!("{:?}", ctx.loops[0].kind), expected_cycles);
+}
+
+#[test]
+fn test_restore_matches_a_fresh_run_to_the_same_step() {
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    let start = ctx.checkpoint();
+    step_to(&mut ctx, 200);
+    ctx.restore(&start).unwrap();
+    step_to(&mut ctx, 80);
+
+    let (mut fresh, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    step_to(&mut fresh, 80);
+    assert_eq!(ctx.state_hash(), fresh.state_hash());
+}
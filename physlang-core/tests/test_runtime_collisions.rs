@@ -0,0 +1,124 @@
+//! Tests for collision resolution, collision events, and the collision_count detector
+
+use glam::Vec2;
+use physlang_core::runtime::evaluate_detectors;
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    build_simulation_context_from_source, parse_program, run_program, step_simulation,
+    COLLISION_EVENT_CAPACITY,
+};
+
+const HEAD_ON: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (4.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 300
+detect hits = collision_count(a, b)
+"#;
+
+#[test]
+fn test_head_on_collision_swaps_velocities() {
+    let (mut ctx, _) = build_simulation_context_from_source(HEAD_ON).unwrap();
+    ctx.world.set_collision_radius(0.5);
+    ctx.world.particles[0].vel = Vec2::new(2.0, 0.0);
+    ctx.world.particles[1].vel = Vec2::new(-1.0, 0.0);
+    let momentum = ctx.world.total_momentum();
+
+    while !step_simulation(&mut ctx) {}
+
+    // Equal masses exchange velocities in an elastic collision
+    assert_eq!(ctx.world.particles[0].vel, Vec2::new(-1.0, 0.0));
+    assert_eq!(ctx.world.particles[1].vel, Vec2::new(2.0, 0.0));
+    assert_eq!(ctx.world.total_momentum(), momentum);
+
+    let events = ctx.drain_collision_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].a, events[0].b), (0, 1));
+    assert!(approx_eq_f32(events[0].impulse, 3.0, 1e-5));
+    // Gap of 3 closes at 3 units/s: contact after ~1s = step 100
+    assert!((99..=101).contains(&events[0].step), "{}", events[0].step);
+
+    assert!(ctx.drain_collision_events().is_empty());
+    assert_eq!(ctx.world.collision_count(1, 0), 1);
+}
+
+#[test]
+fn test_collision_count_detector() {
+    // A light particle bouncing between two pinned walls
+    let source = r#"
+particle left at (0.0, 0.0) mass 1.0
+particle ball at (1.0, 0.0) mass 1.0
+particle right at (2.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 1000
+detect left_hits = collision_count(ball, left)
+detect right_hits = collision_count(right, ball)
+detect none = collision_count(left, right)
+"#;
+    let program = parse_program(source).unwrap();
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.world.set_collision_radius(0.1);
+    ctx.world.pin(0);
+    ctx.world.pin(2);
+    ctx.world.particles[1].vel = Vec2::new(1.6, 0.0);
+
+    while !step_simulation(&mut ctx) {}
+    let mut warnings = Vec::new();
+    let detectors = evaluate_detectors(&program, &ctx.world, ctx.time, &mut warnings).unwrap();
+
+    // 10s at 1.6 units/s over a 1.6-unit round trip: 10 bounces, 5 per wall
+    assert_eq!(detectors[0].value, 5.0);
+    assert_eq!(detectors[1].value, 5.0);
+    assert_eq!(detectors[2].value, 0.0);
+    assert_eq!(ctx.world.particles[0].pos, Vec2::ZERO);
+    assert!(approx_eq_f32(
+        ctx.world.particles[1].vel.length(),
+        1.6,
+        1e-5
+    ));
+}
+
+#[test]
+fn test_no_collisions_without_radius() {
+    let (mut ctx, _) = build_simulation_context_from_source(HEAD_ON).unwrap();
+    ctx.world.particles[0].vel = Vec2::new(2.0, 0.0);
+    ctx.world.particles[1].vel = Vec2::new(-1.0, 0.0);
+    while !step_simulation(&mut ctx) {}
+    assert!(ctx.drain_collision_events().is_empty());
+
+    let result = run_program(HEAD_ON).unwrap();
+    assert_eq!(result.detectors[0].value, 0.0);
+}
+
+#[test]
+fn test_event_buffer_is_bounded() {
+    let source = r#"
+particle left at (0.0, 0.0) mass 1.0
+particle ball at (0.5, 0.0) mass 1.0
+particle right at (1.0, 0.0) mass 1.0
+simulate dt = 0.001 steps = 100000
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    ctx.world.set_collision_radius(0.1);
+    ctx.world.pin(0);
+    ctx.world.pin(2);
+    ctx.world.particles[1].vel = Vec2::new(10.0, 0.0);
+    while !step_simulation(&mut ctx) {}
+
+    let total = ctx.world.collision_count(0, 1) + ctx.world.collision_count(1, 2);
+    assert!(total > COLLISION_EVENT_CAPACITY, "{}", total);
+
+    let events = ctx.drain_collision_events();
+    assert_eq!(events.len(), COLLISION_EVENT_CAPACITY);
+    // The newest events are kept, oldest first
+    assert!(events.windows(2).all(|w| w[0].step < w[1].step));
+    assert!(events.last().unwrap().step > 99_000);
+}
+
+#[test]
+fn test_unknown_particle_in_collision_detector() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+detect hits = collision_count(a, ghost)
+"#;
+    assert!(run_program(source).is_err());
+}
@@ -0,0 +1,167 @@
+//! Tests for detectors written as expressions over observables
+
+use physlang_core::ast::{DetectorKind, Expr};
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, format_source, parse_program,
+    run_program, step_simulation,
+};
+
+/// Two particles at rest, 5 apart, with nothing pulling them together
+const AT_REST: &str = "\
+let rest = 1.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 4.0) mass 1.0
+simulate dt = 0.01 steps = 10
+";
+
+fn detector_value(source: &str, name: &str) -> f32 {
+    let result = run_program(source).unwrap();
+    result
+        .detectors
+        .iter()
+        .find(|d| d.name == name)
+        .unwrap()
+        .value
+}
+
+fn analysis_errors(source: &str) -> Vec<String> {
+    let program = parse_program(source).unwrap();
+    analyze_program(&program)
+        .errors()
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn test_arithmetic_on_two_observables() {
+    let source = format!(
+        "{}detect area = distance(a, b) * distance(a, b)\n\
+         detect rise = position(b).y - position(a).y\n",
+        AT_REST
+    );
+    assert!(analysis_errors(&source).is_empty());
+    assert!((detector_value(&source, "area") - 25.0).abs() < 1e-4);
+    assert!((detector_value(&source, "rise") - 4.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_detector_reading_a_let() {
+    let source = format!("{}detect stretch = distance(a, b) - rest\n", AT_REST);
+    assert!(analysis_errors(&source).is_empty());
+    assert!((detector_value(&source, "stretch") - 4.0).abs() < 1e-4);
+
+    // Mid-run values read the same let
+    let (ctx, _) = build_simulation_context_from_source(&source).unwrap();
+    let values = ctx.detector_values().unwrap();
+    assert!((values[0].value - 4.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_legacy_forms_parse_to_single_observables() {
+    let source = format!(
+        "{}detect bare = position(a)\n\
+         detect x = position(a).x\n\
+         detect y = position(b).y\n\
+         detect ke = kinetic_energy() initial\n",
+        AT_REST
+    );
+    let program = parse_program(&source).unwrap();
+    let kinds: Vec<_> = program.detectors.iter().map(|d| d.kind()).collect();
+    assert_eq!(kinds[0], Some(&DetectorKind::Position("a".to_string())));
+    assert_eq!(kinds[1], Some(&DetectorKind::Position("a".to_string())));
+    assert_eq!(kinds[2], Some(&DetectorKind::PositionY("b".to_string())));
+    assert_eq!(kinds[3], Some(&DetectorKind::KineticEnergy));
+    assert!(program.detectors[3].initial);
+    assert_eq!(detector_value(&source, "y"), 4.0);
+}
+
+#[test]
+fn test_velocity_components() {
+    let source = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0
+force gravity(a, b) G = 1.0
+simulate dt = 0.01 steps = 10
+detect vx = velocity(a).x
+detect vy = velocity(a).y * 2.0
+";
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    step_simulation(&mut ctx);
+    let values = ctx.detector_values().unwrap();
+    let vel = ctx.world.particles[0].vel;
+    assert!(vel.x > 0.0);
+    assert_eq!(values[0].value, vel.x);
+    assert_eq!(values[1].value, vel.y * 2.0);
+}
+
+#[test]
+fn test_center_of_mass_components() {
+    let source = format!(
+        "{}detect cx = center_of_mass()
+detect cx2 = center_of_mass().x
+\
+         detect cy = center_of_mass().y
+",
+        AT_REST
+    );
+    assert_eq!(detector_value(&source, "cx"), 1.5);
+    assert_eq!(detector_value(&source, "cx2"), 1.5);
+    assert_eq!(detector_value(&source, "cy"), 2.0);
+
+    let program = parse_program(&source).unwrap();
+    let kinds: Vec<_> = program.detectors.iter().map(|d| d.kind()).collect();
+    assert_eq!(kinds[0], Some(&DetectorKind::CenterOfMass));
+    assert_eq!(kinds[1], Some(&DetectorKind::CenterOfMass));
+    assert_eq!(kinds[2], Some(&DetectorKind::CenterOfMassY));
+    let formatted = format_source(&source).unwrap();
+    assert!(formatted.contains("detect cy = center_of_mass().y\n"));
+}
+
+#[test]
+fn test_analysis_checks_every_observable() {
+    let source = format!("{}detect d = distance(a, b) + position(ghost).x\n", AT_REST);
+    assert_eq!(
+        analysis_errors(&source),
+        ["unknown particle 'ghost' in detector"]
+    );
+
+    let source = format!("{}detect d = distance(a, b) * scale\n", AT_REST);
+    assert_eq!(analysis_errors(&source), ["unknown variable 'scale'"]);
+}
+
+#[test]
+fn test_observables_only_in_detectors() {
+    let source = format!("{}particle c at (1.0, 0.0) mass position(b).x\n", AT_REST);
+    assert_eq!(
+        analysis_errors(&source),
+        ["observable 'position' can only be read by a detector"]
+    );
+}
+
+#[test]
+fn test_malformed_observables() {
+    for (detector, message) in [
+        ("velocity(a)", "Expected '.x' or '.y' after velocity(...)"),
+        ("position(a).z", "Expected '.x' or '.y' after position(...)"),
+        ("center_of_mass().z", "Expected '.x' or '.y' after center_of_mass(...)"),
+        ("distance(a)", "Expected 2 particle names in distance(...)"),
+        ("time().x", "Unexpected '.x' after time(...)"),
+    ] {
+        let source = format!("{}detect d = {}\n", AT_REST, detector);
+        let error = parse_program(&source).unwrap_err();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+}
+
+#[test]
+fn test_format_prints_detector_expressions() {
+    let source = format!(
+        "{}detect d = distance(a,b) - rest\ndetect p = position(a)\n",
+        AT_REST
+    );
+    let formatted = format_source(&source).unwrap();
+    assert!(formatted.contains("detect d = distance(a, b) - rest\n"));
+    assert!(formatted.contains("detect p = position(a)\n"));
+    let program = parse_program(&formatted).unwrap();
+    assert!(matches!(program.detectors[0].expr, Expr::Binary { .. }));
+}
@@ -0,0 +1,52 @@
+//! Tests for reading detector values mid-run from a simulation context
+
+use physlang_core::{build_simulation_context_from_source, run_program, step_simulation};
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (2.0, 0.0) mass 1.0
+force spring(a, b) k = 4.0 rest = 1.0
+simulate dt = 0.01 steps = 50
+detect a_x = position(a).x
+detect gap = distance(a, b)
+detect t = time()
+";
+
+#[test]
+fn test_detector_values_follow_the_simulation() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+
+    let start = ctx.detector_values().unwrap();
+    let names: Vec<&str> = start.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["a_x", "gap", "t"]);
+    assert_eq!(start[1].value, 2.0);
+    assert_eq!(start[2].value, 0.0);
+
+    for _ in 0..10 {
+        step_simulation(&mut ctx);
+    }
+    let mid = ctx.detector_values().unwrap();
+    // The stretched spring pulls the pair together
+    assert!(mid[0].value > 0.0);
+    assert!(mid[1].value < 2.0);
+    assert!((mid[2].value - 0.1).abs() < 1e-5);
+}
+
+#[test]
+fn test_detector_values_at_the_end_match_run() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    while !step_simulation(&mut ctx) {}
+    let live = ctx.detector_values().unwrap();
+    let result = run_program(SOURCE).unwrap();
+    for (live, run) in live.iter().zip(&result.detectors) {
+        assert_eq!(live.name, run.name);
+        assert_eq!(live.value, run.value);
+    }
+}
+
+#[test]
+fn test_detector_values_without_detectors() {
+    let source = "particle a at (0.0, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 1\n";
+    let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+    assert!(ctx.detector_values().unwrap().is_empty());
+}
@@ -0,0 +1,80 @@
+//! Tests for NaN/divergence detection during simulation
+
+use physlang_core::{
+    build_simulation_context_from_source, run_program, run_program_with_options,
+    step_simulation, try_step_simulation, RunError, RunOptions,
+};
+
+/// A spring far too stiff for the timestep: semi-implicit Euler blows up
+/// within a few dozen steps. `anchor` is unconnected and stays finite.
+const STIFF_SPRING: &str = r#"
+particle anchor at (100.0, 100.0) mass 1.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10000.0 rest = 1.0
+simulate dt = 0.1 steps = 1000
+detect gap = distance(a, b)
+"#;
+
+#[test]
+fn test_stiff_spring_reports_divergence() {
+    let err = run_program(STIFF_SPRING).expect_err("stiff spring should diverge");
+    let run_error = err
+        .downcast_ref::<RunError>()
+        .expect("divergence should be reported as a RunError");
+
+    match run_error {
+        RunError::NumericalDivergence { step, particle, quantity } => {
+            assert_eq!(particle, "a");
+            assert!(*step > 1 && *step < 100, "implausible divergence step {}", step);
+            assert!(quantity == "position" || quantity == "velocity");
+        }
+        other => panic!("expected NumericalDivergence, got {:?}", other),
+    }
+
+    let message = err.to_string();
+    assert!(message.contains("'a'"), "message should name the particle: {}", message);
+}
+
+#[test]
+fn test_allow_divergence_runs_to_completion() {
+    let options = RunOptions {
+        allow_divergence: true,
+        ..Default::default()
+    };
+    let result = run_program_with_options(STIFF_SPRING, &options)
+        .expect("divergence check disabled, run should complete");
+    assert!(!result.detectors[0].value.is_finite());
+}
+
+#[test]
+fn test_step_simulation_stops_on_divergence() {
+    let (mut ctx, _) = build_simulation_context_from_source(STIFF_SPRING).unwrap();
+
+    while !step_simulation(&mut ctx) {}
+
+    assert!(ctx.current_step < ctx.max_steps, "stepping should stop early");
+    assert!(matches!(
+        ctx.divergence,
+        Some(RunError::NumericalDivergence { ref particle, .. }) if particle == "a"
+    ));
+
+    // Further steps are refused
+    let stopped_at = ctx.current_step;
+    assert!(step_simulation(&mut ctx));
+    assert_eq!(ctx.current_step, stopped_at);
+}
+
+#[test]
+fn test_stable_simulation_has_no_divergence() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 500
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    while !try_step_simulation(&mut ctx).expect("should not diverge") {}
+    assert_eq!(ctx.current_step, 500);
+    assert!(ctx.divergence.is_none());
+}
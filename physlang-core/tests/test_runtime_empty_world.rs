@@ -0,0 +1,73 @@
+//! Tests for programs that simulate an empty world
+
+use glam::Vec2;
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, get_particle_states, parse_program,
+    run_program, step_simulation, World,
+};
+
+const EMPTY: &str = r#"
+simulate dt = 0.01 steps = 100
+"#;
+
+#[test]
+fn test_empty_program_runs_with_warning() {
+    let result = run_program(EMPTY).unwrap();
+    assert!(result.detectors.is_empty());
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].message, "simulation has no particles");
+}
+
+#[test]
+fn test_analyzer_warns_on_empty_world() {
+    let program = parse_program(EMPTY).unwrap();
+    let diagnostics = analyze_program(&program);
+    assert!(!diagnostics.has_errors());
+    assert!(diagnostics
+        .warnings()
+        .any(|d| d.message == "simulation has no particles"));
+}
+
+#[test]
+fn test_uncalled_function_warns_on_empty_world() {
+    let source = r#"
+fn make() {
+    particle p at (0.0, 0.0) mass 1.0
+}
+simulate dt = 0.01 steps = 10
+"#;
+    let result = run_program(source).unwrap();
+    assert!(result
+        .warnings
+        .iter()
+        .any(|d| d.message == "simulation has no particles"));
+}
+
+#[test]
+fn test_detector_on_empty_world_names_missing_particle() {
+    let source = r#"
+simulate dt = 0.01 steps = 10
+detect x = position(a)
+"#;
+    let err = run_program(source).unwrap_err();
+    assert!(err.to_string().contains("'a'"), "{}", err);
+}
+
+#[test]
+fn test_aggregates_on_empty_world() {
+    let world = World::new();
+    assert_eq!(world.kinetic_energy(), 0.0);
+    assert_eq!(world.potential_energy(), 0.0);
+    assert_eq!(world.total_energy(), 0.0);
+    assert_eq!(world.total_momentum(), Vec2::ZERO);
+    assert_eq!(world.center_of_mass(), Vec2::ZERO);
+    assert_eq!(world.find_non_finite(), None);
+}
+
+#[test]
+fn test_stepping_empty_world() {
+    let (mut ctx, _) = build_simulation_context_from_source(EMPTY).unwrap();
+    while !step_simulation(&mut ctx) {}
+    assert_eq!(ctx.current_step, 100);
+    assert!(get_particle_states(&ctx).is_empty());
+}
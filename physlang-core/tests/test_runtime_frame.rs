@@ -0,0 +1,153 @@
+//! Tests for the `simulate ... frame = center_of_mass` option
+
+use glam::Vec2;
+use physlang_core::ast::{Frame, Recenter};
+use physlang_core::{
+    build_simulation_context_from_source, parse_program, step_simulation, Particle,
+    SimulationContext,
+};
+
+fn two_body(options: &str) -> String {
+    format!(
+        r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (4.0, 0.0) mass 3.0
+force spring(a, b) k = 2.0 rest = 4.0
+simulate dt = 0.01 steps = 2000 {options}
+"#
+    )
+}
+
+/// Build the two-body system and give it asymmetric velocities (net momentum (3, 2))
+fn moving_two_body(options: &str) -> SimulationContext {
+    let (mut ctx, _) = build_simulation_context_from_source(&two_body(options)).unwrap();
+    ctx.world.particles[0].vel = Vec2::new(0.0, 2.0);
+    ctx.world.particles[1].vel = Vec2::new(1.0, 0.0);
+    ctx
+}
+
+#[test]
+fn test_parse_frame_options() {
+    let frame_of = |options: &str| parse_program(&two_body(options)).unwrap().simulate.frame;
+
+    assert_eq!(frame_of(""), Frame::Lab);
+    assert_eq!(frame_of("frame = lab"), Frame::Lab);
+    assert_eq!(
+        frame_of("frame = center_of_mass"),
+        Frame::CenterOfMass {
+            recenter: Recenter::Once
+        }
+    );
+    assert_eq!(
+        frame_of("frame = center_of_mass recenter = always"),
+        Frame::CenterOfMass {
+            recenter: Recenter::Always
+        }
+    );
+    assert_eq!(
+        frame_of("recenter = once frame = center_of_mass"),
+        Frame::CenterOfMass {
+            recenter: Recenter::Once
+        }
+    );
+}
+
+#[test]
+fn test_parse_frame_errors() {
+    for options in [
+        "recenter = always",
+        "frame = lab recenter = once",
+        "frame = rotating",
+        "frame = center_of_mass recenter = sometimes",
+        "frame = center_of_mass frame = lab",
+        "frame center_of_mass",
+    ] {
+        assert!(
+            parse_program(&two_body(options)).is_err(),
+            "'{}' should not parse",
+            options
+        );
+    }
+}
+
+#[test]
+fn test_positions_recentered_at_build() {
+    let (ctx, _) =
+        build_simulation_context_from_source(&two_body("frame = center_of_mass")).unwrap();
+    // Center of mass of (0, 0)·1 and (4, 0)·3 is (3, 0)
+    assert_eq!(ctx.world.particles[0].pos, Vec2::new(-3.0, 0.0));
+    assert_eq!(ctx.world.particles[1].pos, Vec2::new(1.0, 0.0));
+    assert_eq!(ctx.world.center_of_mass(), Vec2::ZERO);
+
+    let (lab, _) = build_simulation_context_from_source(&two_body("")).unwrap();
+    assert_eq!(lab.world.particles[0].pos, Vec2::ZERO);
+}
+
+#[test]
+fn test_center_of_mass_stays_fixed() {
+    let mut ctx = moving_two_body("frame = center_of_mass");
+    while !step_simulation(&mut ctx) {
+        let center = ctx.world.center_of_mass();
+        assert!(
+            center.length() < 1e-4,
+            "center of mass drifted to {:?} at step {}",
+            center,
+            ctx.current_step
+        );
+        assert!(ctx.world.total_momentum().length() < 1e-4);
+    }
+
+    // The bodies still move relative to each other
+    assert!(ctx.max_speed > 0.5);
+}
+
+#[test]
+fn test_lab_frame_drifts() {
+    let mut ctx = moving_two_body("");
+    while !step_simulation(&mut ctx) {}
+
+    // Net momentum (3, 2) over total mass 4 for 20 time units
+    let expected = Vec2::new(3.0, 0.0) + Vec2::new(0.75, 0.5) * 20.0;
+    assert!(ctx.world.center_of_mass().distance(expected) < 1e-2);
+}
+
+#[test]
+fn test_recenter_always_absorbs_later_kicks() {
+    let mut once = moving_two_body("frame = center_of_mass recenter = once");
+    let mut always = moving_two_body("frame = center_of_mass recenter = always");
+
+    for step in 0..1000 {
+        if step % 100 == 50 {
+            once.apply_external_impulse(0, Vec2::new(1.0, 0.0));
+            always.apply_external_impulse(0, Vec2::new(1.0, 0.0));
+        }
+        step_simulation(&mut once);
+        step_simulation(&mut always);
+    }
+
+    // Kicks after t = 0 move the center of mass unless it is re-applied every step
+    assert!(once.world.center_of_mass().length() > 1.0);
+    assert!(always.world.center_of_mass().length() < 0.05);
+}
+
+#[test]
+fn test_pinned_particles_stay_out_of_the_center_of_mass() {
+    let (mut ctx, _) = build_simulation_context_from_source(&two_body("")).unwrap();
+    ctx.world.particles.push(Particle {
+        name: "anchor".to_string(),
+        pos: Vec2::new(100.0, 50.0),
+        vel: Vec2::ZERO,
+        mass: 10.0,
+        pinned: true,
+        ..Default::default()
+    });
+    ctx.world.particles[0].vel = Vec2::new(0.0, 2.0);
+    ctx.world.recenter();
+
+    // The free bodies are centered and at rest overall; the anchor stays put
+    assert_eq!(ctx.world.particles[0].pos, Vec2::new(-3.0, 0.0));
+    assert_eq!(ctx.world.particles[1].pos, Vec2::new(1.0, 0.0));
+    assert_eq!(ctx.world.particles[0].vel, Vec2::new(0.0, 1.5));
+    assert_eq!(ctx.world.particles[1].vel, Vec2::new(0.0, -0.5));
+    assert_eq!(ctx.world.particles[2].pos, Vec2::new(100.0, 50.0));
+}
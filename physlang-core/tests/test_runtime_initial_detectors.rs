@@ -0,0 +1,96 @@
+//! Tests for detectors declared `initial`, which also report their value
+//! before the first step
+
+use physlang_core::{
+    format_source, parse_program, run_program, run_program_with_options, RunError, RunOptions,
+};
+
+/// `a` and `b` start 3.0 apart and at rest, then the spring pulls them in
+const PROGRAM: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b) initial
+detect a_x = position(a).x initial
+detect energy = kinetic_energy() initial
+detect b_x = position(b).x
+";
+
+#[test]
+fn test_initial_is_parsed_as_a_modifier() {
+    let program = parse_program(PROGRAM).unwrap();
+    let flags: Vec<bool> = program.detectors.iter().map(|d| d.initial).collect();
+    assert_eq!(flags, [true, true, true, false]);
+
+    // Detectors may still be named `initial`
+    let program = parse_program("simulate dt = 0.01 steps = 1\ndetect initial = time()\n").unwrap();
+    assert_eq!(program.detectors[0].name, "initial");
+    assert!(!program.detectors[0].initial);
+}
+
+#[test]
+fn test_initial_values_match_the_declared_geometry() {
+    let result = run_program(PROGRAM).unwrap();
+    let initial: Vec<Option<f32>> = result.detectors.iter().map(|d| d.initial).collect();
+    assert_eq!(initial, [Some(3.0), Some(0.0), Some(0.0), None]);
+}
+
+#[test]
+fn test_final_values_match_the_simulation() {
+    let with_initial = run_program(PROGRAM).unwrap();
+    let without_initial = run_program(&PROGRAM.replace(" initial", "")).unwrap();
+    assert_eq!(with_initial.state_hash, without_initial.state_hash);
+    for (with, without) in with_initial
+        .detectors
+        .iter()
+        .zip(&without_initial.detectors)
+    {
+        assert_eq!(with.value, without.value, "{}", with.name);
+        assert!(without.initial.is_none());
+    }
+
+    // The spring has pulled them in and set them moving
+    let gap = &with_initial.detectors[0];
+    assert!(gap.value < 3.0, "{}", gap.value);
+    assert!(with_initial.detectors[2].value > 0.0);
+}
+
+#[test]
+fn test_json_has_both_values() {
+    let result = run_program(PROGRAM).unwrap();
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["detectors"]["gap"]["initial"], 3.0);
+    assert_eq!(
+        json["detectors"]["gap"]["value"],
+        f64::from(result.detectors[0].value)
+    );
+    assert_eq!(
+        json["detectors"]["b_x"],
+        f64::from(result.detectors[3].value)
+    );
+}
+
+#[test]
+fn test_budget_error_keeps_initial_values() {
+    let options = RunOptions {
+        max_steps: Some(10),
+        ..Default::default()
+    };
+    match run_program_with_options(PROGRAM, &options) {
+        Err(error) => match error.downcast_ref::<RunError>() {
+            Some(RunError::BudgetExceeded { detectors, .. }) => {
+                assert_eq!(detectors[0].initial, Some(3.0));
+            }
+            other => panic!("expected a budget error, got {:?}", other),
+        },
+        Ok(_) => panic!("expected the step budget to be exceeded"),
+    }
+}
+
+#[test]
+fn test_formatter_keeps_initial() {
+    let formatted = format_source(PROGRAM).unwrap();
+    assert!(formatted.contains("detect gap = distance(a, b) initial\n"));
+    assert!(formatted.contains("detect b_x = position(b)\n"));
+}
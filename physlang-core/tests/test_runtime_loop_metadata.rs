@@ -0,0 +1,35 @@
+//! Tests for the names carried by runtime loops and wells
+
+use physlang_core::build_simulation_context_from_source;
+
+const SOURCE: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+loop for 2 cycles with frequency 1.0 damping 0.0 on b {
+    force push(b) magnitude 0.1 direction (1.0, 0.0)
+}
+well catch on a if position(a).x >= 1.0 depth 5.0
+simulate dt = 0.01 steps = 10
+";
+
+#[test]
+fn test_loops_and_wells_keep_their_names() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    assert_eq!(ctx.loops[0].target_name, "b");
+    assert_eq!(ctx.loops[0].label, None);
+    assert_eq!(ctx.wells[0].name, "catch");
+}
+
+#[test]
+fn test_well_is_applying_once_the_threshold_is_reached() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    assert!(!ctx.wells[0].is_applying(&ctx.world.particles));
+
+    ctx.world.particles[0].pos.x = 1.5;
+    assert!(ctx.wells[0].is_applying(&ctx.world.particles));
+    assert_eq!(ctx.wells[0].observable.evaluate(&ctx.world.particles), 1.5);
+
+    // A pinned particle isn't pushed
+    ctx.world.particles[0].pinned = true;
+    assert!(!ctx.wells[0].is_applying(&ctx.world.particles));
+}
@@ -0,0 +1,100 @@
+//! Tests that static analysis and the simulation builder resolve particle
+//! names the same way, for worlds generated by functions in any order
+
+use physlang_core::ast::Program;
+use physlang_core::{
+    analyze_program, build_simulation_context, evaluate_lets, execute_functions, parse_program,
+    run_program, SymbolTable,
+};
+
+/// `link` runs before the `node` calls that create the particles it joins
+const LINK_FIRST: &str = r#"fn node(name, x) {
+    particle name at (x, 0.0) mass 1.0
+}
+
+fn link(a, b) {
+    force spring(a, b) k = 2.0 rest = 1.0
+}
+
+particle anchor at (0.0, 1.0) mass 1.0
+link("node_0", "node_3")
+node("node_3", 3.0)
+node("node_0", 0.0)
+simulate dt = 0.01 steps = 10
+detect anchor_x = position(anchor).x
+"#;
+
+/// A program after its functions ran
+fn expanded(source: &str) -> Program {
+    let mut program = parse_program(source).unwrap();
+    let lets = program.lets.clone();
+    let (eval_ctx, _) = evaluate_lets(&lets);
+    let diagnostics = execute_functions(&mut program, &eval_ctx);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    program
+}
+
+/// The errors of analyzing `program`, and the error of building it anyway
+fn check_and_build(program: &Program) -> (Vec<String>, Option<String>) {
+    let errors = analyze_program(program)
+        .errors()
+        .map(|d| d.message.clone())
+        .collect();
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let built = build_simulation_context(program, &eval_ctx);
+    (errors, built.err().map(|e| e.to_string()))
+}
+
+#[test]
+fn test_references_before_generation_check_and_run() {
+    let program = expanded(LINK_FIRST);
+    assert_eq!(check_and_build(&program), (Vec::new(), None));
+    assert!(run_program(LINK_FIRST).is_ok());
+}
+
+#[test]
+fn test_missing_generated_particle_has_one_message() {
+    let source = LINK_FIRST.replace(r#"link("node_0", "node_3")"#, r#"link("node_0", "node_9")"#);
+    let (errors, built) = check_and_build(&expanded(&source));
+    assert_eq!(errors, ["unknown particle 'node_9' in force"]);
+    assert_eq!(built.as_deref(), Some("unknown particle 'node_9' in force"));
+}
+
+#[test]
+fn test_every_place_reports_like_analysis() {
+    let source = "\
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = 10
+detect d = distance(a, ghost)
+";
+    let program = parse_program(source).unwrap();
+    let (errors, _) = check_and_build(&program);
+    assert_eq!(errors, ["unknown particle 'ghost' in detector"]);
+    // Detectors are only resolved once the simulation has run
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let ctx = build_simulation_context(&program, &eval_ctx).unwrap();
+    let mut warnings = Vec::new();
+    let error =
+        physlang_core::runtime::evaluate_detectors(&program, &ctx.world, 0.0, &mut warnings)
+            .unwrap_err();
+    assert_eq!(error.to_string(), errors[0]);
+}
+
+#[test]
+fn test_duplicates_resolve_to_their_first_declaration() {
+    let source = LINK_FIRST
+        .replace(r#"link("node_0", "node_3")"#, r#"link("anchor", "node_3")"#)
+        .replace(r#"node("node_0", 0.0)"#, r#"node("node_3", 0.0)"#);
+    let program = expanded(&source);
+    let symbols = SymbolTable::of_program(&program);
+    assert_eq!(symbols.resolve("node_3").unwrap().index(), 1);
+
+    // Analysis rejects the duplicate; the builder resolves it to the same
+    // particle analysis checked the force against
+    let (errors, built) = check_and_build(&program);
+    assert_eq!(errors, ["duplicate particle name 'node_3'"]);
+    assert_eq!(built, None);
+    let (eval_ctx, _) = evaluate_lets(&program.lets);
+    let ctx = build_simulation_context(&program, &eval_ctx).unwrap();
+    assert_eq!(ctx.symbols(), &symbols);
+}
@@ -0,0 +1,96 @@
+//! Tests for let-binding and simulate overrides in `RunOptions`
+
+use physlang_core::{
+    build_simulation_context_with_overrides, run_program_with_options, OverrideError, RunOptions,
+};
+
+const SPRING: &str = r#"
+let spring_k = 2.0
+let rest_length = spring_k + 1.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (5.0, 0.0) mass 1.0
+force spring(a, b) k = spring_k rest = rest_length
+simulate dt = 0.01 steps = 100
+detect separation = distance(a, b)
+"#;
+
+fn separation(options: &RunOptions) -> f32 {
+    let result = run_program_with_options(SPRING, options).unwrap();
+    result.detectors[0].value
+}
+
+#[test]
+fn test_let_override_changes_result() {
+    let baseline = separation(&RunOptions::default());
+    let stiffer = separation(&RunOptions {
+        let_overrides: vec![("spring_k".to_string(), 12.5)],
+        ..Default::default()
+    });
+    assert_ne!(baseline, stiffer);
+
+    // Overriding with the declared value changes nothing
+    let same = separation(&RunOptions {
+        let_overrides: vec![("spring_k".to_string(), 2.0)],
+        ..Default::default()
+    });
+    assert_eq!(baseline, same);
+}
+
+#[test]
+fn test_dependent_lets_see_override() {
+    // rest_length = spring_k + 1: overriding spring_k moves the equilibrium too
+    let at_rest = separation(&RunOptions {
+        let_overrides: vec![("spring_k".to_string(), 4.0)],
+        ..Default::default()
+    });
+    assert_eq!(at_rest, 5.0);
+}
+
+#[test]
+fn test_simulate_overrides() {
+    let result = run_program_with_options(
+        SPRING,
+        &RunOptions {
+            dt: Some(0.02),
+            steps: Some(10),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(result.steps, 10);
+    assert!((result.elapsed_time - 0.2).abs() < 1e-6);
+}
+
+#[test]
+fn test_unknown_let_lists_available() {
+    let options = RunOptions {
+        let_overrides: vec![("nope".to_string(), 1.0)],
+        ..Default::default()
+    };
+    let err = run_program_with_options(SPRING, &options).unwrap_err();
+    let override_error = err.downcast_ref::<OverrideError>().unwrap();
+    assert_eq!(
+        *override_error,
+        OverrideError::UnknownLet {
+            name: "nope".to_string(),
+            available: vec!["spring_k".to_string(), "rest_length".to_string()],
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "unknown let binding 'nope' (available: spring_k, rest_length)"
+    );
+}
+
+#[test]
+fn test_context_built_with_overrides() {
+    let options = RunOptions {
+        let_overrides: vec![("spring_k".to_string(), 12.5)],
+        dt: Some(0.005),
+        ..Default::default()
+    };
+    let (ctx, _) = build_simulation_context_with_overrides(SPRING, &options).unwrap();
+    assert_eq!(ctx.dt, 0.005);
+    assert_eq!(ctx.max_steps, 100);
+    assert!(ctx.forces()[0].parameters.contains(&("k", 12.5)));
+}
@@ -0,0 +1,111 @@
+//! Tests for the `Pipeline` stages and the entry points built on them
+
+use physlang_core::{
+    build_simulation_context_from_source, run_program, Pipeline, RunError, RunOptions,
+};
+use std::path::Path;
+
+const GENERATED: &str = r#"
+fn pair(p, q, x) {
+    particle p at (0.0, 0.0) mass 1.0
+    particle q at (x, 0.0) mass 1.0
+    force spring(p, q) k = 2.0 rest = 2.0
+}
+pair("a", "b", 3.0)
+simulate dt = 0.01 steps = 100
+"#;
+
+fn fixture(path: &str) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    std::fs::read_to_string(root.join(path)).unwrap()
+}
+
+#[test]
+fn test_broken_fixtures_fail_the_same_way() {
+    let broken = [
+        "duplicate_particle",
+        "invalid_loop_syntax",
+        "missing_simulate",
+        "syntax_error",
+        "unknown_particle",
+    ];
+    for name in broken {
+        let source = fixture(&format!("tests/integration/broken/{}.phys", name));
+        let run = run_program(&source).err().map(|e| e.to_string());
+        let build = build_simulation_context_from_source(&source)
+            .err()
+            .map(|e| e.to_string());
+        assert!(run.is_some(), "{} should fail", name);
+        assert_eq!(run, build, "{} failed differently", name);
+    }
+}
+
+#[test]
+fn test_stages_expand_functions() {
+    let mut pipeline = Pipeline::parse(GENERATED).unwrap();
+    assert!(pipeline.program().particles.is_empty());
+    pipeline.analyze().unwrap();
+    let warnings = pipeline.expand_functions().unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(pipeline.program().particles.len(), 2);
+
+    let ctx = pipeline.build(&RunOptions::default()).unwrap();
+    let (direct, _) = build_simulation_context_from_source(GENERATED).unwrap();
+    assert_eq!(ctx.state_hash(), direct.state_hash());
+}
+
+#[test]
+fn test_failed_stage_keeps_its_diagnostics() {
+    let source = fixture("tests/integration/broken/unknown_particle.phys");
+    let mut pipeline = Pipeline::parse(&source).unwrap();
+    match pipeline.analyze() {
+        Err(RunError::Analysis { stage, messages }) => {
+            assert_eq!(stage, "Static analysis");
+            let recorded: Vec<&str> = pipeline
+                .diagnostics()
+                .errors()
+                .map(|d| d.message.as_str())
+                .collect();
+            assert_eq!(recorded, messages);
+        }
+        other => panic!("expected an analysis error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_overrides_apply_before_analysis() {
+    let source = "let n = 2.0\nparticle a at (n, 0.0) mass 1.0\nsimulate dt = 0.01 steps = 10\n";
+    let options = RunOptions {
+        let_overrides: vec![("n".to_string(), 5.0)],
+        steps: Some(3),
+        ..RunOptions::default()
+    };
+    let mut pipeline = Pipeline::parse(source).unwrap();
+    pipeline.apply_overrides(&options).unwrap();
+    pipeline.analyze().unwrap();
+    pipeline.expand_functions().unwrap();
+    let ctx = pipeline.build(&options).unwrap();
+    assert_eq!(ctx.world.particles[0].pos.x, 5.0);
+    assert_eq!(ctx.max_steps, 3);
+}
+
+#[test]
+fn test_run_program_reports_analysis_errors() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+force gravity(a, b) G = 1.0
+simulate dt = 0.01 steps = 100
+"#;
+    let err = run_program(source).unwrap_err();
+    match err.downcast_ref::<RunError>() {
+        Some(RunError::Analysis { stage, messages }) => {
+            assert_eq!(*stage, "Static analysis");
+            assert_eq!(messages, &["unknown particle 'b' in force".to_string()]);
+        }
+        other => panic!("expected an analysis error, got {:?}", other),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Static analysis errors:\nunknown particle 'b' in force"
+    );
+}
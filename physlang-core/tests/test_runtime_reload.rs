@@ -0,0 +1,199 @@
+//! Tests for program diffing and incremental context reload
+
+use physlang_core::{
+    build_simulation_context_from_source, diff_programs, parse_program,
+    rebuild_simulation_context_from_source, rebuild_simulation_context_with_overrides,
+    step_simulation, ProgramDiff, RunOptions,
+};
+
+const ORIGINAL: &str = r#"
+let k = 10.0
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = k rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+"#;
+
+fn run_steps(source: &str, n: usize) -> physlang_core::SimulationContext {
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    for _ in 0..n {
+        step_simulation(&mut ctx);
+    }
+    ctx
+}
+
+#[test]
+fn test_diff_classification() {
+    let old = parse_program(ORIGINAL).unwrap();
+    let classify = |edited: &str| diff_programs(&old, &parse_program(edited).unwrap());
+
+    assert_eq!(classify(ORIGINAL), ProgramDiff::Unchanged);
+    assert_eq!(
+        classify(&ORIGINAL.replace("steps = 100", "steps = 500")),
+        ProgramDiff::SimulateOnly
+    );
+    assert_eq!(
+        classify(&ORIGINAL.replace("let k = 10.0", "let k = 20.0")),
+        ProgramDiff::LetsOnly
+    );
+    assert_eq!(
+        classify(&ORIGINAL.replace("mass 1.0\nparticle b", "mass 2.0\nparticle b")),
+        ProgramDiff::Structural
+    );
+    assert_eq!(
+        classify(&format!("{}detect a_x = position(a)\n", ORIGINAL)),
+        ProgramDiff::Structural
+    );
+}
+
+#[test]
+fn test_steps_only_edit_preserves_positions() {
+    let old_program = parse_program(ORIGINAL).unwrap();
+    let ctx = run_steps(ORIGINAL, 50);
+    let positions: Vec<_> = ctx.world.particles.iter().map(|p| p.pos).collect();
+
+    let edited = ORIGINAL.replace("steps = 100", "steps = 500");
+    let reload = rebuild_simulation_context_from_source(&edited, &old_program, ctx).unwrap();
+
+    assert_eq!(reload.diff, ProgramDiff::SimulateOnly);
+    assert_eq!(reload.ctx.current_step, 50);
+    assert_eq!(reload.ctx.max_steps, 500);
+    let reloaded: Vec<_> = reload.ctx.world.particles.iter().map(|p| p.pos).collect();
+    assert_eq!(reloaded, positions);
+}
+
+#[test]
+fn test_lets_only_edit_keeps_state_and_applies_new_values() {
+    let old_program = parse_program(ORIGINAL).unwrap();
+    let ctx = run_steps(ORIGINAL, 50);
+    let positions: Vec<_> = ctx.world.particles.iter().map(|p| p.pos).collect();
+
+    let edited = ORIGINAL.replace("let k = 10.0", "let k = 20.0");
+    let reload = rebuild_simulation_context_from_source(&edited, &old_program, ctx).unwrap();
+
+    assert_eq!(reload.diff, ProgramDiff::LetsOnly);
+    assert_eq!(reload.ctx.current_step, 50);
+    let reloaded: Vec<_> = reload.ctx.world.particles.iter().map(|p| p.pos).collect();
+    assert_eq!(reloaded, positions);
+    assert!(matches!(
+        reload.ctx.world.forces[0],
+        physlang_core::Force::Spring { k, .. } if k == 20.0
+    ));
+}
+
+#[test]
+fn test_particle_edit_forces_rebuild() {
+    let old_program = parse_program(ORIGINAL).unwrap();
+    let ctx = run_steps(ORIGINAL, 50);
+
+    let edited = ORIGINAL.replace("particle b at (3.0, 0.0)", "particle b at (4.0, 0.0)");
+    let reload = rebuild_simulation_context_from_source(&edited, &old_program, ctx).unwrap();
+
+    assert_eq!(reload.diff, ProgramDiff::Structural);
+    assert_eq!(reload.ctx.current_step, 0);
+    assert_eq!(reload.ctx.world.particles[1].pos.x, 4.0);
+
+    // The returned program is the baseline for the next edit
+    assert_eq!(
+        diff_programs(&reload.program, &parse_program(&edited).unwrap()),
+        ProgramDiff::Unchanged
+    );
+}
+
+#[test]
+fn test_invalid_edit_is_an_error() {
+    let old_program = parse_program(ORIGINAL).unwrap();
+    let ctx = run_steps(ORIGINAL, 10);
+    let edited = ORIGINAL.replace("spring(a, b)", "spring(a, c)");
+    assert!(rebuild_simulation_context_from_source(&edited, &old_program, ctx).is_err());
+}
+
+#[test]
+fn test_overrides_apply_to_the_edited_source() {
+    let old_program = parse_program(ORIGINAL).unwrap();
+    let ctx = run_steps(ORIGINAL, 50);
+    let options = RunOptions {
+        dt: Some(0.005),
+        ..Default::default()
+    };
+
+    let edited = ORIGINAL.replace("steps = 100", "steps = 500");
+    let reload =
+        rebuild_simulation_context_with_overrides(&edited, &options, &old_program, ctx).unwrap();
+    assert_eq!(reload.diff, ProgramDiff::SimulateOnly);
+    assert_eq!(reload.ctx.current_step, 50);
+    assert_eq!(reload.ctx.dt, 0.005);
+    assert_eq!(reload.ctx.max_steps, 500);
+}
+
+/// A row of `n` falling particles generated by a function
+const GENERATED_ROW: &str = r#"
+let n = 2
+let height = 0.0
+fn place(p: name, x) {
+    particle p at (x, height) mass 1.0
+}
+for i in 0..n {
+    if i == 0 {
+        place("a", 0.0)
+    } else {
+        if i == 1 {
+            place("b", 1.0)
+        } else {
+            place("c", 2.0)
+        }
+    }
+}
+ground y = -100.0 friction 0.0 gravity 9.81
+simulate dt = 0.01 steps = 100
+"#;
+
+#[test]
+fn test_let_changing_generated_particles_forces_rebuild() {
+    let old_program = parse_program(GENERATED_ROW).unwrap();
+    let ctx = run_steps(GENERATED_ROW, 50);
+    assert_eq!(ctx.world.particles.len(), 2);
+
+    let edited = GENERATED_ROW.replace("let n = 2", "let n = 3");
+    assert_eq!(
+        diff_programs(&old_program, &parse_program(&edited).unwrap()),
+        ProgramDiff::LetsOnly
+    );
+    let reload = rebuild_simulation_context_from_source(&edited, &old_program, ctx).unwrap();
+
+    assert_eq!(reload.diff, ProgramDiff::Structural);
+    assert_eq!(reload.ctx.current_step, 0);
+    let positions: Vec<_> = reload
+        .ctx
+        .world
+        .particles
+        .iter()
+        .map(|p| (p.name.as_str(), p.pos.x, p.pos.y))
+        .collect();
+    assert_eq!(
+        positions,
+        [("a", 0.0, 0.0), ("b", 1.0, 0.0), ("c", 2.0, 0.0)]
+    );
+}
+
+#[test]
+fn test_let_keeping_generated_particles_keeps_their_state() {
+    let old_program = parse_program(GENERATED_ROW).unwrap();
+    let ctx = run_steps(GENERATED_ROW, 50);
+    let states: Vec<_> = ctx.world.particles.iter().map(|p| (p.pos, p.vel)).collect();
+
+    let edited = GENERATED_ROW.replace("let height = 0.0", "let height = 5.0");
+    let reload = rebuild_simulation_context_from_source(&edited, &old_program, ctx).unwrap();
+
+    assert_eq!(reload.diff, ProgramDiff::LetsOnly);
+    assert_eq!(reload.ctx.current_step, 50);
+    let reloaded: Vec<_> = reload
+        .ctx
+        .world
+        .particles
+        .iter()
+        .map(|p| (p.pos, p.vel))
+        .collect();
+    assert_eq!(reloaded, states);
+}
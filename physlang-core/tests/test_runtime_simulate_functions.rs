@@ -0,0 +1,88 @@
+//! Tests for `simulate` declarations whose dt and steps call functions
+
+use physlang_core::{analyze_program, parse_program, run_program};
+
+const TOTAL_STEPS: &str = "\
+fn total_steps() {
+    return 100 * 5
+}
+
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = 0.01 steps = total_steps()
+detect t = time()
+";
+
+fn analysis_errors(source: &str) -> Vec<String> {
+    let program = parse_program(source).unwrap();
+    analyze_program(&program)
+        .errors()
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn test_steps_from_a_function() {
+    assert!(analysis_errors(TOTAL_STEPS).is_empty());
+    let result = run_program(TOTAL_STEPS).unwrap();
+    assert_eq!(result.steps, 500);
+}
+
+#[test]
+fn test_dt_from_a_function_with_arguments() {
+    let source = "\
+let rate = 50.0
+fn step_size(n) {
+    return 1.0 / n
+}
+
+particle a at (0.0, 0.0) mass 1.0
+simulate dt = step_size(rate) steps = 2 * 50
+detect t = time()
+";
+    let result = run_program(source).unwrap();
+    assert_eq!(result.steps, 100);
+    assert!(
+        (result.elapsed_time - 2.0).abs() < 1e-4,
+        "{}",
+        result.elapsed_time
+    );
+}
+
+#[test]
+fn test_declarations_in_simulate_calls_are_not_built() {
+    let source = TOTAL_STEPS.replace(
+        "    return 100 * 5",
+        "    particle extra at (1.0, 0.0) mass 1.0\n    return 100 * 5",
+    );
+    let result = run_program(&source).unwrap();
+    assert_eq!(result.steps, 500);
+    assert_eq!(
+        result.state_hash,
+        run_program(TOTAL_STEPS).unwrap().state_hash
+    );
+}
+
+#[test]
+fn test_analysis_checks_simulate_calls() {
+    let unknown = TOTAL_STEPS.replace("steps = total_steps()", "steps = total_step()");
+    assert_eq!(analysis_errors(&unknown), ["unknown function 'total_step'"]);
+
+    let arity = TOTAL_STEPS.replace("steps = total_steps()", "steps = total_steps(2)");
+    assert_eq!(
+        analysis_errors(&arity),
+        ["function 'total_steps' expects 0 argument(s), got 1"]
+    );
+}
+
+#[test]
+fn test_failing_call_names_simulate() {
+    let source = TOTAL_STEPS.replace("return 100 * 5", "return 100 / 0.0");
+    let error = run_program(&source).err().unwrap();
+    assert!(
+        error.to_string().contains(
+            "Error evaluating steps: Division by zero in total_steps() called from simulate steps"
+        ),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,172 @@
+//! Tests for saving and loading binary snapshots of a simulation context
+
+use glam::Vec2;
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, CustomForce, Particle,
+    SimulationContext, SnapshotError, StateHasher, SNAPSHOT_MAGIC, SNAPSHOT_VERSION,
+};
+
+const PROGRAM: &str = r#"
+particle anchor at (0.0, 3.0) mass 1.0
+particle a at (0.0, 1.0) mass 1.0
+particle b at (2.0, 1.0) mass 1.0
+force spring(anchor, a) k = 50.0 rest = 2.0 + 0.5 * sin(time * 2.0)
+force gravity(a, b) G = 0.5
+ground y = 0.0 friction 0.3
+simulate dt = 0.01 steps = 400
+detect hits = collision_count(a, b)
+detect height = position(a)
+detect energy = kinetic_energy()
+"#;
+
+fn context() -> SimulationContext {
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    ctx.world.set_collision_radius(0.3);
+    ctx.world.pin(0);
+    ctx
+}
+
+fn save(ctx: &SimulationContext) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ctx.save(&mut bytes).unwrap();
+    bytes
+}
+
+fn load_err(bytes: &[u8]) -> SnapshotError {
+    match SimulationContext::load(bytes) {
+        Ok(_) => panic!("loaded an invalid snapshot"),
+        Err(err) => err,
+    }
+}
+
+/// Step to the midpoint and queue an impulse the next step will consume
+fn midpoint() -> SimulationContext {
+    let mut ctx = context();
+    for _ in 0..200 {
+        step_simulation(&mut ctx);
+    }
+    ctx.apply_external_impulse(1, Vec2::new(0.5, 0.0));
+    ctx
+}
+
+#[test]
+fn test_resumed_run_matches_uninterrupted_run() {
+    let mut uninterrupted = midpoint();
+    while !step_simulation(&mut uninterrupted) {}
+
+    let snapshot = save(&midpoint());
+    let mut resumed = SimulationContext::load(snapshot.as_slice()).unwrap();
+    while !step_simulation(&mut resumed) {}
+
+    assert_eq!(resumed.state_hash(), uninterrupted.state_hash());
+    assert_eq!(resumed.time.to_bits(), uninterrupted.time.to_bits());
+    assert_eq!(save(&resumed), save(&uninterrupted));
+    assert_eq!(
+        resumed.drain_collision_events(),
+        uninterrupted.drain_collision_events()
+    );
+}
+
+#[test]
+fn test_snapshot_starts_with_magic_and_version() {
+    let bytes = save(&context());
+    assert_eq!(bytes[..SNAPSHOT_MAGIC.len()], SNAPSHOT_MAGIC);
+    assert_eq!(bytes[SNAPSHOT_MAGIC.len()], SNAPSHOT_VERSION);
+}
+
+#[test]
+fn test_load_rejects_other_files() {
+    let err = load_err(b"particle a at (0, 0) mass 1");
+    assert!(matches!(err, SnapshotError::NotASnapshot));
+    let err = load_err(&[]);
+    assert!(matches!(err, SnapshotError::NotASnapshot));
+}
+
+#[test]
+fn test_load_rejects_newer_versions() {
+    let mut bytes = save(&context());
+    bytes[SNAPSHOT_MAGIC.len()] = SNAPSHOT_VERSION + 1;
+    let err = load_err(bytes.as_slice());
+    assert!(matches!(
+        err,
+        SnapshotError::UnsupportedVersion { found } if found == SNAPSHOT_VERSION + 1
+    ));
+}
+
+#[test]
+fn test_load_rejects_corruption() {
+    let bytes = save(&context());
+
+    let mut flipped = bytes.clone();
+    let middle = flipped.len() / 2;
+    flipped[middle] ^= 0x01;
+    let err = load_err(flipped.as_slice());
+    assert!(matches!(err, SnapshotError::Corrupt(_)), "{}", err);
+
+    for len in [SNAPSHOT_MAGIC.len() + 1, bytes.len() / 2, bytes.len() - 1] {
+        let err = load_err(&bytes[..len]);
+        assert!(matches!(err, SnapshotError::Corrupt(_)), "{}", err);
+    }
+}
+
+/// `bytes` with `patch` written into its body at `offset` and the checksum
+/// recomputed, so only the decoder can notice
+fn patched(bytes: &[u8], offset: usize, patch: &[u8]) -> Vec<u8> {
+    let mut framed = bytes[..bytes.len() - 8].to_vec();
+    let start = SNAPSHOT_MAGIC.len() + 1 + offset;
+    framed[start..start + patch.len()].copy_from_slice(patch);
+    let mut hasher = StateHasher::new();
+    hasher.write_bytes(&framed);
+    framed.extend_from_slice(&hasher.finish().to_le_bytes());
+    framed
+}
+
+#[test]
+fn test_load_rejects_corrupt_body_with_valid_checksum() {
+    let bytes = save(&context());
+    // The body opens with the particles, then the forces; the first force is
+    // the spring from anchor (0) to a (1)
+    let particles: usize = ["anchor", "a", "b"]
+        .iter()
+        .map(|name| 8 + name.len() + 8 + 8 + 4 + 1 + 4 + 8)
+        .sum();
+    let spring = 8 + particles + 8;
+    assert_eq!(bytes[SNAPSHOT_MAGIC.len() + 1 + spring], 1);
+
+    for (offset, patch, reason) in [
+        (
+            0,
+            &1000u64.to_le_bytes()[..],
+            "length 1000 exceeds the data left",
+        ),
+        (spring, &[9], "unknown force tag 9"),
+        (
+            spring + 9,
+            &7u64.to_le_bytes(),
+            "force refers to a missing particle",
+        ),
+    ] {
+        match load_err(&patched(&bytes, offset, patch)) {
+            SnapshotError::Corrupt(message) => assert!(message.contains(reason), "{}", message),
+            other => panic!("expected a corrupt snapshot, got {}", other),
+        }
+    }
+}
+
+struct Drag;
+
+impl CustomForce for Drag {
+    fn accumulate(&self, particles: &[Particle], out: &mut [Vec2]) {
+        for (particle, force) in particles.iter().zip(out) {
+            *force -= particle.vel * 0.1;
+        }
+    }
+}
+
+#[test]
+fn test_save_rejects_custom_forces() {
+    let mut ctx = context();
+    ctx.world.add_custom_force(Box::new(Drag));
+    let err = ctx.save(Vec::new()).unwrap_err();
+    assert!(matches!(err, SnapshotError::Unsupported(_)));
+}
@@ -0,0 +1,53 @@
+//! Tests for the run pipeline stages used by `physlang bench`
+
+use physlang_core::{
+    build_run_context, check_program, expand_functions, parse_program, run_program, run_simulation,
+    RunError, RunOptions,
+};
+
+const GENERATED: &str = r#"
+fn pair(p, q, x) {
+    particle p at (0.0, 0.0) mass 1.0
+    particle q at (x, 0.0) mass 1.0
+    force spring(p, q) k = 2.0 rest = 2.0
+}
+pair("a", "b", 3.0)
+simulate dt = 0.01 steps = 100
+"#;
+
+#[test]
+fn test_stages_match_run_program() {
+    let options = RunOptions::default();
+    let mut program = parse_program(GENERATED).unwrap();
+    check_program(&program).unwrap();
+    let warnings = expand_functions(&mut program).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(program.particles.len(), 2);
+
+    let ctx = build_run_context(&program, &options).unwrap();
+    assert_eq!(ctx.world.particles.len(), 2);
+    let staged = run_simulation(ctx, &program, &options, |_| Ok(())).unwrap();
+
+    let direct = run_program(GENERATED).unwrap();
+    assert_eq!(staged.state_hash, direct.state_hash);
+    assert_eq!(staged.steps, 100);
+}
+
+#[test]
+fn test_stage_errors_name_their_stage() {
+    let unknown = parse_program(
+        r#"
+particle a at (0.0, 0.0) mass 1.0
+force spring(a, ghost) k = 2.0 rest = 2.0
+simulate dt = 0.01 steps = 10
+"#,
+    )
+    .unwrap();
+    match check_program(&unknown) {
+        Err(RunError::Analysis { stage, messages }) => {
+            assert_eq!(stage, "Static analysis");
+            assert!(messages.iter().any(|m| m.contains("ghost")));
+        }
+        other => panic!("expected an analysis error, got {:?}", other),
+    }
+}
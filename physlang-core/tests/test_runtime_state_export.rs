@@ -0,0 +1,113 @@
+//! Tests for world state export/import and warm-starting runs
+
+use physlang_core::tests::test_helpers::approx_eq_f32;
+use physlang_core::{
+    build_simulation_context_from_source, run_program, run_program_with_options,
+    step_simulation, RunOptions, StateError, WorldState,
+};
+
+const HALF_RUN: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+force gravity(a, b) G = 0.5
+simulate dt = 0.01 steps = 500
+detect gap = distance(a, b)
+detect pos_a = position(a)
+"#;
+
+const FULL_RUN: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+force gravity(a, b) G = 0.5
+simulate dt = 0.01 steps = 1000
+detect gap = distance(a, b)
+detect pos_a = position(a)
+"#;
+
+fn run_to_end(source: &str, initial: Option<&WorldState>) -> WorldState {
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    if let Some(state) = initial {
+        ctx.import_state(state).unwrap();
+    }
+    while !step_simulation(&mut ctx) {}
+    ctx.export_state()
+}
+
+#[test]
+fn test_split_run_matches_single_run() {
+    let halfway = run_to_end(HALF_RUN, None);
+    let resumed = run_to_end(HALF_RUN, Some(&halfway));
+    let single = run_to_end(FULL_RUN, None);
+
+    assert_eq!(resumed.particles.len(), single.particles.len());
+    for (r, s) in resumed.particles.iter().zip(&single.particles) {
+        assert_eq!(r.name, s.name);
+        assert!(approx_eq_f32(r.pos.x, s.pos.x, 1e-5));
+        assert!(approx_eq_f32(r.pos.y, s.pos.y, 1e-5));
+        assert!(approx_eq_f32(r.vel.x, s.vel.x, 1e-5));
+        assert!(approx_eq_f32(r.vel.y, s.vel.y, 1e-5));
+    }
+}
+
+#[test]
+fn test_state_round_trips_through_json() {
+    let state = run_to_end(HALF_RUN, None);
+    let json = serde_json::to_string(&state).unwrap();
+    let parsed: WorldState = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, state);
+}
+
+#[test]
+fn test_run_options_initial_state_warm_starts() {
+    let halfway = run_to_end(HALF_RUN, None);
+    let options = RunOptions {
+        initial_state: Some(halfway),
+        ..Default::default()
+    };
+    let warm = run_program_with_options(HALF_RUN, &options).unwrap();
+    let single = run_program(FULL_RUN).unwrap();
+
+    for (w, s) in warm.detectors.iter().zip(&single.detectors) {
+        assert_eq!(w.name, s.name);
+        assert!(approx_eq_f32(w.value, s.value, 1e-5));
+    }
+}
+
+#[test]
+fn test_import_matches_by_name_not_order() {
+    let mut state = run_to_end(HALF_RUN, None);
+    state.particles.reverse();
+
+    let (mut ctx, _) = build_simulation_context_from_source(HALF_RUN).unwrap();
+    ctx.import_state(&state).unwrap();
+    assert_eq!(ctx.world.particles[0].name, "a");
+    assert_eq!(ctx.world.particles[0].pos, state.particles[1].pos);
+}
+
+#[test]
+fn test_import_rejects_unknown_particle() {
+    let mut state = run_to_end(HALF_RUN, None);
+    state.particles[0].name = "ghost".to_string();
+
+    let (mut ctx, _) = build_simulation_context_from_source(HALF_RUN).unwrap();
+    let before = ctx.export_state();
+    assert_eq!(
+        ctx.import_state(&state),
+        Err(StateError::UnknownParticle("ghost".to_string()))
+    );
+    assert_eq!(ctx.export_state(), before, "failed import must not modify the world");
+}
+
+#[test]
+fn test_import_rejects_missing_particle() {
+    let mut state = run_to_end(HALF_RUN, None);
+    state.particles.retain(|p| p.name != "b");
+
+    let (mut ctx, _) = build_simulation_context_from_source(HALF_RUN).unwrap();
+    assert_eq!(
+        ctx.import_state(&state),
+        Err(StateError::MissingParticle("b".to_string()))
+    );
+}
@@ -0,0 +1,114 @@
+//! Tests for deterministic world/context state hashing
+
+use physlang_core::tests::test_helpers::{
+    extract_state_hash, golden_matches, hash_phys_source, result_to_json, run_phys_source,
+};
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, SimulationContext, StateHasher,
+};
+
+const PROGRAM: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+force gravity(a, b) G = 0.5
+simulate dt = 0.01 steps = 300
+loop for 5 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+well target on b if position(b).x >= 4.0 depth 10.0
+detect gap = distance(a, b)
+"#;
+
+#[test]
+fn test_identical_runs_hash_equal() {
+    let first = hash_phys_source(PROGRAM).unwrap();
+    let second = hash_phys_source(PROGRAM).unwrap();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_perturbed_run_hashes_differently() {
+    let perturbed = PROGRAM.replace("mass 2.0", "mass 2.0001");
+    let original = hash_phys_source(PROGRAM).unwrap();
+    let changed = hash_phys_source(&perturbed).unwrap();
+    assert_ne!(original, changed);
+}
+
+#[test]
+fn test_context_hash_tracks_stepping() {
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    let (other, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    assert_eq!(ctx.state_hash(), other.state_hash());
+    assert_eq!(ctx.world.state_hash(), other.world.state_hash());
+
+    step_simulation(&mut ctx);
+    assert_ne!(ctx.state_hash(), other.state_hash());
+}
+
+#[test]
+fn test_context_hash_includes_loop_state() {
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    let world_hash = ctx.world.state_hash();
+    let ctx_hash = ctx.state_hash();
+
+    ctx.loops[0].active = false;
+    assert_eq!(ctx.world.state_hash(), world_hash);
+    assert_ne!(ctx.state_hash(), ctx_hash);
+}
+
+#[test]
+fn test_run_result_hash_matches_final_context() {
+    let result = run_phys_source(PROGRAM).unwrap();
+    let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    while !step_simulation(&mut ctx) {}
+    assert_eq!(result.state_hash, ctx.state_hash());
+}
+
+#[test]
+fn test_snapshot_at_midpoint_continues_bit_identically() {
+    let (mut uninterrupted, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+    while !step_simulation(&mut uninterrupted) {}
+
+    let snapshot = {
+        let (mut ctx, _) = build_simulation_context_from_source(PROGRAM).unwrap();
+        for _ in 0..150 {
+            step_simulation(&mut ctx);
+        }
+        let mut bytes = Vec::new();
+        ctx.save(&mut bytes).unwrap();
+        bytes
+    };
+    let mut resumed = SimulationContext::load(snapshot.as_slice()).unwrap();
+    assert_eq!(resumed.current_step, 150);
+    while !step_simulation(&mut resumed) {}
+
+    assert_eq!(resumed.state_hash(), uninterrupted.state_hash());
+    let expected = uninterrupted.detector_values().unwrap();
+    let actual = resumed.detector_values().unwrap();
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(&expected) {
+        assert_eq!(a.name, e.name);
+        assert_eq!(a.value.to_bits(), e.value.to_bits());
+    }
+}
+
+#[test]
+fn test_golden_json_carries_state_hash() {
+    let result = run_phys_source(PROGRAM).unwrap();
+    let json = result_to_json(&result);
+    assert_eq!(extract_state_hash(&json), Some(result.state_hash));
+
+    // Formatting-only differences still match when hashes agree
+    let reformatted = json.replace("\"value\": ", "\"value\":");
+    assert!(golden_matches(&reformatted, &json));
+}
+
+#[test]
+fn test_hasher_is_stable() {
+    // FNV-1a reference values: the hash must never depend on process state
+    assert_eq!(StateHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+    let mut hasher = StateHasher::new();
+    hasher.write_bytes(b"a");
+    assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+}
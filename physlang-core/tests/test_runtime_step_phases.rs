@@ -0,0 +1,118 @@
+//! Tests that skipping the loop and well phases of worlds without loops or
+//! wells leaves every step's outcome unchanged
+
+use physlang_core::loops::{apply_wells, evaluate_loop_conditions, update_and_apply_loops};
+use physlang_core::{
+    build_simulation_context_from_source, step_simulation, step_with, SimulationContext,
+};
+
+const LOOP_AND_WELL: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 400
+loop for 5 cycles with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 1.0 direction (1.0, 0.0)
+}
+well target on b if position(b).x >= 4.0 depth 10.0
+detect gap = distance(a, b)
+"#;
+
+const WHILE_AND_WELL: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+well target on a if position(a).x >= 5.0 depth 10.0
+simulate dt = 0.01 steps = 400
+loop while position(a).x < 0.5 with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.3 direction (1.0, 0.0)
+}
+detect a_x = position(a).x
+"#;
+
+const SPRINGS_ONLY: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 400
+detect gap = distance(a, b)
+"#;
+
+/// One step running every phase, whatever the world has; the programs here
+/// use the lab frame, no host forces and no time-driven parameters, so those
+/// phases have nothing to do
+fn reference_step(ctx: &mut SimulationContext) {
+    update_and_apply_loops(&mut ctx.loops, &mut ctx.world.particles, ctx.dt);
+    apply_wells(&ctx.wells, &mut ctx.world.particles, ctx.dt);
+    step_with(&mut ctx.world, ctx.dt, ctx.integrator);
+    ctx.world.resolve_collisions();
+    evaluate_loop_conditions(&mut ctx.loops, &ctx.world.particles);
+}
+
+/// Step `ctx` and a reference copy of it in lockstep to the end of the run
+fn assert_matches_reference(source: &str, mut ctx: SimulationContext) {
+    let (mut reference, _) = build_simulation_context_from_source(source).unwrap();
+    for step in 0..ctx.max_steps {
+        step_simulation(&mut ctx);
+        reference_step(&mut reference);
+        assert_eq!(
+            ctx.world.state_hash(),
+            reference.world.state_hash(),
+            "step {}",
+            step
+        );
+        let active =
+            |ctx: &SimulationContext| ctx.loops.iter().map(|l| l.active).collect::<Vec<_>>();
+        assert_eq!(active(&ctx), active(&reference), "step {}", step);
+    }
+}
+
+#[test]
+fn test_loops_and_wells_still_run_every_step() {
+    for source in [LOOP_AND_WELL, WHILE_AND_WELL] {
+        let (ctx, _) = build_simulation_context_from_source(source).unwrap();
+        assert!(!ctx.loops.is_empty() && !ctx.wells.is_empty());
+        assert_matches_reference(source, ctx);
+    }
+}
+
+#[test]
+fn test_world_without_loops_or_wells_is_unchanged() {
+    let (ctx, _) = build_simulation_context_from_source(SPRINGS_ONLY).unwrap();
+    assert_matches_reference(SPRINGS_ONLY, ctx);
+}
+
+#[test]
+fn test_loaded_snapshot_keeps_its_phases() {
+    let (mut ctx, _) = build_simulation_context_from_source(LOOP_AND_WELL).unwrap();
+    let mut bytes = Vec::new();
+    ctx.save(&mut bytes).unwrap();
+    let mut loaded = SimulationContext::load(bytes.as_slice()).unwrap();
+    while !step_simulation(&mut ctx) {
+        step_simulation(&mut loaded);
+        assert_eq!(ctx.state_hash(), loaded.state_hash());
+    }
+    assert_matches_reference(
+        LOOP_AND_WELL,
+        SimulationContext::load(bytes.as_slice()).unwrap(),
+    );
+}
+
+#[test]
+fn test_loops_and_wells_added_later_run() {
+    let bare: String = LOOP_AND_WELL
+        .lines()
+        .filter(|line| !line.starts_with("loop") && !line.starts_with("well"))
+        .filter(|line| !line.starts_with("    force push") && *line != "}")
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let (mut ctx, _) = build_simulation_context_from_source(&bare).unwrap();
+    assert!(ctx.loops.is_empty() && ctx.wells.is_empty());
+
+    let (full, _) = build_simulation_context_from_source(LOOP_AND_WELL).unwrap();
+    for loop_inst in full.loops {
+        ctx.add_loop(loop_inst);
+    }
+    for well in full.wells {
+        ctx.add_well(well);
+    }
+    assert_matches_reference(LOOP_AND_WELL, ctx);
+}
@@ -0,0 +1,120 @@
+//! Tests for the symbol table that resolves particle names
+
+use physlang_core::engine::{Particle, World};
+use physlang_core::runtime::evaluate_detectors;
+use physlang_core::{
+    build_simulation_context_from_source, parse_program, SimulationContext, SymbolTable,
+};
+
+const SOURCE: &str = r#"
+fn moon(p, x) {
+    particle p at (x, 5.0) mass 0.1
+}
+particle sun at (0.0, 0.0) mass 10.0
+particle a at (3.0, 0.0) mass 1.0
+particle b at (3.0, 2.0) mass 1.0
+moon("c", 1.0)
+force spring(sun, a) k = 2.0 rest = 1.0
+well w on a if position(a).x >= 5.0 depth 1.0
+simulate dt = 0.01 steps = 20
+detect gap = distance(a, b)
+detect sun_x = position(sun)
+"#;
+
+#[test]
+fn test_ids_follow_insertion_order() {
+    let mut table = SymbolTable::new();
+    assert!(table.is_empty());
+    let a = table.push("a");
+    let b = table.push("b");
+    assert_eq!((a.index(), b.index()), (0, 1));
+    assert_eq!(table.resolve("b"), Some(b));
+    assert_eq!(table.resolve("c"), None);
+    assert_eq!(table.name(a), "a");
+
+    // A repeated name gets its own id but resolves to the first
+    let again = table.push("a");
+    assert_eq!(again.index(), 2);
+    assert_eq!(table.resolve("a"), Some(a));
+    assert_eq!(table.name(again), "a");
+    let names: Vec<&str> = table.iter().map(|(_, name)| name).collect();
+    assert_eq!(names, ["a", "b", "a"]);
+}
+
+#[test]
+fn test_context_symbols_index_particles() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let symbols = ctx.symbols();
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.resolve("c").unwrap().index(), 3);
+    for (id, name) in symbols.iter() {
+        assert_eq!(ctx.world.particles[id.index()].name, name);
+        assert_eq!(symbols.resolve(name), Some(id));
+    }
+    assert_eq!(
+        ctx.wells[0].particle_index,
+        symbols.resolve("a").unwrap().index()
+    );
+}
+
+#[test]
+fn test_detectors_resolve_the_same_way() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let program = parse_program(SOURCE).unwrap();
+    let from_context = ctx.detector_values().unwrap();
+    let from_world = evaluate_detectors(&program, &ctx.world, ctx.time, &mut Vec::new()).unwrap();
+    assert_eq!(from_context.len(), 2);
+    for (a, b) in from_context.iter().zip(&from_world) {
+        assert_eq!((&a.name, a.value), (&b.name, b.value));
+    }
+    assert_eq!(from_context[0].value, 2.0);
+}
+
+#[test]
+fn test_loaded_context_keeps_its_symbols() {
+    let (ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let mut bytes = Vec::new();
+    ctx.save(&mut bytes).unwrap();
+    let loaded = SimulationContext::load(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.symbols(), ctx.symbols());
+}
+
+#[test]
+fn test_symbols_follow_merged_particles() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    let mut other = World::new();
+    other.particles.push(Particle {
+        name: "probe".to_string(),
+        ..Default::default()
+    });
+    ctx.world.merge(other, "extra.").unwrap();
+
+    let symbols = ctx.symbols();
+    assert_eq!(symbols.len(), 5);
+    assert_eq!(symbols.resolve("extra.probe").unwrap().index(), 4);
+    assert_eq!(symbols.resolve("a").unwrap().index(), 1);
+    assert_eq!(ctx.detector_values().unwrap()[0].value, 2.0);
+}
+
+#[test]
+fn test_world_names_the_particles_it_is_given() {
+    let mut world = World::new();
+    let a = world.add_particle(Particle {
+        name: "a".to_string(),
+        ..Default::default()
+    });
+    // A particle pushed directly is named once the world is next extended
+    world.particles.push(Particle {
+        name: "b".to_string(),
+        ..Default::default()
+    });
+    let c = world.add_particle(Particle {
+        name: "c".to_string(),
+        ..Default::default()
+    });
+
+    let symbols = world.symbols();
+    assert_eq!((a.index(), c.index()), (0, 2));
+    assert_eq!(symbols.resolve("b").unwrap().index(), 1);
+    assert_eq!(symbols, &SymbolTable::of_particles(&world.particles));
+}
@@ -0,0 +1,56 @@
+//! Running a simulation on a worker thread and streaming particle states back
+
+use physlang_core::{
+    build_simulation_context_from_source, get_particle_states, step_simulation, ParticleState,
+    SimulationContext, SimulationResult, World,
+};
+use std::sync::mpsc;
+use std::thread;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_context_types_are_send_and_sync() {
+    assert_send::<SimulationContext>();
+    assert_sync::<SimulationContext>();
+    assert_send::<World>();
+    assert_send::<ParticleState>();
+    assert_send::<SimulationResult>();
+}
+
+#[test]
+fn test_step_on_worker_thread() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let (tx, rx) = mpsc::channel::<Vec<ParticleState>>();
+
+    // Worker: step the simulation and send a batch every 10 steps
+    let worker = thread::spawn(move || {
+        while !step_simulation(&mut ctx) {
+            if ctx.current_step % 10 == 0 {
+                tx.send(get_particle_states(&ctx)).unwrap();
+            }
+        }
+        tx.send(get_particle_states(&ctx)).unwrap();
+        ctx
+    });
+
+    // Receiver (the GUI thread in a real embedding)
+    let batches: Vec<Vec<ParticleState>> = rx.iter().collect();
+    let ctx = worker.join().unwrap();
+
+    assert_eq!(ctx.current_step, 100);
+    assert_eq!(batches.len(), 10);
+    assert!(batches.iter().all(|batch| batch.len() == 2));
+
+    // The last batch reflects the final world
+    let last = batches.last().unwrap();
+    assert_eq!(last[0].name, "a");
+    assert_eq!(last[0].pos, ctx.world.particles[0].pos);
+}
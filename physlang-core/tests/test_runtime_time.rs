@@ -0,0 +1,58 @@
+//! Tests for simulated time tracking and the time() detector
+
+use physlang_core::tests::test_helpers::{approx_eq_f32, run_phys_source};
+use physlang_core::{build_simulation_context_from_source, run_program_to_csv, step_simulation};
+
+const SOURCE: &str = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 250
+detect t = time()
+"#;
+
+#[test]
+fn test_elapsed_time_matches_steps_times_dt() {
+    let result = run_phys_source(SOURCE).unwrap();
+    assert!(approx_eq_f32(result.elapsed_time, 250.0 * 0.01, 1e-4));
+}
+
+#[test]
+fn test_time_detector() {
+    let result = run_phys_source(SOURCE).unwrap();
+    assert_eq!(result.detectors[0].name, "t");
+    assert_eq!(result.detectors[0].value, result.elapsed_time);
+}
+
+#[test]
+fn test_context_time_advances_by_dt() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    assert_eq!(ctx.time, 0.0);
+    step_simulation(&mut ctx);
+    assert_eq!(ctx.time, 0.01);
+    while !step_simulation(&mut ctx) {}
+    assert!(approx_eq_f32(ctx.time, 2.5, 1e-4));
+}
+
+#[test]
+fn test_time_follows_dt_changes() {
+    let (mut ctx, _) = build_simulation_context_from_source(SOURCE).unwrap();
+    for _ in 0..100 {
+        step_simulation(&mut ctx);
+    }
+    ctx.apply_simulate_change(0.02, 250);
+    while !step_simulation(&mut ctx) {}
+
+    // 100 steps at 0.01, then 150 at 0.02
+    assert!(approx_eq_f32(ctx.time, 1.0 + 3.0, 1e-4));
+}
+
+#[test]
+fn test_csv_trace_includes_time() {
+    let mut buffer = Vec::new();
+    run_program_to_csv(SOURCE, &mut buffer, 50).unwrap();
+    let csv = String::from_utf8(buffer).unwrap();
+    let last = csv.lines().last().unwrap();
+    let time: f32 = last.split(',').nth(1).unwrap().parse().unwrap();
+    assert!(approx_eq_f32(time, 2.5, 1e-4));
+}
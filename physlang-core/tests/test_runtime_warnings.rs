@@ -0,0 +1,52 @@
+//! Tests for runtime warnings collected into SimulationResult
+
+use physlang_core::{run_program, run_program_with_options, DiagnosticSeverity, RunOptions};
+
+#[test]
+fn test_nan_detector_produces_warning() {
+    // The stiff spring blows up to inf and then NaN once divergence checks are off
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10000.0 rest = 1.0
+simulate dt = 0.1 steps = 1000
+detect gap = distance(a, b)
+"#;
+    let options = RunOptions {
+        allow_divergence: true,
+        ..Default::default()
+    };
+    let result = run_program_with_options(source, &options).unwrap();
+    assert!(result.detectors[0].value.is_nan());
+
+    assert_eq!(result.warnings.len(), 1);
+    let warning = &result.warnings[0];
+    assert!(matches!(warning.severity, DiagnosticSeverity::Warning));
+    assert!(warning.message.contains("'gap'"), "{}", warning.message);
+}
+
+#[test]
+fn test_clean_run_has_no_warnings() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+"#;
+    let result = run_program(source).unwrap();
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn test_distance_well_warns_it_is_skipped() {
+    let source = r#"
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 1.0
+well pull on a if distance(a, b) >= 1.0 depth 5.0
+simulate dt = 0.01 steps = 10
+"#;
+    let result = run_program(source).unwrap();
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].message.contains("distance well on 'a'"));
+}
@@ -0,0 +1,203 @@
+//! Tests for the `mass(a)` and `tag(a, name)` observables, and the tags
+//! particles declare
+
+use physlang_core::ast::{ConditionExpr, Expr, LoopKind, ObservableExpr};
+use physlang_core::loops::ObservableRuntime;
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, format_source, parse_program,
+    step_simulation, SimulationContext,
+};
+
+/// A light and a heavy particle, both given a push to the right
+const PAIR: &str = "\
+particle light at (0.0, 0.0) mass 1.0
+particle heavy at (0.0, 2.0) mass 10.0
+simulate dt = 0.01 steps = 300
+loop for 1 cycles with frequency 10.0 damping 0.0 on light {
+    force push(light) magnitude 1.0 direction (1.0, 0.0)
+}
+loop for 1 cycles with frequency 10.0 damping 0.0 on heavy {
+    force push(heavy) magnitude 1.0 direction (1.0, 0.0)
+}
+";
+
+const TRAPS: &str = "\
+well trap_light on light if mass(light) >= 5.0 depth 20.0
+well trap_heavy on heavy if mass(heavy) >= 5.0 depth 20.0
+";
+
+fn run_to_end(source: &str) -> SimulationContext {
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    while !step_simulation(&mut ctx) {}
+    ctx
+}
+
+fn analysis_errors(source: &str) -> Vec<String> {
+    let program = parse_program(source).unwrap();
+    analyze_program(&program)
+        .errors()
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn test_mass_well_only_captures_the_heavy_particle() {
+    let free = run_to_end(PAIR);
+    let trapped = run_to_end(&format!("{}{}", PAIR, TRAPS));
+
+    let (light, heavy) = (&trapped.world.particles[0], &trapped.world.particles[1]);
+    assert_eq!(light.pos, free.world.particles[0].pos);
+    assert_eq!(light.vel, free.world.particles[0].vel);
+    assert!(free.world.particles[1].pos.x > 2.0);
+    assert!(heavy.pos.x < 0.5, "{}", heavy.pos.x);
+    assert!(heavy.vel.x < 0.01, "{}", heavy.vel.x);
+
+    assert!(!trapped.wells[0].is_applying(&trapped.world.particles));
+    assert!(trapped.wells[1].is_applying(&trapped.world.particles));
+    assert!(matches!(
+        trapped.wells[1].observable,
+        ObservableRuntime::Mass(1)
+    ));
+}
+
+#[test]
+fn test_tag_well_reads_the_declared_value() {
+    let source = format!(
+        "{}well hold on light if tag(light, group) >= 2.0 depth 20.0\n",
+        PAIR.replace("mass 1.0\n", "mass 1.0 tag group = 1.0 + 1.0\n")
+    );
+    let ctx = run_to_end(&source);
+    assert_eq!(ctx.world.particles[0].tags["group"], 2.0);
+    assert!(ctx.world.particles[1].tags.is_empty());
+    assert!(ctx.wells[0].is_applying(&ctx.world.particles));
+    assert!(ctx.world.particles[0].vel.x < 0.01);
+}
+
+#[test]
+fn test_conditions_on_mass_and_tags() {
+    let source = "\
+particle a at (0.0, 0.0) mass 2.0 tag charge = -1.0
+simulate dt = 0.01 steps = 10
+loop while mass(a) > 1.0 with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.1 direction (1.0, 0.0)
+}
+loop while tag(a, charge) < 0.0 with frequency 1.0 damping 0.0 on a {
+    force push(a) magnitude 0.1 direction (1.0, 0.0)
+}
+";
+    let program = parse_program(source).unwrap();
+    let conditions: Vec<_> = program
+        .loops
+        .iter()
+        .map(|l| match &l.kind {
+            LoopKind::WhileCondition { condition, .. } => condition.clone(),
+            LoopKind::ForCycles { .. } => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        conditions,
+        [
+            ConditionExpr::GreaterThan(ObservableExpr::Mass("a".into()), Expr::Literal(1.0)),
+            ConditionExpr::LessThan(
+                ObservableExpr::Tag("a".into(), "charge".into()),
+                Expr::Literal(0.0)
+            ),
+        ]
+    );
+    assert!(analysis_errors(source).is_empty());
+
+    let ctx = run_to_end(source);
+    assert!(ctx.loops.iter().all(|l| l.active));
+}
+
+#[test]
+fn test_unknown_tag_is_an_error() {
+    let source = format!(
+        "{}well hold on light if tag(light, group) >= 2.0 depth 20.0\n",
+        PAIR.replace("mass 1.0\n", "mass 1.0 tag kind = 1.0\n")
+    );
+    assert_eq!(
+        analysis_errors(&source),
+        ["unknown tag 'group' on particle 'light'"]
+    );
+    let program = parse_program(&source).unwrap();
+    let diagnostics = analyze_program(&program);
+    let error = diagnostics.errors().next().unwrap();
+    assert_eq!(error.help.as_deref(), Some("declared tags: kind"));
+
+    let untagged = format!(
+        "{}loop while tag(heavy, kind) > 0.0 with frequency 1.0 damping 0.0 on heavy {{\n}}\n",
+        PAIR
+    );
+    assert_eq!(
+        analysis_errors(&untagged),
+        ["unknown tag 'kind' on particle 'heavy'"]
+    );
+    assert!(build_simulation_context_from_source(&untagged).is_err());
+}
+
+#[test]
+fn test_malformed_tags_are_parse_errors() {
+    for (particle, message) in [
+        ("mass 1.0 tag group", "Expected 'tag name = value'"),
+        ("mass 1.0 tag = 2.0", "Expected 'tag name = value'"),
+        ("mass 1.0 tag g = 1.0 tag g = 2.0", "Duplicate tag 'g'"),
+    ] {
+        let source = format!(
+            "particle a at (0.0, 0.0) {}\nsimulate dt = 0.01 steps = 1\n",
+            particle
+        );
+        let error = parse_program(&source).unwrap_err();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+}
+
+#[test]
+fn test_tags_round_trip_through_function_generated_particles() {
+    let source = r#"fn make(p: name, g) {
+    particle p at (g, 0.0) mass 1.0 damping 0.5 tag group = g * 2.0 tag rank = 7.0
+}
+
+make("a", 1.0)
+make("b", 2.0)
+simulate dt = 0.01 steps = 10
+"#;
+    let (mut ctx, _) = build_simulation_context_from_source(source).unwrap();
+    let groups: Vec<_> = ctx
+        .world
+        .particles
+        .iter()
+        .map(|p| (p.name.as_str(), p.tags["group"], p.tags["rank"], p.damping))
+        .collect();
+    assert_eq!(groups, [("a", 2.0, 7.0, 0.5), ("b", 4.0, 7.0, 0.5)]);
+
+    // Tags survive a snapshot and take part in the state hash
+    step_simulation(&mut ctx);
+    let mut bytes = Vec::new();
+    ctx.save(&mut bytes).unwrap();
+    let mut loaded = SimulationContext::load(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.world.particles[1].tags, ctx.world.particles[1].tags);
+    assert_eq!(loaded.state_hash(), ctx.state_hash());
+    loaded.world.particles[1].tags.insert("group".into(), 5.0);
+    assert_ne!(loaded.state_hash(), ctx.state_hash());
+}
+
+#[test]
+fn test_format_prints_tags_and_observables() {
+    let source = format!(
+        "{}{}",
+        PAIR.replace("mass 1.0\n", "mass 1.0 tag group=1.0+1.0\n"),
+        "well hold on light if tag(light,group) >= 2.0 depth 20.0\n\
+         well trap on heavy if mass(heavy) >= 5.0 depth 20.0\n"
+    );
+    let formatted = format_source(&source).unwrap();
+    assert!(formatted.contains("mass 1.0 tag group = 1.0 + 1.0\n"));
+    assert!(formatted.contains("well hold on light if tag(light, group) >= 2.0 depth 20.0\n"));
+    assert!(formatted.contains("well trap on heavy if mass(heavy) >= 5.0 depth 20.0\n"));
+    let (reparsed, original) = (
+        parse_program(&formatted).unwrap(),
+        parse_program(&source).unwrap(),
+    );
+    assert_eq!(reparsed.particles, original.particles);
+    assert_eq!(reparsed.wells, original.wells);
+}
@@ -12,6 +12,7 @@ fn test_well_below_threshold_no_force() {
         pos: Vec2::new(3.0, 0.0), // Below threshold of 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -19,6 +20,7 @@ fn test_well_below_threshold_no_force() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let initial_vel = particles[0].vel;
@@ -35,6 +37,7 @@ fn test_well_above_threshold_applies_force() {
         pos: Vec2::new(7.0, 0.0), // Above threshold of 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -42,6 +45,7 @@ fn test_well_above_threshold_applies_force() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let initial_vel = particles[0].vel;
@@ -65,6 +69,7 @@ fn test_well_at_threshold() {
         pos: Vec2::new(5.0, 0.0), // Exactly at threshold
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -72,6 +77,7 @@ fn test_well_at_threshold() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let initial_vel = particles[0].vel;
@@ -88,6 +94,7 @@ fn test_well_force_direction() {
         pos: Vec2::new(10.0, 0.0), // Well above threshold of 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -95,6 +102,7 @@ fn test_well_force_direction() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     apply_wells(&wells, &mut particles, 0.01);
@@ -110,6 +118,7 @@ fn test_well_position_y() {
         pos: Vec2::new(0.0, 8.0), // Above threshold of 5.0 in y
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -117,6 +126,7 @@ fn test_well_position_y() {
         observable: ObservableRuntime::PositionY(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let dt = 0.01;
@@ -135,6 +145,7 @@ fn test_well_different_depth() {
         pos: Vec2::new(7.0, 0.0), // Above threshold of 5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -142,6 +153,7 @@ fn test_well_different_depth() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 20.0, // Different depth
+        name: "w".to_string(),
     }];
     
     let dt = 0.01;
@@ -159,6 +171,7 @@ fn test_well_different_mass() {
         pos: Vec2::new(7.0, 0.0),
         vel: Vec2::ZERO,
         mass: 2.0, // Different mass
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -166,6 +179,7 @@ fn test_well_different_mass() {
         observable: ObservableRuntime::PositionX(0),
         threshold: 5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let dt = 0.01;
@@ -183,6 +197,7 @@ fn test_well_negative_threshold() {
         pos: Vec2::new(-3.0, 0.0), // Above threshold of -5.0
         vel: Vec2::ZERO,
         mass: 1.0,
+        ..Default::default()
     }];
     
     let wells = vec![WellInstance {
@@ -190,6 +205,7 @@ fn test_well_negative_threshold() {
         observable: ObservableRuntime::PositionX(0),
         threshold: -5.0,
         depth: 10.0,
+        name: "w".to_string(),
     }];
     
     let dt = 0.01;
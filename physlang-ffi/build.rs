@@ -0,0 +1,7 @@
+// The C test program is compiled for the same target as the library
+fn main() {
+    println!(
+        "cargo:rustc-env=PHYSLANG_FFI_TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+}
@@ -0,0 +1,80 @@
+#ifndef PHYSLANG_H
+#define PHYSLANG_H
+
+/* Generated by cbindgen from physlang-ffi/src/lib.rs; do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A running simulation
+typedef struct PhysSim PhysSim;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Build a simulation of `source` at step 0. On failure returns NULL and,
+// if `err_out` isn't NULL, stores a message there for `phys_string_free`
+//
+// # Safety
+// `source` must be a NUL-terminated string and `err_out` NULL or writable
+PhysSim *phys_sim_create(const char *source, char **err_out);
+
+// Take up to `n` steps. Returns 1 once the run has finished or diverged,
+// 0 if it can go on, and -1 if `sim` is NULL
+//
+// # Safety
+// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+int phys_sim_step(PhysSim *sim, int n);
+
+// Number of particles, or -1 if `sim` is NULL
+//
+// # Safety
+// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+int phys_sim_particle_count(const PhysSim *sim);
+
+// Store the position and velocity of particle `idx` through the pointers
+// that aren't NULL. Returns 0, or -1 if `sim` is NULL or there's no such
+// particle
+//
+// # Safety
+// `sim` must be NULL or from `phys_sim_create` and not yet destroyed; each
+// output pointer must be NULL or writable
+int phys_sim_particle_state(const PhysSim *sim,
+                            int idx,
+                            float *x,
+                            float *y,
+                            float *vx,
+                            float *vy);
+
+// Store the current value of detector `name` in `out`. Returns 0, or -1 if
+// a pointer is NULL, there's no such detector, or it can't be evaluated
+//
+// # Safety
+// `sim` must be NULL or from `phys_sim_create` and not yet destroyed, `name`
+// NULL or a NUL-terminated string, and `out` NULL or writable
+int phys_sim_detector(const PhysSim *sim, const char *name, float *out);
+
+// Free a simulation; NULL is ignored
+//
+// # Safety
+// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+void phys_sim_destroy(PhysSim *sim);
+
+// The language version and supported features as a JSON object, like
+// `physlang info --json`; free it with `phys_string_free`
+char *phys_capabilities_json(void);
+
+// Free a string returned by this library; NULL is ignored
+//
+// # Safety
+// `s` must be NULL or a string from this library not yet freed
+void phys_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PHYSLANG_H */
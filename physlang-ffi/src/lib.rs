@@ -0,0 +1,190 @@
+//! C API for embedding PhysLang in non-Rust hosts
+//!
+//! A simulation is an opaque `PhysSim` pointer from `phys_sim_create`, freed
+//! with `phys_sim_destroy`. Strings passed in and out are UTF-8 and
+//! NUL-terminated; error strings are allocated here and freed with
+//! `phys_string_free`. `include/physlang.h` declares the API for C and C++.
+
+use physlang_core::{
+    build_simulation_context_from_source, language_capabilities, step_simulation, SimulationContext,
+};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+/// A running simulation
+pub struct PhysSim {
+    ctx: SimulationContext,
+}
+
+/// Build a simulation of `source` at step 0. On failure returns NULL and,
+/// if `err_out` isn't NULL, stores a message there for `phys_string_free`
+///
+/// # Safety
+/// `source` must be a NUL-terminated string and `err_out` NULL or writable
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_create(
+    source: *const c_char,
+    err_out: *mut *mut c_char,
+) -> *mut PhysSim {
+    if !err_out.is_null() {
+        *err_out = ptr::null_mut();
+    }
+    match create(source) {
+        Ok(sim) => Box::into_raw(Box::new(sim)),
+        Err(message) => {
+            if !err_out.is_null() {
+                *err_out = into_c_string(message);
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn create(source: *const c_char) -> Result<PhysSim, String> {
+    if source.is_null() {
+        return Err("source is NULL".to_string());
+    }
+    let source = CStr::from_ptr(source)
+        .to_str()
+        .map_err(|e| format!("source is not valid UTF-8: {}", e))?;
+    let (ctx, _) = build_simulation_context_from_source(source).map_err(|e| e.to_string())?;
+    Ok(PhysSim { ctx })
+}
+
+/// Take up to `n` steps. Returns 1 once the run has finished or diverged,
+/// 0 if it can go on, and -1 if `sim` is NULL
+///
+/// # Safety
+/// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_step(sim: *mut PhysSim, n: c_int) -> c_int {
+    let Some(sim) = sim.as_mut() else {
+        return -1;
+    };
+    for _ in 0..n.max(0) {
+        if step_simulation(&mut sim.ctx) {
+            return 1;
+        }
+    }
+    0
+}
+
+/// Number of particles, or -1 if `sim` is NULL
+///
+/// # Safety
+/// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_particle_count(sim: *const PhysSim) -> c_int {
+    match sim.as_ref() {
+        Some(sim) => sim.ctx.world.particles.len() as c_int,
+        None => -1,
+    }
+}
+
+/// Store the position and velocity of particle `idx` through the pointers
+/// that aren't NULL. Returns 0, or -1 if `sim` is NULL or there's no such
+/// particle
+///
+/// # Safety
+/// `sim` must be NULL or from `phys_sim_create` and not yet destroyed; each
+/// output pointer must be NULL or writable
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_particle_state(
+    sim: *const PhysSim,
+    idx: c_int,
+    x: *mut f32,
+    y: *mut f32,
+    vx: *mut f32,
+    vy: *mut f32,
+) -> c_int {
+    let Some(sim) = sim.as_ref() else {
+        return -1;
+    };
+    let Some(particle) = usize::try_from(idx)
+        .ok()
+        .and_then(|i| sim.ctx.world.particles.get(i))
+    else {
+        return -1;
+    };
+    for (out, value) in [
+        (x, particle.pos.x),
+        (y, particle.pos.y),
+        (vx, particle.vel.x),
+        (vy, particle.vel.y),
+    ] {
+        if !out.is_null() {
+            *out = value;
+        }
+    }
+    0
+}
+
+/// Store the current value of detector `name` in `out`. Returns 0, or -1 if
+/// a pointer is NULL, there's no such detector, or it can't be evaluated
+///
+/// # Safety
+/// `sim` must be NULL or from `phys_sim_create` and not yet destroyed, `name`
+/// NULL or a NUL-terminated string, and `out` NULL or writable
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_detector(
+    sim: *const PhysSim,
+    name: *const c_char,
+    out: *mut f32,
+) -> c_int {
+    let Some(sim) = sim.as_ref() else {
+        return -1;
+    };
+    if name.is_null() || out.is_null() {
+        return -1;
+    }
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return -1;
+    };
+    let Ok(values) = sim.ctx.detector_values() else {
+        return -1;
+    };
+    match values.iter().find(|d| d.name == name) {
+        Some(detector) => {
+            *out = detector.value;
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Free a simulation; NULL is ignored
+///
+/// # Safety
+/// `sim` must be NULL or from `phys_sim_create` and not yet destroyed
+#[no_mangle]
+pub unsafe extern "C" fn phys_sim_destroy(sim: *mut PhysSim) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
+
+/// The language version and supported features as a JSON object, like
+/// `physlang info --json`; free it with `phys_string_free`
+#[no_mangle]
+pub extern "C" fn phys_capabilities_json() -> *mut c_char {
+    let json = serde_json::to_string(&language_capabilities()).expect("the report is plain data");
+    into_c_string(json)
+}
+
+/// Free a string returned by this library; NULL is ignored
+///
+/// # Safety
+/// `s` must be NULL or a string from this library not yet freed
+#[no_mangle]
+pub unsafe extern "C" fn phys_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// `message` as a heap C string; interior NULs can't cross the boundary, so
+/// they're replaced
+fn into_c_string(message: String) -> *mut c_char {
+    let message = message.replace('\0', "\u{FFFD}");
+    CString::new(message).expect("no interior NULs").into_raw()
+}
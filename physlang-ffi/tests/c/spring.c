@@ -0,0 +1,56 @@
+/* Steps a spring through the C API; exits non-zero on the first failure */
+
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "physlang.h"
+
+#define CHECK(cond)                                                        \
+    do {                                                                   \
+        if (!(cond)) {                                                     \
+            fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #cond);     \
+            return 1;                                                      \
+        }                                                                  \
+    } while (0)
+
+static const char *SPRING =
+    "particle a at (0.0, 0.0) mass 1.0\n"
+    "particle b at (3.0, 0.0) mass 2.0\n"
+    "force spring(a, b) k = 10.0 rest = 2.0\n"
+    "simulate dt = 0.01 steps = 200\n"
+    "detect gap = distance(a, b)\n";
+
+int main(void) {
+    char *err = NULL;
+    PhysSim *sim = phys_sim_create(SPRING, &err);
+    CHECK(sim != NULL);
+    CHECK(err == NULL);
+    CHECK(phys_sim_particle_count(sim) == 2);
+
+    CHECK(phys_sim_step(sim, 100) == 0);
+    float ax, ay, avx, avy, bx;
+    CHECK(phys_sim_particle_state(sim, 0, &ax, &ay, &avx, &avy) == 0);
+    CHECK(phys_sim_particle_state(sim, 1, &bx, NULL, NULL, NULL) == 0);
+    CHECK(phys_sim_particle_state(sim, 2, &bx, NULL, NULL, NULL) == -1);
+    /* The stretched spring pulled the particles together */
+    CHECK(ax > 0.0f && bx < 3.0f);
+
+    float gap;
+    CHECK(phys_sim_detector(sim, "gap", &gap) == 0);
+    CHECK(fabsf(gap - (bx - ax)) < 1e-4f);
+    CHECK(phys_sim_detector(sim, "missing", &gap) == -1);
+
+    CHECK(phys_sim_step(sim, 1000) == 1);
+    phys_sim_destroy(sim);
+
+    sim = phys_sim_create("particle a at (0.0", &err);
+    CHECK(sim == NULL);
+    CHECK(err != NULL && strlen(err) > 0);
+    phys_string_free(err);
+
+    CHECK(phys_sim_step(NULL, 1) == -1);
+    phys_sim_destroy(NULL);
+    puts("ok");
+    return 0;
+}
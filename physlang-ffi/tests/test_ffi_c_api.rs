@@ -0,0 +1,80 @@
+//! Compile tests/c/spring.c against the cdylib and run it
+
+use physlang_ffi::{phys_capabilities_json, phys_sim_create, phys_string_free};
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::ptr;
+
+/// Where cargo put the cdylib: the test binary sits in `target/<profile>/deps`
+#[cfg(unix)]
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_c_program_steps_a_spring() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = std::env::temp_dir().join(format!("physlang-ffi-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let exe = out_dir.join("spring");
+    let lib_dir = library_dir();
+
+    let target = env!("PHYSLANG_FFI_TARGET");
+    let compiler = cc::Build::new()
+        .target(target)
+        .host(target)
+        .opt_level(0)
+        .cargo_metadata(false)
+        .get_compiler();
+    let status = compiler
+        .to_command()
+        .arg(manifest.join("tests/c/spring.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg("-o")
+        .arg(&exe)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg("-lphyslang_ffi")
+        .arg("-lm")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling spring.c failed");
+
+    let output = Command::new(&exe).output().unwrap();
+    std::fs::remove_dir_all(&out_dir).ok();
+    assert!(
+        output.status.success(),
+        "spring.c failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+}
+
+#[test]
+fn test_create_reports_invalid_utf8() {
+    let source = b"particle \xff at (0.0, 0.0) mass 1.0\n\0";
+    let mut err = ptr::null_mut();
+    unsafe {
+        let sim = phys_sim_create(source.as_ptr().cast(), &mut err);
+        assert!(sim.is_null());
+        let message = CStr::from_ptr(err).to_str().unwrap().to_string();
+        assert!(message.contains("UTF-8"), "{}", message);
+        phys_string_free(err);
+    }
+}
+
+#[test]
+fn test_capabilities_json() {
+    unsafe {
+        let json = phys_capabilities_json();
+        let text = CStr::from_ptr(json).to_str().unwrap().to_string();
+        phys_string_free(json);
+        let report: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(report["language_version"], physlang_core::LANGUAGE_VERSION);
+        assert!(report["detectors"].as_array().unwrap().len() > 1);
+    }
+}
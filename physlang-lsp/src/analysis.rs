@@ -0,0 +1,252 @@
+//! What the language server knows about one document
+//!
+//! The AST carries no source positions, so declarations are found by
+//! scanning the text: a top-level line starting with `particle`, `fn`,
+//! `detect`, or `let` declares the name that follows. Scanning the text
+//! also keeps symbols, hover, and completion working while the document
+//! doesn't parse.
+
+use physlang_core::ast::LetDecl;
+use physlang_core::{
+    analyze_program, evaluate_lets, lint_source, parse_expression, parse_program, Diagnostic, Span,
+};
+
+/// What a declaration declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Particle,
+    Function,
+    Detector,
+    Let,
+}
+
+impl DeclarationKind {
+    const ALL: [DeclarationKind; 4] = [
+        DeclarationKind::Particle,
+        DeclarationKind::Function,
+        DeclarationKind::Detector,
+        DeclarationKind::Let,
+    ];
+
+    fn keyword(self) -> &'static str {
+        match self {
+            DeclarationKind::Particle => "particle",
+            DeclarationKind::Function => "fn",
+            DeclarationKind::Detector => "detect",
+            DeclarationKind::Let => "let",
+        }
+    }
+}
+
+/// A name declared at the top level of the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub kind: DeclarationKind,
+    pub name: String,
+    /// Byte range of the name
+    pub name_span: Span,
+    /// Byte range of the declaration, through the closing brace of a function
+    pub span: Span,
+    /// The declaration's first line, without its comment
+    pub detail: String,
+}
+
+/// Parse errors, or static analysis and lint diagnostics, of `text`
+pub fn check(text: &str) -> Vec<Diagnostic> {
+    match parse_program(text) {
+        Err(e) => vec![Diagnostic::from(&e)],
+        Ok(program) => {
+            let mut diagnostics: Vec<Diagnostic> = analyze_program(&program).into_iter().collect();
+            diagnostics.extend(lint_source(text));
+            diagnostics
+        }
+    }
+}
+
+/// Where to show `diagnostic` in `text`: the first use of the name it
+/// quotes within its span, or the span itself. Diagnostics without a span
+/// search the whole document, and failing that point at its start
+pub fn locate(text: &str, diagnostic: &Diagnostic) -> Span {
+    let fallback = diagnostic.span.unwrap_or(Span::new(0, 0));
+    let within = diagnostic.span.unwrap_or(Span::new(0, text.len()));
+    let quoted = diagnostic
+        .message
+        .split('\'')
+        .nth(1)
+        .filter(|name| !name.is_empty() && name.chars().all(is_identifier_char));
+    quoted
+        .zip(text.get(within.start..within.end))
+        .and_then(|(name, searched)| find_word(searched, name))
+        .map_or(fallback, |found| {
+            Span::new(within.start + found.start, within.start + found.end)
+        })
+}
+
+/// Every top-level declaration in `text`, in source order
+pub fn declarations(text: &str) -> Vec<Declaration> {
+    let mut found = Vec::new();
+    let mut open_function = None;
+    let mut depth = 0usize;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let code = line.split('#').next().unwrap_or("");
+        if depth == 0 {
+            if let Some(declaration) = declaration(code, offset) {
+                if declaration.kind == DeclarationKind::Function {
+                    open_function = Some(found.len());
+                }
+                found.push(declaration);
+            }
+        }
+        let opens = code.matches('{').count();
+        let closes = code.matches('}').count();
+        depth = (depth + opens).saturating_sub(closes);
+        if depth == 0 {
+            if let Some(i) = open_function.take() {
+                found[i].span.end = offset + code.trim_end().len();
+            }
+        }
+        offset += line.len();
+    }
+    found
+}
+
+/// The declaration on a line of code starting at byte `offset`, if any
+fn declaration(code: &str, offset: usize) -> Option<Declaration> {
+    let trimmed = code.trim_start();
+    let indent = code.len() - trimmed.len();
+    let kind = DeclarationKind::ALL.into_iter().find(|kind| {
+        trimmed
+            .strip_prefix(kind.keyword())
+            .is_some_and(|rest| rest.starts_with(char::is_whitespace))
+    })?;
+    let after = &trimmed[kind.keyword().len()..];
+    let name = after.trim_start();
+    let name_len = name
+        .find(|c: char| !is_identifier_char(c))
+        .unwrap_or(name.len());
+    if name_len == 0 {
+        return None;
+    }
+    let name_start = offset + indent + kind.keyword().len() + (after.len() - name.len());
+    let detail = trimmed.trim_end().trim_end_matches('{').trim_end();
+    Some(Declaration {
+        kind,
+        name: name[..name_len].to_string(),
+        name_span: Span::new(name_start, name_start + name_len),
+        span: Span::new(offset + indent, offset + indent + trimmed.trim_end().len()),
+        detail: detail.to_string(),
+    })
+}
+
+/// The value of let binding `name`, if its expression and those it uses
+/// evaluate to constants
+pub fn let_value(text: &str, name: &str) -> Option<f32> {
+    let lets: Vec<LetDecl> = declarations(text)
+        .into_iter()
+        .filter(|d| d.kind == DeclarationKind::Let)
+        .filter_map(|d| {
+            let expr = text[d.name_span.end..d.span.end]
+                .trim_start()
+                .strip_prefix('=')?;
+            let expr = parse_expression(expr.trim()).ok()?;
+            Some(LetDecl { name: d.name, expr })
+        })
+        .collect();
+    let (ctx, _) = evaluate_lets(&lets);
+    ctx.values.get(name).copied()
+}
+
+/// Names of the particles declared at the top level, in source order
+pub fn particle_names(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for declaration in declarations(text) {
+        if declaration.kind == DeclarationKind::Particle && !names.contains(&declaration.name) {
+            names.push(declaration.name);
+        }
+    }
+    names
+}
+
+/// Whether a particle name belongs at byte `offset`: inside the parentheses
+/// of a `force`, `detect`, `loop`, or `well` line, or after the `on` of a
+/// `loop` or `well`
+pub fn expects_particle(text: &str, offset: usize) -> bool {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = &text[line_start..offset];
+    if prefix.contains('#') {
+        return false;
+    }
+    let keyword = prefix.split_whitespace().next().unwrap_or("");
+    if !["force", "detect", "loop", "well"].contains(&keyword) {
+        return false;
+    }
+    if prefix.matches('(').count() > prefix.matches(')').count() {
+        return true;
+    }
+    let before_word = prefix.trim_end_matches(is_identifier_char);
+    matches!(keyword, "loop" | "well") && before_word.split_whitespace().last() == Some("on")
+}
+
+/// The identifier around byte `offset`, and its range
+pub fn word_at(text: &str, offset: usize) -> Option<(Span, &str)> {
+    let start = text[..offset]
+        .rfind(|c: char| !is_identifier_char(c))
+        .map_or(0, |i| i + 1);
+    let end = text[offset..]
+        .find(|c: char| !is_identifier_char(c))
+        .map_or(text.len(), |i| offset + i);
+    (start < end).then(|| (Span::new(start, end), &text[start..end]))
+}
+
+/// The first occurrence of `word` in `text` that isn't part of a longer
+/// identifier
+fn find_word(text: &str, word: &str) -> Option<Span> {
+    text.match_indices(word).find_map(|(start, _)| {
+        let end = start + word.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        let whole =
+            !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char);
+        whole.then(|| Span::new(start, end))
+    })
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The LSP position of byte `offset`: zero-based line, and column in UTF-16
+/// code units
+pub fn position(text: &str, offset: usize) -> (u32, u32) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+    (line as u32, character as u32)
+}
+
+/// The byte offset of an LSP position; past the end of a line is its end
+pub fn offset(text: &str, line: u32, character: u32) -> usize {
+    let mut start = 0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line_text = text[start..].split('\n').next().unwrap_or("");
+    let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+    let mut units = 0;
+    for (i, c) in line_text.char_indices() {
+        if units >= character as usize {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    start + line_text.len()
+}
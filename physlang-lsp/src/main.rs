@@ -0,0 +1,20 @@
+//! PhysLang language server, speaking LSP over stdin and stdout
+
+mod analysis;
+mod server;
+mod transport;
+
+use std::io;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    match server::run(stdin.lock(), stdout.lock()) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("physlang-lsp: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
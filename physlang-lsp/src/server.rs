@@ -0,0 +1,259 @@
+//! The language server: JSON-RPC messages in, responses and notifications out
+//!
+//! Everything runs on one thread, one message at a time. Documents are
+//! synced in full and re-checked whole on every change; nothing is shared
+//! between them.
+
+use crate::analysis::{self, DeclarationKind};
+use crate::transport::{read_message, write_message};
+use physlang_core::{DiagnosticSeverity, Span};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// JSON-RPC and LSP error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+// LSP SymbolKind and CompletionItemKind values
+const SYMBOL_FUNCTION: u32 = 12;
+const SYMBOL_OBJECT: u32 = 19;
+const SYMBOL_EVENT: u32 = 24;
+const COMPLETION_VARIABLE: u32 = 6;
+
+/// Serve messages from `reader`, writing replies to `writer`, until `exit`
+/// or the end of input. Returns the exit code: 0 if `shutdown` came first
+pub fn run(mut reader: impl BufRead, mut writer: impl Write) -> io::Result<u8> {
+    let mut server = Server::default();
+    while let Some(body) = read_message(&mut reader)? {
+        let message: Value = match serde_json::from_str(&body) {
+            Ok(message) => message,
+            Err(e) => {
+                write_message(&mut writer, &error(Value::Null, PARSE_ERROR, e.to_string()))?;
+                continue;
+            }
+        };
+        if message["method"] == "exit" {
+            return Ok(if server.shutdown { 0 } else { 1 });
+        }
+        for reply in server.handle(&message) {
+            write_message(&mut writer, &reply)?;
+        }
+    }
+    Ok(1)
+}
+
+/// Open documents and the lifecycle state of the session
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    initialized: bool,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Handle one message, returning the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // A response to a request of ours; we send none
+            return Vec::new();
+        };
+        let params = &message["params"];
+        match message.get("id") {
+            Some(id) => vec![self.request(id.clone(), method, params)],
+            None => self.notification(method, params),
+        }
+    }
+
+    fn request(&mut self, id: Value, method: &str, params: &Value) -> Value {
+        if method == "initialize" {
+            self.initialized = true;
+            return response(id, capabilities());
+        }
+        if !self.initialized {
+            return error(id, SERVER_NOT_INITIALIZED, "initialize first".to_string());
+        }
+        if self.shutdown {
+            return error(
+                id,
+                INVALID_REQUEST,
+                "the server is shutting down".to_string(),
+            );
+        }
+        if method == "shutdown" {
+            self.shutdown = true;
+            return response(id, Value::Null);
+        }
+        let handler: fn(&str, &Value) -> Value = match method {
+            "textDocument/documentSymbol" => document_symbols,
+            "textDocument/hover" => hover,
+            "textDocument/completion" => completion,
+            _ => return error(id, METHOD_NOT_FOUND, format!("unknown method {}", method)),
+        };
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        match self.documents.get(uri) {
+            Some(text) => response(id, handler(text, params)),
+            None => error(id, INVALID_PARAMS, format!("{} is not open", uri)),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        if !self.initialized {
+            return Vec::new();
+        }
+        let document = &params["textDocument"];
+        let Some(uri) = document["uri"].as_str() else {
+            return Vec::new();
+        };
+        match method {
+            "textDocument/didOpen" => {
+                let text = document["text"].as_str().unwrap_or("").to_string();
+                self.documents.insert(uri.to_string(), text);
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change is the whole new text
+                let changes = params["contentChanges"].as_array();
+                let Some(text) = changes
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                else {
+                    return Vec::new();
+                };
+                self.documents.insert(uri.to_string(), text.to_string());
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+            _ => return Vec::new(),
+        }
+        let text = &self.documents[uri];
+        let diagnostics = analysis::check(text)
+            .iter()
+            .map(|d| {
+                let mut message = d.message.clone();
+                if let Some(help) = &d.help {
+                    message.push_str("\nhelp: ");
+                    message.push_str(help);
+                }
+                let mut diagnostic = json!({
+                    "range": range(text, analysis::locate(text, d)),
+                    "severity": match d.severity {
+                        DiagnosticSeverity::Error => 1,
+                        DiagnosticSeverity::Warning => 2,
+                    },
+                    "source": "physlang",
+                    "message": message,
+                });
+                if let Some(code) = d.code {
+                    diagnostic["code"] = json!(code);
+                }
+                diagnostic
+            })
+            .collect();
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+}
+
+/// What the server offers, for the `initialize` response
+fn capabilities() -> Value {
+    json!({
+        "capabilities": {
+            // Full document sync
+            "textDocumentSync": 1,
+            "documentSymbolProvider": true,
+            "hoverProvider": true,
+            "completionProvider": { "triggerCharacters": ["(", ",", " "] },
+        },
+        "serverInfo": { "name": "physlang-lsp", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+/// The particles, functions, and detectors of the document
+fn document_symbols(text: &str, _params: &Value) -> Value {
+    let symbols: Vec<Value> = analysis::declarations(text)
+        .into_iter()
+        .filter_map(|d| {
+            let kind = match d.kind {
+                DeclarationKind::Particle => SYMBOL_OBJECT,
+                DeclarationKind::Function => SYMBOL_FUNCTION,
+                DeclarationKind::Detector => SYMBOL_EVENT,
+                DeclarationKind::Let => return None,
+            };
+            Some(json!({
+                "name": d.name,
+                "detail": d.detail,
+                "kind": kind,
+                "range": range(text, d.span),
+                "selectionRange": range(text, d.name_span),
+            }))
+        })
+        .collect();
+    json!(symbols)
+}
+
+/// The value of the let binding under the cursor, if it's constant
+fn hover(text: &str, params: &Value) -> Value {
+    let offset = cursor(text, params);
+    let Some((span, name)) = analysis::word_at(text, offset) else {
+        return Value::Null;
+    };
+    let is_let = analysis::declarations(text)
+        .iter()
+        .any(|d| d.kind == DeclarationKind::Let && d.name == name);
+    match analysis::let_value(text, name).filter(|_| is_let) {
+        Some(value) => json!({
+            "contents": { "kind": "markdown", "value": format!("`let {} = {}`", name, value) },
+            "range": range(text, span),
+        }),
+        None => Value::Null,
+    }
+}
+
+/// Particle names, where one is expected
+fn completion(text: &str, params: &Value) -> Value {
+    if !analysis::expects_particle(text, cursor(text, params)) {
+        return json!([]);
+    }
+    let items: Vec<Value> = analysis::particle_names(text)
+        .into_iter()
+        .map(|name| json!({ "label": name, "kind": COMPLETION_VARIABLE, "detail": "particle" }))
+        .collect();
+    json!(items)
+}
+
+/// The byte offset of the request's `position`
+fn cursor(text: &str, params: &Value) -> usize {
+    let position = &params["position"];
+    let line = position["line"].as_u64().unwrap_or(0) as u32;
+    let character = position["character"].as_u64().unwrap_or(0) as u32;
+    analysis::offset(text, line, character)
+}
+
+fn range(text: &str, span: Span) -> Value {
+    let (start_line, start_character) = analysis::position(text, span.start);
+    let (end_line, end_character) = analysis::position(text, span.end);
+    json!({
+        "start": { "line": start_line, "character": start_character },
+        "end": { "line": end_line, "character": end_character },
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
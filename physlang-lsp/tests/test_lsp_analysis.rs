@@ -0,0 +1,117 @@
+//! Tests for the per-document analysis behind the language server
+
+#[path = "../src/analysis.rs"]
+#[allow(dead_code)]
+mod analysis;
+
+use analysis::{
+    declarations, expects_particle, let_value, locate, offset, particle_names, position, word_at,
+    DeclarationKind,
+};
+use physlang_core::Diagnostic;
+
+const SOURCE: &str = "\
+let k = 4.0 * 2.5
+let rest = k / 5.0  # derived
+let wobble = sin(t)
+
+fn make_pair(x) {
+    particle \"p\" at (x, 0.0) mass 1.0
+}
+
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = k rest = rest
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+";
+
+#[test]
+fn test_declarations_in_source_order() {
+    let found = declarations(SOURCE);
+    let summary: Vec<(DeclarationKind, &str)> =
+        found.iter().map(|d| (d.kind, d.name.as_str())).collect();
+    assert_eq!(
+        summary,
+        [
+            (DeclarationKind::Let, "k"),
+            (DeclarationKind::Let, "rest"),
+            (DeclarationKind::Let, "wobble"),
+            (DeclarationKind::Function, "make_pair"),
+            (DeclarationKind::Particle, "a"),
+            (DeclarationKind::Particle, "b"),
+            (DeclarationKind::Detector, "gap"),
+        ]
+    );
+
+    let function = &found[3];
+    assert_eq!(
+        &SOURCE[function.name_span.start..function.name_span.end],
+        "make_pair"
+    );
+    assert!(SOURCE[function.span.start..function.span.end].ends_with('}'));
+    assert_eq!(function.detail, "fn make_pair(x)");
+    assert_eq!(found[1].detail, "let rest = k / 5.0");
+}
+
+#[test]
+fn test_let_values_fold_constants() {
+    assert_eq!(let_value(SOURCE, "k"), Some(10.0));
+    assert_eq!(let_value(SOURCE, "rest"), Some(2.0));
+    // `t` isn't a constant
+    assert_eq!(let_value(SOURCE, "wobble"), None);
+    assert_eq!(let_value(SOURCE, "a"), None);
+}
+
+#[test]
+fn test_particle_completion_positions() {
+    let text = "particle a at (0.0, 0.0) mass 1.0\nforce spring(a, \n";
+    assert!(expects_particle(text, text.len() - 1));
+    assert!(!expects_particle(text, 5));
+
+    let text = "loop for 3 cycles with frequency 1.0 damping 0.0 on ";
+    assert!(expects_particle(text, text.len()));
+    assert!(!expects_particle(text, text.len() - 4));
+    assert!(!expects_particle("detect gap = distance(a, b)", 27));
+    assert!(!expects_particle("# force spring(", 15));
+
+    // Still works while the document doesn't parse
+    assert_eq!(particle_names("particle a at (\nparticle b at"), ["a", "b"]);
+    assert_eq!(particle_names(SOURCE), ["a", "b"]);
+}
+
+#[test]
+fn test_positions_count_utf16_units() {
+    let text = "# héllo 🌍\nparticle a";
+    let a = text.rfind('a').unwrap();
+    assert_eq!(position(text, a), (1, 9));
+    assert_eq!(offset(text, 1, 9), a);
+    let after_globe = text.find('\n').unwrap();
+    assert_eq!(position(text, after_globe), (0, 10));
+    assert_eq!(offset(text, 0, 10), after_globe);
+    // Past the end of a line or of the text
+    assert_eq!(offset(text, 0, 99), after_globe);
+    assert_eq!(offset(text, 9, 0), text.len());
+}
+
+#[test]
+fn test_locate_falls_back_to_the_quoted_name() {
+    let text = "particle ghostly at (0.0, 0.0) mass 1.0\nforce spring(a, ghost) k = 1.0 rest = 1.0";
+    let diagnostic = Diagnostic::error("unknown particle 'ghost' in force", None);
+    let span = locate(text, &diagnostic);
+    assert_eq!(&text[span.start..span.end], "ghost");
+    assert!(span.start > text.find('\n').unwrap());
+
+    let unquoted = Diagnostic::error("no simulate declaration", None);
+    assert_eq!(locate(text, &unquoted).start, 0);
+}
+
+#[test]
+fn test_word_at() {
+    let text = "force spring(a, b) k = k_soft";
+    let (span, word) = word_at(text, text.len()).unwrap();
+    assert_eq!((span.start, word), (23, "k_soft"));
+    assert_eq!(word_at(text, 13).unwrap().1, "a");
+    assert_eq!(word_at(text, 5).unwrap().1, "force");
+    assert!(word_at("a  b", 2).is_none());
+}
@@ -0,0 +1,180 @@
+//! Tests driving the language server through a script of LSP messages
+
+#[path = "../src/analysis.rs"]
+#[allow(dead_code)]
+mod analysis;
+#[path = "../src/server.rs"]
+#[allow(dead_code)]
+mod server;
+#[path = "../src/transport.rs"]
+#[allow(dead_code)]
+mod transport;
+
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+const URI: &str = "file:///tmp/spring.phys";
+
+const SOURCE: &str = "\
+let k = 4.0 * 2.5
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = k rest = 2.0
+simulate dt = 0.01 steps = 100
+detect gap = distance(a, b)
+";
+
+/// Run the server over `messages`, returning its exit code and replies
+fn session(messages: &[Value]) -> (u8, Vec<Value>) {
+    let mut input = Vec::new();
+    for message in messages {
+        transport::write_message(&mut input, message).unwrap();
+    }
+    let mut output = Vec::new();
+    let code = server::run(Cursor::new(input), &mut output).unwrap();
+    let mut reader = Cursor::new(output);
+    let mut replies = Vec::new();
+    while let Some(body) = transport::read_message(&mut reader).unwrap() {
+        replies.push(serde_json::from_str(&body).unwrap());
+    }
+    (code, replies)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn open(text: &str) -> Value {
+    notification(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": URI, "languageId": "physlang", "version": 1, "text": text } }),
+    )
+}
+
+fn at(line: u32, character: u32) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+fn reply(replies: &[Value], id: u64) -> &Value {
+    replies.iter().find(|r| r["id"] == id).unwrap()
+}
+
+fn published(replies: &[Value]) -> Vec<&Value> {
+    replies
+        .iter()
+        .filter(|r| r["method"] == "textDocument/publishDiagnostics")
+        .map(|r| &r["params"]["diagnostics"])
+        .collect()
+}
+
+#[test]
+fn test_full_session() {
+    let broken = SOURCE.replace("spring(a, b)", "spring(a, ghost)");
+    let (code, replies) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification("initialized", json!({})),
+        open(SOURCE),
+        request(
+            2,
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": URI } }),
+        ),
+        request(3, "textDocument/hover", at(0, 4)),
+        request(4, "textDocument/completion", at(3, 13)),
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": broken }],
+            }),
+        ),
+        request(5, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+    ]);
+    assert_eq!(code, 0);
+
+    let capabilities = &reply(&replies, 1)["result"]["capabilities"];
+    assert_eq!(capabilities["textDocumentSync"], 1);
+    assert_eq!(capabilities["hoverProvider"], true);
+
+    let diagnostics = published(&replies);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0], &json!([]));
+    let error = &diagnostics[1][0];
+    assert_eq!(error["severity"], 1);
+    assert_eq!(error["source"], "physlang");
+    assert!(error["message"].as_str().unwrap().contains("ghost"));
+    assert_eq!(
+        error["range"],
+        json!({ "start": { "line": 3, "character": 16 }, "end": { "line": 3, "character": 21 } })
+    );
+
+    let symbols = reply(&replies, 2)["result"].as_array().unwrap();
+    let names: Vec<&str> = symbols
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["a", "b", "gap"]);
+    assert_eq!(
+        symbols[0]["selectionRange"],
+        json!({ "start": { "line": 1, "character": 9 }, "end": { "line": 1, "character": 10 } })
+    );
+
+    let hover = &reply(&replies, 3)["result"];
+    assert_eq!(hover["contents"]["value"], "`let k = 10`");
+
+    let labels: Vec<&Value> = reply(&replies, 4)["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| &item["label"])
+        .collect();
+    assert_eq!(labels, ["a", "b"]);
+
+    assert_eq!(reply(&replies, 5)["result"], Value::Null);
+}
+
+#[test]
+fn test_lifecycle_errors() {
+    let (code, replies) = session(&[
+        request(1, "textDocument/hover", at(0, 0)),
+        request(2, "initialize", json!({})),
+        request(3, "textDocument/hover", at(0, 0)),
+        request(4, "workspace/symbol", json!({ "query": "" })),
+        open(SOURCE),
+        request(5, "textDocument/hover", at(2, 0)),
+        notification(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": URI } }),
+        ),
+        notification("exit", Value::Null),
+    ]);
+    // Exit without shutdown
+    assert_eq!(code, 1);
+    assert_eq!(reply(&replies, 1)["error"]["code"], -32002);
+    // Not open
+    assert_eq!(reply(&replies, 3)["error"]["code"], -32602);
+    assert_eq!(reply(&replies, 4)["error"]["code"], -32601);
+    // Not a let binding
+    assert_eq!(reply(&replies, 5)["result"], Value::Null);
+    // Closing clears the document's diagnostics
+    assert_eq!(published(&replies).last().unwrap(), &&json!([]));
+}
+
+#[test]
+fn test_malformed_message() {
+    let mut input = b"Content-Length: 9\r\n\r\nnot json!".to_vec();
+    transport::write_message(&mut input, &notification("exit", Value::Null)).unwrap();
+    let mut output = Vec::new();
+    assert_eq!(server::run(Cursor::new(input), &mut output).unwrap(), 1);
+    let body = transport::read_message(&mut Cursor::new(output))
+        .unwrap()
+        .unwrap();
+    let reply: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(reply["error"]["code"], -32700);
+    assert_eq!(reply["id"], Value::Null);
+}
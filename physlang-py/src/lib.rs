@@ -0,0 +1,292 @@
+//! Python bindings for PhysLang
+//!
+//! Build and install into the current virtualenv with `maturin develop` in
+//! this directory, then `import physlang`. Errors are raised as
+//! `physlang.PhyslangError`, whose `diagnostics` attribute lists every
+//! problem found as dicts.
+
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, lint_source, parse_program, run_program,
+    try_step_simulation, Diagnostic, DiagnosticSeverity, SimulationContext,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+create_exception!(
+    physlang,
+    PhyslangError,
+    PyException,
+    "A program that failed to parse, check, or run; `diagnostics` lists why"
+);
+
+/// Parse and analyze `source`: every error and warning, errors first
+fn diagnose(source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = match parse_program(source) {
+        Ok(program) => analyze_program(&program)
+            .into_iter()
+            .chain(lint_source(source))
+            .collect(),
+        Err(e) => vec![Diagnostic::from(&e)],
+    };
+    diagnostics.sort_by_key(|d| d.severity != DiagnosticSeverity::Error);
+    diagnostics
+}
+
+/// A diagnostic as a dict of `severity`, `message`, `code`, `line`,
+/// `column`, `span` (byte offsets), `help`, and `notes`
+fn diagnostic_dict<'py>(
+    py: Python<'py>,
+    diagnostic: &Diagnostic,
+    source: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    let severity = match diagnostic.severity {
+        DiagnosticSeverity::Error => "error",
+        DiagnosticSeverity::Warning => "warning",
+    };
+    let location = diagnostic.location(source);
+    dict.set_item("severity", severity)?;
+    dict.set_item("message", &diagnostic.message)?;
+    dict.set_item("code", diagnostic.code)?;
+    dict.set_item("line", location.map(|l| l.line))?;
+    dict.set_item("column", location.map(|l| l.column))?;
+    dict.set_item("span", diagnostic.span.map(|s| (s.start, s.end)))?;
+    dict.set_item("help", &diagnostic.help)?;
+    dict.set_item("notes", &diagnostic.notes)?;
+    Ok(dict)
+}
+
+fn diagnostic_list<'py>(
+    py: Python<'py>,
+    diagnostics: &[Diagnostic],
+    source: &str,
+) -> PyResult<Bound<'py, PyList>> {
+    let dicts = diagnostics
+        .iter()
+        .map(|d| diagnostic_dict(py, d, source))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new_bound(py, dicts))
+}
+
+/// A `PhyslangError` with `message` and `diagnostics` attached
+fn physlang_error(
+    py: Python<'_>,
+    message: String,
+    diagnostics: &[Diagnostic],
+    source: &str,
+) -> PyErr {
+    let error = PhyslangError::new_err(message);
+    match diagnostic_list(py, diagnostics, source) {
+        Ok(list) => match error.value_bound(py).setattr("diagnostics", list) {
+            Ok(()) => error,
+            Err(e) => e,
+        },
+        Err(e) => e,
+    }
+}
+
+/// Raise the errors `diagnose` finds in `source`, if any
+fn check_or_raise(py: Python<'_>, source: &str) -> PyResult<Vec<Diagnostic>> {
+    let diagnostics = diagnose(source);
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .count();
+    if errors > 0 {
+        let first = &diagnostics[0].message;
+        let message = match errors {
+            1 => first.clone(),
+            n => format!("{} (and {} more errors)", first, n - 1),
+        };
+        return Err(physlang_error(py, message, &diagnostics, source));
+    }
+    Ok(diagnostics)
+}
+
+/// Every error and warning in `source`, as a list of dicts
+#[pyfunction]
+fn check<'py>(py: Python<'py>, source: &str) -> PyResult<Bound<'py, PyList>> {
+    diagnostic_list(py, &diagnose(source), source)
+}
+
+/// Run `source` to the end; returns a dict of `detectors` (name to value),
+/// `steps`, `elapsed_time`, `state_hash`, and `warnings`
+#[pyfunction]
+fn run<'py>(py: Python<'py>, source: &str) -> PyResult<Bound<'py, PyDict>> {
+    check_or_raise(py, source)?;
+    let result = run_program(source).map_err(|e| {
+        let diagnostic = Diagnostic::error(e.to_string(), None);
+        physlang_error(py, e.to_string(), &[diagnostic], source)
+    })?;
+    let detectors = PyDict::new_bound(py);
+    for detector in &result.detectors {
+        detectors.set_item(&detector.name, detector.value)?;
+    }
+    let dict = PyDict::new_bound(py);
+    dict.set_item("detectors", detectors)?;
+    dict.set_item("steps", result.steps)?;
+    dict.set_item("elapsed_time", result.elapsed_time)?;
+    dict.set_item("state_hash", result.state_hash)?;
+    dict.set_item("warnings", diagnostic_list(py, &result.warnings, source)?)?;
+    Ok(dict)
+}
+
+/// A simulation stepped from Python
+#[pyclass(unsendable)]
+struct Simulation {
+    source: String,
+    ctx: SimulationContext,
+}
+
+#[pymethods]
+impl Simulation {
+    /// Build a simulation of `source` at step 0
+    #[new]
+    fn new(py: Python<'_>, source: &str) -> PyResult<Self> {
+        check_or_raise(py, source)?;
+        let (ctx, _) = build_simulation_context_from_source(source).map_err(|e| {
+            let diagnostic = Diagnostic::error(e.to_string(), None);
+            physlang_error(py, e.to_string(), &[diagnostic], source)
+        })?;
+        Ok(Self {
+            source: source.to_string(),
+            ctx,
+        })
+    }
+
+    /// Take up to `n` steps; returns True once the run has finished.
+    /// Raises `PhyslangError` if a position or velocity stops being finite
+    #[pyo3(signature = (n=1))]
+    fn step(&mut self, py: Python<'_>, n: usize) -> PyResult<bool> {
+        for _ in 0..n {
+            if self.try_step(py)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Particle names, in the order of `positions` and `velocities`
+    fn names(&self) -> Vec<String> {
+        self.ctx
+            .world
+            .particles
+            .iter()
+            .map(|p| p.name.clone())
+            .collect()
+    }
+
+    /// `(x, y)` of every particle
+    fn positions(&self) -> Vec<(f32, f32)> {
+        self.ctx
+            .world
+            .particles
+            .iter()
+            .map(|p| (p.pos.x, p.pos.y))
+            .collect()
+    }
+
+    /// `(vx, vy)` of every particle
+    fn velocities(&self) -> Vec<(f32, f32)> {
+        self.ctx
+            .world
+            .particles
+            .iter()
+            .map(|p| (p.vel.x, p.vel.y))
+            .collect()
+    }
+
+    /// Current detector values, as a dict from name to value
+    fn detectors<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        let values = self.ctx.detector_values().map_err(|e| {
+            let diagnostic = Diagnostic::error(e.to_string(), None);
+            physlang_error(py, e.to_string(), &[diagnostic], &self.source)
+        })?;
+        for detector in values {
+            dict.set_item(detector.name, detector.value)?;
+        }
+        Ok(dict)
+    }
+
+    /// Run to the end, returning one dict per sample: `step`, `time`,
+    /// `<particle>.x`, `.y`, `.vx`, and `.vy` for every particle, and every
+    /// detector. Samples the current step, every `sample_every`th step after
+    /// it, and the last step, so `pandas.DataFrame(rows)` takes it directly
+    #[pyo3(signature = (sample_every=10))]
+    fn run_trace<'py>(
+        &mut self,
+        py: Python<'py>,
+        sample_every: usize,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let sample_every = sample_every.max(1);
+        let rows = PyList::empty_bound(py);
+        rows.append(self.sample(py)?)?;
+        let start = self.ctx.current_step;
+        loop {
+            let finished = self.try_step(py)?;
+            let taken = self.ctx.current_step - start;
+            if taken > 0 && (finished || taken.is_multiple_of(sample_every)) {
+                rows.append(self.sample(py)?)?;
+            }
+            if finished {
+                return Ok(rows);
+            }
+        }
+    }
+
+    /// Steps taken so far
+    #[getter]
+    fn current_step(&self) -> usize {
+        self.ctx.current_step
+    }
+
+    /// Simulated seconds so far
+    #[getter]
+    fn time(&self) -> f32 {
+        self.ctx.time
+    }
+
+    /// Whether every step of the run has been taken
+    #[getter]
+    fn finished(&self) -> bool {
+        self.ctx.current_step >= self.ctx.max_steps
+    }
+}
+
+impl Simulation {
+    fn try_step(&mut self, py: Python<'_>) -> PyResult<bool> {
+        try_step_simulation(&mut self.ctx).map_err(|e| {
+            let diagnostic = Diagnostic::error(e.to_string(), None);
+            physlang_error(py, e.to_string(), &[diagnostic], &self.source)
+        })
+    }
+
+    /// One `run_trace` row for the current state
+    fn sample<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let row = PyDict::new_bound(py);
+        row.set_item("step", self.ctx.current_step)?;
+        row.set_item("time", self.ctx.time)?;
+        for p in &self.ctx.world.particles {
+            row.set_item(format!("{}.x", p.name), p.pos.x)?;
+            row.set_item(format!("{}.y", p.name), p.pos.y)?;
+            row.set_item(format!("{}.vx", p.name), p.vel.x)?;
+            row.set_item(format!("{}.vy", p.name), p.vel.y)?;
+        }
+        for (name, value) in self.detectors(py)?.iter() {
+            row.set_item(name, value)?;
+        }
+        Ok(row)
+    }
+}
+
+#[pymodule]
+fn physlang(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("PhyslangError", m.py().get_type_bound::<PhyslangError>())?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    m.add_class::<Simulation>()?;
+    Ok(())
+}
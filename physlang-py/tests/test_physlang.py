@@ -0,0 +1,68 @@
+"""Tests for the Python bindings; run with `maturin develop && pytest tests`"""
+
+import pytest
+
+import physlang
+
+SPRING = """\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 200
+detect gap = distance(a, b)
+"""
+
+
+def test_run():
+    result = physlang.run(SPRING)
+    assert result["steps"] == 200
+    assert result["elapsed_time"] == pytest.approx(2.0)
+    assert list(result["detectors"]) == ["gap"]
+    assert result["warnings"] == []
+
+
+def test_step_and_positions():
+    sim = physlang.Simulation(SPRING)
+    assert sim.names() == ["a", "b"]
+    assert sim.positions() == [(0.0, 0.0), (3.0, 0.0)]
+
+    assert sim.step(100) is False
+    assert sim.current_step == 100
+    (ax, _), (bx, _) = sim.positions()
+    # The stretched spring pulled the particles together
+    assert ax > 0.0 and bx < 3.0
+    assert sim.detectors()["gap"] == pytest.approx(bx - ax, abs=1e-4)
+
+    assert sim.step(1000) is True
+    assert sim.finished
+
+
+def test_run_trace():
+    sim = physlang.Simulation(SPRING)
+    sim.step(5)
+    rows = sim.run_trace(sample_every=50)
+    assert [row["step"] for row in rows] == [5, 55, 105, 155, 200]
+    assert set(rows[0]) == {
+        "step", "time", "gap",
+        "a.x", "a.y", "a.vx", "a.vy",
+        "b.x", "b.y", "b.vx", "b.vy",
+    }
+    assert rows[-1]["time"] == pytest.approx(2.0)
+
+
+def test_errors_carry_diagnostics():
+    with pytest.raises(physlang.PhyslangError) as raised:
+        physlang.run("particle a at (0.0, 0.0) mass 1.0\nforce spring(a, ghost) k = 1.0 rest = 1.0\n"
+                     "simulate dt = 0.01 steps = 10\n")
+    diagnostics = raised.value.diagnostics
+    assert diagnostics[0]["severity"] == "error"
+    assert "ghost" in diagnostics[0]["message"]
+
+    with pytest.raises(physlang.PhyslangError) as raised:
+        physlang.Simulation("particle a at (0.0")
+    assert raised.value.diagnostics[0]["line"] == 1
+
+
+def test_check():
+    assert physlang.check(SPRING) == []
+    assert physlang.check("particle a at (0.0")[0]["severity"] == "error"
@@ -0,0 +1,107 @@
+//! WebAssembly bindings for PhysLang
+//!
+//! Build with `wasm-pack build physlang-wasm --target web` and load the
+//! generated `pkg/physlang_wasm.js`; `examples/wasm/index.html` runs a
+//! program on a canvas.
+
+use physlang_core::{
+    analyze_program, build_simulation_context_from_source, language_capabilities, lint_source,
+    parse_program, step_simulation, Diagnostic, SimulationContext,
+};
+use wasm_bindgen::prelude::*;
+
+/// Every error and warning in `source`, as an array of diagnostic objects
+/// (`severity`, `message`, `span`, and `code`, `help`, ... when present)
+#[wasm_bindgen]
+pub fn parse_and_check(source: &str) -> Result<JsValue, JsError> {
+    let diagnostics: Vec<Diagnostic> = match parse_program(source) {
+        Ok(program) => analyze_program(&program)
+            .into_iter()
+            .chain(lint_source(source))
+            .collect(),
+        Err(e) => vec![Diagnostic::from(&e)],
+    };
+    Ok(serde_wasm_bindgen::to_value(&diagnostics)?)
+}
+
+/// The language version and supported features, like `physlang info --json`
+#[wasm_bindgen]
+pub fn capabilities() -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(&language_capabilities())?)
+}
+
+/// Build a simulation of `source` at step 0
+#[wasm_bindgen]
+pub fn create_sim(source: &str) -> Result<SimHandle, JsError> {
+    let (ctx, _) =
+        build_simulation_context_from_source(source).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(SimHandle { ctx })
+}
+
+/// A running simulation
+#[wasm_bindgen]
+pub struct SimHandle {
+    ctx: SimulationContext,
+}
+
+#[wasm_bindgen]
+impl SimHandle {
+    /// Take up to `n` steps; true once the run has finished or diverged
+    pub fn step(&mut self, n: usize) -> bool {
+        for _ in 0..n {
+            if step_simulation(&mut self.ctx) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// `x, y, mass` of every particle, one after another
+    pub fn particles(&self) -> js_sys::Float32Array {
+        let values: Vec<f32> = self
+            .ctx
+            .world
+            .particles
+            .iter()
+            .flat_map(|p| [p.pos.x, p.pos.y, p.mass])
+            .collect();
+        js_sys::Float32Array::from(values.as_slice())
+    }
+
+    /// Particle names, in the order of `particles`
+    pub fn names(&self) -> Vec<String> {
+        self.ctx
+            .world
+            .particles
+            .iter()
+            .map(|p| p.name.clone())
+            .collect()
+    }
+
+    /// Current detector values, as an array of `{ name, value }`
+    pub fn detectors(&self) -> Result<JsValue, JsError> {
+        let values = self
+            .ctx
+            .detector_values()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(serde_wasm_bindgen::to_value(&values)?)
+    }
+
+    /// Steps taken so far
+    #[wasm_bindgen(getter)]
+    pub fn current_step(&self) -> usize {
+        self.ctx.current_step
+    }
+
+    /// Simulated seconds so far
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> f32 {
+        self.ctx.time
+    }
+
+    /// Whether the run stopped on a non-finite position or velocity
+    #[wasm_bindgen(getter)]
+    pub fn diverged(&self) -> bool {
+        self.ctx.divergence.is_some()
+    }
+}
@@ -0,0 +1,49 @@
+//! Tests for the WebAssembly bindings; run with `wasm-pack test --node`
+
+use physlang_wasm::{capabilities, create_sim, parse_and_check};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const SPRING: &str = "\
+particle a at (0.0, 0.0) mass 1.0
+particle b at (3.0, 0.0) mass 2.0
+force spring(a, b) k = 10.0 rest = 2.0
+simulate dt = 0.01 steps = 200
+detect gap = distance(a, b)
+";
+
+#[wasm_bindgen_test]
+fn test_steps_a_spring() {
+    let mut sim = create_sim(SPRING).unwrap();
+    assert!(!sim.step(100));
+    assert_eq!(sim.current_step(), 100);
+    assert!((sim.time() - 1.0).abs() < 1e-4);
+
+    let particles = sim.particles().to_vec();
+    assert_eq!(particles.len(), 6);
+    assert_eq!(sim.names(), ["a", "b"]);
+    assert_eq!((particles[2], particles[5]), (1.0, 2.0));
+    // The stretched spring pulled the particles together
+    assert!(particles[0] > 0.0 && particles[3] < 3.0);
+
+    assert!(sim.step(1000));
+    assert_eq!(sim.current_step(), 200);
+    assert!(!sim.diverged());
+    assert!(sim.detectors().unwrap().is_array());
+}
+
+#[wasm_bindgen_test]
+fn test_parse_and_check() {
+    let clean = parse_and_check(SPRING).unwrap();
+    assert_eq!(js_sys::Array::from(&clean).length(), 0);
+
+    let broken = parse_and_check("particle a at (0.0").unwrap();
+    assert_eq!(js_sys::Array::from(&broken).length(), 1);
+    assert!(create_sim("particle a at (0.0").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_capabilities() {
+    let report = capabilities().unwrap();
+    let version = js_sys::Reflect::get(&report, &"language_version".into()).unwrap();
+    assert_eq!(version.as_string().unwrap(), physlang_core::LANGUAGE_VERSION);
+}
@@ -1,6 +1,6 @@
 //! Golden tests - compare outputs to expected snapshots
 
-use physlang_core::tests::test_helpers::{run_phys_file, result_to_json, load_expected, write_expected};
+use physlang_core::tests::test_helpers::{run_phys_file, result_to_json, compare_golden, load_expected, regenerate_expected, write_expected};
 use std::path::PathBuf;
 
 fn golden_data_path(filename: &str) -> PathBuf {
@@ -32,9 +32,10 @@ fn test_simple_particle_golden() {
     // Try to load expected, or write it if it doesn't exist
     match load_expected(expected_path.to_str().unwrap()) {
         Ok(expected_json) => {
-            // Compare JSON (allowing for floating point differences)
-            assert_eq!(actual_json, expected_json, 
-                "Output does not match expected snapshot. If this is intentional, update the .expected file.");
+            // Compare detector values within the file's tolerance
+            if let Err(diff) = compare_golden(&actual_json, &expected_json) {
+                panic!("Output does not match expected snapshot. If this is intentional, update the .expected file.\n{}", diff);
+            }
         }
         Err(_) => {
             // First run - write expected file
@@ -57,8 +58,9 @@ fn test_oscillator_golden() {
     
     match load_expected(expected_path.to_str().unwrap()) {
         Ok(expected_json) => {
-            assert_eq!(actual_json, expected_json,
-                "Oscillator output does not match expected snapshot");
+            if let Err(diff) = compare_golden(&actual_json, &expected_json) {
+                panic!("Oscillator output does not match expected snapshot\n{}", diff);
+            }
         }
         Err(_) => {
             eprintln!("Writing expected file for first time: {:?}", expected_path);
@@ -80,8 +82,9 @@ fn test_spring_equilibrium_golden() {
     
     match load_expected(expected_path.to_str().unwrap()) {
         Ok(expected_json) => {
-            assert_eq!(actual_json, expected_json,
-                "Spring equilibrium output does not match expected snapshot");
+            if let Err(diff) = compare_golden(&actual_json, &expected_json) {
+                panic!("Spring equilibrium output does not match expected snapshot\n{}", diff);
+            }
         }
         Err(_) => {
             eprintln!("Writing expected file for first time: {:?}", expected_path);
@@ -114,8 +117,8 @@ fn regenerate_golden_files() {
         let result = run_phys_file(phys_path.to_str().unwrap())
             .expect(&format!("Failed to run {}", filename));
         
-        let json = result_to_json(&result);
-        write_expected(expected_path.to_str().unwrap(), &json)
+        // Keeps any tolerance the .expected file sets
+        regenerate_expected(expected_path.to_str().unwrap(), &result)
             .expect(&format!("Failed to write expected for {}", filename));
         
         eprintln!("Regenerated: {}", expected_name);